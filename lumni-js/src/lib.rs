@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+
+use js_sys::{Array, Object, Promise, Reflect, Uint8Array, JSON};
+use lumni::{
+    EnvironmentConfig, LumniError, ObjectStoreHandler, ParsedUri, Table,
+    TableExportOptions, UploadOptions,
+};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::future_to_promise;
+
+// JS interface to ObjectStoreHandler, requests go through the fetch api of
+// the host (browser or Node.js 18+). methods return promises, e.g.
+// `await new Client({ region: "us-east-1" }).list("s3://bucket/")`
+#[wasm_bindgen]
+pub struct Client {
+    config: EnvironmentConfig,
+}
+
+#[wasm_bindgen]
+impl Client {
+    // settings are the same as the environment of the cli, e.g.
+    // { AWS_ACCESS_KEY_ID: "...", AWS_SECRET_ACCESS_KEY: "...", region: "..." }
+    #[wasm_bindgen(constructor)]
+    pub fn new(settings: Option<Object>) -> Result<Client, JsValue> {
+        let mut config = HashMap::new();
+        if let Some(settings) = settings {
+            for entry in Object::entries(&settings).iter() {
+                let entry = Array::from(&entry);
+                let key = entry.get(0).as_string();
+                let value = entry.get(1).as_string().ok_or_else(|| {
+                    js_error(&format!(
+                        "Setting {} is not a string",
+                        key.as_deref().unwrap_or("")
+                    ))
+                })?;
+                config.insert(key.unwrap_or_default(), value);
+            }
+        }
+        Ok(Client {
+            config: EnvironmentConfig::new(config),
+        })
+    }
+
+    // resolves to an array of objects, one per row of the listing
+    pub fn list(
+        &self,
+        uri: String,
+        recursive: Option<bool>,
+        max_files: Option<u32>,
+    ) -> Promise {
+        let config = self.config.clone();
+        future_to_promise(async move {
+            let table = list_table(&uri, &config, recursive, max_files).await?;
+            let jsonl = table
+                .to_jsonl(&TableExportOptions::new())
+                .map_err(|e| js_error(&e))?;
+            let rows = Array::new();
+            for line in jsonl.lines().filter(|line| !line.is_empty()) {
+                rows.push(&JSON::parse(line)?);
+            }
+            Ok(rows.into())
+        })
+    }
+
+    // resolves to the listing serialized as "csv", "jsonl" or "markdown"
+    #[wasm_bindgen(js_name = listAs)]
+    pub fn list_as(
+        &self,
+        uri: String,
+        format: String,
+        recursive: Option<bool>,
+        max_files: Option<u32>,
+    ) -> Promise {
+        let config = self.config.clone();
+        future_to_promise(async move {
+            let table = list_table(&uri, &config, recursive, max_files).await?;
+            let options = TableExportOptions::new();
+            let output = match format.as_str() {
+                "csv" => table.to_csv(&options),
+                "jsonl" => table.to_jsonl(&options),
+                "markdown" => table.to_markdown(&options),
+                _ => Err(format!("Unsupported format: {}", format)),
+            }
+            .map_err(|e| js_error(&e))?;
+            Ok(JsValue::from_str(&output))
+        })
+    }
+
+    // resolves to the contents of the object as a Uint8Array
+    pub fn get(&self, uri: String) -> Promise {
+        let config = self.config.clone();
+        future_to_promise(async move {
+            let data = ObjectStoreHandler::new(None)
+                .get_object(&ParsedUri::from_uri(&uri, false), &config, None)
+                .await
+                .map_err(to_js_error)?
+                .unwrap_or_default();
+            Ok(Uint8Array::from(data.as_slice()).into())
+        })
+    }
+
+    // resolves to the number of bytes written
+    pub fn put(
+        &self,
+        uri: String,
+        data: Vec<u8>,
+        content_type: Option<String>,
+    ) -> Promise {
+        let config = self.config.clone();
+        future_to_promise(async move {
+            let mut options = UploadOptions::new();
+            if let Some(content_type) = &content_type {
+                options = options.set_content_type(content_type);
+            }
+            let size = ObjectStoreHandler::new(None)
+                .put_object(
+                    &ParsedUri::from_uri(&uri, false),
+                    &config,
+                    &mut Cursor::new(data),
+                    &options,
+                    None,
+                )
+                .await
+                .map_err(to_js_error)?;
+            Ok(JsValue::from_f64(size as f64))
+        })
+    }
+}
+
+async fn list_table(
+    uri: &str,
+    config: &EnvironmentConfig,
+    recursive: Option<bool>,
+    max_files: Option<u32>,
+) -> Result<Box<dyn Table>, JsValue> {
+    ObjectStoreHandler::new(None)
+        .list_objects(
+            &ParsedUri::from_uri(uri, true),
+            config,
+            None,
+            recursive.unwrap_or(false),
+            max_files,
+            &None,
+            None,
+        )
+        .await
+        .map_err(to_js_error)
+}
+
+// an Error with the kind of LumniError in its name, e.g. "NotFound", so
+// callers can tell errors apart without parsing the message
+fn to_js_error(error: LumniError) -> JsValue {
+    let kind = match error.root() {
+        LumniError::NotFound(_) => "NotFound",
+        LumniError::AccessDenied(_) => "AccessDenied",
+        LumniError::Throttled(_) => "Throttled",
+        LumniError::Network(_) => "Network",
+        LumniError::Config(_) | LumniError::NoBucketInUri(_) => "Config",
+        _ => "LumniError",
+    };
+    let js_error = js_error(&error.to_string());
+    let _ = Reflect::set(&js_error, &"name".into(), &kind.into());
+    js_error
+}
+
+fn js_error(message: &str) -> JsValue {
+    js_sys::Error::new(message).into()
+}
@@ -1,4 +1,4 @@
-from .lumni import _Client
+from .lumni import _Client, AsyncClient, LumniError, Table
 
 class Client:
     def __init__(self):
@@ -47,6 +47,37 @@ class Client:
         """
         return self._client.list_objects(uri, recursive, max_files, filter_dict)
 
+    def list_table(self, uri, recursive=False, max_files=None, filter_dict=None):
+        """
+        List objects from the given URI as a table, for analysis with pyarrow or pandas.
+
+        :param uri: The URI of the object storage.
+        :type uri: str
+        :param recursive: If True, list objects recursively. Default is None.
+        :type recursive: bool, optional
+        :param max_files: The maximum number of files to list. Default is None.
+        :type max_files: int, optional
+        :param filter_dict: A dictionary containing filters for name, size, and mtime,
+                            as in list_objects.
+        :type filter_dict: dict, optional
+        :return: A table with to_arrow() and to_pandas() conversions.
+        :rtype: lumni.Table
+
+        Example usage:
+
+        .. code-block:: python
+
+            import lumni
+
+            client = lumni.Client()
+
+            # List the contents of a storage location as a pandas DataFrame
+            df = client.list_table("s3://your-bucket", recursive=True).to_pandas()
+
+            print(df.sort_values("size").tail())
+        """
+        return self._client.list_table(uri, recursive, max_files, filter_dict)
+
     def list_buckets(self, uri):
         """
         List buckets.
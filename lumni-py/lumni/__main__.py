@@ -5,7 +5,7 @@ from lumni import _Client
 def main():
     args = sys.argv[1:]
     client = _Client()
-    client.cli(args)
+    sys.exit(client.cli(args))
 
 
 if __name__ == "__main__":
@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use arrow::array::{
+    Array, ArrayRef, Float64Array, Int32Array, StringArray, StructArray,
+    TimestampSecondArray, UInt64Array,
+};
+use arrow::datatypes::{Field, Schema};
+use arrow::ffi::to_ffi;
+use arrow::record_batch::RecordBatch;
+use pyo3::prelude::*;
+
+use crate::LumniError;
+
+// start with :: to ensure local crate is used
+use ::lumni::{ColumnType, Table, TableColumn, TableColumnValue};
+
+// a listing as Arrow columns, e.g. `client.list_table(uri).to_pandas()`.
+// the columns are converted once when the listing is done, to_arrow then
+// hands the buffers to pyarrow without copying them
+#[pyclass(name = "Table")]
+pub struct ArrowTable {
+    batch: RecordBatch,
+}
+
+impl ArrowTable {
+    pub fn from_table(table: &dyn Table) -> Result<Self, ::lumni::LumniError> {
+        let mut fields = Vec::new();
+        let mut arrays = Vec::new();
+        for (name, column) in table.columns() {
+            let array = to_array(column.as_ref());
+            fields.push(Field::new(
+                name,
+                array.data_type().clone(),
+                column.is_nullable(),
+            ));
+            arrays.push(array);
+        }
+        let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)
+            .map_err(|e| ::lumni::LumniError::Internal(e.to_string()))?;
+        Ok(ArrowTable { batch })
+    }
+}
+
+#[pymethods]
+impl ArrowTable {
+    fn __len__(&self) -> usize {
+        self.batch.num_rows()
+    }
+
+    fn column_names(&self) -> Vec<String> {
+        self.batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| field.name().to_string())
+            .collect()
+    }
+
+    // a pyarrow.RecordBatch, passed over the Arrow C data interface
+    fn to_arrow(&self, py: Python) -> PyResult<PyObject> {
+        let data = StructArray::from(self.batch.clone()).into_data();
+        let (array, schema) = to_ffi(&data).map_err(|e| {
+            LumniError::new_err(format!("Error exporting table: {}", e))
+        })?;
+        // pyarrow takes over the buffers, and releases them when the
+        // batch is garbage collected
+        let batch =
+            py.import("pyarrow")?.getattr("RecordBatch")?.call_method1(
+                "_import_from_c",
+                (&array as *const _ as usize, &schema as *const _ as usize),
+            )?;
+        Ok(batch.unbind())
+    }
+
+    // a pandas.DataFrame, requires pandas next to pyarrow
+    fn to_pandas(&self, py: Python) -> PyResult<PyObject> {
+        self.to_arrow(py)?.call_method0(py, "to_pandas")
+    }
+}
+
+// ints are stored as i32 or u64, which is kept as is. timestamps are epoch
+// seconds
+fn to_array(column: &dyn TableColumn) -> ArrayRef {
+    let values: Vec<TableColumnValue> = (0..column.len())
+        .filter_map(|index| column.get_value(index))
+        .collect();
+    match column.data_type() {
+        ColumnType::Float => {
+            Arc::new(values.iter().map(to_f64).collect::<Float64Array>())
+        }
+        ColumnType::String => {
+            Arc::new(values.into_iter().map(to_string).collect::<StringArray>())
+        }
+        ColumnType::Timestamp => Arc::new(
+            values
+                .iter()
+                .map(|value| to_u64(value).map(|v| v as i64))
+                .collect::<TimestampSecondArray>(),
+        ),
+        ColumnType::Int if values.first().is_some_and(is_int32) => {
+            Arc::new(values.iter().map(to_i32).collect::<Int32Array>())
+        }
+        ColumnType::Int => {
+            Arc::new(values.iter().map(to_u64).collect::<UInt64Array>())
+        }
+    }
+}
+
+fn is_int32(value: &TableColumnValue) -> bool {
+    matches!(
+        value,
+        TableColumnValue::Int32Column(_)
+            | TableColumnValue::OptionalInt32Column(_)
+    )
+}
+
+fn to_i32(value: &TableColumnValue) -> Option<i32> {
+    match value {
+        TableColumnValue::Int32Column(v) => Some(*v),
+        TableColumnValue::OptionalInt32Column(v) => *v,
+        _ => None,
+    }
+}
+
+fn to_u64(value: &TableColumnValue) -> Option<u64> {
+    match value {
+        TableColumnValue::Uint64Column(v) => Some(*v),
+        TableColumnValue::OptionalUint64Column(v) => *v,
+        _ => None,
+    }
+}
+
+fn to_f64(value: &TableColumnValue) -> Option<f64> {
+    match value {
+        TableColumnValue::FloatColumn(v) => Some(*v),
+        TableColumnValue::OptionalFloatColumn(v) => *v,
+        _ => None,
+    }
+}
+
+fn to_string(value: TableColumnValue) -> Option<String> {
+    match value {
+        TableColumnValue::StringColumn(v) => Some(v),
+        TableColumnValue::OptionalStringColumn(v) => v,
+        _ => None,
+    }
+}
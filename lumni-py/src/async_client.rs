@@ -0,0 +1,338 @@
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::future::Future;
+use std::io::Cursor;
+use std::sync::Arc;
+
+use futures::StreamExt;
+use pyo3::exceptions::PyStopAsyncIteration;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+use tokio::runtime::Handle;
+use tokio::sync::{mpsc, Mutex};
+
+use crate::arrow_table::ArrowTable;
+use crate::utils::create_filter;
+use crate::LumniError;
+
+// start with :: to ensure local crate is used
+use ::lumni::{
+    EnvironmentConfig, ObjectStoreHandler, ParsedUri, TableColumnValue,
+    UploadOptions, AWS_DEFAULT_REGION, DEFAULT_STREAM_BATCH_SIZE,
+};
+
+type RowData = Vec<(String, TableColumnValue)>;
+
+// batches a listing can be ahead of the rows read by python
+const PENDING_BATCHES: usize = 2;
+
+// client for asyncio, e.g. `await client.get("s3://bucket/key")`. the
+// methods return awaitables that run on the tokio runtime of
+// pyo3-async-runtimes,
+// so the event loop is not blocked while objects are listed or transferred
+#[pyclass]
+pub struct AsyncClient {
+    config: EnvironmentConfig,
+}
+
+#[pymethods]
+impl AsyncClient {
+    #[new]
+    #[pyo3(signature = (region=None))]
+    fn new(region: Option<String>) -> PyResult<Self> {
+        let region = region
+            .or_else(|| env::var("AWS_REGION").ok())
+            .unwrap_or_else(|| AWS_DEFAULT_REGION.to_string());
+
+        let mut settings = HashMap::new();
+        settings.insert("region".to_string(), region);
+        Ok(AsyncClient {
+            config: EnvironmentConfig::new(settings),
+        })
+    }
+
+    // awaits a list of dicts, one per object (or bucket, for e.g. "s3://")
+    #[pyo3(signature = (uri, recursive=None, max_files=None, filter_dict=None))]
+    fn list<'p>(
+        &self,
+        py: Python<'p>,
+        uri: String,
+        recursive: Option<bool>,
+        max_files: Option<u32>,
+        filter_dict: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Bound<'p, PyAny>> {
+        let filter = create_filter(py, filter_dict)?;
+        let config = self.config.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let rows = run_local(move || async move {
+                let parsed_uri = ParsedUri::from_uri(&uri, true);
+                let handler = ObjectStoreHandler::new(None);
+                let mut stream = handler.list_objects_stream(
+                    &parsed_uri,
+                    &config,
+                    None,
+                    recursive.unwrap_or(false),
+                    max_files,
+                    &filter,
+                    DEFAULT_STREAM_BATCH_SIZE,
+                );
+                let mut rows = Vec::new();
+                while let Some(batch) = stream.next().await {
+                    rows.extend(
+                        batch?.into_iter().map(|row| row.data().to_vec()),
+                    );
+                }
+                Ok(rows)
+            })
+            .await
+            .map_err(|e| to_py_error("Error listing objects", e))?;
+            Ok(rows.into_iter().map(Row).collect::<Vec<_>>())
+        })
+    }
+
+    // as list, but awaits the listing as a Table of Arrow columns
+    #[pyo3(signature = (uri, recursive=None, max_files=None, filter_dict=None))]
+    fn list_table<'p>(
+        &self,
+        py: Python<'p>,
+        uri: String,
+        recursive: Option<bool>,
+        max_files: Option<u32>,
+        filter_dict: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Bound<'p, PyAny>> {
+        let filter = create_filter(py, filter_dict)?;
+        let config = self.config.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            run_local(move || async move {
+                let parsed_uri = ParsedUri::from_uri(&uri, true);
+                let table = ObjectStoreHandler::new(None)
+                    .list_objects(
+                        &parsed_uri,
+                        &config,
+                        None,
+                        recursive.unwrap_or(false),
+                        max_files,
+                        &filter,
+                        None,
+                    )
+                    .await?;
+                ArrowTable::from_table(table.as_ref())
+            })
+            .await
+            .map_err(|e| to_py_error("Error listing objects", e))
+        })
+    }
+
+    // as list, but returns an async iterator that yields the rows while
+    // they are listed, e.g. `async for row in client.list_stream(uri)`
+    #[pyo3(signature = (
+        uri,
+        recursive=None,
+        max_files=None,
+        filter_dict=None,
+        batch_size=None
+    ))]
+    fn list_stream(
+        &self,
+        py: Python,
+        uri: String,
+        recursive: Option<bool>,
+        max_files: Option<u32>,
+        filter_dict: Option<&Bound<'_, PyDict>>,
+        batch_size: Option<usize>,
+    ) -> PyResult<RowStream> {
+        let filter = create_filter(py, filter_dict)?;
+        let config = self.config.clone();
+        let batch_size = batch_size.unwrap_or(DEFAULT_STREAM_BATCH_SIZE);
+        let (sender, receiver) = mpsc::channel(PENDING_BATCHES);
+        let runtime = pyo3_async_runtimes::tokio::get_runtime();
+        let _guard = runtime.enter();
+        // the listing stops when the iterator is dropped, as the next
+        // batch can then no longer be sent
+        tokio::spawn(run_local(move || async move {
+            let parsed_uri = ParsedUri::from_uri(&uri, true);
+            let handler = ObjectStoreHandler::new(None);
+            let mut stream = handler.list_objects_stream(
+                &parsed_uri,
+                &config,
+                None,
+                recursive.unwrap_or(false),
+                max_files,
+                &filter,
+                batch_size,
+            );
+            while let Some(batch) = stream.next().await {
+                let batch = batch
+                    .map(|rows| {
+                        rows.into_iter()
+                            .map(|row| row.data().to_vec())
+                            .collect()
+                    })
+                    .map_err(|e| e.to_string());
+                let failed = batch.is_err();
+                if sender.send(batch).await.is_err() || failed {
+                    break;
+                }
+            }
+            Ok(())
+        }));
+        Ok(RowStream {
+            state: Arc::new(Mutex::new(StreamState {
+                receiver,
+                rows: VecDeque::new(),
+            })),
+        })
+    }
+
+    // awaits the contents of the object as bytes
+    fn get<'p>(
+        &self,
+        py: Python<'p>,
+        uri: String,
+    ) -> PyResult<Bound<'p, PyAny>> {
+        let config = self.config.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let data = run_local(move || async move {
+                let parsed_uri = ParsedUri::from_uri(&uri, false);
+                ObjectStoreHandler::new(None)
+                    .get_object(&parsed_uri, &config, None)
+                    .await
+            })
+            .await
+            .map_err(|e| to_py_error("Error getting object", e))?
+            .ok_or_else(|| LumniError::new_err("No data received"))?;
+            Ok(Python::with_gil(|py| PyBytes::new(py, &data).unbind()))
+        })
+    }
+
+    // writes the bytes to the object, awaits the number of bytes written
+    #[pyo3(signature = (uri, data, content_type=None))]
+    fn put<'p>(
+        &self,
+        py: Python<'p>,
+        uri: String,
+        data: Vec<u8>,
+        content_type: Option<String>,
+    ) -> PyResult<Bound<'p, PyAny>> {
+        let config = self.config.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            run_local(move || async move {
+                let parsed_uri = ParsedUri::from_uri(&uri, false);
+                let mut options = UploadOptions::new();
+                if let Some(content_type) = &content_type {
+                    options = options.set_content_type(content_type);
+                }
+                ObjectStoreHandler::new(None)
+                    .put_object(
+                        &parsed_uri,
+                        &config,
+                        &mut Cursor::new(data),
+                        &options,
+                        None,
+                    )
+                    .await
+            })
+            .await
+            .map_err(|e| to_py_error("Error putting object", e))
+        })
+    }
+}
+
+struct StreamState {
+    receiver: mpsc::Receiver<Result<Vec<RowData>, String>>,
+    rows: VecDeque<RowData>, // rows of the last batch not yielded yet
+}
+
+// async iterator of the rows of a listing, as dicts
+#[pyclass]
+pub struct RowStream {
+    state: Arc<Mutex<StreamState>>,
+}
+
+#[pymethods]
+impl RowStream {
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __anext__<'p>(
+        &self,
+        py: Python<'p>,
+    ) -> PyResult<Option<Bound<'p, PyAny>>> {
+        let state = Arc::clone(&self.state);
+        let next =
+            pyo3_async_runtimes::tokio::future_into_py(py, async move {
+                let mut state = state.lock().await;
+                loop {
+                    if let Some(row) = state.rows.pop_front() {
+                        return Ok(Row(row));
+                    }
+                    match state.receiver.recv().await {
+                        Some(Ok(rows)) => state.rows.extend(rows),
+                        Some(Err(e)) => {
+                            return Err(to_py_error("Error listing objects", e))
+                        }
+                        None => {
+                            return Err(PyStopAsyncIteration::new_err(()));
+                        }
+                    }
+                }
+            })?;
+        Ok(Some(next))
+    }
+}
+
+// a row of a listing, converted to a dict when it is passed to python
+struct Row(RowData);
+
+impl<'py> IntoPyObject<'py> for Row {
+    type Target = PyDict;
+    type Output = Bound<'py, PyDict>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> PyResult<Self::Output> {
+        let dict = PyDict::new(py);
+        for (name, value) in self.0 {
+            match value {
+                TableColumnValue::Int32Column(v) => dict.set_item(name, v),
+                TableColumnValue::Uint64Column(v) => dict.set_item(name, v),
+                TableColumnValue::FloatColumn(v) => dict.set_item(name, v),
+                TableColumnValue::StringColumn(v) => dict.set_item(name, v),
+                TableColumnValue::OptionalInt32Column(v) => {
+                    dict.set_item(name, v)
+                }
+                TableColumnValue::OptionalUint64Column(v) => {
+                    dict.set_item(name, v)
+                }
+                TableColumnValue::OptionalFloatColumn(v) => {
+                    dict.set_item(name, v)
+                }
+                TableColumnValue::OptionalStringColumn(v) => {
+                    dict.set_item(name, v)
+                }
+            }?;
+        }
+        Ok(dict)
+    }
+}
+
+// the object store futures (and lumni::LumniError) are not Send, so these are run
+// to completion on a thread of the blocking pool, which keeps the event loop
+// free. errors are passed on as their message
+async fn run_local<F, Fut, T>(f: F) -> Result<T, String>
+where
+    F: FnOnce() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<T, ::lumni::LumniError>>,
+    T: Send + 'static,
+{
+    let handle = Handle::current();
+    tokio::task::spawn_blocking(move || {
+        handle.block_on(f()).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn to_py_error(message: &str, error: String) -> PyErr {
+    LumniError::new_err(format!("{}: {}", message, error))
+}
@@ -1,16 +1,16 @@
-
 use std::collections::HashMap;
 use std::env;
+use std::process::Command;
 use pyo3::prelude::*;
 use pyo3::types::{PyList, PyDict, PyBytes};
 
-use crate::LakestreamError;
+use crate::LumniError;
+use crate::arrow_table::ArrowTable;
 use crate::utils::create_filter;
 use tokio::runtime::Runtime;
 
 // start with :: to ensure local crate is used
-use ::lumni::{ListObjectsResult, ObjectStoreHandler, EnvironmentConfig, AWS_DEFAULT_REGION};
-use ::lumni_cli::run_cli;
+use ::lumni::{ObjectStoreHandler, EnvironmentConfig, ParsedUri, Table, TableColumnValue, AWS_DEFAULT_REGION};
 
 #[pyclass]
 pub struct _Client {
@@ -20,36 +20,36 @@ pub struct _Client {
 #[pymethods]
 impl _Client {
     #[new]
+    #[pyo3(signature = (region=None))]
     fn new(region: Option<String>) -> PyResult<Self> {
         let region = region
             .or_else(|| env::var("AWS_REGION").ok())
             .unwrap_or_else(|| AWS_DEFAULT_REGION.to_string());
 
-        let mut config = HashMap::new();
-        config.insert("region".to_string(), region);
-        let config = EnvironmentConfig {
-            settings: config,
-        };
-        Ok(_Client { config })
+        let mut settings = HashMap::new();
+        settings.insert("region".to_string(), region);
+        Ok(_Client {
+            config: EnvironmentConfig::new(settings),
+        })
     }
 
-    fn cli(&self, args: &PyList) -> PyResult<()> {
-        let mut args: Vec<String> = args
-            .iter()
-            .map(|arg| arg.extract::<String>().unwrap())
-            .collect();
-        args.insert(0, "lumni".to_string());
-        run_cli(args);
-        Ok(())
+    // the cli is the lumni executable (`cargo install lumni`), which is
+    // run with the arguments. returns its exit code
+    fn cli(&self, args: Vec<String>) -> PyResult<i32> {
+        let status = Command::new("lumni").args(&args).status().map_err(|e| {
+            LumniError::new_err(format!("Error running lumni: {}", e))
+        })?;
+        Ok(status.code().unwrap_or(1))
     }
 
+    #[pyo3(signature = (uri, recursive=None, max_files=None, filter_dict=None))]
     fn list_objects(
         &self,
         py: Python,
         uri: String,
         recursive: Option<bool>,
         max_files: Option<u32>,
-        filter_dict: Option<&PyDict>,
+        filter_dict: Option<&Bound<'_, PyDict>>,
     ) -> PyResult<PyObject> {
         // Get the namedtuple function from the collections module
         let collections = py.import("collections")?;
@@ -63,13 +63,14 @@ impl _Client {
         let filter = create_filter(py, filter_dict)?;
 
         // Create a new Tokio runtime
-        let rt = Runtime::new().unwrap();
+        let rt = Runtime::new()?;
 
         // Call the async function and block on it to get the result
         let handler = ObjectStoreHandler::new(None);
         let result = rt.block_on(handler.list_objects(
-            &uri,
+            &ParsedUri::from_uri(&uri, true),
             &self.config,
+            Some(vec!["name", "size", "modified"]),
             recursive.unwrap_or(false),
             max_files,
             &filter,
@@ -77,29 +78,57 @@ impl _Client {
         ));
 
         match result {
-            Ok(Some(list_objects_result)) => match list_objects_result {
-                ListObjectsResult::FileObjects(file_objects) => {
-                    let py_file_objects = file_objects
-                        .into_iter()
-                        .map(|fo| {
-                            // Create instances of the FileObject NamedTuple
-                            file_object_named_tuple.call1((
-                                fo.name(),
-                                fo.size(),
-                                fo.modified().unwrap_or_default(),
-                            ))
-                        })
-                        .collect::<Result<Vec<_>, _>>()?; // Collect the PyResult values into a single Result
-                    Ok(PyList::new(py, &py_file_objects).to_object(py))
-                }
-                _ => {
-                    let lumni_error = LakestreamError::new_err(format!("Error listing objects: {}", "Unknown error"));
-                    Err(lumni_error)
-                },
+            Ok(table) => {
+                let py_file_objects = (0..table.len())
+                    .map(|index| {
+                        // Create instances of the FileObject NamedTuple
+                        file_object_named_tuple.call1((
+                            string_value(table.as_ref(), "name", index),
+                            u64_value(table.as_ref(), "size", index).unwrap_or_default(),
+                            u64_value(table.as_ref(), "modified", index).unwrap_or_default(),
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?; // Collect the PyResult values into a single Result
+                Ok(PyList::new(py, py_file_objects)?.into_any().unbind())
+            }
+            Err(err) => {
+                let lumni_error = LumniError::new_err(format!("Error listing objects: {}", err));
+                Err(lumni_error)
             },
-            Ok(None) => Ok(PyList::empty(py).to_object(py)),
+        }
+    }
+
+    #[pyo3(signature = (uri, recursive=None, max_files=None, filter_dict=None))]
+    fn list_table(
+        &self,
+        py: Python,
+        uri: String,
+        recursive: Option<bool>,
+        max_files: Option<u32>,
+        filter_dict: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<ArrowTable> {
+        // Create the filter from the dictionary
+        let filter = create_filter(py, filter_dict)?;
+
+        // Create a new Tokio runtime
+        let rt = Runtime::new()?;
+
+        // Call the async function and block on it to get the result
+        let handler = ObjectStoreHandler::new(None);
+        let result = rt.block_on(handler.list_objects(
+            &ParsedUri::from_uri(&uri, true),
+            &self.config,
+            None,
+            recursive.unwrap_or(false),
+            max_files,
+            &filter,
+            None,
+        ));
+
+        match result.and_then(|table| ArrowTable::from_table(table.as_ref())) {
+            Ok(table) => Ok(table),
             Err(err) => {
-                let lumni_error = LakestreamError::new_err(format!("Error listing objects: {}", err));
+                let lumni_error = LumniError::new_err(format!("Error listing objects: {}", err));
                 Err(lumni_error)
             },
         }
@@ -111,34 +140,27 @@ impl _Client {
         uri: String,
     ) -> PyResult<PyObject> {
         // Create a new Tokio runtime
-        let rt = Runtime::new().unwrap();
+        let rt = Runtime::new()?;
 
         // Call the async function and block on it to get the result
         let handler = ObjectStoreHandler::new(None);
-        // let uri = format!("{}://", self.config.scheme());
         let result = rt.block_on(handler.list_buckets(
-            &uri,
+            &ParsedUri::from_uri(&uri, true),
             &self.config,
+            &Some(vec!["name"]),
+            None,
             None,
         ));
 
         match result {
-            Ok(Some(list_objects_result)) => match list_objects_result {
-                ListObjectsResult::Buckets(buckets) => {
-                    let py_buckets = buckets
-                        .into_iter()
-                        .map(|bucket| bucket.name().to_owned())
-                        .collect::<Vec<_>>();
-                    Ok(PyList::new(py, &py_buckets).to_object(py))
-                }
-                _ => {
-                    let lumni_error = LakestreamError::new_err(format!("Error listing buckets"));
-                    Err(lumni_error)
-                },
-            },
-            Ok(None) => Ok(PyList::empty(py).to_object(py)),
+            Ok(table) => {
+                let py_buckets = (0..table.len())
+                    .map(|index| string_value(table.as_ref(), "name", index))
+                    .collect::<Vec<_>>();
+                Ok(PyList::new(py, py_buckets)?.into_any().unbind())
+            }
             Err(err) => {
-                let lumni_error = LakestreamError::new_err(format!("Error listing buckets: {}", err));
+                let lumni_error = LumniError::new_err(format!("Error listing buckets: {}", err));
                 Err(lumni_error)
             },
         }
@@ -146,19 +168,47 @@ impl _Client {
 
     fn get_object(&self, py: Python, uri: String) -> PyResult<PyObject> {
         // Create a new Tokio runtime
-        let rt = Runtime::new().unwrap();
+        let rt = Runtime::new()?;
 
         // Call the async function and block on it to get the result
         let handler = ObjectStoreHandler::new(None);
-        let result = rt.block_on(handler.get_object(&uri, &self.config, None));
+        let result = rt.block_on(handler.get_object(
+            &ParsedUri::from_uri(&uri, false),
+            &self.config,
+            None,
+        ));
 
         match result {
-            Ok(Some(data)) => Ok(PyBytes::new(py, &data).to_object(py)),
-            Ok(None) => Err(LakestreamError::new_err("No data received")),
+            Ok(Some(data)) => Ok(PyBytes::new(py, &data).into_any().unbind()),
+            Ok(None) => Err(LumniError::new_err("No data received")),
             Err(err) => {
-                let lumni_error = LakestreamError::new_err(format!("Error getting object: {}", err));
+                let lumni_error = LumniError::new_err(format!("Error getting object: {}", err));
                 Err(lumni_error)
             },
         }
     }
 }
+
+fn column_value(table: &dyn Table, name: &str, index: usize) -> Option<TableColumnValue> {
+    table
+        .columns()
+        .iter()
+        .find(|(column, _)| column == name)
+        .and_then(|(_, column)| column.get_value(index))
+}
+
+fn string_value(table: &dyn Table, name: &str, index: usize) -> String {
+    match column_value(table, name, index) {
+        Some(TableColumnValue::StringColumn(value))
+        | Some(TableColumnValue::OptionalStringColumn(Some(value))) => value,
+        _ => String::new(),
+    }
+}
+
+fn u64_value(table: &dyn Table, name: &str, index: usize) -> Option<u64> {
+    match column_value(table, name, index) {
+        Some(TableColumnValue::Uint64Column(value))
+        | Some(TableColumnValue::OptionalUint64Column(Some(value))) => Some(value),
+        _ => None,
+    }
+}
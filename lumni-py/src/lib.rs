@@ -1,4 +1,5 @@
-
+mod arrow_table;
+mod async_client;
 mod client;
 mod utils;
 
@@ -8,14 +9,16 @@ use pyo3::prelude::*;
 use pyo3::create_exception;
 use pyo3::exceptions::PyException;
 
-create_exception!(lumni, LakestreamError, PyException, "An error occurred in the lumni library.");
+create_exception!(lumni, LumniError, PyException, "An error occurred in the lumni library.");
 
 
 
 #[pymodule]
-fn lumni(_py: Python, m: &PyModule) -> PyResult<()> {
+fn lumni(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("LumniError", m.py().get_type::<LumniError>())?;
     m.add_class::<client::_Client>()?;
+    m.add_class::<async_client::AsyncClient>()?;
+    m.add_class::<async_client::RowStream>()?;
+    m.add_class::<arrow_table::ArrowTable>()?;
     Ok(())
 }
-
-
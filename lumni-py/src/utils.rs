@@ -5,19 +5,19 @@ use pyo3::exceptions;
 use ::lumni::FileObjectFilter;
 
 
-pub fn create_filter(py: Python, filter_dict: Option<&PyDict>) -> PyResult<Option<FileObjectFilter>> {
+pub fn create_filter(py: Python, filter_dict: Option<&Bound<'_, PyDict>>) -> PyResult<Option<FileObjectFilter>> {
     // Create the filter from the dictionary
-    let filter = filter_dict.as_ref().map(|filter_dict| {
-        let filter_name = extract_first_value(py, filter_dict.get_item("name"));
-        let filter_size = extract_first_value(py, filter_dict.get_item("size"));
-        let filter_mtime = extract_first_value(py, filter_dict.get_item("mtime"));
+    let filter = filter_dict.map(|filter_dict| -> PyResult<_> {
+        let filter_name = extract_first_value(py, filter_dict.get_item("name")?);
+        let filter_size = extract_first_value(py, filter_dict.get_item("size")?);
+        let filter_mtime = extract_first_value(py, filter_dict.get_item("mtime")?);
 
-        FileObjectFilter::new(
+        Ok(FileObjectFilter::new(
             filter_name.as_deref(),
             filter_size.as_deref(),
             filter_mtime.as_deref(),
-        )
-    });
+        ))
+    }).transpose()?;
 
     let filter = match filter {
         Some(Ok(filter)) => Some(filter),
@@ -32,7 +32,7 @@ pub fn create_filter(py: Python, filter_dict: Option<&PyDict>) -> PyResult<Optio
     PyResult::Ok(filter)
 }
 
-fn extract_first_value(_py: Python, value: Option<&PyAny>) -> Option<String> {
+fn extract_first_value(_py: Python, value: Option<Bound<'_, PyAny>>) -> Option<String> {
     if let Some(value) = value {
         if let Ok(s) = value.extract::<String>() {
             Some(s)
@@ -0,0 +1,76 @@
+import asyncio
+import os
+import tempfile
+import unittest
+
+import lumni
+
+
+class TestClient(unittest.TestCase):
+    def setUp(self):
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.path = self.tmpdir.name
+        with open(os.path.join(self.path, "a.txt"), "wb") as f:
+            f.write(b"hello")
+        self.uri = "localfs://" + self.path
+        self.client = lumni.Client()
+
+    def tearDown(self):
+        self.tmpdir.cleanup()
+
+    def test_list_objects(self):
+        objects = self.client.list_objects(self.uri)
+        self.assertEqual(len(objects), 1)
+        self.assertTrue(objects[0].name.endswith("a.txt"))
+        self.assertEqual(objects[0].size, 5)
+
+    def test_get_object(self):
+        data = self.client.get_object(self.uri + "/a.txt")
+        self.assertEqual(data, b"hello")
+
+    def test_get_missing_object(self):
+        with self.assertRaises(lumni.LumniError):
+            self.client.get_object(self.uri + "/missing.txt")
+
+    def test_list_table(self):
+        table = self.client.list_table(self.uri)
+        self.assertEqual(len(table), 1)
+        self.assertEqual(table.column_names(), ["name", "size", "modified"])
+        try:
+            import pyarrow  # noqa: F401
+        except ImportError:
+            self.skipTest("pyarrow is not installed")
+        batch = table.to_arrow()
+        self.assertEqual(batch.column("size").to_pylist(), [5])
+
+
+class TestAsyncClient(unittest.TestCase):
+    def setUp(self):
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.uri = "localfs://" + self.tmpdir.name
+        self.client = lumni.AsyncClient()
+
+    def tearDown(self):
+        self.tmpdir.cleanup()
+
+    def test_put_get_list(self):
+        async def run():
+            written = await self.client.put(self.uri + "/b.txt", b"xyz")
+            data = await self.client.get(self.uri + "/b.txt")
+            rows = await self.client.list(self.uri)
+            streamed = [
+                row async for row in self.client.list_stream(
+                    self.uri, batch_size=1
+                )
+            ]
+            return written, data, rows, streamed
+
+        written, data, rows, streamed = asyncio.run(run())
+        self.assertEqual(written, 3)
+        self.assertEqual(data, b"xyz")
+        self.assertEqual([row["size"] for row in rows], [3])
+        self.assertEqual(streamed, rows)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -8,4 +8,4 @@ pub(crate) mod vars;
 pub mod app;
 
 pub use base::state::{GlobalState, RunTime};
-pub use lumni::LakestreamError;
+pub use lumni::LumniError;
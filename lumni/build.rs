@@ -69,7 +69,7 @@ fn update_build_version() {
             version
         };
 
-        let crates = &["lumni", "lumni-py", "lumni-web"];
+        let crates = &["lumni", "lumni-py", "lumni-web", "lumni-js"];
 
         for crate_name in crates {
             let path = Path::new("..").join(crate_name).join("Cargo.toml");
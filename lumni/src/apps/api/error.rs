@@ -1,19 +1,10 @@
 use std::fmt;
 
 // export the http client error via api::error
+pub use crate::error::LumniError;
+#[cfg(feature = "http_client")]
 pub use crate::http::client::HttpClientError;
 
-#[allow(dead_code)]
-#[derive(Debug)]
-pub enum LumniError {
-    Request(RequestError),
-    Runtime(RuntimeError),
-    Application(ApplicationError, Option<String>),
-    Invoke(ApplicationError, Option<String>),
-    NotImplemented(String),
-    Message(String),
-}
-
 #[derive(Debug, Clone)]
 pub enum RequestError {
     QueryInvalid(String),
@@ -27,10 +18,12 @@ pub enum ApplicationError {
     Runtime(String),
     InvalidCredentials(String),
     ServerConfigurationError(String),
+    #[cfg(feature = "http_client")]
     HttpClientError(HttpClientError),
     IoError(std::io::Error),
     NotImplemented(String),
     NotReady(String),
+    Conflict(String), // changed elsewhere since it was read
 }
 
 #[allow(dead_code)]
@@ -39,33 +32,6 @@ pub enum RuntimeError {
     Unexpected(String),
 }
 
-impl fmt::Display for LumniError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            LumniError::Request(req_err) => {
-                write!(f, "RequestError: {}", req_err)
-            }
-            LumniError::Runtime(runtime_err) => {
-                write!(f, "RuntimeError: {}", runtime_err)
-            }
-            LumniError::Application(app_err, Some(app_name)) => {
-                write!(f, "[{}]: {}", app_name, app_err)
-            }
-            LumniError::Invoke(app_err, Some(app_name)) => {
-                write!(f, "[{}]: {}", app_name, app_err)
-            }
-            LumniError::Invoke(app_err, None) => {
-                write!(f, "InvokeError: {}", app_err)
-            }
-            LumniError::Application(app_err, None) => {
-                write!(f, "ApplicationError: {}", app_err)
-            }
-            LumniError::NotImplemented(s) => write!(f, "NotImplemented: {}", s),
-            LumniError::Message(s) => write!(f, "{}", s),
-        }
-    }
-}
-
 impl fmt::Display for RequestError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -107,6 +73,7 @@ impl fmt::Display for ApplicationError {
             ApplicationError::ServerConfigurationError(s) => {
                 write!(f, "ServerConfigurationError: {}", s)
             }
+            #[cfg(feature = "http_client")]
             ApplicationError::HttpClientError(e) => {
                 write!(f, "HttpClientError: {}", e)
             }
@@ -115,12 +82,14 @@ impl fmt::Display for ApplicationError {
                 write!(f, "NotImplemented: {}", s)
             }
             ApplicationError::NotReady(s) => write!(f, "NotReady: {}", s),
+            ApplicationError::Conflict(s) => write!(f, "Conflict: {}", s),
         }
     }
 }
 
 impl std::error::Error for ApplicationError {}
 
+#[cfg(feature = "http_client")]
 impl From<HttpClientError> for ApplicationError {
     fn from(error: HttpClientError) -> Self {
         ApplicationError::HttpClientError(error)
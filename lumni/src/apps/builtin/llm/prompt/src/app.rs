@@ -1,11 +1,13 @@
-use std::io;
+use std::io::{self, IsTerminal};
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 
-use clap::{Arg, Command};
+use bytes::Bytes;
+use clap::{Arg, ArgAction, Command};
 use crossterm::cursor::Show;
 use crossterm::event::{
-    poll, read, DisableMouseCapture, EnableMouseCapture, Event, KeyCode,
+    poll, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste,
+    EnableMouseCapture, Event, KeyCode, KeyModifiers, MouseButton,
     MouseEventKind,
 };
 use crossterm::execute;
@@ -15,6 +17,7 @@ use crossterm::terminal::{
 };
 use lumni::api::error::ApplicationError;
 use lumni::api::spec::ApplicationSpec;
+use lumni::{MemoryBudget, Table, TableExportOptions};
 use ratatui::backend::{Backend, CrosstermBackend};
 use ratatui::style::Style;
 use ratatui::Terminal;
@@ -22,13 +25,25 @@ use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
 use tokio::signal;
 use tokio::sync::{mpsc, Mutex};
 use tokio::time::{interval, timeout, Duration};
+use tracing::{info_span, Instrument};
 
-use super::chat::ChatSession;
-use super::server::{ModelServer, PromptInstruction, ServerTrait};
+use super::chat::{
+    conversation_stats, ChatSession, CommandRedactor, EmailExporter,
+    EvalHistory, EvalRunner, EvalSuite, FinetuneExporter, LanguagePreference,
+    ObjectListing, PatternRedactor, ProfileStore, PromptProfile,
+    QueryAssistant, QueryCommand, SessionFactory, SessionPool, SnippetLibrary,
+    VaultExporter, Vote, WebhookDispatcher, DEFAULT_POOL_IDLE_TTL_SECONDS,
+    GRADER_INSTRUCTION, SQL_COMMAND,
+};
+use super::server::{
+    list_models_cached, Capability, LLMDefinition, ModelCatalog, ModelServer,
+    SUPPORTED_MODEL_ENDPOINTS,
+};
 use super::session::AppSession;
 use super::tui::{
-    ColorScheme, ColorSchemeType, CommandLineAction, KeyEventHandler,
-    PromptAction, TabUi, TextWindowTrait, WindowEvent,
+    ColorScheme, ColorSchemeType, CommandLineAction, InputQueue,
+    is_reply, KeyEventHandler, MessageAction, PromptAction, PromptRole,
+    RenderPacer, TabUi, TextWindowTrait, TrashCommand, WindowEvent,
 };
 pub use crate::external as lumni;
 
@@ -39,35 +54,61 @@ const CHANNEL_QUEUE_SIZE: usize = 32;
 async fn prompt_app<B: Backend>(
     terminal: &mut Terminal<B>,
     mut app_session: AppSession<'_>,
+    mut session_pool: SessionPool,
+    mut render_pacer: RenderPacer,
 ) -> Result<(), ApplicationError> {
     let tab = app_session.get_tab_mut(0).expect("No tab found");
+    session_pool.maintain();
 
     let (tx, mut rx) = mpsc::channel(CHANNEL_QUEUE_SIZE);
     let mut tick = interval(Duration::from_millis(1));
     let keep_running = Arc::new(AtomicBool::new(false));
     let mut current_mode = Some(WindowEvent::PromptWindow);
     let mut key_event_handler = KeyEventHandler::new();
+    let mut input_queue = InputQueue::new();
     let mut redraw_ui = true;
 
     // Buffer to store the trimmed trailing newlines or empty spaces
     let mut trim_buffer: Option<String> = None;
+    // final response received, finalized once its text is rendered
+    let mut pending_finalize: Option<Option<usize>> = None;
+    // requests of /query, waiting for a query or its results
+    let mut query_assistant = QueryAssistant::new();
 
     // TODO: add color scheme selection via modal
     let color_scheme = ColorScheme::new(ColorSchemeType::Default);
+    tab.ui.set_gutter_style(color_scheme.get_gutter_style());
 
     loop {
         tokio::select! {
             _ = tick.tick() => {
+                session_pool.maintain();
+                if tab.ui.poll_resize() {
+                    redraw_ui = true;
+                }
+                if let Some(text) = render_pacer.next_frame() {
+                    tab.ui.response.text_append_with_insert(&text, Some(color_scheme.get_secondary_style()));
+                    redraw_ui = true;
+                }
+                if render_pacer.is_empty() {
+                    if let Some(tokens_predicted) = pending_finalize.take() {
+                        finalize_response(&mut tab.chat, &mut tab.ui, tokens_predicted, &mut query_assistant, &color_scheme).await?;
+                        redraw_ui = true;
+                    }
+                }
                 if redraw_ui {
                     tab.draw_ui(terminal)?;
                     redraw_ui = false;
                 }
                 let mut tab_ui = &mut tab.ui;
-                let mut chat = &mut tab.chat;
+                let chat = &mut tab.chat;
 
+                // characters typed in the prompt arrive as text when
+                // committed at once, e.g. by an input method
+                let text_mode = matches!(current_mode, Some(WindowEvent::PromptWindow))
+                    && tab_ui.prompt.is_status_insert();
                 // set timeout to 1ms to allow for non-blocking polling
-                if poll(Duration::from_millis(1))? {
-                    let event = read()?;
+                if let Some(event) = input_queue.next_event(Duration::from_millis(1), text_mode)? {
                     match event {
                         Event::Key(key_event) => {
                             if key_event.code == KeyCode::Tab {
@@ -84,6 +125,7 @@ async fn prompt_app<B: Backend>(
                                         }
                                     }
                                     Some(WindowEvent::ResponseWindow) => {
+                                        tab_ui.message_menu = None;
                                         tab_ui.response.set_status_inactive();
                                         tab_ui.prompt.set_status_normal();
                                         Some(WindowEvent::PromptWindow)
@@ -122,35 +164,166 @@ async fn prompt_app<B: Backend>(
                                     break;
                                 }
                                 Some(WindowEvent::Prompt(prompt_action)) => {
+                                    // actions on a message return to the response window
+                                    let from_response = matches!(prompt_action, PromptAction::Message(..))
+                                        && tab_ui.response.is_active();
                                     match prompt_action {
                                         PromptAction::Write(prompt) => {
-                                            // prompt should end with single newline
-                                            let formatted_prompt = format!("{}\n", prompt.trim_end());
-
-                                            tab_ui.response.text_append_with_insert(
-                                                &formatted_prompt,
-                                                //Some(PromptStyle::user()),
-                                                Some(color_scheme.get_primary_style()),
-                                            );
-                                            tab_ui.response.text_append_with_insert(
-                                                "\n",
-                                                Some(Style::reset()),
-                                            );
-
-                                            chat.message(tx.clone(), formatted_prompt).await?;
+                                            // previous response must be complete before the next exchange
+                                            if let Some(tokens_predicted) = pending_finalize.take() {
+                                                if let Some(text) = render_pacer.flush() {
+                                                    tab_ui.response.text_append_with_insert(&text, Some(color_scheme.get_secondary_style()));
+                                                }
+                                                finalize_response(chat, tab_ui, tokens_predicted, &mut query_assistant, &color_scheme).await?;
+                                            }
+                                            if let Some(listing) = ObjectListing::from_prompt(&prompt) {
+                                                add_listing(chat, tab_ui, listing, &color_scheme).await;
+                                            } else if let Some(command) = QueryCommand::from_prompt(&prompt) {
+                                                run_query_command(chat, tab_ui, &tx, command, &mut query_assistant, &color_scheme).await;
+                                            } else {
+                                                send_prompt(chat, tab_ui, &tx, &prompt, &color_scheme).await;
+                                            }
                                         }
                                         PromptAction::Clear => {
+                                            render_pacer.clear();
+                                            pending_finalize = None;
                                             tab_ui.response.text_empty();
+                                            tab_ui.messages.clear();
+                                            tab_ui.reply_to = None;
                                             chat.reset();
+                                            query_assistant.reset();
                                             trim_buffer = None;
                                         }
+                                        PromptAction::New => {
+                                            // start over with a warm session from the pool
+                                            match session_pool.take().await {
+                                                Ok(session) => {
+                                                    chat.stop();
+                                                    render_pacer.clear();
+                                                    pending_finalize = None;
+                                                    *chat = session;
+                                                    tab_ui.response.text_empty();
+                                                    tab_ui.messages.clear();
+                                                    tab_ui.reply_to = None;
+                                                    query_assistant.reset();
+                                                    trim_buffer = None;
+                                                }
+                                                Err(e) => {
+                                                    tab_ui.command_line.text_set(&e.to_string(), None);
+                                                }
+                                            }
+                                        }
+                                        PromptAction::Notify(name) => {
+                                            let message = match chat.notify(name.as_deref()) {
+                                                0 => "No webhook configured for notify".to_string(),
+                                                count => format!("Notification sent to {} webhook(s)", count),
+                                            };
+                                            tab_ui.command_line.text_set(&message, None);
+                                        }
+                                        PromptAction::Email(address) => {
+                                            let message = match chat.email_conversation(&address).await {
+                                                Ok(_) => format!("Conversation sent to {}", address),
+                                                Err(e) => e.to_string(),
+                                            };
+                                            tab_ui.command_line.text_set(&message, None);
+                                        }
+                                        PromptAction::Vault => {
+                                            let message = match chat.export_to_vault().await {
+                                                Ok(location) => format!("Conversation exported to {}", location),
+                                                Err(e) => e.to_string(),
+                                            };
+                                            tab_ui.command_line.text_set(&message, None);
+                                        }
+                                        PromptAction::Reconnect => {
+                                            let message = match chat.reconnect().await {
+                                                Ok(_) => "Reconnected".to_string(),
+                                                Err(e) => format!("Reconnect failed: {}", e),
+                                            };
+                                            tab_ui.command_line.text_set(&message, None);
+                                        }
+                                        PromptAction::Message(action, index) => {
+                                            // the conversation can not change while an answer streams in
+                                            let message = if trim_buffer.is_some() || pending_finalize.is_some() {
+                                                Some("Wait for the response to finish".to_string())
+                                            } else {
+                                                run_message_action(action, index, chat, tab_ui, &mut session_pool, &tx, &color_scheme)
+                                                    .await
+                                                    .unwrap_or_else(|e| Some(e.to_string()))
+                                            };
+                                            if let Some(message) = message {
+                                                tab_ui.command_line.text_set(&message, None);
+                                            }
+                                        }
+                                        PromptAction::Annotate(index, note) => {
+                                            let message = match chat.exchange_mut(index) {
+                                                Some(exchange) if note.is_empty() => {
+                                                    exchange.set_annotation(None);
+                                                    format!("Removed the note on message {}", index + 1)
+                                                }
+                                                Some(exchange) => {
+                                                    exchange.set_annotation(Some(note));
+                                                    format!("Annotated message {}", index + 1)
+                                                }
+                                                None => format!("No message {}", index + 1),
+                                            };
+                                            tab_ui.command_line.text_set(&message, None);
+                                        }
+                                        PromptAction::Vote(index, vote, reason) => {
+                                            let message = match chat.exchange_mut(index) {
+                                                Some(exchange) => {
+                                                    exchange.set_vote(vote);
+                                                    if !reason.is_empty() {
+                                                        exchange.set_annotation(Some(reason));
+                                                    }
+                                                    match vote {
+                                                        Some(vote) => format!("Voted {} on message {}", vote.as_str(), index + 1),
+                                                        None => format!("Cleared the vote on message {}", index + 1),
+                                                    }
+                                                }
+                                                None => format!("No message {}", index + 1),
+                                            };
+                                            tab_ui.command_line.text_set(&message, None);
+                                        }
+                                        PromptAction::Stats => {
+                                            let token_count = chat.capabilities().check(Capability::TokenCount);
+                                            let mut stats = conversation_stats(chat.conversation(), token_count.is_ok());
+                                            if let Err(reason) = token_count {
+                                                stats.push_str(&format!("\n\n{}", reason));
+                                            }
+                                            tab_ui.set_text_modal("Conversation statistics", &stats);
+                                        }
+                                        PromptAction::Trash(command) => {
+                                            let message = run_trash_command(chat, tab_ui, command, &color_scheme);
+                                            if let Some(message) = message {
+                                                tab_ui.command_line.text_set(&message, None);
+                                            }
+                                        }
+                                        PromptAction::Capabilities => {
+                                            let capabilities = chat.capabilities().describe();
+                                            tab_ui.set_text_modal("Server capabilities", &capabilities);
+                                        }
                                         PromptAction::Stop => {
-                                            chat.stop();
-                                            finalize_response(&mut chat, &mut tab_ui, None, &color_scheme).await?;
-                                            trim_buffer = None;
+                                            if let Err(reason) = chat.capabilities().check(Capability::Streaming) {
+                                                // nothing to stop, explain instead of failing silently
+                                                tab_ui.command_line.text_set(&reason, None);
+                                            } else {
+                                                chat.stop();
+                                                if let Some(text) = render_pacer.flush() {
+                                                    tab_ui.response.text_append_with_insert(&text, Some(color_scheme.get_secondary_style()));
+                                                }
+                                                pending_finalize = None;
+                                                finalize_response(chat, tab_ui, None, &mut query_assistant, &color_scheme).await?;
+                                                trim_buffer = None;
+                                            }
                                         }
                                     }
-                                    current_mode = Some(WindowEvent::PromptWindow);
+                                    current_mode = if let Some(modal) = &tab_ui.modal {
+                                        Some(WindowEvent::Modal(modal.get_type()))
+                                    } else if from_response {
+                                        Some(WindowEvent::ResponseWindow)
+                                    } else {
+                                        Some(WindowEvent::PromptWindow)
+                                    };
                                 }
                                 Some(WindowEvent::CommandLine(ref action)) => {
                                     // enter command line mode
@@ -185,6 +358,20 @@ async fn prompt_app<B: Backend>(
                                 MouseEventKind::ScrollDown => {
                                     window.scroll_down();
                                 },
+                                MouseEventKind::Down(MouseButton::Right)
+                                    if matches!(current_mode, Some(WindowEvent::PromptWindow) | Some(WindowEvent::ResponseWindow)) =>
+                                {
+                                    // actions for the clicked message
+                                    let Some(line) = window.line_at_screen_position(mouse_event.column, mouse_event.row) else {
+                                        continue;
+                                    };
+                                    window.set_cursor_line(line);
+                                    if tab_ui.open_message_menu() {
+                                        tab_ui.prompt.set_status_inactive();
+                                        tab_ui.response.set_status_normal();
+                                        current_mode = Some(WindowEvent::ResponseWindow);
+                                    }
+                                },
                                 MouseEventKind::Down(_) => {
                                     // mouse click is ignored currently
                                     // TODO: implement mouse click for certain actions
@@ -197,6 +384,28 @@ async fn prompt_app<B: Backend>(
                                 }
                             }
                         },
+                        Event::Paste(text) => {
+                            // pasted or committed text is inserted as is,
+                            // a newline in it does not send the prompt
+                            let text = text.replace("\r\n", "\n").replace('\r', "\n");
+                            match current_mode {
+                                Some(WindowEvent::PromptWindow) => {
+                                    // the text under the popup changes
+                                    tab_ui.spell_suggestions = None;
+                                    if !tab_ui.prompt.is_status_insert() {
+                                        tab_ui.prompt.set_insert_mode();
+                                    }
+                                    tab_ui.prompt.text_insert_add(&text, None);
+                                }
+                                Some(WindowEvent::CommandLine(_)) => {
+                                    tab_ui.command_line.text_insert_add(&text, None);
+                                }
+                                _ => {
+                                    // read-only windows and modals take no text
+                                    continue;   // skip redraw_ui
+                                }
+                            }
+                        },
                         _ => {} // Other events are ignored
                     }
                     redraw_ui = true;   // redraw the UI after each type of event
@@ -204,8 +413,8 @@ async fn prompt_app<B: Backend>(
             },
             Some(response_bytes) = rx.recv() => {
                 log::debug!("Received response with length {:?}", response_bytes.len());
-                let mut tab_ui = &mut tab.ui;
-                let mut chat = &mut tab.chat;
+                let tab_ui = &mut tab.ui;
+                let chat = &mut tab.chat;
 
                 if trim_buffer.is_none() {
                     // new response stream started
@@ -228,7 +437,10 @@ async fn prompt_app<B: Backend>(
 
                 if !display_content.is_empty() {
                     chat.update_last_exchange(&display_content);
-                    tab_ui.response.text_append_with_insert(&display_content, Some(color_scheme.get_secondary_style()));
+                    render_pacer.push(&display_content);
+                    if let Some(text) = render_pacer.next_frame() {
+                        tab_ui.response.text_append_with_insert(&text, Some(color_scheme.get_secondary_style()));
+                    }
                 }
 
                 if is_final {
@@ -239,7 +451,12 @@ async fn prompt_app<B: Backend>(
                     while let Ok(post_bytes) = rx.try_recv() {
                         chat.process_response(post_bytes);
                     }
-                    finalize_response(&mut chat, &mut tab_ui, tokens_predicted, &color_scheme).await?;
+                    if render_pacer.is_empty() {
+                        finalize_response(chat, tab_ui, tokens_predicted, &mut query_assistant, &color_scheme).await?;
+                    } else {
+                        // finalized when the paced text is rendered
+                        pending_finalize = Some(tokens_predicted);
+                    }
                     trim_buffer = None;
                } else {
                     // Capture trailing whitespaces or newlines to the trim_buffer
@@ -258,10 +475,249 @@ async fn prompt_app<B: Backend>(
     Ok(())
 }
 
+// shows the prompt and sends it to the server
+async fn send_prompt(
+    chat: &mut ChatSession,
+    tab_ui: &mut TabUi<'_>,
+    tx: &mpsc::Sender<Bytes>,
+    prompt: &str,
+    color_scheme: &ColorScheme,
+) {
+    // prompt should end with single newline
+    let formatted_prompt = format!("{}\n", prompt.trim_end());
+    // the quote may have been removed from the prompt before it was sent
+    let reply_to = tab_ui.reply_to.take().filter(|_| is_reply(prompt));
+    tab_ui.append_question(&formatted_prompt, color_scheme.get_primary_style());
+
+    let sent = chat
+        .message(tx.clone(), formatted_prompt)
+        .instrument(info_span!("prompt_send"))
+        .await;
+    if let Err(e) = sent {
+        // keep the session, the user can :reconnect
+        tab_ui.messages.pop();
+        tab_ui.command_line.text_set(&e.to_string(), None);
+        return;
+    }
+    let last = chat.conversation().len().saturating_sub(1);
+    if let Some(exchange) = chat.exchange_mut(last) {
+        exchange.set_reply_to(reply_to);
+    }
+    if tab_ui.messages.len() != chat.conversation().len() {
+        // an unanswered exchange was dropped from the history
+        render_conversation(chat, tab_ui, color_scheme);
+    }
+}
+
+// the listing goes into the conversation as context, without asking the
+// model. errors are shown on the command line
+async fn add_listing(
+    chat: &mut ChatSession,
+    tab_ui: &mut TabUi<'_>,
+    listing: Result<ObjectListing, ApplicationError>,
+    color_scheme: &ColorScheme,
+) {
+    let result = match listing {
+        Ok(listing) => match listing.to_markdown().await {
+            Ok(markdown) => {
+                let answer = format!("Listing of {} received.", listing.uri());
+                chat.add_context(markdown, answer).await
+            }
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(()) => render_conversation(chat, tab_ui, color_scheme),
+        Err(e) => tab_ui.command_line.text_set(&e.to_string(), None),
+    }
+}
+
+// /query asks the model for a query, /sql runs a query and asks the model
+// to summarize the results. errors are shown on the command line
+async fn run_query_command(
+    chat: &mut ChatSession,
+    tab_ui: &mut TabUi<'_>,
+    tx: &mpsc::Sender<Bytes>,
+    command: Result<QueryCommand, ApplicationError>,
+    query_assistant: &mut QueryAssistant,
+    color_scheme: &ColorScheme,
+) {
+    let prompt = match command {
+        Ok(QueryCommand::Translate(request)) => {
+            Ok(query_assistant.translate(&request))
+        }
+        Ok(QueryCommand::Execute(query)) => {
+            query_assistant.execute(&query).await
+        }
+        Err(e) => Err(e),
+    };
+    match prompt {
+        Ok(prompt) => {
+            send_prompt(chat, tab_ui, tx, &prompt, color_scheme).await
+        }
+        Err(e) => tab_ui.command_line.text_set(&e.to_string(), None),
+    }
+}
+
+// shows the conversation again after it changed, e.g. a deleted message
+// returns a message for the command line
+fn run_trash_command(
+    chat: &mut ChatSession,
+    tab_ui: &mut TabUi<'_>,
+    command: TrashCommand,
+    color_scheme: &ColorScheme,
+) -> Option<String> {
+    let trash = chat.trash_mut();
+    trash.purge_expired();
+    match command {
+        TrashCommand::List => {
+            let title = format!(
+                "Trash, kept for {} days, y to copy",
+                trash.retention_seconds() / (24 * 60 * 60)
+            );
+            let text = trash.render();
+            tab_ui.set_text_modal(&title, &text);
+            None
+        }
+        TrashCommand::Restore(number) => match chat.restore_exchange(number) {
+            Some(index) => {
+                render_conversation(chat, tab_ui, color_scheme);
+                Some(format!("Restored message {}", index + 1))
+            }
+            None => Some(format!("No message {} in the trash", number)),
+        },
+        TrashCommand::Purge(number) => {
+            let count = trash.purge(number);
+            Some(format!("Purged {} message(s) from the trash", count))
+        }
+        TrashCommand::Retention(seconds) => {
+            trash.set_retention_seconds(seconds);
+            Some("Changed how long deleted messages are kept".to_string())
+        }
+    }
+}
+
+fn render_conversation(
+    chat: &ChatSession,
+    tab_ui: &mut TabUi<'_>,
+    color_scheme: &ColorScheme,
+) {
+    tab_ui.response.text_empty();
+    tab_ui.messages.clear();
+    tab_ui.reply_to = None; // exchanges may be renumbered
+    for exchange in chat.conversation() {
+        tab_ui.append_question(
+            exchange.get_question(),
+            color_scheme.get_primary_style(),
+        );
+        if exchange.get_answer().is_empty() {
+            continue; // the answer is streaming in
+        }
+        tab_ui.response.text_append_with_insert(
+            &format!("{}\n", exchange.get_answer()),
+            Some(color_scheme.get_secondary_style()),
+        );
+        tab_ui
+            .response
+            .text_append_with_insert("\n", Some(Style::reset()));
+    }
+}
+
+// actions on a message that change the conversation, returns the text
+// to show on the command line
+async fn run_message_action(
+    action: MessageAction,
+    index: usize,
+    chat: &mut ChatSession,
+    tab_ui: &mut TabUi<'_>,
+    session_pool: &mut SessionPool,
+    tx: &mpsc::Sender<Bytes>,
+    color_scheme: &ColorScheme,
+) -> Result<Option<String>, ApplicationError> {
+    let Some(exchange) = chat.conversation().get(index).cloned() else {
+        return Ok(Some(format!("No message {}", index + 1)));
+    };
+    let number = index + 1;
+    match action {
+        MessageAction::Pin => {
+            let pinned = !exchange.is_pinned();
+            if let Some(exchange) = chat.exchange_mut(index) {
+                exchange.set_pinned(pinned);
+            }
+            Ok(Some(if pinned {
+                format!("Pinned message {}, it stays in the context", number)
+            } else {
+                format!("Unpinned message {}", number)
+            }))
+        }
+        MessageAction::ViewRaw => {
+            let json = serde_json::to_string_pretty(&exchange).map_err(|e| {
+                ApplicationError::Unexpected(e.to_string())
+            })?;
+            tab_ui.set_text_modal(&format!("Message {}", number), &json);
+            Ok(None)
+        }
+        MessageAction::Inspect => match exchange.get_raw() {
+            Some(raw) => {
+                tab_ui.set_text_modal(
+                    &format!("Request of message {}, y to copy", number),
+                    &raw.render(),
+                );
+                Ok(None)
+            }
+            None => Ok(Some(format!(
+                "No request was sent for message {} in this session",
+                number
+            ))),
+        },
+        MessageAction::Delete => {
+            chat.trash_exchange(index);
+            render_conversation(chat, tab_ui, color_scheme);
+            Ok(Some(format!(
+                "Moved message {} to the trash, :trash to restore it",
+                number
+            )))
+        }
+        MessageAction::Fork => {
+            // continue in a new session from the pool, with the
+            // conversation up to and including this message
+            let mut session = session_pool.take().await?;
+            session.load_conversation(chat.conversation()[..=index].to_vec());
+            chat.stop();
+            *chat = session;
+            render_conversation(chat, tab_ui, color_scheme);
+            Ok(Some(format!("Forked the conversation at message {}", number)))
+        }
+        MessageAction::Rerun => {
+            // the question as typed, before the prompt template applied
+            let question = tab_ui
+                .message_text(index, PromptRole::User)
+                .unwrap_or_else(|| exchange.get_question().to_string());
+            let reply_to = exchange.get_reply_to();
+            if number == chat.conversation().len() {
+                // the new answer replaces the last one
+                chat.delete_exchange(index);
+                render_conversation(chat, tab_ui, color_scheme);
+            }
+            tab_ui.reply_to = reply_to;
+            send_prompt(chat, tab_ui, tx, &question, color_scheme).await;
+            Ok(None)
+        }
+        // these only need the text on screen, see the response window
+        MessageAction::Copy
+        | MessageAction::QuoteReply
+        | MessageAction::Annotate
+        | MessageAction::Upvote
+        | MessageAction::Downvote => Ok(None),
+    }
+}
+
 async fn finalize_response(
     chat: &mut ChatSession,
     tab_ui: &mut TabUi<'_>,
     tokens_predicted: Option<usize>,
+    query_assistant: &mut QueryAssistant,
     color_scheme: &ColorScheme,
 ) -> Result<(), ApplicationError> {
     // stop trying to get more responses
@@ -277,6 +733,24 @@ async fn finalize_response(
         .text_append_with_insert("\n", Some(Style::reset()));
     // trim exchange + update token length
     chat.finalize_last_exchange(tokens_predicted).await?;
+    // a query from the model is not run before it is confirmed
+    let answer = chat
+        .conversation()
+        .last()
+        .map_or("", |exchange| exchange.get_answer());
+    match query_assistant.take_query(answer) {
+        Some(Ok(query)) => {
+            tab_ui.prompt.text_empty();
+            tab_ui
+                .prompt
+                .text_insert_add(&format!("{} {}", SQL_COMMAND, query), None);
+            tab_ui
+                .command_line
+                .text_set("Send the query to run it, or edit it first", None);
+        }
+        Some(Err(e)) => tab_ui.command_line.text_set(&e.to_string(), None),
+        None => {}
+    }
     Ok(())
 }
 
@@ -304,12 +778,658 @@ fn parse_cli_arguments(spec: ApplicationSpec) -> Command {
             Arg::new("server")
                 .long("server")
                 .short('S')
+                .global(true)
                 .help("Server to use for processing the request"),
         )
         .arg(Arg::new("options").long("options").short('o').help(
             "Comma-separated list of model options e.g., \
              temperature=1,max_tokens=100",
         ))
+        .arg(Arg::new("language").long("language").short('l').help(
+            "Response language: 'auto' to match the language of the prompt, \
+             a language code (e.g. 'en', 'nl') to always use that language, \
+             or 'off' (default)",
+        ))
+        .arg(Arg::new("profile").long("profile").help(
+            "Profile in profiles.yaml with the server, model, system prompt, \
+             options and webhooks to use",
+        ))
+        .arg(
+            Arg::new("email")
+                .long("email")
+                .help(
+                    "Email the conversation to this address when done \
+                     (non-interactive mode), requires email.yaml",
+                ),
+        )
+        .arg(
+            Arg::new("vault")
+                .long("vault")
+                .action(ArgAction::SetTrue)
+                .help(
+                    "Export the conversation to the vault when done \
+                     (non-interactive mode), requires vault.yaml",
+                ),
+        )
+        .arg(
+            Arg::new("pool-size")
+                .long("pool-size")
+                .value_parser(clap::value_parser!(usize))
+                .default_value("0")
+                .help(
+                    "Number of sessions to keep initialized in the \
+                     background, so :new starts without waiting for the \
+                     server (interactive mode)",
+                ),
+        )
+        .arg(
+            Arg::new("pool-ttl")
+                .long("pool-ttl")
+                .value_parser(clap::value_parser!(u64))
+                .help(
+                    "Seconds a pooled session may stay idle before it is \
+                     replaced",
+                ),
+        )
+        .arg(
+            Arg::new("render-rate")
+                .long("render-rate")
+                .value_parser(clap::value_parser!(usize))
+                .help(
+                    "Maximum number of characters of a response rendered \
+                     per frame (about 60 frames per second), for a paced \
+                     typewriter effect. Default: render as received",
+                ),
+        )
+        .subcommand(
+            Command::new("models")
+                .about("List available models of the selected server")
+                .arg(
+                    Arg::new("refresh")
+                        .long("refresh")
+                        .action(ArgAction::SetTrue)
+                        .help("Refresh the cached model list"),
+                ),
+        )
+        .subcommand(
+            Command::new("export")
+                .about(
+                    "Export conversations from the vault as a dataset, \
+                     requires vault.yaml",
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(["openai-finetune"])
+                        .default_value("openai-finetune")
+                        .help("Chat-format JSONL for fine-tuning"),
+                )
+                .arg(
+                    Arg::new("tag")
+                        .long("tag")
+                        .action(ArgAction::Append)
+                        .help(
+                            "Only export conversations with this tag, can be \
+                             given multiple times",
+                        ),
+                )
+                .arg(
+                    Arg::new("vote")
+                        .long("vote")
+                        .value_parser(["up", "down"])
+                        .help("Only export answers with this vote"),
+                )
+                .arg(
+                    Arg::new("system")
+                        .long("system")
+                        .help("System message to start each conversation with"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .help("File to write to, default: stdout"),
+                )
+                .arg(
+                    Arg::new("validation")
+                        .long("validation")
+                        .requires("validation-split")
+                        .help("File to write the validation set to"),
+                )
+                .arg(
+                    Arg::new("validation-split")
+                        .long("validation-split")
+                        .value_parser(clap::value_parser!(f64))
+                        .requires("validation")
+                        .help(
+                            "Fraction of the conversations for the \
+                             validation set, e.g. 0.1",
+                        ),
+                )
+                .arg(
+                    Arg::new("redact")
+                        .long("redact")
+                        .action(ArgAction::SetTrue)
+                        .help(
+                            "Replace email addresses, phone numbers and IP \
+                             addresses with [REDACTED]",
+                        ),
+                )
+                .arg(
+                    Arg::new("redact-command")
+                        .long("redact-command")
+                        .help(
+                            "Command that gets each message on stdin and \
+                             prints the redacted message",
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("profile")
+                .about("Manage the profiles in profiles.yaml")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("copy")
+                        .about(
+                            "Create a profile from another one, e.g. to use \
+                             another model or temperature",
+                        )
+                        .arg(Arg::new("source").index(1).required(true))
+                        .arg(Arg::new("target").index(2).required(true))
+                        .arg(
+                            Arg::new("include-secrets")
+                                .long("include-secrets")
+                                .action(ArgAction::SetTrue)
+                                .help(
+                                    "Also copy environment variables with \
+                                     keys, secrets, tokens or passwords",
+                                ),
+                        )
+                        .arg(
+                            Arg::new("set")
+                                .long("set")
+                                .action(ArgAction::Append)
+                                .help(
+                                    "Change a setting of the copy, e.g. \
+                                     model=llama3 or options.temperature=0.9",
+                                ),
+                        ),
+                )
+                .subcommand(Command::new("list").about("List the profiles"))
+                .subcommand(
+                    Command::new("show")
+                        .about("Show a profile, with secrets masked")
+                        .arg(Arg::new("name").index(1).required(true))
+                        .arg(
+                            Arg::new("show-secrets")
+                                .long("show-secrets")
+                                .action(ArgAction::SetTrue)
+                                .help("Show secrets as they are"),
+                        )
+                        .arg(
+                            Arg::new("json")
+                                .long("json")
+                                .action(ArgAction::SetTrue)
+                                .help("Print JSON instead of YAML"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("create")
+                        .about(
+                            "Create a profile with the settings its server \
+                             describes, see \"provider show\"",
+                        )
+                        .arg(Arg::new("name").index(1).required(true))
+                        .arg(
+                            Arg::new("server")
+                                .long("server")
+                                .short('s')
+                                .value_parser(SUPPORTED_MODEL_ENDPOINTS)
+                                .conflicts_with("from-json"),
+                        )
+                        .arg(Arg::new("from-json").long("from-json").help(
+                            "Read the profile from a JSON file, or from \
+                             stdin with -",
+                        ))
+                        .arg(
+                            Arg::new("set")
+                                .long("set")
+                                .action(ArgAction::Append)
+                                .help(
+                                    "A setting, e.g. model=llama3. A secret \
+                                     without value, e.g. \
+                                     env.OPENAI_API_KEY, is asked for",
+                                ),
+                        ),
+                )
+                .subcommand(
+                    Command::new("set")
+                        .about("Change settings of a profile")
+                        .arg(Arg::new("name").index(1).required(true))
+                        .arg(
+                            Arg::new("settings")
+                                .index(2)
+                                .num_args(1..)
+                                .required(true)
+                                .help(
+                                    "KEY=VALUE, e.g. options.temperature=0.9. \
+                                     A secret without value, e.g. \
+                                     env.OPENAI_API_KEY, is asked for",
+                                ),
+                        ),
+                )
+                .subcommand(
+                    Command::new("delete")
+                        .about("Delete a profile")
+                        .arg(Arg::new("name").index(1).required(true)),
+                ),
+        )
+        .subcommand(
+            Command::new("provider")
+                .about("Show the servers a profile can use")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("list").about("List the supported servers"),
+                )
+                .subcommand(
+                    Command::new("show")
+                        .about("Show the JSON schema of the profile settings")
+                        .arg(
+                            Arg::new("name")
+                                .index(1)
+                                .required(true)
+                                .value_parser(SUPPORTED_MODEL_ENDPOINTS),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("eval")
+                .about(
+                    "Run the prompts of a YAML suite and check their \
+                     answers against its assertions",
+                )
+                .arg(
+                    Arg::new("suite")
+                        .index(1)
+                        .required(true)
+                        .help("YAML file with the cases to run"),
+                )
+                .arg(
+                    Arg::new("model")
+                        .long("model")
+                        .short('m')
+                        .help("Model to evaluate, default: the first listed"),
+                )
+                .arg(
+                    Arg::new("grader-model")
+                        .long("grader-model")
+                        .help(
+                            "Model that grades rubric assertions, default: \
+                             the evaluated model",
+                        ),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(["text", "csv", "jsonl"])
+                        .default_value("text")
+                        .help("Output format of the results"),
+                ),
+        )
+}
+
+async fn run_eval(
+    matches: &clap::ArgMatches,
+    server_name: &str,
+    options: Option<&String>,
+    default_model: Option<LLMDefinition>,
+) -> Result<(), ApplicationError> {
+    let suite =
+        EvalSuite::from_file(matches.get_one::<String>("suite").unwrap())?;
+    let model = match matches.get_one::<String>("model") {
+        Some(name) => LLMDefinition::new(name.clone()),
+        None => default_model.ok_or_else(|| {
+            ApplicationError::NotReady(
+                "No model available, select one with --model".to_string(),
+            )
+        })?,
+    };
+    let grader_model = matches
+        .get_one::<String>("grader-model")
+        .map_or_else(|| model.clone(), |name| LLMDefinition::new(name.clone()));
+    let sessions = SessionFactory::new(
+        server_name.to_string(),
+        suite.system().map(str::to_string),
+        None,
+        options.cloned(),
+        Some(model.clone()),
+    );
+    let grader = SessionFactory::new(
+        server_name.to_string(),
+        Some(GRADER_INSTRUCTION.to_string()),
+        None,
+        None,
+        Some(grader_model),
+    );
+    let run = EvalRunner::new(sessions, model.get_name())
+        .set_grader(grader)
+        .run(&suite)
+        .await;
+
+    let export_options = TableExportOptions::new();
+    match matches.get_one::<String>("format").map(String::as_str) {
+        Some("csv") => print!(
+            "{}",
+            run.to_table()
+                .to_csv(&export_options)
+                .map_err(ApplicationError::Unexpected)?
+        ),
+        Some("jsonl") => print!(
+            "{}",
+            run.to_table()
+                .to_jsonl(&export_options)
+                .map_err(ApplicationError::Unexpected)?
+        ),
+        _ => run.print(),
+    }
+    eprintln!("{} of {} cases passed", run.passed(), run.len());
+    if let Some(previous) =
+        EvalHistory::previous(suite.name(), model.get_name())
+    {
+        eprintln!(
+            "Previous run: {} of {} cases passed",
+            previous.passed(),
+            previous.len()
+        );
+        for change in run.compare(&previous) {
+            eprintln!("  {}", change);
+        }
+    }
+    EvalHistory::append(&run)?;
+    if run.passed() < run.len() {
+        return Err(ApplicationError::Runtime(format!(
+            "{} of {} cases failed",
+            run.len() - run.passed(),
+            run.len()
+        )));
+    }
+    Ok(())
+}
+
+fn export_dataset(matches: &clap::ArgMatches) -> Result<(), ApplicationError> {
+    let vault = VaultExporter::from_config_file()?.ok_or_else(|| {
+        ApplicationError::InvalidUserConfiguration(
+            "export requires vault.yaml in the prompt config directory"
+                .to_string(),
+        )
+    })?;
+    let mut exporter = FinetuneExporter::new()
+        .set_system(matches.get_one::<String>("system").cloned())
+        .set_tags(
+            matches
+                .get_many::<String>("tag")
+                .map(|tags| tags.cloned().collect())
+                .unwrap_or_default(),
+        )
+        .set_vote(
+            matches
+                .get_one::<String>("vote")
+                .and_then(|vote| Vote::from_str(vote)),
+        )
+        .set_validation_split(
+            matches
+                .get_one::<f64>("validation-split")
+                .copied()
+                .unwrap_or(0.0),
+        );
+    if matches.get_flag("redact") {
+        exporter = exporter.add_redactor(Box::new(PatternRedactor::pii()));
+    }
+    if let Some(command) = matches.get_one::<String>("redact-command") {
+        exporter = exporter
+            .add_redactor(Box::new(CommandRedactor::new(command.clone())));
+    }
+    let budget = MemoryBudget::from_env().map_err(|e| {
+        ApplicationError::InvalidUserConfiguration(e.to_string())
+    })?;
+    let conversations = vault.read_conversations(budget, |conversation| {
+        exporter.selects(conversation)
+    })?;
+    let export = exporter.export(&conversations)?;
+
+    let write = |path: Option<&String>, records: &[String]| {
+        let mut jsonl = records.join("\n");
+        if !jsonl.is_empty() {
+            jsonl.push('\n');
+        }
+        match path {
+            Some(path) => {
+                std::fs::write(path, jsonl).map_err(ApplicationError::IoError)
+            }
+            None => {
+                print!("{}", jsonl);
+                Ok(())
+            }
+        }
+    };
+    write(matches.get_one::<String>("output"), &export.train)?;
+    if let Some(path) = matches.get_one::<String>("validation") {
+        write(Some(path), &export.validation)?;
+    }
+    eprintln!(
+        "Exported {} conversations for training, {} for validation",
+        export.train.len(),
+        export.validation.len()
+    );
+    Ok(())
+}
+
+fn manage_profiles(matches: &clap::ArgMatches) -> Result<(), ApplicationError> {
+    let mut store = ProfileStore::from_config_file()?;
+    let settings = |matches: &clap::ArgMatches, id: &str| -> Vec<String> {
+        matches
+            .get_many::<String>(id)
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect()
+    };
+    match matches.subcommand() {
+        Some(("copy", matches)) => {
+            let source = matches.get_one::<String>("source").unwrap();
+            let target = matches.get_one::<String>("target").unwrap();
+            let include_secrets = matches.get_flag("include-secrets");
+            let profile = store.duplicate(source, target, include_secrets)?;
+            apply_settings(profile, &settings(matches, "set"))?;
+            store.save()?;
+            eprintln!("Created profile {} from {}", target, source);
+        }
+        Some(("list", _)) => {
+            for (name, profile) in store.profiles() {
+                println!(
+                    "{}\t{}\t{}",
+                    name,
+                    profile.server.as_deref().unwrap_or("-"),
+                    profile.model.as_deref().unwrap_or("-")
+                );
+            }
+        }
+        Some(("show", matches)) => {
+            let name = matches.get_one::<String>("name").unwrap();
+            let profile = match matches.get_flag("show-secrets") {
+                true => store.get(name)?.clone(),
+                false => store.get(name)?.masked(),
+            };
+            let text = match matches.get_flag("json") {
+                true => serde_json::to_string_pretty(&profile)
+                    .map_err(|e| ApplicationError::Unexpected(e.to_string()))?,
+                false => serde_yaml::to_string(&profile)
+                    .map_err(|e| ApplicationError::Unexpected(e.to_string()))?,
+            };
+            println!("{}", text.trim_end());
+        }
+        Some(("create", matches)) => {
+            let name = matches.get_one::<String>("name").unwrap();
+            if store.get(name).is_ok() {
+                return Err(ApplicationError::InvalidUserConfiguration(
+                    format!("Profile {} already exists", name),
+                ));
+            }
+            let mut profile = match matches.get_one::<String>("from-json") {
+                Some(path) => read_profile_json(path)?,
+                None => PromptProfile::for_server(
+                    matches.get_one::<String>("server").map(String::as_str),
+                )?,
+            };
+            apply_settings(&mut profile, &settings(matches, "set"))?;
+            store.insert(name, profile);
+            store.save()?;
+            eprintln!("Created profile {}", name);
+        }
+        Some(("set", matches)) => {
+            let name = matches.get_one::<String>("name").unwrap();
+            apply_settings(
+                store.get_mut(name)?,
+                &settings(matches, "settings"),
+            )?;
+            store.save()?;
+            eprintln!("Updated profile {}", name);
+        }
+        Some(("delete", matches)) => {
+            let name = matches.get_one::<String>("name").unwrap();
+            store.remove(name)?;
+            store.save()?;
+            eprintln!("Deleted profile {}", name);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+// KEY=VALUE settings. a secret without a value is asked for, so it does
+// not end up in the shell history
+fn apply_settings(
+    profile: &mut PromptProfile,
+    settings: &[String],
+) -> Result<(), ApplicationError> {
+    for setting in settings {
+        let (key, value) = match setting.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim().to_string()),
+            None => match setting.trim().strip_prefix("env.") {
+                Some(name) if PromptProfile::is_secret_name(name) => {
+                    (setting.trim(), read_secret(name)?)
+                }
+                _ => {
+                    return Err(ApplicationError::InvalidUserConfiguration(
+                        format!(
+                            "Invalid setting {}, expected KEY=VALUE",
+                            setting
+                        ),
+                    ))
+                }
+            },
+        };
+        profile.set(key, &value)?;
+    }
+    Ok(())
+}
+
+// from a file, or from stdin with "-"
+fn read_profile_json(path: &str) -> Result<PromptProfile, ApplicationError> {
+    let json = match path {
+        "-" => io::read_to_string(io::stdin()),
+        path => std::fs::read_to_string(path),
+    }
+    .map_err(ApplicationError::IoError)?;
+    serde_json::from_str(&json).map_err(|e| {
+        ApplicationError::InvalidUserConfiguration(format!(
+            "Invalid profile JSON: {}",
+            e
+        ))
+    })
+}
+
+// read from the terminal without echo, or as a line from stdin if that
+// is not a terminal
+fn read_secret(name: &str) -> Result<String, ApplicationError> {
+    if !io::stdin().is_terminal() {
+        let mut line = String::new();
+        io::stdin()
+            .read_line(&mut line)
+            .map_err(ApplicationError::IoError)?;
+        return Ok(line.trim_end_matches(['\r', '\n']).to_string());
+    }
+    eprint!("{}: ", name);
+    enable_raw_mode().map_err(ApplicationError::IoError)?;
+    let mut secret = String::new();
+    let result = loop {
+        match crossterm::event::read() {
+            Ok(Event::Key(key)) => match key.code {
+                KeyCode::Enter => break Ok(()),
+                KeyCode::Backspace => {
+                    secret.pop();
+                }
+                KeyCode::Char('c')
+                    if key.modifiers.contains(KeyModifiers::CONTROL) =>
+                {
+                    break Err(ApplicationError::Unexpected(
+                        "Interrupted".to_string(),
+                    ))
+                }
+                KeyCode::Char(character) => secret.push(character),
+                _ => {}
+            },
+            Ok(_) => {}
+            Err(e) => break Err(ApplicationError::IoError(e)),
+        }
+    };
+    disable_raw_mode().map_err(ApplicationError::IoError)?;
+    eprintln!();
+    result.map(|_| secret)
+}
+
+fn manage_providers(
+    matches: &clap::ArgMatches,
+) -> Result<(), ApplicationError> {
+    match matches.subcommand() {
+        Some(("list", _)) => {
+            for server in SUPPORTED_MODEL_ENDPOINTS {
+                println!("{}", server);
+            }
+        }
+        Some(("show", matches)) => {
+            let name = matches.get_one::<String>("name").unwrap();
+            let schema = ModelServer::settings_schema(name)?;
+            let text = serde_json::to_string_pretty(&schema)
+                .map_err(|e| ApplicationError::Unexpected(e.to_string()))?;
+            println!("{}", text);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn print_models(models: &[LLMDefinition]) -> Result<(), ApplicationError> {
+    let catalog = ModelCatalog::bundled()?;
+    for model in models {
+        let name = model.get_name();
+        match catalog.lookup(name) {
+            Some(info) => {
+                let context = info
+                    .get_context_size()
+                    .map_or("-".to_string(), |size| size.to_string());
+                let price =
+                    match (info.get_input_price(), info.get_output_price()) {
+                        (Some(input), Some(output)) => {
+                            format!("${}/${} per 1M tokens", input, output)
+                        }
+                        _ => "-".to_string(),
+                    };
+                println!("{}\tcontext={}\t{}", name, context, price);
+            }
+            None => println!("{}", name),
+        }
+    }
+    Ok(())
 }
 
 pub async fn run_cli(
@@ -321,60 +1441,167 @@ pub async fn run_cli(
         e.exit();
     });
 
+    if let Some(profile_matches) = matches.subcommand_matches("profile") {
+        return manage_profiles(profile_matches);
+    }
+    if let Some(provider_matches) = matches.subcommand_matches("provider") {
+        return manage_providers(provider_matches);
+    }
+
+    // a profile gives the defaults of arguments not on the command line
+    let profile = match matches.get_one::<String>("profile") {
+        Some(name) => ProfileStore::from_config_file()?.get(name)?.clone(),
+        None => PromptProfile::default(),
+    };
+    profile.apply_env();
+
     // optional arguments
-    let instruction = matches.get_one::<String>("system").cloned();
-    let assistant = matches.get_one::<String>("assistant").cloned();
-    let options = matches.get_one::<String>("options");
+    let instruction = matches
+        .get_one::<String>("system")
+        .cloned()
+        .or(profile.system.clone());
+    let assistant = matches
+        .get_one::<String>("assistant")
+        .cloned()
+        .or(profile.assistant.clone());
+    let options = matches
+        .get_one::<String>("options")
+        .cloned()
+        .or(profile.options_json());
+    let language_preference = match matches.get_one::<String>("language") {
+        Some(language) => {
+            LanguagePreference::from_str(language).ok_or_else(|| {
+                ApplicationError::InvalidUserConfiguration(format!(
+                    "Unsupported language: {}",
+                    language
+                ))
+            })?
+        }
+        None => LanguagePreference::Off,
+    };
 
     let server_name = matches
         .get_one::<String>("server")
         .cloned()
+        .or(profile.server.clone())
         .unwrap_or_else(|| "ollama".to_string());
 
+    if let Some(export_matches) = matches.subcommand_matches("export") {
+        return export_dataset(export_matches);
+    }
+
     // create new (un-initialized) server from requested server name
     let server = ModelServer::from_str(&server_name)?;
 
-    // get default model from server - if available
-    let default_model = match server.list_models().await {
-        Ok(models) => {
-            if models.is_empty() {
-                log::warn!("Received empty model list");
+    if let Some(models_matches) = matches.subcommand_matches("models") {
+        let refresh = models_matches.get_flag("refresh");
+        let models = list_models_cached(&server, &server_name, refresh).await?;
+        return print_models(&models);
+    }
+
+    // get default model from the profile or server - if available
+    let default_model = match &profile.model {
+        Some(model) => Some(LLMDefinition::new(model.clone())),
+        None => match list_models_cached(&server, &server_name, false).await {
+            Ok(models) => {
+                if models.is_empty() {
+                    log::warn!("Received empty model list");
+                    None
+                } else {
+                    log::debug!("Available models: {:?}", models);
+                    Some(models[0].to_owned())
+                }
+            }
+            Err(e) => {
+                log::error!("Failed to list models: {}", e);
                 None
-            } else {
-                log::debug!("Available models: {:?}", models);
-                Some(models[0].to_owned())
             }
-        }
-        Err(e) => {
-            log::error!("Failed to list models: {}", e);
-            None
-        }
+        },
     };
 
+    if let Some(eval_matches) = matches.subcommand_matches("eval") {
+        return run_eval(
+            eval_matches,
+            &server_name,
+            options.as_ref(),
+            default_model,
+        )
+        .await;
+    }
+
+    let email_exporter = EmailExporter::from_config_file()?;
+    let email_to = matches.get_one::<String>("email").cloned();
+    if email_to.is_some() && email_exporter.is_none() {
+        // fail before the prompt is processed, not after
+        return Err(ApplicationError::InvalidUserConfiguration(
+            "--email requires email.yaml in the prompt config directory"
+                .to_string(),
+        ));
+    }
+
+    let vault_exporter = VaultExporter::from_config_file()?;
+    let export_to_vault = matches.get_flag("vault");
+    if export_to_vault && vault_exporter.is_none() {
+        return Err(ApplicationError::InvalidUserConfiguration(
+            "--vault requires vault.yaml in the prompt config directory"
+                .to_string(),
+        ));
+    }
+
     // setup prompt, server and chat session
-    let prompt_instruction =
-        PromptInstruction::new(instruction, assistant, options)?;
-    let chat_session =
-        ChatSession::new(Box::new(server), prompt_instruction, default_model)
-            .await?;
+    let session_factory = SessionFactory::new(
+        server_name,
+        instruction,
+        assistant,
+        options,
+        default_model,
+    )
+    .set_language_preference(language_preference)
+    .set_webhooks(WebhookDispatcher::new(profile.webhooks.clone()))
+    .set_email_exporter(email_exporter)
+    .set_vault_exporter(vault_exporter);
+    let chat_session = session_factory.create().await?;
 
     match poll(Duration::from_millis(0)) {
         Ok(_) => {
             // Starting interactive session
             let mut app_session = AppSession::new();
             app_session.add_tab(chat_session);
-            interactive_mode(app_session).await
+            if let Some(tab) = app_session.get_tab_mut(0) {
+                tab.ui.set_snippets(SnippetLibrary::from_config_file()?);
+            }
+            let pool_size = *matches.get_one::<usize>("pool-size").unwrap();
+            let pool_ttl = matches
+                .get_one::<u64>("pool-ttl")
+                .copied()
+                .unwrap_or(DEFAULT_POOL_IDLE_TTL_SECONDS);
+            let session_pool = SessionPool::new(
+                session_factory,
+                pool_size,
+                Duration::from_secs(pool_ttl),
+            );
+            let render_pacer = RenderPacer::new(
+                matches.get_one::<usize>("render-rate").copied(),
+            );
+            interactive_mode(app_session, session_pool, render_pacer).await
         }
         Err(_) => {
             // potential non-interactive input detected due to poll error.
             // attempt to use in non interactive mode
-            process_non_interactive_input(chat_session).await
+            process_non_interactive_input(
+                chat_session,
+                email_to,
+                export_to_vault,
+            )
+            .await
         }
     }
 }
 
 async fn interactive_mode(
     app_session: AppSession<'_>,
+    session_pool: SessionPool,
+    render_pacer: RenderPacer,
 ) -> Result<(), ApplicationError> {
     println!("Interactive mode detected. Starting interactive session:");
     let mut stdout = io::stdout().lock();
@@ -385,7 +1612,12 @@ async fn interactive_mode(
         return Err(e.into());
     }
 
-    if let Err(e) = execute!(stdout, EnterAlternateScreen, EnableMouseCapture) {
+    if let Err(e) = execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    ) {
         let _ = disable_raw_mode();
         return Err(e.into());
     }
@@ -398,21 +1630,25 @@ async fn interactive_mode(
             let _ = execute!(
                 io::stdout(),
                 LeaveAlternateScreen,
-                DisableMouseCapture
+                DisableMouseCapture,
+                DisableBracketedPaste
             );
             return Err(e.into());
         }
     };
 
     // Run the application logic and capture the result
-    let result = prompt_app(&mut terminal, app_session).await;
+    let result =
+        prompt_app(&mut terminal, app_session, session_pool, render_pacer)
+            .await;
 
     // Regardless of the result, perform cleanup
     let _ = disable_raw_mode();
     let _ = execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     );
     let _ = terminal.show_cursor();
     result
@@ -420,6 +1656,8 @@ async fn interactive_mode(
 
 async fn process_non_interactive_input(
     chat: ChatSession,
+    email_to: Option<String>,
+    export_to_vault: bool,
 ) -> Result<(), ApplicationError> {
     let chat = Arc::new(Mutex::new(chat));
     let stdin = tokio::io::stdin();
@@ -453,7 +1691,9 @@ async fn process_non_interactive_input(
         // Process the prompt
         let process_handle = tokio::spawn(async move {
             let mut chat = chat_clone.lock().await;
-            chat.process_prompt(input, running.clone()).await
+            chat.process_prompt(input, running.clone())
+                .instrument(info_span!("completion"))
+                .await
         });
 
         // Wait for the process to complete or for a shutdown signal
@@ -502,6 +1742,15 @@ async fn process_non_interactive_input(
                             e
                         ))
                     })?;
+                if let Some(address) = email_to {
+                    chat.lock().await.email_conversation(&address).await?;
+                    eprintln!("Conversation sent to {}", address);
+                }
+                if export_to_vault {
+                    let location =
+                        chat.lock().await.export_to_vault().await?;
+                    eprintln!("Conversation exported to {}", location);
+                }
                 return Ok(());
             }
 
@@ -515,10 +1764,7 @@ async fn process_non_interactive_input(
     }
 }
 
-async fn handle_ctrl_c(
-    r: Arc<Mutex<bool>>,
-    s: Arc<Mutex<bool>>,
-) {
+async fn handle_ctrl_c(r: Arc<Mutex<bool>>, s: Arc<Mutex<bool>>) {
     let mut count = 0;
     loop {
         signal::ctrl_c().await.expect("Failed to listen for Ctrl+C");
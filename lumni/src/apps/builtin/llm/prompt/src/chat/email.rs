@@ -0,0 +1,414 @@
+use std::env;
+use std::fs;
+use std::process::Command;
+
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{
+    AsyncSendmailTransport, AsyncSmtpTransport, AsyncTransport, Message,
+    Tokio1Executor,
+};
+use lumni::api::error::ApplicationError;
+use serde::Deserialize;
+use tokio::time::Duration;
+
+use super::{config_file, ChatExchange};
+pub use crate::external as lumni;
+
+const EMAIL_TIMEOUT_SECONDS: u64 = 30;
+const EMAIL_SUBJECT_MAX_LENGTH: usize = 60;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum EmailFormat {
+    #[default]
+    Html, // html body, with the markdown as plain text alternative
+    Markdown,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SmtpTls {
+    #[default]
+    Starttls, // typically port 587
+    Tls,  // implicit TLS, typically port 465
+    None, // local relays only
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SmtpConfig {
+    host: String,
+    port: Option<u16>,
+    #[serde(default)]
+    tls: SmtpTls,
+    username: Option<String>,
+    // the password is not stored in the config file, but read from the
+    // output of a command (e.g. "pass show smtp") or an environment variable
+    password_command: Option<String>,
+    password_env: Option<String>,
+    // only to detect and reject plain text passwords
+    password: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SendmailConfig {
+    command: Option<String>, // defaults to sendmail in PATH
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum EmailTransport {
+    Smtp(SmtpConfig),
+    Sendmail(SendmailConfig),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmailExporter {
+    from: String,
+    #[serde(default)]
+    format: EmailFormat,
+    #[serde(flatten)]
+    transport: EmailTransport,
+}
+
+impl EmailExporter {
+    // reads email.yaml from the lumni config directory, no file means
+    // email export is not configured
+    pub fn from_config_file() -> Result<Option<Self>, ApplicationError> {
+        let path = match config_file("email.yaml") {
+            Some(path) if path.exists() => path,
+            _ => return Ok(None),
+        };
+        let contents =
+            fs::read_to_string(&path).map_err(ApplicationError::IoError)?;
+        EmailExporter::parse(&contents).map(Some).map_err(|e| {
+            ApplicationError::InvalidUserConfiguration(format!(
+                "Invalid email configuration in {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    fn parse(contents: &str) -> Result<Self, String> {
+        let exporter: EmailExporter =
+            serde_yaml::from_str(contents).map_err(|e| e.to_string())?;
+        if let EmailTransport::Smtp(smtp) = &exporter.transport {
+            if smtp.password.is_some() {
+                return Err("plain text password, use password_command or \
+                            password_env instead"
+                    .to_string());
+            }
+        }
+        Ok(exporter)
+    }
+
+    pub async fn send(
+        &self,
+        to: &str,
+        model: Option<&str>,
+        exchanges: &[ChatExchange],
+    ) -> Result<(), ApplicationError> {
+        if exchanges.is_empty() {
+            return Err(ApplicationError::NotReady(
+                "No conversation to send".to_string(),
+            ));
+        }
+        let message = self.build_message(to, model, exchanges)?;
+        match &self.transport {
+            EmailTransport::Smtp(smtp) => smtp_transport(smtp)?
+                .send(message)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            EmailTransport::Sendmail(sendmail) => sendmail_transport(sendmail)
+                .send(message)
+                .await
+                .map_err(|e| e.to_string()),
+        }
+        .map_err(|e| {
+            ApplicationError::Runtime(format!("Sending email failed: {}", e))
+        })
+    }
+
+    fn build_message(
+        &self,
+        to: &str,
+        model: Option<&str>,
+        exchanges: &[ChatExchange],
+    ) -> Result<Message, ApplicationError> {
+        let from: Mailbox = self.from.parse().map_err(|e| {
+            ApplicationError::InvalidUserConfiguration(format!(
+                "Invalid from address \"{}\": {}",
+                self.from, e
+            ))
+        })?;
+        let to: Mailbox = to.parse().map_err(|e| {
+            ApplicationError::InvalidUserConfiguration(format!(
+                "Invalid address \"{}\": {}",
+                to, e
+            ))
+        })?;
+
+        let markdown = conversation_markdown(model, exchanges);
+        let json = serde_json::to_string_pretty(exchanges).map_err(|e| {
+            ApplicationError::Unexpected(format!(
+                "Failed to serialize conversation: {}",
+                e
+            ))
+        })?;
+
+        let body = match self.format {
+            EmailFormat::Html => {
+                MultiPart::mixed().multipart(MultiPart::alternative_plain_html(
+                    markdown.clone(),
+                    markdown_to_html(&markdown),
+                ))
+            }
+            EmailFormat::Markdown => MultiPart::mixed()
+                .singlepart(SinglePart::plain(markdown.clone())),
+        };
+        let attachment = |name: &str, content: String, content_type: &str| {
+            Attachment::new(name.to_string()).body(
+                content,
+                ContentType::parse(content_type)
+                    .unwrap_or(ContentType::TEXT_PLAIN),
+            )
+        };
+        Message::builder()
+            .from(from)
+            .to(to)
+            .subject(subject(exchanges))
+            .multipart(
+                body.singlepart(attachment(
+                    "conversation.md",
+                    markdown,
+                    "text/markdown; charset=utf-8",
+                ))
+                .singlepart(attachment(
+                    "conversation.json",
+                    json,
+                    "application/json",
+                )),
+            )
+            .map_err(|e| {
+                ApplicationError::Unexpected(format!(
+                    "Failed to build email: {}",
+                    e
+                ))
+            })
+    }
+}
+
+fn smtp_transport(
+    smtp: &SmtpConfig,
+) -> Result<AsyncSmtpTransport<Tokio1Executor>, ApplicationError> {
+    let builder = match smtp.tls {
+        SmtpTls::Starttls => {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host)
+        }
+        SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.host),
+        SmtpTls::None => Ok(
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&smtp.host),
+        ),
+    }
+    .map_err(|e| {
+        ApplicationError::InvalidUserConfiguration(format!(
+            "Invalid SMTP host {}: {}",
+            smtp.host, e
+        ))
+    })?;
+    let mut builder =
+        builder.timeout(Some(Duration::from_secs(EMAIL_TIMEOUT_SECONDS)));
+    if let Some(port) = smtp.port {
+        builder = builder.port(port);
+    }
+    if let Some(username) = &smtp.username {
+        let password = smtp_password(smtp)?;
+        builder =
+            builder.credentials(Credentials::new(username.clone(), password));
+    }
+    Ok(builder.build())
+}
+
+fn smtp_password(smtp: &SmtpConfig) -> Result<String, ApplicationError> {
+    if let Some(command) = &smtp.password_command {
+        let output =
+            Command::new("sh").arg("-c").arg(command).output().map_err(
+                |e| {
+                    ApplicationError::InvalidCredentials(format!(
+                        "Failed to run password_command: {}",
+                        e
+                    ))
+                },
+            )?;
+        if !output.status.success() {
+            return Err(ApplicationError::InvalidCredentials(format!(
+                "password_command exited with {}",
+                output.status
+            )));
+        }
+        // commands such as "pass show" end with a newline
+        let password = String::from_utf8_lossy(&output.stdout);
+        return Ok(password.trim_end_matches(['\r', '\n']).to_string());
+    }
+    if let Some(name) = &smtp.password_env {
+        return env::var(name).map_err(|_| {
+            ApplicationError::InvalidCredentials(format!(
+                "Environment variable {} for the SMTP password is not set",
+                name
+            ))
+        });
+    }
+    Err(ApplicationError::InvalidUserConfiguration(
+        "SMTP username is set without password_command or password_env"
+            .to_string(),
+    ))
+}
+
+fn sendmail_transport(
+    sendmail: &SendmailConfig,
+) -> AsyncSendmailTransport<Tokio1Executor> {
+    match &sendmail.command {
+        Some(command) => AsyncSendmailTransport::new_with_command(command),
+        None => AsyncSendmailTransport::new(),
+    }
+}
+
+fn subject(exchanges: &[ChatExchange]) -> String {
+    let first_line = exchanges
+        .first()
+        .and_then(|exchange| exchange.get_question().lines().next())
+        .unwrap_or_default();
+    let mut subject: String =
+        first_line.chars().take(EMAIL_SUBJECT_MAX_LENGTH).collect();
+    if first_line.chars().count() > EMAIL_SUBJECT_MAX_LENGTH {
+        subject.push('…');
+    }
+    format!("Conversation: {}", subject)
+}
+
+pub(super) fn conversation_markdown(
+    model: Option<&str>,
+    exchanges: &[ChatExchange],
+) -> String {
+    let assistant = model.unwrap_or("Assistant");
+    let mut markdown = String::new();
+    for exchange in exchanges {
+        let you = match exchange.get_reply_to() {
+            Some(reply_to) => {
+                format!("**You**, in reply to message {}", reply_to + 1)
+            }
+            None => "**You**".to_string(),
+        };
+        markdown.push_str(&format!(
+            "{}\n\n{}\n\n**{}**\n\n{}\n\n",
+            you,
+            exchange.get_question().trim(),
+            assistant,
+            exchange.get_answer().trim()
+        ));
+        if let Some(vote) = exchange.get_vote() {
+            markdown.push_str(&format!("*Vote: {}*\n\n", vote.as_str()));
+        }
+        if let Some(annotation) = exchange.get_annotation() {
+            markdown.push_str(&format!("*Note: {}*\n\n", annotation));
+        }
+    }
+    markdown.trim_end().to_string()
+}
+
+// minimal rendering: paragraphs, line breaks, bold labels and code blocks,
+// the markdown itself is included as plain text and attachment
+fn markdown_to_html(markdown: &str) -> String {
+    let mut html = String::from("<html><body>\n");
+    let mut paragraph: Vec<String> = Vec::new();
+    let mut code_block: Option<Vec<String>> = None;
+
+    let flush = |paragraph: &mut Vec<String>, html: &mut String| {
+        if !paragraph.is_empty() {
+            html.push_str(&format!("<p>{}</p>\n", paragraph.join("<br>\n")));
+            paragraph.clear();
+        }
+    };
+
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            match code_block.take() {
+                Some(code) => {
+                    html.push_str(&format!(
+                        "<pre><code>{}</code></pre>\n",
+                        code.join("\n")
+                    ));
+                }
+                None => {
+                    flush(&mut paragraph, &mut html);
+                    code_block = Some(Vec::new());
+                }
+            }
+        } else if let Some(code) = code_block.as_mut() {
+            code.push(escape_html(line));
+        } else if line.trim().is_empty() {
+            flush(&mut paragraph, &mut html);
+        } else {
+            let line = escape_html(line);
+            // labels written by conversation_markdown, e.g. "**You**"
+            match line
+                .strip_prefix("**")
+                .and_then(|line| line.strip_suffix("**"))
+            {
+                Some(label) if !label.contains("**") => {
+                    paragraph.push(format!("<strong>{}</strong>", label))
+                }
+                _ => paragraph.push(line),
+            }
+        }
+    }
+    // an unterminated code block is kept as code
+    if let Some(code) = code_block {
+        html.push_str(&format!(
+            "<pre><code>{}</code></pre>\n",
+            code.join("\n")
+        ));
+    }
+    flush(&mut paragraph, &mut html);
+    html.push_str("</body></html>\n");
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_to_html() {
+        let markdown =
+            "**You**\n\nis 1 < 2?\n\n**llama3**\n\nyes\n```\nif a < b {}\n```";
+        assert_eq!(
+            markdown_to_html(markdown),
+            "<html><body>\n<p><strong>You</strong></p>\n<p>is 1 &lt; \
+             2?</p>\n<p><strong>llama3</strong></p>\n<p>yes</p>\n<pre><code>if \
+             a &lt; b {}</code></pre>\n</body></html>\n"
+        );
+    }
+
+    #[test]
+    fn test_plain_text_password_is_rejected() {
+        let config =
+            "from: me@example.com\nsmtp:\n  host: smtp.example.com\n  \
+                      username: me\n";
+        let exporter = EmailExporter::parse(config).unwrap();
+        assert!(matches!(exporter.transport, EmailTransport::Smtp(_)));
+
+        let config = format!("{}  password: secret\n", config);
+        assert!(EmailExporter::parse(&config).is_err());
+    }
+}
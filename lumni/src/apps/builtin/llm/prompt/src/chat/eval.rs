@@ -0,0 +1,509 @@
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use lumni::api::error::ApplicationError;
+use lumni::{
+    OptionalStringColumn, StringColumn, Table, TableCallback, TableColumn,
+    TableColumnValue, Uint64Column,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::schema::match_schema;
+use super::{data_file, SessionFactory};
+pub use crate::external as lumni;
+
+const EVAL_RUNS_FILE: &str = "eval-runs.jsonl";
+
+// system prompt of the session that grades rubric assertions
+pub const GRADER_INSTRUCTION: &str = "You grade answers against a rubric. \
+                                      Reply with PASS or FAIL on the first \
+                                      line, followed by a short reason.";
+
+// prompts with assertions on their answers, read from YAML:
+//
+// cases:
+//   - name: capital
+//     prompt: What is the capital of France?
+//     assert:
+//       - contains: Paris
+//       - rubric: Names Paris and nothing else
+#[derive(Debug, Deserialize)]
+pub struct EvalSuite {
+    #[serde(default)]
+    name: Option<String>,
+    // system prompt for all cases
+    #[serde(default)]
+    system: Option<String>,
+    cases: Vec<EvalCase>,
+}
+
+impl EvalSuite {
+    pub fn from_file(path: &str) -> Result<Self, ApplicationError> {
+        let contents =
+            fs::read_to_string(path).map_err(ApplicationError::IoError)?;
+        let mut suite: EvalSuite =
+            serde_yaml::from_str(&contents).map_err(|e| {
+                ApplicationError::InvalidUserConfiguration(format!(
+                    "Invalid eval suite {}: {}",
+                    path, e
+                ))
+            })?;
+        if suite.name.is_none() {
+            suite.name = Path::new(path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string());
+        }
+        // invalid patterns fail before any prompt is sent
+        for case in &suite.cases {
+            for assertion in &case.assertions {
+                if let Assertion::Regex(pattern) = assertion {
+                    compile(pattern)?;
+                }
+            }
+        }
+        Ok(suite)
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or("eval")
+    }
+
+    pub fn system(&self) -> Option<&str> {
+        self.system.as_deref()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct EvalCase {
+    name: String,
+    prompt: String,
+    #[serde(default, rename = "assert")]
+    assertions: Vec<Assertion>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(try_from = "AssertionSpec")]
+enum Assertion {
+    Contains(String),
+    NotContains(String),
+    Regex(String),
+    // the answer, without a code fence around it, is JSON that matches
+    // the schema. Supports type, properties, required, items and enum
+    JsonSchema(Value),
+    // graded by a model
+    Rubric(String),
+}
+
+// serde_yaml reads enums from tags (!contains), assertions are written
+// as maps with a single key instead
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AssertionSpec {
+    contains: Option<String>,
+    not_contains: Option<String>,
+    regex: Option<String>,
+    json_schema: Option<Value>,
+    rubric: Option<String>,
+}
+
+impl TryFrom<AssertionSpec> for Assertion {
+    type Error = String;
+
+    fn try_from(spec: AssertionSpec) -> Result<Self, Self::Error> {
+        let assertions: Vec<Assertion> = [
+            spec.contains.map(Assertion::Contains),
+            spec.not_contains.map(Assertion::NotContains),
+            spec.regex.map(Assertion::Regex),
+            spec.json_schema.map(Assertion::JsonSchema),
+            spec.rubric.map(Assertion::Rubric),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        match <[Assertion; 1]>::try_from(assertions) {
+            Ok([assertion]) => Ok(assertion),
+            Err(_) => Err("an assertion needs exactly one of contains, \
+                           not_contains, regex, json_schema or rubric"
+                .to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Assertion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Assertion::Contains(text) => write!(f, "contains {:?}", text),
+            Assertion::NotContains(text) => {
+                write!(f, "not contains {:?}", text)
+            }
+            Assertion::Regex(pattern) => write!(f, "regex {:?}", pattern),
+            Assertion::JsonSchema(_) => write!(f, "json schema"),
+            Assertion::Rubric(rubric) => write!(f, "rubric {:?}", rubric),
+        }
+    }
+}
+
+fn compile(pattern: &str) -> Result<Regex, ApplicationError> {
+    Regex::new(pattern).map_err(|e| {
+        ApplicationError::InvalidUserConfiguration(format!(
+            "Invalid regex {}: {}",
+            pattern, e
+        ))
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseResult {
+    name: String,
+    passed: bool,
+    // failed assertions, or the error that prevented an answer
+    failures: Vec<String>,
+    duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalRun {
+    suite: String,
+    model: String,
+    started_at: u64,
+    cases: Vec<CaseResult>,
+}
+
+impl EvalRun {
+    pub fn passed(&self) -> usize {
+        self.cases.iter().filter(|case| case.passed).count()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cases.len()
+    }
+
+    pub fn to_table(&self) -> EvalTable {
+        let mut table = EvalTable::new();
+        for case in &self.cases {
+            let failures = match case.failures.is_empty() {
+                true => None,
+                false => Some(case.failures.join("; ")),
+            };
+            let status = if case.passed { "pass" } else { "fail" };
+            let row = vec![
+                (
+                    "case".to_string(),
+                    TableColumnValue::StringColumn(case.name.clone()),
+                ),
+                (
+                    "status".to_string(),
+                    TableColumnValue::StringColumn(status.to_string()),
+                ),
+                (
+                    "duration_ms".to_string(),
+                    TableColumnValue::Uint64Column(case.duration_ms),
+                ),
+                (
+                    "failures".to_string(),
+                    TableColumnValue::OptionalStringColumn(failures),
+                ),
+            ];
+            // the columns match the row
+            let _ = table.add_row(row);
+        }
+        table
+    }
+
+    // e.g. "FAIL capital (812 ms): contains \"Paris\""
+    pub fn print(&self) {
+        for case in &self.cases {
+            let status = if case.passed { "PASS" } else { "FAIL" };
+            let mut line =
+                format!("{} {} ({} ms)", status, case.name, case.duration_ms);
+            if !case.failures.is_empty() {
+                line.push_str(&format!(": {}", case.failures.join("; ")));
+            }
+            println!("{}", line);
+        }
+    }
+
+    // cases that pass now but failed in the previous run, and the other
+    // way around
+    pub fn compare(&self, previous: &EvalRun) -> Vec<String> {
+        let mut changes = Vec::new();
+        for case in &self.cases {
+            let before = previous
+                .cases
+                .iter()
+                .find(|previous| previous.name == case.name);
+            match before {
+                Some(before) if before.passed && !case.passed => {
+                    changes.push(format!("{}: regressed", case.name))
+                }
+                Some(before) if !before.passed && case.passed => {
+                    changes.push(format!("{}: fixed", case.name))
+                }
+                None => changes.push(format!("{}: new", case.name)),
+                _ => {}
+            }
+        }
+        changes
+    }
+}
+
+// pass/fail per case, for export as csv or jsonl
+#[derive(Debug)]
+pub struct EvalTable {
+    columns: Vec<(String, Box<dyn TableColumn>)>,
+    rows: usize,
+}
+
+impl EvalTable {
+    fn new() -> Self {
+        let mut table = EvalTable {
+            columns: Vec::new(),
+            rows: 0,
+        };
+        table.add_column("case", Box::new(StringColumn(Vec::new())));
+        table.add_column("status", Box::new(StringColumn(Vec::new())));
+        table.add_column("duration_ms", Box::new(Uint64Column(Vec::new())));
+        table
+            .add_column("failures", Box::new(OptionalStringColumn(Vec::new())));
+        table
+    }
+}
+
+impl Table for EvalTable {
+    fn len(&self) -> usize {
+        self.rows
+    }
+
+    fn add_column(&mut self, name: &str, column_type: Box<dyn TableColumn>) {
+        self.columns.push((name.to_string(), column_type));
+    }
+
+    fn columns(&self) -> &[(String, Box<dyn TableColumn>)] {
+        &self.columns
+    }
+
+    // rows are only exported, not streamed
+    fn set_callback(&mut self, _callback: Arc<dyn TableCallback>) {}
+
+    fn add_row(
+        &mut self,
+        row_data: Vec<(String, TableColumnValue)>,
+    ) -> Result<(), String> {
+        for (name, value) in row_data {
+            match self.columns.iter_mut().find(|(column, _)| column == &name) {
+                Some((_, column)) => column.append(value)?,
+                None => return Err(format!("Column '{}' not found", name)),
+            }
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    fn fmt_debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EvalTable")
+            .field("columns", &self.columns)
+            .finish()
+    }
+}
+
+// sends each case to a new session, so cases do not see each other
+pub struct EvalRunner {
+    sessions: SessionFactory,
+    model: String,
+    // for rubric assertions
+    grader: Option<SessionFactory>,
+}
+
+impl EvalRunner {
+    pub fn new(sessions: SessionFactory, model: &str) -> Self {
+        EvalRunner {
+            sessions,
+            model: model.to_string(),
+            grader: None,
+        }
+    }
+
+    pub fn set_grader(mut self, grader: SessionFactory) -> Self {
+        self.grader = Some(grader);
+        self
+    }
+
+    pub async fn run(&self, suite: &EvalSuite) -> EvalRun {
+        let mut run = EvalRun {
+            suite: suite.name().to_string(),
+            model: self.model.clone(),
+            started_at: now_in_seconds(),
+            cases: Vec::new(),
+        };
+        for case in &suite.cases {
+            let start = Instant::now();
+            let failures = match self.ask(&case.prompt).await {
+                Ok(answer) => self.check(&case.assertions, &answer).await,
+                Err(e) => vec![format!("error: {}", e)],
+            };
+            run.cases.push(CaseResult {
+                name: case.name.clone(),
+                passed: failures.is_empty(),
+                failures,
+                duration_ms: start.elapsed().as_millis() as u64,
+            });
+        }
+        run
+    }
+
+    async fn ask(&self, prompt: &str) -> Result<String, ApplicationError> {
+        let mut session = self.sessions.create().await?;
+        session.ask(prompt.to_string()).await
+    }
+
+    // descriptions of the assertions that failed
+    async fn check(
+        &self,
+        assertions: &[Assertion],
+        answer: &str,
+    ) -> Vec<String> {
+        let mut failures = Vec::new();
+        for assertion in assertions {
+            let failure = match assertion {
+                Assertion::Rubric(rubric) => self.grade(rubric, answer).await,
+                _ => check_assertion(assertion, answer),
+            };
+            if let Some(failure) = failure {
+                failures.push(failure);
+            }
+        }
+        failures
+    }
+
+    async fn grade(&self, rubric: &str, answer: &str) -> Option<String> {
+        let grader = match &self.grader {
+            Some(grader) => grader,
+            None => return Some("rubric: no grader".to_string()),
+        };
+        let question = format!("Rubric:\n{}\n\nAnswer:\n{}", rubric, answer);
+        let verdict = match grader.create().await {
+            Ok(mut session) => session.ask(question).await,
+            Err(e) => Err(e),
+        };
+        match verdict {
+            Ok(verdict) if verdict.to_uppercase().starts_with("PASS") => None,
+            Ok(verdict) => Some(format!(
+                "rubric {:?}: {}",
+                rubric,
+                verdict.lines().next().unwrap_or_default()
+            )),
+            Err(e) => Some(format!("rubric {:?}: error: {}", rubric, e)),
+        }
+    }
+}
+
+// None if the answer meets the assertion. Rubrics are graded by the runner
+fn check_assertion(assertion: &Assertion, answer: &str) -> Option<String> {
+    let passed = match assertion {
+        Assertion::Contains(text) => answer.contains(text.as_str()),
+        Assertion::NotContains(text) => !answer.contains(text.as_str()),
+        Assertion::Regex(pattern) => {
+            compile(pattern).is_ok_and(|regex| regex.is_match(answer))
+        }
+        Assertion::JsonSchema(schema) => {
+            let json = strip_code_fence(answer);
+            return match serde_json::from_str::<Value>(json) {
+                Ok(value) => match_schema(&value, schema, "$")
+                    .map(|e| format!("{}: {}", assertion, e)),
+                Err(e) => Some(format!("{}: not JSON, {}", assertion, e)),
+            };
+        }
+        Assertion::Rubric(_) => true,
+    };
+    match passed {
+        true => None,
+        false => Some(assertion.to_string()),
+    }
+}
+
+// models often wrap JSON in ```json ... ```
+fn strip_code_fence(answer: &str) -> &str {
+    let answer = answer.trim();
+    match answer.strip_prefix("```") {
+        Some(fenced) => {
+            let body = fenced.split_once('\n').map_or("", |(_, body)| body);
+            body.trim_end().trim_end_matches("```").trim()
+        }
+        None => answer,
+    }
+}
+
+fn now_in_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+// earlier runs, one JSON line per run, to compare a run with
+pub struct EvalHistory;
+
+impl EvalHistory {
+    pub fn append(run: &EvalRun) -> Result<(), ApplicationError> {
+        let path = data_file(EVAL_RUNS_FILE).ok_or_else(|| {
+            ApplicationError::Unexpected("No data directory".to_string())
+        })?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(ApplicationError::IoError)?;
+        }
+        let line = serde_json::to_string(run)
+            .map_err(|e| ApplicationError::Unexpected(e.to_string()))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(ApplicationError::IoError)?;
+        writeln!(file, "{}", line).map_err(ApplicationError::IoError)
+    }
+
+    // the last run of the suite with the same model
+    pub fn previous(suite: &str, model: &str) -> Option<EvalRun> {
+        let contents = fs::read_to_string(data_file(EVAL_RUNS_FILE)?).ok()?;
+        contents
+            .lines()
+            .rev()
+            .filter_map(|line| serde_json::from_str::<EvalRun>(line).ok())
+            .find(|run| run.suite == suite && run.model == model)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eval_assertions() {
+        let suite: EvalSuite = serde_yaml::from_str(
+            "cases:\n  - name: city\n    prompt: Capital of France as \
+             JSON?\n    assert:\n      - contains: Paris\n      - regex: \
+             '^```'\n      - json_schema:\n          type: object\n          \
+             required: [city]\n          properties:\n            \
+             population: {type: integer}\n",
+        )
+        .unwrap();
+        let assertions = &suite.cases[0].assertions;
+        let answer = "```json\n{\"city\": \"Paris\", \"population\": 2.1}\n```";
+        let failures: Vec<_> = assertions
+            .iter()
+            .filter_map(|assertion| check_assertion(assertion, answer))
+            .collect();
+        assert_eq!(
+            failures,
+            ["json schema: $.population is not of type integer"]
+        );
+        let answer = "```json\n{\"city\": \"Paris\"}\n```";
+        assert!(assertions
+            .iter()
+            .all(|assertion| check_assertion(assertion, answer).is_none()));
+    }
+}
@@ -1,10 +1,50 @@
 use serde::{Deserialize, Serialize};
 
+use super::RawExchange;
+
+// feedback on an answer, the reason is kept as the annotation
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Vote {
+    Up,
+    Down,
+}
+
+impl Vote {
+    pub fn from_str(name: &str) -> Option<Self> {
+        match name {
+            "up" => Some(Vote::Up),
+            "down" => Some(Vote::Down),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Vote::Up => "up",
+            Vote::Down => "down",
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ChatExchange {
     question: String,
     answer: String,
     token_length: Option<usize>,
+    // pinned exchanges are kept in the context when the history is trimmed
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pinned: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    annotation: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    vote: Option<Vote>,
+    // index in the conversation of the exchange this question quotes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reply_to: Option<usize>,
+    // request and response as sent over the wire, for this session only
+    #[serde(skip)]
+    raw: Option<RawExchange>,
 }
 
 impl ChatExchange {
@@ -13,6 +53,11 @@ impl ChatExchange {
             question,
             answer,
             token_length: None,
+            pinned: false,
+            annotation: None,
+            vote: None,
+            reply_to: None,
+            raw: None,
         }
     }
 
@@ -39,4 +84,100 @@ impl ChatExchange {
     pub fn set_token_length(&mut self, token_length: usize) {
         self.token_length = Some(token_length);
     }
+
+    pub fn is_pinned(&self) -> bool {
+        self.pinned
+    }
+
+    pub fn set_pinned(&mut self, pinned: bool) {
+        self.pinned = pinned;
+    }
+
+    pub fn get_annotation(&self) -> Option<&str> {
+        self.annotation.as_deref()
+    }
+
+    pub fn set_annotation(&mut self, annotation: Option<String>) {
+        self.annotation = annotation;
+    }
+
+    pub fn get_vote(&self) -> Option<Vote> {
+        self.vote
+    }
+
+    pub fn set_vote(&mut self, vote: Option<Vote>) {
+        self.vote = vote;
+    }
+
+    pub fn get_reply_to(&self) -> Option<usize> {
+        self.reply_to
+    }
+
+    pub fn set_reply_to(&mut self, reply_to: Option<usize>) {
+        self.reply_to = reply_to;
+    }
+
+    pub fn get_raw(&self) -> Option<&RawExchange> {
+        self.raw.as_ref()
+    }
+
+    pub fn set_raw(&mut self, raw: Option<RawExchange>) {
+        self.raw = raw;
+    }
+
+    pub fn raw_mut(&mut self) -> Option<&mut RawExchange> {
+        self.raw.as_mut()
+    }
+}
+
+// counts of a conversation and the feedback given on it, shown by ":stats"
+// tokens are left out when the server does not count them
+pub fn conversation_stats(
+    exchanges: &[ChatExchange],
+    count_tokens: bool,
+) -> String {
+    let count = |vote| {
+        exchanges
+            .iter()
+            .filter(|exchange| exchange.get_vote() == Some(vote))
+            .count()
+    };
+    let (up, down) = (count(Vote::Up), count(Vote::Down));
+    let tokens: usize = exchanges
+        .iter()
+        .filter_map(|exchange| exchange.get_token_length())
+        .sum();
+    let mut lines = vec![format!("Messages: {}", exchanges.len())];
+    if count_tokens {
+        lines.push(format!("Tokens: {}", tokens));
+    }
+    lines.extend([
+        format!(
+            "Pinned: {}",
+            exchanges
+                .iter()
+                .filter(|exchange| exchange.is_pinned())
+                .count()
+        ),
+        format!("Thumbs up: {}", up),
+        format!("Thumbs down: {}", down),
+    ]);
+    if let Some(positive) = (up * 100).checked_div(up + down) {
+        lines.push(format!("Rated positive: {}%", positive));
+    }
+    let reasons: Vec<String> = exchanges
+        .iter()
+        .enumerate()
+        .filter_map(|(index, exchange)| {
+            let vote = exchange.get_vote()?;
+            let reason = exchange.get_annotation()?;
+            Some(format!("  {} {}: {}", index + 1, vote.as_str(), reason))
+        })
+        .collect();
+    if !reasons.is_empty() {
+        lines.push(String::new());
+        lines.push("Reasons:".to_string());
+        lines.extend(reasons);
+    }
+    lines.join("\n")
 }
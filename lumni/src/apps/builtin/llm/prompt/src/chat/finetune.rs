@@ -0,0 +1,286 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use lumni::api::error::ApplicationError;
+use regex::Regex;
+use serde::Serialize;
+
+use super::vault::VaultConversation;
+use super::{ChatMessage, Vote};
+pub use crate::external as lumni;
+
+const REDACTED: &str = "[REDACTED]";
+
+// email addresses, phone numbers and IPv4 addresses
+const PII_PATTERNS: [&str; 3] = [
+    r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}",
+    r"\+?\d[\d\s().-]{7,}\d",
+    r"\b\d{1,3}(?:\.\d{1,3}){3}\b",
+];
+
+// applied to every message before it is exported
+pub trait Redactor {
+    fn redact(&self, text: &str) -> Result<String, ApplicationError>;
+}
+
+pub struct PatternRedactor {
+    patterns: Vec<Regex>,
+}
+
+impl PatternRedactor {
+    pub fn new(patterns: &[&str]) -> Result<Self, ApplicationError> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern).map_err(|e| {
+                    ApplicationError::InvalidUserConfiguration(format!(
+                        "Invalid redact pattern {}: {}",
+                        pattern, e
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(PatternRedactor { patterns })
+    }
+
+    pub fn pii() -> Self {
+        PatternRedactor::new(&PII_PATTERNS).expect("valid PII patterns")
+    }
+}
+
+impl Redactor for PatternRedactor {
+    fn redact(&self, text: &str) -> Result<String, ApplicationError> {
+        let mut text = text.to_string();
+        for pattern in &self.patterns {
+            text = pattern.replace_all(&text, REDACTED).into_owned();
+        }
+        Ok(text)
+    }
+}
+
+// message on stdin, redacted message on stdout
+pub struct CommandRedactor {
+    command: String,
+}
+
+impl CommandRedactor {
+    pub fn new(command: String) -> Self {
+        CommandRedactor { command }
+    }
+}
+
+impl Redactor for CommandRedactor {
+    fn redact(&self, text: &str) -> Result<String, ApplicationError> {
+        let error = |e: std::io::Error| {
+            ApplicationError::Runtime(format!(
+                "Failed to run redact command: {}",
+                e
+            ))
+        };
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(error)?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes()).map_err(error)?;
+        }
+        let output = child.wait_with_output().map_err(error)?;
+        if !output.status.success() {
+            return Err(ApplicationError::Runtime(format!(
+                "Redact command exited with {}",
+                output.status
+            )));
+        }
+        // commands such as sed end with a newline
+        let text = String::from_utf8_lossy(&output.stdout);
+        Ok(text.trim_end_matches(['\r', '\n']).to_string())
+    }
+}
+
+#[derive(Serialize)]
+struct FinetuneRecord<'a> {
+    messages: &'a [ChatMessage],
+}
+
+#[derive(Default)]
+pub struct FinetuneExport {
+    pub train: Vec<String>,
+    pub validation: Vec<String>,
+}
+
+// chat-format JSONL, one line of messages per conversation
+pub struct FinetuneExporter {
+    system: Option<String>,
+    tags: Vec<String>,
+    vote: Option<Vote>,
+    validation_split: f64,
+    redactors: Vec<Box<dyn Redactor>>,
+}
+
+impl FinetuneExporter {
+    pub fn new() -> Self {
+        FinetuneExporter {
+            system: None,
+            tags: Vec::new(),
+            vote: None,
+            validation_split: 0.0,
+            redactors: Vec::new(),
+        }
+    }
+
+    pub fn set_system(mut self, system: Option<String>) -> Self {
+        self.system = system;
+        self
+    }
+
+    // conversations need at least one of these tags, none exports all
+    pub fn set_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    // only answers with this vote are exported
+    pub fn set_vote(mut self, vote: Option<Vote>) -> Self {
+        self.vote = vote;
+        self
+    }
+
+    // fraction of the conversations held out for validation
+    pub fn set_validation_split(mut self, split: f64) -> Self {
+        self.validation_split = split.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn add_redactor(mut self, redactor: Box<dyn Redactor>) -> Self {
+        self.redactors.push(redactor);
+        self
+    }
+
+    pub fn export(
+        &self,
+        conversations: &[VaultConversation],
+    ) -> Result<FinetuneExport, ApplicationError> {
+        let mut export = FinetuneExport::default();
+        let selected = conversations
+            .iter()
+            .filter(|conversation| self.selects(conversation));
+        for conversation in selected {
+            let record = match self.record(conversation)? {
+                Some(record) => record,
+                None => continue,
+            };
+            // spread evenly instead of at random, so a split is repeatable
+            let index = export.train.len() + export.validation.len();
+            let split = self.validation_split;
+            if ((index + 1) as f64 * split).floor()
+                > (index as f64 * split).floor()
+            {
+                export.validation.push(record);
+            } else {
+                export.train.push(record);
+            }
+        }
+        Ok(export)
+    }
+
+    // true if the conversation has one of the tags, if any are set
+    pub fn selects(&self, conversation: &VaultConversation) -> bool {
+        self.tags.is_empty()
+            || conversation.tags.iter().any(|tag| self.tags.contains(tag))
+    }
+
+    fn record(
+        &self,
+        conversation: &VaultConversation,
+    ) -> Result<Option<String>, ApplicationError> {
+        let mut messages = Vec::new();
+        if let Some(system) = &self.system {
+            messages.push(self.message("system", system)?);
+        }
+        for exchange in &conversation.exchanges {
+            // unanswered questions are not training examples
+            if exchange.get_answer().trim().is_empty() {
+                continue;
+            }
+            if self.vote.is_some() && exchange.get_vote() != self.vote {
+                continue;
+            }
+            messages.push(self.message("user", exchange.get_question())?);
+            messages.push(self.message("assistant", exchange.get_answer())?);
+        }
+        if !messages.iter().any(|message| message.role == "assistant") {
+            return Ok(None);
+        }
+        let record = FinetuneRecord {
+            messages: &messages,
+        };
+        serde_json::to_string(&record)
+            .map(Some)
+            .map_err(|e| ApplicationError::Unexpected(e.to_string()))
+    }
+
+    fn message(
+        &self,
+        role: &str,
+        content: &str,
+    ) -> Result<ChatMessage, ApplicationError> {
+        let mut content = content.to_string();
+        for redactor in &self.redactors {
+            content = redactor.redact(&content)?;
+        }
+        Ok(ChatMessage {
+            role: role.to_string(),
+            content,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::ChatExchange;
+    use super::*;
+
+    #[test]
+    fn test_finetune_export() {
+        let conversation = |name: &str, tag: &str| VaultConversation {
+            name: name.to_string(),
+            tags: vec!["lumni".to_string(), tag.to_string()],
+            exchanges: vec![
+                ChatExchange::new(
+                    "mail me at jane@example.com".to_string(),
+                    "ok".to_string(),
+                ),
+                ChatExchange::new("unanswered".to_string(), String::new()),
+            ],
+        };
+        let conversations: Vec<_> = (0..4)
+            .map(|i| {
+                conversation(&i.to_string(), if i < 3 { "a" } else { "b" })
+            })
+            .collect();
+        let export = FinetuneExporter::new()
+            .set_system(Some("be brief".to_string()))
+            .set_tags(vec!["a".to_string()])
+            .set_validation_split(0.34)
+            .add_redactor(Box::new(PatternRedactor::pii()))
+            .export(&conversations)
+            .unwrap();
+        assert_eq!(export.train.len(), 2);
+        // none of the answers was voted on
+        let voted = FinetuneExporter::new()
+            .set_vote(Some(Vote::Up))
+            .export(&conversations)
+            .unwrap();
+        assert!(voted.train.is_empty());
+        assert_eq!(export.validation.len(), 1);
+        assert_eq!(
+            export.train[0],
+            "{\"messages\":[{\"role\":\"system\",\"content\":\"be brief\"},\
+             {\"role\":\"user\",\"content\":\"mail me at [REDACTED]\"},\
+             {\"role\":\"assistant\",\"content\":\"ok\"}]}"
+        );
+    }
+}
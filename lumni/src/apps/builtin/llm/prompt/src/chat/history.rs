@@ -30,6 +30,73 @@ impl ChatHistory {
         }
     }
 
+    // exchanges of the conversation, without those preloaded from the
+    // assistant
+    pub fn get_conversation(&self) -> &[ChatExchange] {
+        self.exchanges
+            .get(self.keep_n.unwrap_or(0)..)
+            .unwrap_or_default()
+    }
+
+    pub fn get_conversation_mut(&mut self) -> &mut [ChatExchange] {
+        let start = self.keep_n.unwrap_or(0).min(self.exchanges.len());
+        &mut self.exchanges[start..]
+    }
+
+    // index is in the conversation, as in get_conversation(). replies
+    // to later exchanges are renumbered, replies to this one are dropped
+    pub fn remove_exchange(&mut self, index: usize) -> Option<ChatExchange> {
+        let position = self.keep_n.unwrap_or(0) + index;
+        if position >= self.exchanges.len() {
+            return None;
+        }
+        let removed = self.exchanges.remove(position);
+        for exchange in self.get_conversation_mut() {
+            match exchange.get_reply_to() {
+                Some(reply_to) if reply_to == index => {
+                    exchange.set_reply_to(None)
+                }
+                Some(reply_to) if reply_to > index => {
+                    exchange.set_reply_to(Some(reply_to - 1))
+                }
+                _ => {}
+            }
+        }
+        Some(removed)
+    }
+
+    // puts an exchange back at index, or at the end if the conversation
+    // is shorter by now. returns where it was inserted
+    pub fn insert_exchange(
+        &mut self,
+        index: usize,
+        mut exchange: ChatExchange,
+    ) -> usize {
+        let index = index.min(self.get_conversation().len());
+        for other in self.get_conversation_mut() {
+            match other.get_reply_to() {
+                Some(reply_to) if reply_to >= index => {
+                    other.set_reply_to(Some(reply_to + 1))
+                }
+                _ => {}
+            }
+        }
+        // the quoted message may have moved while it was deleted
+        if exchange
+            .get_reply_to()
+            .is_some_and(|reply_to| reply_to >= index)
+        {
+            exchange.set_reply_to(None);
+        }
+        let position = self.keep_n.unwrap_or(0) + index;
+        self.exchanges.insert(position, exchange);
+        index
+    }
+
+    pub fn extend_conversation(&mut self, exchanges: Vec<ChatExchange>) {
+        self.exchanges.extend(exchanges);
+    }
+
     pub fn get_last_exchange_mut(&mut self) -> Option<&mut ChatExchange> {
         self.exchanges.last_mut()
     }
@@ -63,14 +130,27 @@ impl ChatHistory {
             }
         }
 
-        let mut history_tokens = 0;
+        // pinned exchanges are always added, the others fill the remaining
+        // tokens starting from the most recent one
+        let mut history_tokens: usize = self
+            .exchanges
+            .iter()
+            .filter(|exchange| exchange.is_pinned())
+            .map(|exchange| exchange.get_token_length().unwrap_or(0))
+            .sum();
+        let mut history_full = false;
 
         for exchange in self.exchanges.iter().rev() {
-            let exchange_tokens = exchange.get_token_length().unwrap_or(0);
-            if history_tokens + exchange_tokens > tokens_remaining {
-                break;
+            if !exchange.is_pinned() {
+                let exchange_tokens = exchange.get_token_length().unwrap_or(0);
+                if history_full
+                    || history_tokens + exchange_tokens > tokens_remaining
+                {
+                    history_full = true;
+                    continue;
+                }
+                history_tokens += exchange_tokens;
             }
-            history_tokens += exchange_tokens;
             result_exchanges.insert(0, exchange.clone());
         }
 
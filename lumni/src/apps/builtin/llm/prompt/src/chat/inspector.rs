@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use bytes::Bytes;
+
+// header values that are never shown, compared in lowercase
+const SECRET_HEADERS: [&str; 6] = [
+    "authorization",
+    "proxy-authorization",
+    "x-api-key",
+    "api-key",
+    "x-amz-security-token",
+    "cookie",
+];
+// query parameters that carry credentials on some providers
+const SECRET_PARAMS: [&str; 4] = ["key", "api_key", "token", "access_token"];
+// raw response kept per exchange, later chunks are dropped
+const MAX_RESPONSE_BYTES: usize = 1024 * 1024;
+const REDACTED: &str = "[redacted]";
+
+// last completion request sent by http_post, taken by the session right
+// after the request is started
+static LAST_REQUEST: Mutex<Option<RawExchange>> = Mutex::new(None);
+
+// the request sent to the provider for an exchange and the raw chunks it
+// answered with, secrets are redacted when the request is recorded
+#[derive(Debug, Clone, Default)]
+pub struct RawExchange {
+    url: String,
+    headers: Vec<(String, String)>,
+    payload: String,
+    chunks: Vec<Bytes>,
+    response_bytes: usize,
+    truncated: bool,
+}
+
+pub fn record_request(
+    url: &str,
+    headers: &HashMap<String, String>,
+    payload: &str,
+) {
+    let mut headers: Vec<(String, String)> = headers
+        .iter()
+        .map(|(name, value)| {
+            if SECRET_HEADERS.contains(&name.to_lowercase().as_str()) {
+                (name.clone(), REDACTED.to_string())
+            } else {
+                (name.clone(), value.clone())
+            }
+        })
+        .collect();
+    headers.sort();
+    let raw = RawExchange {
+        url: redact_url(url),
+        headers,
+        payload: payload.to_string(),
+        ..Default::default()
+    };
+    *LAST_REQUEST.lock().unwrap() = Some(raw);
+}
+
+pub fn take_request() -> Option<RawExchange> {
+    LAST_REQUEST.lock().unwrap().take()
+}
+
+impl RawExchange {
+    pub fn push_chunk(&mut self, chunk: &Bytes) {
+        if self.response_bytes + chunk.len() > MAX_RESPONSE_BYTES {
+            self.truncated = true;
+            return;
+        }
+        self.response_bytes += chunk.len();
+        self.chunks.push(chunk.clone());
+    }
+
+    // readable report of the request and response, JSON is pretty-printed
+    pub fn render(&self) -> String {
+        let mut text = format!("POST {}\n", self.url);
+        for (name, value) in &self.headers {
+            text.push_str(&format!("{}: {}\n", name, value));
+        }
+        text.push_str("\n== Request payload ==\n");
+        text.push_str(&pretty_json(&self.payload));
+        text.push_str(&format!(
+            "\n\n== Response: {} chunks, {} bytes ==\n",
+            self.chunks.len(),
+            self.response_bytes
+        ));
+        for (number, chunk) in self.chunks.iter().enumerate() {
+            text.push_str(&format!("-- chunk {} --\n", number + 1));
+            // lines of server-sent events or newline delimited JSON
+            for line in String::from_utf8_lossy(chunk).lines() {
+                match line.strip_prefix("data:") {
+                    Some(data) => text.push_str(&format!(
+                        "data: {}\n",
+                        pretty_json(data.trim())
+                    )),
+                    None => text.push_str(&format!("{}\n", pretty_json(line))),
+                }
+            }
+        }
+        if self.truncated {
+            text.push_str(&format!(
+                "-- truncated after {} bytes --\n",
+                MAX_RESPONSE_BYTES
+            ));
+        }
+        text
+    }
+}
+
+fn pretty_json(text: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(text)
+        .ok()
+        .filter(|value| value.is_object() || value.is_array())
+        .and_then(|value| serde_json::to_string_pretty(&value).ok())
+        .unwrap_or_else(|| text.to_string())
+}
+
+fn redact_url(url: &str) -> String {
+    let Some((base, query)) = url.split_once('?') else {
+        return url.to_string();
+    };
+    let query: Vec<String> = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if SECRET_PARAMS.contains(&name) => {
+                format!("{}={}", name, REDACTED)
+            }
+            _ => pair.to_string(),
+        })
+        .collect();
+    format!("{}?{}", base, query.join("&"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_request_redacts_secrets() {
+        let headers = HashMap::from([
+            ("Authorization".to_string(), "Bearer sk-123".to_string()),
+            ("Content-Type".to_string(), "application/json".to_string()),
+        ]);
+        record_request("https://host/v1?key=abc&alt=sse", &headers, "{}");
+        let mut raw = take_request().unwrap();
+        raw.push_chunk(&Bytes::from("data: {\"a\":1}\n"));
+        let text = raw.render();
+        assert!(!text.contains("sk-123") && !text.contains("abc"));
+        assert!(text.contains("key=[redacted]&alt=sse"));
+        assert!(text.contains("data: {\n  \"a\": 1\n}"));
+        assert!(take_request().is_none());
+    }
+}
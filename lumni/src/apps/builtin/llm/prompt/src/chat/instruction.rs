@@ -1,6 +1,7 @@
 use lumni::api::error::ApplicationError;
 
 use super::history::ChatHistory;
+use super::language::Language;
 use super::prompt::Prompt;
 use super::{
     ChatCompletionOptions, ChatExchange, PromptOptions, DEFAULT_N_PREDICT,
@@ -78,6 +79,30 @@ impl PromptInstruction {
         self.history.update_last_exchange(answer);
     }
 
+    pub fn get_conversation(&self) -> &[ChatExchange] {
+        self.history.get_conversation()
+    }
+
+    pub fn get_conversation_mut(&mut self) -> &mut [ChatExchange] {
+        self.history.get_conversation_mut()
+    }
+
+    pub fn remove_exchange(&mut self, index: usize) -> Option<ChatExchange> {
+        self.history.remove_exchange(index)
+    }
+
+    pub fn insert_exchange(
+        &mut self,
+        index: usize,
+        exchange: ChatExchange,
+    ) -> usize {
+        self.history.insert_exchange(index, exchange)
+    }
+
+    pub fn extend_conversation(&mut self, exchanges: Vec<ChatExchange>) {
+        self.history.extend_conversation(exchanges);
+    }
+
     pub fn get_last_exchange_mut(&mut self) -> Option<&mut ChatExchange> {
         self.history.get_last_exchange_mut()
     }
@@ -132,6 +157,22 @@ impl PromptInstruction {
         self.system_prompt.get_instruction()
     }
 
+    pub fn get_response_language(&self) -> Option<Language> {
+        self.system_prompt.get_language()
+    }
+
+    pub fn set_response_language(
+        &mut self,
+        language: Option<Language>,
+    ) -> bool {
+        // returns true if the system prompt changed
+        if self.system_prompt.get_language() == language {
+            return false;
+        }
+        self.system_prompt.set_language(language);
+        true
+    }
+
     pub fn preload_from_assistant(
         &mut self,
         assistant: String,
@@ -190,25 +231,59 @@ impl PromptInstruction {
 }
 
 struct SystemPrompt {
-    instruction: String,
+    base_instruction: String,
+    instruction: String, // base instruction + optional language instruction
+    language: Option<Language>,
     token_length: Option<usize>,
 }
 
 impl SystemPrompt {
     pub fn default() -> Self {
         SystemPrompt {
+            base_instruction: "".to_string(),
             instruction: "".to_string(),
+            language: None,
             token_length: Some(0),
         }
     }
 
     fn new(instruction: String) -> Self {
         SystemPrompt {
+            base_instruction: instruction.clone(),
             instruction,
+            language: None,
             token_length: None,
         }
     }
 
+    fn get_language(&self) -> Option<Language> {
+        self.language
+    }
+
+    fn set_language(&mut self, language: Option<Language>) {
+        self.language = language;
+        self.instruction = match language {
+            Some(language) => {
+                let language_instruction = format!(
+                    "Always respond in {}, unless asked otherwise.",
+                    language.name()
+                );
+                if self.base_instruction.is_empty() {
+                    language_instruction
+                } else {
+                    format!(
+                        "{} {}",
+                        self.base_instruction.trim_end(),
+                        language_instruction
+                    )
+                }
+            }
+            None => self.base_instruction.clone(),
+        };
+        // token length must be re-computed for the new instruction
+        self.token_length = None;
+    }
+
     fn get_instruction(&self) -> &str {
         &self.instruction
     }
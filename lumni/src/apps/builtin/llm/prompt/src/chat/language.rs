@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+
+// minimum number of stopword hits required before a latin-script language
+// is considered detected, short prompts are too ambiguous otherwise
+const MIN_STOPWORD_HITS: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Language {
+    English,
+    Dutch,
+    German,
+    French,
+    Spanish,
+    Italian,
+    Portuguese,
+    Russian,
+    Ukrainian,
+    Greek,
+    Arabic,
+    Hebrew,
+    Hindi,
+    Thai,
+    Chinese,
+    Japanese,
+    Korean,
+}
+
+impl Language {
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.to_lowercase().as_str() {
+            "en" => Some(Language::English),
+            "nl" => Some(Language::Dutch),
+            "de" => Some(Language::German),
+            "fr" => Some(Language::French),
+            "es" => Some(Language::Spanish),
+            "it" => Some(Language::Italian),
+            "pt" => Some(Language::Portuguese),
+            "ru" => Some(Language::Russian),
+            "uk" => Some(Language::Ukrainian),
+            "el" => Some(Language::Greek),
+            "ar" => Some(Language::Arabic),
+            "he" => Some(Language::Hebrew),
+            "hi" => Some(Language::Hindi),
+            "th" => Some(Language::Thai),
+            "zh" => Some(Language::Chinese),
+            "ja" => Some(Language::Japanese),
+            "ko" => Some(Language::Korean),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Dutch => "nl",
+            Language::German => "de",
+            Language::French => "fr",
+            Language::Spanish => "es",
+            Language::Italian => "it",
+            Language::Portuguese => "pt",
+            Language::Russian => "ru",
+            Language::Ukrainian => "uk",
+            Language::Greek => "el",
+            Language::Arabic => "ar",
+            Language::Hebrew => "he",
+            Language::Hindi => "hi",
+            Language::Thai => "th",
+            Language::Chinese => "zh",
+            Language::Japanese => "ja",
+            Language::Korean => "ko",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Dutch => "Dutch",
+            Language::German => "German",
+            Language::French => "French",
+            Language::Spanish => "Spanish",
+            Language::Italian => "Italian",
+            Language::Portuguese => "Portuguese",
+            Language::Russian => "Russian",
+            Language::Ukrainian => "Ukrainian",
+            Language::Greek => "Greek",
+            Language::Arabic => "Arabic",
+            Language::Hebrew => "Hebrew",
+            Language::Hindi => "Hindi",
+            Language::Thai => "Thai",
+            Language::Chinese => "Chinese",
+            Language::Japanese => "Japanese",
+            Language::Korean => "Korean",
+        }
+    }
+
+    fn stopwords(&self) -> &'static [&'static str] {
+        match self {
+            Language::English => &[
+                "the", "and", "is", "are", "of", "to", "in", "that", "it",
+                "what", "how", "with", "for", "this", "you", "can", "do",
+            ],
+            Language::Dutch => &[
+                "de", "het", "een", "en", "is", "van", "ik", "niet", "dat",
+                "wat", "hoe", "met", "voor", "zijn", "je", "kan", "ook",
+            ],
+            Language::German => &[
+                "der", "die", "das", "und", "ist", "nicht", "ich", "ein",
+                "eine", "wie", "was", "mit", "für", "sie", "zu", "auch",
+            ],
+            Language::French => &[
+                "le", "la", "les", "et", "est", "un", "une", "des", "je",
+                "pas", "que", "comment", "pour", "avec", "vous", "dans",
+            ],
+            Language::Spanish => &[
+                "el", "la", "los", "las", "y", "es", "un", "una", "que", "no",
+                "cómo", "por", "para", "con", "del", "qué",
+            ],
+            Language::Italian => &[
+                "il", "lo", "la", "gli", "e", "è", "un", "una", "che", "non",
+                "come", "per", "con", "di", "sono", "questo",
+            ],
+            Language::Portuguese => &[
+                "o", "os", "as", "e", "é", "um", "uma", "que", "não", "como",
+                "para", "com", "do", "da", "você", "isso",
+            ],
+            _ => &[],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LanguagePreference {
+    Off,             // do not add a language instruction
+    Auto,            // detect from each prompt
+    Fixed(Language), // always respond in the given language
+}
+
+impl LanguagePreference {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "off" | "none" => Some(LanguagePreference::Off),
+            "auto" => Some(LanguagePreference::Auto),
+            code => Language::from_code(code).map(LanguagePreference::Fixed),
+        }
+    }
+}
+
+pub fn detect_language(text: &str) -> Option<Language> {
+    if let Some(language) = detect_by_script(text) {
+        return Some(language);
+    }
+    detect_by_stopwords(text)
+}
+
+fn detect_by_script(text: &str) -> Option<Language> {
+    let mut counts: HashMap<&'static str, usize> = HashMap::new();
+    let mut letters = 0;
+
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        letters += 1;
+        let script = match c as u32 {
+            0x0370..=0x03FF => "greek",
+            0x0400..=0x04FF => "cyrillic",
+            0x0590..=0x05FF => "hebrew",
+            0x0600..=0x06FF => "arabic",
+            0x0900..=0x097F => "devanagari",
+            0x0E00..=0x0E7F => "thai",
+            0x3040..=0x30FF => "kana",
+            0x4E00..=0x9FFF => "han",
+            0xAC00..=0xD7AF | 0x1100..=0x11FF => "hangul",
+            _ => continue,
+        };
+        *counts.entry(script).or_insert(0) += 1;
+    }
+
+    // kana is a stronger signal than han, as japanese text mixes both
+    if counts.contains_key("kana") {
+        return Some(Language::Japanese);
+    }
+
+    let (script, count) = counts.into_iter().max_by_key(|(_, n)| *n)?;
+    // script must cover a significant part of the text
+    if count * 2 < letters {
+        return None;
+    }
+    match script {
+        "greek" => Some(Language::Greek),
+        "cyrillic" => {
+            if text.chars().any(|c| matches!(c, 'і' | 'ї' | 'є' | 'ґ')) {
+                Some(Language::Ukrainian)
+            } else {
+                Some(Language::Russian)
+            }
+        }
+        "hebrew" => Some(Language::Hebrew),
+        "arabic" => Some(Language::Arabic),
+        "devanagari" => Some(Language::Hindi),
+        "thai" => Some(Language::Thai),
+        "han" => Some(Language::Chinese),
+        "hangul" => Some(Language::Korean),
+        _ => None,
+    }
+}
+
+fn detect_by_stopwords(text: &str) -> Option<Language> {
+    const CANDIDATES: [Language; 7] = [
+        Language::English,
+        Language::Dutch,
+        Language::German,
+        Language::French,
+        Language::Spanish,
+        Language::Italian,
+        Language::Portuguese,
+    ];
+
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect();
+
+    let mut best: Option<(Language, usize)> = None;
+    let mut tie = false;
+    for language in CANDIDATES {
+        let stopwords = language.stopwords();
+        let hits = words
+            .iter()
+            .filter(|w| stopwords.contains(&w.as_str()))
+            .count();
+        match best {
+            Some((_, best_hits)) if hits == best_hits => tie = true,
+            Some((_, best_hits)) if hits < best_hits => {}
+            _ => {
+                best = Some((language, hits));
+                tie = false;
+            }
+        }
+    }
+
+    match best {
+        Some((language, hits)) if hits >= MIN_STOPWORD_HITS && !tie => {
+            Some(language)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_latin_languages() {
+        assert_eq!(
+            detect_language("What is the best way to learn Rust?"),
+            Some(Language::English)
+        );
+        assert_eq!(
+            detect_language("Hoe kan ik het beste een bestand lezen?"),
+            Some(Language::Dutch)
+        );
+        assert_eq!(
+            detect_language("Wie ist das Wetter und was ist neu?"),
+            Some(Language::German)
+        );
+    }
+
+    #[test]
+    fn test_detect_by_script() {
+        assert_eq!(
+            detect_language("Привет, как дела?"),
+            Some(Language::Russian)
+        );
+        assert_eq!(detect_language("こんにちは世界"), Some(Language::Japanese));
+        assert_eq!(detect_language("你好世界"), Some(Language::Chinese));
+        assert_eq!(detect_language("안녕하세요"), Some(Language::Korean));
+    }
+
+    #[test]
+    fn test_ambiguous_input() {
+        assert_eq!(detect_language("ok"), None);
+        assert_eq!(detect_language("1234"), None);
+    }
+
+    #[test]
+    fn test_language_preference() {
+        assert_eq!(
+            LanguagePreference::from_str("auto"),
+            Some(LanguagePreference::Auto)
+        );
+        assert_eq!(
+            LanguagePreference::from_str("NL"),
+            Some(LanguagePreference::Fixed(Language::Dutch))
+        );
+        assert_eq!(LanguagePreference::from_str("xx"), None);
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+
+use lumni::api::error::ApplicationError;
+use regex::Regex;
+
+pub use crate::external as lumni;
+use crate::{
+    EnvironmentConfig, FileObjectFilter, ObjectStoreHandler, ParsedUri,
+    TableExportOptions,
+};
+
+pub const LIST_COMMAND: &str = "/ls";
+// rows put in the conversation, more would mostly fill the context
+const MAX_LISTED_ROWS: usize = 100;
+
+// objects under a uri as a markdown table, to ask questions about, e.g.
+// "/ls s3://bucket/logs -r --name .*\.gz --size +1M"
+#[derive(Debug, PartialEq)]
+pub struct ObjectListing {
+    uri: String,
+    recursive: bool,
+    name: Option<String>,
+    size: Option<String>,
+    mtime: Option<String>,
+}
+
+impl ObjectListing {
+    // None if the prompt is not a list command
+    pub fn from_prompt(
+        prompt: &str,
+    ) -> Option<Result<ObjectListing, ApplicationError>> {
+        let args = prompt.trim().strip_prefix(LIST_COMMAND)?;
+        if !args.is_empty() && !args.starts_with(char::is_whitespace) {
+            return None;
+        }
+        Some(ObjectListing::parse(args))
+    }
+
+    fn parse(args: &str) -> Result<ObjectListing, ApplicationError> {
+        let usage = || {
+            ApplicationError::InvalidUserConfiguration(format!(
+                "Usage: {} <uri> [-r] [--name <regex>] [--size <size>] \
+                 [--mtime <mtime>]",
+                LIST_COMMAND
+            ))
+        };
+        let mut listing = ObjectListing {
+            uri: String::new(),
+            recursive: false,
+            name: None,
+            size: None,
+            mtime: None,
+        };
+        let mut args = args.split_whitespace();
+        while let Some(arg) = args.next() {
+            let value = match arg {
+                "-r" | "--recursive" => {
+                    listing.recursive = true;
+                    continue;
+                }
+                "-n" | "--name" => &mut listing.name,
+                "-s" | "--size" => &mut listing.size,
+                "-t" | "--mtime" => &mut listing.mtime,
+                uri if listing.uri.is_empty() && !uri.starts_with('-') => {
+                    listing.uri = uri.to_string();
+                    continue;
+                }
+                _ => return Err(usage()),
+            };
+            *value = Some(args.next().ok_or_else(usage)?.to_string());
+        }
+        if listing.uri.is_empty() {
+            return Err(usage());
+        }
+        Ok(listing)
+    }
+
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    // the table, with a note when there are more rows than listed
+    pub async fn to_markdown(&self) -> Result<String, ApplicationError> {
+        let filter = self.filter()?;
+        let uri = match self.uri.contains("://") {
+            true => self.uri.clone(),
+            false => format!("localfs://{}", self.uri),
+        };
+        let config = EnvironmentConfig::new(HashMap::new());
+        let table = ObjectStoreHandler::new(None)
+            .list_objects(
+                &ParsedUri::from_uri(&uri, true),
+                &config,
+                None,
+                self.recursive,
+                // one more, to know if the listing is truncated
+                Some(MAX_LISTED_ROWS as u32 + 1),
+                &filter,
+                None,
+            )
+            .await
+            .map_err(|e| ApplicationError::Unexpected(e.to_string()))?;
+        if table.len() == 0 {
+            return Ok(format!("No objects found in {}", self.uri));
+        }
+        let options =
+            TableExportOptions::new().set_max_rows(Some(MAX_LISTED_ROWS));
+        let mut markdown = table
+            .to_markdown(&options)
+            .map_err(ApplicationError::Unexpected)?;
+        if table.len() > MAX_LISTED_ROWS {
+            markdown.push_str(&format!(
+                "\n(only the first {} objects are listed)\n",
+                MAX_LISTED_ROWS
+            ));
+        }
+        Ok(format!("Objects in {}:\n\n{}", self.uri, markdown))
+    }
+
+    fn filter(&self) -> Result<Option<FileObjectFilter>, ApplicationError> {
+        if self.name.is_none() && self.size.is_none() && self.mtime.is_none() {
+            return Ok(None);
+        }
+        // an invalid pattern would panic in the filter
+        if let Some(name) = &self.name {
+            Regex::new(name).map_err(|e| {
+                ApplicationError::InvalidUserConfiguration(e.to_string())
+            })?;
+        }
+        FileObjectFilter::new(
+            self.name.as_deref(),
+            self.size.as_deref(),
+            self.mtime.as_deref(),
+        )
+        .map(Some)
+        .map_err(ApplicationError::InvalidUserConfiguration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_object_listing() {
+        assert!(ObjectListing::from_prompt("/lsx .").is_none());
+        assert!(ObjectListing::from_prompt("what is /ls").is_none());
+        assert!(ObjectListing::from_prompt("/ls").unwrap().is_err());
+        assert!(ObjectListing::from_prompt("/ls . --name").unwrap().is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.csv"), "a,b").unwrap();
+        fs::write(dir.path().join("b.txt"), "text").unwrap();
+        let prompt = format!("/ls {} --name .*\\.csv", dir.path().display());
+        let listing = ObjectListing::from_prompt(&prompt).unwrap().unwrap();
+        let markdown = listing.to_markdown().await.unwrap();
+        assert!(markdown.contains("a.csv | 3 |"));
+        assert!(!markdown.contains("b.txt"));
+    }
+}
@@ -1,25 +1,59 @@
+use std::collections::hash_map::DefaultHasher;
+use std::env;
 use std::error::Error;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
 
+mod email;
+mod eval;
 mod exchange;
+mod finetune;
 mod history;
+mod inspector;
 mod instruction;
+mod language;
+mod listing;
 mod options;
+mod pool;
+mod profiles;
 mod prompt;
+mod query_assistant;
+mod schema;
 mod send;
 mod session;
+mod snippets;
+mod trash;
+mod vault;
+mod webhook;
 
-pub use exchange::ChatExchange;
+pub use email::EmailExporter;
+pub use eval::{EvalHistory, EvalRunner, EvalSuite, GRADER_INSTRUCTION};
+pub use exchange::{conversation_stats, ChatExchange, Vote};
+pub use finetune::{CommandRedactor, FinetuneExporter, PatternRedactor};
 pub use history::{ChatHistory, ChatMessage};
+pub use inspector::RawExchange;
 pub use instruction::PromptInstruction;
+pub use language::{detect_language, Language, LanguagePreference};
+pub use listing::ObjectListing;
 pub use options::{ChatCompletionOptions, PromptOptions};
+pub use pool::{SessionFactory, SessionPool};
+pub use profiles::{profile_schema, ProfileStore, PromptProfile};
 use prompt::Prompt;
+pub use query_assistant::{QueryAssistant, QueryCommand, SQL_COMMAND};
 pub use send::{http_get_with_response, http_post, http_post_with_response};
 use serde::Deserialize;
 pub use session::ChatSession;
+pub use snippets::{Snippet, SnippetLibrary, SNIPPET_PREFIX};
+pub use trash::parse_retention;
+pub use vault::VaultExporter;
+pub use webhook::WebhookDispatcher;
 
 pub use super::defaults::*;
 pub use super::model::PromptRole;
-pub use super::server::{LLMDefinition, ServerManager};
+use super::server::ModelServer;
+pub use super::server::{LLMDefinition, ServerCapabilities, ServerManager};
+use crate::external::api::error::ApplicationError;
 
 // gets PERSONAS from the generated code
 include!(concat!(env!("OUT_DIR"), "/llm/prompt/templates.rs"));
@@ -33,6 +67,41 @@ pub fn list_assistants() -> Result<Vec<String>, Box<dyn Error>> {
     Ok(assistants)
 }
 
+// file in the prompt config directory, e.g. ~/.config/lumni/prompt/
+fn config_file(file_name: &str) -> Option<PathBuf> {
+    let config_dir = match env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var("HOME").ok()?).join(".config"),
+    };
+    Some(config_dir.join("lumni").join("prompt").join(file_name))
+}
+
+// contents of a config file, None if there is no file yet
+fn read_if_exists(path: &Path) -> Result<Option<String>, ApplicationError> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(ApplicationError::IoError(e)),
+    }
+}
+
+// version of a config file as read, to tell whether another lumni
+// instance changed it before it is saved
+fn hash_contents(contents: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+// file in the prompt data directory, e.g. ~/.local/share/lumni/prompt/
+fn data_file(file_name: &str) -> Option<PathBuf> {
+    let data_dir = match env::var("XDG_DATA_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var("HOME").ok()?).join(".local/share"),
+    };
+    Some(data_dir.join("lumni").join("prompt").join(file_name))
+}
+
 #[derive(Deserialize)]
 pub struct TokenResponse {
     tokens: Vec<usize>,
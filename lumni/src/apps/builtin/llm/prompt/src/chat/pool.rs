@@ -0,0 +1,186 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::FutureExt;
+use tokio::task::JoinHandle;
+
+use super::email::EmailExporter;
+use super::vault::VaultExporter;
+use super::webhook::WebhookDispatcher;
+use super::{
+    ChatSession, LLMDefinition, LanguagePreference, ModelServer,
+    PromptInstruction,
+};
+use crate::api::error::ApplicationError;
+
+const RETRY_AFTER_SECONDS: u64 = 10;
+
+// everything needed to create a ChatSession with the settings given
+// on the command line
+#[derive(Clone)]
+pub struct SessionFactory {
+    server_name: String,
+    instruction: Option<String>,
+    assistant: Option<String>,
+    options: Option<String>,
+    model: Option<LLMDefinition>,
+    language_preference: LanguagePreference,
+    webhooks: WebhookDispatcher,
+    email: Option<EmailExporter>,
+    vault: Option<VaultExporter>,
+}
+
+impl SessionFactory {
+    pub fn new(
+        server_name: String,
+        instruction: Option<String>,
+        assistant: Option<String>,
+        options: Option<String>,
+        model: Option<LLMDefinition>,
+    ) -> Self {
+        SessionFactory {
+            server_name,
+            instruction,
+            assistant,
+            options,
+            model,
+            language_preference: LanguagePreference::Off,
+            webhooks: WebhookDispatcher::default(),
+            email: None,
+            vault: None,
+        }
+    }
+
+    pub fn set_language_preference(
+        mut self,
+        preference: LanguagePreference,
+    ) -> Self {
+        self.language_preference = preference;
+        self
+    }
+
+    pub fn set_webhooks(mut self, webhooks: WebhookDispatcher) -> Self {
+        self.webhooks = webhooks;
+        self
+    }
+
+    pub fn set_email_exporter(mut self, email: Option<EmailExporter>) -> Self {
+        self.email = email;
+        self
+    }
+
+    pub fn set_vault_exporter(mut self, vault: Option<VaultExporter>) -> Self {
+        self.vault = vault;
+        self
+    }
+
+    pub async fn create(&self) -> Result<ChatSession, ApplicationError> {
+        let server = ModelServer::from_str(&self.server_name)?;
+        let prompt_instruction = PromptInstruction::new(
+            self.instruction.clone(),
+            self.assistant.clone(),
+            self.options.as_ref(),
+        )?;
+        let mut chat_session = ChatSession::new(
+            Box::new(server),
+            prompt_instruction,
+            self.model.clone(),
+        )
+        .await?;
+        chat_session.set_language_preference(self.language_preference);
+        chat_session.set_webhooks(self.webhooks.clone());
+        chat_session.set_email_exporter(self.email.clone());
+        chat_session.set_vault_exporter(self.vault.clone());
+        Ok(chat_session)
+    }
+}
+
+// keeps sessions initialized in the background (model loaded, system
+// prompt sent, HTTP connection open), so a new conversation can start
+// without waiting for the server
+pub struct SessionPool {
+    factory: Arc<SessionFactory>,
+    size: usize,
+    idle_ttl: Duration,
+    standby: VecDeque<(ChatSession, Instant)>,
+    pending: Vec<JoinHandle<Result<ChatSession, ApplicationError>>>,
+    failed_at: Option<Instant>,
+}
+
+impl SessionPool {
+    pub fn new(
+        factory: SessionFactory,
+        size: usize,
+        idle_ttl: Duration,
+    ) -> Self {
+        SessionPool {
+            factory: Arc::new(factory),
+            size,
+            idle_ttl,
+            standby: VecDeque::new(),
+            pending: Vec::new(),
+            failed_at: None,
+        }
+    }
+
+    // collects sessions that finished initializing, drops the ones idle
+    // longer than the TTL and starts new ones up to the pool size.
+    // does not block, cheap enough to call on every tick
+    pub fn maintain(&mut self) {
+        let mut pending = Vec::new();
+        for handle in self.pending.drain(..) {
+            if !handle.is_finished() {
+                pending.push(handle);
+                continue;
+            }
+            match handle.now_or_never() {
+                Some(Ok(Ok(session))) => {
+                    self.standby.push_back((session, Instant::now()))
+                }
+                Some(Ok(Err(e))) => {
+                    log::warn!("Failed to initialize standby session: {}", e);
+                    self.failed_at = Some(Instant::now());
+                }
+                Some(Err(e)) => {
+                    log::error!("Standby session task failed: {}", e);
+                    self.failed_at = Some(Instant::now());
+                }
+                None => {}
+            }
+        }
+        self.pending = pending;
+
+        let idle_ttl = self.idle_ttl;
+        self.standby
+            .retain(|(_, created)| created.elapsed() < idle_ttl);
+
+        // do not keep hammering a server that is down
+        if let Some(failed_at) = self.failed_at {
+            if failed_at.elapsed() < Duration::from_secs(RETRY_AFTER_SECONDS) {
+                return;
+            }
+            self.failed_at = None;
+        }
+        while self.standby.len() + self.pending.len() < self.size {
+            let factory = Arc::clone(&self.factory);
+            self.pending
+                .push(tokio::spawn(async move { factory.create().await }));
+        }
+    }
+
+    // a warm session if one is ready, otherwise a newly created one
+    pub async fn take(&mut self) -> Result<ChatSession, ApplicationError> {
+        self.maintain();
+        let session = match self.standby.pop_front() {
+            Some((session, _)) => session,
+            None => {
+                log::debug!("No standby session ready, creating one");
+                self.factory.create().await?
+            }
+        };
+        // start a replacement right away
+        self.maintain();
+        Ok(session)
+    }
+}
@@ -0,0 +1,628 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use lumni::api::error::ApplicationError;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use super::schema::{schema_errors, SchemaError};
+use super::webhook::Webhook;
+use super::ModelServer;
+use super::{config_file, hash_contents, read_if_exists};
+use crate::base::encryption::write_private_file;
+pub use crate::external as lumni;
+
+// parts of environment variable names that hold credentials
+const SECRET_MARKERS: [&str; 4] = ["KEY", "SECRET", "TOKEN", "PASSWORD"];
+const SECRET_MASK: &str = "********";
+
+// settings a session starts with, selected with --profile. options are
+// the model options as given with --options, env is set for the server
+// e.g. OPENAI_API_KEY. webhooks are notified of events of the session
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PromptProfile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assistant: Option<String>,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub options: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<Webhook>,
+}
+
+impl PromptProfile {
+    // as accepted by --options
+    pub fn options_json(&self) -> Option<String> {
+        match self.options.is_empty() {
+            true => None,
+            false => Some(Value::Object(self.options.clone()).to_string()),
+        }
+    }
+
+    pub fn without_secrets(&self) -> Self {
+        let mut profile = self.clone();
+        profile.env.retain(|name, _| !is_secret(name));
+        profile.webhooks.iter_mut().for_each(Webhook::remove_secret);
+        profile
+    }
+
+    // secrets replaced, e.g. to print a profile
+    pub fn masked(&self) -> Self {
+        let mut profile = self.clone();
+        for (name, value) in profile.env.iter_mut() {
+            if is_secret(name) && !value.is_empty() {
+                *value = SECRET_MASK.to_string();
+            }
+        }
+        for webhook in profile.webhooks.iter_mut() {
+            webhook.mask_secret(SECRET_MASK);
+        }
+        profile
+    }
+
+    // environment variables that hold credentials, e.g. OPENAI_API_KEY
+    pub fn is_secret_name(name: &str) -> bool {
+        is_secret(name)
+    }
+
+    // "model=x", "options.temperature=0.2", "env.OPENAI_API_KEY=..." or
+    // "webhooks.0.url=...". option and webhook values are JSON, or else a
+    // string
+    pub fn set(
+        &mut self,
+        key: &str,
+        value: &str,
+    ) -> Result<(), ApplicationError> {
+        let text = || Some(value.to_string());
+        match key {
+            "server" => self.server = text(),
+            "model" => self.model = text(),
+            "system" => self.system = text(),
+            "assistant" => self.assistant = text(),
+            _ => {
+                if let Some(option) = key.strip_prefix("options.") {
+                    let value = serde_json::from_str(value)
+                        .unwrap_or_else(|_| Value::String(value.to_string()));
+                    self.set_option(option, value)?;
+                } else if let Some(name) = key.strip_prefix("env.") {
+                    self.env.insert(name.to_string(), value.to_string());
+                } else if let Some(path) = key.strip_prefix("webhooks.") {
+                    let value = serde_json::from_str(value)
+                        .unwrap_or_else(|_| Value::String(value.to_string()));
+                    self.set_webhook(path, value)?;
+                } else {
+                    return Err(ApplicationError::InvalidUserConfiguration(
+                        format!(
+                            "Unknown profile setting {}, expected server, \
+                             model, system, assistant, options.<name>, \
+                             env.<name> or webhooks.<index>",
+                            key
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // "stop.1" is the second item of the stop option, "role_prefix.user"
+    // a field of an object option. missing objects are created on the way
+    fn set_option(
+        &mut self,
+        path: &str,
+        value: Value,
+    ) -> Result<(), ApplicationError> {
+        let segments: Vec<&str> = path.split('.').collect();
+        let mut options = Value::Object(std::mem::take(&mut self.options));
+        let result = set_path(&mut options, &segments, value);
+        if let Value::Object(options) = options {
+            self.options = options;
+        }
+        result.map_err(|e| {
+            ApplicationError::InvalidUserConfiguration(format!(
+                "Can not set options.{}: {}",
+                path, e
+            ))
+        })
+    }
+
+    // "0.url" is the url of the first webhook, "1" a whole webhook as JSON.
+    // the webhooks must be complete after the change
+    fn set_webhook(
+        &mut self,
+        path: &str,
+        value: Value,
+    ) -> Result<(), ApplicationError> {
+        let invalid = |e: String| {
+            ApplicationError::InvalidUserConfiguration(format!(
+                "Can not set webhooks.{}: {}",
+                path, e
+            ))
+        };
+        let mut webhooks = serde_json::to_value(&self.webhooks)
+            .map_err(|e| ApplicationError::Unexpected(e.to_string()))?;
+        let segments: Vec<&str> = path.split('.').collect();
+        set_path(&mut webhooks, &segments, value).map_err(invalid)?;
+        self.webhooks = serde_json::from_value(webhooks)
+            .map_err(|e| invalid(e.to_string()))?;
+        Ok(())
+    }
+
+    // a new profile as the schema of a server describes it, with the
+    // required settings and those that have a default
+    pub fn for_server(server: Option<&str>) -> Result<Self, ApplicationError> {
+        let profile = PromptProfile {
+            server: server.map(String::from),
+            ..Default::default()
+        };
+        serde_json::from_value(template(&profile.schema()?)).map_err(|e| {
+            ApplicationError::Unexpected(format!("Invalid schema: {}", e))
+        })
+    }
+
+    // the schema published by the server of the profile
+    pub fn schema(&self) -> Result<Value, ApplicationError> {
+        match &self.server {
+            Some(server) => ModelServer::settings_schema(server),
+            None => Ok(profile_schema(None, json!({}))),
+        }
+    }
+
+    pub fn schema_errors(&self) -> Result<Vec<SchemaError>, ApplicationError> {
+        let value = serde_json::to_value(self)
+            .map_err(|e| ApplicationError::Unexpected(e.to_string()))?;
+        Ok(schema_errors(&value, &self.schema()?, "profile"))
+    }
+
+    pub fn validate(&self) -> Result<(), ApplicationError> {
+        let errors = self.schema_errors()?;
+        if errors.is_empty() {
+            return Ok(());
+        }
+        let messages: Vec<String> =
+            errors.into_iter().map(|e| e.message).collect();
+        Err(ApplicationError::InvalidUserConfiguration(
+            messages.join(", "),
+        ))
+    }
+
+    // before the server is created, as servers read their settings from
+    // the environment. an empty value, e.g. of a new profile, leaves the
+    // environment as it is
+    pub fn apply_env(&self) {
+        for (name, value) in &self.env {
+            if !value.is_empty() {
+                std::env::set_var(name, value);
+            }
+        }
+    }
+}
+
+fn set_path(
+    target: &mut Value,
+    segments: &[&str],
+    value: Value,
+) -> Result<(), String> {
+    let Some((segment, rest)) = segments.split_first() else {
+        *target = value;
+        return Ok(());
+    };
+    if target.is_null() {
+        *target = Value::Object(Map::new());
+    }
+    let child = match target {
+        Value::Object(map) => {
+            map.entry(segment.to_string()).or_insert(Value::Null)
+        }
+        Value::Array(items) => {
+            let index = segment
+                .parse::<usize>()
+                .map_err(|_| format!("{} is not an index", segment))?;
+            // one past the end appends
+            if index == items.len() {
+                items.push(Value::Null);
+            }
+            items
+                .get_mut(index)
+                .ok_or_else(|| format!("no item {}", index))?
+        }
+        _ => return Err(format!("{} is not in an object or array", segment)),
+    };
+    set_path(child, rest, value)
+}
+
+// values of the required properties and of those with a default
+fn template(schema: &Value) -> Value {
+    if let Some(default) = schema.get("default") {
+        if !default.is_object() {
+            return default.clone();
+        }
+    }
+    match schema.get("type").and_then(Value::as_str) {
+        Some("object") => {
+            let mut object = match schema.get("default") {
+                Some(Value::Object(default)) => default.clone(),
+                _ => Map::new(),
+            };
+            let required: Vec<&str> = schema
+                .get("required")
+                .and_then(Value::as_array)
+                .map(|keys| keys.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_default();
+            let properties =
+                schema.get("properties").and_then(Value::as_object);
+            for (key, property) in properties.into_iter().flatten() {
+                if required.contains(&key.as_str())
+                    || property.get("default").is_some()
+                {
+                    object.insert(key.clone(), template(property));
+                }
+            }
+            Value::Object(object)
+        }
+        Some("array") => json!([]),
+        Some("string") => json!(""),
+        Some("number") | Some("integer") => json!(0),
+        Some("boolean") => json!(false),
+        _ => Value::Null,
+    }
+}
+
+// the settings of a profile for a server, which describes the environment
+// variables it reads. without a server, any server is accepted
+pub fn profile_schema(server: Option<&str>, env: Value) -> Value {
+    let server = match server {
+        Some(server) => json!({
+            "type": "string",
+            "enum": [server],
+            "default": server
+        }),
+        None => json!({ "type": "string" }),
+    };
+    json!({
+        "type": "object",
+        "properties": {
+            "server": server,
+            "model": { "type": "string" },
+            "system": { "type": "string" },
+            "assistant": { "type": "string" },
+            "options": options_schema(),
+            "env": {
+                "type": "object",
+                "properties": env,
+                "default": {}
+            },
+            "webhooks": webhooks_schema()
+        }
+    })
+}
+
+// see Webhook
+fn webhooks_schema() -> Value {
+    json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "required": ["url", "events"],
+            "properties": {
+                "name": { "type": "string" },
+                "url": { "type": "string" },
+                "events": {
+                    "type": "array",
+                    "items": {
+                        "type": "string",
+                        "enum": ["completion_finished", "notify"]
+                    }
+                },
+                "format": {
+                    "type": "string",
+                    "enum": ["json", "slack", "discord"]
+                },
+                "template": { "type": "string" },
+                "secret": { "type": "string" }
+            }
+        }
+    })
+}
+
+// types of the options the servers know, see ChatCompletionOptions and
+// PromptOptions. other options are passed on unchecked
+fn options_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "temperature": { "type": "number" },
+            "top_k": { "type": "integer" },
+            "top_p": { "type": "number" },
+            "n_keep": { "type": "integer" },
+            "n_predict": { "type": "integer" },
+            "cache_prompt": { "type": "boolean" },
+            "stop": { "type": "array", "items": { "type": "string" } },
+            "stream": { "type": "boolean" },
+            "n_ctx": { "type": "integer" },
+            "role_prefix": {
+                "type": "object",
+                "properties": {
+                    "user": { "type": "string" },
+                    "assistant": { "type": "string" },
+                    "system": { "type": "string" }
+                }
+            }
+        }
+    })
+}
+
+fn no_profile(name: &str) -> ApplicationError {
+    ApplicationError::InvalidUserConfiguration(format!("No profile {}", name))
+}
+
+fn is_secret(name: &str) -> bool {
+    let name = name.to_uppercase();
+    SECRET_MARKERS.iter().any(|marker| name.contains(marker))
+}
+
+// profiles.yaml in the prompt config directory, profiles by name
+#[derive(Debug, Default)]
+pub struct ProfileStore {
+    profiles: BTreeMap<String, PromptProfile>,
+    path: Option<PathBuf>,
+    // hash of the file as loaded or last saved, None if there was no file
+    version: Option<u64>,
+}
+
+impl ProfileStore {
+    pub fn from_config_file() -> Result<Self, ApplicationError> {
+        Self::from_file(config_file("profiles.yaml"))
+    }
+
+    fn from_file(path: Option<PathBuf>) -> Result<Self, ApplicationError> {
+        let contents = match &path {
+            Some(path) => read_if_exists(path)?,
+            None => None,
+        };
+        let profiles = match (&path, &contents) {
+            (Some(path), Some(contents)) => serde_yaml::from_str(contents)
+                .map_err(|e| {
+                    ApplicationError::InvalidUserConfiguration(format!(
+                        "Invalid profiles in {}: {}",
+                        path.display(),
+                        e
+                    ))
+                })?,
+            _ => BTreeMap::new(),
+        };
+        Ok(ProfileStore {
+            profiles,
+            path,
+            version: contents.as_deref().map(hash_contents),
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Result<&PromptProfile, ApplicationError> {
+        self.profiles.get(name).ok_or_else(|| no_profile(name))
+    }
+
+    pub fn get_mut(
+        &mut self,
+        name: &str,
+    ) -> Result<&mut PromptProfile, ApplicationError> {
+        self.profiles.get_mut(name).ok_or_else(|| no_profile(name))
+    }
+
+    pub fn profiles(&self) -> &BTreeMap<String, PromptProfile> {
+        &self.profiles
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<(), ApplicationError> {
+        self.profiles
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| no_profile(name))
+    }
+
+    // a variant of a profile, e.g. the same server with another model.
+    // secrets are left out unless included, to be set for the copy
+    pub fn duplicate(
+        &mut self,
+        source: &str,
+        target: &str,
+        include_secrets: bool,
+    ) -> Result<&mut PromptProfile, ApplicationError> {
+        if self.profiles.contains_key(target) {
+            return Err(ApplicationError::InvalidUserConfiguration(format!(
+                "Profile {} already exists",
+                target
+            )));
+        }
+        let profile = self.get(source)?;
+        let profile = match include_secrets {
+            true => profile.clone(),
+            false => profile.without_secrets(),
+        };
+        Ok(self.profiles.entry(target.to_string()).or_insert(profile))
+    }
+
+    pub fn insert(&mut self, name: &str, profile: PromptProfile) {
+        self.profiles.insert(name.to_string(), profile);
+    }
+
+    // profiles are checked against the schema of their server first. if
+    // another lumni instance changed the file since it was loaded, nothing
+    // is written and a Conflict is returned, the store is then to be
+    // loaded again to not overwrite the other change
+    pub fn save(&mut self) -> Result<(), ApplicationError> {
+        for (name, profile) in &self.profiles {
+            match profile.validate() {
+                Err(ApplicationError::InvalidUserConfiguration(e)) => {
+                    return Err(ApplicationError::InvalidUserConfiguration(
+                        format!("Profile {}: {}", name, e),
+                    ))
+                }
+                result => result?,
+            }
+        }
+        let path = self.path.as_ref().ok_or_else(|| {
+            ApplicationError::Unexpected("No config directory".to_string())
+        })?;
+        let current = read_if_exists(path)?;
+        if current.as_deref().map(hash_contents) != self.version {
+            return Err(ApplicationError::Conflict(format!(
+                "{} was changed by another lumni instance, load the \
+                 profiles again and repeat the change",
+                path.display()
+            )));
+        }
+        let contents = serde_yaml::to_string(&self.profiles).map_err(|e| {
+            ApplicationError::Unexpected(format!(
+                "Failed to save profiles: {}",
+                e
+            ))
+        })?;
+        // env holds secrets, e.g. OPENAI_API_KEY
+        write_private_file(path, contents.as_bytes())?;
+        self.version = Some(hash_contents(&contents));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicate_profile() {
+        let mut store = ProfileStore::default();
+        let mut profile = PromptProfile::default();
+        profile.set("server", "openai").unwrap();
+        profile.set("options.temperature", "0.2").unwrap();
+        profile.set("env.OPENAI_API_KEY", "sk-test").unwrap();
+        profile
+            .set("env.OPENAI_BASE_URL", "http://localhost")
+            .unwrap();
+        store.profiles.insert("work".to_string(), profile);
+
+        let copy = store.duplicate("work", "creative", false).unwrap();
+        copy.set("options.temperature", "1.2").unwrap();
+        assert!(copy.set("temperature", "1.2").is_err());
+        assert_eq!(
+            copy.options_json().as_deref(),
+            Some("{\"temperature\":1.2}")
+        );
+        assert_eq!(copy.env.keys().collect::<Vec<_>>(), ["OPENAI_BASE_URL"]);
+        assert!(store.duplicate("work", "creative", true).is_err());
+        assert_eq!(store.get("work").unwrap().env.len(), 2);
+
+        let masked = store.get("work").unwrap().masked();
+        assert_eq!(masked.env["OPENAI_API_KEY"], SECRET_MASK);
+        assert_eq!(masked.env["OPENAI_BASE_URL"], "http://localhost");
+        store.remove("creative").unwrap();
+        assert!(store.remove("creative").is_err());
+    }
+
+    #[test]
+    fn test_concurrent_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = Some(dir.path().join("profiles.yaml"));
+        let mut first = ProfileStore::from_file(path.clone()).unwrap();
+        let mut second = ProfileStore::from_file(path.clone()).unwrap();
+        first.insert("a", PromptProfile::default());
+        first.save().unwrap();
+
+        // the second instance loaded the profiles before the first saved
+        second.insert("b", PromptProfile::default());
+        assert!(matches!(second.save(), Err(ApplicationError::Conflict(_))));
+        let mut second = ProfileStore::from_file(path.clone()).unwrap();
+        second.insert("b", PromptProfile::default());
+        second.save().unwrap();
+
+        // a store can be saved again after its own save
+        second.remove("a").unwrap();
+        second.save().unwrap();
+        let saved = ProfileStore::from_file(path).unwrap();
+        assert_eq!(saved.profiles().keys().collect::<Vec<_>>(), ["b"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_save_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("profiles.yaml");
+        // as written by an earlier version
+        std::fs::write(&path, "{}").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644))
+            .unwrap();
+        let mut store = ProfileStore::from_file(Some(path.clone())).unwrap();
+        let mut profile = PromptProfile::default();
+        profile.set("env.OPENAI_API_KEY", "sk-test").unwrap();
+        store.insert("work", profile);
+        store.save().unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_profile_webhooks() {
+        let mut profile = PromptProfile::default();
+        profile
+            .set(
+                "webhooks.0",
+                r#"{"url": "http://localhost/hook", "events": ["notify"]}"#,
+            )
+            .unwrap();
+        profile.set("webhooks.0.secret", "s3cret").unwrap();
+        profile.set("webhooks.0.format", "slack").unwrap();
+        // a webhook needs a url and events
+        assert!(profile.set("webhooks.1.name", "team-chat").is_err());
+        assert!(profile.set("webhooks.0.events.0", "unknown").is_err());
+        assert_eq!(profile.webhooks.len(), 1);
+        assert!(profile.validate().is_ok());
+
+        let masked = serde_json::to_value(profile.masked()).unwrap();
+        assert_eq!(masked["webhooks"][0]["secret"], SECRET_MASK);
+        assert_eq!(masked["webhooks"][0]["format"], "slack");
+        let copy = serde_json::to_value(profile.without_secrets()).unwrap();
+        assert!(copy["webhooks"][0].get("secret").is_none());
+    }
+
+    #[test]
+    fn test_nested_options() {
+        let mut profile = PromptProfile::default();
+        profile.set("options.stop", "[\"</s>\"]").unwrap();
+        profile.set("options.stop.1", "User:").unwrap();
+        profile.set("options.role_prefix.user", "### User").unwrap();
+        assert!(profile.set("options.stop.5", "x").is_err());
+        assert_eq!(
+            profile.options_json().as_deref(),
+            Some(
+                "{\"role_prefix\":{\"user\":\"### User\"},\
+                 \"stop\":[\"</s>\",\"User:\"]}"
+            )
+        );
+        assert!(profile.validate().is_ok());
+        profile.set("options.top_k", "0.5").unwrap();
+        profile.set("server", "openai").unwrap();
+        let errors = profile.schema_errors().unwrap();
+        assert_eq!(
+            errors
+                .iter()
+                .map(|e| e.pointer.as_str())
+                .collect::<Vec<_>>(),
+            ["/options/top_k"]
+        );
+        profile.set("server", "other").unwrap();
+        assert!(profile.validate().is_err());
+
+        let profile = PromptProfile::for_server(Some("openai")).unwrap();
+        assert_eq!(profile.server.as_deref(), Some("openai"));
+        assert_eq!(profile.env.keys().collect::<Vec<_>>(), ["OPENAI_API_KEY"]);
+    }
+}
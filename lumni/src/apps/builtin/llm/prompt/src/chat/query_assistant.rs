@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use lumni::api::error::ApplicationError;
+use sqlparser::ast::Statement;
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::Parser;
+
+pub use crate::external as lumni;
+use crate::{EnvironmentConfig, ObjectStoreHandler, TableExportOptions};
+
+pub const QUERY_COMMAND: &str = "/query";
+pub const SQL_COMMAND: &str = "/sql";
+// rows put in the summary prompt, more would mostly fill the context
+const MAX_RESULT_ROWS: usize = 100;
+
+// the columns of an object listing as the query engine knows them
+const QUERY_SCHEMA: &str = "\
+Objects are queried with SQL on a listing, one row per object:
+  name           TEXT     key of the object, relative to the uri
+  size           INTEGER  size in bytes
+  modified       INTEGER  last modified time, in seconds since the epoch
+  checksum       TEXT     checksum or ETag, NULL if unknown
+  storage_class  TEXT     e.g. STANDARD or GLACIER, NULL if unknown
+
+Syntax:
+  SELECT <columns> | * FROM '<uri>' [WHERE <condition>]
+  [ORDER BY <column> [ASC|DESC], ...] [LIMIT <n>] [OFFSET <n>]
+
+The uri includes a scheme, e.g. 's3://bucket/prefix/',
+'localfs:///home/user/data/' or 'gs://bucket/'. Conditions use =, <>, <,
+<=, >, >=, LIKE, IN (...), IS [NOT] NULL, AND, OR, NOT and numbers or
+'quoted' strings. There are no functions, joins, GROUP BY or aggregates.";
+
+// a prompt that starts with one of the query commands
+#[derive(Debug, PartialEq)]
+pub enum QueryCommand {
+    Translate(String), // a request in natural language
+    Execute(String),   // a query, e.g. as proposed by the model
+}
+
+impl QueryCommand {
+    // None if the prompt is not a query command
+    pub fn from_prompt(
+        prompt: &str,
+    ) -> Option<Result<QueryCommand, ApplicationError>> {
+        let prompt = prompt.trim();
+        let (command, args) = prompt
+            .split_once(char::is_whitespace)
+            .unwrap_or((prompt, ""));
+        let args = args.trim().to_string();
+        let usage = |arg: &str| {
+            Err(ApplicationError::InvalidUserConfiguration(format!(
+                "Usage: {} <{}>",
+                command, arg
+            )))
+        };
+        match command {
+            QUERY_COMMAND if args.is_empty() => Some(usage("request")),
+            SQL_COMMAND if args.is_empty() => Some(usage("query")),
+            QUERY_COMMAND => Some(Ok(QueryCommand::Translate(args))),
+            SQL_COMMAND => Some(Ok(QueryCommand::Execute(args))),
+            _ => None,
+        }
+    }
+}
+
+// translates requests in natural language into a query on an object store,
+// which is shown for confirmation before it runs. the results are then
+// summarized by the model
+#[derive(Debug, Default)]
+pub struct QueryAssistant {
+    request: Option<String>,
+    translating: bool, // the next answer should hold a query
+}
+
+impl QueryAssistant {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reset(&mut self) {
+        self.request = None;
+        self.translating = false;
+    }
+
+    // the prompt that asks the model for a query
+    pub fn translate(&mut self, request: &str) -> String {
+        self.request = Some(request.to_string());
+        self.translating = true;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        format!(
+            "{}\n\nThe current time is {} seconds since the epoch. Translate \
+             the following request into a single query, and answer with \
+             the query in a ```sql code block. Ask for the uri if the \
+             request does not tell where the objects are.\n\nRequest: {}",
+            QUERY_SCHEMA, now, request
+        )
+    }
+
+    // the query in an answer to the prompt of translate(), None if the
+    // answer was not to such a prompt
+    pub fn take_query(
+        &mut self,
+        answer: &str,
+    ) -> Option<Result<String, ApplicationError>> {
+        if !std::mem::take(&mut self.translating) {
+            return None;
+        }
+        Some(extract_query(answer).ok_or_else(|| {
+            ApplicationError::Unexpected(
+                "No query found in the answer".to_string(),
+            )
+        }))
+    }
+
+    // runs the query and returns the prompt that asks for a summary
+    pub async fn execute(
+        &mut self,
+        statement: &str,
+    ) -> Result<String, ApplicationError> {
+        let config = EnvironmentConfig::new(HashMap::new());
+        let table = ObjectStoreHandler::new(None)
+            .execute_query(statement, &config, None)
+            .await
+            .map_err(|e| ApplicationError::Unexpected(e.to_string()))?;
+        let results = if table.len() == 0 {
+            "The query returned no objects.".to_string()
+        } else {
+            let options =
+                TableExportOptions::new().set_max_rows(Some(MAX_RESULT_ROWS));
+            let mut markdown = table
+                .to_markdown(&options)
+                .map_err(ApplicationError::Unexpected)?;
+            if table.len() > MAX_RESULT_ROWS {
+                markdown.push_str(&format!(
+                    "\n(only the first {} of {} rows are listed)\n",
+                    MAX_RESULT_ROWS,
+                    table.len()
+                ));
+            }
+            markdown
+        };
+        let request = match self.request.take() {
+            Some(request) => format!(" to answer the request: {}", request),
+            None => ".".to_string(),
+        };
+        Ok(format!(
+            "Results of {}\n\n{}\n\nSummarize these results{}",
+            statement, results, request
+        ))
+    }
+}
+
+// the first SELECT statement in a code block, or else on its own line
+fn extract_query(answer: &str) -> Option<String> {
+    let mut candidates: Vec<String> = answer
+        .split("```")
+        .skip(1)
+        .step_by(2)
+        .map(|block| {
+            // the language of the block, if any
+            let block = block.trim_start_matches(|c: char| c.is_alphabetic());
+            block.trim().to_string()
+        })
+        .collect();
+    candidates.extend(answer.lines().filter_map(|line| {
+        let line = line.trim().trim_matches('`');
+        line.get(..6)
+            .filter(|start| start.eq_ignore_ascii_case("select"))
+            .map(|_| line.to_string())
+    }));
+    candidates.into_iter().find_map(|candidate| {
+        let query = candidate.trim().trim_end_matches(';').trim();
+        let statements = Parser::parse_sql(&GenericDialect {}, query).ok()?;
+        match statements.as_slice() {
+            [Statement::Query(_)] => Some(query.to_string()),
+            _ => None,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_assistant() {
+        assert!(QueryCommand::from_prompt("/queryx a").is_none());
+        assert!(QueryCommand::from_prompt("/sql").unwrap().is_err());
+        assert_eq!(
+            QueryCommand::from_prompt("/query large logs\n")
+                .unwrap()
+                .unwrap(),
+            QueryCommand::Translate("large logs".to_string())
+        );
+
+        let mut assistant = QueryAssistant::new();
+        assert!(assistant.take_query("SELECT * FROM 'a'").is_none());
+        let prompt = assistant.translate("csv files over 1 MB in s3://data");
+        assert!(prompt.contains("storage_class"));
+        assert!(prompt.ends_with("csv files over 1 MB in s3://data"));
+        let answer = "Here it is:\n```sql\nSELECT name, size\nFROM \
+                      's3://data/' WHERE name LIKE '%.csv' AND size > \
+                      1000000;\n```\n";
+        let query = assistant.take_query(answer).unwrap().unwrap();
+        assert!(query.starts_with("SELECT name, size\nFROM 's3://data/'"));
+        assert!(query.ends_with("1000000"));
+        assert!(assistant.take_query(answer).is_none());
+
+        assert_eq!(
+            extract_query("Try:\n  SELECT * FROM 'localfs:///tmp/'").as_deref(),
+            Some("SELECT * FROM 'localfs:///tmp/'")
+        );
+        assert!(extract_query("```\nDELETE FROM t\n```").is_none());
+        assert!(extract_query("Which bucket?").is_none());
+    }
+}
@@ -0,0 +1,110 @@
+use serde_json::Value;
+
+// a value that does not match a JSON schema. only type, enum, required,
+// properties and items are checked
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    pub pointer: String, // JSON pointer of the value, e.g. /options/top_k
+    pub message: String, // with the path of the value, e.g. $.options.top_k
+}
+
+// all mismatches, a value of the wrong type is not looked into further
+pub fn schema_errors(
+    value: &Value,
+    schema: &Value,
+    path: &str,
+) -> Vec<SchemaError> {
+    let mut errors = Vec::new();
+    collect_errors(value, schema, "", path, &mut errors);
+    errors
+}
+
+// the first mismatch, by its path in the value
+pub fn match_schema(
+    value: &Value,
+    schema: &Value,
+    path: &str,
+) -> Option<String> {
+    schema_errors(value, schema, path)
+        .into_iter()
+        .next()
+        .map(|e| e.message)
+}
+
+fn collect_errors(
+    value: &Value,
+    schema: &Value,
+    pointer: &str,
+    path: &str,
+    errors: &mut Vec<SchemaError>,
+) {
+    let mut error = |pointer: String, message: String| {
+        errors.push(SchemaError { pointer, message })
+    };
+    if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+        let matches = match expected {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "number" => value.is_number(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "boolean" => value.is_boolean(),
+            "null" => value.is_null(),
+            _ => true,
+        };
+        if !matches {
+            let message = format!("{} is not of type {}", path, expected);
+            return error(pointer.to_string(), message);
+        }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            let message = format!(
+                "{} is not one of {}",
+                path,
+                Value::from(allowed.clone())
+            );
+            error(pointer.to_string(), message);
+        }
+    }
+    if let Some(required) = schema.get("required").and_then(Value::as_array) {
+        for key in required.iter().filter_map(Value::as_str) {
+            if value.get(key).is_none() {
+                let message = format!("{}.{} is missing", path, key);
+                error(format!("{}/{}", pointer, escape(key)), message);
+            }
+        }
+    }
+    if let Some(properties) =
+        schema.get("properties").and_then(Value::as_object)
+    {
+        for (key, property) in properties {
+            if let Some(field) = value.get(key) {
+                collect_errors(
+                    field,
+                    property,
+                    &format!("{}/{}", pointer, escape(key)),
+                    &format!("{}.{}", path, key),
+                    errors,
+                );
+            }
+        }
+    }
+    if let (Some(items), Some(values)) = (schema.get("items"), value.as_array())
+    {
+        for (index, item) in values.iter().enumerate() {
+            collect_errors(
+                item,
+                items,
+                &format!("{}/{}", pointer, index),
+                &format!("{}[{}]", path, index),
+                errors,
+            );
+        }
+    }
+}
+
+// keys in JSON pointers, see RFC 6901
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
@@ -5,6 +5,7 @@ use lumni::api::error::{ApplicationError, HttpClientError};
 use lumni::HttpClient;
 use tokio::sync::{mpsc, oneshot};
 
+use super::inspector::record_request;
 pub use crate::external as lumni;
 
 pub async fn http_post(
@@ -23,6 +24,7 @@ pub async fn http_post(
             "application/json".to_string(),
         )])
     };
+    record_request(&url, &headers, &payload);
     let payload_bytes = Bytes::from(payload.into_bytes());
     tokio::spawn(async move {
         match http_client
@@ -1,18 +1,38 @@
+use std::collections::HashMap;
 use std::io::{self, Write};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use bytes::Bytes;
 use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::task::JoinHandle;
 
+use super::email::EmailExporter;
 use super::exchange::ChatExchange;
 use super::history::ChatHistory;
-use super::{LLMDefinition, PromptInstruction, ServerManager};
+use super::inspector::take_request;
+use super::trash::MessageTrash;
+use super::vault::{VaultExporter, VaultNote};
+use super::webhook::{WebhookDispatcher, WebhookEvent};
+use super::{
+    detect_language, LLMDefinition, Language, LanguagePreference,
+    PromptInstruction, ServerCapabilities, ServerManager,
+    STALE_SESSION_SECONDS,
+};
 use crate::api::error::ApplicationError;
+use crate::external::MemoryBudget;
 
 pub struct ChatSession {
     server: Box<dyn ServerManager>,
     prompt_instruction: PromptInstruction,
+    language_preference: LanguagePreference,
     cancel_tx: Option<oneshot::Sender<()>>,
+    webhooks: WebhookDispatcher,
+    email: Option<EmailExporter>,
+    vault: Option<VaultExporter>,
+    vault_note: Option<VaultNote>, // once exported
+    last_request: Instant,
+    trash: MessageTrash,
 }
 
 impl ChatSession {
@@ -32,10 +52,73 @@ impl ChatSession {
         Ok(ChatSession {
             server,
             prompt_instruction,
+            language_preference: LanguagePreference::Off,
             cancel_tx: None,
+            webhooks: WebhookDispatcher::default(),
+            email: None,
+            vault: None,
+            vault_note: None,
+            last_request: Instant::now(),
+            trash: MessageTrash::default(),
         })
     }
 
+    pub fn set_language_preference(&mut self, preference: LanguagePreference) {
+        self.language_preference = preference;
+        if let LanguagePreference::Fixed(language) = preference {
+            self.prompt_instruction
+                .set_response_language(Some(language));
+        } else {
+            self.prompt_instruction.set_response_language(None);
+        }
+    }
+
+    pub fn set_webhooks(&mut self, webhooks: WebhookDispatcher) {
+        self.webhooks = webhooks;
+    }
+
+    pub fn set_email_exporter(&mut self, email: Option<EmailExporter>) {
+        self.email = email;
+    }
+
+    pub fn set_vault_exporter(&mut self, vault: Option<VaultExporter>) {
+        self.vault = vault;
+    }
+
+    pub fn capabilities(&self) -> ServerCapabilities {
+        self.server.capabilities()
+    }
+
+    pub fn response_language(&self) -> Option<Language> {
+        self.prompt_instruction.get_response_language()
+    }
+
+    async fn update_response_language(
+        &mut self,
+        question: &str,
+    ) -> Result<(), ApplicationError> {
+        if self.language_preference != LanguagePreference::Auto {
+            return Ok(());
+        }
+        // keep the previous language if detection is inconclusive,
+        // to avoid flipping the instruction on short follow-up prompts
+        let language = match detect_language(question) {
+            Some(language) => language,
+            None => return Ok(()),
+        };
+        if self
+            .prompt_instruction
+            .set_response_language(Some(language))
+        {
+            log::debug!("Response language set to: {}", language.name());
+            let instruction = self.prompt_instruction.get_instruction();
+            let token_length = self.server.token_length(instruction).await?;
+            self.prompt_instruction
+                .set_system_token_length(token_length);
+        }
+        Ok(())
+    }
+
     pub fn stop(&mut self) {
         // Stop the chat session by sending a cancel signal
         if let Some(cancel_tx) = self.cancel_tx.take() {
@@ -46,6 +129,99 @@ impl ChatSession {
     pub fn reset(&mut self) {
         self.stop();
         self.prompt_instruction.reset_history();
+        self.trash.clear();
+        self.vault_note = None;
+    }
+
+    // exchanges of the conversation, without those preloaded from the
+    // assistant
+    pub fn conversation(&self) -> &[ChatExchange] {
+        self.prompt_instruction.get_conversation()
+    }
+
+    pub fn exchange_mut(&mut self, index: usize) -> Option<&mut ChatExchange> {
+        self.prompt_instruction
+            .get_conversation_mut()
+            .get_mut(index)
+    }
+
+    pub fn delete_exchange(&mut self, index: usize) -> Option<ChatExchange> {
+        self.prompt_instruction.remove_exchange(index)
+    }
+
+    // deletes the exchange, it can be restored from the trash until the
+    // retention period has passed
+    pub fn trash_exchange(&mut self, index: usize) -> bool {
+        match self.prompt_instruction.remove_exchange(index) {
+            Some(exchange) => {
+                self.trash.push(exchange, index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // number as listed in the trash, returns where the exchange is put
+    // back in the conversation
+    pub fn restore_exchange(&mut self, number: usize) -> Option<usize> {
+        let entry = self.trash.take(number)?;
+        let index = entry.index();
+        Some(
+            self.prompt_instruction
+                .insert_exchange(index, entry.into_exchange()),
+        )
+    }
+
+    pub fn trash_mut(&mut self) -> &mut MessageTrash {
+        &mut self.trash
+    }
+
+    // continues from the exchanges of another conversation, e.g. a fork
+    pub fn load_conversation(&mut self, exchanges: Vec<ChatExchange>) {
+        self.prompt_instruction.extend_conversation(exchanges);
+    }
+
+    // context for the questions that follow, e.g. an object listing. The
+    // answer acknowledges it, as models expect turns to alternate. Context
+    // that would make the conversation outgrow the memory budget is not
+    // added, and sent to the webhooks subscribed to "budget_exceeded"
+    pub async fn add_context(
+        &mut self,
+        context: String,
+        answer: String,
+    ) -> Result<(), ApplicationError> {
+        let budget = MemoryBudget::from_env().map_err(|e| {
+            ApplicationError::InvalidUserConfiguration(e.to_string())
+        })?;
+        let used = self
+            .conversation()
+            .iter()
+            .map(|exchange| {
+                exchange.get_question().len() + exchange.get_answer().len()
+            })
+            .sum::<usize>()
+            + context.len()
+            + answer.len();
+        if let Some(budget) = budget.filter(|b| b.exceeded_by(used as u64)) {
+            let message = budget.exceeded_message(
+                "Adding the context to the conversation",
+                "narrow it down or start a new conversation",
+            );
+            self.send_notification(
+                WebhookEvent::BudgetExceeded,
+                None,
+                Some(&message),
+            );
+            return Err(ApplicationError::Runtime(message));
+        }
+        let mut exchange = ChatExchange::new(context, answer);
+        let model = self.server.get_selected_model()?;
+        let text = ChatHistory::exchanges_to_string(model, vec![&exchange]);
+        if let Some(response) = self.server.tokenizer(&text).await? {
+            exchange.set_token_length(response.get_tokens().len());
+        }
+        self.prompt_instruction.extend_conversation(vec![exchange]);
+        Ok(())
     }
 
     pub fn update_last_exchange(&mut self, answer: &str) {
@@ -88,7 +264,125 @@ impl ChatSession {
                 last_exchange.set_token_length(token_length);
             }
         }
+        self.notify_completion_finished();
+        Ok(())
+    }
 
+    fn notify_completion_finished(&mut self) -> Vec<JoinHandle<()>> {
+        self.send_notification(WebhookEvent::CompletionFinished, None, None)
+    }
+
+    // sends the last exchange to the webhooks subscribed to "notify",
+    // returns the number of webhooks it was sent to
+    pub fn notify(&mut self, name: Option<&str>) -> usize {
+        self.send_notification(WebhookEvent::Notify, name, None).len()
+    }
+
+    fn send_notification(
+        &mut self,
+        event: WebhookEvent,
+        name: Option<&str>,
+        message: Option<&str>,
+    ) -> Vec<JoinHandle<()>> {
+        let mut fields = HashMap::new();
+        if let Some(message) = message {
+            fields.insert("message", message.to_string());
+        }
+        if let Ok(model) = self.server.get_selected_model() {
+            fields.insert("model", model.get_name().to_string());
+        }
+        if let Some(exchange) = self.prompt_instruction.get_last_exchange_mut()
+        {
+            fields.insert("question", exchange.get_question().to_string());
+            fields.insert("answer", exchange.get_answer().to_string());
+        }
+        self.webhooks.dispatch(event, name, fields)
+    }
+
+    // emails the conversation so far, as configured in email.yaml
+    pub async fn email_conversation(
+        &self,
+        to: &str,
+    ) -> Result<(), ApplicationError> {
+        let email = self.email.as_ref().ok_or_else(|| {
+            ApplicationError::InvalidUserConfiguration(
+                "Email is not configured, add email.yaml to the prompt \
+                 config directory"
+                    .to_string(),
+            )
+        })?;
+        let model = self
+            .server
+            .get_selected_model()
+            .ok()
+            .map(|model| model.get_name().to_string());
+        email
+            .send(
+                to,
+                model.as_deref(),
+                self.prompt_instruction.get_conversation(),
+            )
+            .await
+    }
+
+    // writes the conversation so far as a note to the vault configured in
+    // vault.yaml, exporting again updates the same note
+    pub async fn export_to_vault(
+        &mut self,
+    ) -> Result<String, ApplicationError> {
+        let vault = self.vault.as_ref().ok_or_else(|| {
+            ApplicationError::InvalidUserConfiguration(
+                "Vault export is not configured, add vault.yaml to the \
+                 prompt config directory"
+                    .to_string(),
+            )
+        })?;
+        let model = self
+            .server
+            .get_selected_model()
+            .ok()
+            .map(|model| model.get_name().to_string());
+        let (note, location) = vault
+            .export(
+                self.vault_note.clone(),
+                model.as_deref(),
+                self.prompt_instruction.get_conversation(),
+            )
+            .await?;
+        self.vault_note = Some(note);
+        Ok(location)
+    }
+
+    // re-initializes the selected model, e.g. after the server restarted
+    pub async fn reconnect(&mut self) -> Result<(), ApplicationError> {
+        let model = self.server.get_selected_model()?.clone();
+        log::info!("Reconnecting with model: {}", model.get_name());
+        self.server
+            .setup_and_initialize(model, &mut self.prompt_instruction)
+            .await?;
+        self.server.check_connection().await?;
+        self.last_request = Instant::now();
+        Ok(())
+    }
+
+    // a session that was idle may have lost its server (restarted, token
+    // expired), check it first and try to reconnect once
+    async fn ensure_connected(&mut self) -> Result<(), ApplicationError> {
+        let stale_after = Duration::from_secs(STALE_SESSION_SECONDS);
+        if self.last_request.elapsed() < stale_after {
+            return Ok(());
+        }
+        if let Err(e) = self.server.check_connection().await {
+            log::warn!("Server unreachable, reconnecting: {}", e);
+            self.reconnect().await.map_err(|e| {
+                ApplicationError::NotReady(format!(
+                    "Server is unreachable ({}). Check that it is running \
+                     and the credentials are valid, then retry with \
+                     :reconnect",
+                    e
+                ))
+            })?;
+        }
         Ok(())
     }
 
@@ -97,6 +391,9 @@ impl ChatSession {
         tx: mpsc::Sender<Bytes>,
         question: String,
     ) -> Result<(), ApplicationError> {
+        self.ensure_connected().await?;
+        self.last_request = Instant::now();
+        self.update_response_language(&question).await?;
         let max_token_length = self
             .server
             .get_context_size(&mut self.prompt_instruction)
@@ -120,9 +417,19 @@ impl ChatSession {
                 Some(cancel_rx),
             )
             .await?;
+        // kept with the exchange for the message inspector
+        let raw = take_request();
+        if let Some(exchange) = self.last_exchange_mut() {
+            exchange.set_raw(raw);
+        }
         Ok(())
     }
 
+    fn last_exchange_mut(&mut self) -> Option<&mut ChatExchange> {
+        let index = self.conversation().len().checked_sub(1)?;
+        self.exchange_mut(index)
+    }
+
     pub async fn initiate_new_exchange(
         &self,
         user_question: String,
@@ -157,9 +464,15 @@ impl ChatSession {
     }
 
     pub fn process_response(
-        &self,
+        &mut self,
         response: Bytes,
     ) -> (Option<String>, bool, Option<usize>) {
+        if let Some(raw) = self
+            .last_exchange_mut()
+            .and_then(|exchange| exchange.raw_mut())
+        {
+            raw.push_chunk(&response);
+        }
         self.server.process_response(response)
     }
 
@@ -170,17 +483,38 @@ impl ChatSession {
         stop_signal: Arc<Mutex<bool>>,
     ) -> Result<(), ApplicationError> {
         let (tx, rx) = mpsc::channel(32);
-        let _ = self.message(tx, question).await;
-        self.handle_response(rx, stop_signal).await?;
+        self.message(tx, question).await?;
+        let answer = self.handle_response(rx, stop_signal, true).await?;
         self.stop();
+        self.update_last_exchange(&answer);
+        // the process exits after the prompt, wait for the deliveries
+        for handle in self.notify_completion_finished() {
+            let _ = handle.await;
+        }
         Ok(())
     }
 
+    // the answer to a question, without printing it
+    pub async fn ask(
+        &mut self,
+        question: String,
+    ) -> Result<String, ApplicationError> {
+        let (tx, rx) = mpsc::channel(32);
+        self.message(tx, question).await?;
+        let running = Arc::new(Mutex::new(true));
+        let answer = self.handle_response(rx, running, false).await?;
+        self.stop();
+        self.update_last_exchange(&answer);
+        Ok(answer.trim().to_string())
+    }
+
     async fn handle_response(
-        &self,
+        &mut self,
         mut rx: mpsc::Receiver<Bytes>,
         stop_signal: Arc<Mutex<bool>>,
-    ) -> Result<(), ApplicationError> {
+        echo: bool,
+    ) -> Result<String, ApplicationError> {
+        let mut answer = String::new();
         let mut final_received = false;
         while let Some(response) = rx.recv().await {
             // check if the session must be kept running
@@ -198,14 +532,17 @@ impl ChatSession {
             let (response_content, is_final, _) =
                 self.process_response(response);
             if let Some(response_content) = response_content {
-                print!("{}", response_content);
+                if echo {
+                    print!("{}", response_content);
+                    io::stdout().flush().expect("Failed to flush stdout");
+                }
+                answer.push_str(&response_content);
             }
-            io::stdout().flush().expect("Failed to flush stdout");
 
             if is_final {
                 final_received = true;
             }
         }
-        Ok(())
+        Ok(answer)
     }
 }
@@ -0,0 +1,266 @@
+use std::fs;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use lumni::api::error::ApplicationError;
+use serde::{Deserialize, Serialize};
+
+use super::{config_file, hash_contents, read_if_exists};
+
+pub use crate::external as lumni;
+
+// typed before the trigger, e.g. ";sig" expands the snippet "sig"
+pub const SNIPPET_PREFIX: char = ';';
+
+// text inserted for a trigger. the body can have tab stops: $1, $2, ...
+// are visited in order with Tab, ${1:text} fills in default text, and
+// $0 is where the cursor ends up (the end of the snippet if not set)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snippet {
+    trigger: String,
+    body: String,
+}
+
+// text of an expanded snippet, with the tab stops as byte ranges in it
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnippetExpansion {
+    pub text: String,
+    pub tab_stops: Vec<Range<usize>>,
+}
+
+impl Snippet {
+    pub fn new(trigger: &str, body: &str) -> Self {
+        Snippet {
+            trigger: trigger.to_string(),
+            body: body.to_string(),
+        }
+    }
+
+    pub fn trigger(&self) -> &str {
+        &self.trigger
+    }
+
+    pub fn expand(&self) -> SnippetExpansion {
+        let mut text = String::new();
+        let mut tab_stops: Vec<(usize, Range<usize>)> = Vec::new();
+        let mut rest = self.body.as_str();
+        while let Some(position) = rest.find(['$', '\\']) {
+            text.push_str(&rest[..position]);
+            rest = &rest[position..];
+            if let Some(escaped) = rest.strip_prefix("\\$") {
+                text.push('$');
+                rest = escaped;
+                continue;
+            }
+            match parse_tab_stop(&rest[1..]).filter(|_| rest.starts_with('$')) {
+                Some((number, default, length)) => {
+                    let start = text.len();
+                    text.push_str(default);
+                    // a number used twice is only a tab stop the first time
+                    if tab_stops.iter().all(|(n, _)| *n != number) {
+                        tab_stops.push((number, start..text.len()));
+                    }
+                    rest = &rest[1 + length..];
+                }
+                None => {
+                    text.push_str(&rest[..1]);
+                    rest = &rest[1..];
+                }
+            }
+        }
+        text.push_str(rest);
+
+        if tab_stops.iter().all(|(number, _)| *number != 0) {
+            tab_stops.push((0, text.len()..text.len()));
+        }
+        // $0 is visited last
+        tab_stops.sort_by_key(
+            |(number, _)| {
+                if *number == 0 {
+                    usize::MAX
+                } else {
+                    *number
+                }
+            },
+        );
+        SnippetExpansion {
+            text,
+            tab_stops: tab_stops.into_iter().map(|(_, range)| range).collect(),
+        }
+    }
+}
+
+// (number, default text, bytes used) of a tab stop after the "$"
+fn parse_tab_stop(text: &str) -> Option<(usize, &str, usize)> {
+    if let Some(inner) = text.strip_prefix('{') {
+        let end = inner.find('}')?;
+        let field = &inner[..end];
+        let (number, default) = field.split_once(':').unwrap_or((field, ""));
+        return Some((number.parse().ok()?, default, end + 2));
+    }
+    let digits = text.len()
+        - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    Some((text[..digits].parse().ok()?, "", digits))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SnippetLibrary {
+    snippets: Vec<Snippet>,
+    path: Option<PathBuf>, // saved to this file when changed
+    // hash of the file as last read or written, None if there was no
+    // file. another lumni instance may have changed it since
+    version: Option<u64>,
+    conflict: bool,
+}
+
+impl SnippetLibrary {
+    // reads snippets.yaml from the lumni config directory, a list of
+    // trigger and body pairs. no file means no snippets are defined yet
+    pub fn from_config_file() -> Result<Self, ApplicationError> {
+        Self::from_file(config_file("snippets.yaml"))
+    }
+
+    fn from_file(path: Option<PathBuf>) -> Result<Self, ApplicationError> {
+        let mut library = SnippetLibrary {
+            path,
+            ..Default::default()
+        };
+        library.reload()?;
+        Ok(library)
+    }
+
+    // reads the file again, changes that were not saved are discarded
+    pub fn reload(&mut self) -> Result<(), ApplicationError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let contents = read_if_exists(path)?;
+        self.snippets = match &contents {
+            Some(contents) => serde_yaml::from_str(contents).map_err(|e| {
+                ApplicationError::InvalidUserConfiguration(format!(
+                    "Invalid snippets in {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?,
+            None => Vec::new(),
+        };
+        self.version = contents.as_deref().map(hash_contents);
+        self.conflict = false;
+        Ok(())
+    }
+
+    // true if saving stopped because the file was changed elsewhere
+    pub fn has_conflict(&self) -> bool {
+        self.conflict
+    }
+
+    pub fn get(&self, trigger: &str) -> Option<&Snippet> {
+        self.snippets
+            .iter()
+            .find(|snippet| snippet.trigger == trigger)
+    }
+
+    pub fn snippets(&self) -> &[Snippet] {
+        &self.snippets
+    }
+
+    // adds a snippet, or replaces the one with the same trigger
+    pub fn set(&mut self, snippet: Snippet) -> Result<(), ApplicationError> {
+        match self
+            .snippets
+            .iter_mut()
+            .find(|existing| existing.trigger == snippet.trigger)
+        {
+            Some(existing) => *existing = snippet,
+            None => self.snippets.push(snippet),
+        }
+        self.save(false)
+    }
+
+    // returns false if there is no snippet with the trigger
+    pub fn remove(&mut self, trigger: &str) -> Result<bool, ApplicationError> {
+        let count = self.snippets.len();
+        self.snippets.retain(|snippet| snippet.trigger != trigger);
+        if self.snippets.len() == count {
+            return Ok(false);
+        }
+        self.save(false).map(|_| true)
+    }
+
+    // saves the snippets as they are now, replacing changes made to the
+    // file by another lumni instance
+    pub fn overwrite(&mut self) -> Result<(), ApplicationError> {
+        self.save(true)
+    }
+
+    fn save(&mut self, force: bool) -> Result<(), ApplicationError> {
+        let Some(path) = &self.path else {
+            return Ok(()); // no config directory, kept for this session
+        };
+        let current = read_if_exists(path)?;
+        if !force && current.as_deref().map(hash_contents) != self.version {
+            // the change is kept until reloaded or overwritten
+            self.conflict = true;
+            return Err(ApplicationError::Conflict(format!(
+                "{} was changed by another lumni instance, :snippet reload \
+                 to discard your change or :snippet overwrite to keep it",
+                path.display()
+            )));
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(ApplicationError::IoError)?;
+        }
+        let contents = serde_yaml::to_string(&self.snippets).map_err(|e| {
+            ApplicationError::Unexpected(format!(
+                "Failed to save snippets: {}",
+                e
+            ))
+        })?;
+        fs::write(path, &contents).map_err(ApplicationError::IoError)?;
+        self.version = Some(hash_contents(&contents));
+        self.conflict = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let snippet =
+            Snippet::new("sig", "Hi ${2:there},\n$1 costs \\$5 $3$0.\n$1");
+        assert_eq!(
+            snippet.expand(),
+            SnippetExpansion {
+                text: "Hi there,\n costs $5 .\n".to_string(),
+                tab_stops: vec![10..10, 3..8, 20..20, 20..20],
+            }
+        );
+        let snippet = Snippet::new("x", "a $ b");
+        assert_eq!(snippet.expand().tab_stops, vec![5..5]);
+    }
+
+    #[test]
+    fn test_concurrent_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = Some(dir.path().join("snippets.yaml"));
+        let mut first = SnippetLibrary::from_file(path.clone()).unwrap();
+        let mut second = SnippetLibrary::from_file(path.clone()).unwrap();
+        first.set(Snippet::new("a", "first")).unwrap();
+
+        // the second instance did not see the first change
+        assert!(second.set(Snippet::new("b", "second")).is_err());
+        assert!(second.has_conflict());
+        second.reload().unwrap();
+        assert!(second.get("a").is_some() && second.get("b").is_none());
+        second.set(Snippet::new("b", "second")).unwrap();
+
+        assert!(first.remove("a").is_err());
+        first.overwrite().unwrap();
+        let saved = SnippetLibrary::from_file(path).unwrap();
+        assert!(saved.get("a").is_none() && saved.get("b").is_none());
+    }
+}
@@ -0,0 +1,167 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::exchange::ChatExchange;
+use super::DEFAULT_TRASH_RETENTION_SECONDS;
+
+// a deleted exchange, kept so it can be restored until it is purged
+#[derive(Debug, Clone)]
+pub struct TrashedExchange {
+    exchange: ChatExchange,
+    index: usize, // in the conversation when it was deleted
+    deleted_at: u64,
+}
+
+impl TrashedExchange {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn into_exchange(self) -> ChatExchange {
+        self.exchange
+    }
+}
+
+// deleted exchanges of a conversation, most recent last
+#[derive(Debug, Clone)]
+pub struct MessageTrash {
+    entries: Vec<TrashedExchange>,
+    retention_seconds: u64,
+}
+
+impl Default for MessageTrash {
+    fn default() -> Self {
+        MessageTrash {
+            entries: Vec::new(),
+            retention_seconds: DEFAULT_TRASH_RETENTION_SECONDS,
+        }
+    }
+}
+
+impl MessageTrash {
+    pub fn retention_seconds(&self) -> u64 {
+        self.retention_seconds
+    }
+
+    // entries older than this are purged, 0 purges on the next delete
+    pub fn set_retention_seconds(&mut self, seconds: u64) {
+        self.retention_seconds = seconds;
+        self.purge_expired();
+    }
+
+    pub fn push(&mut self, exchange: ChatExchange, index: usize) {
+        self.purge_expired();
+        self.entries.push(TrashedExchange {
+            exchange,
+            index,
+            deleted_at: now(),
+        });
+    }
+
+    // number is as listed by render(), starting at 1
+    pub fn take(&mut self, number: usize) -> Option<TrashedExchange> {
+        let position = number.checked_sub(1)?;
+        (position < self.entries.len()).then(|| self.entries.remove(position))
+    }
+
+    // all entries without a number, returns how many were purged
+    pub fn purge(&mut self, number: Option<usize>) -> usize {
+        match number {
+            Some(number) => self.take(number).map_or(0, |_| 1),
+            None => {
+                let count = self.entries.len();
+                self.entries.clear();
+                count
+            }
+        }
+    }
+
+    pub fn purge_expired(&mut self) -> usize {
+        let cutoff = now().saturating_sub(self.retention_seconds);
+        let count = self.entries.len();
+        self.entries.retain(|entry| entry.deleted_at > cutoff);
+        count - self.entries.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn render(&self) -> String {
+        if self.entries.is_empty() {
+            return "Trash is empty".to_string();
+        }
+        let now = now();
+        let mut text = String::new();
+        for (number, entry) in self.entries.iter().enumerate() {
+            let question = entry.exchange.get_question();
+            let first_line = question.lines().next().unwrap_or_default();
+            text.push_str(&format!(
+                "{}. message {}, deleted {} ago, purged in {}\n   {}\n\n",
+                number + 1,
+                entry.index + 1,
+                format_duration(now.saturating_sub(entry.deleted_at)),
+                format_duration(
+                    (entry.deleted_at + self.retention_seconds)
+                        .saturating_sub(now)
+                ),
+                first_line
+            ));
+        }
+        text.push_str(":trash restore <n> puts a message back, :trash purge [n] deletes it");
+        text
+    }
+}
+
+// e.g. "7d", "12h" or "30m", a plain number is in days
+pub fn parse_retention(value: &str) -> Option<u64> {
+    let (number, unit) = match value.char_indices().last()? {
+        (i, unit) if unit.is_ascii_alphabetic() => (&value[..i], unit),
+        _ => (value, 'd'),
+    };
+    let number: u64 = number.parse().ok()?;
+    let seconds = match unit {
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        _ => return None,
+    };
+    number.checked_mul(seconds)
+}
+
+fn format_duration(seconds: u64) -> String {
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m", seconds / 60),
+        3600..=86399 => format!("{}h", seconds / 3600),
+        _ => format!("{}d", seconds / 86400),
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trash() {
+        let mut trash = MessageTrash::default();
+        trash.push(ChatExchange::new("a".to_string(), "1".to_string()), 0);
+        trash.push(ChatExchange::new("b".to_string(), "2".to_string()), 3);
+        let entry = trash.take(2).unwrap();
+        assert_eq!(entry.index(), 3);
+        assert_eq!(entry.into_exchange().get_question(), "b");
+        assert!(trash.take(2).is_none());
+        assert_eq!(trash.purge(None), 1);
+
+        assert_eq!(parse_retention("7d"), Some(7 * 86400));
+        assert_eq!(parse_retention("12h"), Some(12 * 3600));
+        assert_eq!(parse_retention("3"), Some(3 * 86400));
+        assert_eq!(parse_retention("5x"), None);
+    }
+}
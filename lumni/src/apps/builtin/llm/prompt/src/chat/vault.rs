@@ -0,0 +1,596 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use lumni::api::error::ApplicationError;
+use lumni::MemoryBudget;
+use serde::Deserialize;
+use time::OffsetDateTime;
+
+use super::email::conversation_markdown;
+use super::{config_file, ChatExchange, Vote};
+pub use crate::external as lumni;
+use crate::{
+    EnvironmentConfig, LumniError, ObjectStoreHandler, ParsedUri, UploadOptions,
+};
+
+const NOTE_TITLE_MAX_LENGTH: usize = 60;
+const DEFAULT_NOTES_FOLDER: &str = "lumni";
+const DEFAULT_TAG: &str = "lumni";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum VaultFormat {
+    #[default]
+    Obsidian,
+    Logseq, // pages/ and journals/, messages as blocks
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VaultExporter {
+    // vault directory, or an object URI such as s3://bucket/notes
+    path: String,
+    #[serde(default)]
+    format: VaultFormat,
+    // folder for the conversation notes, obsidian only
+    folder: Option<String>,
+    // folder for the daily index, obsidian only, defaults to the vault root
+    daily_folder: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+// note of a conversation once exported, so exporting it again updates
+// the same note instead of adding another
+#[derive(Debug, Clone)]
+pub struct VaultNote {
+    name: String,
+    created: OffsetDateTime,
+}
+
+// conversation read back from a note written by export
+#[derive(Debug, Clone)]
+pub struct VaultConversation {
+    pub name: String,
+    pub tags: Vec<String>,
+    pub exchanges: Vec<ChatExchange>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct NoteFrontmatter {
+    #[serde(default)]
+    tags: Vec<String>,
+    model: Option<String>,
+}
+
+impl VaultExporter {
+    // reads vault.yaml from the lumni config directory, no file means
+    // vault export is not configured
+    pub fn from_config_file() -> Result<Option<Self>, ApplicationError> {
+        let path = match config_file("vault.yaml") {
+            Some(path) if path.exists() => path,
+            _ => return Ok(None),
+        };
+        let contents =
+            fs::read_to_string(&path).map_err(ApplicationError::IoError)?;
+        VaultExporter::parse(&contents).map(Some).map_err(|e| {
+            ApplicationError::InvalidUserConfiguration(format!(
+                "Invalid vault configuration in {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    fn parse(contents: &str) -> Result<Self, String> {
+        let exporter: VaultExporter =
+            serde_yaml::from_str(contents).map_err(|e| e.to_string())?;
+        if exporter.path.trim().is_empty() {
+            return Err("path is empty".to_string());
+        }
+        Ok(exporter)
+    }
+
+    // writes the conversation note and links it from the daily index,
+    // returns the note as written and where it was written to
+    pub async fn export(
+        &self,
+        note: Option<VaultNote>,
+        model: Option<&str>,
+        exchanges: &[ChatExchange],
+    ) -> Result<(VaultNote, String), ApplicationError> {
+        if exchanges.is_empty() {
+            return Err(ApplicationError::NotReady(
+                "No conversation to export".to_string(),
+            ));
+        }
+        let now = OffsetDateTime::now_utc();
+        let note = note.unwrap_or_else(|| VaultNote {
+            name: note_name(now, exchanges),
+            created: now,
+        });
+
+        let note_path = self.note_path(&note.name);
+        let content = self.render_note(&note, now, model, exchanges);
+        self.write(&note_path, content).await?;
+
+        // linked from the index of the day the conversation started
+        let index_path = self.daily_index_path(note.created);
+        let link = format!("- [[{}]]", note.name);
+        let index = self.read(&index_path).await?.unwrap_or_default();
+        if !index.lines().any(|line| line.trim() == link) {
+            let mut index = index.trim_end().to_string();
+            if !index.is_empty() {
+                index.push('\n');
+            }
+            index.push_str(&link);
+            index.push('\n');
+            self.write(&index_path, index).await?;
+        }
+        let location = self.location(&note_path);
+        Ok((note, location))
+    }
+
+    // conversations in the notes folder that are kept by select, ordered
+    // by note name. notes that were not written by export are skipped.
+    // fails when the kept notes outgrow the memory budget
+    pub fn read_conversations(
+        &self,
+        budget: Option<MemoryBudget>,
+        select: impl Fn(&VaultConversation) -> bool,
+    ) -> Result<Vec<VaultConversation>, ApplicationError> {
+        if is_uri(&self.path) {
+            return Err(ApplicationError::NotImplemented(
+                "Reading conversations from a vault on an object store"
+                    .to_string(),
+            ));
+        }
+        let folder = match self.note_path("").rsplit_once('/') {
+            Some((folder, _)) => self.location(folder),
+            None => self.path.clone(),
+        };
+        let entries = match fs::read_dir(expand_home(&folder)) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Vec::new())
+            }
+            Err(e) => return Err(ApplicationError::IoError(e)),
+        };
+        let mut conversations = Vec::new();
+        let mut used = 0;
+        for entry in entries {
+            let path = entry.map_err(ApplicationError::IoError)?.path();
+            let name = match path.file_name().and_then(|name| name.to_str()) {
+                Some(name) if name.ends_with(".md") => {
+                    name.trim_end_matches(".md").to_string()
+                }
+                _ => continue,
+            };
+            let contents =
+                fs::read_to_string(&path).map_err(ApplicationError::IoError)?;
+            let Some(conversation) = parse_note(&name, &contents) else {
+                continue;
+            };
+            if !select(&conversation) {
+                continue;
+            }
+            used += contents.len() as u64;
+            if let Some(budget) = budget.filter(|b| b.exceeded_by(used)) {
+                return Err(ApplicationError::Runtime(
+                    budget.exceeded_message(
+                        &format!(
+                            "Reading more than {} conversations",
+                            conversations.len()
+                        ),
+                        "select fewer of them with --tag",
+                    ),
+                ));
+            }
+            conversations.push(conversation);
+        }
+        conversations.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(conversations)
+    }
+
+    fn note_path(&self, name: &str) -> String {
+        match self.format {
+            VaultFormat::Obsidian => {
+                let folder =
+                    self.folder.as_deref().unwrap_or(DEFAULT_NOTES_FOLDER);
+                join_path(folder, &format!("{}.md", name))
+            }
+            VaultFormat::Logseq => format!("pages/{}.md", name),
+        }
+    }
+
+    fn daily_index_path(&self, date: OffsetDateTime) -> String {
+        match self.format {
+            VaultFormat::Obsidian => join_path(
+                self.daily_folder.as_deref().unwrap_or(""),
+                &format!("{}.md", iso_date(date)),
+            ),
+            VaultFormat::Logseq => format!(
+                "journals/{:04}_{:02}_{:02}.md",
+                date.year(),
+                date.month() as u8,
+                date.day()
+            ),
+        }
+    }
+
+    // link to the daily index as the vault names it
+    fn daily_link(&self, date: OffsetDateTime) -> String {
+        match self.format {
+            VaultFormat::Obsidian => format!("[[{}]]", iso_date(date)),
+            VaultFormat::Logseq => format!("[[{}]]", journal_title(date)),
+        }
+    }
+
+    fn render_note(
+        &self,
+        note: &VaultNote,
+        now: OffsetDateTime,
+        model: Option<&str>,
+        exchanges: &[ChatExchange],
+    ) -> String {
+        let mut tags = vec![DEFAULT_TAG.to_string()];
+        for tag in &self.tags {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+        let mut frontmatter = vec![
+            "---".to_string(),
+            format!("title: {}", yaml_string(&note_title(exchanges))),
+            "tags:".to_string(),
+        ];
+        frontmatter.extend(tags.iter().map(|tag| format!("  - {}", tag)));
+        if let Some(model) = model {
+            frontmatter.push(format!("model: {}", yaml_string(model)));
+        }
+        frontmatter.push(format!("created: {}", rfc3339(note.created)));
+        frontmatter.push(format!("updated: {}", rfc3339(now)));
+        frontmatter.push(format!("messages: {}", exchanges.len()));
+        frontmatter.push("---".to_string());
+
+        let daily = self.daily_link(note.created);
+        let body = match self.format {
+            VaultFormat::Obsidian => format!(
+                "Started on {}\n\n{}",
+                daily,
+                conversation_markdown(model, exchanges)
+            ),
+            VaultFormat::Logseq => format!(
+                "- Started on {}\n{}",
+                daily,
+                logseq_blocks(model, exchanges)
+            ),
+        };
+        format!("{}\n\n{}\n", frontmatter.join("\n"), body)
+    }
+
+    fn location(&self, relative: &str) -> String {
+        join_path(&self.path, relative)
+    }
+
+    async fn read(
+        &self,
+        relative: &str,
+    ) -> Result<Option<String>, ApplicationError> {
+        let location = self.location(relative);
+        if !is_uri(&self.path) {
+            return match fs::read_to_string(expand_home(&location)) {
+                Ok(contents) => Ok(Some(contents)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(ApplicationError::IoError(e)),
+            };
+        }
+        let handler = ObjectStoreHandler::new(None);
+        let config = EnvironmentConfig::new(HashMap::new());
+        match handler
+            .get_object(&ParsedUri::from_uri(&location, false), &config, None)
+            .await
+        {
+            Ok(data) => {
+                Ok(data.map(|data| String::from_utf8_lossy(&data).into_owned()))
+            }
+            Err(LumniError::NotFound(_)) => Ok(None),
+            Err(e) => Err(export_error(&location, e)),
+        }
+    }
+
+    async fn write(
+        &self,
+        relative: &str,
+        content: String,
+    ) -> Result<(), ApplicationError> {
+        let location = self.location(relative);
+        if !is_uri(&self.path) {
+            let path = expand_home(&location);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(ApplicationError::IoError)?;
+            }
+            return fs::write(&path, content)
+                .map_err(ApplicationError::IoError);
+        }
+        let handler = ObjectStoreHandler::new(None);
+        let config = EnvironmentConfig::new(HashMap::new());
+        let options = UploadOptions::new()
+            .set_content_type("text/markdown; charset=utf-8");
+        handler
+            .put_object(
+                &ParsedUri::from_uri(&location, false),
+                &config,
+                &mut content.as_bytes(),
+                &options,
+                None,
+            )
+            .await
+            .map(|_| ())
+            .map_err(|e| export_error(&location, e))
+    }
+}
+
+fn export_error(location: &str, e: LumniError) -> ApplicationError {
+    ApplicationError::Runtime(format!("Export to {} failed: {}", location, e))
+}
+
+fn is_uri(path: &str) -> bool {
+    path.contains("://")
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn join_path(base: &str, relative: &str) -> String {
+    let base = base.trim_end_matches('/');
+    if base.is_empty() {
+        relative.to_string()
+    } else {
+        format!("{}/{}", base, relative)
+    }
+}
+
+// first line of the first question, without the characters that are not
+// allowed in note names or links
+fn note_title(exchanges: &[ChatExchange]) -> String {
+    let first_line = exchanges
+        .first()
+        .and_then(|exchange| exchange.get_question().lines().next())
+        .unwrap_or_default();
+    let title: String = first_line
+        .chars()
+        .filter(|c| !"*\"\\/<>:|?#^[]".contains(*c))
+        .take(NOTE_TITLE_MAX_LENGTH)
+        .collect();
+    match title.trim() {
+        "" => "Conversation".to_string(),
+        title => title.to_string(),
+    }
+}
+
+fn note_name(now: OffsetDateTime, exchanges: &[ChatExchange]) -> String {
+    format!(
+        "{} {:02}{:02} {}",
+        iso_date(now),
+        now.hour(),
+        now.minute(),
+        note_title(exchanges)
+    )
+}
+
+fn iso_date(date: OffsetDateTime) -> String {
+    format!(
+        "{:04}-{:02}-{:02}",
+        date.year(),
+        date.month() as u8,
+        date.day()
+    )
+}
+
+fn rfc3339(date: OffsetDateTime) -> String {
+    format!(
+        "{}T{:02}:{:02}:{:02}Z",
+        iso_date(date),
+        date.hour(),
+        date.minute(),
+        date.second()
+    )
+}
+
+// default journal title of logseq, e.g. "Oct 16th, 2026"
+fn journal_title(date: OffsetDateTime) -> String {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct",
+        "Nov", "Dec",
+    ];
+    let day = date.day();
+    let suffix = match (day % 10, day % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!(
+        "{} {}{}, {}",
+        MONTHS[date.month() as usize - 1],
+        day,
+        suffix,
+        date.year()
+    )
+}
+
+fn yaml_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// one block per message, continuation lines indented under the block
+fn logseq_blocks(model: Option<&str>, exchanges: &[ChatExchange]) -> String {
+    let assistant = model.unwrap_or("Assistant");
+    let block = |label: &str, text: &str| {
+        let mut block = format!("- {}", label);
+        for line in text.trim().lines() {
+            block.push_str("\n  ");
+            block.push_str(line);
+        }
+        block
+    };
+    let mut blocks = Vec::new();
+    for exchange in exchanges {
+        let you = match exchange.get_reply_to() {
+            Some(reply_to) => {
+                format!("**You**, in reply to message {}", reply_to + 1)
+            }
+            None => "**You**".to_string(),
+        };
+        blocks.push(block(&you, exchange.get_question()));
+        blocks
+            .push(block(&format!("**{}**", assistant), exchange.get_answer()));
+        if let Some(vote) = exchange.get_vote() {
+            blocks.push(format!("  - *Vote: {}*", vote.as_str()));
+        }
+        if let Some(annotation) = exchange.get_annotation() {
+            blocks.push(format!("  - *Note: {}*", annotation));
+        }
+    }
+    blocks.join("\n")
+}
+
+// inverse of render_note, for both formats
+fn parse_note(name: &str, contents: &str) -> Option<VaultConversation> {
+    let rest = contents.strip_prefix("---\n")?;
+    let (frontmatter, body) = rest.split_once("\n---\n")?;
+    let frontmatter: NoteFrontmatter =
+        serde_yaml::from_str(frontmatter).ok()?;
+    if !frontmatter.tags.iter().any(|tag| tag == DEFAULT_TAG) {
+        return None;
+    }
+    let assistant = format!(
+        "**{}**",
+        frontmatter.model.as_deref().unwrap_or("Assistant")
+    );
+
+    // (label, text) per message, logseq blocks are unindented first
+    let logseq = body.trim_start().starts_with("- ");
+    let mut messages: Vec<(String, Vec<String>)> = Vec::new();
+    let mut remarks: Vec<(usize, &str, &str)> = Vec::new();
+    for line in body.lines() {
+        let line = match line.strip_prefix("- ") {
+            Some(block) if logseq => block,
+            _ if logseq => line.strip_prefix("  ").unwrap_or(line),
+            _ => line,
+        };
+        if line == "**You**"
+            || line.starts_with("**You**, in reply to message ")
+            || line == assistant
+        {
+            messages.push((line.to_string(), Vec::new()));
+        } else if let (Some((key, value)), Some(index)) =
+            (parse_remark(line), messages.len().checked_sub(1))
+        {
+            remarks.push((index, key, value));
+        } else if let Some((_, text)) = messages.last_mut() {
+            text.push(line.to_string());
+        }
+    }
+
+    let mut exchanges: Vec<ChatExchange> = Vec::new();
+    let mut question: Option<(Option<usize>, String)> = None;
+    for (index, (label, text)) in messages.iter().enumerate() {
+        let text = text.join("\n").trim().to_string();
+        if *label == assistant {
+            if let Some((reply_to, question)) = question.take() {
+                let mut exchange = ChatExchange::new(question, text);
+                exchange.set_reply_to(reply_to);
+                for (_, key, value) in
+                    remarks.iter().filter(|(at, _, _)| *at == index)
+                {
+                    match *key {
+                        "Vote" => exchange.set_vote(Vote::from_str(value)),
+                        _ => exchange.set_annotation(Some(value.to_string())),
+                    }
+                }
+                exchanges.push(exchange);
+            }
+        } else {
+            let reply_to = label
+                .strip_prefix("**You**, in reply to message ")
+                .and_then(|number| number.parse::<usize>().ok())
+                .and_then(|number| number.checked_sub(1));
+            question = Some((reply_to, text));
+        }
+    }
+    if exchanges.is_empty() {
+        return None;
+    }
+    Some(VaultConversation {
+        name: name.to_string(),
+        tags: frontmatter.tags,
+        exchanges,
+    })
+}
+
+// "*Note: text*" or "*Vote: up*" below an answer
+fn parse_remark(line: &str) -> Option<(&str, &str)> {
+    let remark = line
+        .trim_start_matches("- ")
+        .strip_prefix('*')?
+        .strip_suffix('*')?;
+    let (key, value) = remark.split_once(": ")?;
+    matches!(key, "Note" | "Vote").then_some((key, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_note() {
+        let exporter = VaultExporter::parse(
+            "path: ~/vault\nformat: logseq\ntags: [chat, lumni]\n",
+        )
+        .unwrap();
+        let created = OffsetDateTime::from_unix_timestamp(1790931900).unwrap();
+        let note = VaultNote {
+            name: "2026-10-02 0905 What is 1 2".to_string(),
+            created,
+        };
+        let exchanges = vec![ChatExchange::new(
+            "What is 1: 2?\nthanks".to_string(),
+            "a ratio".to_string(),
+        )];
+        assert_eq!(
+            note_name(created, &exchanges),
+            "2026-10-02 0905 What is 1 2"
+        );
+        assert_eq!(
+            exporter.daily_index_path(created),
+            "journals/2026_10_02.md"
+        );
+        assert_eq!(
+            exporter.render_note(&note, created, Some("llama3"), &exchanges),
+            "---\ntitle: \"What is 1 2\"\ntags:\n  - lumni\n  - chat\nmodel: \
+             \"llama3\"\ncreated: 2026-10-02T09:05:00Z\nupdated: \
+             2026-10-02T09:05:00Z\nmessages: 1\n---\n\n- Started on [[Oct \
+             2nd, 2026]]\n- **You**\n  What is 1: 2?\n  thanks\n- \
+             **llama3**\n  a ratio\n"
+        );
+        // read back as exported
+        let rendered =
+            exporter.render_note(&note, created, Some("llama3"), &exchanges);
+        let conversation = parse_note(&note.name, &rendered).unwrap();
+        assert_eq!(conversation.tags, vec!["lumni", "chat"]);
+        assert_eq!(conversation.exchanges.len(), 1);
+        assert_eq!(
+            conversation.exchanges[0].get_question(),
+            "What is 1: 2?\nthanks"
+        );
+        assert_eq!(conversation.exchanges[0].get_answer(), "a ratio");
+    }
+}
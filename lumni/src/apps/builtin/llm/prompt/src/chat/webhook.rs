@@ -0,0 +1,360 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use bytes::Bytes;
+use hmac::{Hmac, Mac, NewMac};
+use lumni::api::error::HttpClientError;
+use lumni::{HttpClient, RetryPolicy};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::Sha256;
+use tokio::task::JoinHandle;
+use tokio::time::Duration;
+use url::Url;
+
+pub use crate::external as lumni;
+
+const WEBHOOK_MAX_ATTEMPTS: u32 = 3;
+const WEBHOOK_RETRY_DELAY_MS: u64 = 1000; // doubles after every attempt
+const WEBHOOK_TIMEOUT_SECONDS: u64 = 10;
+// longer messages are rejected (Discord) or cut off (Slack)
+const SLACK_MAX_TEXT_LENGTH: usize = 40_000;
+const DISCORD_MAX_CONTENT_LENGTH: usize = 2000;
+const TRUNCATED_SUFFIX: &str = "\n… (truncated)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    CompletionFinished,
+    Notify, // sent on request, with the :notify command
+    // context not added to the conversation, as it would outgrow the
+    // memory budget (MEMORY_BUDGET)
+    BudgetExceeded,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::CompletionFinished => "completion_finished",
+            WebhookEvent::Notify => "notify",
+            WebhookEvent::BudgetExceeded => "budget_exceeded",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    #[default]
+    Json,
+    Slack,   // incoming webhook, {"text": ...}
+    Discord, // channel webhook, {"content": ...}
+}
+
+// configured per profile, see PromptProfile
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Webhook {
+    // used to select the webhook, e.g. with ":notify team-chat"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    url: String,
+    events: Vec<WebhookEvent>,
+    #[serde(default)]
+    format: WebhookFormat,
+    // body with {{ EVENT }}, {{ MODEL }}, {{ QUESTION }}, {{ ANSWER }} and
+    // {{ MESSAGE }} placeholders, defaults to a JSON object with these
+    // fields
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    template: Option<String>,
+    // signs the body with HMAC-SHA256 in the X-Lumni-Signature header
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secret: Option<String>,
+}
+
+impl Webhook {
+    pub fn mask_secret(&mut self, mask: &str) {
+        if let Some(secret) = self.secret.as_mut().filter(|s| !s.is_empty()) {
+            *secret = mask.to_string();
+        }
+    }
+
+    pub fn remove_secret(&mut self) {
+        self.secret = None;
+    }
+
+    // the url may hold a token (e.g. Slack and Discord webhooks), so it
+    // is referred to by name or host only
+    fn label(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        Url::parse(&self.url)
+            .ok()
+            .and_then(|url| url.host_str().map(String::from))
+            .unwrap_or_else(|| "(invalid url)".to_string())
+    }
+
+    fn render(
+        &self,
+        event: WebhookEvent,
+        fields: &HashMap<&str, String>,
+    ) -> String {
+        let field = |name: &str| fields.get(name).cloned().unwrap_or_default();
+        // chat formats show the answer, or the message of an event
+        // without one
+        let text = || match fields.get("message") {
+            Some(message) => message.clone(),
+            None => field("answer"),
+        };
+        match &self.template {
+            Some(template) => {
+                // all placeholders in one pass, so a value that contains
+                // one is sent as is
+                placeholder()
+                    .replace_all(template, |caps: &Captures| {
+                        let name = caps[1].to_lowercase();
+                        if name == "event" {
+                            return event.as_str().to_string();
+                        }
+                        match fields.get(name.as_str()) {
+                            // templates are typically JSON, keep the values
+                            // valid
+                            Some(value) => {
+                                let quoted = serde_json::to_string(value)
+                                    .unwrap_or_else(|_| "\"\"".to_string());
+                                quoted[1..quoted.len() - 1].to_string()
+                            }
+                            None => caps[0].to_string(),
+                        }
+                    })
+                    .into_owned()
+            }
+            None => match self.format {
+                WebhookFormat::Json => json!({
+                    "event": event.as_str(),
+                    "model": field("model"),
+                    "question": field("question"),
+                    "answer": field("answer"),
+                    "message": field("message"),
+                })
+                .to_string(),
+                WebhookFormat::Slack => {
+                    let text = format!("*{}*\n{}", field("model"), text());
+                    json!({
+                        "text": truncate_markdown(&text, SLACK_MAX_TEXT_LENGTH)
+                    })
+                    .to_string()
+                }
+                WebhookFormat::Discord => {
+                    let text = format!("**{}**\n{}", field("model"), text());
+                    json!({
+                        "content": truncate_markdown(
+                            &text,
+                            DISCORD_MAX_CONTENT_LENGTH
+                        )
+                    })
+                    .to_string()
+                }
+            },
+        }
+    }
+
+    fn signature(&self, body: &str) -> Option<String> {
+        let secret = self.secret.as_ref()?;
+        let mut hmac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+            .expect("HMAC can take key of any size");
+        hmac.update(body.as_bytes());
+        Some(format!(
+            "sha256={}",
+            hex::encode(hmac.finalize().into_bytes())
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WebhookDispatcher {
+    webhooks: Vec<Webhook>,
+}
+
+impl WebhookDispatcher {
+    pub fn new(webhooks: Vec<Webhook>) -> Self {
+        WebhookDispatcher { webhooks }
+    }
+
+    // delivers in the background, callers that exit right after (e.g.
+    // non-interactive mode) should await the returned handles. With a
+    // name, only the webhook with that name is used.
+    pub fn dispatch(
+        &self,
+        event: WebhookEvent,
+        name: Option<&str>,
+        fields: HashMap<&str, String>,
+    ) -> Vec<JoinHandle<()>> {
+        self.webhooks
+            .iter()
+            .filter(|webhook| webhook.events.contains(&event))
+            .filter(|webhook| {
+                name.is_none_or(|name| webhook.name.as_deref() == Some(name))
+            })
+            .map(|webhook| {
+                let webhook = webhook.clone();
+                let body = webhook.render(event, &fields);
+                tokio::spawn(async move {
+                    if let Err(e) = deliver(&webhook, body).await {
+                        log::error!(
+                            "Webhook {} failed: {}",
+                            webhook.label(),
+                            e
+                        );
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+// {{ NAME }} placeholder of a template
+fn placeholder() -> &'static Regex {
+    static PLACEHOLDER: OnceLock<Regex> = OnceLock::new();
+    PLACEHOLDER.get_or_init(|| Regex::new(r"\{\{ ([A-Z_]+) \}\}").unwrap())
+}
+
+async fn deliver(
+    webhook: &Webhook,
+    body: String,
+) -> Result<(), HttpClientError> {
+    let mut headers = HashMap::from([(
+        "Content-Type".to_string(),
+        "application/json".to_string(),
+    )]);
+    if let Some(signature) = webhook.signature(&body) {
+        headers.insert("X-Lumni-Signature".to_string(), signature);
+    }
+    // a rejected request (4xx) is not retried, except when throttled
+    let retry_policy = RetryPolicy::new()
+        .set_max_attempts(WEBHOOK_MAX_ATTEMPTS)
+        .set_backoff_base(Duration::from_millis(WEBHOOK_RETRY_DELAY_MS))
+        .set_retry_on_status(std::iter::once(429).chain(500..600).collect());
+    let http_client = HttpClient::new()
+        .with_timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECONDS))
+        .with_retry_policy(retry_policy);
+    let body = Bytes::from(body);
+    http_client
+        .post(&webhook.url, Some(&headers), None, Some(&body), None, None)
+        .await?;
+    Ok(())
+}
+
+// cuts the text to at most `max_length` characters, closing a code block
+// that is cut in half so the remainder is not rendered as code
+fn truncate_markdown(text: &str, max_length: usize) -> String {
+    if text.chars().count() <= max_length {
+        return text.to_string();
+    }
+    let closing_fence = "\n```";
+    let reserved =
+        TRUNCATED_SUFFIX.chars().count() + closing_fence.chars().count();
+    let mut truncated: String = text
+        .chars()
+        .take(max_length.saturating_sub(reserved))
+        .collect();
+    // prefer to cut at the end of a line
+    if let Some(position) = truncated.rfind('\n') {
+        if position > truncated.len() / 2 {
+            truncated.truncate(position);
+        }
+    }
+    if truncated.matches("```").count() % 2 == 1 {
+        truncated.push_str(closing_fence);
+    }
+    truncated.push_str(TRUNCATED_SUFFIX);
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn webhook(template: Option<&str>, secret: Option<&str>) -> Webhook {
+        Webhook {
+            name: None,
+            format: WebhookFormat::Json,
+            url: "http://localhost/hook".to_string(),
+            events: vec![WebhookEvent::CompletionFinished],
+            template: template.map(String::from),
+            secret: secret.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_render_template_escapes_values() {
+        let webhook = webhook(Some(r#"{"text": "{{ ANSWER }}"}"#), None);
+        let fields = HashMap::from([("answer", "\nsay \"hi\"".to_string())]);
+        let body = webhook.render(WebhookEvent::CompletionFinished, &fields);
+        assert_eq!(body, r#"{"text": "\nsay \"hi\""}"#);
+    }
+
+    #[test]
+    fn test_render_template_keeps_placeholders_in_values() {
+        let webhook = webhook(
+            Some("{{ EVENT }} {{ MODEL }}: {{ QUESTION }} {{ ANSWER }}"),
+            None,
+        );
+        let fields = HashMap::from([
+            ("model", "llama3".to_string()),
+            ("question", "say {{ ANSWER }}".to_string()),
+            ("answer", "{{ MODEL }} {{ QUESTION }}".to_string()),
+        ]);
+        let body = webhook.render(WebhookEvent::CompletionFinished, &fields);
+        assert_eq!(
+            body,
+            "completion_finished llama3: say {{ ANSWER }} {{ MODEL }} \
+             {{ QUESTION }}"
+        );
+    }
+
+    #[test]
+    fn test_label_hides_url() {
+        let mut webhook = webhook(None, None);
+        webhook.url =
+            "https://hooks.slack.com/services/T0/B0/token".to_string();
+        assert_eq!(webhook.label(), "hooks.slack.com");
+        webhook.name = Some("team-chat".to_string());
+        assert_eq!(webhook.label(), "team-chat");
+    }
+
+    #[test]
+    fn test_render_budget_exceeded() {
+        let mut webhook = webhook(None, None);
+        webhook.format = WebhookFormat::Slack;
+        let fields = HashMap::from([
+            ("model", "llama3".to_string()),
+            ("message", "over budget".to_string()),
+        ]);
+        let body = webhook.render(WebhookEvent::BudgetExceeded, &fields);
+        assert_eq!(body, r#"{"text":"*llama3*\nover budget"}"#);
+    }
+
+    #[test]
+    fn test_signature() {
+        // HMAC-SHA256 test case 2 from RFC 4231
+        let webhook = webhook(None, Some("Jefe"));
+        assert_eq!(
+            webhook.signature("what do ya want for nothing?").unwrap(),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert!(self::webhook(None, None).signature("body").is_none());
+    }
+
+    #[test]
+    fn test_truncate_markdown_closes_code_block() {
+        let text =
+            format!("intro\n```rust\n{}\n```\n", "let x = 1;\n".repeat(50));
+        let truncated = truncate_markdown(&text, 100);
+        assert!(truncated.chars().count() <= 100);
+        assert_eq!(truncated.matches("```").count(), 2);
+        assert!(truncated.ends_with(TRUNCATED_SUFFIX));
+        assert_eq!(truncate_markdown("short", 100), "short");
+    }
+}
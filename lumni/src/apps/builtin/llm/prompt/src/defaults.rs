@@ -1,5 +1,17 @@
 pub const DEFAULT_N_PREDICT: u32 = 1024; // max number of tokens to predict on prompt
 pub const DEFAULT_TEMPERATURE: f64 = 0.8; // randomness of generated text
 
+// idle time after which the server is checked before the next prompt
+pub const STALE_SESSION_SECONDS: u64 = 60;
+
+// standby sessions are replaced before they would need that check
+pub const DEFAULT_POOL_IDLE_TTL_SECONDS: u64 = STALE_SESSION_SECONDS;
+
+// deleted messages are kept this long before they are purged, 7 days
+pub const DEFAULT_TRASH_RETENTION_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+// interval at which paced responses are rendered, about 60 fps
+pub const RENDER_FRAME_MILLIS: u64 = 16;
+
 // only used when cant be fetched from the server, and not set by the user
 pub const DEFAULT_CONTEXT_SIZE: usize = 512;
@@ -4,6 +4,7 @@ use regex::Regex;
 use super::generic::Generic;
 use super::llama3::Llama3;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PromptRole {
     User,
     Assistant,
@@ -12,14 +12,15 @@ use eventstream::EventStreamMessage;
 use lumni::api::error::ApplicationError;
 use lumni::{AWSCredentials, AWSRequestBuilder, HttpClient};
 use request::*;
-use serde_json::Value;
+use serde_json::{json, Value};
 use sha2::{Digest, Sha256};
 use tokio::sync::{mpsc, oneshot};
 use url::Url;
 
 use super::{
-    http_post, ChatExchange, ChatHistory, ChatMessage, Endpoints,
-    LLMDefinition, PromptInstruction, ServerTrait,
+    http_post, profile_schema, Capability, ChatExchange, ChatHistory,
+    ChatMessage, Endpoints, LLMDefinition, PromptInstruction,
+    ServerCapabilities, ServerTrait,
 };
 pub use crate::external as lumni;
 
@@ -46,6 +47,21 @@ impl Bedrock {
         })
     }
 
+    // settings of a profile, credentials are found as for the other AWS
+    // tools, e.g. from a profile in ~/.aws/credentials
+    pub fn settings_schema() -> Value {
+        profile_schema(
+            Some("bedrock"),
+            json!({
+                "AWS_PROFILE": { "type": "string", "default": "default" },
+                "AWS_REGION": { "type": "string" },
+                "AWS_ACCESS_KEY_ID": { "type": "string" },
+                "AWS_SECRET_ACCESS_KEY": { "type": "string" },
+                "AWS_SESSION_TOKEN": { "type": "string" }
+            }),
+        )
+    }
+
     fn completion_api_payload(
         &self,
         _model: &LLMDefinition,
@@ -119,6 +135,17 @@ impl ServerTrait for Bedrock {
         self.model.as_ref()
     }
 
+    fn capabilities(&self) -> ServerCapabilities {
+        // the Converse API takes images and tools for the models that do,
+        // embedding models are not used for chat
+        ServerCapabilities::new()
+            .set(Capability::Streaming, true)
+            .set_from_catalog(
+                self.model.as_ref(),
+                &[Capability::Vision, Capability::Tools],
+            )
+    }
+
     fn process_response(
         &self,
         response_bytes: Bytes,
@@ -168,7 +195,7 @@ impl ServerTrait for Bedrock {
             .collect::<String>();
 
         let request_builder = AWSRequestBuilder::new(completion_endpoint);
-        let credentials = AWSCredentials::from_env()?;
+        let credentials = AWSCredentials::from_chain().await?;
 
         let headers = request_builder
             .generate_headers(
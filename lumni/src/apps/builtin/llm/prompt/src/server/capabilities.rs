@@ -0,0 +1,167 @@
+use serde::Deserialize;
+
+use super::{LLMDefinition, ModelCatalog};
+
+// what a connector supports. vision and tools also depend on the model,
+// a connector only claims them for models listed with them in the catalog
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    Streaming,
+    Vision,
+    Tools,
+    JsonMode,
+    Embeddings,
+    TokenCount,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 6] = [
+        Capability::Streaming,
+        Capability::Vision,
+        Capability::Tools,
+        Capability::JsonMode,
+        Capability::Embeddings,
+        Capability::TokenCount,
+    ];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Capability::Streaming => "streamed responses",
+            Capability::Vision => "image input",
+            Capability::Tools => "tool calls",
+            Capability::JsonMode => "JSON mode",
+            Capability::Embeddings => "embeddings",
+            Capability::TokenCount => "token counting",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ServerCapabilities {
+    streaming: bool,
+    vision: bool,
+    tools: bool,
+    json_mode: bool,
+    embeddings: bool,
+    token_count: bool, // server exposes a tokenizer endpoint
+    max_context: Option<usize>,
+}
+
+impl ServerCapabilities {
+    pub fn new() -> Self {
+        ServerCapabilities::default()
+    }
+
+    pub fn set(mut self, capability: Capability, enabled: bool) -> Self {
+        match capability {
+            Capability::Streaming => self.streaming = enabled,
+            Capability::Vision => self.vision = enabled,
+            Capability::Tools => self.tools = enabled,
+            Capability::JsonMode => self.json_mode = enabled,
+            Capability::Embeddings => self.embeddings = enabled,
+            Capability::TokenCount => self.token_count = enabled,
+        }
+        self
+    }
+
+    // of those the connector supports for some models, the ones the
+    // catalog lists for this model
+    pub fn set_from_catalog(
+        mut self,
+        model: Option<&LLMDefinition>,
+        capabilities: &[Capability],
+    ) -> Self {
+        let listed = model.and_then(|model| {
+            ModelCatalog::bundled().ok()?.lookup(model.get_name())
+        });
+        if let Some(listed) = listed {
+            for capability in capabilities {
+                if listed.get_capabilities().contains(capability) {
+                    self = self.set(*capability, true);
+                }
+            }
+        }
+        self
+    }
+
+    pub fn set_max_context(mut self, max_context: usize) -> Self {
+        self.max_context = Some(max_context);
+        self
+    }
+
+    pub fn get_max_context(&self) -> Option<usize> {
+        self.max_context
+    }
+
+    pub fn supports(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::Streaming => self.streaming,
+            Capability::Vision => self.vision,
+            Capability::Tools => self.tools,
+            Capability::JsonMode => self.json_mode,
+            Capability::Embeddings => self.embeddings,
+            Capability::TokenCount => self.token_count,
+        }
+    }
+
+    // returns an explanation to show in the UI if the capability is missing
+    pub fn check(&self, capability: Capability) -> Result<(), String> {
+        if self.supports(capability) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Not available: the selected server does not support {}",
+                capability.description()
+            ))
+        }
+    }
+
+    // one line per capability, as shown by :capabilities
+    pub fn describe(&self) -> String {
+        let mut lines: Vec<String> = Capability::ALL
+            .iter()
+            .map(|capability| {
+                let supported = match self.supports(*capability) {
+                    true => "yes",
+                    false => "no",
+                };
+                format!("{}: {}", capability.description(), supported)
+            })
+            .collect();
+        lines.push(match self.max_context {
+            Some(max_context) => format!("max context: {} tokens", max_context),
+            None => "max context: as configured on the server".to_string(),
+        });
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_from_catalog() {
+        let model = |name: &str| LLMDefinition::new(name.to_string());
+        let capabilities = |name: &str| {
+            ServerCapabilities::new()
+                .set(Capability::Streaming, true)
+                .set_from_catalog(
+                    Some(&model(name)),
+                    &[Capability::Vision, Capability::Tools],
+                )
+        };
+        let gpt4o = capabilities("gpt-4o-2024-05-13");
+        assert!(gpt4o.supports(Capability::Vision));
+        assert!(gpt4o.supports(Capability::Tools));
+        let gpt35 = capabilities("gpt-3.5-turbo");
+        assert!(!gpt35.supports(Capability::Vision));
+        assert!(gpt35.supports(Capability::Tools));
+        // not listed, only what the connector supports for every model
+        let unknown = capabilities("my-finetune");
+        assert!(unknown.supports(Capability::Streaming));
+        assert!(!unknown.supports(Capability::Tools));
+        assert!(unknown.check(Capability::Vision).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use lumni::api::error::ApplicationError;
+use serde::{Deserialize, Serialize};
+
+use super::{Capability, LLMDefinition, ServerTrait, MODELS};
+use crate::external as lumni;
+
+// cached model lists are refreshed once a day, or on request
+const MODEL_CACHE_TTL_SECONDS: u64 = 24 * 60 * 60;
+
+static BUNDLED_CATALOG: OnceLock<Result<ModelCatalog, String>> =
+    OnceLock::new();
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModelInfo {
+    prefix: String,
+    context_size: Option<usize>,
+    input_price: Option<f64>,  // USD per million tokens
+    output_price: Option<f64>, // USD per million tokens
+    // those that depend on the model, e.g. vision
+    #[serde(default)]
+    capabilities: Vec<Capability>,
+}
+
+impl ModelInfo {
+    pub fn get_context_size(&self) -> Option<usize> {
+        self.context_size
+    }
+
+    pub fn get_input_price(&self) -> Option<f64> {
+        self.input_price
+    }
+
+    pub fn get_output_price(&self) -> Option<f64> {
+        self.output_price
+    }
+
+    pub fn get_capabilities(&self) -> &[Capability] {
+        &self.capabilities
+    }
+}
+
+pub struct ModelCatalog {
+    models: Vec<ModelInfo>,
+}
+
+impl ModelCatalog {
+    // the catalog in templates/models.yaml, parsed on first use
+    pub fn bundled() -> Result<&'static Self, ApplicationError> {
+        BUNDLED_CATALOG
+            .get_or_init(|| {
+                serde_yaml::from_str(MODELS)
+                    .map(|models| ModelCatalog { models })
+                    .map_err(|e| format!("Invalid model catalog: {}", e))
+            })
+            .as_ref()
+            .map_err(|e| ApplicationError::Unexpected(e.clone()))
+    }
+
+    pub fn lookup(&self, model_name: &str) -> Option<&ModelInfo> {
+        let model_name = model_name.to_lowercase();
+        // strip the namespace, e.g. "library/llama3:8b" -> "llama3:8b"
+        let base_name = model_name.rsplit('/').next().unwrap_or(&model_name);
+        self.models
+            .iter()
+            .filter(|m| {
+                model_name.starts_with(&m.prefix)
+                    || base_name.starts_with(&m.prefix)
+            })
+            .max_by_key(|m| m.prefix.len())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ModelCache {
+    fetched_at: u64,
+    models: Vec<LLMDefinition>,
+}
+
+fn cache_file(server_name: &str) -> Option<PathBuf> {
+    let cache_dir = match env::var("XDG_CACHE_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var("HOME").ok()?).join(".cache"),
+    };
+    Some(
+        cache_dir
+            .join("lumni")
+            .join("prompt")
+            .join(format!("models-{}.json", server_name)),
+    )
+}
+
+fn now_in_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn read_cache(server_name: &str) -> Option<Vec<LLMDefinition>> {
+    let contents = fs::read_to_string(cache_file(server_name)?).ok()?;
+    let cache: ModelCache = serde_json::from_str(&contents).ok()?;
+    if now_in_seconds().saturating_sub(cache.fetched_at)
+        > MODEL_CACHE_TTL_SECONDS
+    {
+        return None;
+    }
+    Some(cache.models)
+}
+
+fn write_cache(
+    server_name: &str,
+    models: &[LLMDefinition],
+) -> Result<(), ApplicationError> {
+    let path = cache_file(server_name).ok_or_else(|| {
+        ApplicationError::Unexpected("No cache directory".to_string())
+    })?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(ApplicationError::IoError)?;
+    }
+    let cache = ModelCache {
+        fetched_at: now_in_seconds(),
+        models: models.to_vec(),
+    };
+    let contents = serde_json::to_string(&cache)
+        .map_err(|e| ApplicationError::Unexpected(e.to_string()))?;
+    fs::write(path, contents).map_err(ApplicationError::IoError)
+}
+
+pub async fn list_models_cached(
+    server: &dyn ServerTrait,
+    server_name: &str,
+    refresh: bool,
+) -> Result<Vec<LLMDefinition>, ApplicationError> {
+    if !refresh {
+        if let Some(models) = read_cache(server_name) {
+            log::debug!("Using cached model list for {}", server_name);
+            return Ok(models);
+        }
+    }
+    let models = server.list_models().await?;
+    // an empty list is likely a server hiccup, keep the previous cache
+    if !models.is_empty() {
+        if let Err(e) = write_cache(server_name, &models) {
+            log::warn!("Failed to cache model list: {}", e);
+        }
+    }
+    Ok(models)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_longest_prefix() {
+        let catalog = ModelCatalog::bundled().unwrap();
+        let info = catalog.lookup("gpt-4o-mini-2024-07-18").unwrap();
+        assert_eq!(info.get_input_price(), Some(0.15));
+        let info = catalog.lookup("gpt-4-0613").unwrap();
+        assert_eq!(info.get_context_size(), Some(8192));
+    }
+
+    #[test]
+    fn test_catalog_namespaced_model() {
+        let catalog = ModelCatalog::bundled().unwrap();
+        let info = catalog.lookup("library/Llama3:8b").unwrap();
+        assert_eq!(info.get_context_size(), Some(8192));
+        assert!(catalog.lookup("unknown-model").is_none());
+    }
+}
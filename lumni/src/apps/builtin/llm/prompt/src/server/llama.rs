@@ -5,14 +5,15 @@ use async_trait::async_trait;
 use bytes::Bytes;
 use lumni::api::error::ApplicationError;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde_json::{json, Value};
 use tokio::sync::{mpsc, oneshot};
 use url::Url;
 
 use super::{
-    http_get_with_response, http_post, ChatCompletionOptions, ChatExchange,
-    ChatHistory, Endpoints, HttpClient, LLMDefinition, PromptInstruction,
-    PromptRole, ServerTrait, TokenResponse, DEFAULT_CONTEXT_SIZE,
+    http_get_with_response, http_post, profile_schema, Capability,
+    ChatCompletionOptions, ChatExchange, ChatHistory, Endpoints, HttpClient,
+    LLMDefinition, PromptInstruction, PromptRole, ServerCapabilities,
+    ServerTrait, TokenResponse, DEFAULT_CONTEXT_SIZE,
 };
 use crate::external as lumni;
 
@@ -41,6 +42,11 @@ impl Llama {
         })
     }
 
+    // settings of a profile, llama.cpp reads no environment variables
+    pub fn settings_schema() -> Value {
+        profile_schema(Some("llama"), json!({}))
+    }
+
     fn system_prompt_payload(
         &self,
         prompt_instruction: &PromptInstruction,
@@ -188,6 +194,20 @@ impl ServerTrait for Llama {
         self.model.as_ref()
     }
 
+    fn capabilities(&self) -> ServerCapabilities {
+        // context size is read from the server, see get_context_size.
+        // multimodal models (llava) take images, there are no tool calls
+        ServerCapabilities::new()
+            .set(Capability::Streaming, true)
+            .set(Capability::JsonMode, true) // json_schema
+            .set(Capability::Embeddings, true)
+            .set(
+                Capability::TokenCount,
+                self.endpoints.get_tokenizer().is_some(),
+            )
+            .set_from_catalog(self.model.as_ref(), &[Capability::Vision])
+    }
+
     async fn get_context_size(
         &self,
         prompt_instruction: &mut PromptInstruction,
@@ -1,5 +1,7 @@
 mod bedrock;
 mod openai;
+mod capabilities;
+mod catalog;
 mod endpoints;
 mod llama;
 mod llm;
@@ -8,6 +10,8 @@ mod ollama;
 use async_trait::async_trait;
 pub use bedrock::Bedrock;
 use bytes::Bytes;
+pub use capabilities::{Capability, ServerCapabilities};
+pub use catalog::{list_models_cached, ModelCatalog};
 pub use endpoints::Endpoints;
 pub use llama::Llama;
 pub use llm::LLMDefinition;
@@ -15,12 +19,13 @@ use lumni::api::error::ApplicationError;
 pub use lumni::HttpClient;
 pub use ollama::Ollama;
 pub use openai::OpenAI;
+use serde_json::Value;
 use tokio::sync::{mpsc, oneshot};
 
 pub use super::chat::{
-    http_get_with_response, http_post, http_post_with_response,
+    http_get_with_response, http_post, http_post_with_response, profile_schema,
     ChatCompletionOptions, ChatExchange, ChatHistory, ChatMessage,
-    PromptInstruction, TokenResponse,
+    PromptInstruction, TokenResponse, MODELS,
 };
 pub use super::defaults::*;
 pub use super::model::{ModelFormatter, ModelFormatterTrait, PromptRole};
@@ -62,6 +67,20 @@ impl ModelServer {
             ))),
         }
     }
+
+    // JSON schema of the profile settings of a server, see PromptProfile
+    pub fn settings_schema(s: &str) -> Result<Value, ApplicationError> {
+        match s {
+            "llama" => Ok(Llama::settings_schema()),
+            "ollama" => Ok(Ollama::settings_schema()),
+            "bedrock" => Ok(Bedrock::settings_schema()),
+            "openai" => Ok(OpenAI::settings_schema()),
+            _ => Err(ApplicationError::InvalidUserConfiguration(format!(
+                "Unknown server {}, supported servers: {:?}",
+                s, SUPPORTED_MODEL_ENDPOINTS
+            ))),
+        }
+    }
 }
 
 impl ServerManager for ModelServer {}
@@ -189,6 +208,24 @@ impl ServerTrait for ModelServer {
             ModelServer::OpenAI(openai) => openai.get_model(),
         }
     }
+
+    async fn check_connection(&self) -> Result<(), ApplicationError> {
+        match self {
+            ModelServer::Llama(llama) => llama.check_connection().await,
+            ModelServer::Ollama(ollama) => ollama.check_connection().await,
+            ModelServer::Bedrock(bedrock) => bedrock.check_connection().await,
+            ModelServer::OpenAI(openai) => openai.check_connection().await,
+        }
+    }
+
+    fn capabilities(&self) -> ServerCapabilities {
+        match self {
+            ModelServer::Llama(llama) => llama.capabilities(),
+            ModelServer::Ollama(ollama) => ollama.capabilities(),
+            ModelServer::Bedrock(bedrock) => bedrock.capabilities(),
+            ModelServer::OpenAI(openai) => openai.capabilities(),
+        }
+    }
 }
 
 #[async_trait]
@@ -212,6 +249,17 @@ pub trait ServerTrait: Send + Sync {
 
     fn get_model(&self) -> Option<&LLMDefinition>;
 
+    // used to detect a stale session, e.g. after the server was restarted.
+    // Servers with a static model list should override this with a request
+    // that reaches the server.
+    async fn check_connection(&self) -> Result<(), ApplicationError> {
+        self.list_models().await.map(|_| ())
+    }
+
+    fn capabilities(&self) -> ServerCapabilities {
+        ServerCapabilities::new()
+    }
+
     fn get_selected_model(&self) -> Result<&LLMDefinition, ApplicationError> {
         match self.get_model() {
             Some(m) => Ok(m),
@@ -248,7 +296,17 @@ pub trait ServerTrait: Send + Sync {
         &self,
         _prompt_instruction: &mut PromptInstruction,
     ) -> Result<usize, ApplicationError> {
-        Ok(DEFAULT_CONTEXT_SIZE)
+        if let Some(max_context) = self.capabilities().get_max_context() {
+            return Ok(max_context);
+        }
+        // fall back to the bundled catalog for the selected model
+        let catalog_size = self.get_model().and_then(|model| {
+            ModelCatalog::bundled()
+                .ok()?
+                .lookup(model.get_name())?
+                .get_context_size()
+        });
+        Ok(catalog_size.unwrap_or(DEFAULT_CONTEXT_SIZE))
     }
 
     fn get_role_name(&self, prompt_role: PromptRole) -> &'static str {
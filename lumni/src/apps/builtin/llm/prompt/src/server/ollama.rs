@@ -4,13 +4,14 @@ use async_trait::async_trait;
 use bytes::Bytes;
 use lumni::api::error::ApplicationError;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use tokio::sync::{mpsc, oneshot};
 use url::Url;
 
 use super::{
-    http_get_with_response, http_post, http_post_with_response, ChatExchange,
-    ChatHistory, ChatMessage, Endpoints, HttpClient, LLMDefinition,
-    PromptInstruction, ServerTrait,
+    http_get_with_response, http_post, http_post_with_response, profile_schema,
+    Capability, ChatExchange, ChatHistory, ChatMessage, Endpoints, HttpClient,
+    LLMDefinition, PromptInstruction, ServerCapabilities, ServerTrait,
 };
 use crate::external as lumni;
 
@@ -23,6 +24,7 @@ pub struct Ollama {
     http_client: HttpClient,
     endpoints: Endpoints,
     model: Option<LLMDefinition>,
+    context_size: Option<usize>, // num_ctx of the model, if it sets one
 }
 
 impl Ollama {
@@ -35,9 +37,15 @@ impl Ollama {
             http_client: HttpClient::new(),
             endpoints,
             model: None,
+            context_size: None,
         })
     }
 
+    // settings of a profile, ollama reads no environment variables
+    pub fn settings_schema() -> Value {
+        profile_schema(Some("ollama"), json!({}))
+    }
+
     fn completion_api_payload(
         &self,
         model: &LLMDefinition,
@@ -82,17 +90,20 @@ impl ServerTrait for Ollama {
             payload,
         )
         .await;
+        self.context_size = None;
         if let Ok(response) = response {
             // check if model is available by validating the response format
-            // at this moment we not yet need the response itself
-            if OllamaShowResponse::extract_content(&response).is_err() {
-                let error_message = format!(
-                    "Failed to get model information for: {}",
-                    model.get_name()
-                );
-                return Err(ApplicationError::ServerConfigurationError(
-                    error_message,
-                ));
+            match OllamaShowResponse::extract_content(&response) {
+                Ok(show) => self.context_size = show.num_ctx(),
+                Err(_) => {
+                    let error_message = format!(
+                        "Failed to get model information for: {}",
+                        model.get_name()
+                    );
+                    return Err(ApplicationError::ServerConfigurationError(
+                        error_message,
+                    ));
+                }
             }
         }
         self.model = Some(model);
@@ -103,6 +114,23 @@ impl ServerTrait for Ollama {
         self.model.as_ref()
     }
 
+    fn capabilities(&self) -> ServerCapabilities {
+        let capabilities = ServerCapabilities::new()
+            .set(Capability::Streaming, true)
+            .set(Capability::JsonMode, true) // format: json
+            .set(Capability::Embeddings, true)
+            .set_from_catalog(
+                self.model.as_ref(),
+                &[Capability::Vision, Capability::Tools],
+            );
+        // requests do not pass num_ctx, ollama then uses the one of the
+        // model. without it the context size is looked up in the catalog
+        match self.context_size {
+            Some(context_size) => capabilities.set_max_context(context_size),
+            None => capabilities,
+        }
+    }
+
     fn process_response(
         &self,
         response: Bytes,
@@ -248,6 +276,9 @@ struct ModelDetails {
 #[derive(Deserialize, Debug)]
 struct OllamaShowResponse {
     modelfile: String,
+    // one parameter per line, e.g. "num_ctx 8192"
+    #[serde(default)]
+    parameters: String,
     details: OllamaShowResponseDetails,
 }
 
@@ -258,6 +289,15 @@ impl OllamaShowResponse {
         let text = String::from_utf8(bytes.to_vec())?;
         Ok(serde_json::from_str(&text)?)
     }
+
+    fn num_ctx(&self) -> Option<usize> {
+        self.parameters.lines().find_map(|line| {
+            match line.split_whitespace().collect::<Vec<_>>()[..] {
+                ["num_ctx", value] => value.parse().ok(),
+                _ => None,
+            }
+        })
+    }
 }
 
 // used to check if response can deserialize
@@ -1,25 +1,59 @@
 use std::env;
+use std::sync::Arc;
 
 use lumni::api::error::ApplicationError;
+use lumni::DeviceCodeFlow;
 pub use crate::external as lumni;
 
+// Azure OpenAI with Microsoft Entra ID, offline_access for a refresh token
+const DEFAULT_OAUTH_SCOPE: &str =
+    "https://cognitiveservices.azure.com/.default offline_access";
 
 #[derive(Clone)]
-pub struct OpenAICredentials {
-    api_key: String,
+pub enum OpenAICredentials {
+    ApiKey(String),
+    // signed in with a code on another device, see DeviceCodeFlow
+    DeviceCode(Arc<DeviceCodeFlow>),
 }
 
 impl OpenAICredentials {
+    // OPENAI_API_KEY, or else a sign in with OPENAI_OAUTH_CLIENT_ID at the
+    // OPENAI_OAUTH_DEVICE_URL and OPENAI_OAUTH_TOKEN_URL endpoints, with
+    // the optional OPENAI_OAUTH_SCOPE. in a profile these are set as e.g.
+    // env.OPENAI_OAUTH_CLIENT_ID
     pub fn from_env() -> Result<OpenAICredentials, ApplicationError> {
-        let api_key = env::var("OPENAI_API_KEY").map_err(|_| {
-            ApplicationError::InvalidCredentials(
-                "OPENAI_API_KEY not found in environment".to_string(),
-            )
-        })?;
-        Ok(OpenAICredentials { api_key })
+        if let Ok(api_key) = env::var("OPENAI_API_KEY") {
+            return Ok(OpenAICredentials::ApiKey(api_key));
+        }
+        match (
+            env::var("OPENAI_OAUTH_CLIENT_ID"),
+            env::var("OPENAI_OAUTH_DEVICE_URL"),
+            env::var("OPENAI_OAUTH_TOKEN_URL"),
+        ) {
+            (Ok(client_id), Ok(device_url), Ok(token_url)) => {
+                let scope = env::var("OPENAI_OAUTH_SCOPE")
+                    .unwrap_or_else(|_| DEFAULT_OAUTH_SCOPE.to_string());
+                let flow =
+                    DeviceCodeFlow::new(&client_id, &device_url, &token_url)
+                        .set_scope(&scope);
+                Ok(OpenAICredentials::DeviceCode(Arc::new(flow)))
+            }
+            _ => Err(ApplicationError::InvalidCredentials(
+                "OPENAI_API_KEY not found in environment, or \
+                 OPENAI_OAUTH_CLIENT_ID, OPENAI_OAUTH_DEVICE_URL and \
+                 OPENAI_OAUTH_TOKEN_URL to sign in"
+                    .to_string(),
+            )),
+        }
     }
 
-    pub fn get_api_key(&self) -> &str {
-        &self.api_key
+    // the API key, or an access token that is refreshed when it expires
+    pub async fn get_bearer_token(&self) -> Result<String, ApplicationError> {
+        match self {
+            OpenAICredentials::ApiKey(api_key) => Ok(api_key.clone()),
+            OpenAICredentials::DeviceCode(flow) => {
+                Ok(flow.access_token().await?)
+            }
+        }
     }
 }
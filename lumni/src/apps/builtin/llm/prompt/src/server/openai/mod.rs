@@ -4,6 +4,7 @@ mod response;
 mod credentials;
 
 use std::collections::HashMap;
+use std::env;
 use std::error::Error;
 use std::sync::Arc;
 
@@ -12,12 +13,14 @@ use bytes::Bytes;
 use error::OpenAIErrorHandler;
 use lumni::api::error::ApplicationError;
 use lumni::HttpClient;
+use serde_json::{json, Value};
 use tokio::sync::{mpsc, oneshot};
 use url::Url;
 
 use super::{
     http_post, ChatExchange, ChatHistory, ChatMessage, Endpoints,
-    LLMDefinition, PromptInstruction, ServerTrait,
+    profile_schema, Capability, LLMDefinition, PromptInstruction,
+    ServerCapabilities, ServerTrait,
 };
 use credentials::OpenAICredentials;
 use request::OpenAIRequestPayload;
@@ -29,6 +32,7 @@ pub struct OpenAI {
     http_client: HttpClient,
     endpoints: Endpoints,
     model: Option<LLMDefinition>,
+    credentials: Option<OpenAICredentials>,
 }
 
 const OPENAI_COMPLETION_ENDPOINT: &str = "https://api.openai.com/v1/chat/completions";
@@ -36,17 +40,27 @@ const OPENAI_COMPLETION_ENDPOINT: &str = "https://api.openai.com/v1/chat/complet
 
 impl OpenAI {
     pub fn new() -> Result<Self, Box<dyn Error>> {
-        let endpoints = Endpoints::new()
-            .set_completion(Url::parse(OPENAI_COMPLETION_ENDPOINT)?);
+        let endpoints = Endpoints::new().set_completion(completion_endpoint()?);
 
         Ok(OpenAI {
             http_client: HttpClient::new()
                 .with_error_handler(Arc::new(OpenAIErrorHandler)),
             endpoints,
             model: None,
+            credentials: None,
         })
     }
 
+    // settings of a profile, the key may also be set in the environment
+    pub fn settings_schema() -> Value {
+        profile_schema(
+            Some("openai"),
+            json!({
+                "OPENAI_API_KEY": { "type": "string", "default": "" }
+            }),
+        )
+    }
+
     fn completion_api_payload(
         &self,
         model: &LLMDefinition,
@@ -84,6 +98,11 @@ impl ServerTrait for OpenAI {
         model: LLMDefinition,
         _prompt_instruction: &PromptInstruction,
     ) -> Result<(), ApplicationError> {
+        if let Ok(credentials) = OpenAICredentials::from_env() {
+            // sign in now, a prompt within the interactive session is not seen
+            credentials.get_bearer_token().await?;
+            self.credentials = Some(credentials);
+        }
         self.model = Some(model);
         Ok(())
     }
@@ -92,6 +111,17 @@ impl ServerTrait for OpenAI {
         self.model.as_ref()
     }
 
+    fn capabilities(&self) -> ServerCapabilities {
+        ServerCapabilities::new()
+            .set(Capability::Streaming, true)
+            .set(Capability::JsonMode, true) // response_format
+            .set(Capability::Embeddings, true)
+            .set_from_catalog(
+                self.model.as_ref(),
+                &[Capability::Vision, Capability::Tools],
+            )
+    }
+
     fn process_response(
         &self,
         response_bytes: Bytes,
@@ -136,7 +166,10 @@ impl ServerTrait for OpenAI {
                 ApplicationError::InvalidUserConfiguration(e.to_string())
             })?;
 
-        let credentials = OpenAICredentials::from_env()?;
+        let credentials = match &self.credentials {
+            Some(credentials) => credentials.clone(),
+            None => OpenAICredentials::from_env()?,
+        };
 
         let mut headers = HashMap::new();
         headers.insert(
@@ -145,7 +178,7 @@ impl ServerTrait for OpenAI {
         );
         headers.insert(
             "Authorization".to_string(),
-            format!("Bearer {}", credentials.get_api_key()),
+            format!("Bearer {}", credentials.get_bearer_token().await?),
         );
 
         http_post(
@@ -169,3 +202,18 @@ impl ServerTrait for OpenAI {
         Ok(vec![model])
     }
 }
+
+// OPENAI_BASE_URL points to a compatible server, e.g. an Azure OpenAI
+// deployment. its query, e.g. api-version, is kept
+fn completion_endpoint() -> Result<Url, url::ParseError> {
+    match env::var("OPENAI_BASE_URL") {
+        Ok(base_url) if !base_url.is_empty() => {
+            let mut url = Url::parse(&base_url)?;
+            let path =
+                format!("{}/chat/completions", url.path().trim_end_matches('/'));
+            url.set_path(&path);
+            Ok(url)
+        }
+        _ => Url::parse(OPENAI_COMPLETION_ENDPOINT),
+    }
+}
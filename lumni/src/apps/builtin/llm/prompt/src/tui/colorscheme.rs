@@ -1,5 +1,7 @@
 use ratatui::style::{Color, Style};
 
+use super::components::GutterStyle;
+
 pub enum ColorSchemeType {
     Default,
     Dark,
@@ -14,6 +16,7 @@ pub struct ColorSet {
     pub primary_bg: Color,
     pub secondary_fg: Color,
     pub secondary_bg: Color,
+    pub gutter_fg: Color,
 }
 
 impl ColorSet {
@@ -52,6 +55,13 @@ impl ColorScheme {
         self.colors.background
     }
 
+    pub fn get_gutter_style(&self) -> GutterStyle {
+        GutterStyle {
+            number: Style::new().fg(self.colors.gutter_fg),
+            ..GutterStyle::default()
+        }
+    }
+
     fn get_colors(scheme: &ColorSchemeType) -> ColorSet {
         match scheme {
             ColorSchemeType::Default => ColorSet {
@@ -60,6 +70,7 @@ impl ColorScheme {
                 primary_bg: Color::Rgb(0, 48, 48),
                 secondary_fg: Color::White,
                 secondary_bg: Color::Rgb(0, 24, 24),
+                gutter_fg: Color::DarkGray,
             },
             ColorSchemeType::Dark => ColorSet {
                 background: Color::Rgb(15, 15, 15),
@@ -67,6 +78,7 @@ impl ColorScheme {
                 primary_bg: Color::Rgb(30, 30, 30),
                 secondary_fg: Color::Rgb(200, 200, 200),
                 secondary_bg: Color::Rgb(45, 45, 45),
+                gutter_fg: Color::Rgb(90, 90, 90),
             },
             ColorSchemeType::Light => ColorSet {
                 background: Color::White,
@@ -74,6 +86,7 @@ impl ColorScheme {
                 primary_bg: Color::Rgb(230, 230, 230),
                 secondary_fg: Color::Black,
                 secondary_bg: Color::Rgb(200, 200, 200),
+                gutter_fg: Color::Rgb(140, 140, 140),
             },
             ColorSchemeType::HighContrast => ColorSet {
                 background: Color::Black,
@@ -81,6 +94,7 @@ impl ColorScheme {
                 primary_bg: Color::Blue,
                 secondary_fg: Color::White,
                 secondary_bg: Color::Green,
+                gutter_fg: Color::Gray,
             },
             ColorSchemeType::Pastel => ColorSet {
                 background: Color::Rgb(255, 255, 240),
@@ -88,6 +102,7 @@ impl ColorScheme {
                 primary_bg: Color::Rgb(255, 204, 204),
                 secondary_fg: Color::Black,
                 secondary_bg: Color::Rgb(204, 229, 255),
+                gutter_fg: Color::Rgb(150, 150, 150),
             },
         }
     }
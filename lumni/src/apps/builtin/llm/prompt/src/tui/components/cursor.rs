@@ -1,3 +1,6 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
 use super::piece_table::TextLine;
 
 #[derive(Debug, Clone)]
@@ -13,6 +16,10 @@ pub enum MoveCursor {
     EndOfFileEndOfLine,
 }
 
+// col is a byte offset in the (unwrapped) line, and always on a grapheme
+// boundary so the cursor never ends up inside an emoji or a character
+// with combining marks. desired_col is a display width instead, so moving
+// up or down stays aligned with wide (e.g. CJK) characters
 #[derive(Debug, Clone)]
 pub struct Cursor {
     pub col: u16,
@@ -77,13 +84,16 @@ impl Cursor {
                 for _ in 0..steps {
                     let max_col = get_max_col(text_lines, self.row);
                     if self.col < max_col {
-                        self.col += 1;
+                        let line = get_line(text_lines, self.row);
+                        self.col =
+                            next_grapheme(&line, self.col as usize) as u16;
                     } else if self.row < get_max_row(text_lines) {
                         // Move to the beginning of the next line
                         self.row += 1;
                         if keep_desired {
-                            let max_col = get_max_col(text_lines, self.row);
-                            self.col = std::cmp::min(self.desired_col, max_col);
+                            let line = get_line(text_lines, self.row);
+                            self.col =
+                                col_at_width(&line, self.desired_col) as u16;
                         } else {
                             self.col = 0;
                         }
@@ -91,27 +101,30 @@ impl Cursor {
                         // cursor is at the end of the last line
                     }
                 }
-                self.desired_col = self.col;
+                self.update_desired_col(text_lines);
             }
             MoveCursor::Left(chars) => {
                 // Move the cursor to the left by the specified number of characters
                 for _ in 0..chars {
                     if self.col > 0 {
-                        self.col -= 1;
+                        let line = get_line(text_lines, self.row);
+                        self.col =
+                            prev_grapheme(&line, self.col as usize) as u16;
                     } else if self.row > 0 {
                         // Move to the end of the previous line
                         self.row -= 1;
                         self.col = get_max_col(text_lines, self.row);
                     }
                 }
+                self.update_desired_col(text_lines);
             }
             MoveCursor::Up(lines) => {
                 let current_row = self.row;
                 let new_row = self.row.saturating_sub(lines);
                 self.row = new_row;
 
-                let max_col = get_max_col(text_lines, self.row);
-                self.col = std::cmp::min(self.desired_col, max_col);
+                let line = get_line(text_lines, self.row);
+                self.col = col_at_width(&line, self.desired_col) as u16;
 
                 // If moving up a single line and the cursor cannot move further up,
                 // ensure the cursor moves to the start of the line
@@ -126,14 +139,14 @@ impl Cursor {
                     std::cmp::min(self.row.saturating_add(lines), max_row);
                 self.row = new_row;
 
-                let max_col = get_max_col(text_lines, self.row);
-                self.col = std::cmp::min(self.desired_col, max_col);
+                let line = get_line(text_lines, self.row);
+                self.col = col_at_width(&line, self.desired_col) as u16;
 
                 // when moving down a single line, and cant move further,
                 // move cursor to the end of the line
                 if lines == 1 && new_row == max_row && current_row == new_row {
-                    self.col = max_col;
-                    self.desired_col = max_col;
+                    self.col = get_max_col(text_lines, self.row);
+                    self.update_desired_col(text_lines);
                 }
             }
             MoveCursor::StartOfLine => {
@@ -142,7 +155,7 @@ impl Cursor {
             }
             MoveCursor::EndOfLine => {
                 self.col = get_max_col(text_lines, self.row);
-                self.desired_col = self.col;
+                self.update_desired_col(text_lines);
             }
             MoveCursor::StartOfFile => {
                 self.row = 0;
@@ -157,11 +170,39 @@ impl Cursor {
             MoveCursor::EndOfFileEndOfLine => {
                 self.row = max_row;
                 self.col = get_max_col(text_lines, self.row);
-                self.desired_col = self.col;
+                self.update_desired_col(text_lines);
             }
         }
     }
 
+    // places the cursor at a byte position in the text, e.g. at the end
+    // of inserted text
+    pub fn move_to_position(
+        &mut self,
+        position: usize,
+        text_lines: &[TextLine],
+    ) {
+        let mut line_start = 0;
+        for (row, line) in text_lines.iter().enumerate() {
+            let length = line.get_length();
+            if position <= line_start + length || row + 1 == text_lines.len() {
+                self.row = row as u16;
+                self.col =
+                    position.saturating_sub(line_start).min(length) as u16;
+                break;
+            }
+            line_start += length + 1; // account for newline character
+        }
+        self.update_desired_col(text_lines);
+    }
+
+    fn update_desired_col(&mut self, text_lines: &[TextLine]) {
+        let line = get_line(text_lines, self.row);
+        self.desired_col = line
+            .get(..self.col as usize)
+            .map_or(self.col, |text| text.width() as u16);
+    }
+
     pub fn set_anchor_position(&mut self) {
         self.anchor_col = self.col;
         self.anchor_row = self.row;
@@ -232,6 +273,40 @@ fn get_max_row(display_text: &[TextLine]) -> u16 {
     display_text.len().saturating_sub(1) as u16
 }
 
+fn get_line(lines: &[TextLine], row: u16) -> String {
+    lines
+        .get(row as usize)
+        .map(|line| line.to_string())
+        .unwrap_or_default()
+}
+
+// byte offset of the grapheme after the one at col
+pub fn next_grapheme(text: &str, col: usize) -> usize {
+    text.get(col..)
+        .and_then(|rest| rest.graphemes(true).next())
+        .map_or(col, |grapheme| col + grapheme.len())
+}
+
+// byte offset of the grapheme before col
+pub fn prev_grapheme(text: &str, col: usize) -> usize {
+    text.get(..col)
+        .and_then(|before| before.grapheme_indices(true).next_back())
+        .map_or(0, |(index, _)| index)
+}
+
+// byte offset of the last grapheme that starts at or before a display
+// width, a wide character is not split
+fn col_at_width(text: &str, width: u16) -> usize {
+    let mut current_width = 0;
+    for (index, grapheme) in text.grapheme_indices(true) {
+        current_width += grapheme.width();
+        if current_width > width as usize {
+            return index;
+        }
+    }
+    text.len()
+}
+
 pub fn get_max_col(lines: &[TextLine], row: u16) -> u16 {
     // Get the maximum column of a specific row. This is the line length + 1,
     // to account for either a newline character or empty space for the cursor.
@@ -242,3 +317,50 @@ pub fn get_max_col(lines: &[TextLine], row: u16) -> u16 {
         0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_lines(lines: &[&str]) -> Vec<TextLine> {
+        lines
+            .iter()
+            .map(|text| {
+                let mut line = TextLine::new();
+                line.add_segment(text.to_string(), None);
+                line
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_move_cursor_graphemes() {
+        // "e" with a combining accent, a family emoji (ZWJ sequence), CJK
+        let lines = text_lines(&["e\u{301}👨‍👩‍👧x", "中文字", "ab"]);
+        let mut cursor = Cursor::new(0, 0, true);
+
+        cursor.move_cursor(MoveCursor::Right(1), &lines, false);
+        assert_eq!(cursor.col, 3);
+        cursor.move_cursor(MoveCursor::Right(1), &lines, false);
+        assert_eq!(cursor.col, 21);
+        cursor.move_cursor(MoveCursor::Left(1), &lines, false);
+        assert_eq!(cursor.col, 3);
+
+        // display column 1 is the second half of "中"
+        cursor.move_cursor(MoveCursor::Down(1), &lines, false);
+        assert_eq!((cursor.row, cursor.col), (1, 0));
+        cursor.move_cursor(MoveCursor::Right(1), &lines, false);
+        assert_eq!(cursor.col, 3);
+        cursor.move_cursor(MoveCursor::Up(1), &lines, false);
+        assert_eq!((cursor.row, cursor.col), (0, 3));
+        cursor.move_cursor(MoveCursor::Down(1), &lines, false);
+        cursor.move_cursor(MoveCursor::Right(1), &lines, false);
+        assert_eq!((cursor.row, cursor.col), (1, 6));
+        // display column 4 is past the end of "ab"
+        cursor.move_cursor(MoveCursor::Down(1), &lines, false);
+        assert_eq!((cursor.row, cursor.col), (2, 2));
+
+        cursor.move_to_position(23, &lines);
+        assert_eq!((cursor.row, cursor.col), (1, 0));
+    }
+}
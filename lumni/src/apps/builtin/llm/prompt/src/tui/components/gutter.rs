@@ -0,0 +1,220 @@
+use ratatui::style::{Color, Style};
+use ratatui::text::Span;
+
+const MIN_NUMBER_WIDTH: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineNumbers {
+    Off,
+    Absolute,
+    Relative, // distance to the cursor line, as in vi
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineChange {
+    Added,
+    Modified,
+    Removed, // lines removed before this line
+}
+
+impl LineChange {
+    fn marker(&self) -> &'static str {
+        match self {
+            LineChange::Added => "+",
+            LineChange::Modified => "~",
+            LineChange::Removed => "_",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GutterConfig {
+    pub line_numbers: LineNumbers,
+    pub change_markers: bool,
+}
+
+impl Default for GutterConfig {
+    fn default() -> Self {
+        GutterConfig {
+            line_numbers: LineNumbers::Off,
+            change_markers: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GutterStyle {
+    pub number: Style,
+    pub cursor_number: Style,
+    pub added: Style,
+    pub modified: Style,
+    pub removed: Style,
+}
+
+impl Default for GutterStyle {
+    fn default() -> Self {
+        GutterStyle {
+            number: Style::default().fg(Color::DarkGray),
+            cursor_number: Style::default().fg(Color::White),
+            added: Style::default().fg(Color::Green),
+            modified: Style::default().fg(Color::Yellow),
+            removed: Style::default().fg(Color::Red),
+        }
+    }
+}
+
+// line numbers and change markers, left of the text
+#[derive(Debug, Clone, Default)]
+pub struct Gutter {
+    config: GutterConfig,
+    style: GutterStyle,
+}
+
+impl Gutter {
+    pub fn config(&self) -> GutterConfig {
+        self.config
+    }
+
+    pub fn set_config(&mut self, config: GutterConfig) {
+        self.config = config;
+    }
+
+    pub fn set_style(&mut self, style: GutterStyle) {
+        self.style = style;
+    }
+
+    fn number_width(&self, line_count: usize) -> usize {
+        match self.config.line_numbers {
+            LineNumbers::Off => 0,
+            _ => line_count.to_string().len().max(MIN_NUMBER_WIDTH),
+        }
+    }
+
+    pub fn width(&self, line_count: usize) -> usize {
+        let number_width = self.number_width(line_count);
+        let number_width = if number_width > 0 {
+            number_width + 1 // space between number and text
+        } else {
+            0
+        };
+        number_width + self.config.change_markers as usize
+    }
+
+    // gutter of a display row. wrapped lines only show the number on
+    // their first row
+    pub fn spans(
+        &self,
+        line_index: usize,
+        first_row: bool,
+        cursor_line: usize,
+        line_count: usize,
+        change: Option<LineChange>,
+    ) -> Vec<Span<'static>> {
+        let mut spans = Vec::new();
+        if self.config.change_markers {
+            spans.push(match change.filter(|_| first_row) {
+                Some(change) => {
+                    let style = match change {
+                        LineChange::Added => self.style.added,
+                        LineChange::Modified => self.style.modified,
+                        LineChange::Removed => self.style.removed,
+                    };
+                    Span::styled(change.marker(), style)
+                }
+                None => Span::raw(" "),
+            });
+        }
+        let number_width = self.number_width(line_count);
+        if number_width > 0 {
+            let number = match self.config.line_numbers {
+                _ if !first_row => String::new(),
+                LineNumbers::Relative if line_index != cursor_line => {
+                    line_index.abs_diff(cursor_line).to_string()
+                }
+                _ => (line_index + 1).to_string(),
+            };
+            let style = if line_index == cursor_line {
+                self.style.cursor_number
+            } else {
+                self.style.number
+            };
+            spans.push(Span::styled(
+                format!("{:>width$} ", number, width = number_width),
+                style,
+            ));
+        }
+        spans
+    }
+}
+
+// changes of lines compared to the baseline, per line. only the range
+// between the unchanged lines at the start and at the end is marked,
+// which is enough to show where a prompt is edited
+pub fn line_changes(
+    baseline: &[String],
+    lines: &[String],
+) -> Vec<Option<LineChange>> {
+    let prefix = baseline
+        .iter()
+        .zip(lines)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let max_suffix = baseline.len().min(lines.len()) - prefix;
+    let suffix = baseline
+        .iter()
+        .rev()
+        .zip(lines.iter().rev())
+        .take(max_suffix)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let removed = baseline.len() - prefix - suffix;
+
+    let mut changes = vec![None; lines.len()];
+    let changed = prefix..lines.len() - suffix;
+    if changed.is_empty() {
+        if removed > 0 && !lines.is_empty() {
+            changes[prefix.min(lines.len() - 1)] = Some(LineChange::Removed);
+        }
+    } else {
+        for index in changed {
+            changes[index] = if index - prefix < removed {
+                Some(LineChange::Modified)
+            } else {
+                Some(LineChange::Added)
+            };
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_line_changes() {
+        let baseline = lines("a\nb\nc");
+        assert_eq!(line_changes(&baseline, &baseline), vec![None; 3]);
+        assert_eq!(
+            line_changes(&baseline, &lines("a\nB\nx\nc")),
+            vec![
+                None,
+                Some(LineChange::Modified),
+                Some(LineChange::Added),
+                None
+            ]
+        );
+        assert_eq!(
+            line_changes(&baseline, &lines("a\nc")),
+            vec![None, Some(LineChange::Removed)]
+        );
+        assert_eq!(
+            line_changes(&[], &lines("a")),
+            vec![Some(LineChange::Added)]
+        );
+    }
+}
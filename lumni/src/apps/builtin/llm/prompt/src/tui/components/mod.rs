@@ -1,13 +1,16 @@
 mod cursor;
+mod gutter;
 mod piece_table;
 mod rect_area;
 mod scroller;
+mod spell_checker;
 mod text_buffer;
 mod text_window;
 mod text_wrapper;
 mod window_type;
 
 pub use cursor::MoveCursor;
+pub use gutter::{GutterStyle, LineNumbers};
 pub use scroller::Scroller;
 pub use text_buffer::{LineType, TextBuffer};
 pub use text_window::{TextWindow, TextWindowTrait};
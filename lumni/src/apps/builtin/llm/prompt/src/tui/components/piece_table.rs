@@ -1,4 +1,5 @@
 use ratatui::style::{Color, Style};
+use unicode_width::UnicodeWidthStr;
 
 #[derive(Clone, Debug, PartialEq)]
 enum Action {
@@ -78,6 +79,14 @@ impl TextLine {
         self.length
     }
 
+    // display width, e.g. CJK characters take two columns
+    pub fn get_width(&self) -> usize {
+        self.segments
+            .iter()
+            .map(|segment| segment.text.width())
+            .sum()
+    }
+
     pub fn get_background(&self) -> Option<Color> {
         self.background
     }
@@ -18,6 +18,14 @@ impl RectArea {
         }
     }
 
+    pub fn x(&self) -> u16 {
+        self.x
+    }
+
+    pub fn y(&self) -> u16 {
+        self.y
+    }
+
     pub fn width(&self) -> u16 {
         self.width
     }
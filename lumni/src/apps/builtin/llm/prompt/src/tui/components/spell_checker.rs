@@ -0,0 +1,383 @@
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::{env, fs, io};
+
+use regex::Regex;
+use unicode_segmentation::UnicodeSegmentation;
+
+// common english words and terms used in prompts, base forms only
+const EMBEDDED_WORDS: &str =
+    include_str!("../../../templates/spell_words.txt");
+// path to a hunspell .dic file, the .aff file is expected next to it
+const DICTIONARY_ENV: &str = "LUMNI_SPELL_DICTIONARY";
+const HUNSPELL_PATHS: [&str; 3] = [
+    "/usr/share/hunspell/en_US.dic",
+    "/usr/share/myspell/en_US.dic",
+    "/usr/share/myspell/dicts/en_US.dic",
+];
+const MAX_EDIT_DISTANCE: usize = 2;
+
+static SHARED_SPELL_CHECKER: OnceLock<Arc<SpellChecker>> = OnceLock::new();
+
+#[derive(Debug)]
+struct Affix {
+    flag: char,
+    is_suffix: bool,
+    strip: String,
+    add: String,
+    condition: Regex,
+}
+
+impl Affix {
+    fn apply(&self, word: &str) -> Option<String> {
+        if !self.condition.is_match(word) {
+            return None;
+        }
+        if self.is_suffix {
+            let stem = word.strip_suffix(self.strip.as_str())?;
+            Some(format!("{}{}", stem, self.add))
+        } else {
+            let stem = word.strip_prefix(self.strip.as_str())?;
+            Some(format!("{}{}", self.add, stem))
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SpellChecker {
+    words: HashSet<String>,
+}
+
+impl SpellChecker {
+    pub fn embedded() -> Self {
+        SpellChecker {
+            words: EMBEDDED_WORDS
+                .lines()
+                .filter(|line| !line.starts_with('#'))
+                .flat_map(str::split_whitespace)
+                .map(String::from)
+                .collect(),
+        }
+    }
+
+    // words of a hunspell dictionary, expanded with the prefix and suffix
+    // rules of its .aff file. only single character flags are supported,
+    // and affixes are not combined
+    pub fn from_hunspell(dic_path: &Path) -> io::Result<Self> {
+        let dic = fs::read_to_string(dic_path)?;
+        let affixes = fs::read_to_string(dic_path.with_extension("aff"))
+            .map(|aff| parse_affixes(&aff))
+            .unwrap_or_default();
+        let mut words = HashSet::new();
+        // first line is the number of words
+        for entry in dic.lines().skip(1) {
+            let entry = entry.split_whitespace().next().unwrap_or_default();
+            let (word, flags) = entry.split_once('/').unwrap_or((entry, ""));
+            if word.is_empty() {
+                continue;
+            }
+            let word = word.to_lowercase();
+            for affix in affixes.iter().filter(|a| flags.contains(a.flag)) {
+                if let Some(derived) = affix.apply(&word) {
+                    words.insert(derived);
+                }
+            }
+            words.insert(word);
+        }
+        Ok(SpellChecker { words })
+    }
+
+    // loaded once, from the dictionary in LUMNI_SPELL_DICTIONARY or the
+    // system hunspell dictionary, else the embedded word list
+    pub fn shared() -> Arc<SpellChecker> {
+        SHARED_SPELL_CHECKER
+            .get_or_init(|| Arc::new(SpellChecker::load()))
+            .clone()
+    }
+
+    fn load() -> Self {
+        let paths = env::var_os(DICTIONARY_ENV)
+            .map(PathBuf::from)
+            .into_iter()
+            .chain(HUNSPELL_PATHS.iter().map(PathBuf::from));
+        for path in paths.filter(|path| path.is_file()) {
+            match SpellChecker::from_hunspell(&path) {
+                Ok(checker) if !checker.words.is_empty() => return checker,
+                _ => log::warn!("Failed to load dictionary {:?}", path),
+            }
+        }
+        SpellChecker::embedded()
+    }
+
+    pub fn check(&self, word: &str) -> bool {
+        let word = word.to_lowercase().replace('\u{2019}', "'");
+        if self.words.contains(&word) {
+            return true;
+        }
+        let word = word.strip_suffix("'s").unwrap_or(&word);
+        self.words.contains(word)
+            || stems(word).iter().any(|stem| self.words.contains(stem))
+    }
+
+    // closest words first, in the case of the given word
+    pub fn suggestions(&self, word: &str, max_count: usize) -> Vec<String> {
+        let target: Vec<char> = word.to_lowercase().chars().collect();
+        let mut candidates: Vec<(usize, &String)> = self
+            .words
+            .iter()
+            .filter(|candidate| {
+                candidate.chars().count().abs_diff(target.len())
+                    <= MAX_EDIT_DISTANCE
+            })
+            .map(|candidate| (edit_distance(&target, candidate), candidate))
+            .filter(|(distance, _)| *distance <= MAX_EDIT_DISTANCE)
+            .collect();
+        candidates.sort();
+        candidates
+            .into_iter()
+            .take(max_count)
+            .map(|(_, candidate)| match_case(word, candidate))
+            .collect()
+    }
+
+    // byte ranges of misspelled words, per line. code blocks, inline code,
+    // and words that look like identifiers, paths or addresses are skipped
+    pub fn misspelled(&self, lines: &[String]) -> Vec<Vec<Range<usize>>> {
+        let mut in_code_block = false;
+        lines
+            .iter()
+            .map(|line| {
+                if line.trim_start().starts_with("```") {
+                    in_code_block = !in_code_block;
+                    return Vec::new();
+                }
+                if in_code_block {
+                    return Vec::new();
+                }
+                self.misspelled_in_line(line)
+            })
+            .collect()
+    }
+
+    fn misspelled_in_line(&self, line: &str) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        let mut offset = 0;
+        // text between backticks is inline code
+        for (index, part) in line.split('`').enumerate() {
+            if index % 2 == 0 {
+                let mut chunk_start = None;
+                let end = std::iter::once((part.len(), ' '));
+                for (position, c) in part.char_indices().chain(end) {
+                    match (c.is_whitespace(), chunk_start) {
+                        (false, None) => chunk_start = Some(position),
+                        (true, Some(start)) => {
+                            self.check_chunk(
+                                &part[start..position],
+                                offset + start,
+                                &mut ranges,
+                            );
+                            chunk_start = None;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            offset += part.len() + 1;
+        }
+        ranges
+    }
+
+    fn check_chunk(
+        &self,
+        chunk: &str,
+        offset: usize,
+        ranges: &mut Vec<Range<usize>>,
+    ) {
+        if chunk.contains("://") || chunk.contains(['@', '/', '\\']) {
+            return;
+        }
+        for (start, word) in chunk.unicode_word_indices() {
+            if is_checked_word(word) && !self.check(word) {
+                ranges.push(offset + start..offset + start + word.len());
+            }
+        }
+    }
+}
+
+// words with digits, underscores or inner capitals are likely identifiers
+// (e.g. camelCase, or an acronym), single letters are skipped as well
+fn is_checked_word(word: &str) -> bool {
+    let mut chars = word.chars();
+    chars.next().is_some_and(char::is_alphabetic)
+        && word.chars().nth(1).is_some()
+        && !chars.any(|c| c.is_uppercase() || c.is_numeric() || c == '_')
+        && !word.contains('.')
+}
+
+// base forms of a word with a regular english suffix, the embedded word
+// list only has base forms
+fn stems(word: &str) -> Vec<String> {
+    const SUFFIXES: [(&str, &[&str]); 15] = [
+        ("ies", &["y"]),
+        ("ied", &["y"]),
+        ("ier", &["y"]),
+        ("iest", &["y"]),
+        ("ily", &["y"]),
+        ("es", &[""]),
+        ("s", &[""]),
+        ("ed", &["", "e"]),
+        ("ing", &["", "e"]),
+        ("er", &["", "e"]),
+        ("est", &["", "e"]),
+        ("ly", &[""]),
+        ("ness", &[""]),
+        ("ment", &[""]),
+        ("able", &["", "e"]),
+    ];
+    let mut stems = Vec::new();
+    for (suffix, endings) in SUFFIXES {
+        let Some(base) = word.strip_suffix(suffix) else {
+            continue;
+        };
+        if base.chars().count() < 2 {
+            continue;
+        }
+        for ending in endings {
+            stems.push(format!("{}{}", base, ending));
+        }
+        // doubled consonant, e.g. running or stopped
+        let mut last = base.chars().rev();
+        if let (Some(a), Some(b)) = (last.next(), last.next()) {
+            if a == b && !"aeiou".contains(a) {
+                stems.push(base[..base.len() - a.len_utf8()].to_string());
+            }
+        }
+    }
+    stems
+}
+
+fn match_case(word: &str, suggestion: &str) -> String {
+    let mut chars = word.chars();
+    let first_upper = chars.next().is_some_and(char::is_uppercase);
+    if first_upper && chars.clone().count() > 0 && chars.all(char::is_uppercase)
+    {
+        return suggestion.to_uppercase();
+    }
+    if first_upper {
+        let mut suggestion_chars = suggestion.chars();
+        if let Some(first) = suggestion_chars.next() {
+            return first.to_uppercase().chain(suggestion_chars).collect();
+        }
+    }
+    suggestion.to_string()
+}
+
+// optimal string alignment distance, i.e. the edits to get from one word
+// to the other, where swapping two adjacent letters counts as one edit
+fn edit_distance(source: &[char], target: &str) -> usize {
+    let target: Vec<char> = target.chars().collect();
+    let mut rows = vec![vec![0; target.len() + 1]; source.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=target.len()).collect();
+    for i in 1..=source.len() {
+        for j in 1..=target.len() {
+            let cost = (source[i - 1] != target[j - 1]) as usize;
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1
+                && j > 1
+                && source[i - 1] == target[j - 2]
+                && source[i - 2] == target[j - 1]
+            {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+    rows[source.len()][target.len()]
+}
+
+// PFX and SFX rules, e.g. "SFX D y ied [^aeiou]y". "0" means empty, and
+// flags for combined affixes ("ied/X") are ignored
+fn parse_affixes(aff: &str) -> Vec<Affix> {
+    aff.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let is_suffix = match fields.first() {
+                Some(&"SFX") => true,
+                Some(&"PFX") => false,
+                _ => return None,
+            };
+            if fields.len() < 5 {
+                return None; // header of a rule set
+            }
+            let flag = fields[1].chars().next()?;
+            let empty_if_zero = |field: &str| {
+                let field = field.split('/').next().unwrap_or_default();
+                if field == "0" {
+                    String::new()
+                } else {
+                    field.to_lowercase()
+                }
+            };
+            let condition = if is_suffix {
+                format!("{}$", fields[4])
+            } else {
+                format!("^{}", fields[4])
+            };
+            Some(Affix {
+                flag,
+                is_suffix,
+                strip: empty_if_zero(fields[2]),
+                add: empty_if_zero(fields[3]),
+                condition: Regex::new(&condition).ok()?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_misspelled() {
+        let checker = SpellChecker::embedded();
+        assert!(checker.check("Running"));
+        assert!(checker.check("user's"));
+        assert!(!checker.check("recieve"));
+        // from the header of the word list
+        assert!(!checker.check("hunspell"));
+        assert_eq!(checker.suggestions("Recieve", 1), vec!["Receive"]);
+
+        let lines: Vec<String> = [
+            "Plese read the `fooo` file at ~/fooo/barr",
+            "```",
+            "let fooo = barr;",
+            "```",
+            "camelCase and HTTP are ignord",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+        assert_eq!(
+            checker.misspelled(&lines),
+            vec![vec![0..5], vec![], vec![], vec![], vec![23..29]]
+        );
+    }
+
+    #[test]
+    fn test_hunspell_affixes() {
+        let affixes =
+            parse_affixes("SFX D Y 2\nSFX D 0 d e\nSFX D y ied [^aeiou]y");
+        assert_eq!(affixes.len(), 2);
+        assert_eq!(affixes[0].apply("bake"), Some("baked".to_string()));
+        assert_eq!(affixes[1].apply("copy"), Some("copied".to_string()));
+        assert_eq!(affixes[1].apply("play"), None);
+    }
+}
@@ -1,8 +1,15 @@
-use ratatui::style::{Color, Style};
+use std::ops::Range;
+use std::sync::Arc;
+
+use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Masked, Span};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
-use super::cursor::{Cursor, MoveCursor};
+use super::cursor::{next_grapheme, Cursor, MoveCursor};
+use super::gutter::{line_changes, LineChange};
 use super::piece_table::{PieceTable, TextLine};
+use super::spell_checker::SpellChecker;
 use super::text_wrapper::TextWrapper;
 
 #[allow(dead_code)]
@@ -51,6 +58,19 @@ pub enum LineType {
     Code(CodeBlockLine),
 }
 
+// wraps each line to the display width, does not depend on the
+// TextBuffer so it can run on another thread
+pub fn wrap_text_lines(
+    text_lines: &[TextLine],
+    display_width: usize,
+) -> Vec<Vec<TextLine>> {
+    let text_wrapper = TextWrapper::new(display_width);
+    text_lines
+        .iter()
+        .map(|line| text_wrapper.wrap_text_styled(line))
+        .collect()
+}
+
 #[derive(Debug, Clone)]
 struct LineSegment<'a> {
     line: Line<'a>,              // wrapped line segment
@@ -184,6 +204,9 @@ pub struct TextBuffer<'a> {
     cursor: Cursor,
     code_blocks: Vec<CodeBlock>, // code blocks
     is_editable: bool,
+    baseline: Vec<String>, // lines to compare with for change markers
+    spell_checker: Option<Arc<SpellChecker>>, // None if disabled
+    misspelled: Vec<Vec<Range<usize>>>, // byte ranges per line
 }
 
 impl TextBuffer<'_> {
@@ -195,6 +218,9 @@ impl TextBuffer<'_> {
             cursor: Cursor::new(0, 0, false),
             code_blocks: Vec::new(),
             is_editable,
+            baseline: Vec::new(),
+            spell_checker: None,
+            misspelled: Vec::new(),
         }
     }
 
@@ -221,6 +247,22 @@ impl TextBuffer<'_> {
         self.display.get_column_row()
     }
 
+    // cursor as (display width, row) in the wrapped text. the column of
+    // get_column_row is a byte offset, which differs for wide characters
+    pub fn cursor_display_position(&self) -> (usize, usize) {
+        let (column, row) = self.display.get_column_row();
+        let width = self.display.wrap_lines().get(row).map_or(0, |segment| {
+            let text: String = segment
+                .line
+                .spans
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect();
+            text.get(..column).unwrap_or(&text).width()
+        });
+        (width, row)
+    }
+
     pub fn is_empty(&self) -> bool {
         self.text.is_empty()
     }
@@ -229,6 +271,7 @@ impl TextBuffer<'_> {
         self.display.clear();
         self.cursor = Cursor::new(0, 0, self.is_editable);
         self.text.empty();
+        self.baseline.clear();
         // update display
         self.update_display_text();
     }
@@ -237,39 +280,113 @@ impl TextBuffer<'_> {
         self.display.set_display_width(width);
     }
 
+    pub fn display_width(&self) -> usize {
+        self.display.width()
+    }
+
+    // unwrapped line the cursor is on
+    pub fn cursor_line(&self) -> usize {
+        self.cursor.row as usize
+    }
+
+    // byte offset of the cursor in its line
+    pub fn cursor_column(&self) -> usize {
+        self.cursor.col as usize
+    }
+
+    // byte offset of the cursor in the text
+    pub fn cursor_position(&self) -> usize {
+        self.cursor.real_position()
+    }
+
+    pub fn set_cursor_position(&mut self, position: usize) {
+        let text_lines = self.text.text_lines().to_vec();
+        self.cursor.move_to_position(position, &text_lines);
+        self.update_display_text();
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.text.text_lines().len().max(1)
+    }
+
+    pub fn line_changes(&self) -> Vec<Option<LineChange>> {
+        line_changes(&self.baseline, &self.lines())
+    }
+
+    pub fn set_spell_checker(
+        &mut self,
+        spell_checker: Option<Arc<SpellChecker>>,
+    ) {
+        self.spell_checker = spell_checker;
+        self.update_display_text();
+    }
+
+    pub fn spell_checker(&self) -> Option<&Arc<SpellChecker>> {
+        self.spell_checker.as_ref()
+    }
+
+    // misspelled word at (or right after) the cursor, as
+    // (line index, byte range, word)
+    pub fn misspelled_at_cursor(
+        &self,
+    ) -> Option<(usize, Range<usize>, String)> {
+        let row = self.cursor.row as usize;
+        let col = self.cursor.col as usize;
+        let range = self
+            .misspelled
+            .get(row)?
+            .iter()
+            .find(|range| range.contains(&col) || range.end == col)?
+            .clone();
+        let line = self.text.text_lines().get(row)?.to_string();
+        let word = line.get(range.clone())?.to_string();
+        Some((row, range, word))
+    }
+
+    // replaces a byte range of a line, e.g. a misspelled word with a
+    // suggestion. the cursor moves to the start of the new text
+    pub fn text_replace(
+        &mut self,
+        line: usize,
+        range: Range<usize>,
+        text: &str,
+    ) {
+        let line_start: usize = self
+            .text
+            .text_lines()
+            .iter()
+            .take(line)
+            .map(|text_line| text_line.to_string().len() + 1)
+            .sum();
+        let idx = line_start + range.start;
+        self.text.delete(idx, range.len());
+        self.text.insert(idx, text, None, false);
+        self.text.update_if_modified();
+        let text_lines = self.text.text_lines().to_vec();
+        self.cursor.move_to_position(idx, &text_lines);
+        self.update_display_text();
+    }
+
+    fn lines(&self) -> Vec<String> {
+        self.text
+            .text_lines()
+            .iter()
+            .map(|line| line.to_string())
+            .collect()
+    }
+
     pub fn text_insert_add(&mut self, text: &str, style: Option<Style>) {
         // Get the current cursor position in the underlying (unwrapped) text buffer
         let idx = self.cursor.real_position();
         self.text.insert(idx, text, style, false);
-        self.update_display_text();
-
-        // Calculate the number of newlines and the length of the last line segment
-        let mut newlines = 0;
-        let mut last_line_length = 0;
-        for ch in text.chars() {
-            if ch == '\n' {
-                newlines += 1;
-                last_line_length = 0; // Reset line length counter after each newline
-            } else {
-                last_line_length += 1; // Increment line length for non-newline characters
-            }
-        }
+        self.text.update_if_modified();
 
-        if newlines > 0 {
-            // Move the cursor to the end of the inserted text
-            self.move_cursor(MoveCursor::Down(newlines as u16), true);
-            self.move_cursor(MoveCursor::StartOfLine, true); // Move to the start of the new line
-            if last_line_length > 0 {
-                // Then move right to the end of the inserted text on the last line
-                self.move_cursor(
-                    MoveCursor::Right(last_line_length as u16),
-                    true,
-                );
-            }
-        } else {
-            // If no newlines, just move right
-            self.move_cursor(MoveCursor::Right(text.len() as u16), true);
-        }
+        // Move the cursor to the end of the inserted text. This is done by
+        // position rather than by steps, as inserted text can merge with
+        // the text around it into a single grapheme (e.g. combining marks)
+        let text_lines = self.text.text_lines().to_vec();
+        self.cursor.move_to_position(idx + text.len(), &text_lines);
+        self.update_display_text();
     }
 
     pub fn text_append(&mut self, text: &str, style: Option<Style>) {
@@ -284,28 +401,41 @@ impl TextBuffer<'_> {
             return; // nothing to delete
         }
 
-        let start_idx = if include_cursor {
-            idx //  start at the highlighed (cursor) character
-        } else if idx > 0 {
-            idx - 1 // start at the character before the cursor
+        // characters are counted as graphemes, so e.g. an emoji or a
+        // letter with its accents is deleted as a whole
+        let text = self.to_string();
+        let (start_idx, length) = if include_cursor {
+            //  start at the highlighed (cursor) character
+            let length = text.get(idx..).map_or(0, |after| {
+                after.graphemes(true).take(char_count).map(str::len).sum()
+            });
+            (idx, length)
         } else {
-            return;
+            // start at the character before the cursor
+            let length: usize = text.get(..idx).map_or(0, |before| {
+                before
+                    .graphemes(true)
+                    .rev()
+                    .take(char_count)
+                    .map(str::len)
+                    .sum()
+            });
+            (idx - length, length)
         };
+        if length == 0 {
+            return;
+        }
 
-        self.text.delete(start_idx, char_count);
-
-        if include_cursor {
-            // delete rightwards from the cursor
-            // extra update is required to get correct text after deletion,
-            self.update_display_text();
+        self.text.delete(start_idx, length);
+        self.text.update_if_modified();
+        let text_lines = self.text.text_lines().to_vec();
+        self.cursor.move_to_position(start_idx, &text_lines);
+        self.update_display_text();
 
-            // check if the cursor is at the end of the line
-            if self.cursor.col as usize >= self.to_string().len() {
-                self.move_cursor(MoveCursor::Left(char_count as u16), false);
-            }
-        } else {
-            // delete leftwards from the cursor
-            self.move_cursor(MoveCursor::Left(char_count as u16), true);
+        // check if the cursor is at the end of the line
+        if include_cursor && self.cursor.col as usize >= self.to_string().len()
+        {
+            self.move_cursor(MoveCursor::Left(char_count as u16), false);
         }
     }
 
@@ -478,13 +608,28 @@ impl TextBuffer<'_> {
                 // Iterate over the lines within the selection
                 for (idx, line) in lines.iter().enumerate() {
                     let line_str = line.to_string();
-                    if idx == 0 {
+                    if lines.len() == 1 {
+                        // Single row: get the text from start_col to end_col
+                        let end_col_inclusive =
+                            next_grapheme(&line_str, end_col);
+                        selected_lines.push(
+                            line_str
+                                .get(start_col..end_col_inclusive)
+                                .unwrap_or_default()
+                                .to_string(),
+                        );
+                    } else if idx == 0 {
                         // First row: get the text from start_col to the end
-                        selected_lines.push(line_str[start_col..].to_string());
+                        selected_lines.push(
+                            line_str
+                                .get(start_col..)
+                                .unwrap_or_default()
+                                .to_string(),
+                        );
                     } else if idx == lines.len() - 1 {
                         // Last row: get the text from 0 to end_col
                         let end_col_inclusive =
-                            (end_col + 1).min(line_str.len());
+                            next_grapheme(&line_str, end_col);
                         selected_lines
                             .push(line_str[..end_col_inclusive].to_string());
                     } else {
@@ -621,7 +766,86 @@ impl TextBuffer<'_> {
 
     pub fn update_display_text(&mut self) {
         self.text.update_if_modified();
-        self.display.clear();
+        let text_lines = self.display_text_lines();
+        let wrapped_lines = wrap_text_lines(&text_lines, self.display.width());
+        self.render_wrapped_lines(&text_lines, wrapped_lines);
+    }
+
+    // text to wrap for a new display width, used to wrap it elsewhere
+    // (e.g. off the render thread) and apply it with set_wrapped_text
+    pub fn text_to_wrap(&mut self) -> Vec<TextLine> {
+        self.text.update_if_modified();
+        self.display_text_lines()
+    }
+
+    // applies text wrapped by wrap_text_lines, returns false if the text
+    // changed since text_to_wrap, as the wrapped text is outdated then
+    pub fn set_wrapped_text(
+        &mut self,
+        width: usize,
+        text_lines: Vec<TextLine>,
+        wrapped_lines: Vec<Vec<TextLine>>,
+    ) -> bool {
+        self.text.update_if_modified();
+        if self.display_text_lines() != text_lines {
+            return false;
+        }
+        self.display.set_display_width(width);
+        self.render_wrapped_lines(&text_lines, wrapped_lines);
+        true
+    }
+
+    // index of the unwrapped line that is displayed on the given row
+    pub fn unwrapped_line_index(&self, row: usize) -> usize {
+        self.display
+            .wrap_lines()
+            .iter()
+            .take(row)
+            .filter(|line| line.last_segment)
+            .count()
+    }
+
+    // (unwrapped line index, is first row of the line) of count display
+    // rows from start
+    pub fn display_line_rows(
+        &self,
+        start: usize,
+        count: usize,
+    ) -> Vec<(usize, bool)> {
+        let lines = self.display.wrap_lines();
+        let mut line_index = self.unwrapped_line_index(start);
+        let mut first_row =
+            start == 0 || lines.get(start - 1).is_some_and(|l| l.last_segment);
+        lines
+            .iter()
+            .skip(start)
+            .take(count)
+            .map(|line| {
+                let row = (line_index, first_row);
+                first_row = line.last_segment;
+                if line.last_segment {
+                    line_index += 1;
+                }
+                row
+            })
+            .collect()
+    }
+
+    // first display row of the given unwrapped line
+    pub fn display_row(&self, line_index: usize) -> usize {
+        let mut line_count = 0;
+        for (row, line) in self.display.wrap_lines().iter().enumerate() {
+            if line_count == line_index {
+                return row;
+            }
+            if line.last_segment {
+                line_count += 1;
+            }
+        }
+        self.display.wrap_lines().len().saturating_sub(1)
+    }
+
+    fn display_text_lines(&self) -> Vec<TextLine> {
         let mut text_lines = self.text.text_lines().to_vec();
 
         if text_lines.is_empty() && !self.placeholder.is_empty() {
@@ -631,28 +855,37 @@ impl TextBuffer<'_> {
             line_styled.add_segment(self.placeholder.clone(), Some(style));
             text_lines.push(line_styled);
         }
+        text_lines
+    }
+
+    fn render_wrapped_lines(
+        &mut self,
+        text_lines: &[TextLine],
+        wrapped_lines: Vec<Vec<TextLine>>,
+    ) {
+        self.display.clear();
+        let misspelled = match &self.spell_checker {
+            Some(spell_checker) if !self.text.is_empty() => {
+                let lines: Vec<String> =
+                    text_lines.iter().map(|line| line.to_string()).collect();
+                spell_checker.misspelled(&lines)
+            }
+            _ => Vec::new(),
+        };
 
         // Get the bounds of selected text, position based on unwrapped lines
         // (start_row, start_col, end_row, end_col)
         let selection_bounds = self.get_selection_bounds();
 
-        let text_wrapper = TextWrapper::new(self.display.width());
-        // debug text lines including newlines
-        //let total_length: usize = text_lines.iter().map(|l| l.length() + 1).sum::<usize>().saturating_sub(1);
-        //eprintln!("Text lines:\n{}|{}", text_lines.iter().map(|l| l.to_string()).collect::<Vec<String>>().join("\n"), total_length);
-
-        for (idx, line) in text_lines.iter().enumerate() {
+        for (idx, (line, wrapped_lines)) in
+            text_lines.iter().zip(wrapped_lines).enumerate()
+        {
             let text_str =
                 line.segments().map(|s| s.text()).collect::<String>();
 
             let trailing_spaces =
                 text_str.len() - text_str.trim_end_matches(' ').len();
 
-            let wrapped_lines = text_wrapper.wrap_text_styled(line);
-
-            // debug wrapped lines
-            //eprintln!("Wrapped lines: {:?}", wrapped_lines.iter().map(|l| l.to_string()).collect::<Vec<String>>());
-
             // length of the wrapped lines content
             if wrapped_lines.is_empty() {
                 self.handle_empty_line(trailing_spaces, line.get_background());
@@ -662,14 +895,16 @@ impl TextBuffer<'_> {
                     wrapped_lines,
                     idx,
                     &selection_bounds,
+                    misspelled.get(idx).map_or(&[], Vec::as_slice),
                     trailing_spaces,
                     line.get_background(),
                 );
             }
         }
 
+        self.misspelled = misspelled;
         self.mark_code_blocks();
-        self.cursor.update_real_position(&text_lines);
+        self.cursor.update_real_position(text_lines);
         self.update_cursor_style();
     }
 
@@ -716,6 +951,7 @@ impl TextBuffer<'_> {
         wrapped_lines: Vec<TextLine>,
         unwrapped_line_index: usize,
         selection_bounds: &(usize, usize, usize, usize),
+        misspelled: &[Range<usize>], // same position scheme as selection
         // trailing spaces of the unwrapped line are removed during wrapping,
         // this is added back to the first and last (wrapped) line respectively
         trailing_spaces: usize,
@@ -727,10 +963,10 @@ impl TextBuffer<'_> {
         for (idx, line) in wrapped_lines.iter().enumerate() {
             let mut spans = Vec::new();
 
-            // Start character position for this line from the cumulative offset
+            // Start byte position for this line from the cumulative offset,
+            // one span per grapheme so combining marks stay with their base
             for segment in line.segments() {
-                let chars: Vec<char> = segment.text().chars().collect();
-                for ch in chars.into_iter() {
+                for grapheme in segment.text().graphemes(true) {
                     // Adjust row based on the index in wrapped lines
                     let should_select = self.cursor.should_select(
                         unwrapped_line_index,
@@ -746,8 +982,16 @@ impl TextBuffer<'_> {
                     if should_select {
                         effective_style = effective_style.bg(Color::Blue);
                     }
-                    spans.push(Span::styled(ch.to_string(), effective_style));
-                    char_pos += 1;
+                    if misspelled.iter().any(|range| range.contains(&char_pos))
+                    {
+                        effective_style =
+                            effective_style.add_modifier(Modifier::UNDERLINED);
+                    }
+                    spans.push(Span::styled(
+                        grapheme.to_string(),
+                        effective_style,
+                    ));
+                    char_pos += grapheme.len();
                 }
             }
 
@@ -801,10 +1045,13 @@ impl TextBuffer<'_> {
                     let span_length = span.content.len();
                     if line_column < span_length {
                         // Split the span at the cursor position
+                        // highlight the whole grapheme at the cursor
+                        let cursor_end =
+                            next_grapheme(&span.content, line_column);
                         let before = &span.content[..line_column];
                         let cursor_char =
-                            &span.content[line_column..line_column + 1];
-                        let after = &span.content[line_column + 1..];
+                            &span.content[line_column..cursor_end];
+                        let after = &span.content[cursor_end..];
 
                         if !before.is_empty() {
                             new_spans.push(Span::styled(
@@ -1,22 +1,62 @@
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use futures::FutureExt;
 use ratatui::layout::{Alignment, Rect};
 use ratatui::style::Style;
 use ratatui::text::Text;
 use ratatui::widgets::block::Padding;
 use ratatui::widgets::{Block, Paragraph, ScrollbarState};
+use tokio::task::JoinHandle;
 
 use super::cursor::MoveCursor;
+use super::gutter::{Gutter, GutterConfig, GutterStyle};
+use super::piece_table::TextLine;
 use super::rect_area::RectArea;
 use super::scroller::Scroller;
-use super::text_buffer::{CodeBlock, LineType};
+use super::spell_checker::SpellChecker;
+use super::text_buffer::{wrap_text_lines, CodeBlock, LineType};
 use super::window_type::Highlighted;
 use super::{TextBuffer, WindowStatus, WindowType};
 
+// wait for the terminal size to settle before re-wrapping the text
+const RESIZE_DEBOUNCE_MILLIS: u64 = 100;
+
+type WrapResult = (Vec<TextLine>, Vec<Vec<TextLine>>);
+
+// width change that is not applied to the text yet
+#[derive(Debug)]
+struct PendingResize {
+    width: usize,
+    since: Instant,
+    wrap_job: Option<JoinHandle<WrapResult>>, // started after the debounce
+}
+
+impl PendingResize {
+    fn new(width: usize) -> Self {
+        PendingResize {
+            width,
+            since: Instant::now(),
+            wrap_job: None,
+        }
+    }
+}
+
+impl Clone for PendingResize {
+    fn clone(&self) -> Self {
+        // a clone wraps the text again, the job can not be shared
+        PendingResize::new(self.width)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TextWindow<'a> {
     area: RectArea,
     window_type: WindowType,
     scroller: Scroller,
     text_buffer: TextBuffer<'a>,
+    resize: Option<PendingResize>,
+    gutter: Gutter,
 }
 
 impl<'a> TextWindow<'a> {
@@ -26,6 +66,8 @@ impl<'a> TextWindow<'a> {
             window_type,
             scroller: Scroller::new(),
             text_buffer: TextBuffer::new(window_type.is_editable()),
+            resize: None,
+            gutter: Gutter::default(),
         }
     }
 
@@ -146,6 +188,19 @@ impl<'a> TextWindow<'a> {
         }
     }
 
+    // cursor position on the screen, inside the borders. None when the
+    // cursor is scrolled out of view
+    pub fn cursor_screen_position(&self) -> Option<(u16, u16)> {
+        let (width, row) = self.text_buffer.cursor_display_position();
+        let row = row.checked_sub(self.scroller.vertical_scroll)?;
+        if row >= self.area.height() as usize {
+            return None;
+        }
+        let column = (self.gutter_width() + width) as u16;
+        let column = column.min(self.area.width().saturating_sub(1));
+        Some((self.area.x() + 1 + column, self.area.y() + 1 + row as u16))
+    }
+
     pub fn text_buffer(&mut self) -> &mut TextBuffer<'a> {
         &mut self.text_buffer
     }
@@ -170,11 +225,77 @@ impl<'a> TextWindow<'a> {
         }
     }
 
+    pub fn gutter_config(&self) -> GutterConfig {
+        self.gutter.config()
+    }
+
+    pub fn set_gutter_config(&mut self, config: GutterConfig) {
+        if self.window_type.has_gutter() {
+            // text is re-wrapped to the new width on the next render
+            self.gutter.set_config(config);
+        }
+    }
+
+    pub fn set_gutter_style(&mut self, style: GutterStyle) {
+        self.gutter.set_style(style);
+    }
+
+    pub fn spell_check(&self) -> bool {
+        self.text_buffer.spell_checker().is_some()
+    }
+
+    // only windows that are typed in are checked
+    pub fn set_spell_check(&mut self, enable: bool) {
+        if self.window_type.is_editable() && enable != self.spell_check() {
+            self.text_buffer
+                .set_spell_checker(enable.then(SpellChecker::shared));
+        }
+    }
+
+    // hidden when the window is too narrow to show text next to it
+    fn gutter_width(&self) -> usize {
+        let width = self.gutter.width(self.text_buffer.line_count());
+        if width < self.area.width() as usize {
+            width
+        } else {
+            0
+        }
+    }
+
+    // width available for the text
+    fn text_width(&self) -> usize {
+        (self.area.width() as usize).saturating_sub(self.gutter_width())
+    }
+
+    // width the text is wrapped to, or is being wrapped to
+    fn wrap_width(&self) -> usize {
+        self.resize
+            .as_ref()
+            .map_or(self.text_buffer.display_width(), |resize| resize.width)
+    }
+
     pub fn widget<'b>(&'b mut self, area: &Rect) -> Paragraph<'b> {
-        if self.area.update(area) == true {
-            // re-fit text to updated display
-            self.text_buffer.set_width(self.area.width() as usize);
-            self.text_buffer.update_display_text();
+        let previous_width = self.wrap_width();
+        let area_changed = self.area.update(area);
+        let width = self.text_width();
+        if width != previous_width {
+            if previous_width == 0 || self.text_buffer.is_empty() {
+                // nothing to re-wrap yet, fit text directly
+                self.text_buffer.set_width(width);
+                self.text_buffer.update_display_text();
+                self.resize = None;
+            } else {
+                // re-wrapped in poll_resize, once resizing stops. until
+                // then the text is shown as wrapped before
+                self.resize = Some(PendingResize::new(width));
+            }
+        } else if area_changed {
+            if self.scroller.auto_scroll {
+                // only the height changed, keep following the text
+                self.scroll_to_end();
+            } else {
+                self.update_scroll_bar();
+            }
         }
 
         let mut block = Block::default()
@@ -188,10 +309,34 @@ impl<'a> TextWindow<'a> {
         }
 
         let start_idx = self.scroller.vertical_scroll;
-        let window_text = self.text_buffer.display_window_lines(
+        let mut window_text = self.text_buffer.display_window_lines(
             start_idx,
             start_idx + self.area.height() as usize,
         );
+        if self.gutter_width() > 0 {
+            let cursor_line = self.text_buffer.cursor_line();
+            let line_count = self.text_buffer.line_count();
+            let changes = if self.gutter.config().change_markers {
+                self.text_buffer.line_changes()
+            } else {
+                Vec::new()
+            };
+            let rows = self
+                .text_buffer
+                .display_line_rows(start_idx, window_text.len());
+            for (line, (line_index, first_row)) in
+                window_text.iter_mut().zip(rows)
+            {
+                let spans = self.gutter.spans(
+                    line_index,
+                    first_row,
+                    cursor_line,
+                    line_count,
+                    changes.get(line_index).copied().flatten(),
+                );
+                line.spans.splice(0..0, spans);
+            }
+        }
 
         Paragraph::new(Text::from(window_text))
             .block(block)
@@ -199,6 +344,80 @@ impl<'a> TextWindow<'a> {
             .alignment(Alignment::Left)
     }
 
+    // re-wraps the text after a resize, on a blocking thread so large
+    // conversations do not stall rendering. returns true when the display
+    // is updated and needs to be redrawn
+    pub fn poll_resize(&mut self) -> bool {
+        let resize = match self.resize.as_mut() {
+            Some(resize) => resize,
+            None => return false,
+        };
+        let wrap_job = match resize.wrap_job.as_ref() {
+            Some(wrap_job) => wrap_job,
+            None => {
+                let debounce = Duration::from_millis(RESIZE_DEBOUNCE_MILLIS);
+                if resize.since.elapsed() >= debounce {
+                    let text_lines = self.text_buffer.text_to_wrap();
+                    let width = resize.width;
+                    resize.wrap_job =
+                        Some(tokio::task::spawn_blocking(move || {
+                            let wrapped = wrap_text_lines(&text_lines, width);
+                            (text_lines, wrapped)
+                        }));
+                }
+                return false;
+            }
+        };
+        if !wrap_job.is_finished() {
+            return false;
+        }
+
+        let resize = self.resize.take().expect("resize is pending");
+        let anchor = self.scroll_anchor();
+        let applied = match resize.wrap_job.and_then(|job| job.now_or_never()) {
+            Some(Ok((text_lines, wrapped_lines))) => self
+                .text_buffer
+                .set_wrapped_text(resize.width, text_lines, wrapped_lines),
+            _ => false,
+        };
+        if !applied {
+            // text changed while it was wrapped, e.g. a response that is
+            // streaming, wrap it directly instead
+            self.text_buffer.set_width(resize.width);
+            self.text_buffer.update_display_text();
+        }
+        self.restore_scroll_anchor(anchor);
+        true
+    }
+
+    // None when following the end of the text, otherwise the (unwrapped)
+    // line at the top of the window
+    fn scroll_anchor(&self) -> Option<usize> {
+        if self.scroller.auto_scroll {
+            None
+        } else {
+            Some(
+                self.text_buffer
+                    .unwrapped_line_index(self.scroller.vertical_scroll),
+            )
+        }
+    }
+
+    fn restore_scroll_anchor(&mut self, anchor: Option<usize>) {
+        match anchor {
+            None => self.scroll_to_end(),
+            Some(line_index) => {
+                let end_scroll = self
+                    .text_buffer
+                    .display_lines_len()
+                    .saturating_sub(self.area.height() as usize);
+                self.scroller.vertical_scroll =
+                    self.text_buffer.display_row(line_index).min(end_scroll);
+                self.update_scroll_bar();
+            }
+        }
+    }
+
     pub fn text_insert_add(&mut self, text: &str, style: Option<Style>) {
         // inserted text is added at cursor position
         self.text_buffer.text_insert_add(text, style);
@@ -223,6 +442,47 @@ impl<'a> TextWindow<'a> {
         self.text_buffer.text_delete(include_cursor, count);
         self.scroll_to_cursor();
     }
+
+    pub fn set_cursor_position(&mut self, position: usize) {
+        self.text_buffer.set_cursor_position(position);
+        self.scroll_to_cursor();
+    }
+
+    pub fn text_replace(
+        &mut self,
+        line: usize,
+        range: Range<usize>,
+        text: &str,
+    ) {
+        self.text_buffer.text_replace(line, range, text);
+        self.scroll_to_cursor();
+    }
+
+    // unwrapped line shown at a screen position, None outside the text
+    pub fn line_at_screen_position(&self, x: u16, y: u16) -> Option<usize> {
+        let column = x.checked_sub(self.area.x() + 1)?;
+        let row = y.checked_sub(self.area.y() + 1)?;
+        if column >= self.area.width() || row >= self.area.height() {
+            return None;
+        }
+        let row = self.scroller.vertical_scroll + row as usize;
+        if row >= self.text_buffer.display_lines_len() {
+            return None;
+        }
+        Some(self.text_buffer.unwrapped_line_index(row))
+    }
+
+    // moves the cursor to the start of an unwrapped line
+    pub fn set_cursor_line(&mut self, line: usize) {
+        let position = self
+            .text_buffer
+            .to_string()
+            .split('\n')
+            .take(line)
+            .map(|text| text.len() + 1)
+            .sum();
+        self.set_cursor_position(position);
+    }
 }
 
 pub trait TextWindowTrait<'a> {
@@ -251,6 +511,30 @@ pub trait TextWindowTrait<'a> {
         base.widget(area)
     }
 
+    fn poll_resize(&mut self) -> bool {
+        self.base().poll_resize()
+    }
+
+    fn cursor_screen_position(&mut self) -> Option<(u16, u16)> {
+        self.base().cursor_screen_position()
+    }
+
+    fn gutter_config(&mut self) -> GutterConfig {
+        self.base().gutter_config()
+    }
+
+    fn set_gutter_config(&mut self, config: GutterConfig) {
+        self.base().set_gutter_config(config);
+    }
+
+    fn set_gutter_style(&mut self, style: GutterStyle) {
+        self.base().set_gutter_style(style);
+    }
+
+    fn set_spell_check(&mut self, enable: bool) {
+        self.base().set_spell_check(enable);
+    }
+
     fn vertical_scroll_bar_state<'b>(&'b mut self) -> &'b mut ScrollbarState
     where
         'a: 'b,
@@ -295,6 +579,22 @@ pub trait TextWindowTrait<'a> {
         self.base().text_append_with_insert(text, style);
     }
 
+    fn text_replace(&mut self, line: usize, range: Range<usize>, text: &str) {
+        self.base().text_replace(line, range, text);
+    }
+
+    fn line_at_screen_position(&mut self, x: u16, y: u16) -> Option<usize> {
+        self.base().line_at_screen_position(x, y)
+    }
+
+    fn set_cursor_line(&mut self, line: usize) {
+        self.base().set_cursor_line(line);
+    }
+
+    fn set_cursor_position(&mut self, position: usize) {
+        self.base().set_cursor_position(position);
+    }
+
     fn text_set(&mut self, text: &str, style: Option<Style>) {
         self.text_empty();
         self.text_insert_add(text, style)
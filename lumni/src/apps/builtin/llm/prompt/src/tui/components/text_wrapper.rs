@@ -1,5 +1,10 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
 use super::piece_table::{TextLine, TextSegment};
 
+// widths are display widths, so wide characters (e.g. CJK) count as two
+// columns, and lines are only split between graphemes
 pub struct TextWrapper {
     display_width: usize,
 }
@@ -67,11 +72,11 @@ impl TextWrapper {
 
             let space_len = if !current_text.is_empty() { 1 } else { 0 };
 
-            let needs_wrapping = current_text.len()
+            let needs_wrapping = current_text.width()
                 + space_len
                 + leading_spaces.len()
-                + word.len()
-                + current_line.get_length()
+                + word.width()
+                + current_line.get_width()
                 > max_width;
 
             if needs_wrapping {
@@ -81,7 +86,7 @@ impl TextWrapper {
                     current_line,
                 );
 
-                if leading_spaces.len() + word.len() > max_width {
+                if leading_spaces.len() + word.width() > max_width {
                     // word is too long to fit on a single line
                     self.handle_long_word(
                         &leading_spaces,
@@ -96,7 +101,7 @@ impl TextWrapper {
                     if !leading_spaces.is_empty() {
                         // Calculate the number of spaces that can be added
                         let available_spaces =
-                            max_width.saturating_sub(current_line.get_length());
+                            max_width.saturating_sub(current_line.get_width());
                         let spaces_to_add =
                             available_spaces.min(leading_spaces.len());
 
@@ -155,14 +160,20 @@ impl TextWrapper {
         max_width: usize,
     ) {
         let mut current_text = leading_spaces.to_string();
-        let mut start_index = 0;
-
-        while start_index < word.len() {
-            let end_index = std::cmp::min(
-                (start_index + max_width).saturating_sub(current_text.len()),
-                word.len(),
-            );
-            let slice = &word[start_index..end_index];
+        let mut graphemes = word.graphemes(true).peekable();
+
+        while graphemes.peek().is_some() {
+            let mut slice = String::new();
+            let mut width = current_text.len();
+            while let Some(grapheme) = graphemes.peek() {
+                // at least one grapheme per line, even if it does not fit
+                if width + grapheme.width() > max_width && !slice.is_empty() {
+                    break;
+                }
+                width += grapheme.width();
+                slice.push_str(grapheme);
+                graphemes.next();
+            }
 
             if !current_line.is_empty() {
                 wrapped_lines.push(current_line.clone());
@@ -170,12 +181,11 @@ impl TextWrapper {
             }
 
             current_line.add_segment(
-                current_text.clone() + slice,
+                current_text.clone() + &slice,
                 segment.style().clone(),
             );
             wrapped_lines.push(current_line.clone());
             *current_line = TextLine::new();
-            start_index = end_index;
             current_text.clear();
         }
     }
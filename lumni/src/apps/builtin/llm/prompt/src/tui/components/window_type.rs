@@ -87,6 +87,15 @@ impl WindowType {
         }
     }
 
+    // line numbers and change markers can be shown
+    pub fn has_gutter(&self) -> bool {
+        match self.kind {
+            WindowKind::ResponseWindow => true,
+            WindowKind::PromptWindow => true,
+            WindowKind::CommandLine => false,
+        }
+    }
+
     pub fn is_editable(&self) -> bool {
         match self.kind {
             WindowKind::ResponseWindow => false,
@@ -1,9 +1,11 @@
 use std::io;
 
 use ratatui::backend::Backend;
-use ratatui::layout::{Constraint, Direction, Layout, Rect};
-use ratatui::widgets::{Scrollbar, ScrollbarOrientation};
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Style};
+use ratatui::widgets::{Paragraph, Scrollbar, ScrollbarOrientation};
 use ratatui::Terminal;
+use tracing::info_span;
 
 use super::components::TextWindowTrait;
 use super::TabSession;
@@ -12,6 +14,7 @@ pub fn draw_ui<B: Backend>(
     terminal: &mut Terminal<B>,
     tab: &mut TabSession,
 ) -> Result<(), io::Error> {
+    let _span = info_span!("render").entered();
     terminal.draw(|frame| {
         let terminal_size = frame.size();
         const COMMAND_LINE_HEIGHT: u16 = 3;
@@ -28,7 +31,16 @@ pub fn draw_ui<B: Backend>(
             ])
             .split(terminal_size);
 
-        let command_line_area = main_window[1];
+        let status_line = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Min(0),     // command line
+                Constraint::Length(10), // status indicators
+            ])
+            .split(main_window[1]);
+
+        let command_line_area = status_line[0];
+        let status_area = status_line[1];
 
         let window = Layout::default()
             .direction(Direction::Vertical)
@@ -80,9 +92,40 @@ pub fn draw_ui<B: Backend>(
             command_line_area,
         );
 
+        if let Some(language) = tab.chat.response_language() {
+            frame.render_widget(
+                Paragraph::new(format!("[{}]", language.code()))
+                    .style(Style::default().fg(Color::DarkGray))
+                    .alignment(Alignment::Right),
+                status_area,
+            );
+        }
+
         if let Some(modal) = &mut tab.ui.modal {
             let area = modal_area(main_window[0]);
             modal.render_on_frame(frame, area);
+        } else if let Some(menu) = &tab.ui.message_menu {
+            let cursor = tab
+                .ui
+                .response
+                .cursor_screen_position()
+                .unwrap_or((prompt_log_area.x + 1, prompt_log_area.y + 1));
+            let area = menu.area(cursor, terminal_size);
+            frame.render_widget(menu, area);
+        } else if let Some(suggestions) = &tab.ui.spell_suggestions {
+            let cursor = tab
+                .ui
+                .prompt
+                .cursor_screen_position()
+                .unwrap_or((prompt_edit_area.x + 1, prompt_edit_area.y + 1));
+            let area = suggestions.area(cursor, terminal_size);
+            frame.render_widget(suggestions, area);
+        } else if tab.ui.prompt.is_status_insert() {
+            // the terminal draws the preedit text of an input method at
+            // its own cursor, keep it where text is inserted
+            if let Some((x, y)) = tab.ui.prompt.cursor_screen_position() {
+                frame.set_cursor_position((x, y));
+            }
         }
     })?;
     Ok(())
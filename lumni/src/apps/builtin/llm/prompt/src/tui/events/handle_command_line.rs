@@ -3,10 +3,13 @@ use std::sync::Arc;
 
 use crossterm::event::KeyCode;
 
+use super::handle_response_window::run_message_action;
 use super::key_event::KeyTrack;
 use super::text_window_event::handle_text_window_event;
 use super::{
-    ModalWindowType, PromptAction, TabUi, TextWindowTrait, WindowEvent,
+    parse_retention, LineNumbers, MessageAction, ModalWindowType, PromptAction,
+    PromptRole, Snippet, SnippetLibrary, TabUi, TextWindowTrait, TrashCommand,
+    Vote, WindowEvent, SNIPPET_PREFIX,
 };
 
 pub fn handle_command_line_event(
@@ -50,9 +53,184 @@ pub fn handle_command_line_event(
                     "clear" => {
                         return Some(WindowEvent::Prompt(PromptAction::Clear))
                     }
+                    "new" => {
+                        return Some(WindowEvent::Prompt(PromptAction::New))
+                    }
                     "stop" => {
                         return Some(WindowEvent::Prompt(PromptAction::Stop));
                     }
+                    "vault" => {
+                        return Some(WindowEvent::Prompt(PromptAction::Vault));
+                    }
+                    "stats" => {
+                        return Some(WindowEvent::Prompt(PromptAction::Stats));
+                    }
+                    "capabilities" => {
+                        return Some(WindowEvent::Prompt(
+                            PromptAction::Capabilities,
+                        ));
+                    }
+                    "reconnect" => {
+                        return Some(WindowEvent::Prompt(
+                            PromptAction::Reconnect,
+                        ));
+                    }
+                    "telemetry" => match tab_ui.set_telemetry_modal() {
+                        Ok(()) => {
+                            return Some(WindowEvent::Modal(
+                                ModalWindowType::Text,
+                            ))
+                        }
+                        Err(message) => {
+                            tab_ui.command_line.text_set(&message, None)
+                        }
+                    },
+                    command
+                        if command.split_whitespace().next()
+                            == Some("notify") =>
+                    {
+                        // optional webhook name, e.g. ":notify team-chat"
+                        let name =
+                            command.split_whitespace().nth(1).map(String::from);
+                        return Some(WindowEvent::Prompt(
+                            PromptAction::Notify(name),
+                        ));
+                    }
+                    command
+                        if command.split_whitespace().next()
+                            == Some("email") =>
+                    {
+                        // e.g. ":email someone@example.com"
+                        match command.split_whitespace().nth(1) {
+                            Some(address) => {
+                                return Some(WindowEvent::Prompt(
+                                    PromptAction::Email(address.to_string()),
+                                ));
+                            }
+                            None => tab_ui
+                                .command_line
+                                .text_set("Usage: :email <address>", None),
+                        }
+                    }
+                    command
+                        if command.split_whitespace().next()
+                            == Some("snippet") =>
+                    {
+                        let message =
+                            manage_snippets(&mut tab_ui.snippets, command);
+                        tab_ui.command_line.text_set(&message, None);
+                    }
+                    command
+                        if command.split_whitespace().next()
+                            == Some("message") =>
+                    {
+                        // e.g. ":message copy 2", without a number it
+                        // applies to the message at the response cursor
+                        match parse_message_command(tab_ui, command) {
+                            Ok((action, index, role)) => {
+                                return run_message_action(
+                                    tab_ui, action, index, role,
+                                );
+                            }
+                            Err(message) => {
+                                tab_ui.command_line.text_set(&message, None)
+                            }
+                        }
+                    }
+                    command
+                        if command.split_whitespace().next()
+                            == Some("trash") =>
+                    {
+                        match parse_trash_command(command) {
+                            Ok(trash_command) => {
+                                return Some(WindowEvent::Prompt(
+                                    PromptAction::Trash(trash_command),
+                                ));
+                            }
+                            Err(message) => {
+                                tab_ui.command_line.text_set(&message, None)
+                            }
+                        }
+                    }
+                    command
+                        if command.split_whitespace().next()
+                            == Some("annotate") =>
+                    {
+                        // ":annotate 2 some note", no note removes it
+                        let args = command["annotate".len()..].trim_start();
+                        let (number, note) = args
+                            .split_once(char::is_whitespace)
+                            .unwrap_or((args, ""));
+                        match number.parse::<usize>() {
+                            Ok(number) if number > 0 => {
+                                return Some(WindowEvent::Prompt(
+                                    PromptAction::Annotate(
+                                        number - 1,
+                                        note.trim().to_string(),
+                                    ),
+                                ));
+                            }
+                            _ => tab_ui.command_line.text_set(
+                                "Usage: :annotate <message> [note]",
+                                None,
+                            ),
+                        }
+                    }
+                    command
+                        if command.split_whitespace().next()
+                            == Some("vote") =>
+                    {
+                        match parse_vote_command(command) {
+                            Ok(action) => {
+                                return Some(WindowEvent::Prompt(action));
+                            }
+                            Err(message) => {
+                                tab_ui.command_line.text_set(&message, None)
+                            }
+                        }
+                    }
+                    command
+                        if command.split_whitespace().next()
+                            == Some("profile") =>
+                    {
+                        // e.g. ":profile work", edit a profile. a server
+                        // name gives the settings of a new one
+                        let mut args = command.split_whitespace().skip(1);
+                        match (args.next(), args.next(), args.next()) {
+                            (Some(name), server, None) => {
+                                match tab_ui.set_settings_modal(name, server) {
+                                    Ok(()) => {
+                                        return Some(WindowEvent::Modal(
+                                            ModalWindowType::Settings,
+                                        ));
+                                    }
+                                    Err(message) => tab_ui
+                                        .command_line
+                                        .text_set(&message, None),
+                                }
+                            }
+                            _ => tab_ui.command_line.text_set(
+                                "Usage: :profile <name> [server]",
+                                None,
+                            ),
+                        }
+                    }
+                    command
+                        if command.split_whitespace().next() == Some("set") =>
+                    {
+                        // e.g. ":set relativenumber markers", applies to
+                        // the window the command line was opened from
+                        let options: Vec<&str> =
+                            command.split_whitespace().skip(1).collect();
+                        let result = if tab_ui.response.is_active() {
+                            set_window_options(&mut tab_ui.response, &options)
+                        } else {
+                            set_window_options(&mut tab_ui.prompt, &options)
+                        };
+                        if let Err(message) = result {
+                            tab_ui.command_line.text_set(&message, None);
+                        }
+                    }
                     _ => {} // command not recognized
                 }
             }
@@ -71,3 +249,166 @@ pub fn handle_command_line_event(
         ),
     }
 }
+
+// (action, exchange index, role) of a ":message <action> [number]" command.
+// a number selects the answer of that exchange
+fn parse_message_command(
+    tab_ui: &mut TabUi,
+    command: &str,
+) -> Result<(MessageAction, usize, PromptRole), String> {
+    let mut args = command.split_whitespace().skip(1);
+    let names: Vec<&str> = MessageAction::ALL
+        .iter()
+        .map(|action| action.name())
+        .collect();
+    let usage = format!("Usage: :message <{}> [number]", names.join("|"));
+    let action = args
+        .next()
+        .and_then(MessageAction::from_name)
+        .ok_or_else(|| usage.clone())?;
+    match args.next() {
+        Some(number) => match number.parse::<usize>() {
+            Ok(number) if number > 0 && number <= tab_ui.messages.len() => {
+                Ok((action, number - 1, PromptRole::Assistant))
+            }
+            _ => Err(format!("No message {}", number)),
+        },
+        None => tab_ui
+            .message_at_cursor()
+            .map(|(index, role)| (action, index, role))
+            .ok_or_else(|| "No message at the cursor".to_string()),
+    }
+}
+
+// ":trash" shows the deleted messages, ":trash restore 2" puts one back,
+// ":trash purge [2]" deletes one or all for good and ":trash retention 7d"
+// sets how long they are kept
+fn parse_trash_command(command: &str) -> Result<TrashCommand, String> {
+    let usage =
+        "Usage: :trash [restore <n> | purge [n] | retention <7d|12h|30m>]";
+    let args: Vec<&str> = command.split_whitespace().skip(1).collect();
+    let number = |arg: &str| match arg.parse::<usize>() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err(usage.to_string()),
+    };
+    match args.as_slice() {
+        [] | ["list"] => Ok(TrashCommand::List),
+        ["restore", arg] => Ok(TrashCommand::Restore(number(arg)?)),
+        ["purge"] => Ok(TrashCommand::Purge(None)),
+        ["purge", arg] => Ok(TrashCommand::Purge(Some(number(arg)?))),
+        ["retention", arg] => parse_retention(arg)
+            .map(TrashCommand::Retention)
+            .ok_or_else(|| usage.to_string()),
+        _ => Err(usage.to_string()),
+    }
+}
+
+// ":vote 2 up", ":vote 2 down too verbose" or ":vote 2 clear", a reason
+// replaces the note on the message
+fn parse_vote_command(command: &str) -> Result<PromptAction, String> {
+    let usage = "Usage: :vote <message> <up|down|clear> [reason]";
+    let mut args = command["vote".len()..].trim_start().splitn(3, ' ');
+    let index = match args.next().map(|number| number.parse::<usize>()) {
+        Some(Ok(number)) if number > 0 => number - 1,
+        _ => return Err(usage.to_string()),
+    };
+    let vote = match args.next() {
+        Some("clear") => None,
+        Some(name) => Some(Vote::from_str(name).ok_or(usage)?),
+        None => return Err(usage.to_string()),
+    };
+    let reason = args.next().unwrap_or_default().trim().to_string();
+    Ok(PromptAction::Vote(index, vote, reason))
+}
+
+// vi style options for the line numbers, change markers and spell
+// checking of a window
+fn set_window_options<'a, T>(
+    window: &mut T,
+    options: &[&str],
+) -> Result<(), String>
+where
+    T: TextWindowTrait<'a>,
+{
+    let mut config = window.gutter_config();
+    for option in options {
+        match *option {
+            "number" | "nu" => config.line_numbers = LineNumbers::Absolute,
+            "relativenumber" | "rnu" => {
+                config.line_numbers = LineNumbers::Relative
+            }
+            "nonumber" | "nonu" | "norelativenumber" | "nornu" => {
+                config.line_numbers = LineNumbers::Off
+            }
+            "markers" => config.change_markers = true,
+            "nomarkers" => config.change_markers = false,
+            "spell" | "nospell" if !window.window_type().is_editable() => {
+                return Err("Spell checking applies to the prompt".to_string())
+            }
+            "spell" => window.set_spell_check(true),
+            "nospell" => window.set_spell_check(false),
+            _ => return Err(format!("Unknown option: {}", option)),
+        }
+    }
+    window.set_gutter_config(config);
+    Ok(())
+}
+
+// ":snippet" lists the snippets, ":snippet add sig Regards,\n$1" adds or
+// replaces one, and ":snippet rm sig" removes it. if another instance
+// changed the snippets, ":snippet reload" or ":snippet overwrite" resolves
+// the conflict
+fn manage_snippets(snippets: &mut SnippetLibrary, command: &str) -> String {
+    let args = command.trim_start_matches("snippet").trim_start();
+    let (action, args) =
+        args.split_once(char::is_whitespace).unwrap_or((args, ""));
+    let (trigger, body) = args
+        .trim_start()
+        .split_once(char::is_whitespace)
+        .unwrap_or((args.trim_start(), ""));
+    let trigger = trigger.trim_start_matches(SNIPPET_PREFIX);
+    match action {
+        "" | "list" if snippets.snippets().is_empty() => {
+            "No snippets, add one with :snippet add <trigger> <text>"
+                .to_string()
+        }
+        "" | "list" => {
+            let triggers: Vec<String> = snippets
+                .snippets()
+                .iter()
+                .map(|snippet| {
+                    format!("{}{}", SNIPPET_PREFIX, snippet.trigger())
+                })
+                .collect();
+            format!("Snippets: {}", triggers.join(" "))
+        }
+        "add" if !trigger.is_empty() && !body.trim().is_empty() => {
+            // the command line has a single line, "\n" is a line break
+            let body = body.trim_start().replace("\\n", "\n");
+            match snippets.set(Snippet::new(trigger, &body)) {
+                Ok(()) => {
+                    format!("Saved snippet {}{}", SNIPPET_PREFIX, trigger)
+                }
+                Err(e) => e.to_string(),
+            }
+        }
+        "rm" if !trigger.is_empty() => match snippets.remove(trigger) {
+            Ok(true) => {
+                format!("Removed snippet {}{}", SNIPPET_PREFIX, trigger)
+            }
+            Ok(false) => format!("No snippet {}{}", SNIPPET_PREFIX, trigger),
+            Err(e) => e.to_string(),
+        },
+        "reload" => match snippets.reload() {
+            Ok(()) => "Reloaded the snippets".to_string(),
+            Err(e) => e.to_string(),
+        },
+        "overwrite" if snippets.has_conflict() => match snippets.overwrite() {
+            Ok(()) => "Saved the snippets".to_string(),
+            Err(e) => e.to_string(),
+        },
+        _ => "Usage: :snippet [list | add <trigger> <text> | rm <trigger> \
+              | reload | overwrite]"
+            .to_string(),
+    }
+}
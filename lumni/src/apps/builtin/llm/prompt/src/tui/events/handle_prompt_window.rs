@@ -6,7 +6,9 @@ use crossterm::event::{KeyCode, KeyModifiers};
 use super::key_event::KeyTrack;
 use super::text_window_event::handle_text_window_event;
 use super::{
-    LineType, PromptAction, PromptWindow, TabUi, TextWindowTrait, WindowEvent,
+    LineType, PromptAction, PromptWindow, Snippet, SnippetSession,
+    SpellSuggestions, TabUi, TextWindowTrait, WindowEvent, MAX_SUGGESTIONS,
+    SNIPPET_PREFIX,
 };
 
 pub fn handle_prompt_window_event(
@@ -14,6 +16,9 @@ pub fn handle_prompt_window_event(
     key_track: &mut KeyTrack,
     is_running: Arc<AtomicBool>,
 ) -> Option<WindowEvent> {
+    if tab_ui.spell_suggestions.is_some() {
+        return handle_spell_suggestions_event(tab_ui, key_track);
+    }
     if key_track.current_key().modifiers == KeyModifiers::CONTROL {
         // catch Ctrl + shortcut key
         match key_track.current_key().code {
@@ -22,6 +27,7 @@ pub fn handle_prompt_window_event(
                     return Some(WindowEvent::Quit);
                 } else {
                     tab_ui.prompt.text_empty();
+                    tab_ui.reply_to = None;
                 }
             }
             KeyCode::Char('q') => {
@@ -44,6 +50,7 @@ pub fn handle_prompt_window_event(
                     || !in_editing_block(&mut tab_ui.prompt)
                 {
                     ensure_closed_block(&mut tab_ui.prompt);
+                    tab_ui.snippet_session = None;
                     let question = tab_ui.prompt.text_buffer().to_string();
                     tab_ui.prompt.text_empty();
                     return Some(WindowEvent::Prompt(PromptAction::Write(
@@ -55,9 +62,23 @@ pub fn handle_prompt_window_event(
                 if tab_ui.prompt.is_status_insert() {
                     ensure_closed_block(&mut tab_ui.prompt);
                 }
+                tab_ui.snippet_session = None;
+            }
+            // expand a snippet trigger, or move to the next field of the
+            // snippet. otherwise Tab leaves insert mode
+            KeyCode::Tab
+                if tab_ui.prompt.is_status_insert()
+                    && (expand_snippet(tab_ui) || next_tab_stop(tab_ui)) =>
+            {
+                return Some(WindowEvent::PromptWindow);
             }
-            KeyCode::Tab => {
-                // TODO: tab inside
+            KeyCode::Char('=')
+                if !tab_ui.prompt.is_status_insert()
+                    && key_track.previous_key_str() == Some("z") =>
+            {
+                // suggest corrections for the word at the cursor, as in vi
+                open_spell_suggestions(tab_ui);
+                return Some(WindowEvent::PromptWindow);
             }
             _ => {}
         }
@@ -65,6 +86,109 @@ pub fn handle_prompt_window_event(
     handle_text_window_event(key_track, &mut tab_ui.prompt, is_running)
 }
 
+// expands the snippet of the trigger before the cursor, e.g. ";sig"
+fn expand_snippet(tab_ui: &mut TabUi) -> bool {
+    let text_buffer = tab_ui.prompt.text_buffer();
+    let row = text_buffer.cursor_line();
+    let column = text_buffer.cursor_column();
+    let line = text_buffer.yank_lines(1).pop().unwrap_or_default();
+    let before = line.get(..column).unwrap_or_default();
+    let Some(prefix) = before.rfind(SNIPPET_PREFIX) else {
+        return false;
+    };
+    let trigger = &before[prefix + SNIPPET_PREFIX.len_utf8()..];
+    let at_word_start = before[..prefix]
+        .chars()
+        .next_back()
+        .is_none_or(char::is_whitespace);
+    if !at_word_start
+        || trigger.is_empty()
+        || trigger.contains(char::is_whitespace)
+    {
+        return false;
+    }
+    let Some(expansion) = tab_ui.snippets.get(trigger).map(Snippet::expand)
+    else {
+        return false;
+    };
+    let start = text_buffer.cursor_position() - (column - prefix);
+    tab_ui
+        .prompt
+        .text_replace(row, prefix..column, &expansion.text);
+    let text_length = tab_ui.prompt.text_buffer().to_string().len();
+    tab_ui.snippet_session = Some(SnippetSession::new(
+        start,
+        &expansion.tab_stops,
+        text_length,
+    ));
+    next_tab_stop(tab_ui);
+    true
+}
+
+// moves the cursor to the end of the next field, so its default text can
+// be kept or changed
+fn next_tab_stop(tab_ui: &mut TabUi) -> bool {
+    let Some(session) = tab_ui.snippet_session.as_mut() else {
+        return false;
+    };
+    let text_length = tab_ui.prompt.text_buffer().to_string().len();
+    let stop = session.next_stop(text_length);
+    if session.is_done() {
+        tab_ui.snippet_session = None;
+    }
+    match stop {
+        Some(stop) => {
+            tab_ui.prompt.set_cursor_position(stop.end);
+            true
+        }
+        None => false,
+    }
+}
+
+fn open_spell_suggestions(tab_ui: &mut TabUi) {
+    let text_buffer = tab_ui.prompt.text_buffer();
+    let Some(spell_checker) = text_buffer.spell_checker().cloned() else {
+        tab_ui
+            .command_line
+            .text_set("Spell checking is off, enable with :set spell", None);
+        return;
+    };
+    let Some((line, range, word)) = text_buffer.misspelled_at_cursor() else {
+        return; // word is spelled correctly
+    };
+    let suggestions = spell_checker.suggestions(&word, MAX_SUGGESTIONS);
+    if suggestions.is_empty() {
+        let message = format!("No suggestions for {}", word);
+        tab_ui.command_line.text_set(&message, None);
+    } else {
+        tab_ui.spell_suggestions =
+            Some(SpellSuggestions::new(line, range, suggestions));
+    }
+}
+
+// keys go to the suggestion popup while it is open
+fn handle_spell_suggestions_event(
+    tab_ui: &mut TabUi,
+    key_track: &mut KeyTrack,
+) -> Option<WindowEvent> {
+    let suggestions = tab_ui.spell_suggestions.as_mut()?;
+    match key_track.current_key().code {
+        KeyCode::Down | KeyCode::Char('j') => suggestions.key_down(),
+        KeyCode::Up | KeyCode::Char('k') => suggestions.key_up(),
+        KeyCode::Enter => {
+            let selected = suggestions
+                .selected()
+                .map(|(line, range, text)| (line, range, text.to_string()));
+            tab_ui.spell_suggestions = None;
+            if let Some((line, range, text)) = selected {
+                tab_ui.prompt.text_replace(line, range, &text);
+            }
+        }
+        _ => tab_ui.spell_suggestions = None, // e.g. Esc
+    }
+    Some(WindowEvent::PromptWindow)
+}
+
 fn is_closed_block(prompt_window: &mut PromptWindow) -> Option<bool> {
     // return None if not inside a block
     // return Some(true) if block is closed, else return Some(false)
@@ -4,14 +4,20 @@ use std::sync::Arc;
 use crossterm::event::{KeyCode, KeyModifiers};
 
 use super::key_event::KeyTrack;
-use super::text_window_event::handle_text_window_event;
-use super::{PromptAction, TabUi, TextWindowTrait, WindowEvent};
+use super::text_window_event::{handle_text_window_event, write_to_clipboard};
+use super::{
+    quote, CommandLineAction, MessageAction, PromptAction, PromptRole, TabUi,
+    TextWindowTrait, WindowEvent,
+};
 
 pub fn handle_response_window_event(
     tab_ui: &mut TabUi,
     key_track: &mut KeyTrack,
     is_running: Arc<AtomicBool>,
 ) -> Option<WindowEvent> {
+    if tab_ui.message_menu.is_some() {
+        return handle_message_menu_event(tab_ui, key_track);
+    }
     if key_track.current_key().modifiers == KeyModifiers::CONTROL {
         // catch Ctrl + shortcut key
         match key_track.current_key().code {
@@ -32,5 +38,108 @@ pub fn handle_response_window_event(
         }
         return Some(WindowEvent::ResponseWindow);
     }
+    match key_track.current_key().code {
+        _ if key_track.leader_key_set() => {}
+        KeyCode::Char('m') => {
+            // actions for the message at the cursor
+            if !tab_ui.open_message_menu() {
+                tab_ui
+                    .command_line
+                    .text_set("No message at the cursor", None);
+            }
+            return Some(WindowEvent::ResponseWindow);
+        }
+        KeyCode::Char('>') => {
+            // reply to the message at the cursor, or the selection in it
+            let Some((index, role)) = tab_ui.message_at_cursor() else {
+                tab_ui
+                    .command_line
+                    .text_set("No message at the cursor", None);
+                return Some(WindowEvent::ResponseWindow);
+            };
+            return run_message_action(
+                tab_ui,
+                MessageAction::QuoteReply,
+                index,
+                role,
+            );
+        }
+        _ => {}
+    }
     handle_text_window_event(key_track, &mut tab_ui.response, is_running)
 }
+
+fn handle_message_menu_event(
+    tab_ui: &mut TabUi,
+    key_track: &mut KeyTrack,
+) -> Option<WindowEvent> {
+    let menu = tab_ui.message_menu.as_mut()?;
+    let action = match key_track.current_key().code {
+        KeyCode::Down | KeyCode::Char('j') => {
+            menu.key_down();
+            return Some(WindowEvent::ResponseWindow);
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            menu.key_up();
+            return Some(WindowEvent::ResponseWindow);
+        }
+        KeyCode::Enter => Some(menu.selected()),
+        KeyCode::Char(c) => MessageAction::from_key(c),
+        _ => None,
+    };
+    let (index, role) = (menu.index(), menu.role());
+    // any other key closes the menu
+    tab_ui.message_menu = None;
+    match action {
+        Some(action) => run_message_action(tab_ui, action, index, role),
+        None => Some(WindowEvent::ResponseWindow),
+    }
+}
+
+// actions on the text as shown run here, those that change the
+// conversation are passed on to the chat session
+pub fn run_message_action(
+    tab_ui: &mut TabUi,
+    action: MessageAction,
+    index: usize,
+    role: PromptRole,
+) -> Option<WindowEvent> {
+    match action {
+        MessageAction::Copy => {
+            let text = tab_ui.message_text(index, role).unwrap_or_default();
+            let message = match write_to_clipboard(&text) {
+                Ok(_) => format!("Copied message {}", index + 1),
+                Err(e) => e,
+            };
+            tab_ui.command_line.text_set(&message, None);
+            Some(WindowEvent::ResponseWindow)
+        }
+        MessageAction::QuoteReply => {
+            // a visual selection quotes only that part of the message
+            let text = match tab_ui.response.text_buffer().yank_selected_text()
+            {
+                Some(text) if !text.trim().is_empty() => text,
+                _ => tab_ui.message_text(index, role).unwrap_or_default(),
+            };
+            tab_ui.response.text_unselect();
+            tab_ui.response.set_status_inactive();
+            tab_ui.prompt.set_insert_mode();
+            tab_ui
+                .prompt
+                .text_insert_add(&quote(&text, role, index), None);
+            tab_ui.reply_to = Some(index);
+            Some(WindowEvent::PromptWindow)
+        }
+        MessageAction::Annotate => Some(WindowEvent::CommandLine(
+            CommandLineAction::Write(format!(":annotate {} ", index + 1)),
+        )),
+        // the command line is opened to add an optional reason
+        MessageAction::Upvote => Some(WindowEvent::CommandLine(
+            CommandLineAction::Write(format!(":vote {} up ", index + 1)),
+        )),
+        MessageAction::Downvote => Some(WindowEvent::CommandLine(
+            CommandLineAction::Write(format!(":vote {} down ", index + 1)),
+        )),
+        _ => Some(WindowEvent::Prompt(PromptAction::Message(action, index))),
+    }
+}
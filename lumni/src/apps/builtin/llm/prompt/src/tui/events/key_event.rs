@@ -190,8 +190,14 @@ impl KeyEventHandler {
             ),
             WindowEvent::Modal(window_type) => {
                 // get Escape key press to close modal window
-                if self.key_track.current_key().code == KeyCode::Esc
-                    || self.key_track.current_key().code == KeyCode::Char('q')
+                let is_editing = tab_ui
+                    .modal
+                    .as_ref()
+                    .is_some_and(|modal| modal.is_editing());
+                if !is_editing
+                    && (self.key_track.current_key().code == KeyCode::Esc
+                        || self.key_track.current_key().code
+                            == KeyCode::Char('q'))
                 {
                     tab_ui.clear_modal();
                     Some(WindowEvent::PromptWindow)
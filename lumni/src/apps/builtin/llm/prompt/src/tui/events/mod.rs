@@ -6,12 +6,21 @@ mod leader_key;
 mod text_window_event;
 
 pub use key_event::{KeyEventHandler, KeyTrack};
+pub use text_window_event::write_to_clipboard;
 
 use super::clipboard::ClipboardProvider;
-use super::components::{LineType, MoveCursor, TextWindowTrait, WindowKind};
+use super::components::{
+    LineNumbers, LineType, MoveCursor, TextWindowTrait, WindowKind,
+};
+use super::message_actions::{quote, MessageAction};
 use super::modal::ModalWindowType;
+use super::snippets::SnippetSession;
 use super::ui::TabUi;
+use super::widgets::{SpellSuggestions, MAX_SUGGESTIONS};
 use super::windows::PromptWindow;
+use super::{
+    parse_retention, PromptRole, Snippet, SnippetLibrary, Vote, SNIPPET_PREFIX,
+};
 
 #[derive(Debug)]
 pub enum WindowEvent {
@@ -25,9 +34,29 @@ pub enum WindowEvent {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum PromptAction {
-    Stop,          // stop stream
-    Clear,         // stop stream and clear prompt
-    Write(String), // send prompt
+    Stop,                          // stop stream
+    Clear,                         // stop stream and clear prompt
+    New,                           // start a new conversation, from the pool
+    Write(String),                 // send prompt
+    Notify(Option<String>),        // send last response to webhook(s)
+    Email(String),                 // email the conversation
+    Vault,                         // export the conversation to the vault
+    Reconnect,                     // re-initialize the server
+    Message(MessageAction, usize), // action on an exchange of the conversation
+    Annotate(usize, String),       // note on an exchange, empty to remove
+    Stats,                         // show statistics of the conversation
+    Capabilities,                  // show what the server supports
+    Trash(TrashCommand),           // view or manage deleted messages
+    // feedback on an answer, with an optional reason
+    Vote(usize, Option<Vote>, String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrashCommand {
+    List,
+    Restore(usize),       // number as listed in the trash
+    Purge(Option<usize>), // one, or all without a number
+    Retention(u64),       // seconds deleted messages are kept
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -230,7 +230,7 @@ where
     }
 }
 
-fn write_to_clipboard(text: &str) -> Result<(), String> {
+pub fn write_to_clipboard(text: &str) -> Result<(), String> {
     let mut clipboard = ClipboardProvider::new();
 
     match clipboard.write_line(text, false) {
@@ -0,0 +1,100 @@
+use std::collections::VecDeque;
+use std::io;
+use std::time::Duration;
+
+use crossterm::event::{
+    poll, read, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers,
+};
+
+// events are read at most this many at a time, so a large burst of input
+// can not hold up rendering
+const MAX_BURST_EVENTS: usize = 1024;
+
+// reads terminal events, and returns text that is committed at once as a
+// single Event::Paste. terminals do not report the composition of an input
+// method (IME): the preedit text is drawn by the terminal itself, and on
+// commit the text arrives as a burst of key presses. handling that burst
+// as text keeps it from being dispatched as keybindings, and inserts it
+// in one step
+pub struct InputQueue {
+    pending: VecDeque<Event>,
+}
+
+impl InputQueue {
+    pub fn new() -> Self {
+        InputQueue {
+            pending: VecDeque::new(),
+        }
+    }
+
+    // next event, if one is available within timeout. with text_mode set
+    // (e.g. the prompt is in insert mode), a burst of characters is returned
+    // as text. outside text_mode this only applies to bursts that start
+    // with a non-ascii character, which is never a keybinding
+    pub fn next_event(
+        &mut self,
+        timeout: Duration,
+        text_mode: bool,
+    ) -> io::Result<Option<Event>> {
+        let event = match self.pending.pop_front() {
+            Some(event) => event,
+            None => match self.read_event(timeout)? {
+                Some(event) => event,
+                None => return Ok(None),
+            },
+        };
+        let first = match text_char(&event) {
+            Some(c) if text_mode || !c.is_ascii() => c,
+            _ => return Ok(Some(event)),
+        };
+        let mut text = first.to_string();
+        for _ in 0..MAX_BURST_EVENTS {
+            let event = match self.pending.pop_front() {
+                Some(event) => event,
+                None => match self.read_event(Duration::ZERO)? {
+                    Some(event) => event,
+                    None => break,
+                },
+            };
+            match text_char(&event) {
+                Some(c) => text.push(c),
+                None => {
+                    // handled after the text, in the order it arrived
+                    self.pending.push_front(event);
+                    break;
+                }
+            }
+        }
+        if text.len() == first.len_utf8() && first.is_ascii() {
+            // a single key press, e.g. typed by hand
+            return Ok(Some(event));
+        }
+        Ok(Some(Event::Paste(text)))
+    }
+
+    fn read_event(&mut self, timeout: Duration) -> io::Result<Option<Event>> {
+        while poll(timeout)? {
+            match read()? {
+                // key releases are reported on some platforms (e.g.
+                // Windows), the key press is already handled
+                Event::Key(key_event)
+                    if key_event.kind == KeyEventKind::Release => {}
+                event => return Ok(Some(event)),
+            }
+        }
+        Ok(None)
+    }
+}
+
+// character of a key press that inserts text, i.e. without modifiers
+// other than shift
+fn text_char(event: &Event) -> Option<char> {
+    match event {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(c),
+            modifiers,
+            ..
+        }) if (*modifiers - KeyModifiers::SHIFT).is_empty() => Some(*c),
+        _ => None,
+    }
+}
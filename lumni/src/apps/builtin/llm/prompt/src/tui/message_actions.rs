@@ -0,0 +1,200 @@
+use super::PromptRole;
+
+// actions on a single message of the conversation, shared by the message
+// menu and the ":message" command
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MessageAction {
+    Copy,
+    QuoteReply,
+    Pin,
+    Annotate,
+    Upvote,
+    Downvote,
+    Fork,
+    Delete,
+    ViewRaw,
+    Inspect,
+    Rerun,
+}
+
+impl MessageAction {
+    // in the order they are listed in the menu
+    pub const ALL: [MessageAction; 11] = [
+        MessageAction::Copy,
+        MessageAction::QuoteReply,
+        MessageAction::Pin,
+        MessageAction::Annotate,
+        MessageAction::Upvote,
+        MessageAction::Downvote,
+        MessageAction::Fork,
+        MessageAction::Delete,
+        MessageAction::ViewRaw,
+        MessageAction::Inspect,
+        MessageAction::Rerun,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MessageAction::Copy => "copy",
+            MessageAction::QuoteReply => "quote",
+            MessageAction::Pin => "pin",
+            MessageAction::Annotate => "annotate",
+            MessageAction::Upvote => "up",
+            MessageAction::Downvote => "down",
+            MessageAction::Fork => "fork",
+            MessageAction::Delete => "delete",
+            MessageAction::ViewRaw => "raw",
+            MessageAction::Inspect => "inspect",
+            MessageAction::Rerun => "rerun",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MessageAction::Copy => "Copy",
+            MessageAction::QuoteReply => "Quote reply",
+            MessageAction::Pin => "Pin / unpin",
+            MessageAction::Annotate => "Annotate",
+            MessageAction::Upvote => "Thumbs up",
+            MessageAction::Downvote => "Thumbs down",
+            MessageAction::Fork => "Fork here",
+            MessageAction::Delete => "Delete",
+            MessageAction::ViewRaw => "View raw JSON",
+            MessageAction::Inspect => "Inspect request",
+            MessageAction::Rerun => "Re-run",
+        }
+    }
+
+    // selects the action directly when the menu is open
+    pub fn key(&self) -> char {
+        match self {
+            MessageAction::Copy => 'c',
+            MessageAction::QuoteReply => 'r',
+            MessageAction::Pin => 'p',
+            MessageAction::Annotate => 'a',
+            MessageAction::Upvote => '+',
+            MessageAction::Downvote => '-',
+            MessageAction::Fork => 'f',
+            MessageAction::Delete => 'd',
+            MessageAction::ViewRaw => 'v',
+            MessageAction::Inspect => 'i',
+            MessageAction::Rerun => 'g',
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    pub fn from_key(key: char) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.key() == key)
+    }
+}
+
+// where an exchange of the conversation starts in the response window,
+// as unwrapped line indices
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MessageLines {
+    pub question: usize,
+    pub answer: usize,
+}
+
+// exchange index and role of the message shown on a line
+pub fn message_at_line(
+    messages: &[MessageLines],
+    line: usize,
+) -> Option<(usize, PromptRole)> {
+    let index = messages
+        .iter()
+        .rposition(|message| message.question <= line)?;
+    let role = if line >= messages[index].answer {
+        PromptRole::Assistant
+    } else {
+        PromptRole::User
+    };
+    Some((index, role))
+}
+
+// text of a message as displayed, without the blank lines around it
+pub fn message_text(
+    messages: &[MessageLines],
+    text: &str,
+    index: usize,
+    role: PromptRole,
+) -> Option<String> {
+    let message = messages.get(index)?;
+    let next = messages.get(index + 1).map(|next| next.question);
+    let range = match role {
+        PromptRole::Assistant => message.answer..next.unwrap_or(usize::MAX),
+        _ => message.question..message.answer,
+    };
+    let lines: Vec<&str> = text
+        .split('\n')
+        .skip(range.start)
+        .take(range.end.saturating_sub(range.start))
+        .collect();
+    Some(lines.join("\n").trim().to_string())
+}
+
+// text as a quoted block for a reply, with who wrote it and where
+pub fn quote(text: &str, role: PromptRole, index: usize) -> String {
+    let author = match role {
+        PromptRole::Assistant => "Assistant",
+        _ => "You",
+    };
+    let quoted: Vec<String> = text
+        .lines()
+        .map(|line| format!("> {}", line).trim_end().to_string())
+        .collect();
+    format!(
+        "> {} wrote (message {}):\n{}\n\n",
+        author,
+        index + 1,
+        quoted.join("\n")
+    )
+}
+
+// a prompt with a quoted block is a reply to the quoted message
+pub fn is_reply(prompt: &str) -> bool {
+    prompt.lines().any(|line| line.starts_with('>'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_text() {
+        let text = "hello\n\nhi there\n\n\nhow are you?\n\nfine\n\n";
+        let messages = vec![
+            MessageLines {
+                question: 0,
+                answer: 2,
+            },
+            MessageLines {
+                question: 5,
+                answer: 7,
+            },
+        ];
+        assert!(matches!(
+            message_at_line(&messages, 3),
+            Some((0, PromptRole::Assistant))
+        ));
+        assert!(matches!(
+            message_at_line(&messages, 5),
+            Some((1, PromptRole::User))
+        ));
+        assert_eq!(
+            message_text(&messages, text, 0, PromptRole::Assistant).as_deref(),
+            Some("hi there")
+        );
+        assert_eq!(
+            message_text(&messages, text, 1, PromptRole::User).as_deref(),
+            Some("how are you?")
+        );
+        assert_eq!(
+            quote("a\n\nb", PromptRole::Assistant, 0),
+            "> Assistant wrote (message 1):\n> a\n>\n> b\n\n"
+        );
+    }
+}
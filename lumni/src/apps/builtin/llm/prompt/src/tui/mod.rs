@@ -3,7 +3,11 @@ mod colorscheme;
 mod components;
 mod draw;
 mod events;
+mod input;
+mod message_actions;
 mod modal;
+mod pacer;
+mod snippets;
 mod ui;
 mod widgets;
 mod windows;
@@ -12,11 +16,22 @@ pub use colorscheme::{ColorScheme, ColorSchemeType};
 pub use components::TextWindowTrait;
 pub use draw::draw_ui;
 pub use events::{
-    CommandLineAction, KeyEventHandler, PromptAction, WindowEvent,
+    CommandLineAction, KeyEventHandler, PromptAction, TrashCommand,
+    WindowEvent,
 };
-pub use modal::{ModalConfigWindow, ModalWindowTrait, ModalWindowType};
+pub use input::InputQueue;
+pub use message_actions::{is_reply, MessageAction};
+pub use modal::{
+    ModalConfigWindow, ModalSettingsWindow, ModalTextWindow, ModalWindowTrait,
+    ModalWindowType,
+};
+pub use pacer::RenderPacer;
 pub use ui::TabUi;
 pub use windows::{CommandLine, PromptWindow, ResponseWindow};
 
+pub use super::chat::{
+    parse_retention, ProfileStore, PromptProfile, PromptRole, Snippet,
+    SnippetLibrary, Vote, SNIPPET_PREFIX,
+};
 pub use super::server::SUPPORTED_MODEL_ENDPOINTS;
 pub use super::session::TabSession;
@@ -1,16 +1,21 @@
 use crossterm::event::KeyCode;
-use ratatui::layout::Rect;
-use ratatui::widgets::Clear;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Style};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
 use ratatui::Frame;
+use serde_json::Value;
 
 use super::components::Scroller;
-use super::events::KeyTrack;
-use super::widgets::SelectEndpoint;
-use super::WindowEvent;
+use super::events::{write_to_clipboard, KeyTrack};
+use super::widgets::{SelectEndpoint, SettingsTree};
+use super::{ProfileStore, PromptProfile, WindowEvent};
+use crate::external::api::error::ApplicationError;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ModalWindowType {
     Config,
+    Text,     // read-only text, e.g. the raw JSON of a message
+    Settings, // options of a profile
 }
 
 pub trait ModalWindowTrait {
@@ -20,6 +25,10 @@ pub trait ModalWindowTrait {
         &mut self,
         key_event: &mut KeyTrack,
     ) -> Option<WindowEvent>;
+    // while text is typed, Esc and q are handled by the window itself
+    fn is_editing(&self) -> bool {
+        false
+    }
 }
 
 pub struct ModalConfigWindow {
@@ -70,3 +79,200 @@ impl ModalWindowTrait for ModalConfigWindow {
         Some(WindowEvent::Modal(ModalWindowType::Config))
     }
 }
+
+// shows text that does not fit on the command line, scrolled with the
+// arrow keys or j/k, y copies all of it
+pub struct ModalTextWindow {
+    title: String,
+    text: String,
+    scroll: u16,
+}
+
+impl ModalTextWindow {
+    pub fn new(title: &str, text: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            text: text.to_string(),
+            scroll: 0,
+        }
+    }
+}
+
+impl ModalWindowTrait for ModalTextWindow {
+    fn get_type(&self) -> ModalWindowType {
+        ModalWindowType::Text
+    }
+
+    fn render_on_frame(&mut self, frame: &mut Frame, area: Rect) {
+        let max_scroll = (self.text.lines().count() as u16)
+            .saturating_sub(area.height.saturating_sub(2));
+        self.scroll = self.scroll.min(max_scroll);
+        let paragraph = Paragraph::new(self.text.as_str())
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(self.title.as_str())
+                    .style(Style::default().bg(Color::Black)),
+            )
+            .scroll((self.scroll, 0));
+        frame.render_widget(Clear, area);
+        frame.render_widget(paragraph, area);
+    }
+
+    fn handle_key_event(
+        &mut self,
+        key_event: &mut KeyTrack,
+    ) -> Option<WindowEvent> {
+        match key_event.current_key().code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.scroll = self.scroll.saturating_sub(1)
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.scroll = self.scroll.saturating_add(1)
+            }
+            KeyCode::Char('y') => {
+                write_to_clipboard(&self.text).ok();
+            }
+            _ => {}
+        }
+        Some(WindowEvent::Modal(ModalWindowType::Text))
+    }
+}
+
+const SETTINGS_HELP: &str =
+    "enter edit/toggle, a add, d delete, s save, esc close";
+
+// edits a profile as a tree. fields that do not match the schema of its
+// server are marked, and keep it from being saved to profiles.yaml
+pub struct ModalSettingsWindow {
+    name: String,
+    tree: SettingsTree,
+    message: Option<String>,
+    // profiles as loaded when the window opened, saving fails if another
+    // lumni instance changed them since
+    store: ProfileStore,
+}
+
+impl ModalSettingsWindow {
+    pub fn new(
+        name: &str,
+        profile: &PromptProfile,
+        store: ProfileStore,
+    ) -> Self {
+        let value = serde_json::to_value(profile).unwrap_or(Value::Null);
+        let mut window = Self {
+            name: name.to_string(),
+            tree: SettingsTree::new(value),
+            message: None,
+            store,
+        };
+        window.message = window.check().err();
+        window
+    }
+
+    // marks the fields that do not match, and tells the first of them
+    fn check(&mut self) -> Result<PromptProfile, String> {
+        let profile: PromptProfile =
+            serde_json::from_value(self.tree.value().clone())
+                .map_err(|e| format!("Invalid profile: {}", e))?;
+        let errors = profile.schema_errors().map_err(|e| e.to_string())?;
+        self.tree
+            .set_errors(errors.iter().map(|e| e.pointer.clone()).collect());
+        match errors.first() {
+            Some(first) if errors.len() > 1 => Err(format!(
+                "{} (and {} more)",
+                first.message,
+                errors.len() - 1
+            )),
+            Some(first) => Err(first.message.clone()),
+            None => Ok(profile),
+        }
+    }
+
+    fn save(&mut self) -> String {
+        let saved = self.check().and_then(|profile| {
+            self.store.insert(&self.name, profile);
+            self.store.save().map_err(|e| match e {
+                ApplicationError::Conflict(_) => "Profiles were changed by \
+                                                  another lumni instance, \
+                                                  reopen the settings"
+                    .to_string(),
+                e => e.to_string(),
+            })
+        });
+        match saved {
+            Ok(()) => format!("Saved profile {}", self.name),
+            Err(e) => e,
+        }
+    }
+}
+
+impl ModalWindowTrait for ModalSettingsWindow {
+    fn get_type(&self) -> ModalWindowType {
+        ModalWindowType::Settings
+    }
+
+    fn render_on_frame(&mut self, frame: &mut Frame, area: Rect) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Profile {}", self.name))
+            .style(Style::default().bg(Color::Black));
+        let inner = block.inner(area);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .split(inner);
+        let status = self
+            .tree
+            .edit_line()
+            .or_else(|| self.message.clone())
+            .unwrap_or_else(|| SETTINGS_HELP.to_string());
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+        frame.render_widget(&self.tree, chunks[0]);
+        frame.render_widget(
+            Paragraph::new(status).style(Style::default().fg(Color::DarkGray)),
+            chunks[1],
+        );
+    }
+
+    fn handle_key_event(
+        &mut self,
+        key_event: &mut KeyTrack,
+    ) -> Option<WindowEvent> {
+        let key_code = key_event.current_key().code;
+        if self.tree.is_editing() {
+            match key_code {
+                KeyCode::Char(character) => self.tree.edit_input(character),
+                KeyCode::Backspace => self.tree.edit_backspace(),
+                KeyCode::Esc => self.tree.edit_cancel(),
+                KeyCode::Enter => {
+                    // a mismatch is shown, but only stops saving
+                    self.message = match self.tree.edit_commit() {
+                        Ok(()) => self.check().err(),
+                        Err(e) => Some(e),
+                    };
+                }
+                _ => {}
+            }
+            return Some(WindowEvent::Modal(ModalWindowType::Settings));
+        }
+        self.message = None;
+        match key_code {
+            KeyCode::Up | KeyCode::Char('k') => self.tree.key_up(),
+            KeyCode::Down | KeyCode::Char('j') => self.tree.key_down(),
+            KeyCode::Left | KeyCode::Char('h') => self.tree.collapse(),
+            KeyCode::Right | KeyCode::Char('l') => self.tree.expand(),
+            KeyCode::Enter => self.tree.select(),
+            KeyCode::Char('a') => self.tree.add(),
+            KeyCode::Char('d') => self.tree.delete(),
+            KeyCode::Char('s') => self.message = Some(self.save()),
+            _ => {}
+        }
+        Some(WindowEvent::Modal(ModalWindowType::Settings))
+    }
+
+    fn is_editing(&self) -> bool {
+        self.tree.is_editing()
+    }
+}
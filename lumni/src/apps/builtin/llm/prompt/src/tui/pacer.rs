@@ -0,0 +1,87 @@
+use std::time::{Duration, Instant};
+
+use super::super::defaults::RENDER_FRAME_MILLIS;
+
+// decouples rendering of a response from the rate at which it arrives,
+// bursts are buffered and released at most chars_per_frame at a time
+pub struct RenderPacer {
+    chars_per_frame: Option<usize>, // None renders everything directly
+    pending: String,
+    last_frame: Instant,
+}
+
+impl RenderPacer {
+    pub fn new(chars_per_frame: Option<usize>) -> Self {
+        RenderPacer {
+            chars_per_frame: chars_per_frame.filter(|chars| *chars > 0),
+            pending: String::new(),
+            last_frame: Instant::now(),
+        }
+    }
+
+    pub fn push(&mut self, text: &str) {
+        self.pending.push_str(text);
+    }
+
+    // text to render now, if any
+    pub fn next_frame(&mut self) -> Option<String> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let chars_per_frame = match self.chars_per_frame {
+            Some(chars_per_frame) => chars_per_frame,
+            None => return self.flush(),
+        };
+        if self.last_frame.elapsed()
+            < Duration::from_millis(RENDER_FRAME_MILLIS)
+        {
+            return None;
+        }
+        self.last_frame = Instant::now();
+        let end = self
+            .pending
+            .char_indices()
+            .nth(chars_per_frame)
+            .map_or(self.pending.len(), |(index, _)| index);
+        Some(self.pending.drain(..end).collect())
+    }
+
+    // all text that is not rendered yet, e.g. when the stream is stopped
+    pub fn flush(&mut self) -> Option<String> {
+        if self.pending.is_empty() {
+            None
+        } else {
+            Some(std::mem::take(&mut self.pending))
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_frame() {
+        let mut pacer = RenderPacer::new(None);
+        pacer.push("héllo");
+        assert_eq!(pacer.next_frame().as_deref(), Some("héllo"));
+        assert_eq!(pacer.next_frame(), None);
+
+        let mut pacer = RenderPacer::new(Some(2));
+        pacer.last_frame -= Duration::from_millis(RENDER_FRAME_MILLIS);
+        pacer.push("héllo");
+        assert_eq!(pacer.next_frame().as_deref(), Some("hé"));
+        // wait for the next frame
+        assert_eq!(pacer.next_frame(), None);
+        assert_eq!(pacer.flush().as_deref(), Some("llo"));
+        assert!(pacer.is_empty());
+    }
+}
@@ -0,0 +1,48 @@
+use std::ops::Range;
+
+// tab stops of a snippet that is expanded in the prompt, as byte
+// positions in the text
+#[derive(Debug, Clone)]
+pub struct SnippetSession {
+    tab_stops: Vec<Range<usize>>, // in the order they are visited
+    next: usize,
+    text_length: usize, // length of the text at the last tab stop
+}
+
+impl SnippetSession {
+    // tab_stops are relative to the start of the snippet
+    pub fn new(
+        start: usize,
+        tab_stops: &[Range<usize>],
+        text_length: usize,
+    ) -> Self {
+        SnippetSession {
+            tab_stops: tab_stops
+                .iter()
+                .map(|stop| start + stop.start..start + stop.end)
+                .collect(),
+            next: 0,
+            text_length,
+        }
+    }
+
+    // next tab stop, None when all are visited. text typed since the last
+    // tab stop is assumed to be typed in its field, so the fields after it
+    // move by the same amount
+    pub fn next_stop(&mut self, text_length: usize) -> Option<Range<usize>> {
+        let growth = text_length as isize - self.text_length as isize;
+        self.text_length = text_length;
+        for stop in self.tab_stops.iter_mut().skip(self.next) {
+            let shift =
+                |position: usize| position.saturating_add_signed(growth);
+            *stop = shift(stop.start)..shift(stop.end);
+        }
+        let stop = self.tab_stops.get(self.next).cloned();
+        self.next += 1;
+        stop
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next >= self.tab_stops.len()
+    }
+}
@@ -1,13 +1,27 @@
+use ratatui::style::Style;
+
+use super::components::GutterStyle;
+use super::message_actions::{message_at_line, message_text, MessageLines};
+use super::snippets::SnippetSession;
+use super::widgets::{MessageMenu, SpellSuggestions};
 use super::{
-    CommandLine, ModalConfigWindow, ModalWindowTrait, ModalWindowType,
-    PromptWindow, ResponseWindow, TextWindowTrait,
+    CommandLine, ModalConfigWindow, ModalSettingsWindow, ModalTextWindow,
+    ModalWindowTrait, ModalWindowType, ProfileStore, PromptProfile, PromptRole,
+    PromptWindow, ResponseWindow, SnippetLibrary, TextWindowTrait,
 };
+use crate::Telemetry;
 
 pub struct TabUi<'a> {
     pub prompt: PromptWindow<'a>,
     pub response: ResponseWindow<'a>,
     pub command_line: CommandLine<'a>,
     pub modal: Option<Box<dyn ModalWindowTrait>>,
+    pub spell_suggestions: Option<SpellSuggestions>, // popup in the prompt
+    pub snippets: SnippetLibrary,
+    pub snippet_session: Option<SnippetSession>, // snippet being filled in
+    pub messages: Vec<MessageLines>, // exchanges shown in the response
+    pub message_menu: Option<MessageMenu>,
+    pub reply_to: Option<usize>, // exchange quoted in the prompt
 }
 
 impl TabUi<'_> {
@@ -17,6 +31,12 @@ impl TabUi<'_> {
             response: ResponseWindow::new(),
             command_line: CommandLine::new(),
             modal: None,
+            spell_suggestions: None,
+            snippets: SnippetLibrary::default(),
+            snippet_session: None,
+            messages: Vec::new(),
+            message_menu: None,
+            reply_to: None,
         }
     }
 
@@ -26,10 +46,108 @@ impl TabUi<'_> {
         self.command_line.init(); // initialize with defaults
     }
 
+    pub fn set_gutter_style(&mut self, style: GutterStyle) {
+        self.prompt.set_gutter_style(style);
+        self.response.set_gutter_style(style);
+    }
+
+    pub fn set_snippets(&mut self, snippets: SnippetLibrary) {
+        self.snippets = snippets;
+    }
+
     pub fn set_new_modal(&mut self, modal_type: ModalWindowType) {
         self.modal = match modal_type {
             ModalWindowType::Config => Some(Box::new(ModalConfigWindow::new())),
+            ModalWindowType::Text => {
+                Some(Box::new(ModalTextWindow::new("", "")))
+            }
+            ModalWindowType::Settings => {
+                Some(Box::new(ModalSettingsWindow::new(
+                    "default",
+                    &PromptProfile::default(),
+                    ProfileStore::from_config_file().unwrap_or_default(),
+                )))
+            }
+        };
+    }
+
+    // a profile that does not exist yet is created when saved, from the
+    // settings the server describes
+    pub fn set_settings_modal(
+        &mut self,
+        name: &str,
+        server: Option<&str>,
+    ) -> Result<(), String> {
+        let store =
+            ProfileStore::from_config_file().map_err(|e| e.to_string())?;
+        let profile = match store.get(name) {
+            Ok(profile) => profile.clone(),
+            Err(_) => {
+                PromptProfile::for_server(server).map_err(|e| e.to_string())?
+            }
         };
+        self.modal =
+            Some(Box::new(ModalSettingsWindow::new(name, &profile, store)));
+        Ok(())
+    }
+
+    // what is collected and sent, changed with "lumni telemetry"
+    pub fn set_telemetry_modal(&mut self) -> Result<(), String> {
+        let telemetry = Telemetry::load().map_err(|e| e.to_string())?;
+        self.set_text_modal("Telemetry", &telemetry.status());
+        Ok(())
+    }
+
+    pub fn set_text_modal(&mut self, title: &str, text: &str) {
+        self.modal = Some(Box::new(ModalTextWindow::new(title, text)));
+    }
+
+    // line in the response window where the next text is appended
+    pub fn response_end_line(&mut self) -> usize {
+        self.response
+            .text_buffer()
+            .to_string()
+            .matches('\n')
+            .count()
+    }
+
+    // shows a question in the response window, and where its exchange
+    // starts. the answer is appended after it
+    pub fn append_question(&mut self, question: &str, style: Style) {
+        let question_line = self.response_end_line();
+        let question = format!("{}\n", question.trim_end());
+        self.response
+            .text_append_with_insert(&question, Some(style));
+        self.response
+            .text_append_with_insert("\n", Some(Style::reset()));
+        let answer_line = self.response_end_line();
+        self.messages.push(MessageLines {
+            question: question_line,
+            answer: answer_line,
+        });
+    }
+
+    // exchange and role of the message at the cursor in the response window
+    pub fn message_at_cursor(&mut self) -> Option<(usize, PromptRole)> {
+        let line = self.response.text_buffer().cursor_line();
+        message_at_line(&self.messages, line)
+    }
+
+    pub fn message_text(
+        &mut self,
+        index: usize,
+        role: PromptRole,
+    ) -> Option<String> {
+        let text = self.response.text_buffer().to_string();
+        message_text(&self.messages, &text, index, role)
+    }
+
+    // opens the message menu for the message at the cursor
+    pub fn open_message_menu(&mut self) -> bool {
+        self.message_menu = self
+            .message_at_cursor()
+            .map(|(index, role)| MessageMenu::new(index, role));
+        self.message_menu.is_some()
     }
 
     pub fn needs_modal_update(&self, new_type: ModalWindowType) -> bool {
@@ -39,6 +157,15 @@ impl TabUi<'_> {
         }
     }
 
+    // applies pending re-wraps after a terminal resize,
+    // returns true if the UI needs to be redrawn
+    pub fn poll_resize(&mut self) -> bool {
+        let prompt = self.prompt.poll_resize();
+        let response = self.response.poll_resize();
+        let command_line = self.command_line.poll_resize();
+        prompt || response || command_line
+    }
+
     pub fn clear_modal(&mut self) {
         self.modal = None;
     }
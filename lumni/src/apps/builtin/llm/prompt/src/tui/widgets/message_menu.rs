@@ -0,0 +1,99 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::block::{Block, Padding};
+use ratatui::widgets::{Borders, Clear, List, ListItem, Widget};
+
+use super::{MessageAction, PromptRole};
+
+const WIDTH: u16 = 24;
+
+// actions for the message at the cursor in the response window
+pub struct MessageMenu {
+    index: usize, // exchange in the conversation
+    role: PromptRole,
+    current_index: usize,
+}
+
+impl MessageMenu {
+    pub fn new(index: usize, role: PromptRole) -> Self {
+        Self {
+            index,
+            role,
+            current_index: 0,
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn role(&self) -> PromptRole {
+        self.role
+    }
+
+    pub fn key_down(&mut self) {
+        self.current_index =
+            (self.current_index + 1) % MessageAction::ALL.len();
+    }
+
+    pub fn key_up(&mut self) {
+        self.current_index = self
+            .current_index
+            .checked_sub(1)
+            .unwrap_or(MessageAction::ALL.len() - 1);
+    }
+
+    pub fn selected(&self) -> MessageAction {
+        MessageAction::ALL[self.current_index]
+    }
+
+    // next to the cursor, kept inside the frame
+    pub fn area(&self, cursor: (u16, u16), frame: Rect) -> Rect {
+        let width = WIDTH.min(frame.width);
+        let height = (MessageAction::ALL.len() as u16 + 2).min(frame.height);
+        let (x, y) = cursor;
+        let x = x.min(frame.right().saturating_sub(width));
+        let y = y.min(frame.bottom().saturating_sub(height));
+        Rect::new(x, y, width, height)
+    }
+}
+
+impl Widget for &MessageMenu {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        Clear.render(area, buf);
+        let items: Vec<ListItem> = MessageAction::ALL
+            .iter()
+            .enumerate()
+            .map(|(index, action)| {
+                let style = if index == self.current_index {
+                    Style::default()
+                        .add_modifier(Modifier::BOLD)
+                        .fg(Color::Yellow)
+                } else {
+                    Style::default().fg(Color::White)
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{} ", action.key()),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(action.label(), style),
+                ]))
+            })
+            .collect();
+        let title = match self.role {
+            PromptRole::Assistant => format!("Answer {}", self.index + 1),
+            _ => format!("Question {}", self.index + 1),
+        };
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .padding(Padding::horizontal(1))
+                .style(Style::default().bg(Color::Black)),
+        );
+        list.render(area, buf);
+    }
+}
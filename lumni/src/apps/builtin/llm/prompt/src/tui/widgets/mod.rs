@@ -1,5 +1,12 @@
 mod config_modal;
+mod message_menu;
+mod settings_tree;
+mod spell_suggestions;
 
 pub use config_modal::SelectEndpoint;
+pub use message_menu::MessageMenu;
+pub use settings_tree::SettingsTree;
+pub use spell_suggestions::{SpellSuggestions, MAX_SUGGESTIONS};
 
-pub use super::SUPPORTED_MODEL_ENDPOINTS;
+pub use super::message_actions::MessageAction;
+pub use super::{PromptRole, SUPPORTED_MODEL_ENDPOINTS};
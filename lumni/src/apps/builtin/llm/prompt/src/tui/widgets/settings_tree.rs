@@ -0,0 +1,420 @@
+use std::collections::HashSet;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{List, ListItem, Widget};
+use serde_json::Value;
+
+// nested settings as a tree. objects and arrays are expanded or collapsed,
+// values are edited as text and parsed as the type they already have
+pub struct SettingsTree {
+    value: Value,
+    collapsed: HashSet<String>, // pointers of collapsed objects and arrays
+    errors: HashSet<String>,    // pointers of values that are not valid
+    current_index: usize,
+    edit: Option<TreeEdit>,
+}
+
+struct TreeEdit {
+    pointer: String, // the edited value, or the parent a child is added to
+    add: bool,
+    text: String,
+}
+
+struct TreeRow {
+    pointer: String,
+    depth: usize,
+    label: String,
+}
+
+impl SettingsTree {
+    pub fn new(value: Value) -> Self {
+        Self {
+            value,
+            collapsed: HashSet::new(),
+            errors: HashSet::new(),
+            current_index: 0,
+            edit: None,
+        }
+    }
+
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    // e.g. from a schema, marked until set again
+    pub fn set_errors(&mut self, errors: HashSet<String>) {
+        self.errors = errors;
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.edit.is_some()
+    }
+
+    // what is typed, with a hint of what is expected
+    pub fn edit_line(&self) -> Option<String> {
+        let edit = self.edit.as_ref()?;
+        let hint = match (edit.add, self.value.pointer(&edit.pointer)) {
+            (true, Some(Value::Object(_))) => "key=value",
+            (true, _) => "new item",
+            (false, _) => "value",
+        };
+        Some(format!("{}: {}", hint, edit.text))
+    }
+
+    pub fn key_down(&mut self) {
+        let rows = self.rows().len();
+        if self.current_index + 1 < rows {
+            self.current_index += 1;
+        }
+    }
+
+    pub fn key_up(&mut self) {
+        self.current_index = self.current_index.saturating_sub(1);
+    }
+
+    pub fn expand(&mut self) {
+        if let Some(pointer) = self.current_pointer() {
+            self.collapsed.remove(&pointer);
+        }
+    }
+
+    // collapses an expanded node, or else moves up to its parent
+    pub fn collapse(&mut self) {
+        let Some(pointer) = self.current_pointer() else {
+            return;
+        };
+        if self.is_container(&pointer) && !self.collapsed.contains(&pointer) {
+            self.collapsed.insert(pointer);
+        } else if let Some(index) = self
+            .rows()
+            .iter()
+            .position(|row| row.pointer == parent_pointer(&pointer))
+        {
+            self.current_index = index;
+        }
+    }
+
+    // objects and arrays are toggled, other values are edited
+    pub fn select(&mut self) {
+        let Some(pointer) = self.current_pointer() else {
+            return;
+        };
+        if self.is_container(&pointer) {
+            if !self.collapsed.remove(&pointer) {
+                self.collapsed.insert(pointer);
+            }
+            return;
+        }
+        let text = match self.value.pointer(&pointer) {
+            Some(Value::String(text)) => text.clone(),
+            Some(value) => value.to_string(),
+            None => return,
+        };
+        self.edit = Some(TreeEdit {
+            pointer,
+            add: false,
+            text,
+        });
+    }
+
+    // a child of the current object or array, or else a sibling
+    pub fn add(&mut self) {
+        let pointer = match self.current_pointer() {
+            Some(pointer) if self.is_container(&pointer) => pointer,
+            Some(pointer) => parent_pointer(&pointer),
+            None => String::new(),
+        };
+        self.edit = Some(TreeEdit {
+            pointer,
+            add: true,
+            text: String::new(),
+        });
+    }
+
+    pub fn delete(&mut self) {
+        let Some(pointer) = self.current_pointer() else {
+            return;
+        };
+        let key = unescape(pointer.rsplit('/').next().unwrap_or_default());
+        match self.value.pointer_mut(&parent_pointer(&pointer)) {
+            Some(Value::Object(map)) => {
+                map.remove(&key);
+            }
+            Some(Value::Array(items)) => {
+                if let Ok(index) = key.parse::<usize>() {
+                    if index < items.len() {
+                        items.remove(index);
+                    }
+                }
+            }
+            _ => {}
+        }
+        let rows = self.rows().len();
+        self.current_index = self.current_index.min(rows.saturating_sub(1));
+    }
+
+    pub fn edit_input(&mut self, character: char) {
+        if let Some(edit) = self.edit.as_mut() {
+            edit.text.push(character);
+        }
+    }
+
+    pub fn edit_backspace(&mut self) {
+        if let Some(edit) = self.edit.as_mut() {
+            edit.text.pop();
+        }
+    }
+
+    pub fn edit_cancel(&mut self) {
+        self.edit = None;
+    }
+
+    // the edit stays open when the text does not fit the type
+    pub fn edit_commit(&mut self) -> Result<(), String> {
+        let Some(edit) = self.edit.as_ref() else {
+            return Ok(());
+        };
+        let pointer = edit.pointer.clone();
+        let text = edit.text.clone();
+        let Some(target) = self.value.pointer_mut(&pointer) else {
+            return Err("Setting no longer exists".to_string());
+        };
+        match (edit.add, target) {
+            (false, target) => *target = parse_as(target, &text)?,
+            (true, Value::Object(map)) => {
+                let (key, text) = text
+                    .split_once('=')
+                    .ok_or("Expected key=value".to_string())?;
+                let key = key.trim();
+                if key.is_empty() || map.contains_key(key) {
+                    return Err(format!("Invalid or existing key \"{}\"", key));
+                }
+                map.insert(key.to_string(), parse_new(text.trim()));
+            }
+            (true, Value::Array(items)) => {
+                // items are of one type, as the first one
+                let item = match items.first() {
+                    Some(first) => parse_as(first, &text)?,
+                    None => parse_new(&text),
+                };
+                items.push(item);
+            }
+            (true, _) => return Err("Can only add to an object".to_string()),
+        }
+        self.collapsed.remove(&pointer);
+        self.edit = None;
+        Ok(())
+    }
+
+    fn current_pointer(&self) -> Option<String> {
+        self.rows()
+            .into_iter()
+            .nth(self.current_index)
+            .map(|row| row.pointer)
+    }
+
+    fn is_container(&self, pointer: &str) -> bool {
+        matches!(
+            self.value.pointer(pointer),
+            Some(Value::Object(_)) | Some(Value::Array(_))
+        )
+    }
+
+    // visible nodes, the root itself is not shown
+    fn rows(&self) -> Vec<TreeRow> {
+        let mut rows = Vec::new();
+        self.add_rows(&self.value, "", 0, &mut rows);
+        rows
+    }
+
+    fn add_rows(
+        &self,
+        value: &Value,
+        pointer: &str,
+        depth: usize,
+        rows: &mut Vec<TreeRow>,
+    ) {
+        let children: Vec<(String, &Value)> = match value {
+            Value::Object(map) => map
+                .iter()
+                .map(|(key, child)| (key.clone(), child))
+                .collect(),
+            Value::Array(items) => items
+                .iter()
+                .enumerate()
+                .map(|(index, child)| (index.to_string(), child))
+                .collect(),
+            _ => return,
+        };
+        for (key, child) in children {
+            let child_pointer = format!("{}/{}", pointer, escape(&key));
+            let label = match value {
+                Value::Array(_) => format!("[{}]", key),
+                _ => key,
+            };
+            let expanded = !self.collapsed.contains(&child_pointer);
+            rows.push(TreeRow {
+                pointer: child_pointer.clone(),
+                depth,
+                label,
+            });
+            if expanded {
+                self.add_rows(child, &child_pointer, depth + 1, rows);
+            }
+        }
+    }
+}
+
+// booleans and numbers stay what they are, strings take the text as is
+fn parse_as(current: &Value, text: &str) -> Result<Value, String> {
+    let text = match current {
+        Value::String(_) => return Ok(Value::String(text.to_string())),
+        Value::Null => return Ok(parse_new(text)),
+        _ => text.trim(),
+    };
+    match (current, serde_json::from_str::<Value>(text)) {
+        (Value::Bool(_), Ok(value)) if value.is_boolean() => Ok(value),
+        (Value::Bool(_), _) => Err("Expected true or false".to_string()),
+        (Value::Number(number), Ok(value))
+            if value.is_number() && (number.is_f64() || !value.is_f64()) =>
+        {
+            Ok(value)
+        }
+        (Value::Number(number), _) if !number.is_f64() => {
+            Err("Expected an integer".to_string())
+        }
+        (Value::Number(_), _) => Err("Expected a number".to_string()),
+        (_, Ok(value)) if value.is_object() || value.is_array() => Ok(value),
+        _ => Err("Expected JSON".to_string()),
+    }
+}
+
+// JSON, or else a string
+fn parse_new(text: &str) -> Value {
+    serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.into()))
+}
+
+fn parent_pointer(pointer: &str) -> String {
+    match pointer.rsplit_once('/') {
+        Some((parent, _)) => parent.to_string(),
+        None => String::new(),
+    }
+}
+
+// keys in JSON pointers, see RFC 6901
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn unescape(key: &str) -> String {
+    key.replace("~1", "/").replace("~0", "~")
+}
+
+fn summary(value: &Value, collapsed: bool) -> String {
+    match value {
+        Value::Object(map) if collapsed => format!("{{{} keys}}", map.len()),
+        Value::Array(items) if collapsed => {
+            format!("[{} items]", items.len())
+        }
+        Value::Object(_) | Value::Array(_) => String::new(),
+        value => value.to_string(),
+    }
+}
+
+impl Widget for &SettingsTree {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let rows = self.rows();
+        // keeps the current row in view
+        let height = area.height as usize;
+        let offset = (self.current_index + 1).saturating_sub(height);
+        let items: Vec<ListItem> = rows
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(height)
+            .map(|(index, row)| {
+                let value = self.value.pointer(&row.pointer);
+                let collapsed = self.collapsed.contains(&row.pointer);
+                let marker = match value {
+                    Some(Value::Object(_)) | Some(Value::Array(_)) => {
+                        if collapsed {
+                            "+ "
+                        } else {
+                            "- "
+                        }
+                    }
+                    _ => "  ",
+                };
+                let color = match self.errors.contains(&row.pointer) {
+                    true => Color::Red,
+                    false if index == self.current_index => Color::Yellow,
+                    false => Color::White,
+                };
+                let style = match index == self.current_index {
+                    true => {
+                        Style::default().add_modifier(Modifier::BOLD).fg(color)
+                    }
+                    false => Style::default().fg(color),
+                };
+                ListItem::new(Line::from(vec![
+                    Span::raw("  ".repeat(row.depth)),
+                    Span::styled(marker, Style::default().fg(Color::DarkGray)),
+                    Span::styled(format!("{} ", row.label), style),
+                    Span::styled(
+                        value.map_or(String::new(), |value| {
+                            summary(value, collapsed)
+                        }),
+                        Style::default().fg(Color::Cyan),
+                    ),
+                ]))
+            })
+            .collect();
+        List::new(items).render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_edit_settings_tree() {
+        let mut tree = SettingsTree::new(json!({
+            "stop": ["</s>"],
+            "temperature": 0.7,
+            "top_k": 40
+        }));
+        // stop, [0], temperature, top_k
+        assert_eq!(tree.rows().len(), 4);
+        tree.select();
+        assert_eq!(tree.rows().len(), 3);
+        tree.select();
+        tree.add();
+        "User:".chars().for_each(|c| tree.edit_input(c));
+        tree.edit_commit().unwrap();
+
+        tree.current_index = 4;
+        tree.select();
+        tree.edit_backspace();
+        tree.edit_backspace();
+        tree.edit_input('x');
+        assert!(tree.edit_commit().is_err());
+        tree.edit_backspace();
+        "0.5".chars().for_each(|c| tree.edit_input(c));
+        assert!(tree.edit_commit().is_err());
+        tree.edit_cancel();
+        tree.delete();
+        assert_eq!(
+            tree.value(),
+            &json!({"stop": ["</s>", "User:"], "temperature": 0.7})
+        );
+        assert!(parse_as(&json!(true), "yes").is_err());
+        assert_eq!(parse_as(&json!(1), "2").unwrap(), json!(2));
+        assert_eq!(parse_as(&json!(0.5), "2").unwrap(), json!(2));
+    }
+}
@@ -0,0 +1,101 @@
+use std::ops::Range;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::block::{Block, Padding};
+use ratatui::widgets::{Borders, Clear, List, ListItem, Widget};
+
+pub const MAX_SUGGESTIONS: usize = 8;
+const MIN_WIDTH: u16 = 16;
+
+// replacements for a misspelled word in the prompt, shown below the cursor
+pub struct SpellSuggestions {
+    line: usize,         // line of the word in the prompt
+    range: Range<usize>, // byte range of the word in the line
+    suggestions: Vec<String>,
+    current_index: usize,
+}
+
+impl SpellSuggestions {
+    pub fn new(
+        line: usize,
+        range: Range<usize>,
+        suggestions: Vec<String>,
+    ) -> Self {
+        Self {
+            line,
+            range,
+            suggestions,
+            current_index: 0,
+        }
+    }
+
+    pub fn key_down(&mut self) {
+        self.current_index = (self.current_index + 1) % self.suggestions.len();
+    }
+
+    pub fn key_up(&mut self) {
+        self.current_index = if self.current_index == 0 {
+            self.suggestions.len().saturating_sub(1)
+        } else {
+            self.current_index - 1
+        };
+    }
+
+    // (line, byte range, replacement) of the selected suggestion
+    pub fn selected(&self) -> Option<(usize, Range<usize>, &str)> {
+        let suggestion = self.suggestions.get(self.current_index)?;
+        Some((self.line, self.range.clone(), suggestion))
+    }
+
+    // below the cursor, or above it if there is no room left below
+    pub fn area(&self, cursor: (u16, u16), frame: Rect) -> Rect {
+        let longest = self
+            .suggestions
+            .iter()
+            .map(|suggestion| suggestion.chars().count())
+            .max()
+            .unwrap_or(0) as u16;
+        let width = (longest + 4).max(MIN_WIDTH).min(frame.width);
+        let height = (self.suggestions.len() as u16 + 2).min(frame.height);
+        let (x, y) = cursor;
+        let y = if y + 1 + height <= frame.bottom() {
+            y + 1
+        } else {
+            y.saturating_sub(height)
+        };
+        let x = x.min(frame.right().saturating_sub(width));
+        Rect::new(x, y, width, height)
+    }
+}
+
+impl Widget for &SpellSuggestions {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        Clear.render(area, buf);
+        let items: Vec<ListItem> = self
+            .suggestions
+            .iter()
+            .enumerate()
+            .map(|(index, suggestion)| {
+                let style = if index == self.current_index {
+                    Style::default()
+                        .add_modifier(Modifier::BOLD)
+                        .fg(Color::Yellow)
+                } else {
+                    Style::default().fg(Color::White)
+                };
+                ListItem::new(Line::styled(suggestion.as_str(), style))
+            })
+            .collect();
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Spelling")
+                .padding(Padding::horizontal(1))
+                .style(Style::default().bg(Color::Black)),
+        );
+        list.render(area, buf);
+    }
+}
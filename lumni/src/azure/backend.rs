@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+
+pub use super::bucket::AzureBucket;
+use super::config::validate_config;
+use super::list::list_containers;
+use crate::handlers::object_store::ObjectStoreBackend;
+use crate::{EnvironmentConfig, LumniError, ObjectStoreTable};
+
+pub struct AzureBackend;
+
+#[async_trait(?Send)]
+impl ObjectStoreBackend for AzureBackend {
+    fn new(_config: EnvironmentConfig) -> Result<Self, LumniError> {
+        Ok(Self)
+    }
+
+    async fn list_buckets(
+        config: EnvironmentConfig,
+        max_files: Option<u32>,
+        table: &mut ObjectStoreTable,
+    ) -> Result<(), LumniError> {
+        let mut config = config;
+        validate_config(&mut config)?;
+        list_containers(&config, max_files, table).await
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+
+use super::config::validate_config;
+use super::get::{get_object, get_object_stream};
+use super::head::head_object;
+use super::list::list_files;
+use crate::base::config::EnvironmentConfig;
+use crate::handlers::object_store::{
+    ByteRange, ObjectStoreTrait, ObjectStream,
+};
+use crate::table::FileObjectTable;
+use crate::{FileObjectFilter, LumniError};
+
+#[derive(Debug, Clone)]
+pub struct AzureBucket {
+    name: String,
+    container: String,
+    config: EnvironmentConfig,
+}
+
+impl AzureBucket {
+    pub fn new(
+        name: &str,
+        mut config: EnvironmentConfig,
+    ) -> Result<AzureBucket, LumniError> {
+        // abfss names carry the account, e.g.
+        // "container@account.dfs.core.windows.net"
+        let container = match name.split_once('@') {
+            Some((container, host)) => {
+                let account = host.split('.').next().unwrap_or(host);
+                config.insert(
+                    "AZURE_STORAGE_ACCOUNT".to_string(),
+                    account.to_string(),
+                );
+                container
+            }
+            None => name,
+        };
+        validate_config(&mut config)?;
+
+        Ok(AzureBucket {
+            name: name.to_string(),
+            container: container.to_string(),
+            config,
+        })
+    }
+
+    pub fn config(&self) -> &EnvironmentConfig {
+        &self.config
+    }
+
+    pub fn container(&self) -> &str {
+        &self.container
+    }
+
+    pub fn is_abfss(&self) -> bool {
+        self.name != self.container
+    }
+}
+
+#[async_trait(?Send)]
+impl ObjectStoreTrait for AzureBucket {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn config(&self) -> &EnvironmentConfig {
+        &self.config
+    }
+
+    async fn list_files(
+        &self,
+        prefix: Option<&str>,
+        selected_columns: &Option<Vec<&str>>,
+        recursive: bool,
+        max_keys: Option<u32>,
+        filter: &Option<FileObjectFilter>,
+        table: &mut FileObjectTable,
+    ) -> Result<(), LumniError> {
+        if let Some(prefix) = prefix {
+            // prefix should not exist as a file object
+            let (status_code, _response_headers) =
+                self.head_object(prefix.trim_end_matches('/')).await?;
+            if status_code != 404 {
+                return Err(LumniError::NoBucketInUri(prefix.to_string()));
+            }
+        }
+        list_files(
+            self,
+            prefix,
+            selected_columns,
+            recursive,
+            max_keys,
+            filter,
+            table,
+        )
+        .await
+    }
+
+    async fn get_object(
+        &self,
+        key: &str,
+        data: &mut Vec<u8>,
+    ) -> Result<(), LumniError> {
+        get_object(self, key, data).await
+    }
+
+    async fn head_object(
+        &self,
+        key: &str,
+    ) -> Result<(u16, HashMap<String, String>), LumniError> {
+        head_object(self, key).await
+    }
+
+    async fn get_object_stream(
+        &self,
+        key: &str,
+        range: Option<ByteRange>,
+    ) -> Result<ObjectStream, LumniError> {
+        get_object_stream(self, key, range).await
+    }
+}
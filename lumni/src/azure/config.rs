@@ -0,0 +1,71 @@
+use std::env;
+
+use crate::{EnvironmentConfig, LumniError};
+
+pub fn validate_config(
+    config: &mut EnvironmentConfig,
+) -> Result<(), LumniError> {
+    // Set AZURE_STORAGE_ACCOUNT, required for both the endpoint and for
+    // signing requests with an account key
+    if !config.contains_key("AZURE_STORAGE_ACCOUNT") {
+        let account = env::var("AZURE_STORAGE_ACCOUNT").map_err(|_| {
+            LumniError::Config("AZURE_STORAGE_ACCOUNT is required".to_string())
+        })?;
+        config.insert("AZURE_STORAGE_ACCOUNT".to_string(), account);
+    }
+
+    // Set AZURE_STORAGE_KEY (optional), used for Shared Key authorization
+    if !config.contains_key("AZURE_STORAGE_KEY") {
+        if let Ok(key) = env::var("AZURE_STORAGE_KEY") {
+            config.insert("AZURE_STORAGE_KEY".to_string(), key);
+        }
+    }
+
+    // Set AZURE_STORAGE_SAS_TOKEN (optional), takes precedence over the
+    // account key when both are set
+    if !config.contains_key("AZURE_STORAGE_SAS_TOKEN") {
+        if let Ok(token) = env::var("AZURE_STORAGE_SAS_TOKEN") {
+            config.insert("AZURE_STORAGE_SAS_TOKEN".to_string(), token);
+        }
+    }
+    if let Some(token) = config.get("AZURE_STORAGE_SAS_TOKEN") {
+        // tokens are often copied from the portal including the '?'
+        let token = token.trim_start_matches('?').to_string();
+        config.insert("AZURE_STORAGE_SAS_TOKEN".to_string(), token);
+    }
+
+    // Set AZURE_STORAGE_ENDPOINT, can point to Azurite for local testing,
+    // e.g. http://127.0.0.1:10000/devstoreaccount1
+    if !config.contains_key("AZURE_STORAGE_ENDPOINT") {
+        let endpoint_url =
+            env::var("AZURE_STORAGE_ENDPOINT").unwrap_or_else(|_| {
+                format!(
+                    "https://{}.blob.core.windows.net",
+                    config.get("AZURE_STORAGE_ACCOUNT").unwrap()
+                )
+            });
+        config.insert("AZURE_STORAGE_ENDPOINT".to_string(), endpoint_url);
+    }
+    Ok(())
+}
+
+// account and key or SAS token of the config or the environment, as the
+// environment variables of the Azure CLI. none without an account
+pub fn credential_variables(
+    config: &EnvironmentConfig,
+) -> Vec<(String, String)> {
+    let mut config = config.clone();
+    if validate_config(&mut config).is_err() {
+        return Vec::new();
+    }
+    [
+        "AZURE_STORAGE_ACCOUNT",
+        "AZURE_STORAGE_KEY",
+        "AZURE_STORAGE_SAS_TOKEN",
+    ]
+    .into_iter()
+    .filter_map(|name| {
+        config.get(name).map(|value| (name.to_string(), value.clone()))
+    })
+    .collect()
+}
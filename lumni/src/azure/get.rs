@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use super::bucket::AzureBucket;
+use super::client::AzureClient;
+use super::list::check_status;
+use crate::base::ranged_read::{ranged_stream, Chunk};
+use crate::handlers::object_store::{ByteRange, ObjectStream};
+use crate::http::requests::{http_get_request, http_request_with_headers};
+use crate::{LumniError, DEFAULT_DOWNLOAD_CHUNK_SIZE};
+
+pub async fn get_object(
+    azure_bucket: &AzureBucket,
+    object_key: &str,
+    data: &mut Vec<u8>,
+) -> Result<(), LumniError> {
+    let client = AzureClient::new(
+        azure_bucket.config(),
+        Some(azure_bucket.container()),
+    )?;
+
+    log::info!("Getting object: {}", object_key);
+    let (url, headers) = client.request("GET", Some(object_key), &[])?;
+    let (body_bytes, status) = http_get_request(&url, &headers).await?;
+    check_status(status, object_key)?;
+    log::info!(
+        "Got object: {} of size {} bytes",
+        object_key,
+        body_bytes.len()
+    );
+    data.clear();
+    data.extend_from_slice(&body_bytes);
+    Ok(())
+}
+
+// each chunk is fetched with its own range request, as S3 does
+pub async fn get_object_stream(
+    azure_bucket: &AzureBucket,
+    object_key: &str,
+    range: Option<ByteRange>,
+) -> Result<ObjectStream, LumniError> {
+    let azure_bucket = azure_bucket.clone();
+    let object_key = object_key.to_string();
+    Ok(ranged_stream(range, DEFAULT_DOWNLOAD_CHUNK_SIZE, move |range| {
+        let azure_bucket = azure_bucket.clone();
+        let object_key = object_key.clone();
+        async move { get_chunk(&azure_bucket, &object_key, range).await }
+    }))
+}
+
+async fn get_chunk(
+    azure_bucket: &AzureBucket,
+    object_key: &str,
+    range: ByteRange,
+) -> Result<Chunk, LumniError> {
+    let client = AzureClient::new(
+        azure_bucket.config(),
+        Some(azure_bucket.container()),
+    )?;
+    log::debug!(
+        "Getting range {} of object: {}",
+        range.to_header_value(),
+        object_key
+    );
+    // x-ms-range is signed, unlike Range with shared key authorization
+    let range_header =
+        HashMap::from([("x-ms-range".to_string(), range.to_header_value())]);
+    let (url, headers) = client.request_with_headers(
+        "GET",
+        Some(object_key),
+        &[],
+        range_header,
+    )?;
+    let (body_bytes, status, response_headers) =
+        http_request_with_headers(&url, &headers, "GET").await?;
+    Chunk::from_http(status, &response_headers, body_bytes, object_key)
+}
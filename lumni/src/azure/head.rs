@@ -0,0 +1,24 @@
+use std::collections::HashMap;
+
+use log::info;
+
+use super::bucket::AzureBucket;
+use super::client::AzureClient;
+use crate::http::requests::http_request_with_headers;
+use crate::LumniError;
+
+pub async fn head_object(
+    azure_bucket: &AzureBucket,
+    object_key: &str,
+) -> Result<(u16, HashMap<String, String>), LumniError> {
+    let client = AzureClient::new(
+        azure_bucket.config(),
+        Some(azure_bucket.container()),
+    )?;
+
+    info!("Head object: {}", object_key);
+    let (url, headers) = client.request("HEAD", Some(object_key), &[])?;
+    let (_body_bytes, status, response_headers) =
+        http_request_with_headers(&url, &headers, "HEAD").await?;
+    Ok((status, response_headers))
+}
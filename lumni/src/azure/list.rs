@@ -0,0 +1,171 @@
+use std::collections::VecDeque;
+
+use tracing::{info_span, Instrument};
+
+use super::bucket::AzureBucket;
+use super::client::AzureClient;
+use super::parse_http_response::{parse_container_names, parse_file_objects};
+use crate::base::config::EnvironmentConfig;
+use crate::handlers::object_store::{ObjectStore, ObjectStoreTrait};
+use crate::http::requests::http_get_request;
+use crate::table::{FileObjectTable, ObjectStoreTable, Table};
+use crate::{FileObject, FileObjectFilter, LumniError};
+
+// maximum page size accepted by the Blob service
+const AZURE_MAX_LIST_OBJECTS: u32 = 5000;
+
+pub async fn list_files(
+    azure_bucket: &AzureBucket,
+    prefix: Option<&str>,
+    _selected_columns: &Option<Vec<&str>>, // not yet implemented
+    recursive: bool,
+    max_keys: Option<u32>,
+    filter: &Option<FileObjectFilter>,
+    table: &mut FileObjectTable,
+) -> Result<(), LumniError> {
+    let client = AzureClient::new(
+        azure_bucket.config(),
+        Some(azure_bucket.container()),
+    )?;
+
+    let max_files = max_keys.unwrap_or(AZURE_MAX_LIST_OBJECTS) as usize;
+    // when filter is provided, fetch full pages because we are not sure
+    // how many objects will be filtered out
+    let page_size = if filter.is_some() {
+        AZURE_MAX_LIST_OBJECTS
+    } else {
+        max_keys
+            .unwrap_or(AZURE_MAX_LIST_OBJECTS)
+            .min(AZURE_MAX_LIST_OBJECTS)
+    };
+
+    let mut directory_stack = VecDeque::new();
+    directory_stack.push_back(prefix.map(|p| p.to_owned()));
+
+    while let Some(prefix) = directory_stack.pop_front() {
+        let mut marker: Option<String> = None;
+        loop {
+            let mut query = vec![
+                ("restype", "container".to_string()),
+                ("comp", "list".to_string()),
+                ("delimiter", "/".to_string()),
+                ("maxresults", page_size.to_string()),
+            ];
+            if let Some(prefix) = &prefix {
+                query.push(("prefix", prefix.clone()));
+            }
+            if let Some(marker) = &marker {
+                query.push(("marker", marker.clone()));
+            }
+            let (url, headers) = client.request("GET", None, &query)?;
+            let (body, status) = http_get_request(&url, &headers)
+                .instrument(info_span!("list_page"))
+                .await?;
+            check_status(status, azure_bucket.name())?;
+
+            let (file_objects, prefixes, next_marker) = parse_file_objects(
+                &String::from_utf8_lossy(&body),
+            )
+            .map_err(|e| {
+                LumniError::Internal(format!(
+                    "Failed to parse list response: {}",
+                    e
+                ))
+            })?;
+
+            let mut temp_file_objects = Vec::new();
+            for virtual_directory in prefixes {
+                if filter.is_none() {
+                    temp_file_objects.push(FileObject::new(
+                        virtual_directory.clone(),
+                        0,
+                        None,
+                        None,
+                    ));
+                }
+                if recursive {
+                    directory_stack.push_back(Some(virtual_directory));
+                }
+            }
+            for file_object in file_objects {
+                if let Some(filter) = filter {
+                    if !filter.matches(&file_object) {
+                        continue;
+                    }
+                }
+                temp_file_objects.push(file_object);
+            }
+
+            let max_to_add = max_files.saturating_sub(table.len());
+            if !temp_file_objects.is_empty() && max_to_add > 0 {
+                let objects_to_add = temp_file_objects
+                    .drain(..)
+                    .take(max_to_add)
+                    .collect::<Vec<_>>();
+                table.add_file_objects(objects_to_add).await?;
+            }
+
+            if table.len() >= max_files {
+                return Ok(());
+            }
+            marker = next_marker;
+            if marker.is_none() {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+pub async fn list_containers(
+    config: &EnvironmentConfig,
+    max_files: Option<u32>,
+    table: &mut ObjectStoreTable,
+) -> Result<(), LumniError> {
+    let client = AzureClient::new(config, None)?;
+    let account = config.get("AZURE_STORAGE_ACCOUNT").unwrap();
+
+    let mut count = 0;
+    let mut marker: Option<String> = None;
+    loop {
+        let mut query = vec![("comp", "list".to_string())];
+        if let Some(marker) = &marker {
+            query.push(("marker", marker.clone()));
+        }
+        let (url, headers) = client.request("GET", None, &query)?;
+        let (body, status) = http_get_request(&url, &headers).await?;
+        check_status(status, account)?;
+
+        let (container_names, next_marker) = parse_container_names(
+            &String::from_utf8_lossy(&body),
+        )
+        .map_err(|e| {
+            LumniError::Internal(format!(
+                "Failed to parse container list: {}",
+                e
+            ))
+        })?;
+
+        for name in container_names {
+            if max_files.is_some_and(|max| count >= max as usize) {
+                return Ok(());
+            }
+            let object_store =
+                ObjectStore::new(&format!("az://{}", name), config.clone())?;
+            table.add_object_store(object_store).await?;
+            count += 1;
+        }
+        marker = next_marker;
+        if marker.is_none() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+pub fn check_status(status: u16, resource: &str) -> Result<(), LumniError> {
+    match status {
+        200..=299 => Ok(()),
+        _ => Err(LumniError::from_status(status, resource, "")),
+    }
+}
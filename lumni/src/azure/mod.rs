@@ -0,0 +1,10 @@
+pub mod backend;
+mod bucket;
+mod client;
+mod config;
+mod get;
+mod head;
+mod list;
+mod parse_http_response;
+
+pub(crate) use config::credential_variables;
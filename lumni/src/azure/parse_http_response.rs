@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::utils::time::rfc2822_to_epoch;
+use crate::{Checksum, ChecksumAlgorithm, FileObject};
+
+// allow non snake case for the XML response
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize)]
+struct ListBlobsResult {
+    Blobs: Option<Blobs>,
+    NextMarker: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Blobs {
+    // blobs and virtual directories are interleaved in lexical order
+    #[serde(rename = "$value", default)]
+    items: Vec<BlobItem>,
+}
+
+#[derive(Debug, Deserialize)]
+enum BlobItem {
+    Blob(Blob),
+    BlobPrefix(BlobPrefix),
+}
+
+// allow non snake case for the XML response
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize)]
+struct Blob {
+    Name: String,
+    Properties: BlobProperties,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlobProperties {
+    #[serde(rename = "Last-Modified")]
+    last_modified: Option<String>,
+    #[serde(rename = "Content-Length")]
+    content_length: Option<u64>,
+    #[serde(rename = "Etag")]
+    etag: Option<String>,
+    #[serde(rename = "Content-MD5")]
+    content_md5: Option<String>,
+    // Hot, Cool, Cold or Archive
+    #[serde(rename = "AccessTier")]
+    access_tier: Option<String>,
+}
+
+// allow non snake case for the XML response
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize)]
+struct BlobPrefix {
+    Name: String,
+}
+
+// allow non snake case for the XML response
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize)]
+struct ListContainersResult {
+    Containers: Option<Containers>,
+    NextMarker: Option<String>,
+}
+
+// allow non snake case for the XML response
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize)]
+struct Containers {
+    #[serde(default)]
+    Container: Vec<Container>,
+}
+
+// allow non snake case for the XML response
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize)]
+struct Container {
+    Name: String,
+}
+
+impl Blob {
+    fn into_file_object(self) -> FileObject {
+        let properties = self.Properties;
+        let modified = properties
+            .last_modified
+            .as_deref()
+            .and_then(|last_modified| rfc2822_to_epoch(last_modified).ok());
+
+        // only set when the MD5 was provided on upload
+        let checksum = properties
+            .content_md5
+            .as_deref()
+            .and_then(|md5| Checksum::from_base64(ChecksumAlgorithm::Md5, md5));
+        let mut tags = HashMap::new();
+        if let Some(etag) = properties.etag {
+            tags.insert("ETag".to_string(), etag);
+        }
+        if let Some(content_md5) = properties.content_md5 {
+            tags.insert("Content-MD5".to_string(), content_md5);
+        }
+        FileObject::new(
+            self.Name,
+            properties.content_length.unwrap_or(0),
+            modified,
+            Some(tags),
+        )
+        .set_checksum(checksum)
+        .set_storage_class(properties.access_tier)
+    }
+}
+
+// an empty <NextMarker /> marks the last page
+fn next_marker(marker: Option<String>) -> Option<String> {
+    marker.filter(|m| !m.is_empty())
+}
+
+// file objects, virtual directories and the next marker
+type ListBlobsPage = (Vec<FileObject>, Vec<String>, Option<String>);
+
+pub fn parse_file_objects(
+    body: &str,
+) -> Result<ListBlobsPage, serde_xml_rs::Error> {
+    let result: ListBlobsResult = serde_xml_rs::from_str(body)?;
+    let mut file_objects = Vec::new();
+    let mut prefixes = Vec::new();
+    for item in result.Blobs.map(|b| b.items).unwrap_or_default() {
+        match item {
+            BlobItem::Blob(blob) => file_objects.push(blob.into_file_object()),
+            BlobItem::BlobPrefix(prefix) => prefixes.push(prefix.Name),
+        }
+    }
+    Ok((file_objects, prefixes, next_marker(result.NextMarker)))
+}
+
+pub fn parse_container_names(
+    body: &str,
+) -> Result<(Vec<String>, Option<String>), serde_xml_rs::Error> {
+    let result: ListContainersResult = serde_xml_rs::from_str(body)?;
+    let names = result
+        .Containers
+        .map(|c| c.Container)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|container| container.Name)
+        .collect();
+    Ok((names, next_marker(result.NextMarker)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file_objects() {
+        let body = r#"<?xml version="1.0" encoding="utf-8"?>
+<EnumerationResults ServiceEndpoint="https://acc.blob.core.windows.net/" ContainerName="data">
+  <Prefix>logs/</Prefix>
+  <Delimiter>/</Delimiter>
+  <Blobs>
+    <Blob>
+      <Name>logs/a.txt</Name>
+      <Properties>
+        <Last-Modified>Mon, 27 Jul 2009 12:28:53 GMT</Last-Modified>
+        <Etag>0x8CBFF45D8A29A19</Etag>
+        <Content-Length>42</Content-Length>
+        <Content-Type>text/plain</Content-Type>
+      </Properties>
+    </Blob>
+    <BlobPrefix><Name>logs/2024/</Name></BlobPrefix>
+    <Blob>
+      <Name>logs/b.txt</Name>
+      <Properties>
+        <Content-Length>0</Content-Length>
+      </Properties>
+    </Blob>
+  </Blobs>
+  <NextMarker />
+</EnumerationResults>"#;
+        let (file_objects, prefixes, marker) =
+            parse_file_objects(body).unwrap();
+        assert_eq!(file_objects.len(), 2);
+        assert_eq!(file_objects[0].name(), "logs/a.txt");
+        assert_eq!(file_objects[0].size(), 42);
+        assert_eq!(file_objects[0].modified(), Some(1248697733));
+        assert_eq!(prefixes, vec!["logs/2024/".to_string()]);
+        assert_eq!(marker, None);
+    }
+}
@@ -0,0 +1,178 @@
+use std::fmt;
+use std::io::{self, Read};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+use crate::{EnvironmentConfig, LumniError};
+
+const CHECKSUM_READ_BUFFER_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChecksumAlgorithm {
+    Md5,
+    Sha256,
+    Crc32c,
+    // an S3 ETag that is not known to be the md5 of the content, e.g. of
+    // a multipart upload or of an object encrypted with SSE-KMS/SSE-C.
+    // it can only be compared with another ETag, not computed locally
+    Etag,
+}
+
+impl ChecksumAlgorithm {
+    pub fn from_name(algorithm: &str) -> Option<Self> {
+        match algorithm.to_lowercase().as_str() {
+            "md5" => Some(ChecksumAlgorithm::Md5),
+            "sha256" => Some(ChecksumAlgorithm::Sha256),
+            "crc32c" => Some(ChecksumAlgorithm::Crc32c),
+            "etag" => Some(ChecksumAlgorithm::Etag),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Md5 => "md5",
+            ChecksumAlgorithm::Sha256 => "sha256",
+            ChecksumAlgorithm::Crc32c => "crc32c",
+            ChecksumAlgorithm::Etag => "etag",
+        }
+    }
+
+    // CHECKSUM_ALGORITHM selects what is computed locally, md5 by default
+    // as it is what S3 (single part ETag), GCS and Azure return
+    pub fn from_config(config: &EnvironmentConfig) -> Result<Self, LumniError> {
+        match config.get("CHECKSUM_ALGORITHM") {
+            Some(algorithm) => ChecksumAlgorithm::from_name(algorithm)
+                .filter(|algorithm| *algorithm != ChecksumAlgorithm::Etag)
+                .ok_or_else(|| {
+                    LumniError::Config(format!(
+                        "Unsupported checksum algorithm: {}",
+                        algorithm
+                    ))
+                }),
+            None => Ok(ChecksumAlgorithm::Md5),
+        }
+    }
+}
+
+// content checksum of an object, the value is lowercase hex
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checksum {
+    algorithm: ChecksumAlgorithm,
+    value: String,
+}
+
+impl Checksum {
+    pub fn new(algorithm: ChecksumAlgorithm, value: &str) -> Self {
+        Checksum {
+            algorithm,
+            value: value.to_lowercase(),
+        }
+    }
+
+    // object stores return checksums base64 encoded, e.g. GCS md5Hash
+    pub fn from_base64(
+        algorithm: ChecksumAlgorithm,
+        value: &str,
+    ) -> Option<Self> {
+        let bytes = STANDARD.decode(value).ok()?;
+        Some(Checksum {
+            algorithm,
+            value: hex::encode(bytes),
+        })
+    }
+
+    pub fn from_reader(
+        algorithm: ChecksumAlgorithm,
+        reader: &mut dyn Read,
+    ) -> io::Result<Self> {
+        if algorithm == ChecksumAlgorithm::Etag {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "an ETag cannot be computed from the content",
+            ));
+        }
+        let mut buffer = vec![0u8; CHECKSUM_READ_BUFFER_SIZE];
+        let mut md5 = Md5::new();
+        let mut sha256 = Sha256::new();
+        let mut crc32c = 0u32;
+        loop {
+            let count = reader.read(&mut buffer)?;
+            if count == 0 {
+                break;
+            }
+            let data = &buffer[..count];
+            match algorithm {
+                ChecksumAlgorithm::Md5 => md5.update(data),
+                ChecksumAlgorithm::Sha256 => sha256.update(data),
+                ChecksumAlgorithm::Crc32c => {
+                    crc32c = crc32c::crc32c_append(crc32c, data)
+                }
+                ChecksumAlgorithm::Etag => unreachable!(),
+            }
+        }
+        let value = match algorithm {
+            ChecksumAlgorithm::Md5 => hex::encode(md5.finalize()),
+            ChecksumAlgorithm::Sha256 => hex::encode(sha256.finalize()),
+            ChecksumAlgorithm::Crc32c => hex::encode(crc32c.to_be_bytes()),
+            ChecksumAlgorithm::Etag => unreachable!(),
+        };
+        Ok(Checksum { algorithm, value })
+    }
+
+    pub fn algorithm(&self) -> ChecksumAlgorithm {
+        self.algorithm
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+impl fmt::Display for Checksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm.as_str(), self.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_reader() {
+        let checksum = |algorithm| {
+            Checksum::from_reader(algorithm, &mut &b"hello world"[..])
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(
+            checksum(ChecksumAlgorithm::Md5),
+            "md5:5eb63bbbe01eeed093cb22bb8f5acdc3"
+        );
+        assert_eq!(
+            checksum(ChecksumAlgorithm::Sha256),
+            "sha256:b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+        assert_eq!(checksum(ChecksumAlgorithm::Crc32c), "crc32c:c99465aa");
+        assert!(Checksum::from_reader(
+            ChecksumAlgorithm::Etag,
+            &mut &b"hello world"[..]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_from_base64_matches_hex() {
+        // GCS md5Hash of "hello world"
+        let checksum = Checksum::from_base64(
+            ChecksumAlgorithm::Md5,
+            "XrY7u+Ae7tCTyyK7j1rNww==",
+        )
+        .unwrap();
+        assert_eq!(checksum.value(), "5eb63bbbe01eeed093cb22bb8f5acdc3");
+    }
+}
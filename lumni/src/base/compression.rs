@@ -0,0 +1,609 @@
+use std::borrow::Cow;
+use std::io::{self, Read, Write};
+use std::mem;
+
+use flate2::write::{GzEncoder, MultiGzDecoder};
+
+use super::config::EnvironmentConfig;
+use crate::LumniError;
+
+// "false" turns off decompressing objects while they are read, e.g. by
+// "-X GET", grep and the preview of browse
+pub const DECOMPRESS_SETTING: &str = "DECOMPRESS";
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const BZIP2_MAGIC: &[u8] = b"BZh";
+const LZ4_MAGIC: u32 = 0x184d2204;
+// bytes needed to recognize any of the formats
+const DETECT_LEN: usize = 4;
+// bytes read from the source per chunk when compressing
+const COMPRESS_CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Bzip2,
+    Lz4,
+}
+
+impl Compression {
+    pub fn from_name(name: &str) -> Result<Self, LumniError> {
+        match name.to_lowercase().as_str() {
+            "gzip" | "gz" => Ok(Compression::Gzip),
+            "zstd" | "zst" => Ok(Compression::Zstd),
+            "bzip2" | "bz2" => Ok(Compression::Bzip2),
+            "lz4" => Ok(Compression::Lz4),
+            _ => Err(LumniError::Config(format!(
+                "Unknown compression: {}, expected gzip, zstd, bzip2 or lz4",
+                name
+            ))),
+        }
+    }
+
+    // e.g. "logs/app.log.gz"
+    pub fn from_extension(key: &str) -> Option<Self> {
+        let (_, extension) = key.rsplit_once('.')?;
+        match extension.to_lowercase().as_str() {
+            "gz" | "gzip" => Some(Compression::Gzip),
+            "zst" | "zstd" => Some(Compression::Zstd),
+            "bz2" => Some(Compression::Bzip2),
+            "lz4" => Some(Compression::Lz4),
+            _ => None,
+        }
+    }
+
+    // by the magic bytes at the start of the data, the key of an object
+    // can not be relied on
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(GZIP_MAGIC) {
+            Some(Compression::Gzip)
+        } else if data.starts_with(ZSTD_MAGIC) {
+            Some(Compression::Zstd)
+        } else if data.starts_with(BZIP2_MAGIC)
+            && data
+                .get(3)
+                .is_some_and(|level| (b'1'..=b'9').contains(level))
+        {
+            Some(Compression::Bzip2)
+        } else if data.len() >= 4 && read_u32(data) == LZ4_MAGIC {
+            Some(Compression::Lz4)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+            Compression::Bzip2 => "bzip2",
+            Compression::Lz4 => "lz4",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+            Compression::Bzip2 => "bz2",
+            Compression::Lz4 => "lz4",
+        }
+    }
+}
+
+// decompresses data that arrives in chunks, e.g. of an ObjectStream,
+// without keeping more than what one chunk decompresses to
+pub enum StreamDecoder {
+    Gzip(MultiGzDecoder<Vec<u8>>),
+    Zstd(zstd::stream::write::Decoder<'static, Vec<u8>>),
+    Bzip2(bzip2::write::BzDecoder<Vec<u8>>),
+    Lz4(Lz4Decoder),
+}
+
+impl StreamDecoder {
+    pub fn new(compression: Compression) -> io::Result<Self> {
+        Ok(match compression {
+            Compression::Gzip => {
+                StreamDecoder::Gzip(MultiGzDecoder::new(Vec::new()))
+            }
+            Compression::Zstd => StreamDecoder::Zstd(
+                zstd::stream::write::Decoder::new(Vec::new())?,
+            ),
+            Compression::Bzip2 => {
+                StreamDecoder::Bzip2(bzip2::write::BzDecoder::new(Vec::new()))
+            }
+            Compression::Lz4 => StreamDecoder::Lz4(Lz4Decoder::default()),
+        })
+    }
+
+    // the data the chunk decompresses to, may be empty
+    pub fn write(&mut self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            StreamDecoder::Gzip(decoder) => {
+                decoder.write_all(chunk)?;
+                Ok(mem::take(decoder.get_mut()))
+            }
+            StreamDecoder::Zstd(decoder) => {
+                decoder.write_all(chunk)?;
+                decoder.flush()?;
+                Ok(mem::take(decoder.get_mut()))
+            }
+            StreamDecoder::Bzip2(decoder) => {
+                decoder.write_all(chunk)?;
+                Ok(mem::take(decoder.get_mut()))
+            }
+            StreamDecoder::Lz4(decoder) => decoder.write(chunk),
+        }
+    }
+
+    // the rest of the data
+    pub fn finish(self) -> io::Result<Vec<u8>> {
+        match self {
+            StreamDecoder::Gzip(decoder) => decoder.finish(),
+            StreamDecoder::Zstd(mut decoder) => {
+                decoder.flush()?;
+                Ok(decoder.into_inner())
+            }
+            StreamDecoder::Bzip2(mut decoder) => decoder.finish(),
+            StreamDecoder::Lz4(decoder) => decoder.finish(),
+        }
+    }
+}
+
+// decompresses data if it starts like one of the formats, and passes it
+// on as is otherwise
+pub enum AutoDecoder {
+    Detect(Vec<u8>), // the first bytes, until there are enough to tell
+    Decode(StreamDecoder),
+    Plain,
+}
+
+impl AutoDecoder {
+    pub fn new() -> Self {
+        AutoDecoder::Detect(Vec::new())
+    }
+
+    // unless DECOMPRESS is set to false
+    pub fn enabled(config: &EnvironmentConfig) -> bool {
+        !matches!(
+            config
+                .get(DECOMPRESS_SETTING)
+                .map(|value| value.to_lowercase())
+                .as_deref(),
+            Some("false" | "0" | "no" | "off")
+        )
+    }
+
+    pub fn write<'a>(&mut self, chunk: &'a [u8]) -> io::Result<Cow<'a, [u8]>> {
+        match self {
+            AutoDecoder::Plain => Ok(Cow::Borrowed(chunk)),
+            AutoDecoder::Decode(decoder) => {
+                Ok(Cow::Owned(decoder.write(chunk)?))
+            }
+            AutoDecoder::Detect(head) => {
+                head.extend_from_slice(chunk);
+                if head.len() < DETECT_LEN {
+                    return Ok(Cow::Borrowed(&[]));
+                }
+                let head = mem::take(head);
+                match Compression::detect(&head) {
+                    Some(compression) => {
+                        let mut decoder = StreamDecoder::new(compression)?;
+                        let data = decoder.write(&head)?;
+                        *self = AutoDecoder::Decode(decoder);
+                        Ok(Cow::Owned(data))
+                    }
+                    None => {
+                        *self = AutoDecoder::Plain;
+                        Ok(Cow::Owned(head))
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn finish(self) -> io::Result<Vec<u8>> {
+        match self {
+            // too short to be compressed
+            AutoDecoder::Detect(head) => Ok(head),
+            AutoDecoder::Decode(decoder) => decoder.finish(),
+            AutoDecoder::Plain => Ok(Vec::new()),
+        }
+    }
+
+    // all of the data at once, e.g. of get_object
+    pub fn decode(data: Vec<u8>) -> io::Result<Vec<u8>> {
+        if Compression::detect(&data).is_none() {
+            return Ok(data);
+        }
+        let mut decoder = AutoDecoder::new();
+        let mut decoded = decoder.write(&data)?.into_owned();
+        decoded.extend(decoder.finish()?);
+        Ok(decoded)
+    }
+}
+
+impl Default for AutoDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// compresses data that arrives in chunks, as StreamDecoder decompresses
+pub enum StreamEncoder {
+    Gzip(GzEncoder<Vec<u8>>),
+    Zstd(zstd::stream::write::Encoder<'static, Vec<u8>>),
+    Bzip2(bzip2::write::BzEncoder<Vec<u8>>),
+    Lz4(lz4_flex::frame::FrameEncoder<Vec<u8>>),
+}
+
+impl StreamEncoder {
+    pub fn new(compression: Compression) -> io::Result<Self> {
+        Ok(match compression {
+            Compression::Gzip => StreamEncoder::Gzip(GzEncoder::new(
+                Vec::new(),
+                flate2::Compression::default(),
+            )),
+            Compression::Zstd => StreamEncoder::Zstd(
+                zstd::stream::write::Encoder::new(Vec::new(), 0)?,
+            ),
+            Compression::Bzip2 => {
+                StreamEncoder::Bzip2(bzip2::write::BzEncoder::new(
+                    Vec::new(),
+                    bzip2::Compression::default(),
+                ))
+            }
+            Compression::Lz4 => StreamEncoder::Lz4(
+                lz4_flex::frame::FrameEncoder::new(Vec::new()),
+            ),
+        })
+    }
+
+    // the compressed data so far, may be empty
+    pub fn write(&mut self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        Ok(match self {
+            StreamEncoder::Gzip(encoder) => {
+                encoder.write_all(chunk)?;
+                mem::take(encoder.get_mut())
+            }
+            StreamEncoder::Zstd(encoder) => {
+                encoder.write_all(chunk)?;
+                mem::take(encoder.get_mut())
+            }
+            StreamEncoder::Bzip2(encoder) => {
+                encoder.write_all(chunk)?;
+                mem::take(encoder.get_mut())
+            }
+            StreamEncoder::Lz4(encoder) => {
+                encoder.write_all(chunk)?;
+                mem::take(encoder.get_mut())
+            }
+        })
+    }
+
+    pub fn finish(self) -> io::Result<Vec<u8>> {
+        match self {
+            StreamEncoder::Gzip(encoder) => encoder.finish(),
+            StreamEncoder::Zstd(encoder) => encoder.finish(),
+            StreamEncoder::Bzip2(encoder) => encoder.finish(),
+            StreamEncoder::Lz4(encoder) => Ok(encoder.finish()?),
+        }
+    }
+}
+
+// reads the source compressed, e.g. to upload it
+pub struct CompressReader<R: Read> {
+    source: R,
+    encoder: Option<StreamEncoder>, // None once the source is read
+    buffer: Vec<u8>,
+    output: Vec<u8>,
+    position: usize,
+}
+
+impl<R: Read> CompressReader<R> {
+    pub fn new(source: R, compression: Compression) -> io::Result<Self> {
+        Ok(CompressReader {
+            source,
+            encoder: Some(StreamEncoder::new(compression)?),
+            buffer: vec![0; COMPRESS_CHUNK_SIZE],
+            output: Vec::new(),
+            position: 0,
+        })
+    }
+}
+
+impl<R: Read> Read for CompressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.output.len() {
+            let Some(encoder) = self.encoder.as_mut() else {
+                return Ok(0);
+            };
+            let read = self.source.read(&mut self.buffer)?;
+            self.output = if read == 0 {
+                self.encoder.take().map_or(Ok(Vec::new()), |e| e.finish())?
+            } else {
+                encoder.write(&self.buffer[..read])?
+            };
+            self.position = 0;
+        }
+        let len = buf.len().min(self.output.len() - self.position);
+        buf[..len]
+            .copy_from_slice(&self.output[self.position..self.position + len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+// lz4 frames, decoded block by block as the data arrives. checksums are
+// not verified
+#[derive(Default)]
+pub struct Lz4Decoder {
+    input: Vec<u8>,
+    frame: Option<Lz4Frame>, // None between frames
+    window: Vec<u8>,         // last output, for blocks that refer to it
+}
+
+struct Lz4Frame {
+    linked: bool,
+    block_checksum: bool,
+    content_checksum: bool,
+    max_block_size: usize,
+}
+
+const LZ4_SKIPPABLE_MAGIC: u32 = 0x184d2a50; // to 0x184d2a5f
+const LZ4_WINDOW_SIZE: usize = 64 * 1024;
+const LZ4_UNCOMPRESSED_BLOCK: u32 = 0x8000_0000;
+
+impl Lz4Decoder {
+    fn write(&mut self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        let mut input = mem::take(&mut self.input);
+        input.extend_from_slice(chunk);
+        let mut output = Vec::new();
+        let mut position = 0;
+        while let Some(consumed) =
+            self.decode_next(&input[position..], &mut output)?
+        {
+            position += consumed;
+        }
+        input.drain(..position);
+        self.input = input;
+        Ok(output)
+    }
+
+    fn finish(self) -> io::Result<Vec<u8>> {
+        if self.frame.is_some() || !self.input.is_empty() {
+            return Err(invalid_lz4("truncated frame"));
+        }
+        Ok(Vec::new())
+    }
+
+    // bytes consumed, None if more input is needed
+    fn decode_next(
+        &mut self,
+        input: &[u8],
+        output: &mut Vec<u8>,
+    ) -> io::Result<Option<usize>> {
+        if input.len() < 4 {
+            return Ok(None);
+        }
+        let Some(frame) = &self.frame else {
+            return self.decode_header(input);
+        };
+        let word = read_u32(input);
+        if word == 0 {
+            // end of the frame
+            let end = 4 + if frame.content_checksum { 4 } else { 0 };
+            if input.len() < end {
+                return Ok(None);
+            }
+            self.frame = None;
+            return Ok(Some(end));
+        }
+        let size = (word & !LZ4_UNCOMPRESSED_BLOCK) as usize;
+        if size > frame.max_block_size {
+            return Err(invalid_lz4("block larger than the maximum size"));
+        }
+        let end = 4 + size + if frame.block_checksum { 4 } else { 0 };
+        if input.len() < end {
+            return Ok(None);
+        }
+        let data = &input[4..4 + size];
+        let block = if word & LZ4_UNCOMPRESSED_BLOCK != 0 {
+            data.to_vec()
+        } else if frame.linked {
+            lz4_flex::block::decompress_with_dict(
+                data,
+                frame.max_block_size,
+                &self.window,
+            )
+            .map_err(|e| invalid_lz4(&e.to_string()))?
+        } else {
+            lz4_flex::block::decompress(data, frame.max_block_size)
+                .map_err(|e| invalid_lz4(&e.to_string()))?
+        };
+        if frame.linked {
+            self.window.extend_from_slice(&block);
+            let excess = self.window.len().saturating_sub(LZ4_WINDOW_SIZE);
+            self.window.drain(..excess);
+        }
+        output.extend(block);
+        Ok(Some(end))
+    }
+
+    fn decode_header(&mut self, input: &[u8]) -> io::Result<Option<usize>> {
+        let magic = read_u32(input);
+        if magic & 0xffff_fff0 == LZ4_SKIPPABLE_MAGIC {
+            if input.len() < 8 {
+                return Ok(None);
+            }
+            let end = 8 + read_u32(&input[4..]) as usize;
+            return Ok((input.len() >= end).then_some(end));
+        }
+        if magic != LZ4_MAGIC {
+            return Err(invalid_lz4("no frame header"));
+        }
+        if input.len() < 7 {
+            return Ok(None);
+        }
+        let (flags, block_descriptor) = (input[4], input[5]);
+        if flags >> 6 != 1 {
+            return Err(invalid_lz4("unsupported version"));
+        }
+        // magic, flags, block descriptor, content size, dictionary id and
+        // header checksum
+        let end = 6
+            + if flags & 0x08 != 0 { 8 } else { 0 }
+            + if flags & 0x01 != 0 { 4 } else { 0 }
+            + 1;
+        if input.len() < end {
+            return Ok(None);
+        }
+        let max_block_size = match (block_descriptor >> 4) & 0x07 {
+            4 => 64 * 1024,
+            5 => 256 * 1024,
+            6 => 1024 * 1024,
+            7 => 4 * 1024 * 1024,
+            _ => return Err(invalid_lz4("invalid block size")),
+        };
+        self.frame = Some(Lz4Frame {
+            linked: flags & 0x20 == 0,
+            block_checksum: flags & 0x10 != 0,
+            content_checksum: flags & 0x04 != 0,
+            max_block_size,
+        });
+        self.window.clear();
+        Ok(Some(end))
+    }
+}
+
+fn invalid_lz4(reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid lz4 data: {}", reason),
+    )
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+    u32::from_le_bytes([data[0], data[1], data[2], data[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODECS: [Compression; 4] = [
+        Compression::Gzip,
+        Compression::Zstd,
+        Compression::Bzip2,
+        Compression::Lz4,
+    ];
+
+    #[test]
+    fn test_stream_decoder() {
+        let data = "line\n".repeat(1000);
+        for compression in CODECS {
+            let mut compressed = Vec::new();
+            CompressReader::new(data.as_bytes(), compression)
+                .unwrap()
+                .read_to_end(&mut compressed)
+                .unwrap();
+            assert_eq!(Compression::detect(&compressed), Some(compression));
+
+            let mut decoder = StreamDecoder::new(compression).unwrap();
+            let mut decompressed = Vec::new();
+            for chunk in compressed.chunks(7) {
+                decompressed.extend(decoder.write(chunk).unwrap());
+            }
+            decompressed.extend(decoder.finish().unwrap());
+            assert_eq!(decompressed, data.as_bytes(), "{:?}", compression);
+            assert_eq!(AutoDecoder::decode(compressed).unwrap(), decompressed);
+        }
+        assert_eq!(Compression::detect(data.as_bytes()), None);
+
+        // blocks that refer to the previous ones, as written by the lz4 cli
+        let data: Vec<u8> = (0..200_000u32)
+            .flat_map(|i| (i % 251).to_be_bytes())
+            .collect();
+        let frame_info = lz4_flex::frame::FrameInfo::new()
+            .block_mode(lz4_flex::frame::BlockMode::Linked)
+            .block_checksums(true)
+            .content_checksum(true);
+        let mut encoder = lz4_flex::frame::FrameEncoder::with_frame_info(
+            frame_info,
+            Vec::new(),
+        );
+        encoder.write_all(&data).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let mut decoder = StreamDecoder::new(Compression::Lz4).unwrap();
+        let mut decompressed = Vec::new();
+        for chunk in compressed.chunks(1000) {
+            decompressed.extend(decoder.write(chunk).unwrap());
+        }
+        decompressed.extend(decoder.finish().unwrap());
+        assert_eq!(decompressed, data);
+
+        let mut decoder = AutoDecoder::new();
+        assert_eq!(decoder.write(b"ab").unwrap().as_ref(), b"");
+        assert_eq!(decoder.write(b"cdef").unwrap().as_ref(), b"abcdef");
+        assert_eq!(decoder.finish().unwrap(), b"");
+    }
+
+    #[test]
+    fn test_compression_names() {
+        assert_eq!(
+            Compression::from_extension("logs/a.log.ZST"),
+            Some(Compression::Zstd)
+        );
+        assert_eq!(Compression::from_extension("logs/a.log"), None);
+        assert_eq!(Compression::from_name("bz2").unwrap(), Compression::Bzip2);
+        assert!(Compression::from_name("zip").is_err());
+
+        let config = EnvironmentConfig::with_setting(
+            DECOMPRESS_SETTING.to_string(),
+            "False".to_string(),
+        );
+        assert!(!AutoDecoder::enabled(&config));
+    }
+
+    #[test]
+    fn test_put_compressed() {
+        use std::collections::HashMap;
+
+        use futures::executor::block_on;
+
+        use crate::{ObjectStoreHandler, ParsedUri, UploadOptions};
+
+        let dir = tempfile::tempdir().unwrap();
+        let uri = ParsedUri::from_uri(
+            &format!("localfs://{}/data.csv.zst", dir.path().display()),
+            false,
+        );
+        let config = EnvironmentConfig::new(HashMap::new());
+        let handler = ObjectStoreHandler::new(None);
+        let options = UploadOptions::new().set_compression(Compression::Zstd);
+        let data = "id,name\n".repeat(100);
+        let mut source = data.as_bytes();
+        block_on(handler.put_object(
+            &uri,
+            &config,
+            &mut source,
+            &options,
+            None,
+        ))
+        .unwrap();
+
+        let stored = std::fs::read(dir.path().join("data.csv.zst")).unwrap();
+        assert_eq!(Compression::detect(&stored), Some(Compression::Zstd));
+        let read = block_on(handler.get_object(&uri, &config, None)).unwrap();
+        assert_eq!(read.as_deref(), Some(data.as_bytes()));
+
+        let config = EnvironmentConfig::with_setting(
+            DECOMPRESS_SETTING.to_string(),
+            "off".to_string(),
+        );
+        let read = block_on(handler.get_object(&uri, &config, None)).unwrap();
+        assert_eq!(read, Some(stored));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{EnvironmentConfig, LakestreamError, ObjectStoreHandler, Table};
+use crate::{EnvironmentConfig, LumniError, ObjectStoreHandler, Table};
 
 #[derive(Clone)]
 pub struct LakestreamHandler {
@@ -17,7 +17,7 @@ impl LakestreamHandler {
     pub async fn execute_query(
         &self,
         query: String,
-    ) -> Result<Box<dyn Table>, LakestreamError> {
+    ) -> Result<Box<dyn Table>, LumniError> {
         let callback = None;
         let result = self
             .handler
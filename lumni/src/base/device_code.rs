@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::Bytes;
+use serde::Deserialize;
+
+use super::token_cache::{cache_token, cached_token, CachedToken};
+use crate::http::requests::http_post_request;
+use crate::utils::time::system_time_in_seconds;
+use crate::LumniError;
+
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+const DEFAULT_POLL_INTERVAL_SECONDS: u64 = 5;
+// added to the interval when the token endpoint asks to slow down
+const SLOW_DOWN_SECONDS: u64 = 5;
+// refresh tokens rarely say when they expire, this is the inactivity
+// window of e.g. Microsoft Entra ID
+const REFRESH_TOKEN_LIFETIME_SECONDS: u64 = 90 * 24 * 3600;
+
+// what the user is asked to do to sign in
+#[derive(Debug, Clone)]
+pub struct DeviceCodePrompt {
+    pub user_code: String,
+    pub verification_uri: String,
+    pub message: Option<String>, // as given by the provider
+}
+
+impl fmt::Display for DeviceCodePrompt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.message {
+            Some(message) => write!(f, "{}", message),
+            None => write!(
+                f,
+                "To sign in, open {} and enter the code {}",
+                self.verification_uri, self.user_code
+            ),
+        }
+    }
+}
+
+pub type DeviceCodePromptCallback =
+    Arc<dyn Fn(&DeviceCodePrompt) + Send + Sync>;
+
+#[derive(Deserialize)]
+struct DeviceAuthorizationResponse {
+    device_code: String,
+    user_code: String,
+    // Google names it verification_url
+    #[serde(alias = "verification_url")]
+    verification_uri: String,
+    expires_in: u64,
+    interval: Option<u64>,
+    message: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+    refresh_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenErrorResponse {
+    error: String,
+    error_description: Option<String>,
+}
+
+// what a token response means while waiting for the user to sign in
+enum PollState {
+    Token(TokenResponse),
+    Pending,
+    SlowDown,
+}
+
+// OAuth 2.0 device authorization grant (RFC 8628), for providers where
+// there is no browser redirect to listen for, e.g. Azure OpenAI with
+// Microsoft Entra ID. the user signs in on another device with a code.
+// access and refresh tokens are kept in the TokenCache, so the sign in is
+// only needed again when the refresh token is no longer accepted
+pub struct DeviceCodeFlow {
+    client_id: String,
+    device_authorization_url: String,
+    token_url: String,
+    scope: Option<String>,
+    prompt: DeviceCodePromptCallback,
+    // for when the token cache is off
+    token: Mutex<Option<CachedToken>>,
+    refresh_token: Mutex<Option<String>>,
+}
+
+impl DeviceCodeFlow {
+    pub fn new(
+        client_id: &str,
+        device_authorization_url: &str,
+        token_url: &str,
+    ) -> Self {
+        DeviceCodeFlow {
+            client_id: client_id.to_string(),
+            device_authorization_url: device_authorization_url.to_string(),
+            token_url: token_url.to_string(),
+            scope: None,
+            prompt: Arc::new(|prompt| eprintln!("{}", prompt)),
+            token: Mutex::new(None),
+            refresh_token: Mutex::new(None),
+        }
+    }
+
+    // space separated, e.g. "https://cognitiveservices.azure.com/.default
+    // offline_access". offline_access is needed for a refresh token
+    pub fn set_scope(mut self, scope: &str) -> Self {
+        self.scope = Some(scope.to_string());
+        self
+    }
+
+    // shows the code and URL to the user, printed to stderr by default
+    pub fn set_prompt(mut self, prompt: DeviceCodePromptCallback) -> Self {
+        self.prompt = prompt;
+        self
+    }
+
+    // a valid access token, refreshed or signed in for when needed
+    pub async fn access_token(&self) -> Result<String, LumniError> {
+        let cache_key = self.cache_key("access");
+        if let Some(token) = cached_token(&cache_key) {
+            return Ok(token);
+        }
+        if let Some(token) = self.token.lock().unwrap().as_ref() {
+            if token.expiration > system_time_in_seconds() {
+                return Ok(token.value.clone());
+            }
+        }
+        let refresh_token = cached_token(&self.cache_key("refresh"))
+            .or_else(|| self.refresh_token.lock().unwrap().clone());
+        let refreshed = match refresh_token {
+            Some(refresh_token) => match self.refresh(&refresh_token).await {
+                Ok(token) => Some(token),
+                Err(e) => {
+                    // e.g. revoked, or expired after inactivity
+                    log::debug!("Token refresh failed: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+        let token = match refreshed {
+            Some(token) => token,
+            None => self.sign_in().await?,
+        };
+        Ok(self.store(token))
+    }
+
+    async fn sign_in(&self) -> Result<TokenResponse, LumniError> {
+        let mut form = vec![("client_id", self.client_id.as_str())];
+        if let Some(scope) = &self.scope {
+            form.push(("scope", scope));
+        }
+        let (body, status) =
+            post_form(&self.device_authorization_url, &form).await?;
+        if status != 200 {
+            return Err(token_error(status, &body));
+        }
+        let authorization: DeviceAuthorizationResponse =
+            serde_json::from_slice(&body).map_err(|e| {
+                LumniError::Internal(format!(
+                    "Failed to parse device authorization response: {}",
+                    e
+                ))
+            })?;
+        (self.prompt)(&DeviceCodePrompt {
+            user_code: authorization.user_code,
+            verification_uri: authorization.verification_uri,
+            message: authorization.message,
+        });
+
+        let form = [
+            ("grant_type", DEVICE_CODE_GRANT),
+            ("device_code", &authorization.device_code),
+            ("client_id", &self.client_id),
+        ];
+        let expires_at = system_time_in_seconds() + authorization.expires_in;
+        let mut interval = authorization
+            .interval
+            .unwrap_or(DEFAULT_POLL_INTERVAL_SECONDS);
+        while system_time_in_seconds() < expires_at {
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+            let (body, status) = post_form(&self.token_url, &form).await?;
+            match poll_state(status, &body)? {
+                PollState::Token(token) => return Ok(token),
+                PollState::Pending => {}
+                PollState::SlowDown => interval += SLOW_DOWN_SECONDS,
+            }
+        }
+        Err(device_code_expired())
+    }
+
+    async fn refresh(
+        &self,
+        refresh_token: &str,
+    ) -> Result<TokenResponse, LumniError> {
+        let mut form = vec![
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+            ("client_id", self.client_id.as_str()),
+        ];
+        if let Some(scope) = &self.scope {
+            form.push(("scope", scope));
+        }
+        let (body, status) = post_form(&self.token_url, &form).await?;
+        if status != 200 {
+            return Err(token_error(status, &body));
+        }
+        parse_token(&body)
+    }
+
+    // caches the tokens, a refresh token may be replaced by a new one
+    fn store(&self, token: TokenResponse) -> String {
+        let now = system_time_in_seconds();
+        if let Some(refresh_token) = token.refresh_token {
+            cache_token(
+                &self.cache_key("refresh"),
+                refresh_token.clone(),
+                now + REFRESH_TOKEN_LIFETIME_SECONDS,
+            );
+            *self.refresh_token.lock().unwrap() = Some(refresh_token);
+        }
+        if let Some(expires_in) = token.expires_in {
+            let expiration = now + expires_in;
+            cache_token(
+                &self.cache_key("access"),
+                token.access_token.clone(),
+                expiration,
+            );
+            *self.token.lock().unwrap() = Some(CachedToken {
+                value: token.access_token.clone(),
+                expiration,
+            });
+        }
+        token.access_token
+    }
+
+    // e.g. oauth-access:CLIENT_ID@https://login.example.com/token
+    fn cache_key(&self, kind: &str) -> String {
+        let mut key =
+            format!("oauth-{}:{}@{}", kind, self.client_id, self.token_url);
+        if let Some(scope) = &self.scope {
+            key.push_str(&format!(" {}", scope));
+        }
+        key
+    }
+}
+
+async fn post_form(
+    url: &str,
+    form: &[(&str, &str)],
+) -> Result<(Bytes, u16), LumniError> {
+    let headers = HashMap::from([(
+        "Content-Type".to_string(),
+        "application/x-www-form-urlencoded".to_string(),
+    )]);
+    let body = form
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, form_encode(value)))
+        .collect::<Vec<_>>()
+        .join("&");
+    http_post_request(url, &headers, body.as_bytes()).await
+}
+
+// error codes of RFC 8628, section 3.5
+fn poll_state(status: u16, body: &[u8]) -> Result<PollState, LumniError> {
+    if status == 200 {
+        return parse_token(body).map(PollState::Token);
+    }
+    match serde_json::from_slice::<TokenErrorResponse>(body) {
+        Ok(e) if e.error == "authorization_pending" => Ok(PollState::Pending),
+        Ok(e) if e.error == "slow_down" => Ok(PollState::SlowDown),
+        Ok(e) if e.error == "expired_token" => Err(device_code_expired()),
+        _ => Err(token_error(status, body)),
+    }
+}
+
+fn device_code_expired() -> LumniError {
+    LumniError::AccessDenied(
+        "Device code expired before sign in was completed".to_string(),
+    )
+}
+
+fn parse_token(body: &[u8]) -> Result<TokenResponse, LumniError> {
+    serde_json::from_slice(body).map_err(|e| {
+        LumniError::Internal(format!("Failed to parse token response: {}", e))
+    })
+}
+
+fn token_error(status: u16, body: &[u8]) -> LumniError {
+    let message = match serde_json::from_slice::<TokenErrorResponse>(body) {
+        Ok(e) => e.error_description.unwrap_or(e.error),
+        Err(_) => String::from_utf8_lossy(body).to_string(),
+    };
+    LumniError::AccessDenied(format!(
+        "Token request failed with status {}: {}",
+        status, message
+    ))
+}
+
+fn form_encode(value: &str) -> String {
+    percent_encoding::utf8_percent_encode(
+        value,
+        percent_encoding::NON_ALPHANUMERIC,
+    )
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PENDING: &[u8] = br#"{
+        "error": "authorization_pending",
+        "error_description": "The user has not yet completed sign in"
+    }"#;
+    const SLOW_DOWN: &[u8] = br#"{"error": "slow_down"}"#;
+    const EXPIRED: &[u8] = br#"{
+        "error": "expired_token",
+        "error_description": "The device code has expired"
+    }"#;
+    const DENIED: &[u8] = br#"{
+        "error": "access_denied",
+        "error_description": "The user declined the sign in"
+    }"#;
+    const TOKEN: &[u8] = br#"{
+        "token_type": "Bearer",
+        "access_token": "eyJ0eXAi",
+        "expires_in": 3599,
+        "refresh_token": "0.AXkA"
+    }"#;
+
+    fn access_denied(result: Result<PollState, LumniError>) -> String {
+        match result {
+            Err(LumniError::AccessDenied(message)) => message,
+            _ => panic!("expected access denied"),
+        }
+    }
+
+    #[test]
+    fn test_poll_state() {
+        assert!(matches!(poll_state(400, PENDING), Ok(PollState::Pending)));
+        assert!(matches!(
+            poll_state(400, SLOW_DOWN),
+            Ok(PollState::SlowDown)
+        ));
+        match poll_state(200, TOKEN) {
+            Ok(PollState::Token(token)) => {
+                assert_eq!(token.access_token, "eyJ0eXAi");
+                assert_eq!(token.expires_in, Some(3599));
+                assert_eq!(token.refresh_token.as_deref(), Some("0.AXkA"));
+            }
+            _ => panic!("expected a token"),
+        }
+
+        // the sign in is not polled any further
+        assert_eq!(
+            access_denied(poll_state(400, EXPIRED)),
+            "Device code expired before sign in was completed"
+        );
+        assert_eq!(
+            access_denied(poll_state(400, DENIED)),
+            "Token request failed with status 400: \
+             The user declined the sign in"
+        );
+        assert_eq!(
+            access_denied(poll_state(502, b"Bad Gateway")),
+            "Token request failed with status 502: Bad Gateway"
+        );
+        assert!(matches!(
+            poll_state(200, b"{}"),
+            Err(LumniError::Internal(_))
+        ));
+    }
+
+    #[test]
+    fn test_device_authorization_response() {
+        // Google names the verification URI verification_url
+        let body = br#"{
+            "device_code": "AH-1Ng",
+            "user_code": "GQVQ-JKEC",
+            "verification_url": "https://www.google.com/device",
+            "expires_in": 1800,
+            "interval": 5
+        }"#;
+        let response: DeviceAuthorizationResponse =
+            serde_json::from_slice(body).unwrap();
+        assert_eq!(response.verification_uri, "https://www.google.com/device");
+        assert_eq!(response.interval, Some(5));
+
+        let prompt = DeviceCodePrompt {
+            user_code: response.user_code,
+            verification_uri: response.verification_uri,
+            message: response.message,
+        };
+        assert_eq!(
+            prompt.to_string(),
+            "To sign in, open https://www.google.com/device and enter the \
+             code GQVQ-JKEC"
+        );
+    }
+}
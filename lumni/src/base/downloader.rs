@@ -0,0 +1,552 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use super::config::EnvironmentConfig;
+use super::progress::{ProgressReporter, ProgressTracker};
+#[cfg(feature = "http_client")]
+use super::throttle::{Throttle, ThrottleConfig};
+use crate::handlers::object_store::{inventory_prefix, ObjectStore};
+use crate::handlers::{ByteRange, DiffStrategy, InventoryEntry};
+use crate::{LumniError, ObjectStoreHandler, ParsedUri};
+
+// kept in the destination directory while and after downloading
+pub const DOWNLOAD_MANIFEST_NAME: &str = ".lumni-download.json";
+const PARTIAL_SUFFIX: &str = ".part";
+const DEFAULT_CONCURRENCY: usize = 8;
+// prefix of the throttle settings of downloads, e.g.
+// DOWNLOAD_BANDWIDTH_LIMIT
+pub const DOWNLOAD_OPERATION: &str = "DOWNLOAD";
+
+// objects of a download and which of them are complete. Partial objects
+// are written to "<key>.part", their size is where a resume continues
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DownloadManifest {
+    source: String,
+    objects: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ManifestEntry {
+    size: u64,
+    modified: Option<u64>,
+    #[serde(default)]
+    complete: bool,
+}
+
+impl ManifestEntry {
+    // a changed object is downloaded again from the start
+    fn same_object(&self, entry: &InventoryEntry) -> bool {
+        self.size == entry.size && self.modified == entry.modified
+    }
+}
+
+// an object still to download, from offset to size
+struct ObjectTransfer {
+    key: String,        // relative to the prefix
+    path: PathBuf,      // of the key, relative to the destination
+    object_key: String, // in the object store
+    size: u64,
+    offset: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct DownloadResult {
+    downloaded: Vec<String>,
+    resumed: Vec<String>,
+    skipped: Vec<String>,
+    failed: Vec<(String, String)>,
+}
+
+impl DownloadResult {
+    pub fn downloaded(&self) -> &[String] {
+        &self.downloaded
+    }
+
+    // downloaded, continuing from a partial file
+    pub fn resumed(&self) -> &[String] {
+        &self.resumed
+    }
+
+    // already complete in an earlier run
+    pub fn skipped(&self) -> &[String] {
+        &self.skipped
+    }
+
+    pub fn failed(&self) -> &[(String, String)] {
+        &self.failed
+    }
+}
+
+// downloads all objects under a prefix to a local directory, several at
+// a time, and keeps a manifest so an interrupted download can resume
+pub struct Downloader {
+    concurrency: usize,
+    resume: bool,
+    reporter: Option<Arc<dyn ProgressReporter>>,
+}
+
+impl Downloader {
+    pub fn new() -> Self {
+        Downloader {
+            concurrency: DEFAULT_CONCURRENCY,
+            resume: false,
+            reporter: None,
+        }
+    }
+
+    pub fn set_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    // continue from the manifest of an earlier download to the same
+    // destination, instead of downloading everything again
+    pub fn set_resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    pub fn set_progress(mut self, reporter: Arc<dyn ProgressReporter>) -> Self {
+        self.reporter = Some(reporter);
+        self
+    }
+
+    pub async fn download(
+        &self,
+        source: &ParsedUri,
+        destination: &Path,
+        config: &EnvironmentConfig,
+    ) -> Result<DownloadResult, LumniError> {
+        let bucket = source
+            .bucket
+            .as_ref()
+            .ok_or_else(|| LumniError::NoBucketInUri(source.to_string()))?;
+        let bucket_uri = format!("{}://{}", source.scheme.to_string(), bucket);
+        let object_store = ObjectStore::new(&bucket_uri, config.clone())?;
+        // not chained to the global throttle, the requests of the object
+        // store already wait for it
+        #[cfg(feature = "http_client")]
+        let throttle = Throttle::new(ThrottleConfig::from_config(
+            config,
+            Some(DOWNLOAD_OPERATION),
+        )?);
+        let prefix = inventory_prefix(source);
+        // every object under the prefix, a get that stops after the first
+        // page would report success for a partial download
+        let inventory = ObjectStoreHandler::new(None)
+            .list_inventory(source, config, DiffStrategy::Mtime)
+            .await?;
+
+        fs::create_dir_all(destination)?;
+        let manifest_path = destination.join(DOWNLOAD_MANIFEST_NAME);
+        let previous = match self.resume {
+            true => read_manifest(&manifest_path, &source.to_string())?,
+            false => DownloadManifest::default(),
+        };
+
+        let mut result = DownloadResult::default();
+        let mut manifest = DownloadManifest {
+            source: source.to_string(),
+            objects: BTreeMap::new(),
+        };
+        let mut pending = Vec::new();
+        for (key, entry) in &inventory {
+            // keys come from the object store, one that does not map to a
+            // path inside the destination is not downloaded
+            let path = match relative_path(key) {
+                Ok(path) => path,
+                Err(err) => {
+                    result.failed.push((key.clone(), err));
+                    continue;
+                }
+            };
+            let previous = previous.objects.get(key);
+            let complete = previous.is_some_and(|previous| {
+                previous.complete
+                    && previous.same_object(entry)
+                    && file_size(&destination.join(&path)) == Some(entry.size)
+            });
+            // partial files of another version of the object are not reused
+            let offset = match previous {
+                Some(previous) if previous.same_object(entry) && !complete => {
+                    file_size(&partial_path(destination, &path))
+                        .filter(|size| *size <= entry.size)
+                        .unwrap_or(0)
+                }
+                _ => 0,
+            };
+            manifest.objects.insert(
+                key.clone(),
+                ManifestEntry {
+                    size: entry.size,
+                    modified: entry.modified,
+                    complete,
+                },
+            );
+            if complete {
+                result.skipped.push(key.clone());
+            } else {
+                pending.push(ObjectTransfer {
+                    key: key.clone(),
+                    path,
+                    object_key: format!("{}{}", prefix, key),
+                    size: entry.size,
+                    offset,
+                });
+            }
+        }
+        write_manifest(&manifest_path, &manifest)?;
+
+        let total: u64 = pending.iter().map(|transfer| transfer.size).sum();
+        let offset: u64 = pending.iter().map(|transfer| transfer.offset).sum();
+        let tracker = self.reporter.as_ref().map(|reporter| {
+            ProgressTracker::new("download", Arc::clone(reporter))
+                .set_total_bytes(Some(total))
+                .set_total_objects(Some(pending.len() as u64))
+                .set_offset(offset)
+        });
+
+        let manifest = Mutex::new(manifest);
+        let transfers = pending.into_iter().map(|transfer| {
+            let object_store = &object_store;
+            let tracker = tracker.as_ref();
+            #[cfg(feature = "http_client")]
+            let throttle = &throttle;
+            async move {
+                // a connection of the download is an object in transfer
+                #[cfg(feature = "http_client")]
+                let _permit = throttle.acquire().await;
+                let result = download_object(
+                    object_store,
+                    &transfer,
+                    destination,
+                    tracker,
+                    #[cfg(feature = "http_client")]
+                    throttle,
+                )
+                .await;
+                (transfer.key, transfer.offset, result)
+            }
+        });
+        let mut transfers =
+            stream::iter(transfers).buffer_unordered(self.concurrency);
+        while let Some((key, offset, transfer)) = transfers.next().await {
+            match transfer {
+                Ok(()) => {
+                    let mut manifest = manifest.lock().unwrap();
+                    if let Some(entry) = manifest.objects.get_mut(&key) {
+                        entry.complete = true;
+                    }
+                    write_manifest(&manifest_path, &manifest)?;
+                    if let Some(tracker) = &tracker {
+                        tracker.add_object(0);
+                    }
+                    match offset {
+                        0 => result.downloaded.push(key),
+                        _ => result.resumed.push(key),
+                    }
+                }
+                Err(err) => result.failed.push((key, err.to_string())),
+            }
+        }
+        if let Some(tracker) = &tracker {
+            tracker.finish();
+        }
+        Ok(result)
+    }
+}
+
+impl Default for Downloader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// appends to the partial file from the offset, and moves it in place
+// once all bytes are there
+async fn download_object(
+    object_store: &ObjectStore,
+    transfer: &ObjectTransfer,
+    destination: &Path,
+    tracker: Option<&ProgressTracker>,
+    #[cfg(feature = "http_client")] throttle: &Throttle,
+) -> Result<(), LumniError> {
+    let ObjectTransfer {
+        key,
+        path,
+        object_key,
+        size,
+        offset,
+    } = transfer;
+    let (size, offset) = (*size, *offset);
+    let target = destination.join(path);
+    let partial = partial_path(destination, path);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(offset > 0)
+        .truncate(offset == 0)
+        .open(&partial)?;
+
+    if offset < size {
+        let range = match offset {
+            0 => None,
+            _ => Some(ByteRange::new(offset, None)?),
+        };
+        let mut stream =
+            object_store.get_object_stream(object_key, range).await?;
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            #[cfg(feature = "http_client")]
+            throttle.consume(chunk.len()).await;
+            file.write_all(&chunk)?;
+            if let Some(tracker) = tracker {
+                tracker.add_bytes(chunk.len() as u64);
+            }
+        }
+    }
+    file.flush()?;
+    drop(file);
+
+    let written = file_size(&partial).unwrap_or(0);
+    if written != size {
+        // kept, so a resume continues from what did arrive
+        return Err(LumniError::Internal(format!(
+            "{}: received {} of {} bytes",
+            key, written, size
+        )));
+    }
+    fs::rename(&partial, &target)?;
+    Ok(())
+}
+
+fn partial_path(destination: &Path, path: &Path) -> PathBuf {
+    let mut partial = destination.join(path).into_os_string();
+    partial.push(PARTIAL_SUFFIX);
+    PathBuf::from(partial)
+}
+
+// the key as a path that stays inside the destination. keys with an
+// absolute path, "..", "." or empty parts (e.g. "a//b") are refused, as
+// is the manifest of the download
+fn relative_path(key: &str) -> Result<PathBuf, String> {
+    let path = Path::new(key);
+    let valid = !key.is_empty()
+        && !key.split('/').any(str::is_empty)
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        && path != Path::new(DOWNLOAD_MANIFEST_NAME);
+    match valid {
+        true => Ok(path.to_path_buf()),
+        false => Err(format!(
+            "Key {:?} is not a path inside the destination",
+            key
+        )),
+    }
+}
+
+fn file_size(path: &Path) -> Option<u64> {
+    fs::metadata(path).ok().map(|metadata| metadata.len())
+}
+
+// a manifest of another source is not resumed from
+fn read_manifest(
+    path: &Path,
+    source: &str,
+) -> Result<DownloadManifest, LumniError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(DownloadManifest::default())
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let manifest: DownloadManifest =
+        serde_json::from_str(&contents).map_err(|e| {
+            LumniError::Config(format!(
+                "Invalid download manifest {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+    if manifest.source != source {
+        return Ok(DownloadManifest::default());
+    }
+    Ok(manifest)
+}
+
+// written to a temporary file first, so an interrupted write does not
+// leave a truncated manifest
+fn write_manifest(
+    path: &Path,
+    manifest: &DownloadManifest,
+) -> Result<(), LumniError> {
+    let contents = serde_json::to_string_pretty(manifest)
+        .map_err(|e| LumniError::Internal(e.to_string()))?;
+    let temporary = path.with_extension("json.tmp");
+    File::create(&temporary)?.write_all(contents.as_bytes())?;
+    fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use async_trait::async_trait;
+    use futures::executor::block_on;
+
+    use super::*;
+    use crate::handlers::{
+        register_backend, ObjectStoreBackendFactory, ObjectStoreTrait,
+    };
+    use crate::table::FileObjectTable;
+    use crate::{FileObject, FileObjectFilter};
+
+    // objects with keys that are not valid paths, which a local
+    // filesystem cannot hold
+    struct KeysFactory;
+
+    struct KeysStore {
+        config: EnvironmentConfig,
+    }
+
+    const KEYS: [&str; 4] = [
+        "prefix/ok.txt",
+        "prefix//etc/escape.txt",
+        "prefix/../escape.txt",
+        "prefix/sub/../../escape.txt",
+    ];
+
+    impl ObjectStoreBackendFactory for KeysFactory {
+        fn create(
+            &self,
+            _name: &str,
+            config: EnvironmentConfig,
+        ) -> Result<Box<dyn ObjectStoreTrait + Sync>, LumniError> {
+            Ok(Box::new(KeysStore { config }))
+        }
+    }
+
+    #[async_trait(?Send)]
+    impl ObjectStoreTrait for KeysStore {
+        fn name(&self) -> &str {
+            "bucket"
+        }
+
+        fn config(&self) -> &EnvironmentConfig {
+            &self.config
+        }
+
+        async fn list_files(
+            &self,
+            _prefix: Option<&str>,
+            _selected_columns: &Option<Vec<&str>>,
+            _recursive: bool,
+            _max_keys: Option<u32>,
+            _filter: &Option<FileObjectFilter>,
+            table: &mut FileObjectTable,
+        ) -> Result<(), LumniError> {
+            let file_objects = KEYS
+                .iter()
+                .map(|key| FileObject::new(key.to_string(), 4, None, None))
+                .collect();
+            table
+                .add_file_objects(file_objects)
+                .await
+                .map_err(LumniError::Internal)
+        }
+
+        async fn get_object(
+            &self,
+            _key: &str,
+            data: &mut Vec<u8>,
+        ) -> Result<(), LumniError> {
+            data.extend_from_slice(b"data");
+            Ok(())
+        }
+
+        async fn head_object(
+            &self,
+            _key: &str,
+        ) -> Result<(u16, HashMap<String, String>), LumniError> {
+            Ok((404, HashMap::new()))
+        }
+    }
+
+    #[test]
+    fn test_download_resume() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        fs::create_dir_all(source.path().join("prefix/sub")).unwrap();
+        fs::write(source.path().join("prefix/a.txt"), "aaaa").unwrap();
+        fs::write(source.path().join("prefix/sub/b.txt"), "bbbbbbbb").unwrap();
+        let uri = ParsedUri::from_uri(
+            &format!("localfs://{}/prefix", source.path().display()),
+            true,
+        );
+        let config = EnvironmentConfig::new(HashMap::new());
+        let downloader = Downloader::new().set_concurrency(2).set_resume(true);
+
+        let result =
+            block_on(downloader.download(&uri, destination.path(), &config))
+                .unwrap();
+        assert_eq!(result.downloaded(), ["a.txt", "sub/b.txt"]);
+
+        // interrupted halfway through b.txt
+        let manifest_path = destination.path().join(DOWNLOAD_MANIFEST_NAME);
+        let mut manifest =
+            read_manifest(&manifest_path, &uri.to_string()).unwrap();
+        manifest.objects.get_mut("sub/b.txt").unwrap().complete = false;
+        write_manifest(&manifest_path, &manifest).unwrap();
+        fs::remove_file(destination.path().join("sub/b.txt")).unwrap();
+        fs::write(
+            partial_path(destination.path(), Path::new("sub/b.txt")),
+            "bbbb",
+        )
+            .unwrap();
+
+        let result =
+            block_on(downloader.download(&uri, destination.path(), &config))
+                .unwrap();
+        assert_eq!(result.skipped(), ["a.txt"]);
+        assert_eq!(result.resumed(), ["sub/b.txt"]);
+        assert_eq!(
+            fs::read_to_string(destination.path().join("sub/b.txt")).unwrap(),
+            "bbbbbbbb"
+        );
+    }
+
+    #[test]
+    fn test_download_refuses_keys_outside_destination() {
+        register_backend("keysdl", Box::new(KeysFactory)).unwrap();
+        let root = tempfile::tempdir().unwrap();
+        let destination = root.path().join("destination");
+        let uri = ParsedUri::from_uri("keysdl://bucket/prefix", true);
+        let config = EnvironmentConfig::new(HashMap::new());
+
+        let result =
+            block_on(Downloader::new().download(&uri, &destination, &config))
+                .unwrap();
+        assert_eq!(result.downloaded(), ["ok.txt"]);
+        let mut failed: Vec<_> =
+            result.failed().iter().map(|(key, _)| key.as_str()).collect();
+        failed.sort();
+        assert_eq!(
+            failed,
+            ["../escape.txt", "/etc/escape.txt", "sub/../../escape.txt"]
+        );
+        assert!(!root.path().join("escape.txt").exists());
+        assert!(!root.path().join("etc").exists());
+    }
+}
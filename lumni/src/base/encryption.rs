@@ -0,0 +1,168 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+
+use crate::LumniError;
+
+const NONCE_LENGTH: usize = 12;
+
+// encrypts data at rest with AES-256-GCM. the key is kept in a file that
+// only the user can read, in a directory apart from the data, so a copy
+// of the data directory alone (e.g. synced or shared) can not be read.
+// anyone who can read all files of the user can still decrypt the data
+pub struct EncryptionHandler {
+    cipher: Aes256Gcm,
+}
+
+impl EncryptionHandler {
+    pub fn new(key: &[u8; 32]) -> Self {
+        EncryptionHandler {
+            cipher: Aes256Gcm::new(&Key::<Aes256Gcm>::from(*key)),
+        }
+    }
+
+    // with the key in key_file, which is created if it does not exist.
+    // its directory is made accessible to the user only
+    pub fn from_key_file(key_file: &Path) -> Result<Self, LumniError> {
+        match fs::read(key_file) {
+            Ok(key) => {
+                let key: [u8; 32] = key.try_into().map_err(|_| {
+                    LumniError::Config(format!(
+                        "Invalid encryption key in {}",
+                        key_file.display()
+                    ))
+                })?;
+                Ok(EncryptionHandler::new(&key))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let key = Aes256Gcm::generate_key(OsRng);
+                if let Some(dir) = key_file.parent() {
+                    create_private_dir(dir)?;
+                }
+                write_private_file(key_file, &key)?;
+                Ok(EncryptionHandler::new(&key.into()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    // the nonce followed by the ciphertext
+    pub fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>, LumniError> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, data)
+            .map_err(|e| LumniError::Internal(format!("Encryption: {}", e)))?;
+        let mut encrypted = nonce.to_vec();
+        encrypted.extend_from_slice(&ciphertext);
+        Ok(encrypted)
+    }
+
+    // fails if the data was changed, or encrypted with another key
+    pub fn decrypt(&self, encrypted: &[u8]) -> Result<Vec<u8>, LumniError> {
+        if encrypted.len() < NONCE_LENGTH {
+            return Err(LumniError::Internal(
+                "Encrypted data is truncated".to_string(),
+            ));
+        }
+        let (nonce, ciphertext) = encrypted.split_at(NONCE_LENGTH);
+        // the length is checked above
+        let nonce: [u8; NONCE_LENGTH] = nonce.try_into().unwrap();
+        self.cipher
+            .decrypt(&Nonce::from(nonce), ciphertext)
+            .map_err(|e| LumniError::Internal(format!("Decryption: {}", e)))
+    }
+}
+
+// creates the directory, accessible to the user only
+pub fn create_private_dir(dir: &Path) -> Result<(), LumniError> {
+    fs::create_dir_all(dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+// creates or replaces the file with permissions for the user only. the
+// data is written to a temporary file that is renamed to the path, so
+// concurrent invocations never read or leave a partly written file
+pub fn write_private_file(path: &Path, data: &[u8]) -> Result<(), LumniError> {
+    // unique per process and write, the file is renamed in the same dir
+    static WRITES: AtomicU64 = AtomicU64::new(0);
+    let file_name = path.file_name().ok_or_else(|| {
+        LumniError::Config(format!("{} is not a file", path.display()))
+    })?;
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id(),
+        WRITES.fetch_add(1, Ordering::Relaxed)
+    ));
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let result = options.open(&temp_path).and_then(|mut file| {
+        file.write_all(data)?;
+        file.sync_all()
+    });
+    if let Err(e) = result.and_then(|_| fs::rename(&temp_path, path)) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let handler = EncryptionHandler::new(&[7; 32]);
+        let encrypted = handler.encrypt(b"token").unwrap();
+        assert_ne!(&encrypted[NONCE_LENGTH..], b"token");
+        assert_eq!(handler.decrypt(&encrypted).unwrap(), b"token");
+
+        let other = EncryptionHandler::new(&[8; 32]);
+        assert!(other.decrypt(&encrypted).is_err());
+    }
+
+    #[test]
+    fn test_write_private_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data");
+        write_private_file(&path, b"first").unwrap();
+        write_private_file(&path, b"second").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        // no temporary files are left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let key_file = dir.path().join("keys").join("key");
+        let handler = EncryptionHandler::from_key_file(&key_file).unwrap();
+        let encrypted = handler.encrypt(b"token").unwrap();
+        let handler = EncryptionHandler::from_key_file(&key_file).unwrap();
+        assert_eq!(handler.decrypt(&encrypted).unwrap(), b"token");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |path: &Path| {
+                fs::metadata(path).unwrap().permissions().mode() & 0o777
+            };
+            assert_eq!(mode(key_file.parent().unwrap()), 0o700);
+            assert_eq!(mode(&key_file), 0o600);
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use crate::table::TableColumnValue;
+use crate::Checksum;
 
 #[derive(Debug, Clone)]
 pub struct FileObject {
@@ -8,6 +9,41 @@ pub struct FileObject {
     size: u64,
     modified: Option<u64>,
     tags: Option<HashMap<String, String>>,
+    checksum: Option<Checksum>,
+    storage_class: Option<String>,
+    version: Option<ObjectVersion>,
+}
+
+// a version of an object in a bucket with versioning enabled, listed with
+// the other versions of the same key
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectVersion {
+    id: String,
+    is_latest: bool,
+    delete_marker: bool,
+}
+
+impl ObjectVersion {
+    pub fn new(id: String, is_latest: bool, delete_marker: bool) -> Self {
+        ObjectVersion {
+            id,
+            is_latest,
+            delete_marker,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn is_latest(&self) -> bool {
+        self.is_latest
+    }
+
+    // the key was deleted in this version, there is no content
+    pub fn is_delete_marker(&self) -> bool {
+        self.delete_marker
+    }
 }
 
 impl FileObject {
@@ -22,9 +58,35 @@ impl FileObject {
             size,
             modified,
             tags,
+            checksum: None,
+            storage_class: None,
+            version: None,
         }
     }
 
+    pub fn set_checksum(mut self, checksum: Option<Checksum>) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    // e.g. STANDARD, GLACIER or DEEP_ARCHIVE, as reported by the store
+    pub fn set_storage_class(mut self, storage_class: Option<String>) -> Self {
+        self.storage_class = storage_class;
+        self
+    }
+
+    pub fn set_version(mut self, version: Option<ObjectVersion>) -> Self {
+        self.version = version;
+        self
+    }
+
+    // size and modified time of a separate metadata request, see HeadBatch
+    pub fn set_metadata(mut self, size: u64, modified: Option<u64>) -> Self {
+        self.size = size;
+        self.modified = modified;
+        self
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -41,6 +103,18 @@ impl FileObject {
         &self.tags
     }
 
+    pub fn checksum(&self) -> Option<&Checksum> {
+        self.checksum.as_ref()
+    }
+
+    pub fn storage_class(&self) -> Option<&str> {
+        self.storage_class.as_deref()
+    }
+
+    pub fn version(&self) -> Option<&ObjectVersion> {
+        self.version.as_ref()
+    }
+
     pub fn get_value_by_column_name(
         &self,
         column_name: &str,
@@ -48,9 +122,30 @@ impl FileObject {
         match column_name {
             "name" => Some(TableColumnValue::StringColumn(self.name.clone())),
             "size" => Some(TableColumnValue::Uint64Column(self.size)),
-            "modified" => self
-                .modified
-                .map(|val| TableColumnValue::OptionalUint64Column(Some(val))),
+            // not known for common prefixes
+            "modified" => {
+                Some(TableColumnValue::OptionalUint64Column(self.modified))
+            }
+            "checksum" => Some(TableColumnValue::OptionalStringColumn(
+                self.checksum.as_ref().map(|checksum| checksum.to_string()),
+            )),
+            "storage_class" => Some(TableColumnValue::OptionalStringColumn(
+                self.storage_class.clone(),
+            )),
+            "version_id" => Some(TableColumnValue::OptionalStringColumn(
+                self.version.as_ref().map(|version| version.id.clone()),
+            )),
+            // 1 or 0, not set when versions are not listed
+            "is_latest" => Some(TableColumnValue::OptionalInt32Column(
+                self.version
+                    .as_ref()
+                    .map(|version| version.is_latest as i32),
+            )),
+            "delete_marker" => Some(TableColumnValue::OptionalInt32Column(
+                self.version
+                    .as_ref()
+                    .map(|version| version.delete_marker as i32),
+            )),
             _ => None,
         }
     }
@@ -4,13 +4,14 @@ use crate::utils::time::system_time_in_seconds;
 use crate::utils::time_parse::calculate_time_offset_seconds;
 use crate::FileObject;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct FileObjectFilter {
     name_regex: Option<Regex>,
     min_size: Option<u64>,
     max_size: Option<u64>,
     min_mtime: Option<u64>,
     max_mtime: Option<u64>,
+    storage_class: Option<String>,
 }
 
 impl FileObjectFilter {
@@ -37,14 +38,61 @@ impl FileObjectFilter {
             max_size,
             min_mtime,
             max_mtime,
+            storage_class: None,
         })
     }
 
-    pub fn matches(&self, file_object: &FileObject) -> bool {
-        let name_match = match &self.name_regex {
-            Some(re) => re.is_match(file_object.name()),
+    pub fn set_name_regex(mut self, name_regex: Regex) -> Self {
+        self.name_regex = Some(name_regex);
+        self
+    }
+
+    pub fn set_size_range(
+        mut self,
+        min: Option<u64>,
+        max: Option<u64>,
+    ) -> Self {
+        self.min_size = min;
+        self.max_size = max;
+        self
+    }
+
+    pub fn set_mtime_range(
+        mut self,
+        min: Option<u64>,
+        max: Option<u64>,
+    ) -> Self {
+        self.min_mtime = min;
+        self.max_mtime = max;
+        self
+    }
+
+    // objects that do not report a storage class, e.g. local files,
+    // do not match
+    pub fn set_storage_class(mut self, storage_class: Option<String>) -> Self {
+        self.storage_class = storage_class;
+        self
+    }
+
+    // whether matching takes the size or modified time, which some
+    // listings do not return (see HeadBatch)
+    pub fn needs_metadata(&self) -> bool {
+        self.min_size.is_some()
+            || self.max_size.is_some()
+            || self.min_mtime.is_some()
+            || self.max_mtime.is_some()
+    }
+
+    // e.g. for a deleted file, of which only the name is known
+    pub fn matches_name(&self, name: &str) -> bool {
+        match &self.name_regex {
+            Some(re) => re.is_match(name),
             None => true,
-        };
+        }
+    }
+
+    pub fn matches(&self, file_object: &FileObject) -> bool {
+        let name_match = self.matches_name(file_object.name());
 
         let size_match = {
             (self.min_size.map_or(true, |min| file_object.size() >= min))
@@ -59,7 +107,14 @@ impl FileObjectFilter {
             }))
         };
 
-        name_match && size_match && mtime_match
+        let storage_class_match = match &self.storage_class {
+            Some(storage_class) => file_object
+                .storage_class()
+                .is_some_and(|class| class.eq_ignore_ascii_case(storage_class)),
+            None => true,
+        };
+
+        name_match && size_match && mtime_match && storage_class_match
     }
 }
 
@@ -306,4 +361,19 @@ mod tests {
             println!("input: {}", input);
         }
     }
+
+    #[test]
+    fn test_storage_class_filter() {
+        let filter = FileObjectFilter::default()
+            .set_storage_class(Some("glacier".to_string()));
+        let object = FileObject::new("a.parquet".to_string(), 10, None, None);
+        assert!(!filter.matches(&object));
+        assert!(!filter.matches(
+            &object
+                .clone()
+                .set_storage_class(Some("STANDARD".to_string()))
+        ));
+        assert!(filter
+            .matches(&object.set_storage_class(Some("GLACIER".to_string()))));
+    }
 }
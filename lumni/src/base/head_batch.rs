@@ -0,0 +1,188 @@
+use std::future::Future;
+
+use futures::stream::{self, StreamExt};
+
+use crate::{EnvironmentConfig, FileObject, LumniError, ObjectMetadata};
+
+// setting with the most metadata requests in flight, e.g. "32"
+pub const HEAD_CONCURRENCY_SETTING: &str = "HEAD_CONCURRENCY";
+pub const DEFAULT_HEAD_CONCURRENCY: usize = 16;
+
+// completes objects that a listing returned without size or modified
+// time, e.g. symlinks of a local directory or a backend that only lists
+// names, with a metadata (HEAD) request per object. The requests of a
+// batch run concurrently, up to the limit, instead of one after another
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeadBatch {
+    concurrency: usize,
+}
+
+impl Default for HeadBatch {
+    fn default() -> Self {
+        HeadBatch {
+            concurrency: DEFAULT_HEAD_CONCURRENCY,
+        }
+    }
+}
+
+impl HeadBatch {
+    pub fn new() -> Self {
+        HeadBatch::default()
+    }
+
+    pub fn from_config(config: &EnvironmentConfig) -> Result<Self, LumniError> {
+        let Some(value) = config.get(HEAD_CONCURRENCY_SETTING) else {
+            return Ok(HeadBatch::default());
+        };
+        match value.parse::<usize>() {
+            Ok(concurrency) if concurrency > 0 => {
+                Ok(HeadBatch::new().set_concurrency(concurrency))
+            }
+            _ => Err(LumniError::Config(format!(
+                "Invalid {}: {}, expected a number greater than 0",
+                HEAD_CONCURRENCY_SETTING, value
+            ))),
+        }
+    }
+
+    pub fn set_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    // the modified time is what tells an incomplete object apart, a
+    // size of 0 is valid
+    pub fn needs_head(file_object: &FileObject) -> bool {
+        file_object.modified().is_none()
+    }
+
+    // objects in the order given, those that needed it with the size and
+    // modified time of head(name). An object of which the metadata
+    // cannot be read is kept as listed
+    pub async fn complete<F, Fut>(
+        &self,
+        file_objects: Vec<FileObject>,
+        head: F,
+    ) -> Vec<FileObject>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<ObjectMetadata, LumniError>>,
+    {
+        let head = &head;
+        stream::iter(file_objects)
+            .map(|file_object| async move {
+                if !HeadBatch::needs_head(&file_object) {
+                    return file_object;
+                }
+                match head(file_object.name().to_string()).await {
+                    Ok(metadata) => {
+                        let size =
+                            metadata.size().unwrap_or(file_object.size());
+                        file_object.set_metadata(size, metadata.modified())
+                    }
+                    Err(err) => {
+                        log::debug!(
+                            "Metadata of {} not read: {}",
+                            file_object.name(),
+                            err
+                        );
+                        file_object
+                    }
+                }
+            })
+            .buffered(self.concurrency)
+            .collect()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use futures::executor::block_on;
+
+    use super::*;
+
+    #[test]
+    fn test_complete() {
+        let in_flight = Cell::new(0usize);
+        let max_in_flight = Cell::new(0usize);
+        let head = |name: String| {
+            let (in_flight, max_in_flight) = (&in_flight, &max_in_flight);
+            async move {
+                in_flight.set(in_flight.get() + 1);
+                max_in_flight.set(max_in_flight.get().max(in_flight.get()));
+                // yield once, so the other requests of the batch start
+                let mut yielded = false;
+                futures::future::poll_fn(|cx| {
+                    if yielded {
+                        return std::task::Poll::Ready(());
+                    }
+                    yielded = true;
+                    cx.waker().wake_by_ref();
+                    std::task::Poll::Pending
+                })
+                .await;
+                in_flight.set(in_flight.get() - 1);
+                match name.as_str() {
+                    "missing" => Err(LumniError::NotFound(name)),
+                    _ => Ok(ObjectMetadata::new(&name)
+                        .set_size(Some(name.len() as u64))
+                        .set_modified(Some(100))),
+                }
+            }
+        };
+        let mut file_objects: Vec<FileObject> = (0..10)
+            .map(|index| FileObject::new(format!("{}", index), 0, None, None))
+            .collect();
+        file_objects.push(FileObject::new(
+            "listed".to_string(),
+            1,
+            Some(1),
+            None,
+        ));
+        file_objects.push(FileObject::new(
+            "missing".to_string(),
+            0,
+            None,
+            None,
+        ));
+
+        let batch = HeadBatch::new().set_concurrency(3);
+        let completed = block_on(batch.complete(file_objects, head));
+        assert_eq!(max_in_flight.get(), 3);
+        assert_eq!(completed.len(), 12);
+        assert_eq!(completed[0].modified(), Some(100));
+        assert_eq!(completed[9].name(), "9");
+        assert_eq!(completed[9].size(), 1);
+        assert_eq!(completed[10].modified(), Some(1)); // not requested
+        assert_eq!(completed[11].modified(), None);
+    }
+
+    #[test]
+    fn test_from_config() {
+        use std::collections::HashMap;
+
+        let config = |value: &str| {
+            EnvironmentConfig::new(HashMap::from([(
+                HEAD_CONCURRENCY_SETTING.to_string(),
+                value.to_string(),
+            )]))
+        };
+        assert_eq!(
+            HeadBatch::from_config(&config("4")).unwrap().concurrency(),
+            4
+        );
+        assert!(HeadBatch::from_config(&config("0")).is_err());
+        assert_eq!(
+            HeadBatch::from_config(&EnvironmentConfig::new(HashMap::new()))
+                .unwrap(),
+            HeadBatch::new()
+        );
+    }
+}
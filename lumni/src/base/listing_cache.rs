@@ -0,0 +1,331 @@
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[cfg(feature = "http_client")]
+use super::encryption::{create_private_dir, write_private_file};
+use crate::utils::time::system_time_in_seconds;
+use crate::{
+    EnvironmentConfig, FileObjectFilter, LumniError, Table, TableColumnValue,
+};
+
+// seconds a listing is reused, e.g. "300" or "5m". unset or "0" turns the
+// cache off
+pub const LISTING_CACHE_TTL_SETTING: &str = "LISTING_CACHE_TTL";
+
+const TTL_UNITS: &[(char, u64)] =
+    &[('s', 1), ('m', 60), ('h', 60 * 60), ('d', 24 * 60 * 60)];
+
+// where a bucket is, the same name can exist on several endpoints,
+// regions or storage accounts
+const LOCATION_SETTINGS: [&str; 6] = [
+    "S3_ENDPOINT_URL",
+    "AWS_REGION",
+    "GCS_ENDPOINT_URL",
+    "AZURE_STORAGE_ACCOUNT",
+    "AZURE_STORAGE_ENDPOINT",
+    "HDFS_WEBHDFS_ENDPOINT",
+];
+// as whom a bucket is listed, other credentials may see other objects
+const IDENTITY_SETTINGS: [&str; 4] = [
+    "AWS_PROFILE",
+    "AWS_ACCESS_KEY_ID",
+    "GOOGLE_APPLICATION_CREDENTIALS",
+    "HDFS_USER",
+];
+
+// without http_client (e.g. in a browser) there is no encryption module,
+// and no home directory to cache listings in
+#[cfg(not(feature = "http_client"))]
+fn create_private_dir(dir: &Path) -> Result<(), LumniError> {
+    Ok(fs::create_dir_all(dir)?)
+}
+
+#[cfg(not(feature = "http_client"))]
+fn write_private_file(path: &Path, data: &[u8]) -> Result<(), LumniError> {
+    Ok(fs::write(path, data)?)
+}
+
+pub type CachedRow = Vec<(String, TableColumnValue)>;
+
+#[derive(Serialize, Deserialize)]
+struct CachedListing {
+    created: u64, // epoch seconds
+    rows: Vec<CachedRow>,
+}
+
+// rows of listings kept in ~/.lumni/cache/listings, so listing the same
+// prefix again does not go to the object store until the ttl expires.
+// a directory per object store, which is dropped when lumni writes to it.
+// the files are only readable by the user, as the objects are
+pub struct ListingCache {
+    dir: PathBuf,
+    ttl: u64,
+}
+
+impl ListingCache {
+    pub fn new(dir: &Path, ttl: u64) -> Self {
+        ListingCache {
+            dir: dir.to_path_buf(),
+            ttl,
+        }
+    }
+
+    // None if the ttl is not set or there is no home directory
+    pub fn from_config(
+        config: &EnvironmentConfig,
+    ) -> Result<Option<Self>, LumniError> {
+        let ttl = match config.get(LISTING_CACHE_TTL_SETTING) {
+            Some(value) => parse_ttl(value).ok_or_else(|| {
+                LumniError::Config(format!(
+                    "Invalid {}: {}, expected seconds such as 300 or 5m",
+                    LISTING_CACHE_TTL_SETTING, value
+                ))
+            })?,
+            None => 0,
+        };
+        if ttl == 0 {
+            return Ok(None);
+        }
+        Ok(cache_dir().map(|dir| ListingCache::new(&dir, ttl)))
+    }
+
+    // rows of the listing if it was cached within the ttl
+    pub fn get(&self, store: &str, key: &str) -> Option<Vec<CachedRow>> {
+        let path = self.path(store, key);
+        let listing: CachedListing =
+            serde_json::from_slice(&fs::read(path).ok()?).ok()?;
+        let age = system_time_in_seconds().saturating_sub(listing.created);
+        (age < self.ttl).then_some(listing.rows)
+    }
+
+    pub fn insert(
+        &self,
+        store: &str,
+        key: &str,
+        rows: Vec<CachedRow>,
+    ) -> Result<(), LumniError> {
+        let path = self.path(store, key);
+        create_private_dir(&self.dir)?;
+        if let Some(parent) = path.parent() {
+            create_private_dir(parent)?;
+        }
+        let listing = CachedListing {
+            created: system_time_in_seconds(),
+            rows,
+        };
+        let data = serde_json::to_vec(&listing)
+            .map_err(|e| LumniError::Internal(e.to_string()))?;
+        // written aside first, so a concurrent get never reads half a file
+        write_private_file(&path, &data)
+    }
+
+    // drops the cached listings of an object store, see listing_store,
+    // whether or not the cache is turned on
+    pub fn invalidate(store: &str) -> Result<(), LumniError> {
+        let Some(dir) = cache_dir() else {
+            return Ok(());
+        };
+        remove_dir(&dir.join(hash(store)))?;
+        Ok(())
+    }
+
+    // removes all cached listings, returns how many there were
+    pub fn clear() -> Result<usize, LumniError> {
+        let Some(dir) = cache_dir() else {
+            return Ok(0);
+        };
+        let count = fs::read_dir(&dir)
+            .map(|stores| {
+                stores
+                    .flatten()
+                    .filter_map(|store| fs::read_dir(store.path()).ok())
+                    .map(|listings| listings.count())
+                    .sum()
+            })
+            .unwrap_or(0);
+        remove_dir(&dir)?;
+        Ok(count)
+    }
+
+    fn path(&self, store: &str, key: &str) -> PathBuf {
+        self.dir
+            .join(hash(store))
+            .join(format!("{}.json", hash(key)))
+    }
+}
+
+// identifies an object store by its uri, e.g. "s3://bucket", and where
+// it is. a write drops the listings of the store for every identity
+pub fn listing_store(uri: &str, config: &EnvironmentConfig) -> String {
+    format!("{}|{}", uri, settings(config, &LOCATION_SETTINGS))
+}
+
+// identifies a listing of an object store by what is listed and as whom,
+// the filter is part of it as the rows are listed after filtering
+pub fn listing_key(
+    config: &EnvironmentConfig,
+    prefix: Option<&str>,
+    selected_columns: &Option<Vec<&str>>,
+    recursive: bool,
+    max_files: Option<u32>,
+    filter: &Option<FileObjectFilter>,
+) -> String {
+    format!(
+        "{}|{:?}|{:?}|{}|{:?}|{:?}",
+        settings(config, &IDENTITY_SETTINGS),
+        prefix,
+        selected_columns,
+        recursive,
+        max_files,
+        filter
+    )
+}
+
+// the settings of the config, or else of the environment. only hashes of
+// the keys are written
+fn settings(config: &EnvironmentConfig, names: &[&str]) -> String {
+    names
+        .iter()
+        .filter_map(|name| {
+            let value =
+                config.get(name).cloned().or_else(|| env::var(name).ok())?;
+            Some(format!("{}={}", name, value))
+        })
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+// rows of a table, to cache them
+pub fn table_rows(table: &dyn Table) -> Vec<CachedRow> {
+    let columns = table.columns();
+    (0..table.len())
+        .map(|index| {
+            columns
+                .iter()
+                .filter_map(|(name, column)| {
+                    column.get_value(index).map(|value| (name.clone(), value))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn parse_ttl(value: &str) -> Option<u64> {
+    let value = value.trim().to_lowercase();
+    let (number, multiplier) = match value.chars().last() {
+        Some(unit) if unit.is_alphabetic() => {
+            let (_, multiplier) =
+                TTL_UNITS.iter().find(|(suffix, _)| *suffix == unit)?;
+            (&value[..value.len() - 1], *multiplier)
+        }
+        _ => (value.as_str(), 1),
+    };
+    number.trim().parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+fn hash(value: &str) -> String {
+    hex::encode(&Sha256::digest(value.as_bytes())[..16])
+}
+
+fn remove_dir(dir: &Path) -> Result<(), LumniError> {
+    match fs::remove_dir_all(dir) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+// e.g. ~/.lumni/cache/listings
+fn cache_dir() -> Option<PathBuf> {
+    let home = env::var("HOME").ok()?;
+    Some(
+        PathBuf::from(home)
+            .join(".lumni")
+            .join("cache")
+            .join("listings"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_ttl() {
+        assert_eq!(parse_ttl("300"), Some(300));
+        assert_eq!(parse_ttl("5m"), Some(300));
+        assert_eq!(parse_ttl("2H"), Some(7200));
+        assert_eq!(parse_ttl("0"), Some(0));
+        assert_eq!(parse_ttl("5w"), None);
+        assert_eq!(parse_ttl("soon"), None);
+    }
+
+    #[test]
+    fn test_listing_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ListingCache::new(dir.path(), 60);
+        let config = EnvironmentConfig::new(HashMap::new());
+        let key = listing_key(&config, Some("data/"), &None, true, None, &None);
+        assert!(cache.get("s3://bucket", &key).is_none());
+
+        let rows = vec![vec![
+            (
+                "name".to_string(),
+                TableColumnValue::StringColumn("data/a.csv".to_string()),
+            ),
+            ("size".to_string(), TableColumnValue::Uint64Column(10)),
+        ]];
+        cache.insert("s3://bucket", &key, rows).unwrap();
+        let cached = cache.get("s3://bucket", &key).unwrap();
+        assert_eq!(cached.len(), 1);
+        assert!(matches!(
+            &cached[0][1],
+            (name, TableColumnValue::Uint64Column(10)) if name == "size"
+        ));
+        // other listings and object stores are not affected
+        let other_key =
+            listing_key(&config, Some("data/"), &None, false, None, &None);
+        assert!(cache.get("s3://bucket", &other_key).is_none());
+        assert!(cache.get("s3://other", &key).is_none());
+
+        let expired = ListingCache::new(dir.path(), 0);
+        assert!(expired.get("s3://bucket", &key).is_none());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let path = cache.path("s3://bucket", &key);
+            let mode = |path: &Path| {
+                fs::metadata(path).unwrap().permissions().mode() & 0o777
+            };
+            assert_eq!(mode(&path), 0o600);
+            assert_eq!(mode(path.parent().unwrap()), 0o700);
+        }
+    }
+
+    #[test]
+    fn test_listing_store() {
+        let config = |endpoint: &str| {
+            EnvironmentConfig::new(HashMap::from([(
+                "S3_ENDPOINT_URL".to_string(),
+                endpoint.to_string(),
+            )]))
+        };
+        // the same bucket name on AWS and on MinIO
+        let aws = config("https://s3.amazonaws.com");
+        let minio = config("http://localhost:9000");
+        assert_ne!(
+            listing_store("s3://bucket", &aws),
+            listing_store("s3://bucket", &minio)
+        );
+        assert_eq!(
+            listing_store("s3://bucket", &aws),
+            listing_store("s3://bucket", &config("https://s3.amazonaws.com"))
+        );
+    }
+}
@@ -0,0 +1,475 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::thread;
+
+use futures::stream::{self, StreamExt};
+use hmac::{Hmac, Mac, NewMac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::config::EnvironmentConfig;
+use super::progress::{ProgressReporter, ProgressTracker};
+#[cfg(feature = "http_client")]
+use super::throttle::{Throttle, ThrottleConfig};
+use crate::handlers::object_store::{inventory_prefix, ObjectStore};
+use crate::handlers::DiffStrategy;
+use crate::utils::time::system_time_in_seconds;
+use crate::{LumniError, ObjectStoreHandler, ParsedUri};
+
+// secret a manifest is signed with (HMAC-SHA256), without it manifests
+// are not signed and signatures are not checked
+pub const MANIFEST_SIGNING_KEY_SETTING: &str = "MANIFEST_SIGNING_KEY";
+// prefix of the throttle settings of hashing, e.g.
+// MANIFEST_BANDWIDTH_LIMIT
+pub const MANIFEST_OPERATION: &str = "MANIFEST";
+const MANIFEST_VERSION: u32 = 1;
+const SIGNATURE_PREFIX: &str = "hmac-sha256:";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestObject {
+    pub size: u64,
+    pub sha256: String, // lowercase hex
+}
+
+// size and sha256 of every object under a prefix, by key relative to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityManifest {
+    version: u32,
+    source: String,
+    created: u64, // epoch seconds
+    objects: BTreeMap<String, ManifestObject>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
+}
+
+impl IntegrityManifest {
+    pub fn new(
+        source: &str,
+        objects: BTreeMap<String, ManifestObject>,
+    ) -> Self {
+        IntegrityManifest {
+            version: MANIFEST_VERSION,
+            source: source.to_string(),
+            created: system_time_in_seconds(),
+            objects,
+            signature: None,
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, LumniError> {
+        let manifest: IntegrityManifest =
+            serde_json::from_str(json).map_err(|e| {
+                LumniError::Config(format!("Invalid manifest: {}", e))
+            })?;
+        if manifest.version != MANIFEST_VERSION {
+            return Err(LumniError::Config(format!(
+                "Unsupported manifest version {}",
+                manifest.version
+            )));
+        }
+        Ok(manifest)
+    }
+
+    pub fn to_json(&self) -> Result<String, LumniError> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| LumniError::Internal(e.to_string()))
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn created(&self) -> u64 {
+        self.created
+    }
+
+    pub fn objects(&self) -> &BTreeMap<String, ManifestObject> {
+        &self.objects
+    }
+
+    pub fn is_signed(&self) -> bool {
+        self.signature.is_some()
+    }
+
+    pub fn sign(&mut self, key: &[u8]) -> Result<(), LumniError> {
+        let mac = self.mac(key)?;
+        self.signature = Some(format!(
+            "{}{}",
+            SIGNATURE_PREFIX,
+            hex::encode(mac.finalize().into_bytes())
+        ));
+        Ok(())
+    }
+
+    // false if the manifest is not signed, was changed after signing or
+    // was signed with another key
+    pub fn has_valid_signature(&self, key: &[u8]) -> Result<bool, LumniError> {
+        let Some(signature) = self
+            .signature
+            .as_deref()
+            .and_then(|signature| signature.strip_prefix(SIGNATURE_PREFIX))
+            .and_then(|signature| hex::decode(signature).ok())
+        else {
+            return Ok(false);
+        };
+        Ok(self.mac(key)?.verify(&signature).is_ok())
+    }
+
+    // of the manifest without its signature
+    fn mac(&self, key: &[u8]) -> Result<Hmac<Sha256>, LumniError> {
+        let unsigned = IntegrityManifest {
+            signature: None,
+            ..self.clone()
+        };
+        let data = serde_json::to_vec(&unsigned)
+            .map_err(|e| LumniError::Internal(e.to_string()))?;
+        let mut mac = Hmac::<Sha256>::new_from_slice(key)
+            .expect("HMAC can take key of any size");
+        mac.update(&data);
+        Ok(mac)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureStatus {
+    Valid,
+    Invalid, // also when a signing key is set but the manifest is unsigned
+    Unchecked, // no signing key is set
+}
+
+#[derive(Debug)]
+pub struct ManifestVerification {
+    signature: SignatureStatus,
+    verified: Vec<String>,
+    missing: Vec<String>, // in the manifest, not under the prefix
+    unexpected: Vec<String>, // under the prefix, not in the manifest
+    mismatched: Vec<(String, String)>, // key, what differs
+    failed: Vec<(String, String)>, // key, why it could not be read
+}
+
+impl ManifestVerification {
+    pub fn signature(&self) -> SignatureStatus {
+        self.signature
+    }
+
+    pub fn verified(&self) -> &[String] {
+        &self.verified
+    }
+
+    pub fn missing(&self) -> &[String] {
+        &self.missing
+    }
+
+    pub fn unexpected(&self) -> &[String] {
+        &self.unexpected
+    }
+
+    pub fn mismatched(&self) -> &[(String, String)] {
+        &self.mismatched
+    }
+
+    pub fn failed(&self) -> &[(String, String)] {
+        &self.failed
+    }
+
+    // every object of the manifest is there, unchanged, and nothing else
+    pub fn is_intact(&self) -> bool {
+        self.signature != SignatureStatus::Invalid
+            && self.missing.is_empty()
+            && self.unexpected.is_empty()
+            && self.mismatched.is_empty()
+            && self.failed.is_empty()
+    }
+}
+
+// creates and verifies manifests, reading and hashing several objects at
+// a time. concurrency defaults to the number of cpus, MANIFEST_* throttle
+// settings limit the bandwidth and requests
+pub struct ManifestHasher {
+    concurrency: usize,
+    reporter: Option<Arc<dyn ProgressReporter>>,
+}
+
+impl ManifestHasher {
+    pub fn new() -> Self {
+        ManifestHasher {
+            concurrency: thread::available_parallelism()
+                .map_or(1, |cpus| cpus.get()),
+            reporter: None,
+        }
+    }
+
+    pub fn set_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn set_progress(mut self, reporter: Arc<dyn ProgressReporter>) -> Self {
+        self.reporter = Some(reporter);
+        self
+    }
+
+    // all objects under the prefix, on every page of the listing. fails
+    // if any object can not be read, as the manifest would be incomplete.
+    // signed if MANIFEST_SIGNING_KEY is set
+    pub async fn create(
+        &self,
+        source: &ParsedUri,
+        config: &EnvironmentConfig,
+    ) -> Result<IntegrityManifest, LumniError> {
+        let objects: Vec<(String, u64)> = ObjectStoreHandler::new(None)
+            .list_inventory(source, config, DiffStrategy::Mtime)
+            .await?
+            .into_iter()
+            .map(|(key, entry)| (key, entry.size))
+            .collect();
+        let (hashed, failed) =
+            self.hash_objects(source, config, objects).await?;
+        if let Some((key, reason)) = failed.first() {
+            return Err(LumniError::Internal(format!(
+                "{} objects could not be read, e.g. {}: {}",
+                failed.len(),
+                key,
+                reason
+            )));
+        }
+        let mut manifest = IntegrityManifest::new(&source.to_string(), hashed);
+        if let Some(key) = signing_key(config) {
+            manifest.sign(&key)?;
+        }
+        Ok(manifest)
+    }
+
+    // compares the objects under the prefix, e.g. after a migration, with
+    // the manifest. objects of another size are not read. the listing is
+    // complete, so an object missing from it is missing from the store
+    pub async fn verify(
+        &self,
+        manifest: &IntegrityManifest,
+        source: &ParsedUri,
+        config: &EnvironmentConfig,
+    ) -> Result<ManifestVerification, LumniError> {
+        let signature = match signing_key(config) {
+            Some(key) if manifest.has_valid_signature(&key)? => {
+                SignatureStatus::Valid
+            }
+            Some(_) => SignatureStatus::Invalid,
+            None => SignatureStatus::Unchecked,
+        };
+        let inventory = ObjectStoreHandler::new(None)
+            .list_inventory(source, config, DiffStrategy::Mtime)
+            .await?;
+
+        let mut verification = ManifestVerification {
+            signature,
+            verified: Vec::new(),
+            missing: Vec::new(),
+            unexpected: Vec::new(),
+            mismatched: Vec::new(),
+            failed: Vec::new(),
+        };
+        let mut pending = Vec::new();
+        for (key, expected) in manifest.objects() {
+            match inventory.get(key) {
+                None => verification.missing.push(key.clone()),
+                Some(entry) if entry.size != expected.size => {
+                    verification.mismatched.push((
+                        key.clone(),
+                        format!(
+                            "size is {}, expected {}",
+                            entry.size, expected.size
+                        ),
+                    ))
+                }
+                Some(entry) => pending.push((key.clone(), entry.size)),
+            }
+        }
+        verification.unexpected = inventory
+            .keys()
+            .filter(|key| !manifest.objects().contains_key(*key))
+            .cloned()
+            .collect();
+
+        let (hashed, failed) =
+            self.hash_objects(source, config, pending).await?;
+        verification.failed = failed;
+        for (key, object) in hashed {
+            match manifest.objects().get(&key) {
+                Some(expected) if *expected == object => {
+                    verification.verified.push(key)
+                }
+                _ => verification
+                    .mismatched
+                    .push((key, "sha256 differs".to_string())),
+            }
+        }
+        Ok(verification)
+    }
+
+    async fn hash_objects(
+        &self,
+        source: &ParsedUri,
+        config: &EnvironmentConfig,
+        objects: Vec<(String, u64)>, // key and size
+    ) -> Result<
+        (BTreeMap<String, ManifestObject>, Vec<(String, String)>),
+        LumniError,
+    > {
+        let bucket = source
+            .bucket
+            .as_ref()
+            .ok_or_else(|| LumniError::NoBucketInUri(source.to_string()))?;
+        let bucket_uri = format!("{}://{}", source.scheme.to_string(), bucket);
+        let object_store = ObjectStore::new(&bucket_uri, config.clone())?;
+        // as for downloads, not chained to the global throttle
+        #[cfg(feature = "http_client")]
+        let throttle = Throttle::new(ThrottleConfig::from_config(
+            config,
+            Some(MANIFEST_OPERATION),
+        )?);
+        let prefix = inventory_prefix(source);
+        let tracker = self.reporter.as_ref().map(|reporter| {
+            ProgressTracker::new("hash", Arc::clone(reporter))
+                .set_total_bytes(Some(
+                    objects.iter().map(|(_, size)| size).sum(),
+                ))
+                .set_total_objects(Some(objects.len() as u64))
+        });
+
+        let reads = objects.into_iter().map(|(key, _)| {
+            let object_store = &object_store;
+            let tracker = tracker.as_ref();
+            let object_key = format!("{}{}", prefix, key);
+            #[cfg(feature = "http_client")]
+            let throttle = &throttle;
+            async move {
+                #[cfg(feature = "http_client")]
+                let _permit = throttle.acquire().await;
+                let object = hash_object(
+                    object_store,
+                    &object_key,
+                    tracker,
+                    #[cfg(feature = "http_client")]
+                    throttle,
+                )
+                .await;
+                (key, object)
+            }
+        });
+        let mut reads = stream::iter(reads).buffer_unordered(self.concurrency);
+        let mut hashed = BTreeMap::new();
+        let mut failed = Vec::new();
+        while let Some((key, object)) = reads.next().await {
+            match object {
+                Ok(object) => {
+                    if let Some(tracker) = &tracker {
+                        tracker.add_object(0);
+                    }
+                    hashed.insert(key, object);
+                }
+                Err(err) => failed.push((key, err.to_string())),
+            }
+        }
+        if let Some(tracker) = &tracker {
+            tracker.finish();
+        }
+        Ok((hashed, failed))
+    }
+}
+
+impl Default for ManifestHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// hashes the chunks while they arrive, the object is not kept
+async fn hash_object(
+    object_store: &ObjectStore,
+    object_key: &str,
+    tracker: Option<&ProgressTracker>,
+    #[cfg(feature = "http_client")] throttle: &Throttle,
+) -> Result<ManifestObject, LumniError> {
+    let mut stream = object_store.get_object_stream(object_key, None).await?;
+    let mut hasher = Sha256::new();
+    let mut size = 0u64;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        #[cfg(feature = "http_client")]
+        throttle.consume(chunk.len()).await;
+        hasher.update(&chunk);
+        size += chunk.len() as u64;
+        if let Some(tracker) = tracker {
+            tracker.add_bytes(chunk.len() as u64);
+        }
+    }
+    Ok(ManifestObject {
+        size,
+        sha256: hex::encode(hasher.finalize()),
+    })
+}
+
+fn signing_key(config: &EnvironmentConfig) -> Option<Vec<u8>> {
+    config
+        .get(MANIFEST_SIGNING_KEY_SETTING)
+        .filter(|key| !key.is_empty())
+        .map(|key| key.as_bytes().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+
+    use futures::executor::block_on;
+
+    use super::*;
+
+    #[test]
+    fn test_manifest_verify() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("data/sub")).unwrap();
+        fs::write(dir.path().join("data/a.txt"), "aaaa").unwrap();
+        fs::write(dir.path().join("data/sub/b.txt"), "bbbb").unwrap();
+        let uri = ParsedUri::from_uri(
+            &format!("localfs://{}/data", dir.path().display()),
+            true,
+        );
+        let config = EnvironmentConfig::with_setting(
+            MANIFEST_SIGNING_KEY_SETTING.to_string(),
+            "secret".to_string(),
+        );
+        let hasher = ManifestHasher::new().set_concurrency(2);
+
+        let manifest = block_on(hasher.create(&uri, &config)).unwrap();
+        assert_eq!(manifest.objects().len(), 2);
+        assert_eq!(
+            manifest.objects()["a.txt"].sha256,
+            hex::encode(Sha256::digest(b"aaaa"))
+        );
+        let manifest =
+            IntegrityManifest::from_json(&manifest.to_json().unwrap()).unwrap();
+        let verification =
+            block_on(hasher.verify(&manifest, &uri, &config)).unwrap();
+        assert!(verification.is_intact());
+        assert_eq!(verification.signature(), SignatureStatus::Valid);
+
+        // same size, other content, and an object that was not there
+        fs::write(dir.path().join("data/sub/b.txt"), "cccc").unwrap();
+        fs::write(dir.path().join("data/c.txt"), "c").unwrap();
+        let verification =
+            block_on(hasher.verify(&manifest, &uri, &config)).unwrap();
+        assert!(!verification.is_intact());
+        assert_eq!(verification.verified(), ["a.txt"]);
+        assert_eq!(verification.unexpected(), ["c.txt"]);
+        assert_eq!(verification.mismatched()[0].0, "sub/b.txt");
+
+        let other_key = EnvironmentConfig::new(HashMap::from([(
+            MANIFEST_SIGNING_KEY_SETTING.to_string(),
+            "other".to_string(),
+        )]));
+        let verification =
+            block_on(hasher.verify(&manifest, &uri, &other_key)).unwrap();
+        assert_eq!(verification.signature(), SignatureStatus::Invalid);
+    }
+}
@@ -0,0 +1,137 @@
+use std::env;
+
+use crate::{EnvironmentConfig, LumniError, TableColumnValue};
+
+// setting with the most memory a listing or a set of loaded conversations
+// may take, e.g. "512M". "0" turns the check off
+pub const MEMORY_BUDGET_SETTING: &str = "MEMORY_BUDGET";
+pub const DEFAULT_MEMORY_BUDGET: u64 = 1024 * 1024 * 1024;
+
+const BYTE_UNITS: &[(char, u64)] = &[
+    ('b', 1),
+    ('k', 1024),
+    ('m', 1024 * 1024),
+    ('g', 1024 * 1024 * 1024),
+    ('t', 1024 * 1024 * 1024 * 1024),
+];
+
+// bytes that collected data (e.g. the rows of a Table) may take before the
+// operation changes course, by streaming the data instead of keeping it or
+// by failing with a request to narrow it down. sizes are estimates of the
+// heap use, not exact
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryBudget {
+    limit: u64,
+}
+
+impl Default for MemoryBudget {
+    fn default() -> Self {
+        MemoryBudget {
+            limit: DEFAULT_MEMORY_BUDGET,
+        }
+    }
+}
+
+impl MemoryBudget {
+    pub fn new(limit: u64) -> Self {
+        MemoryBudget { limit }
+    }
+
+    // the budget of MEMORY_BUDGET, None if the check is turned off
+    pub fn from_config(
+        config: &EnvironmentConfig,
+    ) -> Result<Option<Self>, LumniError> {
+        let budget = match config.get(MEMORY_BUDGET_SETTING) {
+            Some(value) => parse_bytes(value).ok_or_else(|| {
+                LumniError::Config(format!(
+                    "Invalid {}: {}, expected a size such as 512M or 2G",
+                    MEMORY_BUDGET_SETTING, value
+                ))
+            })?,
+            None => DEFAULT_MEMORY_BUDGET,
+        };
+        Ok((budget > 0).then_some(MemoryBudget::new(budget)))
+    }
+
+    // as from_config, for apps that are not given the config of the cli
+    pub fn from_env() -> Result<Option<Self>, LumniError> {
+        let settings = env::var(MEMORY_BUDGET_SETTING)
+            .map(|value| (MEMORY_BUDGET_SETTING.to_string(), value))
+            .into_iter()
+            .collect();
+        Self::from_config(&EnvironmentConfig::new(settings))
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    pub fn exceeded_by(&self, used: u64) -> bool {
+        used > self.limit
+    }
+
+    // the message when the budget runs out, with what to do about it
+    pub fn exceeded_message(&self, operation: &str, hint: &str) -> String {
+        format!(
+            "{} exceeds the memory budget of {} bytes, {}, or raise {}",
+            operation, self.limit, hint, MEMORY_BUDGET_SETTING
+        )
+    }
+}
+
+// e.g. "1024", "64k" or "2G"
+pub fn parse_bytes(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let (number, multiplier) = match value.chars().last()? {
+        unit if unit.is_ascii_alphabetic() => {
+            let unit = unit.to_ascii_lowercase();
+            let (_, multiplier) =
+                BYTE_UNITS.iter().find(|(u, _)| *u == unit)?;
+            (&value[..value.len() - 1], *multiplier)
+        }
+        _ => (value, 1),
+    };
+    number.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+// heap and inline size of a table row, as kept in its columns
+pub fn row_size(row: &[(String, TableColumnValue)]) -> u64 {
+    row.iter()
+        .map(|(_, value)| match value {
+            TableColumnValue::StringColumn(s) => s.len() + 24,
+            TableColumnValue::OptionalStringColumn(s) => {
+                s.as_ref().map_or(0, String::len) + 24
+            }
+            _ => 16,
+        } as u64)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bytes() {
+        assert_eq!(parse_bytes("1024"), Some(1024));
+        assert_eq!(parse_bytes("64k"), Some(64 * 1024));
+        assert_eq!(parse_bytes("2G"), Some(2 * 1024 * 1024 * 1024));
+        assert_eq!(parse_bytes("0"), Some(0));
+        assert_eq!(parse_bytes("2X"), None);
+        assert_eq!(parse_bytes("M"), None);
+    }
+
+    #[test]
+    fn test_from_config() {
+        let config = EnvironmentConfig::with_setting(
+            MEMORY_BUDGET_SETTING.to_string(),
+            "0".to_string(),
+        );
+        assert_eq!(MemoryBudget::from_config(&config).unwrap(), None);
+        let config = EnvironmentConfig::new(Default::default());
+        assert_eq!(
+            MemoryBudget::from_config(&config).unwrap(),
+            Some(MemoryBudget::default())
+        );
+    }
+}
@@ -1,5 +1,27 @@
 pub mod callback_wrapper;
+pub mod checksum;
+#[cfg(feature = "compression")]
+pub mod compression;
 pub mod config;
 pub mod connector;
+#[cfg(feature = "http_client")]
+pub mod device_code;
+pub mod downloader;
+#[cfg(feature = "http_client")]
+pub mod encryption;
 pub mod file_object;
 pub mod filters;
+pub mod head_batch;
+pub mod listing_cache;
+pub mod manifest;
+pub mod memory;
+pub mod object_metadata;
+pub mod progress;
+pub mod ranged_read;
+#[cfg(feature = "http_client")]
+pub mod telemetry;
+#[cfg(feature = "http_client")]
+pub mod throttle;
+#[cfg(feature = "http_client")]
+pub mod token_cache;
+pub mod watch;
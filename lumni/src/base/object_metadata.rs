@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::utils::time::rfc2822_to_epoch;
+
+// prefixes of user metadata headers on S3, Azure and GCS
+const USER_METADATA_PREFIXES: [&str; 3] =
+    ["x-amz-meta-", "x-ms-meta-", "x-goog-meta-"];
+const STORAGE_CLASS_HEADERS: [&str; 3] = [
+    "x-amz-storage-class",
+    "x-ms-access-tier",
+    "x-goog-storage-class",
+];
+
+// properties of a single object, as returned by a HEAD request
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ObjectMetadata {
+    key: String,
+    size: Option<u64>,
+    modified: Option<u64>,
+    content_type: Option<String>,
+    storage_class: Option<String>,
+    etag: Option<String>,
+    metadata: HashMap<String, String>, // user metadata, without prefix
+    tags: HashMap<String, String>,
+}
+
+impl ObjectMetadata {
+    pub fn new(key: &str) -> Self {
+        ObjectMetadata {
+            key: key.to_string(),
+            ..Default::default()
+        }
+    }
+
+    // response headers of a HEAD request, names in lowercase
+    pub fn from_headers(key: &str, headers: &HashMap<String, String>) -> Self {
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(header, _)| header.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.clone())
+        };
+        let metadata = headers
+            .iter()
+            .filter_map(|(name, value)| {
+                let name = name.to_lowercase();
+                USER_METADATA_PREFIXES.iter().find_map(|prefix| {
+                    name.strip_prefix(prefix)
+                        .map(|name| (name.to_string(), value.clone()))
+                })
+            })
+            .collect();
+        ObjectMetadata {
+            key: key.to_string(),
+            size: header("content-length").and_then(|size| size.parse().ok()),
+            modified: header("last-modified")
+                .and_then(|date| rfc2822_to_epoch(&date).ok()),
+            content_type: header("content-type"),
+            storage_class: STORAGE_CLASS_HEADERS
+                .iter()
+                .find_map(|name| header(name)),
+            etag: header("etag").map(|etag| etag.trim_matches('"').to_string()),
+            metadata,
+            tags: HashMap::new(),
+        }
+    }
+
+    pub fn set_size(mut self, size: Option<u64>) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn set_modified(mut self, modified: Option<u64>) -> Self {
+        self.modified = modified;
+        self
+    }
+
+    pub fn set_storage_class(mut self, storage_class: Option<String>) -> Self {
+        self.storage_class = storage_class;
+        self
+    }
+
+    // without quotes, as from_headers keeps it
+    pub fn set_etag(mut self, etag: Option<String>) -> Self {
+        self.etag = etag;
+        self
+    }
+
+    pub fn set_tags(mut self, tags: HashMap<String, String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    // seconds since the epoch
+    pub fn modified(&self) -> Option<u64> {
+        self.modified
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    pub fn storage_class(&self) -> Option<&str> {
+        self.storage_class.as_deref()
+    }
+
+    pub fn etag(&self) -> Option<&str> {
+        self.etag.as_deref()
+    }
+
+    pub fn metadata(&self) -> &HashMap<String, String> {
+        &self.metadata
+    }
+
+    pub fn tags(&self) -> &HashMap<String, String> {
+        &self.tags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_headers() {
+        let headers = HashMap::from([
+            ("content-length".to_string(), "42".to_string()),
+            ("Content-Type".to_string(), "text/plain".to_string()),
+            ("etag".to_string(), "\"abc\"".to_string()),
+            ("x-amz-storage-class".to_string(), "GLACIER".to_string()),
+            ("x-amz-meta-owner".to_string(), "lumni".to_string()),
+            (
+                "last-modified".to_string(),
+                "Wed, 21 Oct 2015 07:28:00 GMT".to_string(),
+            ),
+        ]);
+        let metadata = ObjectMetadata::from_headers("a.txt", &headers);
+        assert_eq!(metadata.size(), Some(42));
+        assert_eq!(metadata.content_type(), Some("text/plain"));
+        assert_eq!(metadata.etag(), Some("abc"));
+        assert_eq!(metadata.storage_class(), Some("GLACIER"));
+        assert_eq!(metadata.modified(), Some(1445412480));
+        assert_eq!(metadata.metadata()["owner"], "lumni");
+    }
+}
@@ -0,0 +1,252 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::callback_wrapper::{
+    BinaryCallbackWrapper, CallbackItem, CallbackWrapper,
+};
+use crate::table::{TableCallback, TableColumnValue, TableRow};
+
+// reports are throttled to this interval, except the final one
+const DEFAULT_REPORT_INTERVAL: Duration = Duration::from_millis(100);
+
+// counts of an operation such as a listing, upload or download
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressUpdate {
+    pub operation: String,
+    pub objects: u64,
+    pub bytes: u64,
+    pub total_objects: Option<u64>,
+    pub total_bytes: Option<u64>,
+    pub elapsed: Duration,
+    // bytes that were already done before this run, e.g. when resuming
+    pub offset: u64,
+    pub finished: bool,
+}
+
+impl ProgressUpdate {
+    pub fn bytes_per_second(&self) -> f64 {
+        rate(self.bytes.saturating_sub(self.offset), self.elapsed)
+    }
+
+    pub fn objects_per_second(&self) -> f64 {
+        rate(self.objects, self.elapsed)
+    }
+
+    // of the total bytes if known, else of the total objects
+    pub fn percent(&self) -> Option<u64> {
+        match (self.total_bytes, self.total_objects) {
+            (Some(total), _) if total > 0 => {
+                Some((self.bytes * 100 / total).min(100))
+            }
+            (_, Some(total)) if total > 0 => {
+                Some((self.objects * 100 / total).min(100))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn rate(count: u64, elapsed: Duration) -> f64 {
+    match elapsed.as_secs_f64() {
+        seconds if seconds > 0.0 => count as f64 / seconds,
+        _ => 0.0,
+    }
+}
+
+impl CallbackItem for ProgressUpdate {
+    fn println_path(&self) -> String {
+        format!(
+            "{}: {} objects, {} bytes",
+            self.operation, self.objects, self.bytes
+        )
+    }
+}
+
+pub trait ProgressReporter: Send + Sync {
+    fn report(&self, update: &ProgressUpdate);
+}
+
+// lets embedding apps receive updates as they receive listed objects.
+// async callbacks are awaited in place, so should not wait on other tasks
+impl ProgressReporter for CallbackWrapper<ProgressUpdate> {
+    fn report(&self, update: &ProgressUpdate) {
+        match self {
+            CallbackWrapper::Sync(func) => func(std::slice::from_ref(update)),
+            CallbackWrapper::Async(func) => {
+                futures::executor::block_on(func(vec![update.clone()]))
+            }
+        }
+    }
+}
+
+// counts objects and bytes of an operation, shared by the tasks doing
+// the work, and passes them on to a reporter
+pub struct ProgressTracker {
+    operation: String,
+    reporter: Arc<dyn ProgressReporter>,
+    start: Instant,
+    interval: Duration,
+    offset: u64,
+    total_objects: Option<u64>,
+    total_bytes: Option<u64>,
+    objects: AtomicU64,
+    bytes: AtomicU64,
+    last_report: Mutex<Option<Instant>>,
+}
+
+impl ProgressTracker {
+    pub fn new(operation: &str, reporter: Arc<dyn ProgressReporter>) -> Self {
+        ProgressTracker {
+            operation: operation.to_string(),
+            reporter,
+            start: Instant::now(),
+            interval: DEFAULT_REPORT_INTERVAL,
+            offset: 0,
+            total_objects: None,
+            total_bytes: None,
+            objects: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            last_report: Mutex::new(None),
+        }
+    }
+
+    pub fn set_total_objects(mut self, total: Option<u64>) -> Self {
+        self.total_objects = total;
+        self
+    }
+
+    pub fn set_total_bytes(mut self, total: Option<u64>) -> Self {
+        self.total_bytes = total;
+        self
+    }
+
+    // bytes already done, counted but not part of the rate
+    pub fn set_offset(mut self, offset: u64) -> Self {
+        self.offset = offset;
+        self.bytes = AtomicU64::new(offset);
+        self
+    }
+
+    pub fn set_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn add_bytes(&self, bytes: u64) {
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+        self.maybe_report();
+    }
+
+    pub fn add_object(&self, bytes: u64) {
+        self.objects.fetch_add(1, Ordering::Relaxed);
+        self.add_bytes(bytes);
+    }
+
+    pub fn update(&self) -> ProgressUpdate {
+        ProgressUpdate {
+            operation: self.operation.clone(),
+            objects: self.objects.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+            total_objects: self.total_objects,
+            total_bytes: self.total_bytes,
+            elapsed: self.start.elapsed(),
+            offset: self.offset,
+            finished: false,
+        }
+    }
+
+    // always reported, regardless of the interval
+    pub fn finish(&self) {
+        let mut update = self.update();
+        update.finished = true;
+        self.reporter.report(&update);
+    }
+
+    fn maybe_report(&self) {
+        let now = Instant::now();
+        {
+            let mut last_report = self.last_report.lock().unwrap();
+            if last_report
+                .is_some_and(|last| now.duration_since(last) < self.interval)
+            {
+                return;
+            }
+            *last_report = Some(now);
+        }
+        self.reporter.report(&self.update());
+    }
+
+    // counts the chunks of an upload
+    pub fn binary_callback(self: &Arc<Self>) -> BinaryCallbackWrapper {
+        let tracker = Arc::clone(self);
+        BinaryCallbackWrapper::create_async(move |data: Vec<u8>| {
+            tracker.add_bytes(data.len() as u64);
+            async {}
+        })
+    }
+}
+
+// counts the rows of a listing as objects, with their size as bytes,
+// and passes them on to the callback that consumes the listing
+pub struct ProgressTableCallback {
+    tracker: Arc<ProgressTracker>,
+    inner: Option<Arc<dyn TableCallback>>,
+}
+
+impl ProgressTableCallback {
+    pub fn new(
+        tracker: Arc<ProgressTracker>,
+        inner: Option<Arc<dyn TableCallback>>,
+    ) -> Self {
+        ProgressTableCallback { tracker, inner }
+    }
+}
+
+impl TableCallback for ProgressTableCallback {
+    fn on_row_add(&self, row: &mut TableRow) {
+        let size = row.data().iter().find_map(|(name, value)| match value {
+            TableColumnValue::Uint64Column(size) if name == "size" => {
+                Some(*size)
+            }
+            _ => None,
+        });
+        self.tracker.add_object(size.unwrap_or(0));
+        if let Some(inner) = &self.inner {
+            inner.on_row_add(row);
+        }
+    }
+
+    fn retain_rows(&self) -> bool {
+        self.inner.as_ref().is_none_or(|inner| inner.retain_rows())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_tracker() {
+        let updates = Arc::new(Mutex::new(Vec::new()));
+        let received = Arc::clone(&updates);
+        let reporter =
+            CallbackWrapper::create_sync(move |items: &[ProgressUpdate]| {
+                received.lock().unwrap().extend_from_slice(items)
+            });
+        let tracker = ProgressTracker::new("download", Arc::new(reporter))
+            .set_total_bytes(Some(200))
+            .set_offset(50)
+            .set_interval(Duration::from_secs(60));
+        tracker.add_object(10);
+        tracker.add_bytes(40); // within the interval, not reported
+        tracker.finish();
+
+        let updates = updates.lock().unwrap();
+        assert_eq!(updates.len(), 2);
+        assert_eq!((updates[0].objects, updates[0].bytes), (1, 60));
+        assert_eq!(updates[1].bytes, 100);
+        assert_eq!(updates[1].percent(), Some(50));
+        assert!(updates[1].finished);
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::future::Future;
+
+use bytes::Bytes;
+use futures::stream;
+
+use crate::handlers::object_store::{ByteRange, ObjectStream};
+use crate::LumniError;
+
+// what the request for one chunk of an object returned
+#[derive(Debug)]
+pub enum Chunk {
+    // the bytes of the range, with the size of the object if known
+    Partial(Bytes, Option<u64>),
+    // the full object, the range was ignored
+    Full(Bytes),
+    // the range starts at or beyond the end of the object
+    End,
+}
+
+impl Chunk {
+    // the response to a request with a Range header
+    pub fn from_http(
+        status: u16,
+        headers: &HashMap<String, String>,
+        body: Bytes,
+        key: &str,
+    ) -> Result<Self, LumniError> {
+        match status {
+            206 => {
+                // e.g. "bytes 0-8388607/20971520", gives the object size
+                let size = headers
+                    .iter()
+                    .find(|(name, _)| {
+                        name.eq_ignore_ascii_case("content-range")
+                    })
+                    .and_then(|(_, value)| value.rsplit('/').next())
+                    .and_then(|size| size.parse::<u64>().ok());
+                Ok(Chunk::Partial(body, size))
+            }
+            200 => Ok(Chunk::Full(body)),
+            416 => Ok(Chunk::End),
+            _ => Err(LumniError::from_status(status, key, "")),
+        }
+    }
+}
+
+struct ReadState<F> {
+    fetch: F,
+    chunk_size: u64,
+    offset: u64,
+    end: Option<u64>, // inclusive, unknown until the first response
+    done: bool,
+}
+
+// streams the object, or the range of it, in chunks that are each fetched
+// with their own ranged request by fetch(range), so only one chunk is held
+// in memory at a time
+pub fn ranged_stream<F, Fut>(
+    range: Option<ByteRange>,
+    chunk_size: u64,
+    fetch: F,
+) -> ObjectStream
+where
+    F: Fn(ByteRange) -> Fut + 'static,
+    Fut: Future<Output = Result<Chunk, LumniError>>,
+{
+    let state = ReadState {
+        fetch,
+        chunk_size: chunk_size.max(1),
+        offset: range.map_or(0, |r| r.start()),
+        end: range.and_then(|r| r.end()),
+        done: false,
+    };
+    let stream = stream::unfold(state, |mut state| async move {
+        if state.done || state.end.is_some_and(|end| state.offset > end) {
+            return None;
+        }
+        match next_chunk(&mut state).await {
+            Ok(Some(chunk)) => Some((Ok(chunk), state)),
+            Ok(None) => None,
+            Err(err) => {
+                state.done = true;
+                Some((Err(err), state))
+            }
+        }
+    });
+    Box::pin(stream)
+}
+
+async fn next_chunk<F, Fut>(
+    state: &mut ReadState<F>,
+) -> Result<Option<Bytes>, LumniError>
+where
+    F: Fn(ByteRange) -> Fut,
+    Fut: Future<Output = Result<Chunk, LumniError>>,
+{
+    let chunk_end = state.offset + state.chunk_size - 1;
+    let chunk_end = state.end.map_or(chunk_end, |end| end.min(chunk_end));
+    let range = ByteRange::new(state.offset, Some(chunk_end))?;
+    match (state.fetch)(range).await? {
+        Chunk::Partial(body, size) => {
+            if state.end.is_none() {
+                state.end = size.map(|size| size.saturating_sub(1));
+            }
+            if body.is_empty() {
+                return Ok(None);
+            }
+            state.offset += body.len() as u64;
+            Ok(Some(body))
+        }
+        Chunk::Full(body) => {
+            state.done = true;
+            let requested = ByteRange::new(state.offset, state.end)?;
+            Ok(Some(Bytes::copy_from_slice(requested.slice(&body))))
+        }
+        Chunk::End => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use futures::executor::block_on;
+    use futures::StreamExt;
+
+    use super::*;
+
+    fn read(stream: ObjectStream) -> Vec<Bytes> {
+        block_on(stream.map(|chunk| chunk.unwrap()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_ranged_stream() {
+        let data: Bytes = (0..100u8).collect::<Vec<_>>().into();
+        let requests = Rc::new(RefCell::new(Vec::new()));
+        let fetch = |data: Bytes, requests: Rc<RefCell<Vec<String>>>| {
+            move |range: ByteRange| {
+                requests.borrow_mut().push(range.to_header_value());
+                let data = data.clone();
+                async move {
+                    if range.start() >= data.len() as u64 {
+                        return Ok(Chunk::End);
+                    }
+                    let chunk = Bytes::copy_from_slice(range.slice(&data));
+                    Ok(Chunk::Partial(chunk, Some(data.len() as u64)))
+                }
+            }
+        };
+
+        let chunks = read(ranged_stream(
+            None,
+            30,
+            fetch(data.clone(), requests.clone()),
+        ));
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(chunks.concat(), data.to_vec());
+        // the size is known after the first chunk, no request past it
+        assert_eq!(requests.borrow().last().unwrap(), "bytes=90-99");
+
+        requests.borrow_mut().clear();
+        let range = ByteRange::new(10, Some(44)).unwrap();
+        let chunks = read(ranged_stream(
+            Some(range),
+            30,
+            fetch(data.clone(), requests.clone()),
+        ));
+        assert_eq!(chunks.concat(), data[10..45].to_vec());
+        assert_eq!(*requests.borrow(), ["bytes=10-39", "bytes=40-44"]);
+    }
+
+    #[test]
+    fn test_ranged_stream_range_ignored() {
+        let data: Bytes = (0..100u8).collect::<Vec<_>>().into();
+        let range = ByteRange::new(95, None).unwrap();
+        let chunks = read(ranged_stream(Some(range), 30, move |_| {
+            let data = data.clone();
+            async move { Ok(Chunk::Full(data)) }
+        }));
+        assert_eq!(chunks.concat(), vec![95, 96, 97, 98, 99]);
+    }
+}
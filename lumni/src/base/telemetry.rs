@@ -0,0 +1,267 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::time::Duration;
+use std::{env, fs};
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::http::client::HttpClient;
+use crate::http::retry::RetryPolicy;
+use crate::LumniError;
+
+const TELEMETRY_FILE: &str = "telemetry.yaml";
+const DEFAULT_BATCH_SIZE: u64 = 50;
+// a slow endpoint should not hold up the command that is recorded
+const SEND_TIMEOUT_SECONDS: u64 = 5;
+// "0", "false" or "off" disables telemetry, whatever the settings say
+pub const TELEMETRY_ENV: &str = "LUMNI_TELEMETRY";
+
+// every field that is sent, nothing else is. uris, file names, prompts and
+// other content are never recorded
+pub const DATA_DICTIONARY: [(&str, &str); 5] = [
+    ("version", "version of lumni, e.g. 0.0.5"),
+    ("os", "operating system, e.g. linux"),
+    ("arch", "processor architecture, e.g. x86_64"),
+    (
+        "features",
+        "times each feature was used, by the name of the subcommand or \
+         app, e.g. {\"ls\": 3, \"cp\": 1}",
+    ),
+    ("uses", "total of the counts in features"),
+];
+
+// opt-in usage statistics, off until enabled. counts are kept in
+// ~/.config/lumni/telemetry.yaml until a batch is full, and then sent
+// as one anonymous report
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Telemetry {
+    #[serde(default)]
+    enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    endpoint: Option<String>,
+    #[serde(default = "default_batch_size")]
+    batch_size: u64,
+    #[serde(default)]
+    pending: BTreeMap<String, u64>, // counts not sent yet
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+fn default_batch_size() -> u64 {
+    DEFAULT_BATCH_SIZE
+}
+
+impl Default for Telemetry {
+    fn default() -> Self {
+        Telemetry {
+            enabled: false,
+            endpoint: None,
+            batch_size: DEFAULT_BATCH_SIZE,
+            pending: BTreeMap::new(),
+            path: None,
+        }
+    }
+}
+
+impl Telemetry {
+    // disabled if there is no settings file yet
+    pub fn load() -> Result<Self, LumniError> {
+        let path = telemetry_file();
+        let mut telemetry = match path.as_ref().filter(|path| path.exists()) {
+            Some(path) => {
+                let contents = fs::read_to_string(path)?;
+                serde_yaml::from_str(&contents).map_err(|e| {
+                    LumniError::Config(format!(
+                        "Invalid {}: {}",
+                        path.display(),
+                        e
+                    ))
+                })?
+            }
+            None => Telemetry::default(),
+        };
+        telemetry.path = path;
+        Ok(telemetry)
+    }
+
+    pub fn save(&self) -> Result<(), LumniError> {
+        let path = self.path.as_ref().ok_or_else(|| {
+            LumniError::Config("No config directory".to_string())
+        })?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let contents = serde_yaml::to_string(self)
+            .map_err(|e| LumniError::Internal(e.to_string()))?;
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled && !disabled_by_env()
+    }
+
+    // counts that were not sent are dropped when disabled
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.pending.clear();
+        }
+    }
+
+    pub fn endpoint(&self) -> Option<&str> {
+        self.endpoint.as_deref()
+    }
+
+    pub fn set_endpoint(&mut self, endpoint: Option<String>) {
+        self.endpoint = endpoint;
+    }
+
+    pub fn set_batch_size(&mut self, batch_size: u64) {
+        self.batch_size = batch_size.max(1);
+    }
+
+    pub fn record(&mut self, feature: &str) {
+        if self.is_enabled() {
+            *self.pending.entry(feature.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    fn uses(&self) -> u64 {
+        self.pending.values().sum()
+    }
+
+    // the report as it is sent, see DATA_DICTIONARY
+    pub fn report(&self) -> serde_json::Value {
+        json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "os": env::consts::OS,
+            "arch": env::consts::ARCH,
+            "features": self.pending,
+            "uses": self.uses(),
+        })
+    }
+
+    // sends the counts once a batch is full, returns if they were sent
+    pub async fn flush(&mut self) -> Result<bool, LumniError> {
+        let endpoint = match &self.endpoint {
+            Some(endpoint) if self.is_enabled() => endpoint,
+            _ => return Ok(false),
+        };
+        if self.uses() < self.batch_size {
+            return Ok(false);
+        }
+        let body = Bytes::from(self.report().to_string());
+        let headers = HashMap::from([(
+            "Content-Type".to_string(),
+            "application/json".to_string(),
+        )]);
+        HttpClient::new()
+            .with_timeout(Duration::from_secs(SEND_TIMEOUT_SECONDS))
+            .with_retry_policy(RetryPolicy::none())
+            .post(endpoint, Some(&headers), None, Some(&body), None, None)
+            .await
+            .map_err(LumniError::HttpClientError)?;
+        self.pending.clear();
+        Ok(true)
+    }
+
+    // what is collected, where it goes and what would be sent next
+    pub fn status(&self) -> String {
+        let state = match (self.enabled, disabled_by_env()) {
+            (true, true) => format!("disabled by {}", TELEMETRY_ENV),
+            (true, false) => "enabled".to_string(),
+            (false, _) => "disabled".to_string(),
+        };
+        let mut lines = vec![
+            format!("Telemetry: {}", state),
+            format!(
+                "Endpoint: {}",
+                self.endpoint.as_deref().unwrap_or("(not set)")
+            ),
+            format!(
+                "Batch: {} of {} uses recorded",
+                self.uses(),
+                self.batch_size
+            ),
+            String::new(),
+            "Data sent in a report:".to_string(),
+        ];
+        lines.extend(DATA_DICTIONARY.iter().map(|(field, description)| {
+            format!("  {}: {}", field, description)
+        }));
+        if !self.pending.is_empty() {
+            lines.push(String::new());
+            lines.push("Next report:".to_string());
+            lines.push(
+                serde_json::to_string_pretty(&self.report())
+                    .unwrap_or_default(),
+            );
+        }
+        lines.join("\n")
+    }
+}
+
+// counts a use of the feature if telemetry is enabled. errors are only
+// logged, telemetry should never fail what is recorded
+pub async fn record_usage(feature: &str) {
+    let result = async {
+        let mut telemetry = Telemetry::load()?;
+        if !telemetry.is_enabled() {
+            return Ok(());
+        }
+        telemetry.record(feature);
+        // counts are kept for the next attempt when sending fails
+        if let Err(e) = telemetry.flush().await {
+            log::debug!("Failed to send telemetry: {}", e);
+        }
+        telemetry.save()
+    }
+    .await;
+    if let Err(e) = result {
+        log::debug!("Failed to record telemetry: {}", e);
+    }
+}
+
+fn disabled_by_env() -> bool {
+    env::var(TELEMETRY_ENV).is_ok_and(|value| {
+        matches!(value.to_lowercase().as_str(), "0" | "false" | "off")
+    })
+}
+
+// e.g. ~/.config/lumni/telemetry.yaml
+fn telemetry_file() -> Option<PathBuf> {
+    let config_dir = match env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var("HOME").ok()?).join(".config"),
+    };
+    Some(config_dir.join("lumni").join(TELEMETRY_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_telemetry() {
+        let mut telemetry = Telemetry::default();
+        telemetry.record("ls");
+        assert_eq!(telemetry.uses(), 0);
+
+        telemetry.set_enabled(true);
+        telemetry.set_batch_size(3);
+        telemetry.record("ls");
+        telemetry.record("ls");
+        telemetry.record("cp");
+        assert_eq!(telemetry.report()["features"], json!({"ls": 2, "cp": 1}));
+        assert_eq!(telemetry.report()["uses"], 3);
+        // nothing is sent without an endpoint
+        assert!(!telemetry.flush().await.unwrap());
+        assert!(telemetry.status().contains("Batch: 3 of 3"));
+
+        telemetry.set_enabled(false);
+        assert!(telemetry.pending.is_empty());
+    }
+}
@@ -0,0 +1,362 @@
+use std::env;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use super::memory::parse_bytes;
+use crate::{EnvironmentConfig, LumniError};
+
+// settings of the global throttle. an operation (e.g. "DOWNLOAD") has its
+// own limits in "<OPERATION>_<SETTING>", applied on top of the global ones
+pub const BANDWIDTH_LIMIT_SETTING: &str = "BANDWIDTH_LIMIT";
+pub const REQUEST_RATE_LIMIT_SETTING: &str = "REQUEST_RATE_LIMIT";
+pub const MAX_CONNECTIONS_SETTING: &str = "MAX_CONNECTIONS";
+pub const THROTTLE_SETTINGS: [&str; 3] = [
+    BANDWIDTH_LIMIT_SETTING,
+    REQUEST_RATE_LIMIT_SETTING,
+    MAX_CONNECTIONS_SETTING,
+];
+
+static GLOBAL_THROTTLE: OnceLock<Throttle> = OnceLock::new();
+
+// limits of a throttle, None is no limit
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ThrottleConfig {
+    bytes_per_second: Option<u64>,
+    requests_per_second: Option<f64>,
+    max_connections: Option<usize>,
+}
+
+impl ThrottleConfig {
+    pub fn new() -> Self {
+        ThrottleConfig::default()
+    }
+
+    pub fn set_bytes_per_second(mut self, bytes: Option<u64>) -> Self {
+        self.bytes_per_second = bytes.filter(|bytes| *bytes > 0);
+        self
+    }
+
+    pub fn set_requests_per_second(mut self, requests: Option<f64>) -> Self {
+        self.requests_per_second = requests.filter(|requests| *requests > 0.0);
+        self
+    }
+
+    pub fn set_max_connections(mut self, connections: Option<usize>) -> Self {
+        self.max_connections =
+            connections.filter(|connections| *connections > 0);
+        self
+    }
+
+    pub fn bytes_per_second(&self) -> Option<u64> {
+        self.bytes_per_second
+    }
+
+    pub fn requests_per_second(&self) -> Option<f64> {
+        self.requests_per_second
+    }
+
+    pub fn max_connections(&self) -> Option<usize> {
+        self.max_connections
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        *self == ThrottleConfig::default()
+    }
+
+    // the global limits if operation is None, else those of the operation,
+    // e.g. DOWNLOAD_BANDWIDTH_LIMIT. a value of 0 is no limit
+    pub fn from_config(
+        config: &EnvironmentConfig,
+        operation: Option<&str>,
+    ) -> Result<Self, LumniError> {
+        let key = |setting: &str| match operation {
+            Some(operation) => {
+                format!("{}_{}", operation.to_uppercase(), setting)
+            }
+            None => setting.to_string(),
+        };
+        let invalid = |key: &str, value: &str, expected: &str| {
+            LumniError::Config(format!(
+                "Invalid {}: {}, expected {}",
+                key, value, expected
+            ))
+        };
+
+        let mut throttle_config = ThrottleConfig::new();
+        let bandwidth_key = key(BANDWIDTH_LIMIT_SETTING);
+        if let Some(value) = config.get(&bandwidth_key) {
+            let bytes = parse_bytes(value).ok_or_else(|| {
+                invalid(&bandwidth_key, value, "bytes per second such as 10M")
+            })?;
+            throttle_config = throttle_config.set_bytes_per_second(Some(bytes));
+        }
+        let rate_key = key(REQUEST_RATE_LIMIT_SETTING);
+        if let Some(value) = config.get(&rate_key) {
+            let requests = value
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|requests| requests.is_finite() && *requests >= 0.0)
+                .ok_or_else(|| {
+                    invalid(&rate_key, value, "requests per second such as 50")
+                })?;
+            throttle_config =
+                throttle_config.set_requests_per_second(Some(requests));
+        }
+        let connections_key = key(MAX_CONNECTIONS_SETTING);
+        if let Some(value) = config.get(&connections_key) {
+            let connections = value.trim().parse::<usize>().map_err(|_| {
+                invalid(&connections_key, value, "a number of connections")
+            })?;
+            throttle_config =
+                throttle_config.set_max_connections(Some(connections));
+        }
+        Ok(throttle_config)
+    }
+
+    // as from_config, for apps that are not given the config of the cli
+    pub fn from_env() -> Result<Self, LumniError> {
+        let settings = THROTTLE_SETTINGS
+            .iter()
+            .filter_map(|setting| {
+                env::var(setting)
+                    .ok()
+                    .map(|value| (setting.to_string(), value))
+            })
+            .collect();
+        Self::from_config(&EnvironmentConfig::new(settings), None)
+    }
+}
+
+// tokens refill at `rate` per second, up to a burst of one second.
+// a reservation larger than what is left takes the balance negative, and
+// the caller waits until it is paid back
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: f64, now: Instant) -> Self {
+        TokenBucket {
+            rate,
+            tokens: rate,
+            updated: now,
+        }
+    }
+
+    // the time to wait before `amount` may be used
+    fn reserve(&mut self, amount: f64, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.updated);
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() * self.rate).min(self.rate);
+        self.updated = now;
+        self.tokens -= amount;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+struct ThrottleState {
+    config: ThrottleConfig,
+    bandwidth: Option<Mutex<TokenBucket>>,
+    requests: Option<Mutex<TokenBucket>>,
+    connections: Option<Arc<Semaphore>>,
+    parent: Option<Throttle>,
+}
+
+// limits the bytes per second, requests per second and concurrent
+// connections of everything that shares it. clones share the same limits.
+// a throttle of an operation also waits for the global throttle
+#[derive(Clone, Default)]
+pub struct Throttle {
+    state: Option<Arc<ThrottleState>>, // None is unlimited
+}
+
+impl Throttle {
+    pub fn new(config: ThrottleConfig) -> Self {
+        Self::with_parent(config, None)
+    }
+
+    // limits of an operation, on top of the global ones
+    pub fn for_operation(config: ThrottleConfig) -> Self {
+        Self::with_parent(config, Some(Throttle::global()))
+    }
+
+    pub fn unlimited() -> Self {
+        Throttle::default()
+    }
+
+    fn with_parent(config: ThrottleConfig, parent: Option<Throttle>) -> Self {
+        let parent = parent.filter(|parent| parent.state.is_some());
+        if config.is_unlimited() {
+            return parent.unwrap_or_default();
+        }
+        let now = Instant::now();
+        Throttle {
+            state: Some(Arc::new(ThrottleState {
+                config,
+                bandwidth: config
+                    .bytes_per_second
+                    .map(|rate| Mutex::new(TokenBucket::new(rate as f64, now))),
+                requests: config
+                    .requests_per_second
+                    .map(|rate| Mutex::new(TokenBucket::new(rate, now))),
+                connections: config
+                    .max_connections
+                    .map(|max| Arc::new(Semaphore::new(max))),
+                parent,
+            })),
+        }
+    }
+
+    // throttle used by HttpClient::new() and the http::requests functions,
+    // unlimited unless configured with init_global
+    pub fn global() -> Self {
+        GLOBAL_THROTTLE.get_or_init(Throttle::unlimited).clone()
+    }
+
+    // configure the global throttle, before it is first used. returns
+    // false if it already exists
+    pub fn init_global(config: ThrottleConfig) -> bool {
+        GLOBAL_THROTTLE.set(Throttle::new(config)).is_ok()
+    }
+
+    pub fn config(&self) -> ThrottleConfig {
+        self.state
+            .as_ref()
+            .map_or_else(ThrottleConfig::default, |state| state.config)
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.state.is_none()
+    }
+
+    // waits for a free connection and a request slot. the connection is
+    // counted until the permit is dropped
+    pub async fn acquire(&self) -> ThrottlePermit {
+        let mut permits = Vec::new();
+        let mut throttle = self;
+        while let Some(state) = &throttle.state {
+            if let Some(connections) = &state.connections {
+                let permit = Arc::clone(connections)
+                    .acquire_owned()
+                    .await
+                    .expect("throttle semaphore is never closed");
+                permits.push(permit);
+            }
+            if let Some(requests) = &state.requests {
+                let delay =
+                    requests.lock().unwrap().reserve(1.0, Instant::now());
+                sleep(delay).await;
+            }
+            match &state.parent {
+                Some(parent) => throttle = parent,
+                None => break,
+            }
+        }
+        ThrottlePermit { _permits: permits }
+    }
+
+    // waits until `bytes` fit in the bandwidth, called for every chunk
+    // that is sent or received
+    pub async fn consume(&self, bytes: usize) {
+        let mut throttle = self;
+        while let Some(state) = &throttle.state {
+            if let Some(bandwidth) = &state.bandwidth {
+                let delay = bandwidth
+                    .lock()
+                    .unwrap()
+                    .reserve(bytes as f64, Instant::now());
+                sleep(delay).await;
+            }
+            match &state.parent {
+                Some(parent) => throttle = parent,
+                None => break,
+            }
+        }
+    }
+}
+
+// a connection counted against the limits of a throttle
+pub struct ThrottlePermit {
+    _permits: Vec<OwnedSemaphorePermit>,
+}
+
+async fn sleep(delay: Duration) {
+    if !delay.is_zero() {
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(100.0, start);
+        // the first second is a burst
+        assert_eq!(bucket.reserve(100.0, start), Duration::ZERO);
+        assert_eq!(bucket.reserve(50.0, start), Duration::from_millis(500));
+        // half a second later the debt is paid
+        let later = start + Duration::from_millis(500);
+        assert_eq!(bucket.reserve(0.0, later), Duration::ZERO);
+        assert_eq!(bucket.reserve(200.0, later), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_throttle_config() {
+        let config = EnvironmentConfig::new(HashMap::from([
+            (BANDWIDTH_LIMIT_SETTING.to_string(), "2M".to_string()),
+            ("DOWNLOAD_MAX_CONNECTIONS".to_string(), "4".to_string()),
+            ("DOWNLOAD_REQUEST_RATE_LIMIT".to_string(), "0.5".to_string()),
+        ]));
+        let global = ThrottleConfig::from_config(&config, None).unwrap();
+        assert_eq!(global.bytes_per_second(), Some(2 * 1024 * 1024));
+        assert_eq!(global.max_connections(), None);
+
+        let download =
+            ThrottleConfig::from_config(&config, Some("download")).unwrap();
+        assert_eq!(download.bytes_per_second(), None);
+        assert_eq!(download.max_connections(), Some(4));
+        assert_eq!(download.requests_per_second(), Some(0.5));
+
+        let config = EnvironmentConfig::with_setting(
+            MAX_CONNECTIONS_SETTING.to_string(),
+            "many".to_string(),
+        );
+        assert!(ThrottleConfig::from_config(&config, None).is_err());
+        // 0 is no limit
+        let config = EnvironmentConfig::with_setting(
+            BANDWIDTH_LIMIT_SETTING.to_string(),
+            "0".to_string(),
+        );
+        assert!(ThrottleConfig::from_config(&config, None)
+            .unwrap()
+            .is_unlimited());
+    }
+
+    #[tokio::test]
+    async fn test_max_connections() {
+        let throttle =
+            Throttle::new(ThrottleConfig::new().set_max_connections(Some(1)));
+        let permit = throttle.acquire().await;
+        let waiting =
+            tokio::time::timeout(Duration::from_millis(20), throttle.acquire());
+        assert!(waiting.await.is_err());
+        drop(permit);
+        let _permit = throttle.acquire().await;
+        assert!(Throttle::unlimited().is_unlimited());
+    }
+}
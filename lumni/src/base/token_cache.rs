@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+use serde::{Deserialize, Serialize};
+
+use super::encryption::{write_private_file, EncryptionHandler};
+use crate::utils::time::system_time_in_seconds;
+use crate::LumniError;
+
+// set to "off" to not read or write cached tokens
+pub const TOKEN_CACHE_ENV: &str = "LUMNI_TOKEN_CACHE";
+
+// tokens are refreshed this long before they expire
+pub const TOKEN_EXPIRY_MARGIN_SECONDS: u64 = 300;
+
+const TOKENS_FILE: &str = "tokens";
+// in ~/.lumni/keys, not next to the tokens, see EncryptionHandler
+const KEY_FILE: &str = "token-cache";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedToken {
+    pub value: String,
+    pub expiration: u64, // epoch seconds
+}
+
+// temporary credentials (e.g. of STS, SSO or an OAuth token endpoint)
+// kept in ~/.lumni/cache between invocations, so the auth flows do not
+// run again until the credentials are about to expire. the file is
+// encrypted, see EncryptionHandler
+pub struct TokenCache {
+    dir: PathBuf,
+    handler: EncryptionHandler,
+    tokens: BTreeMap<String, CachedToken>,
+}
+
+impl TokenCache {
+    // None if caching is turned off or there is no home directory. a
+    // cache that can not be read is treated as empty
+    pub fn open() -> Result<Option<Self>, LumniError> {
+        let Some(dir) = cache_dir() else {
+            return Ok(None);
+        };
+        let handler = EncryptionHandler::from_key_file(&key_file(&dir))?;
+        let tokens = fs::read(dir.join(TOKENS_FILE))
+            .ok()
+            .and_then(|encrypted| handler.decrypt(&encrypted).ok())
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+        Ok(Some(TokenCache {
+            dir,
+            handler,
+            tokens,
+        }))
+    }
+
+    // the value if it does not expire within the margin
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.get_token(key).map(|token| token.value.as_str())
+    }
+
+    // as get, with the expiration of the token
+    pub fn get_token(&self, key: &str) -> Option<&CachedToken> {
+        self.tokens.get(key).filter(|token| {
+            token.expiration
+                > system_time_in_seconds() + TOKEN_EXPIRY_MARGIN_SECONDS
+        })
+    }
+
+    // stores the token, and drops those that expired
+    pub fn insert(
+        &mut self,
+        key: &str,
+        value: String,
+        expiration: u64,
+    ) -> Result<(), LumniError> {
+        let now = system_time_in_seconds();
+        self.tokens.retain(|_, token| token.expiration > now);
+        self.tokens
+            .insert(key.to_string(), CachedToken { value, expiration });
+        self.save()
+    }
+
+    pub fn tokens(&self) -> &BTreeMap<String, CachedToken> {
+        &self.tokens
+    }
+
+    // removes the cached tokens and the key they were encrypted with
+    pub fn clear() -> Result<usize, LumniError> {
+        let Some(dir) = cache_dir() else {
+            return Ok(0);
+        };
+        let count = TokenCache::open()?.map_or(0, |cache| cache.tokens.len());
+        for file in [dir.join(TOKENS_FILE), key_file(&dir)] {
+            match fs::remove_file(file) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(e.into())
+                }
+                _ => {}
+            }
+        }
+        Ok(count)
+    }
+
+    fn save(&self) -> Result<(), LumniError> {
+        let data = serde_json::to_vec(&self.tokens)
+            .map_err(|e| LumniError::Internal(e.to_string()))?;
+        write_private_file(
+            &self.dir.join(TOKENS_FILE),
+            &self.handler.encrypt(&data)?,
+        )
+    }
+}
+
+// a cached token, failures to read the cache only skip it
+pub fn cached_token(key: &str) -> Option<String> {
+    cached_token_entry(key).map(|token| token.value)
+}
+
+// as cached_token, with the expiration of the token
+pub fn cached_token_entry(key: &str) -> Option<CachedToken> {
+    match TokenCache::open() {
+        Ok(cache) => cache?.get_token(key).cloned(),
+        Err(e) => {
+            log::debug!("Token cache not available: {}", e);
+            None
+        }
+    }
+}
+
+// stores a token, failures are logged as the token is still usable
+pub fn cache_token(key: &str, value: String, expiration: u64) {
+    let result = TokenCache::open().and_then(|cache| match cache {
+        Some(mut cache) => cache.insert(key, value, expiration),
+        None => Ok(()),
+    });
+    if let Err(e) = result {
+        log::debug!("Token not cached: {}", e);
+    }
+}
+
+// e.g. ~/.lumni/cache
+fn cache_dir() -> Option<PathBuf> {
+    if env::var(TOKEN_CACHE_ENV).is_ok_and(|v| v.eq_ignore_ascii_case("off")) {
+        return None;
+    }
+    let home = env::var("HOME").ok()?;
+    Some(PathBuf::from(home).join(".lumni").join("cache"))
+}
+
+// e.g. ~/.lumni/keys/token-cache
+fn key_file(cache_dir: &Path) -> PathBuf {
+    cache_dir.with_file_name("keys").join(KEY_FILE)
+}
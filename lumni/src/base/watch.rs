@@ -0,0 +1,45 @@
+use crate::FileObject;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WatchEventKind {
+    Created,
+    Modified,
+    Deleted,
+}
+
+impl WatchEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WatchEventKind::Created => "created",
+            WatchEventKind::Modified => "modified",
+            WatchEventKind::Deleted => "deleted",
+        }
+    }
+}
+
+// a change to a file in a watched location. of a deleted file only the
+// name is known, its size is 0
+#[derive(Debug, Clone)]
+pub struct WatchEvent {
+    kind: WatchEventKind,
+    file_object: FileObject,
+}
+
+impl WatchEvent {
+    pub fn new(kind: WatchEventKind, file_object: FileObject) -> Self {
+        WatchEvent { kind, file_object }
+    }
+
+    pub fn kind(&self) -> WatchEventKind {
+        self.kind
+    }
+
+    pub fn file_object(&self) -> &FileObject {
+        &self.file_object
+    }
+}
+
+pub trait WatchCallback: Send + Sync {
+    // returns false to stop watching
+    fn on_event(&self, event: &WatchEvent) -> bool;
+}
@@ -0,0 +1,218 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::{env, fs};
+
+use lumni::{AWSCredentialProvider, AWSCredentialSource, LumniError};
+
+pub const PROFILE_ENV: &str = "LUMNI_PROFILE";
+pub const DEFAULT_PROFILE: &str = "default";
+pub const PROFILE_KEYS: [&str; 3] = ["endpoint", "region", "credentials"];
+
+// named profiles in ~/.lumni/config, e.g.
+//
+// [minio]
+// endpoint = http://localhost:9000
+// region = us-east-1
+// credentials = aws-profile:minio
+//
+// credentials refer to where they are kept rather than holding them:
+// "aws-profile:NAME" for a profile of the AWS shared config files, or
+// "gcs-key:PATH" for a service account key
+#[derive(Debug, Default)]
+pub struct ConfigFile {
+    profiles: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl ConfigFile {
+    pub fn load() -> Result<Self, LumniError> {
+        let Some(path) = config_file() else {
+            return Ok(ConfigFile::default());
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => Ok(ConfigFile::parse(&contents)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Ok(ConfigFile::default())
+            }
+            Err(e) => Err(LumniError::Config(format!(
+                "Can not read {}: {}",
+                path.display(),
+                e
+            ))),
+        }
+    }
+
+    pub fn save(&self) -> Result<PathBuf, LumniError> {
+        let path = config_file()
+            .ok_or_else(|| LumniError::Config("HOME is not set".to_string()))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, self.to_string())?;
+        Ok(path)
+    }
+
+    fn parse(contents: &str) -> Self {
+        let mut config = ConfigFile::default();
+        let mut section: Option<String> = None;
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(['#', ';']) {
+                continue;
+            }
+            if let Some(header) =
+                line.strip_prefix('[').and_then(|l| l.strip_suffix(']'))
+            {
+                let name = header.trim().to_string();
+                config.profiles.entry(name.clone()).or_default();
+                section = Some(name);
+                continue;
+            }
+            let (Some(name), Some((key, value))) =
+                (&section, line.split_once('='))
+            else {
+                continue;
+            };
+            config
+                .profiles
+                .entry(name.clone())
+                .or_default()
+                .insert(key.trim().to_string(), value.trim().to_string());
+        }
+        config
+    }
+
+    pub fn profile(&self, name: &str) -> Option<&BTreeMap<String, String>> {
+        self.profiles.get(name)
+    }
+
+    pub fn profiles(&self) -> &BTreeMap<String, BTreeMap<String, String>> {
+        &self.profiles
+    }
+
+    // an empty value removes the key
+    pub fn set(&mut self, profile: &str, key: &str, value: &str) {
+        let settings = self.profiles.entry(profile.to_string()).or_default();
+        if value.is_empty() {
+            settings.remove(key);
+        } else {
+            settings.insert(key.to_string(), value.to_string());
+        }
+    }
+
+    // settings of the profile as config keys of the object stores. an
+    // explicit profile must exist, while "default" may be absent
+    pub async fn settings(
+        &self,
+        name: &str,
+        explicit: bool,
+    ) -> Result<HashMap<String, String>, LumniError> {
+        let mut settings = HashMap::new();
+        let Some(profile) = self.profiles.get(name) else {
+            return match explicit {
+                true => Err(LumniError::Config(format!(
+                    "Profile \"{}\" not found, see lumni configure",
+                    name
+                ))),
+                false => Ok(settings),
+            };
+        };
+        if let Some(region) = profile.get("region") {
+            settings.insert("region".to_string(), region.clone());
+        }
+        if let Some(endpoint) = profile.get("endpoint") {
+            settings.insert("S3_ENDPOINT_URL".to_string(), endpoint.clone());
+        }
+        match profile.get("credentials").map(|c| c.split_once(':')) {
+            None => {}
+            Some(Some(("aws-profile", aws_profile))) => {
+                let credentials = AWSCredentialProvider::new()
+                    .set_sources(vec![AWSCredentialSource::Profile])
+                    .set_profile(aws_profile)
+                    .credentials()
+                    .await?;
+                settings.insert(
+                    "AWS_ACCESS_KEY_ID".to_string(),
+                    credentials.access_key().to_string(),
+                );
+                settings.insert(
+                    "AWS_SECRET_ACCESS_KEY".to_string(),
+                    credentials.secret_key().to_string(),
+                );
+                if let Some(token) = credentials.session_token() {
+                    settings.insert(
+                        "AWS_SESSION_TOKEN".to_string(),
+                        token.to_string(),
+                    );
+                }
+            }
+            Some(Some(("gcs-key", path))) => {
+                settings.insert(
+                    "GOOGLE_APPLICATION_CREDENTIALS".to_string(),
+                    path.to_string(),
+                );
+            }
+            Some(_) => {
+                return Err(LumniError::Config(format!(
+                    "Invalid credentials of profile \"{}\", expected \
+                     aws-profile:NAME or gcs-key:PATH",
+                    name
+                )))
+            }
+        }
+        Ok(settings)
+    }
+}
+
+impl std::fmt::Display for ConfigFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, (name, settings)) in self.profiles.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "[{}]", name)?;
+            for (key, value) in settings {
+                writeln!(f, "{} = {}", key, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// the profile given by --profile or LUMNI_PROFILE, and whether it was
+// given at all
+pub fn selected_profile(profile: Option<&String>) -> (String, bool) {
+    match profile.cloned().or_else(|| env::var(PROFILE_ENV).ok()) {
+        Some(name) => (name, true),
+        None => (DEFAULT_PROFILE.to_string(), false),
+    }
+}
+
+// ~/.lumni/config
+fn config_file() -> Option<PathBuf> {
+    let home = env::var("HOME").ok()?;
+    Some(PathBuf::from(home).join(".lumni").join("config"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_write() {
+        let contents = "# comment\n[default]\nregion = eu-west-1\n\n[minio]\n\
+                        endpoint = http://localhost:9000\ncredentials = \
+                        aws-profile:minio\n";
+        let mut config = ConfigFile::parse(contents);
+        assert_eq!(
+            config.profile("minio").unwrap().get("endpoint").unwrap(),
+            "http://localhost:9000"
+        );
+        config.set("default", "region", "");
+        config.set("r2", "region", "auto");
+        assert_eq!(
+            config.to_string(),
+            "[default]\n\n[minio]\ncredentials = aws-profile:minio\n\
+             endpoint = http://localhost:9000\n\n[r2]\nregion = auto\n"
+        );
+    }
+}
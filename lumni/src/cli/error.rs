@@ -0,0 +1,303 @@
+use std::fmt;
+use std::sync::OnceLock;
+
+use lumni::api::error::{ApplicationError, HttpClientError, LumniError};
+use serde_json::json;
+
+use super::json_output::{is_json_output, print_json};
+
+static ERROR_FORMAT: OnceLock<ErrorFormat> = OnceLock::new();
+
+// exit codes are part of the CLI interface, scripts may depend on them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCode {
+    GeneralError = 1,
+    UsageError = 2,
+    ConfigError = 3,
+    AuthError = 4,
+    NotFound = 5,
+    PartialFailure = 6,
+    PreconditionFailed = 7,
+}
+
+impl ExitCode {
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+
+    pub fn kind(self) -> &'static str {
+        match self {
+            ExitCode::GeneralError => "general_error",
+            ExitCode::UsageError => "usage_error",
+            ExitCode::ConfigError => "config_error",
+            ExitCode::AuthError => "auth_error",
+            ExitCode::NotFound => "not_found",
+            ExitCode::PartialFailure => "partial_failure",
+            ExitCode::PreconditionFailed => "precondition_failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat {
+    Text,
+    Json,
+}
+
+impl ErrorFormat {
+    pub fn from_str(format: &str) -> Self {
+        match format {
+            "json" => ErrorFormat::Json,
+            _ => ErrorFormat::Text,
+        }
+    }
+}
+
+// can only be set once, before the subcommand runs
+pub fn set_error_format(format: ErrorFormat) {
+    let _ = ERROR_FORMAT.set(format);
+}
+
+#[derive(Debug)]
+pub struct CliError {
+    exit_code: ExitCode,
+    message: String,
+}
+
+impl CliError {
+    pub fn new(exit_code: ExitCode, message: impl Into<String>) -> Self {
+        CliError {
+            exit_code,
+            message: message.into(),
+        }
+    }
+
+    pub fn general(message: impl Into<String>) -> Self {
+        CliError::new(ExitCode::GeneralError, message)
+    }
+
+    pub fn usage(message: impl Into<String>) -> Self {
+        CliError::new(ExitCode::UsageError, message)
+    }
+
+    // prefixes the message, e.g. with the path or url that failed
+    pub fn context(mut self, context: &str) -> Self {
+        self.message = format!("{}: {}", context, self.message);
+        self
+    }
+
+    // prints the error to stderr in the selected format and exits. with
+    // --json it goes to stdout, next to the results
+    pub fn exit(&self) -> ! {
+        let error = json!({
+            "error": {
+                "kind": self.exit_code.kind(),
+                "exit_code": self.exit_code.code(),
+                "message": self.message,
+            }
+        });
+        let format = ERROR_FORMAT.get().copied().unwrap_or(ErrorFormat::Text);
+        match format {
+            _ if is_json_output() => print_json(&error),
+            ErrorFormat::Text => eprintln!("Error: {}", self.message),
+            ErrorFormat::Json => eprintln!("{}", error),
+        }
+        std::process::exit(self.exit_code.code())
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+fn exit_code_for_status(status: u16) -> ExitCode {
+    match status {
+        401 | 403 => ExitCode::AuthError,
+        404 => ExitCode::NotFound,
+        412 => ExitCode::PreconditionFailed,
+        _ => ExitCode::GeneralError,
+    }
+}
+
+impl From<HttpClientError> for CliError {
+    fn from(error: HttpClientError) -> Self {
+        let exit_code = match &error {
+            HttpClientError::HttpError(status, _) => {
+                exit_code_for_status(*status)
+            }
+            _ => ExitCode::GeneralError,
+        };
+        CliError::new(exit_code, error.to_string())
+    }
+}
+
+impl From<LumniError> for CliError {
+    fn from(error: LumniError) -> Self {
+        let exit_code = match error.root() {
+            LumniError::Config(_) | LumniError::NoBucketInUri(_) => {
+                ExitCode::ConfigError
+            }
+            LumniError::AccessDenied(_) => ExitCode::AuthError,
+            LumniError::NotFound(_) => ExitCode::NotFound,
+            LumniError::PreconditionFailed(_) => ExitCode::PreconditionFailed,
+            #[cfg(feature = "http_client")]
+            LumniError::HttpClientError(HttpClientError::HttpError(
+                status,
+                _,
+            )) => exit_code_for_status(*status),
+            LumniError::Application(app_error, _)
+            | LumniError::Invoke(app_error, _) => match app_error {
+                ApplicationError::InvalidUserConfiguration(_)
+                | ApplicationError::ServerConfigurationError(_) => {
+                    ExitCode::ConfigError
+                }
+                ApplicationError::InvalidCredentials(_) => ExitCode::AuthError,
+                ApplicationError::Conflict(_) => ExitCode::PreconditionFailed,
+                ApplicationError::HttpClientError(
+                    HttpClientError::HttpError(status, _),
+                ) => exit_code_for_status(*status),
+                _ => ExitCode::GeneralError,
+            },
+            LumniError::Request(_) => ExitCode::UsageError,
+            _ => ExitCode::GeneralError,
+        };
+        CliError::new(exit_code, error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lumni::api::error::RequestError;
+
+    use super::*;
+
+    #[test]
+    fn test_exit_codes() {
+        let http_error =
+            |status| HttpClientError::HttpError(status, String::new());
+        let app_error = |error| LumniError::Application(error, None);
+        let cases = vec![
+            (LumniError::Config(String::new()), ExitCode::ConfigError),
+            (
+                LumniError::NoBucketInUri(String::new()),
+                ExitCode::ConfigError,
+            ),
+            (LumniError::AccessDenied(String::new()), ExitCode::AuthError),
+            (LumniError::NotFound(String::new()), ExitCode::NotFound),
+            (
+                LumniError::PreconditionFailed(String::new()),
+                ExitCode::PreconditionFailed,
+            ),
+            (LumniError::Throttled(String::new()), ExitCode::GeneralError),
+            (LumniError::Internal(String::new()), ExitCode::GeneralError),
+            // the context does not change the exit code
+            (
+                LumniError::NotFound(String::new()).context("s3://bucket"),
+                ExitCode::NotFound,
+            ),
+            (LumniError::from_status(403, "key", ""), ExitCode::AuthError),
+            (LumniError::from_status(404, "key", ""), ExitCode::NotFound),
+            (
+                LumniError::from_status(412, "key", ""),
+                ExitCode::PreconditionFailed,
+            ),
+            (
+                LumniError::from_status(500, "key", ""),
+                ExitCode::GeneralError,
+            ),
+            (
+                LumniError::HttpClientError(http_error(401)),
+                ExitCode::AuthError,
+            ),
+            (
+                LumniError::HttpClientError(http_error(404)),
+                ExitCode::NotFound,
+            ),
+            (
+                LumniError::HttpClientError(http_error(412)),
+                ExitCode::PreconditionFailed,
+            ),
+            (
+                LumniError::HttpClientError(http_error(500)),
+                ExitCode::GeneralError,
+            ),
+            (
+                LumniError::HttpClientError(HttpClientError::TimeoutError),
+                ExitCode::GeneralError,
+            ),
+            (
+                app_error(ApplicationError::InvalidUserConfiguration(
+                    String::new(),
+                )),
+                ExitCode::ConfigError,
+            ),
+            (
+                app_error(ApplicationError::ServerConfigurationError(
+                    String::new(),
+                )),
+                ExitCode::ConfigError,
+            ),
+            (
+                app_error(ApplicationError::InvalidCredentials(String::new())),
+                ExitCode::AuthError,
+            ),
+            (
+                app_error(ApplicationError::Conflict(String::new())),
+                ExitCode::PreconditionFailed,
+            ),
+            (
+                app_error(ApplicationError::HttpClientError(http_error(403))),
+                ExitCode::AuthError,
+            ),
+            (
+                LumniError::Invoke(
+                    ApplicationError::Unexpected(String::new()),
+                    None,
+                ),
+                ExitCode::GeneralError,
+            ),
+            (
+                LumniError::Request(RequestError::QueryInvalid(String::new())),
+                ExitCode::UsageError,
+            ),
+        ];
+        for (error, exit_code) in cases {
+            let message = error.to_string();
+            assert_eq!(
+                CliError::from(error).exit_code,
+                exit_code,
+                "{}",
+                message
+            );
+        }
+
+        assert_eq!(
+            CliError::from(http_error(403)).exit_code,
+            ExitCode::AuthError
+        );
+        assert_eq!(
+            CliError::from(HttpClientError::RequestCancelled).exit_code,
+            ExitCode::GeneralError
+        );
+    }
+
+    #[test]
+    fn test_exit_code_values() {
+        // scripts depend on these, they must not change
+        let codes = [
+            (ExitCode::GeneralError, 1, "general_error"),
+            (ExitCode::UsageError, 2, "usage_error"),
+            (ExitCode::ConfigError, 3, "config_error"),
+            (ExitCode::AuthError, 4, "auth_error"),
+            (ExitCode::NotFound, 5, "not_found"),
+            (ExitCode::PartialFailure, 6, "partial_failure"),
+            (ExitCode::PreconditionFailed, 7, "precondition_failed"),
+        ];
+        for (exit_code, code, kind) in codes {
+            assert_eq!(exit_code.code(), code);
+            assert_eq!(exit_code.kind(), kind);
+        }
+    }
+}
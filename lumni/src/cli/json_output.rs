@@ -0,0 +1,54 @@
+use std::io::{self, Write};
+use std::process;
+use std::sync::OnceLock;
+
+use serde_json::{json, Value};
+
+static JSON_OUTPUT: OnceLock<bool> = OnceLock::new();
+
+// with --json every line on stdout is one JSON object: {"error": {..}},
+// {"warning": {..}}, or else a result of the subcommand. the schemas are
+// part of the CLI interface, like the exit codes
+pub const JSON_HELP: &str = "With --json, stdout has one JSON object per \
+                             line: {\"error\": {\"kind\", \"exit_code\", \
+                             \"message\"}}, {\"warning\": {\"message\"}}, or \
+                             a result of the subcommand";
+
+// can only be set once, before the subcommand runs
+pub fn set_json_output() {
+    let _ = JSON_OUTPUT.set(true);
+}
+
+pub fn is_json_output() -> bool {
+    JSON_OUTPUT.get().copied().unwrap_or(false)
+}
+
+// prints the value as one line on stdout
+pub fn print_json(value: &Value) {
+    if let Err(e) = writeln!(io::stdout().lock(), "{}", value) {
+        if e.kind() == io::ErrorKind::BrokenPipe {
+            // reader went away, e.g. "lumni rm --json ... | head"
+            process::exit(0);
+        }
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+}
+
+// prints the result as JSON, or the text otherwise
+pub fn print_result(value: Value, text: impl FnOnce() -> String) {
+    if is_json_output() {
+        print_json(&value);
+    } else {
+        println!("{}", text());
+    }
+}
+
+// a problem that does not stop the subcommand
+pub fn print_warning(message: &str) {
+    if is_json_output() {
+        print_json(&json!({ "warning": { "message": message } }));
+    } else {
+        eprintln!("Warning: {}", message);
+    }
+}
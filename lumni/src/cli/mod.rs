@@ -1,4 +1,8 @@
+mod config_file;
+mod error;
+mod json_output;
 mod parser;
+mod profile;
 mod subcommands;
 
 pub use parser::run_cli;
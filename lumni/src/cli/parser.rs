@@ -1,22 +1,59 @@
 use std::collections::HashMap;
 use std::env;
 
-use clap::{Arg, Command};
-use lumni::EnvironmentConfig;
+use clap::{Arg, ArgAction, Command};
+use lumni::{
+    record_usage, EnvironmentConfig, LumniError, Throttle, ThrottleConfig,
+    BANDWIDTH_LIMIT_SETTING, HEAD_CONCURRENCY_SETTING,
+    LISTING_CACHE_TTL_SETTING, MANIFEST_SIGNING_KEY_SETTING,
+    MEMORY_BUDGET_SETTING, THROTTLE_SETTINGS,
+};
 
+use super::config_file::{selected_profile, ConfigFile};
+use super::error::{set_error_format, CliError, ErrorFormat};
+use super::json_output::{set_json_output, JSON_HELP};
+use super::profile::PerfProfile;
+
+use super::subcommands::access::*;
 use super::subcommands::app::*;
+use super::subcommands::auth::*;
+use super::subcommands::browse::*;
+use super::subcommands::cache::*;
+use super::subcommands::completions::*;
+use super::subcommands::configure::*;
 use super::subcommands::cp::*;
+use super::subcommands::diff::*;
+use super::subcommands::env::*;
+use super::subcommands::get::*;
+use super::subcommands::grep::*;
 use super::subcommands::ls::*;
+use super::subcommands::manifest::*;
+use super::subcommands::mb::*;
+use super::subcommands::presign::*;
+use super::subcommands::put::*;
 use super::subcommands::query::*;
+use super::subcommands::rb::*;
 use super::subcommands::request::*;
+use super::subcommands::restore::*;
+use super::subcommands::rm::*;
+use super::subcommands::s3::*;
+use super::subcommands::stat::*;
+use super::subcommands::telemetry::*;
+use super::subcommands::watch::*;
 
 const PROGRAM_NAME: &str = "Lumni";
 
-pub async fn run_cli(_args: Vec<String>) {
-    env_logger::init();
-    let app = Command::new(PROGRAM_NAME)
+// the command line interface, also used to generate shell completions
+pub fn cli_command() -> Command {
+    Command::new(PROGRAM_NAME)
         .version(env!("CARGO_PKG_VERSION"))
         .arg_required_else_help(true)
+        .after_help(format!(
+            "Exit codes: 1 general error, 2 usage error, 3 config error, 4 \
+             auth error, 5 not found, 6 partial failure, 7 precondition \
+             failed\n\n{}",
+            JSON_HELP
+        ))
         .about(format!(
             "{}: explore, process and connect data",
             PROGRAM_NAME
@@ -27,18 +64,148 @@ pub async fn run_cli(_args: Vec<String>) {
                 .short('r')
                 .help("Region to use"),
         )
+        .arg(
+            Arg::new("request_payer")
+                .long("request-payer")
+                .action(ArgAction::SetTrue)
+                .help(
+                    "Agree to pay for requests to S3 buckets with requester \
+                     pays enabled",
+                ),
+        )
+        .arg(Arg::new("profile").long("profile").global(true).help(
+            "Profile of ~/.lumni/config to use, see configure \
+                     [default: LUMNI_PROFILE, or \"default\" if it exists]",
+        ))
+        .arg(
+            Arg::new("memory_budget")
+                .long("memory-budget")
+                .value_name("SIZE")
+                .help(
+                    "Memory a listing may take before it streams its rows \
+                     without keeping them, or stops if they are needed at \
+                     the end (e.g. for parquet). E.g. 512M, 0 for no limit \
+                     [default: 1G, or MEMORY_BUDGET]",
+                ),
+        )
+        .arg(
+            Arg::new("bandwidth_limit")
+                .long("bandwidth-limit")
+                .value_name("SIZE")
+                .help(
+                    "Bytes per second all requests may transfer together, \
+                     e.g. 10M. REQUEST_RATE_LIMIT and MAX_CONNECTIONS limit \
+                     the requests, DOWNLOAD_* settings a download \
+                     [default: no limit, or BANDWIDTH_LIMIT]",
+                ),
+        )
+        .arg(
+            Arg::new("no_cache")
+                .long("no-cache")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help(
+                    "List from the object store, also if LISTING_CACHE_TTL \
+                     caches listings",
+                ),
+        )
+        .arg(
+            Arg::new("error_format")
+                .long("error-format")
+                .global(true)
+                .value_parser(["text", "json"])
+                .default_value("text")
+                .help("Format of errors written to stderr"),
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help(
+                    "Print results, warnings and errors as JSON lines on \
+                     stdout, for scripts",
+                ),
+        )
+        .arg(
+            Arg::new("profile_perf")
+                .long("profile-perf")
+                .global(true)
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("")
+                .value_name("TRACE_FILE")
+                .help(
+                    "Print the time spent per phase (e.g. auth, list_page, \
+                     render) to stderr, and write a flamegraph-compatible \
+                     trace to TRACE_FILE if given",
+                ),
+        )
         .subcommand(request_subcommand()) // "-X/--request [GET,PUT]"
         .subcommand(query_subcommand()) // "-Q/--query [SELECT,DESCRIBE]"
         .subcommand(ls_subcommand()) // "ls [URI]"
         .subcommand(cp_subcommand()) // "cp" [SOURCE] [TARGET]
+        .subcommand(get_subcommand()) // "get" [URL] [DESTINATION]
+        .subcommand(put_subcommand()) // "put" [SOURCE] [TARGET]
+        .subcommand(rm_subcommand()) // "rm" [URI]
+        .subcommand(stat_subcommand()) // "stat" [URI]
+        .subcommand(diff_subcommand()) // "diff" [URI_A] [URI_B]
+        .subcommand(manifest_subcommand()) // "manifest" [ACTION] [URI]
+        .subcommand(grep_subcommand()) // "grep" [PATTERN] [URI]
+        .subcommand(mb_subcommand()) // "mb" [URI]
+        .subcommand(rb_subcommand()) // "rb" [URI]
+        .subcommand(presign_subcommand()) // "presign" [URI]
+        .subcommand(s3_subcommand()) // "s3" [ACTION]
+        .subcommand(access_subcommand()) // "access" [URI]
+        .subcommand(restore_subcommand()) // "restore" [URI]
+        .subcommand(watch_subcommand()) // "watch" [URI]
+        .subcommand(browse_subcommand()) // "browse" [URI]
+        .subcommand(env_subcommand()) // "env" -- [COMMAND]
+        .subcommand(telemetry_subcommand()) // "telemetry" [ACTION]
+        .subcommand(cache_subcommand()) // "cache" [ACTION]
+        .subcommand(configure_subcommand()) // "configure"
+        .subcommand(auth_subcommand()) // "auth" [ACTION]
+        .subcommand(completions_subcommand()) // "completions" [SHELL]
         .subcommand(apps_subcommand()) // "app"
-        .allow_external_subcommands(true);
+        .allow_external_subcommands(true)
+}
+
+pub async fn run_cli(args: Vec<String>) {
+    env_logger::init();
+    let app = cli_command();
+    // only these are counted by name, other names may be typed by the user
+    let builtin_subcommands: Vec<String> = app
+        .get_subcommands()
+        .map(|subcommand| subcommand.get_name().to_string())
+        .collect();
 
     let matches = app.try_get_matches();
 
     match matches {
         Ok(matches) => {
-            let mut config = create_initial_config(&matches);
+            if let Some(format) = matches.get_one::<String>("error_format") {
+                set_error_format(ErrorFormat::from_str(format));
+            }
+            if global_flag(&matches, "json") {
+                set_json_output();
+            }
+            let profile =
+                matches.get_one::<String>("profile_perf").map(|trace_file| {
+                    let trace_file =
+                        Some(trace_file.as_str()).filter(|f| !f.is_empty());
+                    PerfProfile::init(trace_file).unwrap_or_else(|e| e.exit())
+                });
+            let mut config = create_initial_config(&matches).await;
+            let throttle_config = ThrottleConfig::from_config(&config, None)
+                .unwrap_or_else(|e| CliError::from(e).exit());
+            Throttle::init_global(throttle_config);
+            match matches.subcommand_name() {
+                Some("telemetry") | None => {}
+                Some(name) if builtin_subcommands.iter().any(|b| b == name) => {
+                    record_usage(name).await
+                }
+                Some(_) => record_usage("app").await,
+            }
 
             match matches.subcommand() {
                 Some(("-X", matches)) => {
@@ -57,6 +224,90 @@ pub async fn run_cli(_args: Vec<String>) {
                     // copy
                     handle_cp(matches, &mut config).await;
                 }
+                Some(("get", matches)) => {
+                    // download
+                    handle_get(matches, &mut config).await;
+                }
+                Some(("put", matches)) => {
+                    // upload
+                    handle_put(matches, &mut config).await;
+                }
+                Some(("rm", matches)) => {
+                    // delete
+                    handle_rm(matches, &mut config).await;
+                }
+                Some(("stat", matches)) => {
+                    // object properties
+                    handle_stat(matches, &mut config).await;
+                }
+                Some(("diff", matches)) => {
+                    // compare two locations
+                    handle_diff(matches, &mut config).await;
+                }
+                Some(("manifest", matches)) => {
+                    // integrity manifests
+                    handle_manifest(matches, &mut config).await;
+                }
+                Some(("grep", matches)) => {
+                    // search object contents
+                    handle_grep(matches, &mut config).await;
+                }
+                Some(("mb", matches)) => {
+                    // make bucket
+                    handle_mb(matches, &mut config).await;
+                }
+                Some(("rb", matches)) => {
+                    // remove bucket
+                    handle_rb(matches, &mut config).await;
+                }
+                Some(("presign", matches)) => {
+                    // temporary url
+                    handle_presign(matches, &mut config).await;
+                }
+                Some(("s3", matches)) => {
+                    // s3 specific tools, e.g. request signing
+                    handle_s3(matches, &mut config).await;
+                }
+                Some(("access", matches)) => {
+                    // probe the permissions of the credentials
+                    handle_access(matches, &mut config).await;
+                }
+                Some(("restore", matches)) => {
+                    // restore an archived object
+                    handle_restore(matches, &mut config).await;
+                }
+                Some(("watch", matches)) => {
+                    // print changes to local files
+                    handle_watch(matches, &mut config).await;
+                }
+                Some(("browse", matches)) => {
+                    // interactive file browser
+                    handle_browse(matches, &mut config).await;
+                }
+                Some(("env", matches)) => {
+                    // run a command with injected credentials
+                    handle_env(matches, &mut config).await;
+                }
+                Some(("telemetry", matches)) => {
+                    // opt-in usage statistics
+                    handle_telemetry(matches, &mut config).await;
+                }
+                Some(("cache", matches)) => {
+                    // cached listings
+                    handle_cache(matches, &mut config).await;
+                }
+                Some(("configure", matches)) => {
+                    // create or change a profile
+                    handle_configure(matches, &mut config).await;
+                }
+                Some(("auth", matches)) => {
+                    // cached temporary credentials
+                    handle_auth(matches, &mut config).await;
+                }
+                Some(("completions", matches)) => {
+                    // print a shell completion script
+                    handle_completions(matches, &mut config).await;
+                }
                 Some(("apps", matches)) => {
                     // show list of apps
                     handle_apps(matches, &mut config).await;
@@ -71,6 +322,9 @@ pub async fn run_cli(_args: Vec<String>) {
                     unreachable!("arg_required_else_help(true) not defined")
                 }
             }
+            if let Some(profile) = profile {
+                profile.report();
+            }
         }
         Err(e) => {
             if e.kind() == clap::error::ErrorKind::DisplayHelp
@@ -79,27 +333,115 @@ pub async fn run_cli(_args: Vec<String>) {
                 // catches --help and --version, which are not errors
                 print!("{}", e);
             } else {
-                eprintln!("Error parsing command-line arguments: {}", e);
-                eprintln!(
-                    "Please ensure you have provided all required arguments \
-                     correctly."
-                );
-                eprintln!(
-                    "For more detailed help, try running '--help' or \
-                     '<subcommand> --help'."
-                );
-                std::process::exit(1);
+                // arguments could not be parsed, look for the flags directly
+                set_error_format(error_format_from_args(&args));
+                if args.iter().any(|arg| arg == "--json") {
+                    set_json_output();
+                }
+                let message = e.to_string();
+                CliError::usage(
+                    message.trim_start_matches("error: ").trim_end(),
+                )
+                .exit();
             }
         }
     }
 }
 
-fn create_initial_config(matches: &clap::ArgMatches) -> EnvironmentConfig {
-    let mut config_hashmap = HashMap::new();
+async fn create_initial_config(
+    matches: &clap::ArgMatches,
+) -> EnvironmentConfig {
+    // settings of the profile, overridden by those on the command line. a
+    // broken profile should not stop it from being fixed
+    let mut config_hashmap = match matches.subcommand_name() {
+        Some("configure") | Some("completions") | Some("auth")
+        | Some("cache") => HashMap::new(),
+        _ => profile_settings(matches)
+            .await
+            .unwrap_or_else(|e| CliError::from(e).exit()),
+    };
     if let Some(region) = matches.get_one::<String>("region") {
         config_hashmap.insert("region".to_string(), region.to_string());
     }
+    if matches.get_flag("request_payer") {
+        config_hashmap
+            .insert("S3_REQUEST_PAYER".to_string(), "requester".to_string());
+    }
+    if let Some(budget) = matches
+        .get_one::<String>("memory_budget")
+        .cloned()
+        .or_else(|| env::var(MEMORY_BUDGET_SETTING).ok())
+    {
+        config_hashmap.insert(MEMORY_BUDGET_SETTING.to_string(), budget);
+    }
+    for setting in THROTTLE_SETTINGS {
+        if let Ok(value) = env::var(setting) {
+            config_hashmap.entry(setting.to_string()).or_insert(value);
+        }
+    }
+    if let Ok(ttl) = env::var(LISTING_CACHE_TTL_SETTING) {
+        config_hashmap
+            .entry(LISTING_CACHE_TTL_SETTING.to_string())
+            .or_insert(ttl);
+    }
+    if let Ok(concurrency) = env::var(HEAD_CONCURRENCY_SETTING) {
+        config_hashmap
+            .entry(HEAD_CONCURRENCY_SETTING.to_string())
+            .or_insert(concurrency);
+    }
+    if let Ok(key) = env::var(MANIFEST_SIGNING_KEY_SETTING) {
+        config_hashmap
+            .entry(MANIFEST_SIGNING_KEY_SETTING.to_string())
+            .or_insert(key);
+    }
+    #[cfg(feature = "compression")]
+    if let Ok(decompress) = env::var(lumni::DECOMPRESS_SETTING) {
+        config_hashmap
+            .entry(lumni::DECOMPRESS_SETTING.to_string())
+            .or_insert(decompress);
+    }
+    if global_flag(matches, "no_cache") {
+        config_hashmap.remove(LISTING_CACHE_TTL_SETTING);
+    }
+    if let Some(limit) = matches.get_one::<String>("bandwidth_limit") {
+        config_hashmap
+            .insert(BANDWIDTH_LIMIT_SETTING.to_string(), limit.to_string());
+    }
 
     // Create a Config instance
     EnvironmentConfig::new(config_hashmap)
 }
+
+async fn profile_settings(
+    matches: &clap::ArgMatches,
+) -> Result<HashMap<String, String>, LumniError> {
+    // global args are only passed down, so look in the subcommand first
+    let profile = matches
+        .subcommand()
+        .and_then(|(_, sub_matches)| {
+            sub_matches.try_get_one::<String>("profile").ok().flatten()
+        })
+        .or_else(|| matches.get_one::<String>("profile"));
+    let (name, explicit) = selected_profile(profile);
+    ConfigFile::load()?.settings(&name, explicit).await
+}
+
+// global flags given after a subcommand are only set in its matches
+fn global_flag(matches: &clap::ArgMatches, name: &str) -> bool {
+    matches.try_get_one::<bool>(name).ok().flatten() == Some(&true)
+        || matches
+            .subcommand()
+            .is_some_and(|(_, sub_matches)| global_flag(sub_matches, name))
+}
+
+fn error_format_from_args(args: &[String]) -> ErrorFormat {
+    args.iter()
+        .enumerate()
+        .find_map(|(i, arg)| match arg.strip_prefix("--error-format") {
+            Some(value) if value.starts_with('=') => Some(&value[1..]),
+            Some("") => args.get(i + 1).map(String::as_str),
+            _ => None,
+        })
+        .map(ErrorFormat::from_str)
+        .unwrap_or(ErrorFormat::Text)
+}
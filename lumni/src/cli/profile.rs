@@ -0,0 +1,140 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::span;
+use tracing::subscriber::{set_global_default, Subscriber};
+use tracing_flame::{FlameLayer, FlushGuard};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Registry;
+
+use super::error::CliError;
+
+type Phases = Arc<Mutex<HashMap<&'static str, Phase>>>;
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Phase {
+    count: u64,
+    total: Duration,
+}
+
+// timings of the spans of a command, by the name of the span (e.g.
+// "list_page"), as collected for --profile-perf
+pub struct PerfProfile {
+    phases: Phases,
+    start: Instant,
+    flame: Option<FlushGuard<BufWriter<File>>>,
+}
+
+impl PerfProfile {
+    // collects the timings from here on, and writes a trace of folded
+    // stacks to trace_file if given, e.g. for inferno-flamegraph
+    pub fn init(trace_file: Option<&str>) -> Result<Self, CliError> {
+        let phases = Phases::default();
+        let (flame_layer, flame) = match trace_file {
+            Some(path) => {
+                let (layer, guard) =
+                    FlameLayer::with_file(path).map_err(|e| {
+                        CliError::general(format!(
+                            "Can not write trace to {}: {}",
+                            path, e
+                        ))
+                    })?;
+                (Some(layer), Some(guard))
+            }
+            None => (None, None),
+        };
+        let subscriber = Registry::default()
+            .with(PhaseLayer {
+                phases: Arc::clone(&phases),
+            })
+            .with(flame_layer);
+        set_global_default(subscriber)
+            .map_err(|e| CliError::general(e.to_string()))?;
+        Ok(PerfProfile {
+            phases,
+            start: Instant::now(),
+            flame,
+        })
+    }
+
+    // prints the breakdown to stderr, phases can be nested (e.g. render
+    // within table_build), so their times do not add up to the total
+    pub fn report(self) {
+        let total = self.start.elapsed();
+        if let Some(flame) = &self.flame {
+            if let Err(e) = flame.flush() {
+                eprintln!("Failed to write trace: {}", e);
+            }
+        }
+        let mut phases: Vec<(&str, Phase)> = self
+            .phases
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, phase)| (*name, *phase))
+            .collect();
+        phases.sort_by_key(|(_, phase)| Reverse(phase.total));
+
+        eprintln!(
+            "{:<16} {:>8} {:>12} {:>12}",
+            "Phase", "Count", "Total", "Mean"
+        );
+        for (name, phase) in phases {
+            let mean = phase.total / phase.count.max(1) as u32;
+            eprintln!(
+                "{:<16} {:>8} {:>12} {:>12}",
+                name,
+                phase.count,
+                format_duration(phase.total),
+                format_duration(mean)
+            );
+        }
+        eprintln!("{:<16} {:>8} {:>12}", "total", "", format_duration(total));
+    }
+}
+
+// adds the time from creation to close of each span to its phase
+struct PhaseLayer {
+    phases: Phases,
+}
+
+struct SpanStart(Instant);
+
+impl<S> Layer<S> for PhaseLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(
+        &self,
+        _attrs: &span::Attributes<'_>,
+        id: &span::Id,
+        ctx: Context<'_, S>,
+    ) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanStart(Instant::now()));
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let elapsed = match span.extensions().get::<SpanStart>() {
+            Some(SpanStart(start)) => start.elapsed(),
+            None => return,
+        };
+        let mut phases = self.phases.lock().unwrap();
+        let phase = phases.entry(span.name()).or_default();
+        phase.count += 1;
+        phase.total += elapsed;
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.1}ms", duration.as_secs_f64() * 1000.0)
+}
@@ -0,0 +1,37 @@
+use clap::{Arg, ArgAction, Command};
+
+pub use super::access_handler::handle_access;
+
+pub fn access_subcommand() -> Command {
+    Command::new("access")
+        .about(
+            "Probe which operations (list, read, write, delete) the current \
+             credentials allow under a bucket or prefix",
+        )
+        .arg(
+            Arg::new("uri")
+                .index(1)
+                .required(true)
+                .help("URI of the bucket or prefix, e.g. s3://bucket/prefix/"),
+        )
+        .arg(
+            Arg::new("no-write")
+                .long("no-write")
+                .action(ArgAction::SetTrue)
+                .help(
+                    "Only probe list and read. By default a small probe \
+                     object is written under the prefix and deleted again",
+                ),
+        )
+        .arg(
+            Arg::new("require")
+                .long("require")
+                .value_delimiter(',')
+                .value_parser(["list", "read", "write", "delete"])
+                .value_name("OPERATIONS")
+                .help(
+                    "Exit with an auth error unless these operations are \
+                     allowed, e.g. list,read,write",
+                ),
+        )
+}
@@ -0,0 +1,80 @@
+use lumni::{AccessReport, EnvironmentConfig, ObjectStoreHandler, ParsedUri};
+use serde_json::json;
+
+use crate::cli::error::{CliError, ExitCode};
+use crate::cli::json_output::{is_json_output, print_json, print_warning};
+
+pub async fn handle_access(
+    matches: &clap::ArgMatches,
+    config: &mut EnvironmentConfig,
+) {
+    let uri = matches.get_one::<String>("uri").unwrap();
+    let write = !matches.get_flag("no-write");
+    let required: Vec<&String> = matches
+        .get_many::<String>("require")
+        .unwrap_or_default()
+        .collect();
+    if !write && required.iter().any(|op| *op == "write" || *op == "delete") {
+        CliError::usage("--require write or delete needs the write probe")
+            .exit();
+    }
+
+    let uri = if uri.contains("://") {
+        uri.to_string()
+    } else {
+        format!("localfs://{}", uri)
+    };
+    let parsed_uri = ParsedUri::from_uri(&uri, true);
+    let report = ObjectStoreHandler::new(None)
+        .probe_access(&parsed_uri, config, write)
+        .await
+        .unwrap_or_else(|e| CliError::from(e).exit());
+
+    print_report(&report);
+    if let Some(key) = report.leftover() {
+        print_warning(&format!(
+            "probe object {} could not be deleted, remove it manually",
+            key
+        ));
+    }
+
+    let missing: Vec<&str> = report
+        .results()
+        .iter()
+        .filter(|(operation, status)| {
+            !status.is_allowed()
+                && required.iter().any(|op| *op == operation.as_str())
+        })
+        .map(|(operation, _)| operation.as_str())
+        .collect();
+    if !missing.is_empty() {
+        CliError::new(
+            ExitCode::AuthError,
+            format!("not allowed on {}: {}", uri, missing.join(", ")),
+        )
+        .exit();
+    }
+}
+
+fn print_report(report: &AccessReport) {
+    if is_json_output() {
+        for (operation, status) in report.results() {
+            print_json(&json!({
+                "operation": operation.as_str(),
+                "status": status.as_str(),
+                "reason": status.reason(),
+            }));
+        }
+        return;
+    }
+    println!("{:<10} {:<9} reason", "operation", "status");
+    for (operation, status) in report.results() {
+        let line = format!(
+            "{:<10} {:<9} {}",
+            operation.as_str(),
+            status.as_str(),
+            status.reason().unwrap_or_default()
+        );
+        println!("{}", line.trim_end());
+    }
+}
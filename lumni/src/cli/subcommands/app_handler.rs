@@ -3,6 +3,8 @@ use lumni::api::{find_builtin_app, get_app_handler, get_available_apps};
 use lumni::EnvironmentConfig;
 use regex::Regex;
 
+use crate::cli::error::{CliError, ExitCode};
+
 pub async fn handle_apps(
     _matches: &clap::ArgMatches,
     _config: &mut EnvironmentConfig,
@@ -55,15 +57,15 @@ pub async fn handle_application(
                 app_handler.invoke_main(app_spec, app_arguments).await;
             match app_run {
                 Ok(_) => {} // app ran successfully
-                Err(e) => {
-                    eprintln!("{}", e);
-                    std::process::exit(1);
-                }
+                Err(e) => CliError::from(e).exit(),
             }
         }
         None => {
-            eprintln!("app not found: {}", app);
-            std::process::exit(1);
+            CliError::new(
+                ExitCode::NotFound,
+                format!("app not found: {}", app),
+            )
+            .exit();
         }
     }
 }
@@ -0,0 +1,20 @@
+use clap::Command;
+
+pub use super::auth_handler::handle_auth;
+
+pub fn auth_subcommand() -> Command {
+    Command::new("auth")
+        .about(
+            "Show or remove the temporary credentials cached in \
+             ~/.lumni/cache, e.g. of STS, SSO or OAuth token requests",
+        )
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("status")
+                .about("List the cached credentials and when they expire"),
+        )
+        .subcommand(Command::new("clear").about(
+            "Remove the cached credentials, the next request \
+                 authenticates again",
+        ))
+}
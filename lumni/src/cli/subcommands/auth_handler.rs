@@ -0,0 +1,61 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use lumni::{EnvironmentConfig, TokenCache, TOKEN_CACHE_ENV};
+use serde_json::json;
+
+use crate::cli::error::CliError;
+use crate::cli::json_output::{
+    is_json_output, print_json, print_result, print_warning,
+};
+
+pub async fn handle_auth(
+    matches: &clap::ArgMatches,
+    _config: &mut EnvironmentConfig,
+) {
+    match matches.subcommand() {
+        Some(("status", _)) => {
+            let cache = TokenCache::open().unwrap_or_else(|e| {
+                CliError::from(e).exit();
+            });
+            let Some(cache) = cache else {
+                let message =
+                    format!("Token cache is off, see {}", TOKEN_CACHE_ENV);
+                match is_json_output() {
+                    true => print_warning(&message),
+                    false => println!("{}", message),
+                }
+                return;
+            };
+            if cache.tokens().is_empty() && !is_json_output() {
+                println!("No cached credentials");
+                return;
+            }
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs());
+            for (key, token) in cache.tokens() {
+                if is_json_output() {
+                    print_json(&json!({
+                        "key": key,
+                        "expiration": token.expiration,
+                    }));
+                    continue;
+                }
+                let status = match token.expiration.checked_sub(now) {
+                    Some(seconds) if seconds > 0 => {
+                        format!("expires in {}m", seconds / 60)
+                    }
+                    _ => "expired".to_string(),
+                };
+                println!("{:<56} {}", key, status);
+            }
+        }
+        Some(("clear", _)) => match TokenCache::clear() {
+            Ok(count) => print_result(json!({ "removed": count }), || {
+                format!("Removed {} cached credentials", count)
+            }),
+            Err(e) => CliError::from(e).exit(),
+        },
+        _ => unreachable!("subcommand_required(true) not defined"),
+    }
+}
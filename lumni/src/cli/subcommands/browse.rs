@@ -0,0 +1,25 @@
+use clap::{Arg, Command};
+
+pub use super::browse_handler::handle_browse;
+
+pub fn browse_subcommand() -> Command {
+    Command::new("browse")
+        .about("Browse an object store URI in an interactive file browser")
+        .after_help(
+            "Keys: up/down to move, enter/right to open a directory, \
+             left/backspace to go up, space to select, a to select all, d to \
+             download, x to delete, r to refresh, q to quit",
+        )
+        .arg(
+            Arg::new("uri")
+                .index(1)
+                .required(true)
+                .help("URI to start in, e.g. s3://bucket/prefix/ or ./data"),
+        )
+        .arg(
+            Arg::new("dest")
+                .long("dest")
+                .default_value(".")
+                .help("Local directory that objects are downloaded to"),
+        )
+}
@@ -0,0 +1,547 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen,
+    LeaveAlternateScreen,
+};
+use futures::StreamExt;
+#[cfg(feature = "compression")]
+use lumni::AutoDecoder;
+use lumni::{
+    ByteRange, EnvironmentConfig, LumniError, ObjectStoreHandler, ParsedUri,
+    Table, TableColumnValue,
+};
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{
+    Block, Borders, List, ListItem, ListState, Paragraph, Wrap,
+};
+use ratatui::{Frame, Terminal};
+
+use super::progress::human_bytes;
+use crate::cli::error::CliError;
+use crate::cli::json_output::is_json_output;
+
+// bytes of an object shown in the preview pane
+const PREVIEW_BYTES: u64 = 4096;
+const PAGE_SIZE: isize = 20;
+
+pub async fn handle_browse(
+    matches: &clap::ArgMatches,
+    config: &mut EnvironmentConfig,
+) {
+    let uri = matches.get_one::<String>("uri").unwrap();
+    let dest = matches.get_one::<String>("dest").unwrap();
+    if is_json_output() {
+        CliError::usage("browse is interactive, --json is not supported")
+            .exit();
+    }
+
+    let mut browser =
+        Browser::new(browse_uri(uri), PathBuf::from(dest), config.clone());
+    // the first listing is done before the terminal is taken over, so
+    // errors such as a missing bucket are reported as for ls
+    if let Err(err) = browser.load().await {
+        CliError::from(err).exit();
+    }
+    if let Err(err) = run(&mut browser).await {
+        CliError::general(format!("Terminal error: {}", err)).exit();
+    }
+}
+
+// uri of the directory to start in, a path without scheme is local
+fn browse_uri(uri: &str) -> String {
+    let uri = if uri.contains("://") {
+        uri.to_string()
+    } else {
+        let path = fs::canonicalize(uri).unwrap_or_else(|_| PathBuf::from(uri));
+        format!("localfs://{}", path.display())
+    };
+    if uri.ends_with('/') {
+        uri
+    } else {
+        format!("{}/", uri)
+    }
+}
+
+// e.g. "s3://bucket/a/b/" -> "s3://bucket/a/", None at the bucket
+fn parent_uri(uri: &str) -> Option<String> {
+    let (scheme, path) = uri.split_once("://")?;
+    let path = path.trim_end_matches('/');
+    let end = path.rfind('/')?;
+    Some(format!("{}://{}/", scheme, &path[..end]))
+}
+
+async fn run(browser: &mut Browser) -> io::Result<()> {
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+
+    let result = event_loop(&mut terminal, browser).await;
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    result
+}
+
+async fn event_loop<B: Backend>(
+    terminal: &mut Terminal<B>,
+    browser: &mut Browser,
+) -> io::Result<()> {
+    loop {
+        browser.load_preview().await;
+        terminal.draw(|frame| draw(frame, browser))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press && !browser.handle_key(key).await
+            {
+                return Ok(());
+            }
+        }
+    }
+}
+
+struct Entry {
+    name: String, // last part of the key, without trailing slash
+    uri: String,  // directories end with a slash
+    size: u64,
+    is_dir: bool,
+}
+
+struct Browser {
+    uri: String, // of the directory that is listed, ends with a slash
+    dest: PathBuf,
+    config: EnvironmentConfig,
+    handler: ObjectStoreHandler,
+    entries: Vec<Entry>,
+    list_state: ListState,
+    selected: BTreeSet<String>, // uris of the selected objects
+    previews: HashMap<String, String>,
+    confirm_delete: bool,
+    status: String,
+}
+
+impl Browser {
+    fn new(uri: String, dest: PathBuf, config: EnvironmentConfig) -> Self {
+        Browser {
+            uri,
+            dest,
+            config,
+            handler: ObjectStoreHandler::new(None),
+            entries: Vec::new(),
+            list_state: ListState::default(),
+            selected: BTreeSet::new(),
+            previews: HashMap::new(),
+            confirm_delete: false,
+            status: String::new(),
+        }
+    }
+
+    async fn load(&mut self) -> Result<(), LumniError> {
+        let table = self
+            .handler
+            .list_objects(
+                &ParsedUri::from_uri(&self.uri, true),
+                &self.config,
+                None,
+                false,
+                None,
+                &None,
+                None,
+            )
+            .await?;
+        let mut entries = entries_from_table(table.as_ref(), &self.uri);
+        entries
+            .sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then(a.name.cmp(&b.name)));
+        self.entries = entries;
+        self.selected.clear();
+        let cursor = self.list_state.selected().unwrap_or(0);
+        self.list_state.select(match self.entries.len() {
+            0 => None,
+            len => Some(cursor.min(len - 1)),
+        });
+        self.status = format!("{} entries", self.entries.len());
+        Ok(())
+    }
+
+    // lists another directory, the current one is kept if that fails
+    async fn change_dir(&mut self, uri: String) {
+        let previous = std::mem::replace(&mut self.uri, uri);
+        let cursor = self.list_state.selected();
+        self.list_state.select(Some(0));
+        if let Err(err) = self.load().await {
+            self.uri = previous;
+            self.list_state.select(cursor);
+            self.status = err.to_string();
+        }
+    }
+
+    fn current(&self) -> Option<&Entry> {
+        self.list_state
+            .selected()
+            .and_then(|index| self.entries.get(index))
+    }
+
+    fn move_cursor(&mut self, delta: isize) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let last = self.entries.len() as isize - 1;
+        let cursor = self.list_state.selected().unwrap_or(0) as isize;
+        self.list_state
+            .select(Some((cursor + delta).clamp(0, last) as usize));
+    }
+
+    // the selected objects, or else the object under the cursor.
+    // directories are not included
+    fn targets(&self) -> Vec<&Entry> {
+        if self.selected.is_empty() {
+            self.current()
+                .filter(|entry| !entry.is_dir)
+                .into_iter()
+                .collect()
+        } else {
+            self.entries
+                .iter()
+                .filter(|entry| self.selected.contains(&entry.uri))
+                .collect()
+        }
+    }
+
+    // returns false to quit
+    async fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if self.confirm_delete {
+            self.confirm_delete = false;
+            if key.code == KeyCode::Char('y') {
+                self.delete().await;
+            } else {
+                self.status = "Delete cancelled".to_string();
+            }
+            return true;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Up | KeyCode::Char('k') => self.move_cursor(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_cursor(1),
+            KeyCode::PageUp => self.move_cursor(-PAGE_SIZE),
+            KeyCode::PageDown => self.move_cursor(PAGE_SIZE),
+            KeyCode::Home => self.move_cursor(isize::MIN / 2),
+            KeyCode::End => self.move_cursor(isize::MAX / 2),
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+                if let Some(entry) = self.current().filter(|e| e.is_dir) {
+                    let uri = entry.uri.clone();
+                    self.change_dir(uri).await;
+                }
+            }
+            KeyCode::Left | KeyCode::Backspace | KeyCode::Char('h') => {
+                if let Some(uri) = parent_uri(&self.uri) {
+                    self.change_dir(uri).await;
+                }
+            }
+            KeyCode::Char(' ') => {
+                if let Some(entry) = self.current().filter(|e| !e.is_dir) {
+                    let uri = entry.uri.clone();
+                    if !self.selected.remove(&uri) {
+                        self.selected.insert(uri);
+                    }
+                }
+                self.move_cursor(1);
+            }
+            KeyCode::Char('a') => {
+                if self.selected.is_empty() {
+                    self.selected = self
+                        .entries
+                        .iter()
+                        .filter(|entry| !entry.is_dir)
+                        .map(|entry| entry.uri.clone())
+                        .collect();
+                } else {
+                    self.selected.clear();
+                }
+            }
+            KeyCode::Char('d') => self.download().await,
+            KeyCode::Char('x') | KeyCode::Delete => {
+                let count = self.targets().len();
+                if count == 0 {
+                    self.status = "No objects selected".to_string();
+                } else {
+                    self.confirm_delete = true;
+                    self.status = format!("Delete {} objects? (y/n)", count);
+                }
+            }
+            KeyCode::Char('r') => {
+                self.previews.clear();
+                if let Err(err) = self.load().await {
+                    self.status = err.to_string();
+                }
+            }
+            _ => {}
+        }
+        true
+    }
+
+    async fn download(&mut self) {
+        let targets: Vec<(String, String)> = self
+            .targets()
+            .iter()
+            .map(|entry| (entry.uri.clone(), entry.name.clone()))
+            .collect();
+        if targets.is_empty() {
+            self.status = "No objects selected".to_string();
+            return;
+        }
+        let mut downloaded = 0;
+        for (uri, name) in &targets {
+            let path = self.dest.join(name);
+            match self.download_object(uri, &path).await {
+                Ok(()) => downloaded += 1,
+                Err(err) => {
+                    self.status =
+                        format!("Failed to download {}: {}", uri, err);
+                    return;
+                }
+            }
+        }
+        self.status = format!(
+            "Downloaded {} objects to {}",
+            downloaded,
+            self.dest.display()
+        );
+    }
+
+    async fn download_object(
+        &self,
+        uri: &str,
+        path: &PathBuf,
+    ) -> Result<(), LumniError> {
+        let mut stream = self
+            .handler
+            .get_object_stream(
+                &ParsedUri::from_uri(uri, false),
+                &self.config,
+                None,
+            )
+            .await?;
+        let mut file = File::create(path)?;
+        while let Some(chunk) = stream.next().await {
+            file.write_all(&chunk?)?;
+        }
+        Ok(())
+    }
+
+    async fn delete(&mut self) {
+        let targets: Vec<String> = self
+            .targets()
+            .iter()
+            .map(|entry| entry.uri.clone())
+            .collect();
+        let mut deleted = 0;
+        for uri in &targets {
+            let result = self
+                .handler
+                .delete_objects(
+                    &ParsedUri::from_uri(uri, false),
+                    &self.config,
+                    false,
+                    None,
+                )
+                .await;
+            match result {
+                Ok(_) => deleted += 1,
+                Err(err) => {
+                    self.status = format!("Failed to delete {}: {}", uri, err);
+                    return;
+                }
+            }
+        }
+        self.previews.clear();
+        if let Err(err) = self.load().await {
+            self.status = err.to_string();
+            return;
+        }
+        self.status = format!("Deleted {} objects", deleted);
+    }
+
+    // the first bytes of the object under the cursor, fetched once
+    async fn load_preview(&mut self) {
+        let uri = match self.current() {
+            Some(entry) if !entry.is_dir => entry.uri.clone(),
+            _ => return,
+        };
+        if self.previews.contains_key(&uri) {
+            return;
+        }
+        let preview = match self.fetch_preview(&uri).await {
+            Ok(data) if data.contains(&0) => {
+                format!("(binary, {} bytes shown)", data.len())
+            }
+            Ok(data) => String::from_utf8_lossy(&data).to_string(),
+            Err(err) => format!("(no preview: {})", err),
+        };
+        self.previews.insert(uri, preview);
+    }
+
+    async fn fetch_preview(&self, uri: &str) -> Result<Vec<u8>, LumniError> {
+        let range = ByteRange::new(0, Some(PREVIEW_BYTES - 1))?;
+        let mut stream = self
+            .handler
+            .get_object_stream(
+                &ParsedUri::from_uri(uri, false),
+                &self.config,
+                Some(range),
+            )
+            .await?;
+        // compressed objects are shown decompressed, as far as the first
+        // PREVIEW_BYTES of them go
+        #[cfg(feature = "compression")]
+        let mut decoder =
+            AutoDecoder::enabled(&self.config).then(AutoDecoder::new);
+        let mut data = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            #[cfg(feature = "compression")]
+            if let Some(decoder) = decoder.as_mut() {
+                data.extend_from_slice(&decoder.write(&chunk)?);
+            } else {
+                data.extend_from_slice(&chunk);
+            }
+            #[cfg(not(feature = "compression"))]
+            data.extend_from_slice(&chunk);
+            if data.len() as u64 >= PREVIEW_BYTES {
+                break;
+            }
+        }
+        // objects shorter than what is needed to detect a format
+        #[cfg(feature = "compression")]
+        if let Some(AutoDecoder::Detect(head)) = decoder {
+            data.extend(head);
+        }
+        data.truncate(PREVIEW_BYTES as usize);
+        Ok(data)
+    }
+}
+
+// directories are listed with a trailing slash (e.g. S3 prefixes) or
+// without a modified time, as ls shows them as PRE
+fn entries_from_table(table: &dyn Table, dir_uri: &str) -> Vec<Entry> {
+    let column = |name: &str| {
+        table
+            .columns()
+            .iter()
+            .find(|(column_name, _)| column_name == name)
+            .map(|(_, column)| column)
+    };
+    let (Some(names), sizes, modified) =
+        (column("name"), column("size"), column("modified"))
+    else {
+        return Vec::new();
+    };
+    (0..table.len())
+        .filter_map(|index| {
+            let key = match names.get_value(index)? {
+                TableColumnValue::StringColumn(key) => key,
+                TableColumnValue::OptionalStringColumn(key) => key?,
+                _ => return None,
+            };
+            let size = match sizes.and_then(|c| c.get_value(index)) {
+                Some(TableColumnValue::Uint64Column(size)) => size,
+                Some(TableColumnValue::OptionalUint64Column(size)) => {
+                    size.unwrap_or(0)
+                }
+                _ => 0,
+            };
+            let has_modified = matches!(
+                modified.and_then(|c| c.get_value(index)),
+                Some(TableColumnValue::Uint64Column(_))
+                    | Some(TableColumnValue::OptionalUint64Column(Some(_)))
+            );
+            let is_dir = key.ends_with('/') || !has_modified;
+            let name = key.trim_end_matches('/').rsplit('/').next()?;
+            if name.is_empty() {
+                return None;
+            }
+            let uri =
+                format!("{}{}{}", dir_uri, name, if is_dir { "/" } else { "" });
+            Some(Entry {
+                name: name.to_string(),
+                uri,
+                size,
+                is_dir,
+            })
+        })
+        // e.g. the marker object of an S3 "directory" itself
+        .filter(|entry| entry.uri != dir_uri)
+        .collect()
+}
+
+fn draw(frame: &mut Frame, browser: &mut Browser) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(2)])
+        .split(frame.area());
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(rows[0]);
+
+    let items: Vec<ListItem> = browser
+        .entries
+        .iter()
+        .map(|entry| {
+            let mark = if browser.selected.contains(&entry.uri) {
+                "[x]"
+            } else {
+                "[ ]"
+            };
+            let line = if entry.is_dir {
+                format!("{} {:>10} {}/", mark, "", entry.name)
+            } else {
+                format!(
+                    "{} {:>10} {}",
+                    mark,
+                    human_bytes(entry.size),
+                    entry.name
+                )
+            };
+            ListItem::new(line)
+        })
+        .collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(browser.uri.as_str()),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(list, panes[0], &mut browser.list_state);
+
+    let preview = browser
+        .current()
+        .map(|entry| match entry.is_dir {
+            true => format!("{}/", entry.name),
+            false => browser
+                .previews
+                .get(&entry.uri)
+                .cloned()
+                .unwrap_or_default(),
+        })
+        .unwrap_or_default();
+    let preview = Paragraph::new(preview)
+        .block(Block::default().borders(Borders::ALL).title("Preview"))
+        .wrap(Wrap { trim: false });
+    frame.render_widget(preview, panes[1]);
+
+    let status = format!(
+        "{} | {} selected\nspace select, a all, d download, x delete, r \
+         refresh, q quit",
+        browser.status,
+        browser.selected.len()
+    );
+    frame.render_widget(Paragraph::new(status), rows[1]);
+}
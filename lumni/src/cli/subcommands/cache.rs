@@ -0,0 +1,16 @@
+use clap::Command;
+
+pub use super::cache_handler::handle_cache;
+
+pub fn cache_subcommand() -> Command {
+    Command::new("cache")
+        .about(
+            "Manage the listings cached in ~/.lumni/cache when \
+             LISTING_CACHE_TTL is set, e.g. to 5m",
+        )
+        .subcommand_required(true)
+        .subcommand(Command::new("clear").about(
+            "Remove the cached listings, the next listing goes to the object \
+             store",
+        ))
+}
@@ -0,0 +1,20 @@
+use lumni::{EnvironmentConfig, ListingCache};
+use serde_json::json;
+
+use crate::cli::error::CliError;
+use crate::cli::json_output::print_result;
+
+pub async fn handle_cache(
+    matches: &clap::ArgMatches,
+    _config: &mut EnvironmentConfig,
+) {
+    match matches.subcommand() {
+        Some(("clear", _)) => match ListingCache::clear() {
+            Ok(count) => print_result(json!({ "removed": count }), || {
+                format!("Removed {} cached listings", count)
+            }),
+            Err(e) => CliError::from(e).exit(),
+        },
+        _ => unreachable!("subcommand_required(true) not defined"),
+    }
+}
@@ -0,0 +1,18 @@
+use clap::{Arg, Command};
+
+pub use super::completions_handler::handle_completions;
+
+pub fn completions_subcommand() -> Command {
+    Command::new("completions")
+        .about(
+            "Print a shell completion script, e.g. `source <(lumni \
+             completions bash)`",
+        )
+        .arg(
+            Arg::new("shell")
+                .index(1)
+                .required(true)
+                .value_parser(["bash", "zsh", "fish"])
+                .help("Shell to complete for"),
+        )
+}
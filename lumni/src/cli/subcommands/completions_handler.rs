@@ -0,0 +1,67 @@
+use clap_complete::{generate, Shell};
+use lumni::EnvironmentConfig;
+
+use crate::cli::parser::cli_command;
+
+const BIN_NAME: &str = "lumni";
+
+// offered next to the files when a URI is completed
+const URI_SCHEMES: &str =
+    "s3:// gs:// az:// abfss:// sftp:// hdfs:// localfs:// http:// https://";
+
+// subcommands that take a URI
+const URI_SUBCOMMANDS: &str =
+    "ls cp get put rm stat diff grep mb rb presign access restore watch browse \
+     env";
+
+pub async fn handle_completions(
+    matches: &clap::ArgMatches,
+    _config: &mut EnvironmentConfig,
+) {
+    let shell = matches.get_one::<String>("shell").unwrap();
+    let (shell, uri_completion) = match shell.as_str() {
+        "bash" => (Shell::Bash, bash_uri_completion()),
+        "zsh" => (Shell::Zsh, zsh_uri_completion()),
+        _ => (Shell::Fish, fish_uri_completion()),
+    };
+    let mut script = Vec::new();
+    generate(shell, &mut cli_command(), BIN_NAME, &mut script);
+    print!("{}", String::from_utf8_lossy(&script));
+    println!("{}", uri_completion);
+}
+
+// the generated completions know the subcommands and options, these wrap
+// them to add the URI schemes
+fn bash_uri_completion() -> String {
+    format!(
+        r#"
+_lumni_uris() {{
+    _lumni "$@"
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    if [[ "$cur" != -* ]]; then
+        COMPREPLY+=( $(compgen -W "{}" -- "$cur") )
+    fi
+}}
+complete -F _lumni_uris -o nospace -o bashdefault -o default lumni"#,
+        URI_SCHEMES
+    )
+}
+
+fn zsh_uri_completion() -> String {
+    format!(
+        r#"
+_lumni_uris() {{
+    _lumni "$@"
+    [[ "$PREFIX" == -* ]] || compadd -S '' -- {}
+}}
+compdef _lumni_uris lumni"#,
+        URI_SCHEMES
+    )
+}
+
+fn fish_uri_completion() -> String {
+    format!(
+        r#"complete -c lumni -n "__fish_seen_subcommand_from {}" -a "{}""#,
+        URI_SUBCOMMANDS, URI_SCHEMES
+    )
+}
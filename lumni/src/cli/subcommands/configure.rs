@@ -0,0 +1,34 @@
+use clap::{Arg, ArgAction, Command};
+
+pub use super::configure_handler::handle_configure;
+
+pub fn configure_subcommand() -> Command {
+    Command::new("configure")
+        .about(
+            "Create or change a profile in ~/.lumni/config, named by \
+             --profile (default: \"default\"). Asks for each setting when \
+             none are given",
+        )
+        .arg(
+            Arg::new("endpoint")
+                .long("endpoint")
+                .help("Endpoint of an S3-compatible store, empty to remove"),
+        )
+        .arg(
+            Arg::new("region")
+                .long("region")
+                .help("Region of the store, empty to remove"),
+        )
+        .arg(Arg::new("credentials").long("credentials").help(
+            "Where the credentials are kept: aws-profile:NAME for a profile \
+             in the AWS config files, or gcs-key:PATH for a service account \
+             key. Empty to remove",
+        ))
+        .arg(
+            Arg::new("list")
+                .long("list")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["endpoint", "region", "credentials"])
+                .help("Show the profiles"),
+        )
+}
@@ -0,0 +1,83 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use lumni::EnvironmentConfig;
+use serde_json::json;
+
+use crate::cli::config_file::{selected_profile, ConfigFile, PROFILE_KEYS};
+use crate::cli::error::CliError;
+use crate::cli::json_output::{is_json_output, print_json, print_result};
+
+pub async fn handle_configure(
+    matches: &clap::ArgMatches,
+    _config: &mut EnvironmentConfig,
+) {
+    let mut config_file =
+        ConfigFile::load().unwrap_or_else(|e| CliError::from(e).exit());
+    if matches.get_flag("list") {
+        if is_json_output() {
+            for (name, settings) in config_file.profiles() {
+                print_json(&json!({ "profile": name, "settings": settings }));
+            }
+        } else {
+            print!("{}", config_file);
+        }
+        return;
+    }
+    let (profile, _) = selected_profile(matches.get_one::<String>("profile"));
+
+    let given: Vec<(&str, &String)> = PROFILE_KEYS
+        .iter()
+        .filter_map(|key| matches.get_one::<String>(key).map(|v| (*key, v)))
+        .collect();
+    if given.is_empty() {
+        // ask for each setting, the current value is kept on enter
+        for key in PROFILE_KEYS {
+            let current = config_file
+                .profile(&profile)
+                .and_then(|settings| settings.get(key))
+                .cloned();
+            let answer = ask(key, current.as_deref())
+                .unwrap_or_else(|e| e.exit())
+                .or(current)
+                .unwrap_or_default();
+            config_file.set(&profile, key, &answer);
+        }
+    } else {
+        for (key, value) in given {
+            config_file.set(&profile, key, value);
+        }
+    }
+    match config_file.save() {
+        Ok(path) => print_result(
+            json!({ "saved": profile, "path": path.display().to_string() }),
+            || format!("Saved profile \"{}\" to {}", profile, path.display()),
+        ),
+        Err(e) => CliError::from(e).exit(),
+    }
+}
+
+// None if the answer is empty, "-" clears the value
+fn ask(key: &str, current: Option<&str>) -> Result<Option<String>, CliError> {
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        return Err(CliError::usage(
+            "No settings given, use e.g. --region without a terminal",
+        ));
+    }
+    match current {
+        Some(current) => eprint!("{} [{}, - to remove]: ", key, current),
+        None => eprint!("{}: ", key),
+    }
+    io::stderr().flush().ok();
+
+    let mut answer = String::new();
+    stdin
+        .lock()
+        .read_line(&mut answer)
+        .map_err(|e| CliError::general(e.to_string()))?;
+    Ok(match answer.trim() {
+        "" => None,
+        "-" => Some(String::new()),
+        answer => Some(answer.to_string()),
+    })
+}
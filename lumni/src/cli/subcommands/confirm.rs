@@ -0,0 +1,90 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use clap::{Arg, ArgAction};
+
+use crate::cli::error::CliError;
+
+// shared by destructive commands, so prompts behave the same everywhere
+pub fn confirm_args() -> [Arg; 3] {
+    [
+        Arg::new("interactive")
+            .long("interactive")
+            .short('i')
+            .action(ArgAction::SetTrue)
+            .conflicts_with_all(["yes", "force"])
+            .help("Prompt before every item"),
+        Arg::new("yes")
+            .long("yes")
+            .short('y')
+            .action(ArgAction::SetTrue)
+            .help("Answer yes to all prompts"),
+        Arg::new("force")
+            .long("force")
+            .short('f')
+            .action(ArgAction::SetTrue)
+            .help("Never prompt and ignore items that do not exist"),
+    ]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmMode {
+    Default,     // prompt once before a bulk operation
+    Interactive, // prompt before every item
+    Yes,         // no prompts
+    Force,       // no prompts, missing items are not an error
+}
+
+impl ConfirmMode {
+    pub fn from_matches(matches: &clap::ArgMatches) -> Self {
+        if matches.get_flag("force") {
+            ConfirmMode::Force
+        } else if matches.get_flag("yes") {
+            ConfirmMode::Yes
+        } else if matches.get_flag("interactive") {
+            ConfirmMode::Interactive
+        } else {
+            ConfirmMode::Default
+        }
+    }
+
+    pub fn is_force(self) -> bool {
+        self == ConfirmMode::Force
+    }
+
+    // asks once before an operation that affects many items
+    pub fn confirm_bulk(self, question: &str) -> Result<bool, CliError> {
+        match self {
+            ConfirmMode::Default => prompt(question),
+            // items are confirmed one by one instead
+            ConfirmMode::Interactive => Ok(true),
+            ConfirmMode::Yes | ConfirmMode::Force => Ok(true),
+        }
+    }
+
+    pub fn confirm_item(self, question: &str) -> Result<bool, CliError> {
+        match self {
+            ConfirmMode::Interactive => prompt(question),
+            _ => Ok(true),
+        }
+    }
+}
+
+fn prompt(question: &str) -> Result<bool, CliError> {
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        // never assume an answer when no one can give it
+        return Err(CliError::usage(format!(
+            "{} requires confirmation, use --yes to run without a terminal",
+            question.trim_end_matches('?')
+        )));
+    }
+    eprint!("{} [y/N] ", question);
+    io::stderr().flush().ok();
+
+    let mut answer = String::new();
+    stdin
+        .lock()
+        .read_line(&mut answer)
+        .map_err(|e| CliError::general(e.to_string()))?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
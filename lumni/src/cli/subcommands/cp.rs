@@ -1,6 +1,7 @@
 use clap::{Arg, Command};
 
 pub use super::cp_handler::handle_cp;
+use super::plan::dry_run_args;
 
 pub fn cp_subcommand() -> Command {
     Command::new("cp")
@@ -17,4 +18,5 @@ pub fn cp_subcommand() -> Command {
                 .required(true)
                 .help("Target URI to copy objects to"),
         )
+        .args(dry_run_args())
 }
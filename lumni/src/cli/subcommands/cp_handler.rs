@@ -1,11 +1,59 @@
-use lumni::EnvironmentConfig;
+use lumni::{EnvironmentConfig, ObjectStoreHandler, ParsedUri};
+
+use super::plan::{is_dry_run, print_plan};
+use crate::cli::error::CliError;
+use crate::cli::json_output::{is_json_output, print_warning};
 
 pub async fn handle_cp(
     matches: &clap::ArgMatches,
-    _config: &mut EnvironmentConfig,
+    config: &mut EnvironmentConfig,
 ) {
     let source = matches.get_one::<String>("source").unwrap();
     let target = matches.get_one::<String>("target").unwrap();
+
+    if is_dry_run(matches) {
+        plan_cp(matches, config, source, target).await;
+        return;
+    }
+
+    if is_json_output() {
+        print_warning(&format!(
+            "Copying from {} to {} is not yet implemented",
+            source, target
+        ));
+        return;
+    }
     println!("Not yet implemented");
     println!("Copying from {} to {}", source, target);
 }
+
+// every object the copy would write, with its source and size
+async fn plan_cp(
+    matches: &clap::ArgMatches,
+    config: &EnvironmentConfig,
+    source: &str,
+    target: &str,
+) {
+    // uri should start with a scheme, if not add default
+    let with_scheme = |uri: &str| {
+        if uri.contains("://") {
+            uri.to_string()
+        } else {
+            format!("localfs://{}", uri)
+        }
+    };
+    let source = ParsedUri::from_uri(&with_scheme(source), false);
+    // a trailing slash is kept, it copies an object into the target
+    let target =
+        ParsedUri::from_uri(&with_scheme(target), target.ends_with('/'));
+
+    let handler = ObjectStoreHandler::new(None);
+    match handler.plan_copy(&source, &target, config).await {
+        Ok(planned) => {
+            if let Err(err) = print_plan(matches, planned) {
+                CliError::general(err).exit();
+            }
+        }
+        Err(err) => CliError::from(err).exit(),
+    }
+}
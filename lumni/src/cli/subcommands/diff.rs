@@ -0,0 +1,35 @@
+use clap::{Arg, Command};
+
+pub use super::diff_handler::handle_diff;
+use super::output::output_args;
+
+pub fn diff_subcommand() -> Command {
+    Command::new("diff")
+        .about(
+            "Compare two locations and list objects that were added, removed \
+             or modified",
+        )
+        .arg(
+            Arg::new("uri_a")
+                .index(1)
+                .required(true)
+                .help("URI to compare from, e.g. s3://bucket/prefix/"),
+        )
+        .arg(
+            Arg::new("uri_b")
+                .index(2)
+                .required(true)
+                .help("URI to compare to, e.g. ./local/dir/"),
+        )
+        .arg(
+            Arg::new("strategy")
+                .long("strategy")
+                .value_parser(["size", "mtime", "etag"])
+                .default_value("mtime")
+                .help(
+                    "How objects on both sides are compared: by size, by \
+                     size and modification time, or by checksum",
+                ),
+        )
+        .args(output_args())
+}
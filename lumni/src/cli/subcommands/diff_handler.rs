@@ -0,0 +1,43 @@
+use std::sync::Arc;
+
+use lumni::{DiffStrategy, EnvironmentConfig, ObjectStoreHandler, ParsedUri};
+
+use super::output::{ExportCallback, OutputFormat};
+use crate::cli::error::CliError;
+
+pub async fn handle_diff(
+    matches: &clap::ArgMatches,
+    config: &mut EnvironmentConfig,
+) {
+    let uri_a = parse_uri(matches.get_one::<String>("uri_a").unwrap());
+    let uri_b = parse_uri(matches.get_one::<String>("uri_b").unwrap());
+    let strategy = matches.get_one::<String>("strategy").unwrap();
+    let strategy = DiffStrategy::from_name(strategy)
+        .unwrap_or_else(|err| CliError::usage(err.to_string()).exit());
+    let output_format =
+        OutputFormat::from_matches(matches).unwrap_or_else(|e| e.exit());
+
+    let handler = ObjectStoreHandler::new(None);
+    if let Err(err) = handler
+        .diff(
+            &uri_a,
+            &uri_b,
+            config,
+            strategy,
+            Some(Arc::new(ExportCallback::new(output_format))),
+        )
+        .await
+    {
+        CliError::from(err).exit();
+    }
+}
+
+fn parse_uri(uri: &str) -> ParsedUri {
+    // uri should start with a scheme, if not add default
+    let uri = if uri.contains("://") {
+        uri.to_string()
+    } else {
+        format!("localfs://{}", uri)
+    };
+    ParsedUri::from_uri(&uri, true)
+}
@@ -0,0 +1,20 @@
+use clap::{Arg, Command};
+
+pub use super::env_handler::handle_env;
+
+pub fn env_subcommand() -> Command {
+    Command::new("env")
+        .about(
+            "Run a command with the credentials of a profile, e.g. lumni env \
+             --profile dev -- aws s3 ls",
+        )
+        .arg(
+            Arg::new("command")
+                .index(1)
+                .required(true)
+                .num_args(1..)
+                .last(true)
+                .allow_hyphen_values(true)
+                .help("Command to run, given after --"),
+        )
+}
@@ -0,0 +1,43 @@
+use std::process::Command;
+
+use lumni::{EnvironmentConfig, ObjectStoreHandler};
+
+use crate::cli::error::CliError;
+
+// the profile (--profile) is already applied to the config, its
+// credentials are resolved as for any other command
+pub async fn handle_env(
+    matches: &clap::ArgMatches,
+    config: &mut EnvironmentConfig,
+) {
+    let command: Vec<&String> =
+        matches.get_many::<String>("command").unwrap().collect();
+
+    let handler = ObjectStoreHandler::new(None);
+    let variables = match handler.credential_variables(config).await {
+        Ok(variables) if variables.is_empty() => {
+            CliError::general("No credentials found, see lumni configure")
+                .exit()
+        }
+        Ok(variables) => variables,
+        Err(err) => CliError::from(err).exit(),
+    };
+
+    log::debug!(
+        "Injecting {:?} into {}",
+        variables.keys().collect::<Vec<_>>(),
+        command[0]
+    );
+    let status = Command::new(command[0])
+        .args(&command[1..])
+        .envs(&variables)
+        .status();
+
+    match status {
+        // a child killed by a signal has no exit code
+        Ok(status) => std::process::exit(status.code().unwrap_or(1)),
+        Err(err) => CliError::general(err.to_string())
+            .context(command[0])
+            .exit(),
+    }
+}
@@ -0,0 +1,69 @@
+use clap::{Arg, ArgAction, Command};
+
+pub use super::get_handler::handle_get;
+
+pub fn get_subcommand() -> Command {
+    Command::new("get")
+        .about(
+            "Download a file over HTTP, or all objects under a prefix with \
+             --recursive",
+        )
+        .arg(
+            Arg::new("url")
+                .index(1)
+                .required(true)
+                .help("HTTP(S) URL, or object store URI with --recursive"),
+        )
+        .arg(Arg::new("destination").index(2).help(
+            "Local file or object store URI to write to, defaults to the \
+                     URL filename. With --recursive a directory, defaults to \
+                     the current one",
+        ))
+        .arg(
+            Arg::new("recursive")
+                .long("recursive")
+                .short('r')
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["sha256", "checksum_file", "no_resume"])
+                .help("Download all objects under the URI to a directory"),
+        )
+        .arg(
+            Arg::new("resume")
+                .long("resume")
+                .action(ArgAction::SetTrue)
+                .requires("recursive")
+                .help("Continue an interrupted recursive download"),
+        )
+        .arg(
+            Arg::new("concurrency")
+                .long("concurrency")
+                .value_parser(clap::value_parser!(usize))
+                .default_value("8")
+                .requires("recursive")
+                .help("Number of objects downloaded at the same time"),
+        )
+        .arg(
+            Arg::new("sha256")
+                .long("sha256")
+                .help("Expected SHA-256 checksum (hex) of the file"),
+        )
+        .arg(
+            Arg::new("checksum_file")
+                .long("checksum-file")
+                .action(ArgAction::SetTrue)
+                .help("Verify against the checksum in <URL>.sha256"),
+        )
+        .arg(
+            Arg::new("no_resume")
+                .long("no-resume")
+                .action(ArgAction::SetTrue)
+                .help("Always download the full file"),
+        )
+        .arg(
+            Arg::new("quiet")
+                .long("quiet")
+                .short('q')
+                .action(ArgAction::SetTrue)
+                .help("Do not show download progress"),
+        )
+}
@@ -0,0 +1,390 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use lumni::{
+    Downloader, EnvironmentConfig, HttpClient, ObjectStoreHandler, ParsedUri,
+    ProgressTracker, UploadOptions, UriScheme,
+};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+
+use super::progress::ProgressBar;
+use crate::cli::error::CliError;
+use crate::cli::json_output::{is_json_output, print_json};
+
+// number of chunks to buffer between the http client and the file writer
+const CHANNEL_QUEUE_SIZE: usize = 32;
+
+pub async fn handle_get(
+    matches: &clap::ArgMatches,
+    config: &mut EnvironmentConfig,
+) {
+    let url = matches.get_one::<String>("url").unwrap();
+    if matches.get_flag("recursive") {
+        return handle_get_recursive(matches, url, config).await;
+    }
+    let parsed_uri = ParsedUri::from_uri(url, false);
+    if !matches!(parsed_uri.scheme, UriScheme::Http | UriScheme::Https)
+        || !url.contains("://")
+    {
+        CliError::usage("only http:// and https:// URLs can be downloaded")
+            .exit();
+    }
+
+    let destination = match matches.get_one::<String>("destination") {
+        Some(destination) => destination.to_string(),
+        None => filename_from_url(url),
+    };
+    // an object store destination is downloaded to a temporary file, which
+    // is uploaded once the checksum is verified
+    let (destination, target) = match destination.contains("://") {
+        true => (temporary_file(url), Some(destination)),
+        false => (destination, None),
+    };
+
+    let download = Download {
+        client: HttpClient::new(),
+        url: url.to_string(),
+        destination,
+        resume: target.is_none() && !matches.get_flag("no_resume"),
+        quiet: matches.get_flag("quiet"),
+    };
+
+    let expected_checksum = match matches.get_one::<String>("sha256") {
+        Some(checksum) => Some(checksum.to_lowercase()),
+        None if matches.get_flag("checksum_file") => {
+            match download.fetch_checksum_file().await {
+                Ok(checksum) => Some(checksum),
+                Err(err) => err.exit(),
+            }
+        }
+        None => None,
+    };
+
+    let result = download
+        .run()
+        .await
+        .and_then(|checksum| download.verify(checksum, &expected_checksum));
+    let result = match (result, &target) {
+        (Ok(checksum), Some(target)) => {
+            let uploaded =
+                upload(&download.destination, target, config, download.quiet)
+                    .await;
+            uploaded.map(|_| checksum)
+        }
+        (result, _) => result,
+    };
+    if target.is_some() {
+        fs::remove_file(&download.destination).ok();
+    }
+    let checksum = match result {
+        Ok(checksum) => checksum,
+        Err(err) => err.exit(),
+    };
+
+    if is_json_output() {
+        print_json(&json!({
+            "downloaded": target.as_ref().unwrap_or(&download.destination),
+            "sha256": checksum,
+            "verified": expected_checksum.is_some(),
+        }));
+    }
+}
+
+// uploads a downloaded file the way put does, e.g. in parts to S3
+async fn upload(
+    path: &str,
+    target: &str,
+    config: &EnvironmentConfig,
+    quiet: bool,
+) -> Result<u64, CliError> {
+    let mut file = File::open(path)
+        .map_err(|e| CliError::general(format!("{}: {}", path, e)))?;
+    let size = file.metadata().ok().map(|m| m.len());
+    let tracker = Arc::new(
+        ProgressTracker::new("upload", Arc::new(ProgressBar::new("Uploaded")))
+            .set_total_bytes(size),
+    );
+    let callback = (!quiet).then(|| tracker.binary_callback());
+    let size = ObjectStoreHandler::new(None)
+        .put_object(
+            &ParsedUri::from_uri(target, false),
+            config,
+            &mut file,
+            &UploadOptions::new(),
+            callback,
+        )
+        .await
+        .map_err(CliError::from)?;
+    if !quiet {
+        tracker.finish();
+        if !is_json_output() {
+            eprintln!("Uploaded {} bytes to {}", size, target);
+        }
+    }
+    Ok(size)
+}
+
+// in the temp directory, unique per process
+fn temporary_file(url: &str) -> String {
+    std::env::temp_dir()
+        .join(format!(
+            ".lumni-get-{}-{}",
+            std::process::id(),
+            filename_from_url(url)
+        ))
+        .to_string_lossy()
+        .to_string()
+}
+
+async fn handle_get_recursive(
+    matches: &clap::ArgMatches,
+    uri: &str,
+    config: &EnvironmentConfig,
+) {
+    let uri = if uri.contains("://") {
+        uri.to_string()
+    } else {
+        format!("localfs://{}", uri)
+    };
+    let source = ParsedUri::from_uri(&uri, true);
+    let destination = matches
+        .get_one::<String>("destination")
+        .map_or(".", |destination| destination.as_str());
+    if destination.contains("://") {
+        CliError::usage("recursive downloads go to a local directory").exit();
+    }
+
+    let mut downloader = Downloader::new()
+        .set_resume(matches.get_flag("resume"))
+        .set_concurrency(*matches.get_one::<usize>("concurrency").unwrap());
+    if !matches.get_flag("quiet") {
+        downloader =
+            downloader.set_progress(Arc::new(ProgressBar::new("Downloaded")));
+    }
+    let result = match downloader
+        .download(&source, Path::new(destination), config)
+        .await
+    {
+        Ok(result) => result,
+        Err(err) => CliError::from(err).exit(),
+    };
+
+    if is_json_output() {
+        print_json(&json!({
+            "downloaded": result.downloaded(),
+            "resumed": result.resumed(),
+            "skipped": result.skipped(),
+        }));
+    } else if !matches.get_flag("quiet") {
+        eprintln!(
+            "{} downloaded, {} resumed, {} already complete",
+            result.downloaded().len(),
+            result.resumed().len(),
+            result.skipped().len()
+        );
+    }
+    if !result.failed().is_empty() {
+        for (key, err) in result.failed() {
+            if is_json_output() {
+                print_json(&json!({ "failed": key, "reason": err }));
+            } else {
+                eprintln!("Failed to download {}: {}", key, err);
+            }
+        }
+        CliError::general(format!(
+            "{} objects failed, retry with --resume",
+            result.failed().len()
+        ))
+        .exit();
+    }
+}
+
+struct Download {
+    client: HttpClient,
+    url: String,
+    destination: String,
+    resume: bool,
+    quiet: bool,
+}
+
+impl Download {
+    // downloads to the destination and returns the sha256 hex digest
+    async fn run(&self) -> Result<String, CliError> {
+        let response = self
+            .client
+            .head(&self.url, None)
+            .await
+            .map_err(CliError::from)?;
+        let total_size = response
+            .headers()
+            .get("content-length")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok());
+        let accepts_ranges = response
+            .headers()
+            .get("accept-ranges")
+            .is_some_and(|v| v.as_bytes() == b"bytes");
+
+        let existing_size = if self.resume {
+            fs::metadata(&self.destination)
+                .map(|m| m.len())
+                .unwrap_or(0)
+        } else {
+            0
+        };
+        // a local file larger than the remote one is not a partial download
+        let offset = if accepts_ranges
+            && total_size.is_none_or(|total| existing_size <= total)
+        {
+            existing_size
+        } else {
+            0
+        };
+
+        let mut hasher = Sha256::new();
+        if offset > 0 {
+            // checksum covers the whole file, including the resumed part
+            hash_file(&self.destination, &mut hasher)
+                .map_err(|e| CliError::general(e.to_string()))?;
+        }
+        if offset > 0 && Some(offset) == total_size {
+            if !self.quiet {
+                eprintln!("{} is already complete", self.destination);
+            }
+            return Ok(hex::encode(hasher.finalize()));
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(offset > 0)
+            .truncate(offset == 0)
+            .open(&self.destination)
+            .map_err(|e| {
+                CliError::general(format!("{}: {}", self.destination, e))
+            })?;
+
+        let mut headers = HashMap::new();
+        if offset > 0 {
+            if !self.quiet {
+                eprintln!("Resuming download at byte {}", offset);
+            }
+            headers.insert("Range".to_string(), format!("bytes={}-", offset));
+        }
+
+        let (tx, mut rx) = mpsc::channel(CHANNEL_QUEUE_SIZE);
+        let request =
+            self.client
+                .get(&self.url, Some(&headers), None, Some(tx), None);
+        let writer = async {
+            let progress = ProgressTracker::new(
+                "download",
+                Arc::new(ProgressBar::new("Downloaded")),
+            )
+            .set_total_bytes(total_size)
+            .set_offset(offset);
+            while let Some(chunk) = rx.recv().await {
+                file.write_all(&chunk)
+                    .map_err(|e| CliError::general(e.to_string()))?;
+                hasher.update(&chunk);
+                if !self.quiet {
+                    progress.add_bytes(chunk.len() as u64);
+                }
+            }
+            if !self.quiet {
+                progress.finish();
+            }
+            Ok::<(), CliError>(())
+        };
+        let (response, written) = tokio::join!(request, writer);
+        let response = response.map_err(CliError::from)?;
+        written?;
+
+        if offset > 0 && response.status_code() != 206 {
+            return Err(CliError::general(format!(
+                "server did not resume the download of {}, retry with \
+                 --no-resume",
+                self.destination
+            )));
+        }
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    // the checksum, if it is the expected one
+    fn verify(
+        &self,
+        checksum: String,
+        expected_checksum: &Option<String>,
+    ) -> Result<String, CliError> {
+        let Some(expected_checksum) = expected_checksum else {
+            return Ok(checksum);
+        };
+        if &checksum != expected_checksum {
+            return Err(CliError::general(format!(
+                "checksum mismatch for {}, expected {} but got {}",
+                self.url, expected_checksum, checksum
+            )));
+        }
+        if !self.quiet && !is_json_output() {
+            eprintln!("Checksum verified: {}", checksum);
+        }
+        Ok(checksum)
+    }
+
+    // sidecar files use the sha256sum format: "<checksum>  <filename>"
+    async fn fetch_checksum_file(&self) -> Result<String, CliError> {
+        let checksum_url = format!("{}.sha256", self.url);
+        let response = self
+            .client
+            .get(&checksum_url, None, None, None, None)
+            .await
+            .map_err(|e| CliError::from(e).context(&checksum_url))?;
+        let body = response
+            .body()
+            .map(|b| String::from_utf8_lossy(b).to_string())
+            .unwrap_or_default();
+        body.split_whitespace()
+            .next()
+            .filter(|c| {
+                c.len() == 64 && c.chars().all(|c| c.is_ascii_hexdigit())
+            })
+            .map(|c| c.to_lowercase())
+            .ok_or_else(|| {
+                CliError::general(format!(
+                    "{}: no SHA-256 checksum found",
+                    checksum_url
+                ))
+            })
+    }
+}
+
+fn hash_file(path: &str, hasher: &mut Sha256) -> io::Result<()> {
+    let mut file = File::open(path)?;
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            return Ok(());
+        }
+        hasher.update(&buffer[..n]);
+    }
+}
+
+fn filename_from_url(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let path = path.split_once("://").map_or(path, |(_, rest)| rest);
+    match path.split_once('/') {
+        Some((_, path)) => path
+            .rsplit('/')
+            .next()
+            .filter(|name| !name.is_empty())
+            .unwrap_or("index.html")
+            .to_string(),
+        None => "index.html".to_string(),
+    }
+}
@@ -0,0 +1,63 @@
+use clap::{Arg, ArgAction, Command};
+
+pub use super::grep_handler::handle_grep;
+use super::output::output_args;
+
+pub fn grep_subcommand() -> Command {
+    let command = Command::new("grep")
+        .about("Search the contents of objects for lines matching a pattern")
+        .arg(
+            Arg::new("pattern")
+                .index(1)
+                .required(true)
+                .help("Regular expression a line must match"),
+        )
+        .arg(
+            Arg::new("uri")
+                .index(2)
+                .required(true)
+                .help("URI of the objects to search, e.g. s3://bucket/logs/"),
+        )
+        .arg(
+            Arg::new("name")
+                .long("name")
+                .short('n')
+                .help("Only search objects named like a glob, e.g. '*.log'"),
+        )
+        .arg(
+            Arg::new("ignore_case")
+                .long("ignore-case")
+                .short('i')
+                .action(ArgAction::SetTrue)
+                .help("Match the pattern regardless of case"),
+        )
+        .arg(
+            Arg::new("max_count")
+                .long("max-count")
+                .short('m')
+                .value_parser(clap::value_parser!(u64))
+                .help("Stop reading an object after this many matches"),
+        )
+        .arg(
+            Arg::new("concurrency")
+                .long("concurrency")
+                .value_parser(clap::value_parser!(usize))
+                .help(
+                    "Number of objects searched at the same time. \
+                     GREP_BANDWIDTH_LIMIT limits the bandwidth [default: \
+                     number of cpus]",
+                ),
+        );
+    #[cfg(feature = "compression")]
+    let command = command.arg(
+        Arg::new("decompress")
+            .long("decompress")
+            .short('z')
+            .action(ArgAction::SetTrue)
+            .help(
+                "Search compressed objects decompressed, also if DECOMPRESS \
+                 is false",
+            ),
+    );
+    command.args(output_args())
+}
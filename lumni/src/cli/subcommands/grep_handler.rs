@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use lumni::{EnvironmentConfig, GrepOptions, ObjectStoreHandler, ParsedUri};
+use serde_json::json;
+
+use super::output::{ExportCallback, OutputFormat};
+use crate::cli::error::{CliError, ExitCode};
+use crate::cli::json_output::{is_json_output, print_json};
+
+pub async fn handle_grep(
+    matches: &clap::ArgMatches,
+    config: &mut EnvironmentConfig,
+) {
+    let pattern = matches.get_one::<String>("pattern").unwrap();
+    let uri = matches.get_one::<String>("uri").unwrap();
+    // uri should start with a scheme, if not add default
+    let uri = if uri.contains("://") {
+        uri.to_string()
+    } else {
+        format!("localfs://{}", uri)
+    };
+    let parsed_uri = ParsedUri::from_uri(&uri, true);
+    let output_format =
+        OutputFormat::from_matches(matches).unwrap_or_else(|e| e.exit());
+
+    let mut options = GrepOptions::new()
+        .set_ignore_case(matches.get_flag("ignore_case"))
+        .set_max_count(matches.get_one::<u64>("max_count").copied());
+    if let Some(name) = matches.get_one::<String>("name") {
+        options = options.set_name(name);
+    }
+    if let Some(concurrency) = matches.get_one::<usize>("concurrency") {
+        options = options.set_concurrency(*concurrency);
+    }
+    #[cfg(feature = "compression")]
+    if matches.get_flag("decompress") {
+        options = options.set_decompress(true);
+    }
+
+    let handler = ObjectStoreHandler::new(None);
+    let table = handler
+        .grep(
+            &parsed_uri,
+            config,
+            pattern,
+            &options,
+            Some(Arc::new(ExportCallback::new(output_format))),
+        )
+        .await
+        .unwrap_or_else(|err| CliError::from(err).exit());
+
+    for (uri, reason) in table.failed() {
+        if is_json_output() {
+            print_json(&json!({ "failed": uri, "reason": reason }));
+        } else {
+            eprintln!("failed: {}: {}", uri, reason);
+        }
+    }
+    if !table.failed().is_empty() {
+        CliError::new(
+            ExitCode::PartialFailure,
+            format!("{} objects could not be searched", table.failed().len()),
+        )
+        .exit();
+    }
+}
@@ -1,10 +1,16 @@
 use clap::{Arg, ArgAction, Command};
 
 pub use super::ls_handler::handle_ls;
+use super::output::output_args;
+#[cfg(feature = "parquet")]
+use super::parquet_output::parquet_output_arg;
 
 pub fn ls_subcommand() -> Command {
-    Command::new("ls")
-        .about("List objects on Local Filesystem or an S3 bucket")
+    let command = Command::new("ls")
+        .about(
+            "List objects on Local Filesystem, an S3 or a GCS bucket, an \
+             Azure container, or a server over SFTP or WebHDFS",
+        )
         .arg(
             Arg::new("uri")
                 .index(1)
@@ -38,6 +44,10 @@ pub fn ls_subcommand() -> Command {
                      '+5m', '-1h', '+2D', '-3W', '+1M', '-1Y'",
                 ),
         )
+        .arg(Arg::new("storage_class").long("storage-class").help(
+            "Filter objects based on storage class and show it. E.g. \
+             'STANDARD', 'GLACIER', 'DEEP_ARCHIVE', 'INTELLIGENT_TIERING'",
+        ))
         .arg(
             Arg::new("recursive")
                 .long("recursive")
@@ -52,4 +62,51 @@ pub fn ls_subcommand() -> Command {
                 .default_value("1000")
                 .help("Maximum number of files to list"),
         )
+        .arg(
+            Arg::new("checksum")
+                .long("checksum")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("md5")
+                .value_parser(["md5", "sha256", "crc32c"])
+                .help(
+                    "Show content checksums, e.g. --checksum=sha256. Object stores return the \
+                     checksum they keep, local files are hashed",
+                ),
+        )
+        .arg(
+            Arg::new("versions")
+                .long("versions")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["checksum", "storage_class"])
+                .help(
+                    "List all versions and delete markers of the objects in \
+                     an S3 bucket with versioning enabled",
+                ),
+        )
+        .arg(
+            Arg::new("as_of")
+                .long("as-of")
+                .conflicts_with("versions")
+                .help(
+                    "List the objects as they were at a time, rebuilt from \
+                     the versions of an S3 bucket with versioning enabled. \
+                     E.g. '2024-05-01T12:00:00Z', '2024-05-01' or epoch \
+                     seconds",
+                ),
+        )
+        .arg(
+            Arg::new("progress")
+                .long("progress")
+                .action(ArgAction::SetTrue)
+                .help(
+                    "Show the number of objects listed so far on stderr, \
+                     e.g. when the output is written to a file",
+                ),
+        )
+        .args(output_args());
+
+    #[cfg(feature = "parquet")]
+    let command = command.arg(parquet_output_arg());
+    command
 }
@@ -1,10 +1,19 @@
 use std::sync::Arc;
 
-use log::{debug, error};
+use log::debug;
 use lumni::{
-    EnvironmentConfig, FileObjectFilter, LakestreamError, ObjectStoreHandler,
-    ParsedUri, TableCallback, TableRow,
+    EnvironmentConfig, FileObjectFilter, ObjectStoreHandler, ParsedUri,
+    ProgressTableCallback, ProgressTracker, TableCallback, TableRow,
 };
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tracing::info_span;
+
+use super::output::{ExportCallback, OutputFormat};
+#[cfg(feature = "parquet")]
+use super::parquet_output::{parquet_output, write_parquet_output};
+use super::progress::ProgressBar;
+use crate::cli::error::CliError;
 
 pub async fn handle_ls(
     ls_matches: &clap::ArgMatches,
@@ -15,31 +24,114 @@ pub async fn handle_ls(
 
     let handler = ObjectStoreHandler::new(None);
 
-    let callback = Arc::new(PrintCallback);
-
-    match handler
-        .list_objects(
-            &ParsedUri::from_uri(&uri, true),
-            config,
-            None, // functions as "*", prints all columns
-            recursive,
-            Some(max_files),
-            &filter,
-            Some(callback),
-        )
-        .await
-    {
-        Ok(_) => {
-            debug!("List objects executed successfully with no return value.");
-        }
-        Err(LakestreamError::NoBucketInUri(_)) => {
-            error!("Error: No bucket in URI");
-            std::process::exit(1);
+    #[cfg(feature = "parquet")]
+    let parquet_uri = parquet_output(ls_matches);
+    #[cfg(not(feature = "parquet"))]
+    let parquet_uri: Option<String> = None;
+
+    let output_format =
+        OutputFormat::from_matches(ls_matches).unwrap_or_else(|e| e.exit());
+
+    // rows are printed as they are listed, unless written to a file
+    let callback: Option<Arc<dyn TableCallback>> =
+        match (parquet_uri.as_ref(), output_format) {
+            (Some(_), _) => None,
+            (None, OutputFormat::Table) => Some(Arc::new(PrintCallback)),
+            (None, format) => Some(Arc::new(ExportCallback::new(format))),
+        };
+    let progress = ls_matches.get_flag("progress").then(|| {
+        Arc::new(ProgressTracker::new(
+            "list",
+            Arc::new(ProgressBar::new("Listed")),
+        ))
+    });
+    let callback: Option<Arc<dyn TableCallback>> = match &progress {
+        Some(tracker) => Some(Arc::new(ProgressTableCallback::new(
+            Arc::clone(tracker),
+            callback,
+        ))),
+        None => callback,
+    };
+
+    let checksum = ls_matches.get_one::<String>("checksum");
+    let storage_class = ls_matches.get_one::<String>("storage_class");
+    let selected_columns = match (checksum, storage_class) {
+        (None, None) => None, // functions as "*", prints all columns
+        _ => {
+            let mut columns = vec!["name", "size", "modified"];
+            if let Some(algorithm) = checksum {
+                config.set(
+                    "CHECKSUM_ALGORITHM".to_string(),
+                    algorithm.to_string(),
+                );
+                columns.push("checksum");
+            }
+            if storage_class.is_some() {
+                columns.push("storage_class");
+            }
+            Some(columns)
         }
-        Err(err) => {
-            error!("Error listing objects: {}", err);
-            std::process::exit(1);
+    };
+
+    let as_of = ls_matches.get_one::<String>("as_of").map(|value| {
+        parse_time(value, "--as-of")
+            .unwrap_or_else(|err| CliError::usage(err).exit())
+    });
+
+    let parsed_uri = ParsedUri::from_uri(&uri, true);
+    let result = if let Some(as_of) = as_of {
+        // the version of each object, to restore it
+        let mut columns = selected_columns
+            .unwrap_or_else(|| vec!["name", "size", "modified"]);
+        columns.push("version_id");
+        handler
+            .list_objects_as_of(
+                &parsed_uri,
+                config,
+                Some(columns),
+                recursive,
+                Some(max_files),
+                &filter,
+                as_of,
+                callback,
+            )
+            .await
+    } else if ls_matches.get_flag("versions") {
+        handler
+            .list_object_versions(
+                &parsed_uri,
+                config,
+                recursive,
+                Some(max_files),
+                &filter,
+                callback,
+            )
+            .await
+    } else {
+        handler
+            .list_objects(
+                &parsed_uri,
+                config,
+                selected_columns,
+                recursive,
+                Some(max_files),
+                &filter,
+                callback,
+            )
+            .await
+    };
+    match result {
+        Ok(_table) => {
+            if let Some(tracker) = progress {
+                tracker.finish();
+            }
+            #[cfg(feature = "parquet")]
+            if let Some(uri) = parquet_uri {
+                write_parquet_output(_table.as_ref(), &uri, config).await;
+            }
+            debug!("List objects executed successfully with no return value.");
         }
+        Err(err) => CliError::from(err).exit(),
     }
 }
 
@@ -65,9 +157,16 @@ fn prepare_handle_ls_arguments(
     let filter_mtime = ls_matches
         .get_one::<String>("mtime")
         .map(ToString::to_string);
+    let filter_storage_class =
+        ls_matches.get_one::<String>("storage_class").cloned();
 
-    let filter = match (&filter_name, &filter_size, &filter_mtime) {
-        (None, None, None) => None,
+    let filter = match (
+        &filter_name,
+        &filter_size,
+        &filter_mtime,
+        &filter_storage_class,
+    ) {
+        (None, None, None, None) => None,
         _ => {
             let filter_result = FileObjectFilter::new(
                 filter_name.as_deref(),
@@ -75,10 +174,12 @@ fn prepare_handle_ls_arguments(
                 filter_mtime.as_deref(),
             );
             match filter_result {
-                Ok(filter) => Some(filter),
+                Ok(filter) => {
+                    Some(filter.set_storage_class(filter_storage_class))
+                }
                 Err(err) => {
-                    error!("Error creating filter: {}", err);
-                    std::process::exit(1);
+                    CliError::usage(format!("Error creating filter: {}", err))
+                        .exit()
                 }
             }
         }
@@ -93,10 +194,34 @@ fn prepare_handle_ls_arguments(
     (uri, recursive, max_files, filter)
 }
 
+// epoch seconds of an RFC 3339 time, a date (midnight UTC) or epoch
+// seconds
+pub(super) fn parse_time(value: &str, arg: &str) -> Result<u64, String> {
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Ok(seconds);
+    }
+    let timestamp = if value.contains('T') {
+        value.to_string()
+    } else {
+        format!("{}T00:00:00Z", value)
+    };
+    OffsetDateTime::parse(&timestamp, &Rfc3339)
+        .ok()
+        .and_then(|datetime| u64::try_from(datetime.unix_timestamp()).ok())
+        .ok_or_else(|| {
+            format!(
+                "Invalid {} time: {}, expected e.g. \
+                 2024-05-01T12:00:00Z, 2024-05-01 or epoch seconds",
+                arg, value
+            )
+        })
+}
+
 // Callback to print each row to the console
 struct PrintCallback;
 impl TableCallback for PrintCallback {
     fn on_row_add(&self, row: &mut TableRow) {
+        let _span = info_span!("render").entered();
         row.print();
     }
 }
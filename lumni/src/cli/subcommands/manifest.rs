@@ -0,0 +1,70 @@
+use clap::{Arg, ArgAction, Command};
+
+pub use super::manifest_handler::handle_manifest;
+
+pub fn manifest_subcommand() -> Command {
+    Command::new("manifest")
+        .about(
+            "Create or verify a manifest with the size and sha256 of every \
+             object under a prefix, signed if MANIFEST_SIGNING_KEY is set",
+        )
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("create")
+                .about("Read and hash every object under the prefix")
+                .arg(
+                    Arg::new("uri")
+                        .index(1)
+                        .required(true)
+                        .help("URI of the prefix, e.g. s3://bucket/prefix/"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('o')
+                        .value_name("FILE")
+                        .help(
+                            "File to write the manifest to [default: stdout]",
+                        ),
+                )
+                .args(hashing_args()),
+        )
+        .subcommand(
+            Command::new("verify")
+                .about(
+                    "Compare the objects under a prefix with a manifest, to \
+                     detect corrupted, changed, missing or added objects",
+                )
+                .arg(
+                    Arg::new("uri")
+                        .index(1)
+                        .required(true)
+                        .help("URI of the prefix, e.g. s3://bucket/prefix/"),
+                )
+                .arg(
+                    Arg::new("manifest")
+                        .index(2)
+                        .required(true)
+                        .help("Manifest file written by manifest create"),
+                )
+                .args(hashing_args()),
+        )
+}
+
+fn hashing_args() -> Vec<Arg> {
+    vec![
+        Arg::new("concurrency")
+            .long("concurrency")
+            .value_parser(clap::value_parser!(usize))
+            .help(
+                "Number of objects read and hashed at the same time. \
+                 MANIFEST_BANDWIDTH_LIMIT limits the bandwidth \
+                 [default: number of cpus]",
+            ),
+        Arg::new("quiet")
+            .long("quiet")
+            .short('q')
+            .action(ArgAction::SetTrue)
+            .help("Do not show progress"),
+    ]
+}
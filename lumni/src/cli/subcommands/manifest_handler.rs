@@ -0,0 +1,155 @@
+use std::fs;
+use std::sync::Arc;
+
+use lumni::{
+    EnvironmentConfig, IntegrityManifest, ManifestHasher, ParsedUri,
+    SignatureStatus,
+};
+use serde_json::json;
+
+use super::progress::ProgressBar;
+use crate::cli::error::CliError;
+use crate::cli::json_output::{is_json_output, print_json, print_warning};
+
+pub async fn handle_manifest(
+    matches: &clap::ArgMatches,
+    config: &mut EnvironmentConfig,
+) {
+    match matches.subcommand() {
+        Some(("create", matches)) => handle_create(matches, config).await,
+        Some(("verify", matches)) => handle_verify(matches, config).await,
+        _ => unreachable!("subcommand_required(true) not defined"),
+    }
+}
+
+async fn handle_create(matches: &clap::ArgMatches, config: &EnvironmentConfig) {
+    let source = parse_uri(matches);
+    let manifest = hasher(matches, "Hashed")
+        .create(&source, config)
+        .await
+        .unwrap_or_else(|e| CliError::from(e).exit());
+    if !manifest.is_signed() {
+        print_warning(
+            "manifest is not signed, set MANIFEST_SIGNING_KEY to detect \
+             changes to the manifest itself",
+        );
+    }
+    let contents = manifest
+        .to_json()
+        .unwrap_or_else(|e| CliError::from(e).exit());
+
+    let Some(output) = matches.get_one::<String>("output") else {
+        println!("{}", contents);
+        return;
+    };
+    if let Err(e) = fs::write(output, contents + "\n") {
+        CliError::general(format!("failed to write {}: {}", output, e)).exit();
+    }
+    if is_json_output() {
+        print_json(&json!({
+            "manifest": output,
+            "objects": manifest.objects().len(),
+            "signed": manifest.is_signed(),
+        }));
+    } else if !matches.get_flag("quiet") {
+        eprintln!(
+            "Wrote the manifest of {} objects to {}",
+            manifest.objects().len(),
+            output
+        );
+    }
+}
+
+async fn handle_verify(matches: &clap::ArgMatches, config: &EnvironmentConfig) {
+    let source = parse_uri(matches);
+    let path = matches.get_one::<String>("manifest").unwrap();
+    let contents = fs::read_to_string(path).unwrap_or_else(|e| {
+        CliError::general(format!("failed to read {}: {}", path, e)).exit()
+    });
+    let manifest = IntegrityManifest::from_json(&contents)
+        .unwrap_or_else(|e| CliError::from(e).exit());
+    let verification = hasher(matches, "Verified")
+        .verify(&manifest, &source, config)
+        .await
+        .unwrap_or_else(|e| CliError::from(e).exit());
+
+    let problems =
+        verification
+            .missing()
+            .iter()
+            .map(|key| (key.as_str(), "missing", "not under the prefix"))
+            .chain(
+                verification.unexpected().iter().map(|key| {
+                    (key.as_str(), "unexpected", "not in the manifest")
+                }),
+            )
+            .chain(verification.mismatched().iter().map(|(key, reason)| {
+                (key.as_str(), "mismatched", reason.as_str())
+            }))
+            .chain(verification.failed().iter().map(|(key, reason)| {
+                (key.as_str(), "failed", reason.as_str())
+            }));
+    for (key, status, reason) in problems {
+        if is_json_output() {
+            print_json(&json!({
+                "key": key,
+                "status": status,
+                "reason": reason,
+            }));
+        } else {
+            println!("{}: {} ({})", status, key, reason);
+        }
+    }
+
+    match verification.signature() {
+        SignatureStatus::Valid => {}
+        SignatureStatus::Invalid => print_warning(
+            "manifest signature does not match MANIFEST_SIGNING_KEY, the \
+             manifest was changed or signed with another key",
+        ),
+        SignatureStatus::Unchecked => print_warning(
+            "manifest signature not checked, MANIFEST_SIGNING_KEY is not set",
+        ),
+    }
+    if is_json_output() {
+        print_json(&json!({
+            "verified": verification.verified().len(),
+            "intact": verification.is_intact(),
+        }));
+    } else if !matches.get_flag("quiet") {
+        eprintln!(
+            "{} of {} objects verified",
+            verification.verified().len(),
+            manifest.objects().len()
+        );
+    }
+    if !verification.is_intact() {
+        CliError::general(format!(
+            "{} does not match the manifest {}",
+            source.to_string(),
+            path
+        ))
+        .exit();
+    }
+}
+
+fn parse_uri(matches: &clap::ArgMatches) -> ParsedUri {
+    let uri = matches.get_one::<String>("uri").unwrap();
+    let uri = if uri.contains("://") {
+        uri.to_string()
+    } else {
+        format!("localfs://{}", uri)
+    };
+    ParsedUri::from_uri(&uri, true)
+}
+
+fn hasher(matches: &clap::ArgMatches, label: &str) -> ManifestHasher {
+    let mut hasher = ManifestHasher::new();
+    if let Some(concurrency) = matches.get_one::<usize>("concurrency") {
+        hasher = hasher.set_concurrency(*concurrency);
+    }
+    if !matches.get_flag("quiet") {
+        hasher = hasher.set_progress(Arc::new(ProgressBar::new(label)));
+    }
+    hasher
+}
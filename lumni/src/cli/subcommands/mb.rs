@@ -0,0 +1,16 @@
+use clap::{Arg, Command};
+
+pub use super::mb_handler::handle_mb;
+use super::plan::dry_run_args;
+
+pub fn mb_subcommand() -> Command {
+    Command::new("mb")
+        .about("Make a bucket, or a directory on the local filesystem")
+        .arg(
+            Arg::new("uri")
+                .index(1)
+                .required(true)
+                .help("URI of the bucket, e.g. s3://bucket"),
+        )
+        .args(dry_run_args())
+}
@@ -0,0 +1,41 @@
+use lumni::{
+    EnvironmentConfig, ObjectStoreHandler, ParsedUri, PlannedOperation,
+};
+use serde_json::json;
+
+use super::plan::{is_dry_run, print_plan};
+use crate::cli::error::CliError;
+use crate::cli::json_output::print_result;
+
+pub async fn handle_mb(
+    matches: &clap::ArgMatches,
+    config: &mut EnvironmentConfig,
+) {
+    let uri = bucket_uri(matches.get_one::<String>("uri").unwrap());
+    let parsed_uri = ParsedUri::from_uri(&uri, false);
+
+    if is_dry_run(matches) {
+        let operation = PlannedOperation::new("make_bucket", &uri);
+        if let Err(err) = print_plan(matches, vec![operation]) {
+            CliError::general(err).exit();
+        }
+        return;
+    }
+
+    let handler = ObjectStoreHandler::new(None);
+    match handler.create_bucket(&parsed_uri, config).await {
+        Ok(()) => print_result(json!({ "make_bucket": uri }), || {
+            format!("make_bucket: {}", uri)
+        }),
+        Err(err) => CliError::from(err).exit(),
+    }
+}
+
+// uri should start with a scheme, if not add default
+pub fn bucket_uri(uri: &str) -> String {
+    if uri.contains("://") {
+        uri.trim_end_matches('/').to_string()
+    } else {
+        format!("localfs://{}", uri.trim_end_matches('/'))
+    }
+}
@@ -1,10 +1,58 @@
+pub mod access;
+mod access_handler;
 pub mod app;
 mod app_handler;
+pub mod auth;
+mod auth_handler;
+pub mod browse;
+mod browse_handler;
+pub mod cache;
+mod cache_handler;
+pub mod completions;
+mod completions_handler;
+pub mod configure;
+mod configure_handler;
+mod confirm;
 pub mod cp;
 mod cp_handler;
+pub mod diff;
+mod diff_handler;
+pub mod env;
+mod env_handler;
+pub mod get;
+mod get_handler;
+pub mod grep;
+mod grep_handler;
 pub mod ls;
 mod ls_handler;
+pub mod manifest;
+mod manifest_handler;
+pub mod mb;
+mod mb_handler;
+mod output;
+#[cfg(feature = "parquet")]
+mod parquet_output;
+mod plan;
+mod progress;
+pub mod presign;
+mod presign_handler;
+pub mod put;
+mod put_handler;
 pub mod query;
 mod query_handler;
+pub mod rb;
+mod rb_handler;
 pub mod request;
 mod request_handler;
+pub mod restore;
+mod restore_handler;
+pub mod rm;
+mod rm_handler;
+pub mod s3;
+mod s3_handler;
+pub mod stat;
+mod stat_handler;
+pub mod telemetry;
+mod telemetry_handler;
+pub mod watch;
+mod watch_handler;
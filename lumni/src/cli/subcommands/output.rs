@@ -0,0 +1,128 @@
+use std::io::{self, Write};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use clap::Arg;
+use lumni::{RowTemplate, TableCallback, TableRow};
+use tracing::info_span;
+
+use crate::cli::error::CliError;
+use crate::cli::json_output::is_json_output;
+
+// shared by commands that produce a table, e.g. ls and query
+pub fn output_args() -> [Arg; 3] {
+    [
+        Arg::new("output")
+            .long("output")
+            .short('o')
+            .value_parser(["table", "csv", "jsonl"])
+            .default_value("table")
+            .help("Output format, csv and jsonl can be piped into other tools"),
+        Arg::new("delimiter")
+            .long("delimiter")
+            .default_value(",")
+            .help("Field delimiter for --output csv, e.g. ';' or '\\t'"),
+        Arg::new("format")
+            .long("format")
+            .conflicts_with("output")
+            .help(
+                "Line per row from a template, e.g. \
+                 \"{name}\\t{size_human}\\t{modified:%Y-%m-%d}\". A spec after \
+                 ':' is human (sizes), json (escaped value) or a date format",
+            ),
+    ]
+}
+
+pub enum OutputFormat {
+    Table,
+    Csv(char),
+    Jsonl,
+    Template(RowTemplate),
+}
+
+impl OutputFormat {
+    pub fn from_matches(matches: &clap::ArgMatches) -> Result<Self, CliError> {
+        if let Some(template) = matches.get_one::<String>("format") {
+            return RowTemplate::parse(template)
+                .map(OutputFormat::Template)
+                .map_err(CliError::usage);
+        }
+        if is_json_output() {
+            return Ok(OutputFormat::Jsonl);
+        }
+        match matches.get_one::<String>("output").map(String::as_str) {
+            Some("csv") => {
+                let delimiter = matches
+                    .get_one::<String>("delimiter")
+                    .map(String::as_str)
+                    .unwrap_or(",");
+                // accept an escaped tab, as it is awkward to type in a shell
+                let delimiter = match delimiter {
+                    "\\t" => "\t",
+                    delimiter => delimiter,
+                };
+                let mut chars = delimiter.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(delimiter), None) => Ok(OutputFormat::Csv(delimiter)),
+                    _ => Err(CliError::usage(format!(
+                        "Delimiter must be a single character, got '{}'",
+                        delimiter
+                    ))),
+                }
+            }
+            Some("jsonl") => Ok(OutputFormat::Jsonl),
+            _ => Ok(OutputFormat::Table),
+        }
+    }
+}
+
+// prints rows as csv, JSON lines or a template while they are added, the
+// csv header is taken from the first row
+pub struct ExportCallback {
+    format: OutputFormat,
+    header_printed: AtomicBool,
+}
+
+impl ExportCallback {
+    pub fn new(format: OutputFormat) -> Self {
+        ExportCallback {
+            format,
+            header_printed: AtomicBool::new(false),
+        }
+    }
+}
+
+impl TableCallback for ExportCallback {
+    fn on_row_add(&self, row: &mut TableRow) {
+        let _span = info_span!("render").entered();
+        let line = match &self.format {
+            OutputFormat::Csv(delimiter) => {
+                let delimiter = *delimiter;
+                let mut line = String::new();
+                if !self.header_printed.swap(true, Ordering::SeqCst) {
+                    let names: Vec<&str> = row
+                        .data()
+                        .iter()
+                        .map(|(name, _)| name.as_str())
+                        .collect();
+                    line.push_str(&names.join(&delimiter.to_string()));
+                    line.push('\n');
+                }
+                line.push_str(&row.to_csv(delimiter));
+                line
+            }
+            OutputFormat::Jsonl => row.to_json(),
+            OutputFormat::Template(template) => template
+                .render(row)
+                .unwrap_or_else(|e| CliError::usage(e).exit()),
+            OutputFormat::Table => return row.print(),
+        };
+        if let Err(e) = writeln!(io::stdout().lock(), "{}", line) {
+            if e.kind() == io::ErrorKind::BrokenPipe {
+                // reader went away, e.g. "lumni ls -o csv | head"
+                process::exit(0);
+            }
+            CliError::general(e.to_string()).exit();
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use clap::Arg;
+use lumni::{EnvironmentConfig, ParquetWriter, Table};
+use serde_json::json;
+
+use crate::cli::error::CliError;
+use crate::cli::json_output::{is_json_output, print_json};
+
+// shared by commands that produce a table, e.g. ls and query
+pub fn parquet_output_arg() -> Arg {
+    Arg::new("parquet").long("parquet").value_name("URI").help(
+        "Write the result as Parquet to a local path or object store URI, \
+         instead of printing it",
+    )
+}
+
+pub fn parquet_output(matches: &clap::ArgMatches) -> Option<String> {
+    matches.get_one::<String>("parquet").map(|uri| {
+        // uri should start with a scheme, if not add default
+        if uri.contains("://") {
+            uri.to_string()
+        } else {
+            format!("localfs://{}", uri)
+        }
+    })
+}
+
+pub async fn write_parquet_output(
+    table: &dyn Table,
+    uri: &str,
+    config: &EnvironmentConfig,
+) {
+    match table.write_parquet(uri, config).await {
+        Ok(bytes_written) if is_json_output() => print_json(&json!({
+            "written": uri,
+            "rows": table.len(),
+            "bytes": bytes_written,
+        })),
+        Ok(bytes_written) => {
+            eprintln!(
+                "Wrote {} rows ({} bytes) to {}",
+                table.len(),
+                bytes_written,
+                uri
+            );
+        }
+        Err(err) => CliError::from(err).context(uri).exit(),
+    }
+}
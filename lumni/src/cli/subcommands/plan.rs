@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use clap::{Arg, ArgAction};
+use lumni::{OperationTable, PlannedOperation, Table, TableCallback, TableRow};
+use serde_json::json;
+
+use crate::cli::json_output::{is_json_output, print_json};
+
+// shared by all commands that modify data, so the flags behave the same
+pub fn dry_run_args() -> [Arg; 2] {
+    [
+        Arg::new("dry_run")
+            .long("dry-run")
+            .action(ArgAction::SetTrue)
+            .help("Print the operations that would be performed and exit"),
+        Arg::new("plan_format")
+            .long("plan-format")
+            .value_parser(["text", "json"])
+            .default_value("text")
+            .help("Output format of the --dry-run plan"),
+    ]
+}
+
+pub fn is_dry_run(matches: &clap::ArgMatches) -> bool {
+    matches.get_flag("dry_run")
+}
+
+pub fn print_plan(
+    matches: &clap::ArgMatches,
+    operations: Vec<PlannedOperation>,
+) -> Result<(), String> {
+    if is_json_output() {
+        print_json(&json!({ "plan": operations }));
+        return Ok(());
+    }
+    let as_json = matches
+        .get_one::<String>("plan_format")
+        .is_some_and(|format| format == "json");
+
+    let mut table = OperationTable::new();
+    if !as_json {
+        // text rows are printed as they are added
+        table.set_callback(Arc::new(PrintCallback));
+    }
+    for operation in operations {
+        table.add_operation(operation)?;
+    }
+    if as_json {
+        println!("{}", table.to_json().map_err(|e| e.to_string())?);
+    }
+    Ok(())
+}
+
+struct PrintCallback;
+impl TableCallback for PrintCallback {
+    fn on_row_add(&self, row: &mut TableRow) {
+        row.print();
+    }
+}
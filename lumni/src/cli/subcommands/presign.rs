@@ -0,0 +1,27 @@
+use clap::{Arg, Command};
+
+pub use super::presign_handler::handle_presign;
+
+pub fn presign_subcommand() -> Command {
+    Command::new("presign")
+        .about("Create a temporary URL to download or upload an S3 object")
+        .arg(
+            Arg::new("uri")
+                .index(1)
+                .required(true)
+                .help("URI of the object, e.g. s3://bucket/key"),
+        )
+        .arg(
+            Arg::new("method")
+                .long("method")
+                .value_parser(["GET", "PUT"])
+                .default_value("GET")
+                .help("HTTP method the URL is valid for"),
+        )
+        .arg(
+            Arg::new("expires_in")
+                .long("expires-in")
+                .value_parser(clap::value_parser!(u64))
+                .help("Seconds until the URL expires, at most 604800"),
+        )
+}
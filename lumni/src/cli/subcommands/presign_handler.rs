@@ -0,0 +1,33 @@
+use lumni::{
+    EnvironmentConfig, ObjectStoreHandler, ParsedUri,
+    DEFAULT_PRESIGN_EXPIRY_SECONDS,
+};
+use serde_json::json;
+
+use crate::cli::error::CliError;
+use crate::cli::json_output::print_result;
+
+pub async fn handle_presign(
+    matches: &clap::ArgMatches,
+    config: &mut EnvironmentConfig,
+) {
+    let uri = matches.get_one::<String>("uri").unwrap();
+    let method = matches.get_one::<String>("method").unwrap();
+    let expires_in = matches
+        .get_one::<u64>("expires_in")
+        .copied()
+        .unwrap_or(DEFAULT_PRESIGN_EXPIRY_SECONDS);
+
+    let parsed_uri = ParsedUri::from_uri(uri, false);
+    let handler = ObjectStoreHandler::new(None);
+    match handler
+        .presign_url(&parsed_uri, config, method, expires_in)
+        .await
+    {
+        Ok(url) => print_result(
+            json!({ "url": url, "method": method, "expires_in": expires_in }),
+            || url.clone(),
+        ),
+        Err(err) => CliError::from(err).exit(),
+    }
+}
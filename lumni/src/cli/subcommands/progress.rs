@@ -0,0 +1,91 @@
+use std::io::{self, Write};
+
+use lumni::{ProgressReporter, ProgressUpdate};
+
+const BAR_WIDTH: usize = 20;
+// long enough to overwrite any earlier line
+const LINE_WIDTH: usize = 72;
+
+// progress bar on stderr, redrawn in place on every update
+pub struct ProgressBar {
+    label: String,
+}
+
+impl ProgressBar {
+    pub fn new(label: &str) -> Self {
+        ProgressBar {
+            label: label.to_string(),
+        }
+    }
+}
+
+impl ProgressReporter for ProgressBar {
+    fn report(&self, update: &ProgressUpdate) {
+        let mut line = match update.percent() {
+            Some(percent) if !update.finished => {
+                let filled = percent as usize * BAR_WIDTH / 100;
+                format!(
+                    "[{}{}] {:>3}% ",
+                    "#".repeat(filled),
+                    "-".repeat(BAR_WIDTH - filled),
+                    percent
+                )
+            }
+            _ => String::new(),
+        };
+        if update.objects > 0 && update.total_bytes.is_none() {
+            // listings count objects, their sizes are a side note
+            line.push_str(&format!(
+                "{} {} objects ({}), {:.0} objects/s",
+                self.label,
+                update.objects,
+                human_bytes(update.bytes),
+                update.objects_per_second()
+            ));
+        } else {
+            line.push_str(&format!(
+                "{} {}",
+                self.label,
+                human_bytes(update.bytes)
+            ));
+            if let Some(total) = update.total_bytes {
+                line.push_str(&format!(" of {}", human_bytes(total)));
+            }
+            line.push_str(&format!(
+                ", {}/s",
+                human_bytes(update.bytes_per_second() as u64)
+            ));
+            if let Some(total) = update.total_objects {
+                line.push_str(&format!(
+                    ", {} of {} objects",
+                    update.objects, total
+                ));
+            }
+        }
+        if update.finished {
+            eprintln!(
+                "\r{:<width$}",
+                format!("{} in {:.1}s", line, update.elapsed.as_secs_f64()),
+                width = LINE_WIDTH
+            );
+        } else {
+            eprint!("\r{:<width$}", line, width = LINE_WIDTH);
+            let _ = io::stderr().flush();
+        }
+    }
+}
+
+pub(super) fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
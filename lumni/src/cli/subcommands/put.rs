@@ -0,0 +1,109 @@
+use clap::{Arg, ArgAction, Command};
+
+use super::plan::dry_run_args;
+pub use super::put_handler::handle_put;
+
+pub fn put_subcommand() -> Command {
+    let command = Command::new("put")
+        .about("Upload a file or stdin to an object store URI")
+        .arg(
+            Arg::new("source")
+                .index(1)
+                .required(true)
+                .help("Local file to upload, or - to read from stdin"),
+        )
+        .arg(
+            Arg::new("target")
+                .index(2)
+                .required(true)
+                .help("Target URI, e.g. s3://bucket/key"),
+        )
+        .arg(
+            Arg::new("content_type")
+                .long("content-type")
+                .help("Content type to store with the object"),
+        )
+        .arg(
+            Arg::new("metadata")
+                .long("metadata")
+                .short('m')
+                .action(ArgAction::Append)
+                .help("Metadata to store with the object, as KEY=VALUE"),
+        )
+        .arg(
+            Arg::new("sse")
+                .long("sse")
+                .value_parser(["AES256", "aws:kms", "customer"])
+                .help(
+                    "Server-side encryption of S3 uploads, overrides S3_SSE \
+                     (customer: SSE-C)",
+                ),
+        )
+        .arg(
+            Arg::new("sse_kms_key_id")
+                .long("sse-kms-key-id")
+                .requires("sse")
+                .help("KMS key for --sse aws:kms, default: the AWS managed key"),
+        )
+        .arg(
+            Arg::new("sse_customer_key_file")
+                .long("sse-customer-key-file")
+                .requires("sse")
+                .help("File with the base64 encoded 256-bit key for --sse customer"),
+        )
+        .arg(
+            Arg::new("if_match")
+                .long("if-match")
+                .value_name("ETAG")
+                .help(
+                    "Only overwrite the object if it still has this ETag, \
+                     e.g. as read before",
+                ),
+        )
+        .arg(
+            Arg::new("if_none_match")
+                .long("if-none-match")
+                .value_name("ETAG")
+                .help(
+                    "Fail if the object has this ETag, * to only create it \
+                     if it does not exist",
+                ),
+        )
+        .arg(
+            Arg::new("part_size")
+                .long("part-size")
+                .value_parser(clap::value_parser!(usize))
+                .help("Size of each upload part in MiB (default: 8)"),
+        )
+        .arg(
+            Arg::new("concurrency")
+                .long("concurrency")
+                .value_parser(clap::value_parser!(usize))
+                .help("Number of parts to upload in parallel (default: 4)"),
+        )
+        .arg(
+            Arg::new("quiet")
+                .long("quiet")
+                .short('q')
+                .action(ArgAction::SetTrue)
+                .help("Do not show upload progress"),
+        );
+    #[cfg(feature = "compression")]
+    let command = command.arg(compress_arg());
+    command.args(dry_run_args())
+}
+
+// the codec can be left out when the target ends with its extension
+#[cfg(feature = "compression")]
+fn compress_arg() -> Arg {
+    Arg::new("compress")
+        .long("compress")
+        .num_args(0..=1)
+        .require_equals(true)
+        .default_missing_value("")
+        .value_name("CODEC")
+        .help(
+            "Compress while uploading, with gzip, zstd, bzip2 or lz4 \
+             [default: by the extension of the target, e.g. .gz]",
+        )
+}
@@ -0,0 +1,171 @@
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::sync::Arc;
+
+#[cfg(feature = "compression")]
+use lumni::Compression;
+use lumni::{
+    Conditions, EnvironmentConfig, ObjectStoreHandler, ParsedUri,
+    PlannedOperation, ProgressTracker, ServerSideEncryption, UploadOptions,
+};
+
+use serde_json::json;
+
+use super::plan::{is_dry_run, print_plan};
+use super::progress::ProgressBar;
+use crate::cli::error::{CliError, ExitCode};
+use crate::cli::json_output::{is_json_output, print_json};
+
+pub async fn handle_put(
+    matches: &clap::ArgMatches,
+    config: &mut EnvironmentConfig,
+) {
+    let source = matches.get_one::<String>("source").unwrap();
+    let target = matches.get_one::<String>("target").unwrap();
+    let quiet = matches.get_flag("quiet");
+
+    // target should start with a scheme, if not add default
+    let target = if target.contains("://") {
+        target.to_string()
+    } else {
+        format!("localfs://{}", target)
+    };
+
+    let options = match upload_options(matches, &target) {
+        Ok(options) => options,
+        Err(err) => CliError::usage(err).exit(),
+    };
+
+    // size of stdin is unknown until it is read
+    let size = match source.as_str() {
+        "-" => None,
+        path => fs::metadata(path).ok().map(|m| m.len()),
+    };
+    if is_dry_run(matches) {
+        let operation = PlannedOperation::new("upload", &target)
+            .set_source(source)
+            .set_size(size);
+        if let Err(err) = print_plan(matches, vec![operation]) {
+            CliError::general(err).exit();
+        }
+        return;
+    }
+
+    // stdin has no known size, the upload is split in parts as it is read
+    let mut reader: Box<dyn Read> = if source == "-" {
+        Box::new(io::stdin())
+    } else {
+        match File::open(source) {
+            Ok(file) => Box::new(file),
+            Err(err) => {
+                let exit_code = match err.kind() {
+                    io::ErrorKind::NotFound => ExitCode::NotFound,
+                    _ => ExitCode::GeneralError,
+                };
+                CliError::new(exit_code, format!("{}: {}", source, err)).exit();
+            }
+        }
+    };
+
+    let tracker = Arc::new(
+        ProgressTracker::new("upload", Arc::new(ProgressBar::new("Uploaded")))
+            .set_total_bytes(size),
+    );
+    let callback = (!quiet).then(|| tracker.binary_callback());
+
+    let handler = ObjectStoreHandler::new(None);
+    match handler
+        .put_object(
+            &ParsedUri::from_uri(&target, false),
+            config,
+            &mut reader,
+            &options,
+            callback,
+        )
+        .await
+    {
+        Ok(size) => {
+            if !quiet {
+                tracker.finish();
+            }
+            if is_json_output() {
+                print_json(&json!({ "uploaded": target, "size": size }));
+            } else if !quiet {
+                eprintln!("Uploaded {} bytes to {}", size, target);
+            }
+        }
+        Err(err) => {
+            if !quiet {
+                eprintln!();
+            }
+            CliError::from(err).exit();
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "compression"), allow(unused_variables))]
+fn upload_options(
+    matches: &clap::ArgMatches,
+    target: &str,
+) -> Result<UploadOptions, String> {
+    let mut options = UploadOptions::new();
+    if let Some(part_size) = matches.get_one::<usize>("part_size") {
+        options = options.set_part_size(part_size * 1024 * 1024);
+    }
+    if let Some(concurrency) = matches.get_one::<usize>("concurrency") {
+        options = options.set_concurrency(*concurrency);
+    }
+    if let Some(content_type) = matches.get_one::<String>("content_type") {
+        options = options.set_content_type(content_type);
+    }
+    if let Some(metadata) = matches.get_many::<String>("metadata") {
+        for item in metadata {
+            let (key, value) = item.split_once('=').ok_or_else(|| {
+                format!("invalid metadata \"{}\", expected KEY=VALUE", item)
+            })?;
+            options = options.set_metadata(key.trim(), value.trim());
+        }
+    }
+    if let Some(sse) = matches.get_one::<String>("sse") {
+        let encryption = match sse.as_str() {
+            "AES256" => ServerSideEncryption::S3,
+            "aws:kms" => ServerSideEncryption::Kms {
+                key_id: matches.get_one::<String>("sse_kms_key_id").cloned(),
+            },
+            _ => {
+                let path =
+                    matches.get_one::<String>("sse_customer_key_file").ok_or(
+                        "--sse customer requires --sse-customer-key-file",
+                    )?;
+                let key = fs::read_to_string(path)
+                    .map_err(|e| format!("{}: {}", path, e))?;
+                ServerSideEncryption::customer(&key)
+                    .map_err(|e| e.to_string())?
+            }
+        };
+        options = options.set_server_side_encryption(encryption);
+    }
+    let mut conditions = Conditions::new();
+    if let Some(etag) = matches.get_one::<String>("if_match") {
+        conditions = conditions.set_if_match(etag);
+    }
+    if let Some(etag) = matches.get_one::<String>("if_none_match") {
+        conditions = conditions.set_if_none_match(etag);
+    }
+    options = options.set_conditions(conditions);
+    #[cfg(feature = "compression")]
+    if let Some(codec) = matches.get_one::<String>("compress") {
+        let compression = if codec.is_empty() {
+            Compression::from_extension(target).ok_or_else(|| {
+                format!(
+                    "--compress needs a codec, {} has no extension of one",
+                    target
+                )
+            })?
+        } else {
+            Compression::from_name(codec).map_err(|e| e.to_string())?
+        };
+        options = options.set_compression(compression);
+    }
+    Ok(options)
+}
@@ -1,15 +1,28 @@
 use clap::{Arg, Command};
 
+use super::output::output_args;
+#[cfg(feature = "parquet")]
+use super::parquet_output::parquet_output_arg;
 pub use super::query_handler::handle_query;
 
 pub fn query_subcommand() -> Command {
-    Command::new("-Q")
+    let command = Command::new("-Q")
         .long_flag("query")
-        .about("Executes a Query")
+        .alias("query")
+        .about(
+            "Executes a SQL query on an object listing, e.g. \
+             SELECT name, size FROM 's3://bucket/prefix' WHERE size > 1000000 \
+             ORDER BY modified DESC",
+        )
         .arg(
             Arg::new("statement")
                 .index(1)
                 .required(true)
                 .help("Query statement"),
         )
+        .args(output_args());
+
+    #[cfg(feature = "parquet")]
+    let command = command.arg(parquet_output_arg());
+    command
 }
@@ -1,8 +1,13 @@
 use std::sync::Arc;
 
-use log::{debug, error};
+use log::debug;
 use lumni::{EnvironmentConfig, ObjectStoreHandler, TableCallback, TableRow};
 
+use super::output::{ExportCallback, OutputFormat};
+#[cfg(feature = "parquet")]
+use super::parquet_output::{parquet_output, write_parquet_output};
+use crate::cli::error::CliError;
+
 pub async fn handle_query(
     query_matches: &clap::ArgMatches,
     config: &mut EnvironmentConfig,
@@ -14,20 +19,32 @@ pub async fn handle_query(
 
     let handler = ObjectStoreHandler::new(None);
 
-    let callback = Arc::new(PrintCallback);
+    #[cfg(feature = "parquet")]
+    let parquet_uri = parquet_output(query_matches);
+    #[cfg(not(feature = "parquet"))]
+    let parquet_uri: Option<String> = None;
+
+    let output_format =
+        OutputFormat::from_matches(query_matches).unwrap_or_else(|e| e.exit());
+
+    // rows are printed as they are queried, unless written to a file
+    let callback: Option<Arc<dyn TableCallback>> =
+        match (parquet_uri.as_ref(), output_format) {
+            (Some(_), _) => None,
+            (None, OutputFormat::Table) => Some(Arc::new(PrintCallback)),
+            (None, format) => Some(Arc::new(ExportCallback::new(format))),
+        };
     // Execute the SQL query through the ObjectStoreHandler
     // Assuming `execute_query` can utilize the same `ListObjectsResult` for its output
-    match handler
-        .execute_query(statement, config, Some(callback))
-        .await
-    {
-        Ok(_) => {
+    match handler.execute_query(statement, config, callback).await {
+        Ok(_table) => {
+            #[cfg(feature = "parquet")]
+            if let Some(uri) = parquet_uri {
+                write_parquet_output(_table.as_ref(), &uri, config).await;
+            }
             debug!("Query executed successfully with no return value.");
         }
-        Err(err) => {
-            error!("Error executing query: {}", err);
-            std::process::exit(1);
-        }
+        Err(err) => CliError::from(err).exit(),
     }
 }
 struct PrintCallback;
@@ -0,0 +1,25 @@
+use clap::{Arg, ArgAction, Command};
+
+use super::confirm::confirm_args;
+use super::plan::dry_run_args;
+pub use super::rb_handler::handle_rb;
+
+pub fn rb_subcommand() -> Command {
+    Command::new("rb")
+        .about("Remove an empty bucket, or a directory on the local filesystem")
+        .arg(
+            Arg::new("uri")
+                .index(1)
+                .required(true)
+                .help("URI of the bucket, e.g. s3://bucket"),
+        )
+        .arg(
+            Arg::new("recursive")
+                .long("recursive")
+                .short('r')
+                .action(ArgAction::SetTrue)
+                .help("Delete all objects in the bucket first"),
+        )
+        .args(confirm_args())
+        .args(dry_run_args())
+}
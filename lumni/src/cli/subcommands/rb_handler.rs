@@ -0,0 +1,104 @@
+use std::cell::RefCell;
+
+use lumni::{
+    EnvironmentConfig, LumniError, ObjectStoreHandler, ParsedUri,
+    PlannedOperation,
+};
+use serde_json::json;
+
+use super::confirm::ConfirmMode;
+use super::mb_handler::bucket_uri;
+use super::plan::{is_dry_run, print_plan};
+use crate::cli::error::CliError;
+use crate::cli::json_output::print_result;
+
+pub async fn handle_rb(
+    matches: &clap::ArgMatches,
+    config: &mut EnvironmentConfig,
+) {
+    let uri = bucket_uri(matches.get_one::<String>("uri").unwrap());
+    let recursive = matches.get_flag("recursive");
+    let mode = ConfirmMode::from_matches(matches);
+    let parsed_uri = ParsedUri::from_uri(&uri, false);
+    let handler = ObjectStoreHandler::new(None);
+
+    if is_dry_run(matches) {
+        plan_rb(matches, config, &handler, &parsed_uri, &uri, recursive).await;
+        return;
+    }
+
+    let question = match recursive {
+        true => format!("Delete {} and all objects in it?", uri),
+        false => format!("Remove {}?", uri),
+    };
+    // only a bucket with its objects is confirmed by default
+    let confirmed = match mode {
+        ConfirmMode::Interactive => mode.confirm_item(&question),
+        _ if recursive => mode.confirm_bulk(&question),
+        _ => Ok(true),
+    };
+    match confirmed {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(err) => err.exit(),
+    }
+
+    match handler.delete_bucket(&parsed_uri, config, recursive).await {
+        Ok(()) => print_result(json!({ "remove_bucket": uri }), || {
+            format!("remove_bucket: {}", uri)
+        }),
+        Err(LumniError::NotFound(_)) if mode.is_force() => {}
+        Err(err) => CliError::from(err).exit(),
+    }
+}
+
+// the objects in the bucket that -r deletes first, then the bucket
+async fn plan_rb(
+    matches: &clap::ArgMatches,
+    config: &EnvironmentConfig,
+    handler: &ObjectStoreHandler,
+    parsed_uri: &ParsedUri,
+    uri: &str,
+    recursive: bool,
+) {
+    // keys are relative to the bucket, which is the root directory for
+    // the local filesystem
+    let bucket_root = format!(
+        "{}://{}",
+        parsed_uri.scheme.to_string(),
+        parsed_uri
+            .bucket
+            .as_deref()
+            .unwrap_or_default()
+            .trim_end_matches('/')
+    );
+    let planned = RefCell::new(Vec::new());
+    if recursive {
+        // every key is recorded and declined, so nothing is deleted
+        let plan_delete = |key: &str| {
+            planned.borrow_mut().push(PlannedOperation::new(
+                "delete",
+                &format!("{}/{}", bucket_root, key),
+            ));
+            false
+        };
+        match handler
+            .delete_objects(parsed_uri, config, true, Some(&plan_delete))
+            .await
+        {
+            Ok(_) => {}
+            Err(LumniError::NotFound(_))
+                if ConfirmMode::from_matches(matches).is_force() =>
+            {
+                return
+            }
+            Err(err) => CliError::from(err).exit(),
+        }
+    }
+    planned
+        .borrow_mut()
+        .push(PlannedOperation::new("remove_bucket", uri));
+    if let Err(err) = print_plan(matches, planned.take()) {
+        CliError::general(err).exit();
+    }
+}
@@ -23,4 +23,25 @@ pub fn request_subcommand() -> Command {
                 .required(true)
                 .help("URI for the HTTP request"),
         )
+        .arg(
+            Arg::new("if_none_match")
+                .long("if-none-match")
+                .value_name("ETAG")
+                .help("Only GET the object if its ETag is another one"),
+        )
+        .arg(
+            Arg::new("if_modified_since")
+                .long("if-modified-since")
+                .value_name("TIME")
+                .help(
+                    "Only GET the object if it was modified after TIME, e.g. \
+                     2024-05-01T12:00:00Z",
+                ),
+        )
+        .arg(
+            Arg::new("if_match")
+                .long("if-match")
+                .value_name("ETAG")
+                .help("Fail unless the object has this ETag"),
+        )
 }
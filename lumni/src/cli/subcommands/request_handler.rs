@@ -5,10 +5,13 @@ use std::sync::{Arc, Mutex};
 #[cfg(feature = "http_client")]
 use lumni::HttpHandler;
 use lumni::{
-    BinaryCallbackWrapper, EnvironmentConfig, ObjectStoreHandler, ParsedUri,
-    UriScheme,
+    BinaryCallbackWrapper, Conditions, EnvironmentConfig, ObjectStoreHandler,
+    ParsedUri, UriScheme,
 };
 
+use super::ls_handler::parse_time;
+use crate::cli::error::CliError;
+
 pub async fn handle_request(
     matches: &clap::ArgMatches,
     config: &mut EnvironmentConfig,
@@ -24,7 +27,8 @@ pub async fn handle_request(
     println!("Handling request: {} {}", method, uri);
     match method.as_str() {
         "GET" => {
-            handle_get_request(uri, config, output_file).await;
+            let conditions = conditions(matches);
+            handle_get_request(uri, config, &conditions, output_file).await;
         }
         "PUT" => {
             println!("PUT request not yet implemented");
@@ -39,14 +43,32 @@ pub async fn handle_request(
             println!("LIST request not yet implemented");
         }
         _ => {
-            eprintln!("Invalid HTTP method: {}", method);
+            CliError::usage(format!("Invalid HTTP method: {}", method)).exit();
         }
     }
 }
 
+// e.g. --if-none-match with the ETag of a copy, to not read it again
+fn conditions(matches: &clap::ArgMatches) -> Conditions {
+    let mut conditions = Conditions::new();
+    if let Some(etag) = matches.get_one::<String>("if_match") {
+        conditions = conditions.set_if_match(etag);
+    }
+    if let Some(etag) = matches.get_one::<String>("if_none_match") {
+        conditions = conditions.set_if_none_match(etag);
+    }
+    if let Some(since) = matches.get_one::<String>("if_modified_since") {
+        let since = parse_time(since, "--if-modified-since")
+            .unwrap_or_else(|err| CliError::usage(err).exit());
+        conditions = conditions.set_if_modified_since(since);
+    }
+    conditions
+}
+
 async fn handle_get_request(
     uri: &str,
     config: &EnvironmentConfig,
+    conditions: &Conditions,
     output_path: Option<&str>,
 ) {
     let callback = if let Some(output_path) = output_path {
@@ -75,32 +97,63 @@ async fn handle_get_request(
     println!("Parsed URI: {}", parsed_uri.to_string());
 
     match parsed_uri.scheme {
-        UriScheme::S3 | UriScheme::LocalFs => {
-            // Handler logic for both S3 and LocalFs
+        UriScheme::S3
+        | UriScheme::GCS
+        | UriScheme::Azure
+        | UriScheme::Abfss
+        | UriScheme::Hdfs
+        | UriScheme::LocalFs
+        | UriScheme::Registered(_) => {
+            // Handler logic for object stores
             let handler = ObjectStoreHandler::new(None);
-            if let Err(err) =
-                handler.get_object(&parsed_uri, config, callback).await
-            {
-                eprintln!("Error: {:?}", err);
+            match handler.get_object_if(&parsed_uri, config, conditions).await {
+                Ok(data) => write_data(uri, data, callback).await,
+                Err(err) => CliError::from(err).exit(),
             }
         }
         #[cfg(feature = "http_client")] // HTTP client feature enabled
         UriScheme::Http | UriScheme::Https => {
-            let handler = HttpHandler::new(callback);
-            if let Err(err) = handler.get(uri).await {
-                eprintln!("Error: {:?}", err);
+            // without a callback, None tells the data is not modified
+            let handler =
+                HttpHandler::new(None).set_conditions(conditions.clone());
+            match handler.get(uri).await {
+                Ok(data) => write_data(uri, data, callback).await,
+                Err(err) => CliError::from(err).exit(),
             }
         }
         #[cfg(not(feature = "http_client"))] // HTTP client feature not enabled
         UriScheme::Http | UriScheme::Https => {
-            eprintln!(
+            CliError::usage(
                 "HTTP and HTTPS support is not enabled. Please enable the \
-                 `http_client` feature to use this functionality."
-            );
+                 `http_client` feature to use this functionality.",
+            )
+            .exit();
         }
         _ => {
             // Handle unsupported schemes
-            eprintln!("Unsupported scheme: {}", parsed_uri.scheme.to_string());
+            CliError::usage(format!(
+                "Unsupported scheme: {}",
+                parsed_uri.scheme.to_string()
+            ))
+            .exit();
+        }
+    }
+}
+
+// None if the object is not modified since the copy the conditions refer
+// to, which is not an error
+async fn write_data(
+    uri: &str,
+    data: Option<Vec<u8>>,
+    callback: Option<BinaryCallbackWrapper>,
+) {
+    match (data, callback) {
+        (Some(data), Some(callback)) => {
+            if let Err(err) = callback.call(data).await {
+                CliError::from(err).exit();
+            }
         }
+        (None, _) => eprintln!("Not modified: {}", uri),
+        (Some(_), None) => {}
     }
 }
@@ -0,0 +1,41 @@
+use clap::{Arg, Command};
+
+use super::plan::dry_run_args;
+pub use super::restore_handler::handle_restore;
+
+pub fn restore_subcommand() -> Command {
+    Command::new("restore")
+        .about(
+            "Restore an archived S3 object (GLACIER, DEEP_ARCHIVE) so it can \
+             be read for a number of days",
+        )
+        .arg(
+            Arg::new("uri")
+                .index(1)
+                .required(true)
+                .help("URI of the object, e.g. s3://bucket/key"),
+        )
+        .arg(
+            Arg::new("days")
+                .long("days")
+                .value_parser(clap::value_parser!(u32).range(1..))
+                .default_value("1")
+                .help("Days the restored copy stays readable"),
+        )
+        .arg(
+            Arg::new("tier")
+                .long("tier")
+                .value_parser(["expedited", "standard", "bulk"])
+                .default_value("standard")
+                .help(
+                    "Retrieval tier, from minutes (expedited) to hours \
+                     (bulk), faster tiers cost more",
+                ),
+        )
+        .arg(
+            Arg::new("version_id")
+                .long("version-id")
+                .help("Version of the object to restore, default: latest"),
+        )
+        .args(dry_run_args())
+}
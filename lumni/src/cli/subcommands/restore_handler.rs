@@ -0,0 +1,72 @@
+use lumni::{
+    EnvironmentConfig, ObjectStoreHandler, ParsedUri, PlannedOperation,
+    RestoreRequest, RestoreStatus, RestoreTier,
+};
+use serde_json::json;
+
+use super::plan::{is_dry_run, print_plan};
+use crate::cli::error::CliError;
+use crate::cli::json_output::print_result;
+
+pub async fn handle_restore(
+    matches: &clap::ArgMatches,
+    config: &mut EnvironmentConfig,
+) {
+    let uri = matches.get_one::<String>("uri").unwrap();
+    let days = *matches.get_one::<u32>("days").unwrap();
+    // value_parser only accepts known tiers
+    let tier = matches
+        .get_one::<String>("tier")
+        .and_then(|tier| RestoreTier::from_name(tier))
+        .unwrap_or_default();
+    let request = RestoreRequest::new(days)
+        .set_tier(tier)
+        .set_version_id(matches.get_one::<String>("version_id").cloned());
+
+    if is_dry_run(matches) {
+        let operation = PlannedOperation::new("restore", uri);
+        if let Err(err) = print_plan(matches, vec![operation]) {
+            CliError::general(err).exit();
+        }
+        return;
+    }
+
+    let parsed_uri = ParsedUri::from_uri(uri, false);
+    let handler = ObjectStoreHandler::new(None);
+    let status =
+        match handler.restore_object(&parsed_uri, config, &request).await {
+            Ok(status) => status,
+            Err(err) => CliError::from(err).exit(),
+        };
+    let result = |status: &str| {
+        json!({
+            "uri": uri,
+            "status": status,
+            "tier": tier.as_str(),
+            "days": days,
+        })
+    };
+    match status {
+        RestoreStatus::Started => print_result(result("started"), || {
+            format!(
+                "Restore of {} started ({}), readable for {} days once done",
+                uri,
+                tier.as_str(),
+                days
+            )
+        }),
+        RestoreStatus::InProgress => {
+            print_result(result("in_progress"), || {
+                format!("Restore of {} is already in progress", uri)
+            })
+        }
+        RestoreStatus::AlreadyRestored => {
+            print_result(result("already_restored"), || {
+                format!(
+                    "{} is already restored, now readable for {} days",
+                    uri, days
+                )
+            })
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use clap::{Arg, ArgAction, Command};
+
+use super::confirm::confirm_args;
+use super::plan::dry_run_args;
+pub use super::rm_handler::handle_rm;
+
+pub fn rm_subcommand() -> Command {
+    Command::new("rm")
+        .about("Delete objects from an object store URI")
+        .arg(
+            Arg::new("uri")
+                .index(1)
+                .required(true)
+                .help("URI of the object, or the prefix with --recursive"),
+        )
+        .arg(
+            Arg::new("recursive")
+                .long("recursive")
+                .short('r')
+                .action(ArgAction::SetTrue)
+                .help("Delete all objects under the prefix"),
+        )
+        .args(confirm_args())
+        .args(dry_run_args())
+}
@@ -0,0 +1,109 @@
+use std::cell::RefCell;
+
+use lumni::{
+    EnvironmentConfig, LumniError, ObjectStoreHandler, ParsedUri,
+    PlannedOperation,
+};
+
+use serde_json::json;
+
+use super::confirm::ConfirmMode;
+use super::plan::{is_dry_run, print_plan};
+use crate::cli::error::{CliError, ExitCode};
+use crate::cli::json_output::{is_json_output, print_json, print_result};
+
+pub async fn handle_rm(
+    matches: &clap::ArgMatches,
+    config: &mut EnvironmentConfig,
+) {
+    let uri = matches.get_one::<String>("uri").unwrap();
+    let recursive = matches.get_flag("recursive");
+    let mode = ConfirmMode::from_matches(matches);
+
+    // uri should start with a scheme, if not add default
+    let uri = if uri.contains("://") {
+        uri.to_string()
+    } else {
+        format!("localfs://{}", uri)
+    };
+    let parsed_uri = ParsedUri::from_uri(&uri, false);
+    let bucket_uri = format!(
+        "{}://{}",
+        parsed_uri.scheme.to_string(),
+        parsed_uri.bucket.as_deref().unwrap_or_default()
+    );
+
+    // a dry run records every key and declines to delete it
+    let planned = RefCell::new(Vec::new());
+    let plan_delete = |key: &str| {
+        planned.borrow_mut().push(PlannedOperation::new(
+            "delete",
+            &format!("{}/{}", bucket_uri, key),
+        ));
+        false
+    };
+    let prompt_delete = |key: &str| {
+        mode.confirm_item(&format!("Delete {}/{}?", bucket_uri, key))
+            .unwrap_or_else(|err| err.exit())
+    };
+    let confirm: Option<&dyn Fn(&str) -> bool> = if is_dry_run(matches) {
+        Some(&plan_delete)
+    } else if mode == ConfirmMode::Interactive {
+        Some(&prompt_delete)
+    } else {
+        None
+    };
+
+    if recursive && !is_dry_run(matches) {
+        let question = format!("Delete all objects under {}?", uri);
+        match mode.confirm_bulk(&question) {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(err) => err.exit(),
+        }
+    }
+
+    let handler = ObjectStoreHandler::new(None);
+    let result = match handler
+        .delete_objects(&parsed_uri, config, recursive, confirm)
+        .await
+    {
+        Ok(result) => result,
+        // like rm -f, removing what does not exist is not an error
+        Err(LumniError::NotFound(_)) if mode.is_force() => return,
+        Err(err) => CliError::from(err).exit(),
+    };
+
+    if is_dry_run(matches) {
+        if let Err(err) = print_plan(matches, planned.take()) {
+            CliError::general(err).exit();
+        }
+        return;
+    }
+
+    for key in result.deleted() {
+        let uri = format!("{}/{}", bucket_uri, key);
+        print_result(json!({ "deleted": uri }), || format!("deleted: {}", uri));
+    }
+    for (key, reason) in result.failed() {
+        let uri = format!("{}/{}", bucket_uri, key);
+        if is_json_output() {
+            print_json(&json!({ "failed": uri, "reason": reason }));
+        } else {
+            eprintln!("failed: {}: {}", uri, reason);
+        }
+    }
+    if !result.failed().is_empty() {
+        let message = format!(
+            "{} of {} objects could not be deleted",
+            result.failed().len(),
+            result.failed().len() + result.deleted().len()
+        );
+        let exit_code = if result.deleted().is_empty() {
+            ExitCode::GeneralError
+        } else {
+            ExitCode::PartialFailure
+        };
+        CliError::new(exit_code, message).exit();
+    }
+}
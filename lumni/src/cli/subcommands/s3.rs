@@ -0,0 +1,57 @@
+use clap::{Arg, ArgAction, Command};
+
+pub use super::s3_handler::handle_s3;
+
+pub fn s3_subcommand() -> Command {
+    Command::new("s3")
+        .about("S3 specific tools")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("sign")
+                .about(
+                    "Sign a request without sending it, to debug signatures \
+                     that an S3 compatible endpoint rejects",
+                )
+                .arg(
+                    Arg::new("uri")
+                        .index(1)
+                        .required(true)
+                        .help("URI of the request, e.g. s3://bucket/key"),
+                )
+                .arg(
+                    Arg::new("method")
+                        .long("method")
+                        .short('X')
+                        .default_value("GET")
+                        .help("HTTP method of the request"),
+                )
+                .arg(
+                    Arg::new("query")
+                        .long("query")
+                        .help("Query string of the request, e.g. uploads="),
+                )
+                .arg(
+                    Arg::new("header")
+                        .long("header")
+                        .short('H')
+                        .action(ArgAction::Append)
+                        .help(
+                            "Header to sign, as NAME:VALUE. \
+                             x-amz-content-sha256 sets the payload hash",
+                        ),
+                )
+                .arg(Arg::new("date").long("date").help(
+                    "Time to sign at as x-amz-date, e.g. 20130524T000000Z, \
+                     to compare with a rejected request [default: now]",
+                ))
+                .arg(
+                    Arg::new("debug")
+                        .long("debug")
+                        .action(ArgAction::SetTrue)
+                        .help(
+                            "Also print the canonical request and the string \
+                             to sign",
+                        ),
+                ),
+        )
+}
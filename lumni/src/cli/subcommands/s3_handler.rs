@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use lumni::{EnvironmentConfig, ObjectStoreHandler, ParsedUri, SignedRequest};
+use serde_json::json;
+
+use crate::cli::error::CliError;
+use crate::cli::json_output::{is_json_output, print_json};
+
+// not printed in full, unlike the signature it is a credential
+const SECURITY_TOKEN_HEADER: &str = "x-amz-security-token";
+
+pub async fn handle_s3(
+    matches: &clap::ArgMatches,
+    config: &mut EnvironmentConfig,
+) {
+    match matches.subcommand() {
+        Some(("sign", matches)) => handle_sign(matches, config).await,
+        _ => unreachable!("subcommand_required(true) not defined"),
+    }
+}
+
+async fn handle_sign(matches: &clap::ArgMatches, config: &EnvironmentConfig) {
+    let uri = matches.get_one::<String>("uri").unwrap();
+    let method = matches.get_one::<String>("method").unwrap().to_uppercase();
+    let query = matches.get_one::<String>("query").map(String::as_str);
+    let date = matches.get_one::<String>("date").map(String::as_str);
+
+    let mut headers = HashMap::new();
+    for header in matches.get_many::<String>("header").unwrap_or_default() {
+        let Some((name, value)) = header.split_once(':') else {
+            CliError::usage(format!(
+                "invalid header \"{}\", expected NAME:VALUE",
+                header
+            ))
+            .exit();
+        };
+        headers.insert(name.trim().to_string(), value.trim().to_string());
+    }
+
+    let parsed_uri = ParsedUri::from_uri(uri, false);
+    let signed = ObjectStoreHandler::new(None)
+        .sign_request(&parsed_uri, config, &method, query, &headers, date)
+        .await
+        .unwrap_or_else(|e| CliError::from(e).exit());
+    print_signed_request(&signed, matches.get_flag("debug"));
+}
+
+fn print_signed_request(signed: &SignedRequest, debug: bool) {
+    let mut headers: Vec<(&String, String)> = signed
+        .headers
+        .iter()
+        .map(|(name, value)| match name.as_str() {
+            SECURITY_TOKEN_HEADER => (name, redact(value)),
+            _ => (name, value.clone()),
+        })
+        .collect();
+    headers.sort_by_key(|(name, _)| name.to_lowercase());
+
+    if is_json_output() {
+        let mut result = json!({
+            "method": signed.method,
+            "url": signed.url,
+            "headers": headers
+                .into_iter()
+                .collect::<HashMap<_, _>>(),
+        });
+        if debug {
+            result["canonical_request"] = json!(signed.canonical_request);
+            result["string_to_sign"] = json!(signed.string_to_sign);
+        }
+        print_json(&result);
+        return;
+    }
+    if debug {
+        println!("Canonical request:\n{}\n", signed.canonical_request);
+        println!("String to sign:\n{}\n", signed.string_to_sign);
+        println!("Headers:");
+    }
+    println!("{} {}", signed.method, signed.url);
+    for (name, value) in headers {
+        println!("{}: {}", name, value);
+    }
+}
+
+fn redact(value: &str) -> String {
+    match value.char_indices().nth(8) {
+        Some((end, _)) => format!("{}...", &value[..end]),
+        None => "...".to_string(),
+    }
+}
@@ -0,0 +1,29 @@
+use clap::{Arg, ArgAction, Command};
+
+use super::output::output_args;
+pub use super::stat_handler::handle_stat;
+
+pub fn stat_subcommand() -> Command {
+    Command::new("stat")
+        .about(
+            "Show properties of an object: size, content type, storage \
+             class, metadata and tags",
+        )
+        .arg(
+            Arg::new("uri")
+                .index(1)
+                .required(true)
+                .help("URI of the object, e.g. s3://bucket/key"),
+        )
+        .arg(
+            Arg::new("tag")
+                .long("tag")
+                .action(ArgAction::Append)
+                .value_name("KEY=VALUE")
+                .help(
+                    "Replace the tags of the object before showing it, can \
+                     be given multiple times",
+                ),
+        )
+        .args(output_args())
+}
@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use lumni::{
+    EnvironmentConfig, ObjectMetadataTable, ObjectStoreHandler, ParsedUri,
+    Table,
+};
+
+use super::output::{ExportCallback, OutputFormat};
+use crate::cli::error::CliError;
+
+pub async fn handle_stat(
+    matches: &clap::ArgMatches,
+    config: &mut EnvironmentConfig,
+) {
+    let uri = matches.get_one::<String>("uri").unwrap();
+    let output_format =
+        OutputFormat::from_matches(matches).unwrap_or_else(|e| e.exit());
+    let tags = matches
+        .get_many::<String>("tag")
+        .map(|values| parse_tags(values).unwrap_or_else(|e| e.exit()));
+
+    // uri should start with a scheme, if not add default
+    let uri = if uri.contains("://") {
+        uri.to_string()
+    } else {
+        format!("localfs://{}", uri)
+    };
+    let parsed_uri = ParsedUri::from_uri(&uri, false);
+    let handler = ObjectStoreHandler::new(None);
+    if let Some(tags) = tags {
+        if let Err(err) =
+            handler.set_object_tags(&parsed_uri, config, &tags).await
+        {
+            CliError::from(err).exit();
+        }
+    }
+    let metadata = match handler.get_object_metadata(&parsed_uri, config).await
+    {
+        Ok(metadata) => metadata,
+        Err(err) => CliError::from(err).exit(),
+    };
+
+    let mut table = ObjectMetadataTable::new();
+    table.set_callback(Arc::new(ExportCallback::new(output_format)));
+    if let Err(err) = table.add_metadata(metadata) {
+        CliError::general(err).exit();
+    }
+}
+
+fn parse_tags<'a>(
+    values: impl Iterator<Item = &'a String>,
+) -> Result<HashMap<String, String>, CliError> {
+    values
+        .map(|value| match value.split_once('=') {
+            Some((key, value)) if !key.is_empty() => {
+                Ok((key.to_string(), value.to_string()))
+            }
+            _ => Err(CliError::usage(format!(
+                "Tag must be given as KEY=VALUE, got '{}'",
+                value
+            ))),
+        })
+        .collect()
+}
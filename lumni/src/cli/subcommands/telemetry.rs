@@ -0,0 +1,35 @@
+use clap::{Arg, Command};
+
+pub use super::telemetry_handler::handle_telemetry;
+
+pub fn telemetry_subcommand() -> Command {
+    Command::new("telemetry")
+        .about(
+            "Show or change the anonymous usage statistics, which are off \
+             until enabled",
+        )
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("status")
+                .about("Show the settings and the data that is sent"),
+        )
+        .subcommand(
+            Command::new("enable")
+                .about("Count the use of subcommands and apps")
+                .arg(
+                    Arg::new("endpoint")
+                        .long("endpoint")
+                        .help("URL the counts are sent to, as JSON"),
+                )
+                .arg(
+                    Arg::new("batch_size")
+                        .long("batch-size")
+                        .value_parser(clap::value_parser!(u64))
+                        .help("Uses that are counted before a report is sent"),
+                ),
+        )
+        .subcommand(
+            Command::new("disable")
+                .about("Stop counting, and drop the counts not sent yet"),
+        )
+}
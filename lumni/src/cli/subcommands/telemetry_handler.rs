@@ -0,0 +1,52 @@
+use lumni::{EnvironmentConfig, Telemetry};
+
+use serde_json::json;
+
+use crate::cli::error::{CliError, ExitCode};
+use crate::cli::json_output::print_result;
+
+pub async fn handle_telemetry(
+    matches: &clap::ArgMatches,
+    _config: &mut EnvironmentConfig,
+) {
+    let mut telemetry = Telemetry::load().unwrap_or_else(|e| {
+        CliError::from(e).exit();
+    });
+    match matches.subcommand() {
+        Some(("status", _)) => {
+            print_status(&telemetry);
+            return;
+        }
+        Some(("enable", matches)) => {
+            if let Some(endpoint) = matches.get_one::<String>("endpoint") {
+                telemetry.set_endpoint(Some(endpoint.to_string()));
+            }
+            if let Some(batch_size) = matches.get_one::<u64>("batch_size") {
+                telemetry.set_batch_size(*batch_size);
+            }
+            if telemetry.endpoint().is_none() {
+                CliError::new(
+                    ExitCode::ConfigError,
+                    "Telemetry needs an endpoint to send to, see --endpoint",
+                )
+                .exit();
+            }
+            telemetry.set_enabled(true);
+        }
+        Some(("disable", _)) => telemetry.set_enabled(false),
+        _ => unreachable!("subcommand_required(true) not defined"),
+    }
+    if let Err(e) = telemetry.save() {
+        CliError::from(e).exit();
+    }
+    print_status(&telemetry);
+}
+
+fn print_status(telemetry: &Telemetry) {
+    let result = json!({
+        "enabled": telemetry.is_enabled(),
+        "endpoint": telemetry.endpoint(),
+        "next_report": telemetry.report(),
+    });
+    print_result(result, || telemetry.status());
+}
@@ -0,0 +1,34 @@
+use clap::{Arg, ArgAction, Command};
+
+pub use super::watch_handler::handle_watch;
+
+pub fn watch_subcommand() -> Command {
+    Command::new("watch")
+        .about(
+            "Print files as they are created, modified or deleted, until \
+             interrupted. S3 buckets are watched through an SQS queue that \
+             receives their event notifications",
+        )
+        .arg(
+            Arg::new("uri")
+                .index(1)
+                .default_value(".")
+                .help("Directory or bucket to watch. E.g. ./data, s3://bucket"),
+        )
+        .arg(
+            Arg::new("name").long("name").short('n').help(
+                "Filter files based on name. E.g. 'foo', 'foo.*', '.*bar'",
+            ),
+        )
+        .arg(
+            Arg::new("recursive")
+                .long("recursive")
+                .short('r')
+                .action(ArgAction::SetTrue)
+                .help("Watch subdirectories too"),
+        )
+        .arg(Arg::new("queue-url").long("queue-url").help(
+            "SQS queue with the event notifications of the bucket, default: \
+             S3_SQS_QUEUE_URL. Handled messages are deleted",
+        ))
+}
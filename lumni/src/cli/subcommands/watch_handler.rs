@@ -0,0 +1,68 @@
+use std::sync::Arc;
+
+use lumni::{
+    EnvironmentConfig, FileObjectFilter, ObjectStoreHandler, ParsedUri,
+    WatchCallback, WatchEvent,
+};
+
+use serde_json::json;
+
+use crate::cli::error::CliError;
+use crate::cli::json_output::print_result;
+
+struct PrintCallback;
+
+impl WatchCallback for PrintCallback {
+    fn on_event(&self, event: &WatchEvent) -> bool {
+        let file_object = event.file_object();
+        let result = json!({
+            "event": event.kind().as_str(),
+            "size": file_object.size(),
+            "name": file_object.name(),
+        });
+        print_result(result, || {
+            format!(
+                "{:8} {:>10} {}",
+                event.kind().as_str(),
+                file_object.size(),
+                file_object.name()
+            )
+        });
+        true
+    }
+}
+
+pub async fn handle_watch(
+    matches: &clap::ArgMatches,
+    config: &mut EnvironmentConfig,
+) {
+    let uri = matches.get_one::<String>("uri").unwrap();
+    let uri = if uri.contains("://") {
+        uri.to_string()
+    } else {
+        format!("localfs://{}", uri)
+    };
+    let filter = matches.get_one::<String>("name").map(|name| {
+        FileObjectFilter::new(Some(name), None, None)
+            .unwrap_or_else(|e| CliError::usage(&e).exit())
+    });
+
+    if let Some(queue_url) = matches.get_one::<String>("queue-url") {
+        config.insert("S3_SQS_QUEUE_URL".to_string(), queue_url.to_string());
+    }
+
+    let parsed_uri = ParsedUri::from_uri(&uri, true);
+    let handler = ObjectStoreHandler::new(None);
+    if let Err(err) = handler
+        .watch(
+            &parsed_uri,
+            config,
+            matches.get_flag("recursive"),
+            &filter,
+            Arc::new(PrintCallback),
+        )
+        .await
+    {
+        CliError::from(err).exit();
+    }
+}
@@ -1,2 +1,14 @@
 pub const AWS_DEFAULT_REGION: &str = "us-east-1";
 pub const AWS_MAX_LIST_OBJECTS: u32 = 1000;
+// max_keys that lists every page, without max_keys S3, GCS and Azure
+// stop after a single page of keys
+pub const LIST_ALL_KEYS: u32 = u32::MAX;
+pub const AWS_MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+pub const AWS_MAX_PARTS: usize = 10_000;
+// presigned URLs are valid for at most 7 days
+pub const AWS_MAX_PRESIGN_EXPIRY_SECONDS: u64 = 7 * 24 * 3600;
+pub const DEFAULT_UPLOAD_PART_SIZE: usize = 8 * 1024 * 1024;
+pub const DEFAULT_UPLOAD_CONCURRENCY: usize = 4;
+pub const DEFAULT_LIST_CONCURRENCY: usize = 8;
+pub const DEFAULT_DOWNLOAD_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+pub const DEFAULT_PRESIGN_EXPIRY_SECONDS: u64 = 3600;
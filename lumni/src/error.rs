@@ -1,98 +1,338 @@
 use std::error::Error;
 use std::{fmt, io};
 
+use serde::Deserialize;
 use url::ParseError;
 
+use crate::apps::api::error::{ApplicationError, RequestError, RuntimeError};
+
+// statuses of throttling (e.g. LLM provider 429) and transient server
+// errors (e.g. S3 503 SlowDown), a request may succeed when repeated
+#[cfg(feature = "http_client")]
+pub(crate) const RETRYABLE_STATUS: [u16; 5] = [429, 500, 502, 503, 504];
+
+// the error of all public APIs. backends map their failures to the typed
+// variants, so callers can act on the kind of error without parsing
+// messages, see is_retryable()
 #[derive(Debug)]
-pub enum LakestreamError {
+pub enum LumniError {
+    NotFound(String),
+    AccessDenied(String),
+    Throttled(String),
+    Network(String), // no response, or a transient server error
+    // a condition of the request did not hold, e.g. If-Match of a write
+    PreconditionFailed(String),
+    Config(String),
+    NoBucketInUri(String),
+    Internal(String),
+    NotImplemented(String),
+    Message(String),
+    // an error with what was being done when it happened, e.g. the uri
+    Context(String, Box<LumniError>),
     Io(io::Error),
     Parse(ParseError),
-    String(String),
-    ConfigError(String),
-    NoBucketInUri(String),
-    AccessDenied(String),
-    InternalError(String),
-    NotFound(String),
     Anyhow(anyhow::Error),
     Wrapped(Box<dyn Error + 'static>),
     #[cfg(target_arch = "wasm32")]
     Js(wasm_bindgen::JsValue),
     #[cfg(feature = "http_client")]
     HttpClientError(crate::http::client::HttpClientError),
+    Request(RequestError),
+    Runtime(RuntimeError),
+    Application(ApplicationError, Option<String>), // and the app name
+    Invoke(ApplicationError, Option<String>),
 }
 
-impl fmt::Display for LakestreamError {
+impl LumniError {
+    // the error of an unexpected response status of a backend. The code
+    // and message of an XML error body are kept, e.g. to tell an expired
+    // token from a signature mismatch
+    pub fn from_status(status: u16, resource: &str, body: &str) -> Self {
+        let (resource, body) = match error_details(body) {
+            Some(details) => (format!("{} ({})", resource, details), ""),
+            None => (resource.to_string(), body),
+        };
+        match status {
+            401 | 403 => LumniError::AccessDenied(resource),
+            404 => LumniError::NotFound(resource),
+            412 => LumniError::PreconditionFailed(resource),
+            429 | 503 => LumniError::Throttled(resource),
+            500 | 502 | 504 => LumniError::Network(format!(
+                "Server error {} for {}",
+                status, resource
+            )),
+            _ if body.is_empty() => LumniError::Internal(format!(
+                "Unexpected status code {} for {}",
+                status, resource
+            )),
+            _ => LumniError::Internal(format!(
+                "Unexpected status code {} for {}: {}",
+                status, resource, body
+            )),
+        }
+    }
+
+    pub fn context(self, context: impl Into<String>) -> Self {
+        LumniError::Context(context.into(), Box::new(self))
+    }
+
+    // the error itself, without the context added to it
+    pub fn root(&self) -> &LumniError {
+        match self {
+            LumniError::Context(_, error) => error.root(),
+            error => error,
+        }
+    }
+
+    // a hint that the same request may succeed later
+    pub fn is_retryable(&self) -> bool {
+        match self.root() {
+            LumniError::Throttled(_) | LumniError::Network(_) => true,
+            LumniError::Io(e) => matches!(
+                e.kind(),
+                io::ErrorKind::TimedOut
+                    | io::ErrorKind::Interrupted
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::BrokenPipe
+            ),
+            #[cfg(feature = "http_client")]
+            LumniError::HttpClientError(e) => e.is_retryable(),
+            #[cfg(feature = "http_client")]
+            LumniError::Application(
+                ApplicationError::HttpClientError(e),
+                _,
+            ) => e.is_retryable(),
+            _ => false,
+        }
+    }
+}
+
+// allow non snake case for the XML response
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    Code: Option<String>,
+    Message: Option<String>,
+}
+
+// "Code: Message" of an S3 error response, Azure uses the same elements
+fn error_details(body: &str) -> Option<String> {
+    if !body.trim_start().starts_with('<') {
+        return None;
+    }
+    let response: ErrorResponse = serde_xml_rs::from_str(body).ok()?;
+    match (response.Code, response.Message) {
+        (Some(code), Some(message)) => Some(format!("{}: {}", code, message)),
+        (Some(code), None) => Some(code),
+        (None, Some(message)) => Some(message),
+        (None, None) => None,
+    }
+}
+
+impl fmt::Display for LumniError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            LakestreamError::Io(e) => write!(f, "{}", e),
-            LakestreamError::Parse(e) => write!(f, "{}", e),
-            LakestreamError::String(s) => write!(f, "{}", s),
-            LakestreamError::ConfigError(s) => write!(f, "Config error: {}", s),
-            LakestreamError::Wrapped(e) => write!(f, "{}", e),
-            LakestreamError::NoBucketInUri(s) => {
-                write!(f, "No bucket specified in URI: {}", s)
+            LumniError::NotFound(s) => write!(f, "Not found: {}", s),
+            LumniError::AccessDenied(s) => write!(f, "Access denied: {}", s),
+            LumniError::Throttled(s) => {
+                write!(f, "Too many requests: {}", s)
             }
-            LakestreamError::AccessDenied(s) => {
-                write!(f, "Access denied: {}", s)
+            LumniError::Network(s) => write!(f, "Network error: {}", s),
+            LumniError::PreconditionFailed(s) => {
+                write!(f, "Precondition failed: {}", s)
             }
-            LakestreamError::InternalError(s) => {
-                write!(f, "Internal error: {}", s)
+            LumniError::Config(s) => write!(f, "Config error: {}", s),
+            LumniError::NoBucketInUri(s) => {
+                write!(f, "No bucket specified in URI: {}", s)
             }
-            LakestreamError::Anyhow(e) => write!(f, "Anyhow error: {}", e),
-            LakestreamError::NotFound(s) => write!(f, "Not found: {}", s),
+            LumniError::Internal(s) => write!(f, "Internal error: {}", s),
+            LumniError::NotImplemented(s) => write!(f, "NotImplemented: {}", s),
+            LumniError::Message(s) => write!(f, "{}", s),
+            LumniError::Context(context, e) => write!(f, "{}: {}", context, e),
+            LumniError::Io(e) => write!(f, "{}", e),
+            LumniError::Parse(e) => write!(f, "{}", e),
+            LumniError::Anyhow(e) => write!(f, "Anyhow error: {}", e),
+            LumniError::Wrapped(e) => write!(f, "{}", e),
             #[cfg(target_arch = "wasm32")]
-            LakestreamError::Js(e) => write!(
+            LumniError::Js(e) => write!(
                 f,
                 "JsError: {}",
                 e.as_string().unwrap_or_else(|| "Unknown error".to_string())
             ),
             #[cfg(feature = "http_client")]
-            LakestreamError::HttpClientError(e) => write!(f, "{}", e),
+            LumniError::HttpClientError(e) => write!(f, "{}", e),
+            LumniError::Request(req_err) => {
+                write!(f, "RequestError: {}", req_err)
+            }
+            LumniError::Runtime(runtime_err) => {
+                write!(f, "RuntimeError: {}", runtime_err)
+            }
+            LumniError::Application(app_err, Some(app_name)) => {
+                write!(f, "[{}]: {}", app_name, app_err)
+            }
+            LumniError::Invoke(app_err, Some(app_name)) => {
+                write!(f, "[{}]: {}", app_name, app_err)
+            }
+            LumniError::Invoke(app_err, None) => {
+                write!(f, "InvokeError: {}", app_err)
+            }
+            LumniError::Application(app_err, None) => {
+                write!(f, "ApplicationError: {}", app_err)
+            }
         }
     }
 }
 
-impl Error for LakestreamError {}
+impl Error for LumniError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LumniError::Context(_, e) => Some(e.as_ref()),
+            LumniError::Io(e) => Some(e),
+            LumniError::Parse(e) => Some(e),
+            LumniError::Anyhow(e) => Some(e.as_ref()),
+            LumniError::Wrapped(e) => Some(e.as_ref()),
+            #[cfg(feature = "http_client")]
+            LumniError::HttpClientError(e) => Some(e),
+            LumniError::Application(e, _) | LumniError::Invoke(e, _) => Some(e),
+            _ => None,
+        }
+    }
+}
 
-impl From<Box<dyn Error>> for LakestreamError {
+impl From<Box<dyn Error>> for LumniError {
     fn from(error: Box<dyn Error>) -> Self {
-        LakestreamError::Wrapped(error)
+        LumniError::Wrapped(error)
     }
 }
 
-impl From<io::Error> for LakestreamError {
+impl From<io::Error> for LumniError {
     fn from(error: io::Error) -> Self {
-        LakestreamError::Io(error)
+        LumniError::Io(error)
     }
 }
 
-impl From<anyhow::Error> for LakestreamError {
+impl From<anyhow::Error> for LumniError {
     fn from(error: anyhow::Error) -> Self {
-        LakestreamError::Anyhow(error)
+        LumniError::Anyhow(error)
     }
 }
 
-impl From<ParseError> for LakestreamError {
+impl From<ParseError> for LumniError {
     fn from(error: ParseError) -> Self {
-        LakestreamError::Parse(error)
+        LumniError::Parse(error)
     }
 }
 
-impl From<&str> for LakestreamError {
+impl From<&str> for LumniError {
     fn from(error: &str) -> Self {
-        LakestreamError::String(error.to_owned())
+        LumniError::Message(error.to_owned())
     }
 }
 
-impl From<std::string::String> for LakestreamError {
+impl From<std::string::String> for LumniError {
     fn from(error: std::string::String) -> Self {
-        LakestreamError::String(error.to_owned())
+        LumniError::Message(error.to_owned())
+    }
+}
+
+impl From<ApplicationError> for LumniError {
+    fn from(error: ApplicationError) -> Self {
+        LumniError::Application(error, None)
     }
 }
 
 #[cfg(target_arch = "wasm32")]
-impl From<wasm_bindgen::JsValue> for LakestreamError {
+impl From<wasm_bindgen::JsValue> for LumniError {
     fn from(error: wasm_bindgen::JsValue) -> Self {
-        LakestreamError::Js(error)
+        LumniError::Js(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_kinds() {
+        let error = LumniError::from_status(503, "s3://bucket/key", "");
+        assert!(matches!(error, LumniError::Throttled(_)));
+        let error = error.context("Failed to get s3://bucket/key");
+        assert!(error.is_retryable());
+        assert!(error.source().unwrap().source().is_none());
+        assert_eq!(
+            error.to_string(),
+            "Failed to get s3://bucket/key: Too many requests: s3://bucket/key"
+        );
+        assert!(matches!(
+            LumniError::from_status(403, "key", "").root(),
+            LumniError::AccessDenied(_)
+        ));
+        let error = LumniError::from_status(400, "key", "bad request");
+        assert!(!error.is_retryable());
+        assert_eq!(
+            error.to_string(),
+            "Internal error: Unexpected status code 400 for key: bad request"
+        );
+        let error = LumniError::from(io::Error::from(io::ErrorKind::TimedOut));
+        assert!(error.is_retryable());
+        let error = LumniError::from_status(412, "key", "");
+        assert!(matches!(error, LumniError::PreconditionFailed(_)));
+        assert!(!error.is_retryable());
+    }
+
+    #[test]
+    fn test_error_body() {
+        let body = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+            <Error><Code>SignatureDoesNotMatch</Code>\
+            <Message>The request signature we calculated does not match \
+            the signature you provided.</Message>\
+            <RequestId>4442587FB7D0A2F9</RequestId></Error>";
+        let error = LumniError::from_status(403, "s3://bucket/key", body);
+        assert!(matches!(error, LumniError::AccessDenied(_)));
+        assert_eq!(
+            error.to_string(),
+            "Access denied: s3://bucket/key (SignatureDoesNotMatch: The \
+             request signature we calculated does not match the signature \
+             you provided.)"
+        );
+
+        let body = "<Error><Code>NoSuchKey</Code></Error>";
+        assert_eq!(
+            LumniError::from_status(404, "key", body).to_string(),
+            "Not found: key (NoSuchKey)"
+        );
+        let body = "<Error><Code>SlowDown</Code>\
+            <Message>Please reduce your request rate.</Message></Error>";
+        let error = LumniError::from_status(503, "key", body);
+        assert!(error.is_retryable());
+        assert_eq!(
+            error.to_string(),
+            "Too many requests: key (SlowDown: Please reduce your request \
+             rate.)"
+        );
+        let body = "<Error><Code>InternalError</Code>\
+            <Message>We encountered an internal error.</Message></Error>";
+        assert_eq!(
+            LumniError::from_status(500, "key", body).to_string(),
+            "Network error: Server error 500 for key (InternalError: We \
+             encountered an internal error.)"
+        );
+        // the details replace the raw body
+        let body = "<Error><Code>InvalidArgument</Code></Error>";
+        assert_eq!(
+            LumniError::from_status(400, "key", body).to_string(),
+            "Internal error: Unexpected status code 400 for key \
+             (InvalidArgument)"
+        );
+        // other bodies are not parsed
+        assert_eq!(
+            LumniError::from_status(403, "key", "{\"error\": {}}").to_string(),
+            "Access denied: key"
+        );
+        assert_eq!(
+            LumniError::from_status(403, "key", "<html></html>").to_string(),
+            "Access denied: key"
+        );
     }
 }
@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+
+pub use super::bucket::GCSBucket;
+use super::config::validate_config;
+use super::list::list_buckets;
+use crate::handlers::object_store::ObjectStoreBackend;
+use crate::{EnvironmentConfig, LumniError, ObjectStoreTable};
+
+pub struct GCSBackend;
+
+#[async_trait(?Send)]
+impl ObjectStoreBackend for GCSBackend {
+    fn new(_config: EnvironmentConfig) -> Result<Self, LumniError> {
+        Ok(Self)
+    }
+
+    async fn list_buckets(
+        config: EnvironmentConfig,
+        max_files: Option<u32>,
+        table: &mut ObjectStoreTable,
+    ) -> Result<(), LumniError> {
+        let mut config = config;
+        validate_config(&mut config)?;
+        list_buckets(&config, max_files, table).await
+    }
+}
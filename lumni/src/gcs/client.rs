@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use tracing::{info_span, Instrument};
+
+use super::credentials::GCSCredentials;
+use crate::{EnvironmentConfig, LumniError};
+
+pub struct GCSClient {
+    endpoint_url: String,
+    bucket: Option<String>,
+    access_token: Option<String>,
+}
+
+impl GCSClient {
+    pub async fn new(
+        config: &EnvironmentConfig,
+        bucket: Option<&str>,
+    ) -> Result<GCSClient, LumniError> {
+        let endpoint_url = config
+            .get("GCS_ENDPOINT_URL")
+            .expect("Missing endpoint in the configuration")
+            .trim_end_matches('/')
+            .to_string();
+        let credentials = GCSCredentials::from_config(config)?;
+        let access_token = credentials
+            .access_token()
+            .instrument(info_span!("auth"))
+            .await?;
+
+        log::info!("GCSClient created with endpoint_url: {}", endpoint_url);
+        Ok(GCSClient {
+            endpoint_url,
+            bucket: bucket.map(|b| b.to_string()),
+            access_token,
+        })
+    }
+
+    pub fn headers(&self) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        if let Some(token) = &self.access_token {
+            headers.insert(
+                "Authorization".to_string(),
+                format!("Bearer {}", token),
+            );
+        }
+        headers
+    }
+
+    pub fn list_buckets_url(
+        &self,
+        project: &str,
+        page_token: Option<&str>,
+    ) -> String {
+        let mut url = format!(
+            "{}/storage/v1/b?project={}",
+            self.endpoint_url,
+            encode(project)
+        );
+        if let Some(page_token) = page_token {
+            url.push_str(&format!("&pageToken={}", encode(page_token)));
+        }
+        url
+    }
+
+    pub fn list_objects_url(
+        &self,
+        prefix: Option<&str>,
+        delimiter: Option<&str>,
+        page_token: Option<&str>,
+        max_results: Option<u32>,
+    ) -> String {
+        let mut query = Vec::new();
+        if let Some(prefix) = prefix {
+            query.push(format!("prefix={}", encode(prefix)));
+        }
+        if let Some(delimiter) = delimiter {
+            query.push(format!("delimiter={}", encode(delimiter)));
+        }
+        if let Some(page_token) = page_token {
+            query.push(format!("pageToken={}", encode(page_token)));
+        }
+        if let Some(max_results) = max_results {
+            query.push(format!("maxResults={}", max_results));
+        }
+        let mut url = format!("{}/o", self.bucket_url());
+        if !query.is_empty() {
+            url.push('?');
+            url.push_str(&query.join("&"));
+        }
+        url
+    }
+
+    pub fn object_metadata_url(&self, key: &str) -> String {
+        format!("{}/o/{}", self.bucket_url(), encode(key))
+    }
+
+    pub fn object_media_url(&self, key: &str) -> String {
+        format!(
+            "{}/download/storage/v1/b/{}/o/{}?alt=media",
+            self.endpoint_url,
+            encode(self.bucket_name()),
+            encode(key)
+        )
+    }
+
+    fn bucket_url(&self) -> String {
+        format!(
+            "{}/storage/v1/b/{}",
+            self.endpoint_url,
+            encode(self.bucket_name())
+        )
+    }
+
+    fn bucket_name(&self) -> &str {
+        self.bucket
+            .as_deref()
+            .expect("Bucket name is required for object requests")
+    }
+}
+
+fn encode(value: &str) -> String {
+    utf8_percent_encode(value, NON_ALPHANUMERIC).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParsedUri, UriScheme};
+
+    fn client(bucket: &str) -> GCSClient {
+        GCSClient {
+            endpoint_url: "http://localhost:4443".to_string(),
+            bucket: Some(bucket.to_string()),
+            access_token: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_uri() {
+        let parsed = ParsedUri::from_uri("gs://my-bucket/data/2024 q1", true);
+        assert_eq!(parsed.scheme, UriScheme::GCS);
+        assert_eq!(parsed.bucket.as_deref(), Some("my-bucket"));
+        assert_eq!(parsed.path.as_deref(), Some("data/2024 q1/"));
+
+        let client = client(parsed.bucket.as_deref().unwrap());
+        assert_eq!(
+            client.list_objects_url(
+                parsed.path.as_deref(),
+                Some("/"),
+                None,
+                None
+            ),
+            "http://localhost:4443/storage/v1/b/my%2Dbucket/o\
+             ?prefix=data%2F2024%20q1%2F&delimiter=%2F"
+        );
+
+        let parsed = ParsedUri::from_uri("gs://my-bucket", true);
+        assert_eq!(parsed.bucket.as_deref(), Some("my-bucket"));
+        assert_eq!(parsed.path, None);
+    }
+
+    #[test]
+    fn test_page_token() {
+        let client = client("bucket");
+        assert_eq!(
+            client.list_objects_url(None, None, None, Some(1000)),
+            "http://localhost:4443/storage/v1/b/bucket/o?maxResults=1000"
+        );
+        // tokens are base64, so + / and = must be encoded
+        assert_eq!(
+            client.list_objects_url(None, None, Some("Cg+a/b=="), Some(10)),
+            "http://localhost:4443/storage/v1/b/bucket/o\
+             ?pageToken=Cg%2Ba%2Fb%3D%3D&maxResults=10"
+        );
+    }
+
+    #[test]
+    fn test_object_urls() {
+        let client = client("bucket");
+        assert_eq!(
+            client.object_metadata_url("dir/a b.txt"),
+            "http://localhost:4443/storage/v1/b/bucket/o/dir%2Fa%20b%2Etxt"
+        );
+        assert_eq!(
+            client.object_media_url("dir/a.txt"),
+            "http://localhost:4443/download/storage/v1/b/bucket/o/\
+             dir%2Fa%2Etxt?alt=media"
+        );
+        assert_eq!(
+            client.list_buckets_url("my-project", None),
+            "http://localhost:4443/storage/v1/b?project=my%2Dproject"
+        );
+        assert_eq!(
+            client.list_buckets_url("my-project", Some("Cg+a")),
+            "http://localhost:4443/storage/v1/b?project=my%2Dproject\
+             &pageToken=Cg%2Ba"
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use std::env;
+use std::path::PathBuf;
+
+use crate::{EnvironmentConfig, LumniError};
+
+pub const GCS_DEFAULT_ENDPOINT_URL: &str = "https://storage.googleapis.com";
+
+pub fn validate_config(
+    config: &mut EnvironmentConfig,
+) -> Result<(), LumniError> {
+    // Set GCS_ENDPOINT_URL, STORAGE_EMULATOR_HOST is the convention
+    // used by the Google client libraries to point to a local emulator
+    if !config.contains_key("GCS_ENDPOINT_URL") {
+        let endpoint_url = env::var("GCS_ENDPOINT_URL")
+            .or_else(|_| env::var("STORAGE_EMULATOR_HOST"))
+            .unwrap_or_else(|_| GCS_DEFAULT_ENDPOINT_URL.to_string());
+        config.insert("GCS_ENDPOINT_URL".to_string(), endpoint_url);
+    }
+
+    // Set GOOGLE_OAUTH_ACCESS_TOKEN (optional), takes precedence over
+    // credential files when set
+    if !config.contains_key("GOOGLE_OAUTH_ACCESS_TOKEN") {
+        if let Ok(token) = env::var("GOOGLE_OAUTH_ACCESS_TOKEN") {
+            config.insert("GOOGLE_OAUTH_ACCESS_TOKEN".to_string(), token);
+        }
+    }
+
+    // Set GOOGLE_APPLICATION_CREDENTIALS (optional), fall back to the
+    // Application Default Credentials file written by gcloud
+    if !config.contains_key("GOOGLE_APPLICATION_CREDENTIALS") {
+        if let Ok(path) = env::var("GOOGLE_APPLICATION_CREDENTIALS") {
+            config.insert("GOOGLE_APPLICATION_CREDENTIALS".to_string(), path);
+        } else if let Some(path) = default_credentials_path() {
+            if path.exists() {
+                config.insert(
+                    "GOOGLE_APPLICATION_CREDENTIALS".to_string(),
+                    path.to_string_lossy().to_string(),
+                );
+            }
+        }
+    }
+
+    // Set GOOGLE_CLOUD_PROJECT (optional), only required to list buckets
+    if !config.contains_key("GOOGLE_CLOUD_PROJECT") {
+        if let Ok(project) = env::var("GOOGLE_CLOUD_PROJECT")
+            .or_else(|_| env::var("GCLOUD_PROJECT"))
+        {
+            config.insert("GOOGLE_CLOUD_PROJECT".to_string(), project);
+        }
+    }
+
+    let endpoint_url = config.get("GCS_ENDPOINT_URL").unwrap();
+    if !endpoint_url.starts_with("http://")
+        && !endpoint_url.starts_with("https://")
+    {
+        // emulator hosts are often given without scheme
+        let endpoint_url = format!("http://{}", endpoint_url);
+        config.insert("GCS_ENDPOINT_URL".to_string(), endpoint_url);
+    }
+    Ok(())
+}
+
+// credentials of the config or the environment, as the environment
+// variables of the Google client libraries
+pub fn credential_variables(
+    config: &EnvironmentConfig,
+) -> Result<Vec<(String, String)>, LumniError> {
+    let mut config = config.clone();
+    validate_config(&mut config)?;
+    Ok([
+        "GOOGLE_APPLICATION_CREDENTIALS",
+        "GOOGLE_OAUTH_ACCESS_TOKEN",
+        "GOOGLE_CLOUD_PROJECT",
+    ]
+    .into_iter()
+    .filter_map(|name| {
+        config.get(name).map(|value| (name.to_string(), value.clone()))
+    })
+    .collect())
+}
+
+fn default_credentials_path() -> Option<PathBuf> {
+    if let Ok(config_dir) = env::var("CLOUDSDK_CONFIG") {
+        return Some(
+            PathBuf::from(config_dir)
+                .join("application_default_credentials.json"),
+        );
+    }
+    #[cfg(windows)]
+    let base = env::var("APPDATA").ok().map(PathBuf::from);
+    #[cfg(not(windows))]
+    let base = env::var("HOME")
+        .ok()
+        .map(|home| PathBuf::from(home).join(".config"));
+
+    base.map(|base| {
+        base.join("gcloud")
+            .join("application_default_credentials.json")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_endpoint_url() {
+        let endpoint_url = |value: &str| {
+            let mut config = EnvironmentConfig::new(HashMap::from([(
+                "GCS_ENDPOINT_URL".to_string(),
+                value.to_string(),
+            )]));
+            validate_config(&mut config).unwrap();
+            config.get("GCS_ENDPOINT_URL").unwrap().to_string()
+        };
+        // emulator hosts are given without scheme
+        assert_eq!(endpoint_url("localhost:4443"), "http://localhost:4443");
+        assert_eq!(
+            endpoint_url("https://storage.example.com"),
+            "https://storage.example.com"
+        );
+    }
+}
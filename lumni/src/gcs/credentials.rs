@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rsa::pkcs1v15::SigningKey;
+use rsa::pkcs8::DecodePrivateKey;
+use rsa::sha2::{Digest, Sha256};
+use rsa::signature::{SignatureEncoding, Signer};
+use rsa::RsaPrivateKey;
+use serde::Deserialize;
+
+#[cfg(feature = "http_client")]
+use crate::base::token_cache::{
+    cache_token, cached_token_entry, TOKEN_EXPIRY_MARGIN_SECONDS,
+};
+use crate::http::requests::http_post_request;
+use crate::utils::time::system_time_in_seconds;
+use crate::{EnvironmentConfig, LumniError};
+
+const GOOGLE_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+// the token is used for reads and writes, IAM roles still decide which
+// of these are allowed
+const GCS_READ_WRITE_SCOPE: &str =
+    "https://www.googleapis.com/auth/devstorage.read_write";
+const JWT_LIFETIME_SECONDS: u64 = 3600;
+
+// tokens are only cached on disk in builds with http_client, otherwise
+// (e.g. in a browser) they are kept for the lifetime of the process
+#[cfg(not(feature = "http_client"))]
+const TOKEN_EXPIRY_MARGIN_SECONDS: u64 = 300;
+
+#[cfg(feature = "http_client")]
+fn disk_token(key: &str) -> Option<(String, u64)> {
+    cached_token_entry(key).map(|token| (token.value, token.expiration))
+}
+
+#[cfg(not(feature = "http_client"))]
+fn disk_token(_key: &str) -> Option<(String, u64)> {
+    None
+}
+
+#[cfg(not(feature = "http_client"))]
+fn cache_token(_key: &str, _value: String, _expiration: u64) {}
+
+// tokens of this process by cache key, so the client created for every
+// request neither reads the disk cache nor requests a token again
+fn session_tokens() -> &'static Mutex<HashMap<String, (String, u64)>> {
+    static TOKENS: OnceLock<Mutex<HashMap<String, (String, u64)>>> =
+        OnceLock::new();
+    TOKENS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+#[derive(Clone)]
+pub enum GCSCredentials {
+    // pre-fetched token, e.g. from `gcloud auth print-access-token`
+    AccessToken(String),
+    ServiceAccount {
+        client_email: String,
+        private_key: String,
+        token_uri: String,
+    },
+    // Application Default Credentials created by
+    // `gcloud auth application-default login`
+    AuthorizedUser {
+        client_id: String,
+        client_secret: String,
+        refresh_token: String,
+    },
+    // no credentials, only works for public buckets
+    Anonymous,
+}
+
+#[derive(Deserialize)]
+struct CredentialsFile {
+    #[serde(rename = "type")]
+    credentials_type: String,
+    client_email: Option<String>,
+    private_key: Option<String>,
+    token_uri: Option<String>,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    refresh_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>, // seconds
+}
+
+impl GCSCredentials {
+    pub fn from_config(
+        config: &EnvironmentConfig,
+    ) -> Result<GCSCredentials, LumniError> {
+        if let Some(token) = config.get("GOOGLE_OAUTH_ACCESS_TOKEN") {
+            return Ok(GCSCredentials::AccessToken(token.to_string()));
+        }
+        match config.get("GOOGLE_APPLICATION_CREDENTIALS") {
+            Some(path) => {
+                let contents = fs::read_to_string(path).map_err(|e| {
+                    LumniError::Config(format!(
+                        "Failed to read credentials file {}: {}",
+                        path, e
+                    ))
+                })?;
+                GCSCredentials::from_json(&contents)
+            }
+            None => Ok(GCSCredentials::Anonymous),
+        }
+    }
+
+    pub fn from_json(contents: &str) -> Result<GCSCredentials, LumniError> {
+        let file: CredentialsFile =
+            serde_json::from_str(contents).map_err(|e| {
+                LumniError::Config(format!("Invalid credentials file: {}", e))
+            })?;
+
+        let missing = |field: &str| {
+            LumniError::Config(format!(
+                "Credentials file of type '{}' is missing '{}'",
+                file.credentials_type, field
+            ))
+        };
+
+        match file.credentials_type.as_str() {
+            "service_account" => Ok(GCSCredentials::ServiceAccount {
+                client_email: file
+                    .client_email
+                    .clone()
+                    .ok_or_else(|| missing("client_email"))?,
+                private_key: file
+                    .private_key
+                    .clone()
+                    .ok_or_else(|| missing("private_key"))?,
+                token_uri: file
+                    .token_uri
+                    .clone()
+                    .unwrap_or_else(|| GOOGLE_TOKEN_URI.to_string()),
+            }),
+            "authorized_user" => Ok(GCSCredentials::AuthorizedUser {
+                client_id: file
+                    .client_id
+                    .clone()
+                    .ok_or_else(|| missing("client_id"))?,
+                client_secret: file
+                    .client_secret
+                    .clone()
+                    .ok_or_else(|| missing("client_secret"))?,
+                refresh_token: file
+                    .refresh_token
+                    .clone()
+                    .ok_or_else(|| missing("refresh_token"))?,
+            }),
+            other => Err(LumniError::Config(format!(
+                "Unsupported credentials type: {}",
+                other
+            ))),
+        }
+    }
+
+    // returns None for anonymous access. tokens of the OAuth flows are
+    // cached in memory and on disk until they are about to expire
+    pub async fn access_token(&self) -> Result<Option<String>, LumniError> {
+        let cache_key = match self {
+            // the scope is part of the key, a token of another scope is
+            // not reused
+            GCSCredentials::ServiceAccount { client_email, .. } => {
+                format!("gcs:service-account:{}:read_write", client_email)
+            }
+            // gcloud logs in every account with the same client_id, the
+            // refresh token is what differs. it is hashed, the key is
+            // not encrypted in memory
+            GCSCredentials::AuthorizedUser { refresh_token, .. } => {
+                let hash = hex::encode(Sha256::digest(refresh_token));
+                format!("gcs:authorized-user:{}", &hash[..16])
+            }
+            GCSCredentials::AccessToken(token) => {
+                return Ok(Some(token.clone()))
+            }
+            GCSCredentials::Anonymous => return Ok(None),
+        };
+        let valid_after =
+            system_time_in_seconds() + TOKEN_EXPIRY_MARGIN_SECONDS;
+        let session_token = session_tokens()
+            .lock()
+            .unwrap()
+            .get(&cache_key)
+            .filter(|(_, expiration)| *expiration > valid_after)
+            .map(|(token, _)| token.clone());
+        if let Some(token) = session_token {
+            return Ok(Some(token));
+        }
+
+        let (token, expiration) = match disk_token(&cache_key) {
+            Some(cached) => cached,
+            None => {
+                let token = match self.request_access_token().await? {
+                    Some(token) => token,
+                    None => return Ok(None),
+                };
+                // a token without a lifetime is used for this request only
+                let Some(expires_in) = token.expires_in else {
+                    return Ok(Some(token.access_token));
+                };
+                let expiration = system_time_in_seconds() + expires_in;
+                cache_token(&cache_key, token.access_token.clone(), expiration);
+                (token.access_token, expiration)
+            }
+        };
+        session_tokens()
+            .lock()
+            .unwrap()
+            .insert(cache_key, (token.clone(), expiration));
+        Ok(Some(token))
+    }
+
+    async fn request_access_token(
+        &self,
+    ) -> Result<Option<TokenResponse>, LumniError> {
+        match self {
+            GCSCredentials::AccessToken(_) => Ok(None),
+            GCSCredentials::ServiceAccount {
+                client_email,
+                private_key,
+                token_uri,
+            } => {
+                let assertion =
+                    create_jwt_assertion(client_email, private_key, token_uri)?;
+                let form = format!(
+                    "grant_type={}&assertion={}",
+                    "urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Ajwt-bearer",
+                    assertion
+                );
+                request_token(token_uri, &form).await.map(Some)
+            }
+            GCSCredentials::AuthorizedUser {
+                client_id,
+                client_secret,
+                refresh_token,
+            } => {
+                let form = format!(
+                    "grant_type=refresh_token&client_id={}&client_secret={}&\
+                     refresh_token={}",
+                    form_encode(client_id),
+                    form_encode(client_secret),
+                    form_encode(refresh_token)
+                );
+                request_token(GOOGLE_TOKEN_URI, &form).await.map(Some)
+            }
+            GCSCredentials::Anonymous => Ok(None),
+        }
+    }
+}
+
+fn create_jwt_assertion(
+    client_email: &str,
+    private_key: &str,
+    token_uri: &str,
+) -> Result<String, LumniError> {
+    let issued_at = system_time_in_seconds();
+    let header = r#"{"alg":"RS256","typ":"JWT"}"#;
+    let claims = serde_json::json!({
+        "iss": client_email,
+        "scope": GCS_READ_WRITE_SCOPE,
+        "aud": token_uri,
+        "iat": issued_at,
+        "exp": issued_at + JWT_LIFETIME_SECONDS,
+    });
+
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(header),
+        URL_SAFE_NO_PAD.encode(claims.to_string())
+    );
+
+    let key = RsaPrivateKey::from_pkcs8_pem(private_key).map_err(|e| {
+        LumniError::Config(format!(
+            "Invalid service account private key: {}",
+            e
+        ))
+    })?;
+    let signature = SigningKey::<Sha256>::new(key)
+        .sign(signing_input.as_bytes())
+        .to_bytes();
+
+    Ok(format!(
+        "{}.{}",
+        signing_input,
+        URL_SAFE_NO_PAD.encode(signature)
+    ))
+}
+
+async fn request_token(
+    token_uri: &str,
+    form: &str,
+) -> Result<TokenResponse, LumniError> {
+    let mut headers = HashMap::new();
+    headers.insert(
+        "Content-Type".to_string(),
+        "application/x-www-form-urlencoded".to_string(),
+    );
+    let (body, status) =
+        http_post_request(token_uri, &headers, form.as_bytes()).await?;
+    if status != 200 {
+        return Err(LumniError::AccessDenied(format!(
+            "Token request failed with status {}: {}",
+            status,
+            String::from_utf8_lossy(&body)
+        )));
+    }
+    serde_json::from_slice(&body).map_err(|e| {
+        LumniError::Internal(format!("Failed to parse token response: {}", e))
+    })
+}
+
+fn form_encode(value: &str) -> String {
+    percent_encoding::utf8_percent_encode(
+        value,
+        percent_encoding::NON_ALPHANUMERIC,
+    )
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_session_token() {
+        let credentials = GCSCredentials::ServiceAccount {
+            client_email: "session@example.com".to_string(),
+            private_key: "not a key".to_string(),
+            token_uri: GOOGLE_TOKEN_URI.to_string(),
+        };
+        // signing fails with this key, so the token can only come from
+        // the session
+        assert!(credentials.access_token().await.is_err());
+        session_tokens().lock().unwrap().insert(
+            "gcs:service-account:session@example.com:read_write".to_string(),
+            ("token".to_string(), system_time_in_seconds() + 3600),
+        );
+        assert_eq!(
+            credentials.access_token().await.unwrap().as_deref(),
+            Some("token")
+        );
+    }
+}
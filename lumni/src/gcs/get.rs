@@ -0,0 +1,72 @@
+use super::bucket::GCSBucket;
+use super::client::GCSClient;
+use super::list::check_status;
+use crate::base::ranged_read::{ranged_stream, Chunk};
+use crate::handlers::object_store::{
+    ByteRange, ObjectStoreTrait, ObjectStream,
+};
+use crate::http::requests::{http_get_request, http_request_with_headers};
+use crate::{LumniError, DEFAULT_DOWNLOAD_CHUNK_SIZE};
+
+pub async fn get_object(
+    gcs_bucket: &GCSBucket,
+    object_key: &str,
+    data: &mut Vec<u8>,
+) -> Result<(), LumniError> {
+    let client =
+        GCSClient::new(gcs_bucket.config(), Some(gcs_bucket.name())).await?;
+
+    log::info!("Getting object: {}", object_key);
+    let (body_bytes, status) = http_get_request(
+        &client.object_media_url(object_key),
+        &client.headers(),
+    )
+    .await?;
+    check_status(status, object_key)?;
+    log::info!(
+        "Got object: {} of size {} bytes",
+        object_key,
+        body_bytes.len()
+    );
+    data.clear();
+    data.extend_from_slice(&body_bytes);
+    Ok(())
+}
+
+// each chunk is fetched with its own range request, as S3 does
+pub async fn get_object_stream(
+    gcs_bucket: &GCSBucket,
+    object_key: &str,
+    range: Option<ByteRange>,
+) -> Result<ObjectStream, LumniError> {
+    let gcs_bucket = gcs_bucket.clone();
+    let object_key = object_key.to_string();
+    Ok(ranged_stream(range, DEFAULT_DOWNLOAD_CHUNK_SIZE, move |range| {
+        let gcs_bucket = gcs_bucket.clone();
+        let object_key = object_key.clone();
+        async move { get_chunk(&gcs_bucket, &object_key, range).await }
+    }))
+}
+
+async fn get_chunk(
+    gcs_bucket: &GCSBucket,
+    object_key: &str,
+    range: ByteRange,
+) -> Result<Chunk, LumniError> {
+    let client =
+        GCSClient::new(gcs_bucket.config(), Some(gcs_bucket.name())).await?;
+    log::debug!(
+        "Getting range {} of object: {}",
+        range.to_header_value(),
+        object_key
+    );
+    let mut headers = client.headers();
+    headers.insert("Range".to_string(), range.to_header_value());
+    let (body_bytes, status, response_headers) = http_request_with_headers(
+        &client.object_media_url(object_key),
+        &headers,
+        "GET",
+    )
+    .await?;
+    Chunk::from_http(status, &response_headers, body_bytes, object_key)
+}
@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+use log::info;
+
+use super::bucket::GCSBucket;
+use super::client::GCSClient;
+use super::parse_http_response::parse_object_resource;
+use crate::handlers::object_store::ObjectStoreTrait;
+use crate::http::requests::http_get_request;
+use crate::LumniError;
+
+pub async fn head_object(
+    gcs_bucket: &GCSBucket,
+    object_key: &str,
+) -> Result<(u16, HashMap<String, String>), LumniError> {
+    let client =
+        GCSClient::new(gcs_bucket.config(), Some(gcs_bucket.name())).await?;
+
+    info!("Head object: {}", object_key);
+    // the JSON API has no HEAD, object metadata is fetched instead
+    let (body_bytes, status) = http_get_request(
+        &client.object_metadata_url(object_key),
+        &client.headers(),
+    )
+    .await?;
+
+    if !(200..300).contains(&status) {
+        return Ok((status, HashMap::new()));
+    }
+    let object = parse_object_resource(&body_bytes).map_err(|e| {
+        LumniError::Internal(format!("Failed to parse object metadata: {}", e))
+    })?;
+    Ok((status, object.into_headers()))
+}
@@ -0,0 +1,161 @@
+use std::collections::VecDeque;
+
+use tracing::{info_span, Instrument};
+
+use super::bucket::GCSBucket;
+use super::client::GCSClient;
+use super::parse_http_response::{parse_bucket_names, parse_file_objects};
+use crate::base::config::EnvironmentConfig;
+use crate::handlers::object_store::{ObjectStore, ObjectStoreTrait};
+use crate::http::requests::http_get_request;
+use crate::table::{FileObjectTable, ObjectStoreTable, Table};
+use crate::{FileObject, FileObjectFilter, LumniError};
+
+// maximum page size accepted by the GCS JSON API
+const GCS_MAX_LIST_OBJECTS: u32 = 1000;
+
+pub async fn list_files(
+    gcs_bucket: &GCSBucket,
+    prefix: Option<&str>,
+    _selected_columns: &Option<Vec<&str>>, // not yet implemented
+    recursive: bool,
+    max_keys: Option<u32>,
+    filter: &Option<FileObjectFilter>,
+    table: &mut FileObjectTable,
+) -> Result<(), LumniError> {
+    let client =
+        GCSClient::new(gcs_bucket.config(), Some(gcs_bucket.name())).await?;
+    let headers = client.headers();
+
+    let max_files = max_keys.unwrap_or(GCS_MAX_LIST_OBJECTS) as usize;
+    // when filter is provided, fetch full pages because we are not sure
+    // how many objects will be filtered out
+    let page_size = if filter.is_some() {
+        GCS_MAX_LIST_OBJECTS
+    } else {
+        max_keys
+            .unwrap_or(GCS_MAX_LIST_OBJECTS)
+            .min(GCS_MAX_LIST_OBJECTS)
+    };
+
+    let mut directory_stack = VecDeque::new();
+    directory_stack.push_back(prefix.map(|p| p.to_owned()));
+
+    while let Some(prefix) = directory_stack.pop_front() {
+        let mut page_token: Option<String> = None;
+        loop {
+            let url = client.list_objects_url(
+                prefix.as_deref(),
+                Some("/"),
+                page_token.as_deref(),
+                Some(page_size),
+            );
+            let (body, status) = http_get_request(&url, &headers)
+                .instrument(info_span!("list_page"))
+                .await?;
+            check_status(status, gcs_bucket.name())?;
+
+            let (file_objects, prefixes, next_page_token) =
+                parse_file_objects(&body).map_err(|e| {
+                    LumniError::Internal(format!(
+                        "Failed to parse list response: {}",
+                        e
+                    ))
+                })?;
+
+            let mut temp_file_objects = Vec::new();
+            for virtual_directory in prefixes {
+                if filter.is_none() {
+                    temp_file_objects.push(FileObject::new(
+                        virtual_directory.clone(),
+                        0,
+                        None,
+                        None,
+                    ));
+                }
+                if recursive {
+                    directory_stack.push_back(Some(virtual_directory));
+                }
+            }
+            for file_object in file_objects {
+                if let Some(filter) = filter {
+                    if !filter.matches(&file_object) {
+                        continue;
+                    }
+                }
+                temp_file_objects.push(file_object);
+            }
+
+            let max_to_add = max_files.saturating_sub(table.len());
+            if !temp_file_objects.is_empty() && max_to_add > 0 {
+                let objects_to_add = temp_file_objects
+                    .drain(..)
+                    .take(max_to_add)
+                    .collect::<Vec<_>>();
+                table.add_file_objects(objects_to_add).await?;
+            }
+
+            if table.len() >= max_files {
+                return Ok(());
+            }
+            page_token = next_page_token;
+            if page_token.is_none() {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+pub async fn list_buckets(
+    config: &EnvironmentConfig,
+    max_files: Option<u32>,
+    table: &mut ObjectStoreTable,
+) -> Result<(), LumniError> {
+    let project = config.get("GOOGLE_CLOUD_PROJECT").ok_or_else(|| {
+        LumniError::Config(
+            "GOOGLE_CLOUD_PROJECT is required to list buckets".to_string(),
+        )
+    })?;
+    let client = GCSClient::new(config, None).await?;
+    let headers = client.headers();
+
+    let mut count = 0;
+    let mut page_token: Option<String> = None;
+    loop {
+        let url = client.list_buckets_url(project, page_token.as_deref());
+        let (body, status) = http_get_request(&url, &headers)
+            .instrument(info_span!("list_page"))
+            .await?;
+        check_status(status, project)?;
+
+        let (bucket_names, next_page_token) = parse_bucket_names(&body)
+            .map_err(|e| {
+                LumniError::Internal(format!(
+                    "Failed to parse bucket list: {}",
+                    e
+                ))
+            })?;
+
+        for name in bucket_names {
+            if max_files.is_some_and(|max| count >= max as usize) {
+                return Ok(());
+            }
+            let object_store =
+                ObjectStore::new(&format!("gs://{}", name), config.clone())?;
+            table.add_object_store(object_store).await?;
+            count += 1;
+        }
+        page_token = next_page_token;
+        if page_token.is_none() {
+            return Ok(());
+        }
+    }
+}
+
+pub fn check_status(status: u16, resource: &str) -> Result<(), LumniError> {
+    match status {
+        200..=299 => Ok(()),
+        _ => Err(LumniError::from_status(status, resource, "")),
+    }
+}
@@ -0,0 +1,11 @@
+pub mod backend;
+mod bucket;
+mod client;
+mod config;
+mod credentials;
+mod get;
+mod head;
+mod list;
+mod parse_http_response;
+
+pub(crate) use config::credential_variables;
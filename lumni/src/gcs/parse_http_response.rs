@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::utils::time::rfc3339_to_epoch;
+use crate::{Checksum, ChecksumAlgorithm, FileObject};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListObjectsResponse {
+    items: Option<Vec<ObjectResource>>,
+    prefixes: Option<Vec<String>>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectResource {
+    name: String,
+    // JSON API encodes 64-bit integers as strings
+    size: Option<String>,
+    updated: Option<String>,
+    etag: Option<String>,
+    md5_hash: Option<String>,
+    crc32c: Option<String>,
+    content_type: Option<String>,
+    storage_class: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListBucketsResponse {
+    items: Option<Vec<BucketResource>>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BucketResource {
+    name: String,
+}
+
+impl ObjectResource {
+    fn size(&self) -> u64 {
+        self.size
+            .as_deref()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(0)
+    }
+
+    fn modified(&self) -> Option<u64> {
+        self.updated
+            .as_deref()
+            .and_then(|updated| rfc3339_to_epoch(updated).ok())
+    }
+
+    fn tags(&self) -> HashMap<String, String> {
+        let mut tags = HashMap::new();
+        if let Some(etag) = &self.etag {
+            tags.insert("ETag".to_string(), etag.clone());
+        }
+        if let Some(md5_hash) = &self.md5_hash {
+            tags.insert("md5Hash".to_string(), md5_hash.clone());
+        }
+        if let Some(crc32c) = &self.crc32c {
+            tags.insert("crc32c".to_string(), crc32c.clone());
+        }
+        tags
+    }
+
+    // composite objects have no MD5, only a CRC32C
+    fn checksum(&self) -> Option<Checksum> {
+        match (&self.md5_hash, &self.crc32c) {
+            (Some(md5_hash), _) => {
+                Checksum::from_base64(ChecksumAlgorithm::Md5, md5_hash)
+            }
+            (None, Some(crc32c)) => {
+                Checksum::from_base64(ChecksumAlgorithm::Crc32c, crc32c)
+            }
+            (None, None) => None,
+        }
+    }
+
+    pub fn into_file_object(self) -> FileObject {
+        let size = self.size();
+        let modified = self.modified();
+        let tags = self.tags();
+        let checksum = self.checksum();
+        FileObject::new(self.name, size, modified, Some(tags))
+            .set_checksum(checksum)
+            .set_storage_class(self.storage_class)
+    }
+
+    // metadata in the shape of HTTP response headers, to match what the
+    // other backends return from head_object
+    pub fn into_headers(self) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        headers.insert("content-length".to_string(), self.size().to_string());
+        if let Some(etag) = self.etag {
+            headers.insert("etag".to_string(), etag);
+        }
+        if let Some(updated) = self.updated {
+            headers.insert("last-modified".to_string(), updated);
+        }
+        if let Some(content_type) = self.content_type {
+            headers.insert("content-type".to_string(), content_type);
+        }
+        headers
+    }
+}
+
+pub fn parse_object_resource(
+    body: &[u8],
+) -> Result<ObjectResource, serde_json::Error> {
+    serde_json::from_slice(body)
+}
+
+// file objects, virtual directories and the next page token
+type ListObjectsPage = (Vec<FileObject>, Vec<String>, Option<String>);
+
+pub fn parse_file_objects(
+    body: &[u8],
+) -> Result<ListObjectsPage, serde_json::Error> {
+    let response: ListObjectsResponse = serde_json::from_slice(body)?;
+    let file_objects = response
+        .items
+        .unwrap_or_default()
+        .into_iter()
+        .map(|item| item.into_file_object())
+        .collect();
+    Ok((
+        file_objects,
+        response.prefixes.unwrap_or_default(),
+        response.next_page_token,
+    ))
+}
+
+// bucket names and the token of the next page
+pub fn parse_bucket_names(
+    body: &[u8],
+) -> Result<(Vec<String>, Option<String>), serde_json::Error> {
+    let response: ListBucketsResponse = serde_json::from_slice(body)?;
+    let names = response
+        .items
+        .unwrap_or_default()
+        .into_iter()
+        .map(|bucket| bucket.name)
+        .collect();
+    Ok((names, response.next_page_token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file_objects() {
+        let body = br#"{
+            "kind": "storage#objects",
+            "prefixes": ["data/2024/"],
+            "items": [
+                {
+                    "name": "data/a.txt",
+                    "size": "1024",
+                    "updated": "2024-01-01T00:00:00.000Z",
+                    "etag": "CKih16GjvYADEAE=",
+                    "md5Hash": "1B2M2Y8AsgTpgAmY7PhCfg==",
+                    "crc32c": "AAAAAA==",
+                    "storageClass": "STANDARD"
+                },
+                {
+                    "name": "data/composite",
+                    "crc32c": "AAAAAA=="
+                }
+            ],
+            "nextPageToken": "CgtkYXRhL2NvbXBvc2l0ZQ=="
+        }"#;
+        let (file_objects, prefixes, next_page_token) =
+            parse_file_objects(body).unwrap();
+        assert_eq!(prefixes, vec!["data/2024/"]);
+        assert_eq!(
+            next_page_token.as_deref(),
+            Some("CgtkYXRhL2NvbXBvc2l0ZQ==")
+        );
+        assert_eq!(file_objects.len(), 2);
+
+        let object = &file_objects[0];
+        assert_eq!(object.name(), "data/a.txt");
+        assert_eq!(object.size(), 1024);
+        assert_eq!(object.modified(), Some(1704067200));
+        assert_eq!(object.storage_class(), Some("STANDARD"));
+        let checksum = object.checksum().unwrap();
+        assert_eq!(checksum.algorithm(), ChecksumAlgorithm::Md5);
+        assert_eq!(checksum.value(), "d41d8cd98f00b204e9800998ecf8427e");
+        let tags = object.tags().as_ref().unwrap();
+        assert_eq!(tags.get("ETag").unwrap(), "CKih16GjvYADEAE=");
+
+        // composite objects have no size or md5 in this response
+        let object = &file_objects[1];
+        assert_eq!(object.size(), 0);
+        assert_eq!(object.modified(), None);
+        let checksum = object.checksum().unwrap();
+        assert_eq!(checksum.algorithm(), ChecksumAlgorithm::Crc32c);
+        assert_eq!(checksum.value(), "00000000");
+    }
+
+    #[test]
+    fn test_parse_last_page() {
+        // an empty page has neither items nor prefixes
+        let (file_objects, prefixes, next_page_token) =
+            parse_file_objects(br#"{"kind": "storage#objects"}"#).unwrap();
+        assert!(file_objects.is_empty());
+        assert!(prefixes.is_empty());
+        assert_eq!(next_page_token, None);
+
+        assert!(parse_file_objects(b"<Error/>").is_err());
+    }
+
+    #[test]
+    fn test_parse_object_resource() {
+        let body = br#"{
+            "name": "a.txt",
+            "size": "5",
+            "etag": "CAE=",
+            "updated": "2024-01-01T00:00:00.000Z",
+            "contentType": "text/plain"
+        }"#;
+        let headers = parse_object_resource(body).unwrap().into_headers();
+        assert_eq!(headers.get("content-length").unwrap(), "5");
+        assert_eq!(headers.get("etag").unwrap(), "CAE=");
+        assert_eq!(headers.get("content-type").unwrap(), "text/plain");
+    }
+
+    #[test]
+    fn test_parse_bucket_names() {
+        let body = br#"{
+            "kind": "storage#buckets",
+            "nextPageToken": "CgZzZWNvbmQ=",
+            "items": [{"name": "first"}, {"name": "second"}]
+        }"#;
+        let (names, next_page_token) = parse_bucket_names(body).unwrap();
+        assert_eq!(names, vec!["first", "second"]);
+        assert_eq!(next_page_token.as_deref(), Some("CgZzZWNvbmQ="));
+        assert_eq!(parse_bucket_names(b"{}").unwrap(), (vec![], None));
+    }
+}
@@ -0,0 +1,251 @@
+use std::io::{self, Cursor};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use futures::StreamExt;
+
+use super::object_store::{ObjectStore, UploadOptions};
+use crate::LumniError;
+
+// keys listed to find an object to read, the listing itself is the
+// list probe
+const PROBE_LIST_KEYS: u32 = 100;
+const PROBE_KEY_PREFIX: &str = ".lumni-access-probe-";
+const PROBE_BODY: &[u8] = b"lumni access probe\n";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessOperation {
+    List,
+    Read,
+    Write,
+    Delete,
+}
+
+impl AccessOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccessOperation::List => "list",
+            AccessOperation::Read => "read",
+            AccessOperation::Write => "write",
+            AccessOperation::Delete => "delete",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccessStatus {
+    Allowed,
+    Denied(String),
+    Untested(String), // why no request was made
+    Failed(String),   // an error that says nothing about the permission
+}
+
+impl AccessStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccessStatus::Allowed => "allowed",
+            AccessStatus::Denied(_) => "denied",
+            AccessStatus::Untested(_) => "untested",
+            AccessStatus::Failed(_) => "failed",
+        }
+    }
+
+    pub fn is_allowed(&self) -> bool {
+        matches!(self, AccessStatus::Allowed)
+    }
+
+    pub fn reason(&self) -> Option<&str> {
+        match self {
+            AccessStatus::Allowed => None,
+            AccessStatus::Denied(reason)
+            | AccessStatus::Untested(reason)
+            | AccessStatus::Failed(reason) => Some(reason),
+        }
+    }
+
+    fn from_error(error: &LumniError) -> Self {
+        match error.root() {
+            LumniError::AccessDenied(_) => {
+                AccessStatus::Denied(error.to_string())
+            }
+            LumniError::Io(e)
+                if e.kind() == io::ErrorKind::PermissionDenied =>
+            {
+                AccessStatus::Denied(error.to_string())
+            }
+            _ => AccessStatus::Failed(error.to_string()),
+        }
+    }
+}
+
+// the operations the credentials allow under a prefix
+#[derive(Debug, Clone, Default)]
+pub struct AccessReport {
+    results: Vec<(AccessOperation, AccessStatus)>,
+    // the probe object if it was written but could not be deleted
+    leftover: Option<String>,
+}
+
+impl AccessReport {
+    pub fn results(&self) -> &[(AccessOperation, AccessStatus)] {
+        &self.results
+    }
+
+    pub fn status(&self, operation: AccessOperation) -> Option<&AccessStatus> {
+        self.results
+            .iter()
+            .find(|(op, _)| *op == operation)
+            .map(|(_, status)| status)
+    }
+
+    pub fn leftover(&self) -> Option<&str> {
+        self.leftover.as_deref()
+    }
+}
+
+// lists and reads what is under the prefix and, if write is set, writes
+// and deletes a small probe object next to it. nothing else is modified
+pub(crate) async fn probe_access(
+    object_store: &ObjectStore,
+    prefix: Option<&str>,
+    write: bool,
+) -> AccessReport {
+    let mut report = AccessReport::default();
+
+    let (list_status, listed_key) = match object_store
+        .list_keys_up_to(prefix, Some(PROBE_LIST_KEYS))
+        .await
+    {
+        Ok(keys) => (AccessStatus::Allowed, keys.into_iter().next()),
+        Err(e) => (AccessStatus::from_error(&e), None),
+    };
+
+    let probe_key = probe_key(prefix);
+    let write_status = if write {
+        let mut source = Cursor::new(PROBE_BODY);
+        let options = UploadOptions::new().set_content_type("text/plain");
+        match object_store
+            .put_object_multipart(&probe_key, &mut source, &options, None)
+            .await
+        {
+            Ok(_) => AccessStatus::Allowed,
+            Err(e) => AccessStatus::from_error(&e),
+        }
+    } else {
+        AccessStatus::Untested("write probe disabled".to_string())
+    };
+    let written = write_status.is_allowed();
+
+    // an existing object is preferred, the probe object may be readable
+    // to its writer only
+    let read_key = listed_key.or_else(|| written.then(|| probe_key.clone()));
+    let read_status = match read_key {
+        Some(key) => read_first_chunk(object_store, &key).await,
+        None => AccessStatus::Untested("no object to read".to_string()),
+    };
+
+    let delete_status = if !write {
+        AccessStatus::Untested("write probe disabled".to_string())
+    } else {
+        // without a probe object, a key that does not exist is deleted.
+        // S3 allows that, other stores tell it was not found
+        match object_store.delete_object(&probe_key).await {
+            Ok(()) => AccessStatus::Allowed,
+            Err(e) => {
+                if written {
+                    report.leftover = Some(probe_key.clone());
+                }
+                match e.root() {
+                    LumniError::NotFound(_) if !written => {
+                        AccessStatus::Untested(
+                            "no object to delete".to_string(),
+                        )
+                    }
+                    _ => AccessStatus::from_error(&e),
+                }
+            }
+        }
+    };
+
+    report.results = vec![
+        (AccessOperation::List, list_status),
+        (AccessOperation::Read, read_status),
+        (AccessOperation::Write, write_status),
+        (AccessOperation::Delete, delete_status),
+    ];
+    report
+}
+
+// only the first chunk is read, the rest of the object is not transferred
+async fn read_first_chunk(
+    object_store: &ObjectStore,
+    key: &str,
+) -> AccessStatus {
+    let mut stream = match object_store.get_object_stream(key, None).await {
+        Ok(stream) => stream,
+        Err(e) => return AccessStatus::from_error(&e),
+    };
+    match stream.next().await {
+        Some(Err(e)) => AccessStatus::from_error(&e),
+        _ => AccessStatus::Allowed,
+    }
+}
+
+// unique per probe, so concurrent probes do not delete each others object
+fn probe_key(prefix: Option<&str>) -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let name = format!("{}{:x}", PROBE_KEY_PREFIX, nanos);
+    match prefix.map(|prefix| prefix.trim_end_matches('/')) {
+        Some(prefix) if !prefix.is_empty() => format!("{}/{}", prefix, name),
+        _ => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+
+    use futures::executor::block_on;
+
+    use super::*;
+    use crate::EnvironmentConfig;
+
+    #[test]
+    fn test_probe_access() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("data")).unwrap();
+        fs::write(dir.path().join("data/a.txt"), "aaaa").unwrap();
+        let object_store = ObjectStore::new(
+            &format!("localfs://{}", dir.path().display()),
+            EnvironmentConfig::new(HashMap::new()),
+        )
+        .unwrap();
+
+        let report = block_on(probe_access(&object_store, Some("data/"), true));
+        for (operation, status) in report.results() {
+            assert!(
+                status.is_allowed(),
+                "{}: {:?}",
+                operation.as_str(),
+                status
+            );
+        }
+        assert_eq!(report.leftover(), None);
+        // the probe object is cleaned up
+        assert_eq!(fs::read_dir(dir.path().join("data")).unwrap().count(), 1);
+
+        let report =
+            block_on(probe_access(&object_store, Some("empty/"), false));
+        assert!(matches!(
+            report.status(AccessOperation::Read),
+            Some(AccessStatus::Untested(_))
+        ));
+        assert!(matches!(
+            report.status(AccessOperation::Write),
+            Some(AccessStatus::Untested(_))
+        ));
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use crate::utils::time::epoch_to_http_date;
+use crate::{LumniError, ObjectMetadata};
+
+// preconditions of reading or writing an object, as the HTTP headers of
+// the same name. ETags are given without quotes, "*" matches any object
+// that exists
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Conditions {
+    if_match: Option<String>,
+    if_none_match: Option<String>,
+    if_modified_since: Option<u64>, // epoch seconds
+}
+
+impl Conditions {
+    pub fn new() -> Self {
+        Conditions::default()
+    }
+
+    // the object is only read or written if it has this ETag, e.g. to
+    // detect that it was modified since it was read
+    pub fn set_if_match(mut self, etag: &str) -> Self {
+        self.if_match = Some(unquote(etag).to_string());
+        self
+    }
+
+    // a read is skipped if the object still has this ETag, a write fails
+    // if it does. "*" writes only if the object does not exist yet
+    pub fn set_if_none_match(mut self, etag: &str) -> Self {
+        self.if_none_match = Some(unquote(etag).to_string());
+        self
+    }
+
+    // a read is skipped if the object was not modified after this time,
+    // ignored when If-None-Match is set
+    pub fn set_if_modified_since(mut self, epoch_seconds: u64) -> Self {
+        self.if_modified_since = Some(epoch_seconds);
+        self
+    }
+
+    pub fn if_match(&self) -> Option<&str> {
+        self.if_match.as_deref()
+    }
+
+    pub fn if_none_match(&self) -> Option<&str> {
+        self.if_none_match.as_deref()
+    }
+
+    pub fn if_modified_since(&self) -> Option<u64> {
+        self.if_modified_since
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Conditions::default()
+    }
+
+    // request headers, names in lowercase
+    pub fn headers(&self) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        if let Some(etag) = &self.if_match {
+            headers.insert("if-match".to_string(), quote(etag));
+        }
+        if let Some(etag) = &self.if_none_match {
+            headers.insert("if-none-match".to_string(), quote(etag));
+        }
+        if let Some(since) = self.if_modified_since {
+            headers.insert(
+                "if-modified-since".to_string(),
+                epoch_to_http_date(since),
+            );
+        }
+        headers
+    }
+
+    // for backends that do not take the headers: whether the object, or
+    // None if it does not exist, should be read. false if it is not
+    // modified
+    pub fn check_read(
+        &self,
+        key: &str,
+        metadata: Option<&ObjectMetadata>,
+    ) -> Result<bool, LumniError> {
+        if !self.matches(metadata) {
+            return Err(LumniError::PreconditionFailed(key.to_string()));
+        }
+        if let Some(etag) = &self.if_none_match {
+            return Ok(!etag_matches(etag, metadata));
+        }
+        match (self.if_modified_since, metadata.and_then(|m| m.modified())) {
+            (Some(since), Some(modified)) => Ok(modified > since),
+            _ => Ok(true),
+        }
+    }
+
+    // as check_read, a write of an object that is not modified fails
+    pub fn check_write(
+        &self,
+        key: &str,
+        metadata: Option<&ObjectMetadata>,
+    ) -> Result<(), LumniError> {
+        match self.check_read(key, metadata)? {
+            true => Ok(()),
+            false => Err(LumniError::PreconditionFailed(key.to_string())),
+        }
+    }
+
+    fn matches(&self, metadata: Option<&ObjectMetadata>) -> bool {
+        self.if_match
+            .as_ref()
+            .is_none_or(|etag| etag_matches(etag, metadata))
+    }
+}
+
+// None if the object does not exist, e.g. of get_object_metadata
+pub(crate) fn if_exists<T>(
+    result: Result<T, LumniError>,
+) -> Result<Option<T>, LumniError> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(err) if matches!(err.root(), LumniError::NotFound(_)) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+fn etag_matches(etag: &str, metadata: Option<&ObjectMetadata>) -> bool {
+    match metadata {
+        Some(_) if etag == "*" => true,
+        Some(metadata) => metadata.etag() == Some(etag),
+        None => false,
+    }
+}
+
+fn unquote(etag: &str) -> &str {
+    etag.trim().trim_matches('"')
+}
+
+fn quote(etag: &str) -> String {
+    match etag {
+        "*" => etag.to_string(),
+        _ => format!("\"{}\"", etag),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conditions() {
+        let object = ObjectMetadata::new("a.csv")
+            .set_modified(Some(200))
+            .set_etag(Some("abc".to_string()));
+
+        let conditions = Conditions::new().set_if_none_match("\"abc\"");
+        assert_eq!(conditions.headers()["if-none-match"], "\"abc\"");
+        assert!(!conditions.check_read("a.csv", Some(&object)).unwrap());
+        assert!(conditions.check_read("a.csv", None).unwrap());
+        assert!(matches!(
+            conditions.check_write("a.csv", Some(&object)),
+            Err(LumniError::PreconditionFailed(_))
+        ));
+
+        let conditions = Conditions::new().set_if_modified_since(200);
+        assert!(!conditions.check_read("a.csv", Some(&object)).unwrap());
+        let conditions = Conditions::new().set_if_modified_since(100);
+        assert!(conditions.check_read("a.csv", Some(&object)).unwrap());
+
+        let conditions = Conditions::new().set_if_match("other");
+        assert!(conditions.check_read("a.csv", Some(&object)).is_err());
+        let create_only = Conditions::new().set_if_none_match("*");
+        assert!(create_only.check_write("a.csv", None).is_ok());
+        assert!(create_only.check_write("a.csv", Some(&object)).is_err());
+        assert!(Conditions::new().is_empty());
+    }
+
+    #[test]
+    fn test_conditional_put_get() {
+        use std::collections::HashMap;
+
+        use futures::executor::block_on;
+
+        use crate::{
+            EnvironmentConfig, ObjectStoreHandler, ParsedUri, UploadOptions,
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let uri = ParsedUri::from_uri(
+            &format!("localfs://{}/a.txt", dir.path().display()),
+            false,
+        );
+        let config = EnvironmentConfig::new(HashMap::new());
+        let handler = ObjectStoreHandler::new(None);
+        let create_only = UploadOptions::new()
+            .set_conditions(Conditions::new().set_if_none_match("*"));
+        let put = |options: &UploadOptions| {
+            let mut source = "data".as_bytes();
+            block_on(handler.put_object(
+                &uri,
+                &config,
+                &mut source,
+                options,
+                None,
+            ))
+        };
+        assert!(put(&create_only).is_ok());
+        assert!(matches!(
+            put(&create_only),
+            Err(LumniError::PreconditionFailed(_))
+        ));
+
+        let later = Conditions::new().set_if_modified_since(u64::MAX / 2);
+        let data = block_on(handler.get_object_if(&uri, &config, &later));
+        assert_eq!(data.unwrap(), None);
+        let data = block_on(handler.get_object_if(
+            &uri,
+            &config,
+            &Conditions::new().set_if_modified_since(0),
+        ));
+        assert_eq!(data.unwrap().as_deref(), Some("data".as_bytes()));
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::table::{DiffChange, TableCallback};
+use crate::{LumniError, TableColumnValue, TableRow};
+
+// how objects found on both sides are compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiffStrategy {
+    Size,
+    // size or modification time differs, as rsync does by default
+    #[default]
+    Mtime,
+    // content checksums (ETag, md5) differ, objects without a checksum
+    // of the same kind on both sides are compared by size
+    Etag,
+}
+
+impl DiffStrategy {
+    pub fn from_name(name: &str) -> Result<Self, LumniError> {
+        match name {
+            "size" => Ok(DiffStrategy::Size),
+            "mtime" => Ok(DiffStrategy::Mtime),
+            "etag" => Ok(DiffStrategy::Etag),
+            _ => Err(LumniError::Config(format!(
+                "Unsupported diff strategy: {}, expected size, mtime or etag",
+                name
+            ))),
+        }
+    }
+
+    // checksums are only listed when asked for, as a local filesystem
+    // has to read every file for it
+    pub fn needs_checksum(&self) -> bool {
+        *self == DiffStrategy::Etag
+    }
+
+    pub fn compare(
+        &self,
+        a: &InventoryEntry,
+        b: &InventoryEntry,
+    ) -> Option<DiffChange> {
+        let changed = match self {
+            DiffStrategy::Size => a.size != b.size,
+            DiffStrategy::Mtime => a.size != b.size || a.modified != b.modified,
+            DiffStrategy::Etag => match (&a.checksum, &b.checksum) {
+                // checksums are listed as "<algorithm>:<value>"
+                (Some(checksum_a), Some(checksum_b))
+                    if checksum_a.split(':').next()
+                        == checksum_b.split(':').next() =>
+                {
+                    checksum_a != checksum_b
+                }
+                _ => a.size != b.size,
+            },
+        };
+        changed.then_some(DiffChange::Modified)
+    }
+}
+
+// properties of a listed object that are compared
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InventoryEntry {
+    pub size: u64,
+    pub modified: Option<u64>,
+    pub checksum: Option<String>,
+}
+
+// collects the rows of a listing by name
+#[derive(Default)]
+pub struct InventoryCollector {
+    entries: Mutex<BTreeMap<String, InventoryEntry>>,
+}
+
+impl InventoryCollector {
+    pub fn take_entries(&self) -> BTreeMap<String, InventoryEntry> {
+        std::mem::take(&mut *self.entries.lock().unwrap())
+    }
+}
+
+impl TableCallback for InventoryCollector {
+    fn on_row_add(&self, row: &mut TableRow) {
+        let mut name = None;
+        let mut entry = InventoryEntry::default();
+        for (column, value) in row.data() {
+            match (column.as_str(), value) {
+                ("name", TableColumnValue::StringColumn(value)) => {
+                    name = Some(value.clone())
+                }
+                ("size", TableColumnValue::Uint64Column(value)) => {
+                    entry.size = *value
+                }
+                ("modified", TableColumnValue::OptionalUint64Column(value)) => {
+                    entry.modified = *value
+                }
+                ("checksum", TableColumnValue::OptionalStringColumn(value)) => {
+                    entry.checksum = value.clone()
+                }
+                _ => {}
+            }
+        }
+        if let Some(name) = name {
+            self.entries.lock().unwrap().insert(name, entry);
+        }
+    }
+}
+
+// keys only on a are removed, only on b added, and on both compared
+pub fn diff_inventories(
+    a: &BTreeMap<String, InventoryEntry>,
+    b: &BTreeMap<String, InventoryEntry>,
+    strategy: DiffStrategy,
+) -> Vec<(String, DiffChange)> {
+    let mut changes = Vec::new();
+    for (key, entry_a) in a {
+        match b.get(key) {
+            Some(entry_b) => {
+                if let Some(change) = strategy.compare(entry_a, entry_b) {
+                    changes.push((key.clone(), change));
+                }
+            }
+            None => changes.push((key.clone(), DiffChange::Removed)),
+        }
+    }
+    for key in b.keys() {
+        if !a.contains_key(key) {
+            changes.push((key.clone(), DiffChange::Added));
+        }
+    }
+    changes.sort_by(|x, y| x.0.cmp(&y.0));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_inventories() {
+        let entry = |size, modified, checksum: Option<&str>| InventoryEntry {
+            size,
+            modified: Some(modified),
+            checksum: checksum.map(String::from),
+        };
+        let a = BTreeMap::from([
+            ("a.csv".to_string(), entry(1, 10, Some("md5:aa"))),
+            ("b.csv".to_string(), entry(2, 10, Some("md5:bb"))),
+            ("c.csv".to_string(), entry(3, 10, None)),
+        ]);
+        let b = BTreeMap::from([
+            ("b.csv".to_string(), entry(2, 20, Some("md5:bb"))),
+            ("c.csv".to_string(), entry(4, 10, Some("md5:cc"))),
+            ("d.csv".to_string(), entry(5, 10, None)),
+        ]);
+        assert_eq!(
+            diff_inventories(&a, &b, DiffStrategy::Mtime),
+            vec![
+                ("a.csv".to_string(), DiffChange::Removed),
+                ("b.csv".to_string(), DiffChange::Modified),
+                ("c.csv".to_string(), DiffChange::Modified),
+                ("d.csv".to_string(), DiffChange::Added),
+            ]
+        );
+        // same checksum, c.csv has none on a and differs in size
+        let changes = diff_inventories(&a, &b, DiffStrategy::Etag);
+        assert_eq!(changes[1], ("c.csv".to_string(), DiffChange::Modified));
+        assert_eq!(changes.len(), 3);
+
+        // an ETag is not compared with an md5, but by size
+        let etag = entry(2, 10, Some("etag:bb"));
+        let strategy = DiffStrategy::Etag;
+        assert_eq!(strategy.compare(&a["b.csv"], &etag), None);
+        assert_eq!(
+            strategy.compare(&a["b.csv"], &entry(2, 10, Some("md5:cc"))),
+            Some(DiffChange::Modified)
+        );
+    }
+}
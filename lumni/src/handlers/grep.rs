@@ -0,0 +1,370 @@
+use std::mem;
+use std::thread;
+
+use futures::stream::{self, StreamExt};
+use regex::{Regex, RegexBuilder};
+
+use super::object_store::ObjectStore;
+#[cfg(feature = "compression")]
+use crate::base::compression::AutoDecoder;
+#[cfg(feature = "http_client")]
+use crate::base::throttle::{Throttle, ThrottleConfig};
+use crate::{EnvironmentConfig, GrepTable, LumniError};
+
+// prefix of the throttle settings of searching, e.g. GREP_BANDWIDTH_LIMIT
+pub const GREP_OPERATION: &str = "GREP";
+
+// which objects are searched and how. concurrency defaults to the number
+// of cpus
+#[derive(Debug, Clone)]
+pub struct GrepOptions {
+    name: Option<Regex>,
+    ignore_case: bool,
+    #[cfg(feature = "compression")]
+    decompress: Option<bool>, // DECOMPRESS of the config if not set
+    concurrency: usize,
+    max_count: Option<u64>,
+}
+
+impl GrepOptions {
+    pub fn new() -> Self {
+        GrepOptions {
+            name: None,
+            ignore_case: false,
+            #[cfg(feature = "compression")]
+            decompress: None,
+            concurrency: thread::available_parallelism()
+                .map_or(1, |cpus| cpus.get()),
+            max_count: None,
+        }
+    }
+
+    // a glob such as "*.log", matched against the last part of the key
+    pub fn set_name(mut self, glob: &str) -> Self {
+        self.name = Some(glob_to_regex(glob));
+        self
+    }
+
+    pub fn set_ignore_case(mut self, ignore_case: bool) -> Self {
+        self.ignore_case = ignore_case;
+        self
+    }
+
+    // objects that start as gzip, zstd, bzip2 or lz4 are searched
+    // decompressed
+    #[cfg(feature = "compression")]
+    pub fn set_decompress(mut self, decompress: bool) -> Self {
+        self.decompress = Some(decompress);
+        self
+    }
+
+    pub fn set_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    // matches per object, the rest of the object is not read
+    pub fn set_max_count(mut self, max_count: Option<u64>) -> Self {
+        self.max_count = max_count;
+        self
+    }
+
+    pub fn matches_name(&self, key: &str) -> bool {
+        let name = key.rsplit('/').next().unwrap_or(key);
+        self.name.as_ref().is_none_or(|re| re.is_match(name))
+    }
+
+    pub(crate) fn regex(&self, pattern: &str) -> Result<Regex, LumniError> {
+        RegexBuilder::new(pattern)
+            .case_insensitive(self.ignore_case)
+            .build()
+            .map_err(|e| LumniError::Config(format!("Invalid pattern: {}", e)))
+    }
+}
+
+impl Default for GrepOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// searches the objects, several at a time, and adds the matching lines to
+// the table in the order the objects complete
+pub(crate) async fn grep_objects(
+    bucket_uri: &str,
+    config: &EnvironmentConfig,
+    object_keys: Vec<String>,
+    regex: &Regex,
+    options: &GrepOptions,
+    table: &mut GrepTable,
+) -> Result<(), LumniError> {
+    let object_store = ObjectStore::new(bucket_uri, config.clone())?;
+    // as for downloads, not chained to the global throttle
+    #[cfg(feature = "http_client")]
+    let throttle = Throttle::new(ThrottleConfig::from_config(
+        config,
+        Some(GREP_OPERATION),
+    )?);
+
+    let searches = object_keys.into_iter().map(|object_key| {
+        let object_store = &object_store;
+        #[cfg(feature = "http_client")]
+        let throttle = &throttle;
+        async move {
+            #[cfg(feature = "http_client")]
+            let _permit = throttle.acquire().await;
+            let matches = grep_object(
+                object_store,
+                &object_key,
+                regex,
+                options,
+                #[cfg(feature = "http_client")]
+                throttle,
+            )
+            .await;
+            (object_key, matches)
+        }
+    });
+    let mut searches =
+        stream::iter(searches).buffer_unordered(options.concurrency);
+    while let Some((object_key, matches)) = searches.next().await {
+        // the bucket of an absolute local path is "/"
+        let uri = if bucket_uri.ends_with('/') {
+            format!("{}{}", bucket_uri, object_key)
+        } else {
+            format!("{}/{}", bucket_uri, object_key)
+        };
+        match matches {
+            Ok(matches) => {
+                for (line, text) in matches {
+                    table.add_match(&uri, line, &text)?;
+                }
+            }
+            Err(err) => table.add_failed(&uri, &err.to_string()),
+        }
+    }
+    Ok(())
+}
+
+// matches the lines while the chunks arrive, the object is not kept
+async fn grep_object(
+    object_store: &ObjectStore,
+    object_key: &str,
+    regex: &Regex,
+    options: &GrepOptions,
+    #[cfg(feature = "http_client")] throttle: &Throttle,
+) -> Result<Vec<(u64, String)>, LumniError> {
+    let mut stream = object_store.get_object_stream(object_key, None).await?;
+    let mut lines = LineMatcher::new(regex, options.max_count);
+    #[cfg(feature = "compression")]
+    let mut decoder = options
+        .decompress
+        .unwrap_or_else(|| AutoDecoder::enabled(object_store.config()))
+        .then(AutoDecoder::new);
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        #[cfg(feature = "http_client")]
+        throttle.consume(chunk.len()).await;
+        #[cfg(feature = "compression")]
+        if let Some(decoder) = decoder.as_mut() {
+            lines.push(&decoder.write(&chunk)?);
+        } else {
+            lines.push(&chunk);
+        }
+        #[cfg(not(feature = "compression"))]
+        lines.push(&chunk);
+        if lines.is_done() {
+            return Ok(lines.finish());
+        }
+    }
+    #[cfg(feature = "compression")]
+    if let Some(decoder) = decoder {
+        lines.push(&decoder.finish()?);
+    }
+    Ok(lines.finish())
+}
+
+// splits data into lines, a line can span chunks
+struct LineMatcher<'a> {
+    regex: &'a Regex,
+    max_count: Option<u64>,
+    partial: Vec<u8>,
+    line: u64,
+    matches: Vec<(u64, String)>,
+}
+
+impl<'a> LineMatcher<'a> {
+    fn new(regex: &'a Regex, max_count: Option<u64>) -> Self {
+        LineMatcher {
+            regex,
+            max_count,
+            partial: Vec::new(),
+            line: 0,
+            matches: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, data: &[u8]) {
+        let mut rest = data;
+        while let Some(end) = rest.iter().position(|b| *b == b'\n') {
+            if self.is_done() {
+                return;
+            }
+            if self.partial.is_empty() {
+                self.match_line(&rest[..end]);
+            } else {
+                self.partial.extend_from_slice(&rest[..end]);
+                let line = mem::take(&mut self.partial);
+                self.match_line(&line);
+            }
+            rest = &rest[end + 1..];
+        }
+        self.partial.extend_from_slice(rest);
+    }
+
+    fn is_done(&self) -> bool {
+        self.max_count
+            .is_some_and(|max| self.matches.len() as u64 >= max)
+    }
+
+    // the last line may not end with a newline
+    fn finish(mut self) -> Vec<(u64, String)> {
+        if !self.partial.is_empty() && !self.is_done() {
+            let line = mem::take(&mut self.partial);
+            self.match_line(&line);
+        }
+        self.matches
+    }
+
+    fn match_line(&mut self, line: &[u8]) {
+        self.line += 1;
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let text = String::from_utf8_lossy(line);
+        if self.regex.is_match(&text) {
+            self.matches.push((self.line, text.into_owned()));
+        }
+    }
+}
+
+// * matches any characters, ? a single one
+fn glob_to_regex(glob: &str) -> Regex {
+    let mut regex = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => regex.push_str("(?s:.*)"),
+            '?' => regex.push_str("(?s:.)"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    // only literals and wildcards, so always valid
+    Regex::new(&regex).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+
+    use futures::executor::block_on;
+
+    use super::*;
+    use crate::{ObjectStoreHandler, ParsedUri, Table};
+
+    #[test]
+    fn test_line_matcher() {
+        let regex = Regex::new("err").unwrap();
+        let mut lines = LineMatcher::new(&regex, None);
+        lines.push(b"ok\nfirst er");
+        lines.push(b"ror\r\nok\nlast error");
+        assert_eq!(
+            lines.finish(),
+            vec![
+                (2, "first error".to_string()),
+                (4, "last error".to_string())
+            ]
+        );
+
+        let mut lines = LineMatcher::new(&regex, Some(1));
+        lines.push(b"error 1\nerror 2\n");
+        assert!(lines.is_done());
+        assert_eq!(lines.finish(), vec![(1, "error 1".to_string())]);
+    }
+
+    #[test]
+    fn test_grep() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("logs/app")).unwrap();
+        fs::write(dir.path().join("logs/a.log"), "start\nERROR disk\n")
+            .unwrap();
+        fs::write(dir.path().join("logs/app/b.log"), "error net\nstop")
+            .unwrap();
+        fs::write(dir.path().join("logs/c.txt"), "error skipped\n").unwrap();
+        let uri = ParsedUri::from_uri(
+            &format!("localfs://{}/logs", dir.path().display()),
+            true,
+        );
+        let config = EnvironmentConfig::new(HashMap::new());
+        let options = GrepOptions::new()
+            .set_name("*.log")
+            .set_ignore_case(true)
+            .set_concurrency(2);
+
+        let table = block_on(
+            ObjectStoreHandler::new(None)
+                .grep(&uri, &config, "^error", &options, None),
+        )
+        .unwrap();
+        assert_eq!(table.len(), 2);
+        assert!(table.failed().is_empty());
+
+        let invalid = block_on(
+            ObjectStoreHandler::new(None)
+                .grep(&uri, &config, "(", &options, None),
+        );
+        assert!(matches!(invalid, Err(LumniError::Config(_))));
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_grep_decompress() {
+        use std::io::Write;
+
+        use crate::base::compression::DECOMPRESS_SETTING;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut gzip = flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        );
+        gzip.write_all(b"one\nerror two\n").unwrap();
+        fs::write(dir.path().join("a.log.gz"), gzip.finish().unwrap()).unwrap();
+        let uri = ParsedUri::from_uri(
+            &format!("localfs://{}", dir.path().display()),
+            true,
+        );
+        let config = EnvironmentConfig::new(HashMap::new());
+        let handler = ObjectStoreHandler::new(None);
+
+        let options = GrepOptions::new().set_decompress(false);
+        let table =
+            block_on(handler.grep(&uri, &config, "error", &options, None))
+                .unwrap();
+        assert_eq!(table.len(), 0);
+
+        let options = GrepOptions::new();
+        let table =
+            block_on(handler.grep(&uri, &config, "error", &options, None))
+                .unwrap();
+        assert_eq!(table.len(), 1);
+
+        let config = EnvironmentConfig::with_setting(
+            DECOMPRESS_SETTING.to_string(),
+            "false".to_string(),
+        );
+        let table =
+            block_on(handler.grep(&uri, &config, "error", &options, None))
+                .unwrap();
+        assert_eq!(table.len(), 0);
+    }
+}
@@ -1,9 +1,10 @@
-use crate::http::client::HttpClient;
-use crate::{BinaryCallbackWrapper, LakestreamError};
+use crate::http::client::{HttpClient, HttpClientError};
+use crate::{BinaryCallbackWrapper, Conditions, LumniError};
 
 pub struct HttpHandler {
     client: HttpClient,
     callback: Option<BinaryCallbackWrapper>,
+    conditions: Conditions,
 }
 
 impl HttpHandler {
@@ -11,18 +12,31 @@ impl HttpHandler {
         Self {
             client: HttpClient::new(),
             callback: callback,
+            conditions: Conditions::default(),
         }
     }
 
-    pub async fn get(
-        &self,
-        url: &str,
-    ) -> Result<Option<Vec<u8>>, LakestreamError> {
-        let response = self
+    // sent with each request, a response that is not modified gives no
+    // data and does not call the callback
+    pub fn set_conditions(mut self, conditions: Conditions) -> Self {
+        self.conditions = conditions;
+        self
+    }
+
+    pub async fn get(&self, url: &str) -> Result<Option<Vec<u8>>, LumniError> {
+        let headers = self.conditions.headers();
+        let response = match self
             .client
-            .get(url, None, None, None, None)
+            .get(url, Some(&headers), None, None, None)
             .await
-            .map_err(|e| LakestreamError::HttpClientError(e))?;
+        {
+            Ok(response) => response,
+            Err(HttpClientError::HttpError(304, _)) => return Ok(None),
+            Err(HttpClientError::HttpError(412, _)) => {
+                return Err(LumniError::PreconditionFailed(url.to_string()))
+            }
+            Err(e) => return Err(LumniError::HttpClientError(e)),
+        };
         let data = response.body();
 
         if self.callback.is_some() {
@@ -1,6 +1,24 @@
+mod access;
+mod conditions;
+mod diff;
+mod grep;
 pub mod object_store;
+mod query;
+mod registry;
 
-pub use object_store::ObjectStoreHandler;
+pub use access::{AccessOperation, AccessReport, AccessStatus};
+pub use conditions::Conditions;
+pub use diff::DiffStrategy;
+pub(crate) use diff::InventoryEntry;
+pub use grep::{GrepOptions, GREP_OPERATION};
+pub(crate) use registry::is_registered;
+
+pub use object_store::ObjectStoreTrait;
+pub use object_store::{
+    ByteRange, ConfirmCallback, DeleteResult, ObjectStoreHandler, ObjectStream,
+    UploadOptions,
+};
+pub use registry::{register_backend, ObjectStoreBackendFactory};
 
 #[cfg(feature = "http_client")]
 mod http_handler;
@@ -1,27 +1,65 @@
 use core::panic;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
-use std::sync::Arc;
+use std::io::Read;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
 
 use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::{self, Stream};
 use log::debug;
-use sqlparser::ast::{Expr, Query, SelectItem, SetExpr, Statement};
+use sqlparser::ast::{Query, Statement};
 use sqlparser::dialect::GenericDialect;
 use sqlparser::parser::Parser;
 
+use super::access::{probe_access, AccessReport};
+use super::conditions::{if_exists, Conditions};
+use super::diff::{
+    diff_inventories, DiffStrategy, InventoryCollector, InventoryEntry,
+};
+use super::grep::{grep_objects, GrepOptions};
+use super::query::SelectQuery;
+use super::registry::{is_registered, RegisteredBucket};
+use crate::azure::backend::AzureBucket;
+#[cfg(feature = "compression")]
+use crate::base::compression::{AutoDecoder, CompressReader, Compression};
+use crate::base::listing_cache::{
+    listing_key, listing_store, table_rows, ListingCache,
+};
+use crate::gcs::backend::GCSBucket;
+use crate::hdfs::backend::HdfsBucket;
 use crate::localfs::backend::LocalFsBucket;
 use crate::s3::backend::S3Bucket;
+use crate::s3::{
+    RestoreRequest, RestoreStatus, ServerSideEncryption, SignedRequest,
+};
+#[cfg(feature = "sftp")]
+use crate::sftp::backend::SftpBucket;
 use crate::table::object_store::table_from_list_bucket;
-use crate::table::{FileObjectTable, Table, TableCallback};
+use crate::table::{
+    DiffSide, DiffTable, FileObjectTable, GrepTable, Table, TableCallback,
+    TableColumnValue, TableRow, TableStream,
+};
 use crate::{
-    BinaryCallbackWrapper, EnvironmentConfig, FileObjectFilter,
-    LakestreamError, ObjectStoreTable, ParsedUri, UriScheme,
+    BinaryCallbackWrapper, EnvironmentConfig, FileObjectFilter, LumniError,
+    MemoryBudget, ObjectMetadata, ObjectStoreTable, ParsedUri,
+    PlannedOperation, UriScheme, WatchCallback, DEFAULT_UPLOAD_CONCURRENCY,
+    DEFAULT_UPLOAD_PART_SIZE, LIST_ALL_KEYS,
 };
 
 #[derive(Debug, Clone)]
 pub enum ObjectStore {
     S3Bucket(S3Bucket),
+    GCSBucket(GCSBucket),
+    AzureBucket(AzureBucket),
+    HdfsBucket(HdfsBucket),
     LocalFsBucket(LocalFsBucket),
+    #[cfg(feature = "sftp")]
+    SftpBucket(SftpBucket),
+    // a scheme registered with register_backend
+    Registered(RegisteredBucket),
 }
 
 impl ObjectStore {
@@ -34,11 +72,46 @@ impl ObjectStore {
             let bucket =
                 S3Bucket::new(name, config).map_err(|err| err.to_string())?;
             Ok(ObjectStore::S3Bucket(bucket))
+        } else if name.starts_with("gs://") {
+            let name = name.trim_start_matches("gs://");
+            let bucket =
+                GCSBucket::new(name, config).map_err(|err| err.to_string())?;
+            Ok(ObjectStore::GCSBucket(bucket))
+        } else if name.starts_with("az://") || name.starts_with("abfss://") {
+            let name = name
+                .trim_start_matches("az://")
+                .trim_start_matches("abfss://");
+            let bucket = AzureBucket::new(name, config)
+                .map_err(|err| err.to_string())?;
+            Ok(ObjectStore::AzureBucket(bucket))
+        } else if name.starts_with("hdfs://") {
+            let name = name.trim_start_matches("hdfs://");
+            let bucket =
+                HdfsBucket::new(name, config).map_err(|err| err.to_string())?;
+            Ok(ObjectStore::HdfsBucket(bucket))
         } else if name.starts_with("localfs://") {
             let name = name.trim_start_matches("localfs://");
             let local_fs = LocalFsBucket::new(name, config)
                 .map_err(|err| err.to_string())?;
             Ok(ObjectStore::LocalFsBucket(local_fs))
+        } else if name.starts_with("sftp://") {
+            #[cfg(feature = "sftp")]
+            {
+                let name = name.trim_start_matches("sftp://");
+                let bucket = SftpBucket::new(name, config)
+                    .map_err(|err| err.to_string())?;
+                Ok(ObjectStore::SftpBucket(bucket))
+            }
+            #[cfg(not(feature = "sftp"))]
+            Err("sftp:// requires lumni built with the sftp feature"
+                .to_string())
+        } else if let Some((scheme, name)) = name
+            .split_once("://")
+            .filter(|(scheme, _)| is_registered(scheme))
+        {
+            let bucket = RegisteredBucket::new(scheme, name, config)
+                .map_err(|err| err.to_string())?;
+            Ok(ObjectStore::Registered(bucket))
         } else {
             // add name to error message
             let err_msg = format!("Unsupported object store: {}", name);
@@ -49,215 +122,1877 @@ impl ObjectStore {
     pub fn name(&self) -> &str {
         match self {
             ObjectStore::S3Bucket(bucket) => bucket.name(),
+            ObjectStore::GCSBucket(bucket) => bucket.name(),
+            ObjectStore::AzureBucket(bucket) => bucket.name(),
+            ObjectStore::HdfsBucket(bucket) => bucket.name(),
             ObjectStore::LocalFsBucket(local_fs) => local_fs.name(),
+            #[cfg(feature = "sftp")]
+            ObjectStore::SftpBucket(bucket) => bucket.name(),
+            ObjectStore::Registered(bucket) => bucket.store().name(),
+        }
+    }
+
+    pub fn config(&self) -> &EnvironmentConfig {
+        match self {
+            ObjectStore::S3Bucket(bucket) => bucket.config(),
+            ObjectStore::GCSBucket(bucket) => bucket.config(),
+            ObjectStore::AzureBucket(bucket) => bucket.config(),
+            ObjectStore::HdfsBucket(bucket) => bucket.config(),
+            ObjectStore::LocalFsBucket(local_fs) => local_fs.config(),
+            #[cfg(feature = "sftp")]
+            ObjectStore::SftpBucket(bucket) => bucket.config(),
+            ObjectStore::Registered(bucket) => bucket.store().config(),
+        }
+    }
+
+    pub fn uri(&self) -> String {
+        match self {
+            ObjectStore::S3Bucket(bucket) => {
+                format!("s3://{}", bucket.name())
+            }
+            ObjectStore::GCSBucket(bucket) => {
+                format!("gs://{}", bucket.name())
+            }
+            ObjectStore::AzureBucket(bucket) => {
+                if bucket.is_abfss() {
+                    format!("abfss://{}", bucket.name())
+                } else {
+                    format!("az://{}", bucket.name())
+                }
+            }
+            ObjectStore::HdfsBucket(bucket) => {
+                format!("hdfs://{}", bucket.name())
+            }
+            ObjectStore::LocalFsBucket(local_fs) => {
+                format!("{}", local_fs.name())
+            }
+            #[cfg(feature = "sftp")]
+            ObjectStore::SftpBucket(bucket) => {
+                format!("sftp://{}", bucket.name())
+            }
+            ObjectStore::Registered(bucket) => {
+                format!("{}://{}", bucket.scheme(), bucket.store().name())
+            }
+        }
+    }
+
+    pub async fn list_files(
+        &self,
+        prefix: Option<&str>,
+        selected_columns: &Option<Vec<&str>>,
+        recursive: bool,
+        max_files: Option<u32>,
+        filter: &Option<FileObjectFilter>,
+        callback: Option<Arc<dyn TableCallback>>,
+    ) -> Result<Box<dyn Table>, LumniError> {
+        let table = self
+            .list_file_table(
+                prefix,
+                selected_columns,
+                recursive,
+                max_files,
+                filter,
+                callback,
+            )
+            .await?;
+        Ok(Box::new(table))
+    }
+
+    // as list_files, reuses a listing of the cache if LISTING_CACHE_TTL
+    // is set. local files are not cached, listing them is cheap
+    pub async fn list_files_cached(
+        &self,
+        prefix: Option<&str>,
+        selected_columns: &Option<Vec<&str>>,
+        recursive: bool,
+        max_files: Option<u32>,
+        filter: &Option<FileObjectFilter>,
+        callback: Option<Arc<dyn TableCallback>>,
+    ) -> Result<Box<dyn Table>, LumniError> {
+        let cache = match self {
+            ObjectStore::LocalFsBucket(_) => None,
+            _ => ListingCache::from_config(self.config())?,
+        };
+        let Some(cache) = cache else {
+            return self
+                .list_files(
+                    prefix,
+                    selected_columns,
+                    recursive,
+                    max_files,
+                    filter,
+                    callback,
+                )
+                .await;
+        };
+
+        let store = listing_store(&self.uri(), self.config());
+        let key = listing_key(
+            self.config(),
+            prefix,
+            selected_columns,
+            recursive,
+            max_files,
+            filter,
+        );
+        if let Some(rows) = cache.get(&store, &key) {
+            debug!("Listing of {} read from the cache", self.uri());
+            let mut table = FileObjectTable::new(selected_columns, callback)
+                .set_memory_budget(MemoryBudget::from_config(self.config())?);
+            for row in rows {
+                table.add_row(row).map_err(LumniError::Internal)?;
+            }
+            return Ok(Box::new(table));
+        }
+
+        // a table without all rows can not be cached
+        let retain_rows = callback
+            .as_ref()
+            .is_none_or(|callback| callback.retain_rows());
+        let table = self
+            .list_file_table(
+                prefix,
+                selected_columns,
+                recursive,
+                max_files,
+                filter,
+                callback,
+            )
+            .await?;
+        if retain_rows && !table.is_streaming() {
+            if let Err(e) = cache.insert(&store, &key, table_rows(&table)) {
+                debug!("Listing of {} not cached: {}", self.uri(), e);
+            }
+        }
+        Ok(Box::new(table))
+    }
+
+    async fn list_file_table(
+        &self,
+        prefix: Option<&str>,
+        selected_columns: &Option<Vec<&str>>,
+        recursive: bool,
+        max_files: Option<u32>,
+        filter: &Option<FileObjectFilter>,
+        callback: Option<Arc<dyn TableCallback>>,
+    ) -> Result<FileObjectTable, LumniError> {
+        let mut table = FileObjectTable::new(selected_columns, callback)
+            .set_memory_budget(MemoryBudget::from_config(self.config())?);
+
+        match self {
+            ObjectStore::S3Bucket(bucket) => {
+                bucket
+                    .list_files(
+                        prefix,
+                        selected_columns,
+                        recursive,
+                        max_files,
+                        filter,
+                        &mut table,
+                    )
+                    .await
+            }
+            ObjectStore::GCSBucket(bucket) => {
+                bucket
+                    .list_files(
+                        prefix,
+                        selected_columns,
+                        recursive,
+                        max_files,
+                        filter,
+                        &mut table,
+                    )
+                    .await
+            }
+            ObjectStore::AzureBucket(bucket) => {
+                bucket
+                    .list_files(
+                        prefix,
+                        selected_columns,
+                        recursive,
+                        max_files,
+                        filter,
+                        &mut table,
+                    )
+                    .await
+            }
+            ObjectStore::HdfsBucket(bucket) => {
+                bucket
+                    .list_files(
+                        prefix,
+                        selected_columns,
+                        recursive,
+                        max_files,
+                        filter,
+                        &mut table,
+                    )
+                    .await
+            }
+            ObjectStore::LocalFsBucket(local_fs) => {
+                local_fs
+                    .list_files(
+                        prefix,
+                        selected_columns,
+                        recursive,
+                        max_files,
+                        filter,
+                        &mut table,
+                    )
+                    .await
+            }
+            #[cfg(feature = "sftp")]
+            ObjectStore::SftpBucket(bucket) => {
+                bucket
+                    .list_files(
+                        prefix,
+                        selected_columns,
+                        recursive,
+                        max_files,
+                        filter,
+                        &mut table,
+                    )
+                    .await
+            }
+            ObjectStore::Registered(bucket) => {
+                bucket
+                    .store()
+                    .list_files(
+                        prefix,
+                        selected_columns,
+                        recursive,
+                        max_files,
+                        filter,
+                        &mut table,
+                    )
+                    .await
+            }
+        }?;
+        Ok(table)
+    }
+
+    // keys of all files under the prefix, relative to the bucket. Every
+    // page is listed, not only the first
+    pub async fn list_keys(
+        &self,
+        prefix: Option<&str>,
+    ) -> Result<Vec<String>, LumniError> {
+        self.list_keys_up_to(prefix, Some(LIST_ALL_KEYS)).await
+    }
+
+    // as list_keys, stops after max_keys entries are listed
+    pub async fn list_keys_up_to(
+        &self,
+        prefix: Option<&str>,
+        max_keys: Option<u32>,
+    ) -> Result<Vec<String>, LumniError> {
+        let collector = Arc::new(KeyCollector::default());
+        self.list_files(
+            prefix,
+            &Some(vec!["name"]),
+            true,
+            max_keys,
+            &None,
+            Some(collector.clone()),
+        )
+        .await?;
+        let names = collector.keys.lock().unwrap().clone();
+        let keys = match self {
+            // local files are listed with their full path, directories
+            // only by their name
+            ObjectStore::LocalFsBucket(local_fs) => names
+                .into_iter()
+                .filter(|name| Path::new(name).is_file())
+                .filter_map(|name| {
+                    name.strip_prefix(local_fs.name())
+                        .map(|key| key.trim_start_matches('/').to_string())
+                })
+                .collect(),
+            // common prefixes end with a slash and are not objects
+            _ => names
+                .into_iter()
+                .filter(|name| !name.ends_with('/'))
+                .collect(),
+        };
+        Ok(keys)
+    }
+
+    pub async fn delete_object(&self, key: &str) -> Result<(), LumniError> {
+        let result = match self {
+            ObjectStore::S3Bucket(bucket) => bucket.delete_object(key).await,
+            ObjectStore::GCSBucket(bucket) => bucket.delete_object(key).await,
+            ObjectStore::AzureBucket(bucket) => bucket.delete_object(key).await,
+            ObjectStore::HdfsBucket(bucket) => bucket.delete_object(key).await,
+            ObjectStore::LocalFsBucket(local_fs) => {
+                local_fs.delete_object(key).await
+            }
+            #[cfg(feature = "sftp")]
+            ObjectStore::SftpBucket(bucket) => bucket.delete_object(key).await,
+            ObjectStore::Registered(bucket) => {
+                bucket.store().delete_object(key).await
+            }
+        };
+        self.invalidate_listings();
+        result
+    }
+
+    pub async fn delete_objects(
+        &self,
+        keys: &[String],
+    ) -> Result<DeleteResult, LumniError> {
+        let result = match self {
+            ObjectStore::S3Bucket(bucket) => bucket.delete_objects(keys).await,
+            ObjectStore::GCSBucket(bucket) => bucket.delete_objects(keys).await,
+            ObjectStore::AzureBucket(bucket) => {
+                bucket.delete_objects(keys).await
+            }
+            ObjectStore::HdfsBucket(bucket) => {
+                bucket.delete_objects(keys).await
+            }
+            ObjectStore::LocalFsBucket(local_fs) => {
+                local_fs.delete_objects(keys).await
+            }
+            #[cfg(feature = "sftp")]
+            ObjectStore::SftpBucket(bucket) => {
+                bucket.delete_objects(keys).await
+            }
+            ObjectStore::Registered(bucket) => {
+                bucket.store().delete_objects(keys).await
+            }
+        };
+        self.invalidate_listings();
+        result
+    }
+
+    pub async fn get_object(
+        &self,
+        key: &str,
+        data: &mut Vec<u8>,
+    ) -> Result<(), LumniError> {
+        match self {
+            ObjectStore::S3Bucket(bucket) => bucket.get_object(key, data).await,
+            ObjectStore::GCSBucket(bucket) => {
+                bucket.get_object(key, data).await
+            }
+            ObjectStore::AzureBucket(bucket) => {
+                bucket.get_object(key, data).await
+            }
+            ObjectStore::HdfsBucket(bucket) => {
+                bucket.get_object(key, data).await
+            }
+            ObjectStore::LocalFsBucket(local_fs) => {
+                local_fs.get_object(key, data).await
+            }
+            #[cfg(feature = "sftp")]
+            ObjectStore::SftpBucket(bucket) => {
+                bucket.get_object(key, data).await
+            }
+            ObjectStore::Registered(bucket) => {
+                bucket.store().get_object(key, data).await
+            }
+        }
+    }
+
+    pub async fn get_object_if(
+        &self,
+        key: &str,
+        conditions: &Conditions,
+        data: &mut Vec<u8>,
+    ) -> Result<bool, LumniError> {
+        match self {
+            ObjectStore::S3Bucket(bucket) => {
+                bucket.get_object_if(key, conditions, data).await
+            }
+            ObjectStore::GCSBucket(bucket) => {
+                bucket.get_object_if(key, conditions, data).await
+            }
+            ObjectStore::AzureBucket(bucket) => {
+                bucket.get_object_if(key, conditions, data).await
+            }
+            ObjectStore::HdfsBucket(bucket) => {
+                bucket.get_object_if(key, conditions, data).await
+            }
+            ObjectStore::LocalFsBucket(local_fs) => {
+                local_fs.get_object_if(key, conditions, data).await
+            }
+            #[cfg(feature = "sftp")]
+            ObjectStore::SftpBucket(bucket) => {
+                bucket.get_object_if(key, conditions, data).await
+            }
+            ObjectStore::Registered(bucket) => {
+                bucket.store().get_object_if(key, conditions, data).await
+            }
+        }
+    }
+
+    pub async fn get_object_stream(
+        &self,
+        key: &str,
+        range: Option<ByteRange>,
+    ) -> Result<ObjectStream, LumniError> {
+        match self {
+            ObjectStore::S3Bucket(bucket) => {
+                bucket.get_object_stream(key, range).await
+            }
+            ObjectStore::GCSBucket(bucket) => {
+                bucket.get_object_stream(key, range).await
+            }
+            ObjectStore::AzureBucket(bucket) => {
+                bucket.get_object_stream(key, range).await
+            }
+            ObjectStore::HdfsBucket(bucket) => {
+                bucket.get_object_stream(key, range).await
+            }
+            ObjectStore::LocalFsBucket(local_fs) => {
+                local_fs.get_object_stream(key, range).await
+            }
+            #[cfg(feature = "sftp")]
+            ObjectStore::SftpBucket(bucket) => {
+                bucket.get_object_stream(key, range).await
+            }
+            ObjectStore::Registered(bucket) => {
+                bucket.store().get_object_stream(key, range).await
+            }
+        }
+    }
+
+    pub async fn create_bucket(&self) -> Result<(), LumniError> {
+        match self {
+            ObjectStore::S3Bucket(bucket) => bucket.create_bucket().await,
+            ObjectStore::LocalFsBucket(local_fs) => local_fs.create_bucket(),
+            _ => Err(self.unsupported("Creating buckets")),
+        }
+    }
+
+    // force deletes all objects in the bucket first
+    pub async fn delete_bucket(&self, force: bool) -> Result<(), LumniError> {
+        let result = match self {
+            ObjectStore::S3Bucket(bucket) => {
+                if force {
+                    // all pages, S3 refuses to delete a bucket that is
+                    // not empty
+                    let keys = self.list_keys(None).await?;
+                    let result = self.delete_objects(&keys).await?;
+                    if let Some((key, reason)) = result.failed().first() {
+                        return Err(LumniError::Internal(format!(
+                            "Failed to delete {}: {}",
+                            key, reason
+                        )));
+                    }
+                }
+                bucket.delete_bucket().await
+            }
+            ObjectStore::LocalFsBucket(local_fs) => {
+                local_fs.delete_bucket(force)
+            }
+            _ => Err(self.unsupported("Deleting buckets")),
+        };
+        self.invalidate_listings();
+        result
+    }
+
+    // response headers if the bucket exists
+    pub async fn head_bucket(
+        &self,
+    ) -> Result<Option<HashMap<String, String>>, LumniError> {
+        match self {
+            ObjectStore::S3Bucket(bucket) => bucket.head_bucket().await,
+            ObjectStore::LocalFsBucket(local_fs) => Ok(local_fs.head_bucket()),
+            _ => Err(self.unsupported("Head bucket")),
+        }
+    }
+
+    pub async fn get_object_metadata(
+        &self,
+        key: &str,
+    ) -> Result<ObjectMetadata, LumniError> {
+        match self {
+            ObjectStore::S3Bucket(bucket) => {
+                bucket.get_object_metadata(key).await
+            }
+            ObjectStore::GCSBucket(bucket) => {
+                bucket.get_object_metadata(key).await
+            }
+            ObjectStore::AzureBucket(bucket) => {
+                bucket.get_object_metadata(key).await
+            }
+            ObjectStore::HdfsBucket(bucket) => {
+                bucket.get_object_metadata(key).await
+            }
+            ObjectStore::LocalFsBucket(local_fs) => {
+                local_fs.get_object_metadata(key).await
+            }
+            #[cfg(feature = "sftp")]
+            ObjectStore::SftpBucket(bucket) => {
+                bucket.get_object_metadata(key).await
+            }
+            ObjectStore::Registered(bucket) => {
+                bucket.store().get_object_metadata(key).await
+            }
+        }
+    }
+
+    pub async fn set_object_tags(
+        &self,
+        key: &str,
+        tags: &HashMap<String, String>,
+    ) -> Result<(), LumniError> {
+        match self {
+            ObjectStore::S3Bucket(bucket) => {
+                bucket.set_object_tags(key, tags).await
+            }
+            _ => Err(self.unsupported("Tagging objects")),
+        }
+    }
+
+    // versions and delete markers, with the columns of list_files plus
+    // version_id, is_latest and delete_marker
+    pub async fn list_object_versions(
+        &self,
+        prefix: Option<&str>,
+        recursive: bool,
+        max_files: Option<u32>,
+        filter: &Option<FileObjectFilter>,
+        callback: Option<Arc<dyn TableCallback>>,
+    ) -> Result<Box<dyn Table>, LumniError> {
+        let columns = Some(vec![
+            "name",
+            "size",
+            "modified",
+            "version_id",
+            "is_latest",
+            "delete_marker",
+        ]);
+        let mut table = FileObjectTable::new(&columns, callback)
+            .set_memory_budget(MemoryBudget::from_config(self.config())?);
+        match self {
+            ObjectStore::S3Bucket(bucket) => {
+                bucket
+                    .list_object_versions(
+                        prefix, recursive, max_files, filter, &mut table,
+                    )
+                    .await?
+            }
+            _ => return Err(self.unsupported("Listing object versions")),
+        }
+        Ok(Box::new(table))
+    }
+
+    // the objects as they were at as_of (epoch seconds), rebuilt from the
+    // versions of a bucket with versioning enabled
+    #[allow(clippy::too_many_arguments)]
+    pub async fn list_objects_as_of(
+        &self,
+        prefix: Option<&str>,
+        selected_columns: &Option<Vec<&str>>,
+        recursive: bool,
+        max_files: Option<u32>,
+        filter: &Option<FileObjectFilter>,
+        as_of: u64,
+        callback: Option<Arc<dyn TableCallback>>,
+    ) -> Result<Box<dyn Table>, LumniError> {
+        let mut table = FileObjectTable::new(selected_columns, callback)
+            .set_memory_budget(MemoryBudget::from_config(self.config())?);
+        match self {
+            ObjectStore::S3Bucket(bucket) => {
+                bucket
+                    .list_objects_as_of(
+                        prefix, recursive, max_files, filter, as_of, &mut table,
+                    )
+                    .await?
+            }
+            _ => {
+                return Err(self.unsupported("Listing objects as of a time"))
+            }
+        }
+        Ok(Box::new(table))
+    }
+
+    pub async fn restore_object(
+        &self,
+        key: &str,
+        request: &RestoreRequest,
+    ) -> Result<RestoreStatus, LumniError> {
+        match self {
+            ObjectStore::S3Bucket(bucket) => {
+                bucket.restore_object(key, request).await
+            }
+            _ => Err(self.unsupported("Restoring archived objects")),
+        }
+    }
+
+    // calls back on every change until the callback returns false
+    pub async fn watch(
+        &self,
+        prefix: Option<&str>,
+        recursive: bool,
+        filter: &Option<FileObjectFilter>,
+        callback: Arc<dyn WatchCallback>,
+    ) -> Result<(), LumniError> {
+        match self {
+            #[cfg(feature = "watch")]
+            ObjectStore::LocalFsBucket(local_fs) => {
+                local_fs.watch(prefix, recursive, filter, callback).await
+            }
+            #[cfg(not(feature = "watch"))]
+            ObjectStore::LocalFsBucket(_) => Err(LumniError::Config(
+                "Watching requires lumni built with the watch feature"
+                    .to_string(),
+            )),
+            ObjectStore::S3Bucket(bucket) => {
+                bucket.watch(prefix, recursive, filter, callback).await
+            }
+            _ => Err(self.unsupported("Watching for changes")),
+        }
+    }
+
+    fn unsupported(&self, operation: &str) -> LumniError {
+        // uri of localfs is a plain path
+        let uri = self.uri();
+        let scheme = match uri.split_once("://") {
+            Some((scheme, _)) => scheme,
+            None => "localfs",
+        };
+        LumniError::Config(format!(
+            "{} is not supported for {}://",
+            operation, scheme
+        ))
+    }
+
+    // cached listings are dropped after a write through lumni, also when it
+    // failed part way. dropped before, a listing during the write could
+    // cache the old state again. changes made by others show up when the
+    // ttl expires
+    fn invalidate_listings(&self) {
+        if let ObjectStore::LocalFsBucket(_) = self {
+            return;
+        }
+        let store = listing_store(&self.uri(), self.config());
+        if let Err(e) = ListingCache::invalidate(&store) {
+            debug!("Cached listings of {} not dropped: {}", self.uri(), e);
+        }
+    }
+
+    pub async fn put_object_multipart(
+        &self,
+        key: &str,
+        source: &mut dyn Read,
+        options: &UploadOptions,
+        callback: Option<&BinaryCallbackWrapper>,
+    ) -> Result<u64, LumniError> {
+        // S3 checks the conditions when the upload completes, the other
+        // backends are checked before, which leaves a short window for a
+        // concurrent write
+        let conditions = options.conditions();
+        if !conditions.is_empty() && !matches!(self, ObjectStore::S3Bucket(_))
+        {
+            let metadata = if_exists(self.get_object_metadata(key).await)?;
+            conditions.check_write(key, metadata.as_ref())?;
+        }
+        let result = match self {
+            ObjectStore::S3Bucket(bucket) => {
+                bucket
+                    .put_object_multipart(key, source, options, callback)
+                    .await
+            }
+            ObjectStore::GCSBucket(bucket) => {
+                bucket
+                    .put_object_multipart(key, source, options, callback)
+                    .await
+            }
+            ObjectStore::AzureBucket(bucket) => {
+                bucket
+                    .put_object_multipart(key, source, options, callback)
+                    .await
+            }
+            ObjectStore::HdfsBucket(bucket) => {
+                bucket
+                    .put_object_multipart(key, source, options, callback)
+                    .await
+            }
+            ObjectStore::LocalFsBucket(local_fs) => {
+                local_fs
+                    .put_object_multipart(key, source, options, callback)
+                    .await
+            }
+            #[cfg(feature = "sftp")]
+            ObjectStore::SftpBucket(bucket) => {
+                bucket
+                    .put_object_multipart(key, source, options, callback)
+                    .await
+            }
+            ObjectStore::Registered(bucket) => {
+                bucket
+                    .store()
+                    .put_object_multipart(key, source, options, callback)
+                    .await
+            }
+        };
+        self.invalidate_listings();
+        result
+    }
+}
+
+#[derive(Default)]
+struct KeyCollector {
+    keys: Mutex<Vec<String>>,
+}
+
+impl TableCallback for KeyCollector {
+    fn on_row_add(&self, row: &mut TableRow) {
+        for (column, value) in row.data() {
+            if let ("name", TableColumnValue::StringColumn(name)) =
+                (column.as_str(), value)
+            {
+                self.keys.lock().unwrap().push(name.clone());
+            }
+        }
+    }
+}
+
+// called for each key before it is deleted, returning false skips the key
+pub type ConfirmCallback<'a> = dyn Fn(&str) -> bool + 'a;
+
+#[derive(Debug, Clone, Default)]
+pub struct DeleteResult {
+    deleted: Vec<String>,
+    skipped: Vec<String>,
+    failed: Vec<(String, String)>, // key, reason
+}
+
+impl DeleteResult {
+    pub fn add_deleted(&mut self, key: &str) {
+        self.deleted.push(key.to_string());
+    }
+
+    pub fn add_skipped(&mut self, key: &str) {
+        self.skipped.push(key.to_string());
+    }
+
+    pub fn add_failed(&mut self, key: &str, reason: &str) {
+        self.failed.push((key.to_string(), reason.to_string()));
+    }
+
+    pub fn deleted(&self) -> &[String] {
+        &self.deleted
+    }
+
+    pub fn skipped(&self) -> &[String] {
+        &self.skipped
+    }
+
+    pub fn failed(&self) -> &[(String, String)] {
+        &self.failed
+    }
+}
+
+pub type ObjectStream = Pin<Box<dyn Stream<Item = Result<Bytes, LumniError>>>>;
+
+// byte range of an object, the end is inclusive as in a HTTP Range header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    start: u64,
+    end: Option<u64>,
+}
+
+impl ByteRange {
+    pub fn new(start: u64, end: Option<u64>) -> Result<Self, LumniError> {
+        if end.is_some_and(|end| end < start) {
+            return Err(LumniError::Config(format!(
+                "Invalid byte range: {}-{}",
+                start,
+                end.unwrap_or_default()
+            )));
+        }
+        Ok(ByteRange { start, end })
+    }
+
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    pub fn end(&self) -> Option<u64> {
+        self.end
+    }
+
+    pub fn to_header_value(&self) -> String {
+        match self.end {
+            Some(end) => format!("bytes={}-{}", self.start, end),
+            None => format!("bytes={}-", self.start),
+        }
+    }
+
+    // returns the part of data within the range
+    pub fn slice<'a>(&self, data: &'a [u8]) -> &'a [u8] {
+        let len = data.len() as u64;
+        let start = self.start.min(len);
+        let end = self.end.map_or(len, |end| (end + 1).min(len));
+        &data[start as usize..end as usize]
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UploadOptions {
+    part_size: usize,
+    concurrency: usize,
+    content_type: Option<String>,
+    metadata: HashMap<String, String>,
+    // S3 only, overrides S3_SSE of the config
+    server_side_encryption: Option<ServerSideEncryption>,
+    #[cfg(feature = "compression")]
+    compression: Option<Compression>,
+    conditions: Conditions,
+}
+
+impl Default for UploadOptions {
+    fn default() -> Self {
+        UploadOptions {
+            part_size: DEFAULT_UPLOAD_PART_SIZE,
+            concurrency: DEFAULT_UPLOAD_CONCURRENCY,
+            content_type: None,
+            metadata: HashMap::new(),
+            server_side_encryption: None,
+            #[cfg(feature = "compression")]
+            compression: None,
+            conditions: Conditions::default(),
+        }
+    }
+}
+
+impl UploadOptions {
+    pub fn new() -> Self {
+        UploadOptions::default()
+    }
+
+    pub fn set_part_size(mut self, part_size: usize) -> Self {
+        self.part_size = part_size;
+        self
+    }
+
+    pub fn set_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn set_content_type(mut self, content_type: &str) -> Self {
+        self.content_type = Some(content_type.to_string());
+        self
+    }
+
+    // user-defined metadata, stored with the object by backends that
+    // support it
+    pub fn set_metadata(mut self, key: &str, value: &str) -> Self {
+        self.metadata.insert(key.to_lowercase(), value.to_string());
+        self
+    }
+
+    pub fn set_server_side_encryption(
+        mut self,
+        encryption: ServerSideEncryption,
+    ) -> Self {
+        self.server_side_encryption = Some(encryption);
+        self
+    }
+
+    // the source is compressed while it is uploaded, the size returned
+    // by put_object is the compressed size
+    #[cfg(feature = "compression")]
+    pub fn set_compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    // e.g. If-None-Match "*" to not overwrite an existing object, or
+    // If-Match its ETag to not overwrite a concurrent modification
+    pub fn set_conditions(mut self, conditions: Conditions) -> Self {
+        self.conditions = conditions;
+        self
+    }
+
+    pub fn part_size(&self) -> usize {
+        self.part_size
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    pub fn metadata(&self) -> &HashMap<String, String> {
+        &self.metadata
+    }
+
+    pub fn server_side_encryption(&self) -> Option<&ServerSideEncryption> {
+        self.server_side_encryption.as_ref()
+    }
+
+    #[cfg(feature = "compression")]
+    pub fn compression(&self) -> Option<Compression> {
+        self.compression
+    }
+
+    pub fn conditions(&self) -> &Conditions {
+        &self.conditions
+    }
+}
+
+#[async_trait(?Send)]
+pub trait ObjectStoreTrait: Send {
+    fn name(&self) -> &str;
+    fn config(&self) -> &EnvironmentConfig;
+    async fn list_files(
+        &self,
+        prefix: Option<&str>,
+        selected_columns: &Option<Vec<&str>>,
+        recursive: bool,
+        max_keys: Option<u32>,
+        filter: &Option<FileObjectFilter>,
+        table: &mut FileObjectTable,
+    ) -> Result<(), LumniError>;
+    async fn get_object(
+        &self,
+        key: &str,
+        data: &mut Vec<u8>,
+    ) -> Result<(), LumniError>;
+    // reads the object if the conditions hold, false if it is not
+    // modified. This default checks them against the metadata of the
+    // object first, backends that send them with the request override it
+    async fn get_object_if(
+        &self,
+        key: &str,
+        conditions: &Conditions,
+        data: &mut Vec<u8>,
+    ) -> Result<bool, LumniError> {
+        if !conditions.is_empty() {
+            let metadata = if_exists(self.get_object_metadata(key).await)?;
+            if !conditions.check_read(key, metadata.as_ref())? {
+                return Ok(false);
+            }
+        }
+        self.get_object(key, data).await?;
+        Ok(true)
+    }
+    async fn head_object(
+        &self,
+        key: &str,
+    ) -> Result<(u16, HashMap<String, String>), LumniError>;
+    // properties of the object as given by head_object, backends that
+    // keep tags separately override this to include them
+    async fn get_object_metadata(
+        &self,
+        key: &str,
+    ) -> Result<ObjectMetadata, LumniError> {
+        let (status, headers) = self.head_object(key).await?;
+        match status {
+            200..=299 => Ok(ObjectMetadata::from_headers(key, &headers)),
+            _ => Err(LumniError::from_status(status, key, "")),
+        }
+    }
+    // replaces all tags of the object
+    async fn set_object_tags(
+        &self,
+        _key: &str,
+        _tags: &HashMap<String, String>,
+    ) -> Result<(), LumniError> {
+        Err(LumniError::Internal(format!(
+            "Tagging objects is not supported for {}",
+            self.name()
+        )))
+    }
+    // streams the object, or the range of it, in chunks. This default reads
+    // the full object into memory, the remote backends override it with
+    // ranged reads to keep memory use bounded.
+    async fn get_object_stream(
+        &self,
+        key: &str,
+        range: Option<ByteRange>,
+    ) -> Result<ObjectStream, LumniError> {
+        let mut data = Vec::new();
+        self.get_object(key, &mut data).await?;
+        let data = match range {
+            Some(range) => Bytes::copy_from_slice(range.slice(&data)),
+            None => Bytes::from(data),
+        };
+        Ok(Box::pin(stream::once(async move { Ok(data) })))
+    }
+    async fn delete_object(&self, _key: &str) -> Result<(), LumniError> {
+        Err(LumniError::Internal(format!(
+            "Deleting objects is not supported for {}",
+            self.name()
+        )))
+    }
+    // backends with a batch API override this to delete in fewer requests
+    async fn delete_objects(
+        &self,
+        keys: &[String],
+    ) -> Result<DeleteResult, LumniError> {
+        let mut result = DeleteResult::default();
+        for key in keys {
+            match self.delete_object(key).await {
+                Ok(()) => result.add_deleted(key),
+                Err(err) => result.add_failed(key, &err.to_string()),
+            }
+        }
+        Ok(result)
+    }
+    async fn put_object(
+        &self,
+        _key: &str,
+        _data: &[u8],
+    ) -> Result<(), LumniError> {
+        Err(LumniError::Internal(format!(
+            "Writing objects is not supported for {}",
+            self.name()
+        )))
+    }
+    // uploads everything read from source and returns the number of bytes
+    // written. The callback receives each chunk once it is stored, which
+    // can be used to report progress. Backends that support it override
+    // this to upload large sources in parts.
+    async fn put_object_multipart(
+        &self,
+        key: &str,
+        source: &mut dyn Read,
+        _options: &UploadOptions,
+        callback: Option<&BinaryCallbackWrapper>,
+    ) -> Result<u64, LumniError> {
+        let mut data = Vec::new();
+        source.read_to_end(&mut data)?;
+        self.put_object(key, &data).await?;
+        let size = data.len() as u64;
+        if let Some(callback) = callback {
+            callback.call(data).await?;
+        }
+        Ok(size)
+    }
+}
+
+#[derive(Clone)]
+pub struct ObjectStoreHandler {}
+
+impl ObjectStoreHandler {
+    pub fn new(_configs: Option<Vec<EnvironmentConfig>>) -> Self {
+        // creating with config will be used in future
+        ObjectStoreHandler {}
+    }
+
+    pub async fn list_objects(
+        &self,
+        parsed_uri: &ParsedUri,
+        config: &EnvironmentConfig,
+        selected_columns: Option<Vec<&str>>,
+        recursive: bool,
+        max_files: Option<u32>,
+        filter: &Option<FileObjectFilter>,
+        callback: Option<Arc<dyn TableCallback>>,
+    ) -> Result<Box<dyn Table>, LumniError> {
+        if let Some(bucket) = &parsed_uri.bucket {
+            // list files in a bucket
+            debug!("Listing files in bucket {}", bucket);
+            let table = self
+                .list_files_in_bucket(
+                    parsed_uri, // FROM
+                    config.clone(),
+                    &selected_columns, // SELECT
+                    recursive,         // true in case Query is used
+                    max_files,         // LIMIT
+                    filter,            // WHERE
+                    callback,          // callback is a custom function
+                                       // applied to what gets selected (via ROW addition)
+                )
+                .await?;
+            Ok(table)
+        } else {
+            if parsed_uri.scheme == UriScheme::S3
+                || parsed_uri.scheme == UriScheme::GCS
+                || parsed_uri.scheme == UriScheme::Azure
+            {
+                debug!("Listing buckets on {}", parsed_uri.scheme.to_string());
+                return self
+                    .list_buckets(
+                        &parsed_uri,
+                        config,
+                        &selected_columns,
+                        max_files,
+                        callback,
+                    )
+                    .await;
+            }
+            Err(LumniError::NoBucketInUri(parsed_uri.to_string()))
+        }
+    }
+
+    // as list_objects, but yields the rows in batches while they are
+    // listed, instead of returning them in a Table
+    #[allow(clippy::too_many_arguments)]
+    pub fn list_objects_stream<'a>(
+        &'a self,
+        parsed_uri: &'a ParsedUri,
+        config: &'a EnvironmentConfig,
+        selected_columns: Option<Vec<&'a str>>,
+        recursive: bool,
+        max_files: Option<u32>,
+        filter: &'a Option<FileObjectFilter>,
+        batch_size: usize,
+    ) -> TableStream<'a> {
+        TableStream::new(
+            move |callback| {
+                Box::pin(self.list_objects(
+                    parsed_uri,
+                    config,
+                    selected_columns,
+                    recursive,
+                    max_files,
+                    filter,
+                    Some(callback),
+                ))
+            },
+            batch_size,
+        )
+    }
+
+    pub async fn list_buckets(
+        &self,
+        parsed_uri: &ParsedUri,
+        config: &EnvironmentConfig,
+        selected_columns: &Option<Vec<&str>>,
+        max_files: Option<u32>,
+        callback: Option<Arc<dyn TableCallback>>,
+    ) -> Result<Box<dyn Table>, LumniError> {
+        if parsed_uri.bucket.is_some() {
+            // should not happen, prefer to panic in case it does
+            panic!("list_buckets called with a bucket uri");
+        }
+
+        // Clone the original config and update the settings
+        let mut updated_config = config.clone();
+        updated_config.set(
+            "uri".to_string(),
+            format!("{}://", parsed_uri.scheme.to_string()),
+        );
+        table_from_list_bucket(
+            updated_config,
+            selected_columns,
+            max_files,
+            callback,
+        )
+        .await
+    }
+
+    pub async fn get_object(
+        &self,
+        parsed_uri: &ParsedUri,
+        config: &EnvironmentConfig,
+        callback: Option<BinaryCallbackWrapper>,
+    ) -> Result<Option<Vec<u8>>, LumniError> {
+        // NOTE: initial callback implementation for get_object. In future updates the callback
+        // mechanism will be pushed to the underlying object store methods, so we can add
+        // chunking as well for increased performance and ability to handle big files that not
+        // fit in memory
+        let data = self
+            .read_object(parsed_uri, config, &Conditions::default())
+            .await?
+            .unwrap_or_default();
+        if let Some(callback) = callback {
+            callback.call(data).await?;
+            Ok(None)
+        } else {
+            Ok(Some(data))
+        }
+    }
+
+    // as get_object, None if the conditions tell the object is not
+    // modified, e.g. If-None-Match with the ETag of a cached copy
+    pub async fn get_object_if(
+        &self,
+        parsed_uri: &ParsedUri,
+        config: &EnvironmentConfig,
+        conditions: &Conditions,
+    ) -> Result<Option<Vec<u8>>, LumniError> {
+        self.read_object(parsed_uri, config, conditions).await
+    }
+
+    async fn read_object(
+        &self,
+        parsed_uri: &ParsedUri,
+        config: &EnvironmentConfig,
+        conditions: &Conditions,
+    ) -> Result<Option<Vec<u8>>, LumniError> {
+        let bucket = parsed_uri
+            .bucket
+            .as_ref()
+            .ok_or_else(|| LumniError::NoBucketInUri(parsed_uri.to_string()))?;
+        let bucket_uri =
+            format!("{}://{}", parsed_uri.scheme.to_string(), bucket);
+        let key = parsed_uri.path.as_deref().unwrap_or("");
+        let object_store = ObjectStore::new(&bucket_uri, config.clone())?;
+
+        let mut data = Vec::new();
+        if !object_store.get_object_if(key, conditions, &mut data).await? {
+            return Ok(None);
+        }
+        // compressed objects are returned decompressed, unless DECOMPRESS
+        // is false
+        #[cfg(feature = "compression")]
+        if AutoDecoder::enabled(config) {
+            data = AutoDecoder::decode(data).map_err(|e| {
+                LumniError::Io(e).context(format!(
+                    "Failed to decompress {}",
+                    parsed_uri.to_string()
+                ))
+            })?;
+        }
+        Ok(Some(data))
+    }
+
+    // credentials of the config (e.g. of a profile) or the environment,
+    // as the environment variables the tools of each object store read.
+    // only the stores with credentials are included, for S3 these can
+    // come from the provider chain (e.g. SSO or an instance role)
+    pub async fn credential_variables(
+        &self,
+        config: &EnvironmentConfig,
+    ) -> Result<BTreeMap<String, String>, LumniError> {
+        let mut variables = BTreeMap::new();
+        match crate::s3::credential_variables(config).await {
+            Ok(s3_variables) => variables.extend(s3_variables),
+            Err(e) => debug!("No AWS credentials: {}", e),
+        }
+        variables.extend(crate::gcs::credential_variables(config)?);
+        variables.extend(crate::azure::credential_variables(config));
+        Ok(variables)
+    }
+
+    pub async fn presign_url(
+        &self,
+        parsed_uri: &ParsedUri,
+        config: &EnvironmentConfig,
+        method: &str,
+        expires_in: u64,
+    ) -> Result<String, LumniError> {
+        let bucket = parsed_uri
+            .bucket
+            .as_ref()
+            .ok_or_else(|| LumniError::NoBucketInUri(parsed_uri.to_string()))?;
+        let key = parsed_uri
+            .path
+            .as_deref()
+            .filter(|key| !key.is_empty())
+            .ok_or_else(|| {
+                LumniError::Config(format!(
+                    "No object key in {}",
+                    parsed_uri.to_string()
+                ))
+            })?;
+        let bucket_uri =
+            format!("{}://{}", parsed_uri.scheme.to_string(), bucket);
+        match ObjectStore::new(&bucket_uri, config.clone())? {
+            ObjectStore::S3Bucket(bucket) => {
+                bucket.presign_url(key, method, expires_in).await
+            }
+            _ => Err(LumniError::Config(format!(
+                "Presigned URLs are not supported for {}://",
+                parsed_uri.scheme.to_string()
+            ))),
+        }
+    }
+
+    // the headers a request to the uri gets, with the canonical request
+    // and string to sign they are computed from. nothing is sent, this is
+    // for debugging signatures that a (S3 compatible) server rejects
+    pub async fn sign_request(
+        &self,
+        parsed_uri: &ParsedUri,
+        config: &EnvironmentConfig,
+        method: &str,
+        query_string: Option<&str>,
+        headers: &HashMap<String, String>,
+        x_amz_date: Option<&str>, // None signs at the current time
+    ) -> Result<SignedRequest, LumniError> {
+        let bucket = parsed_uri
+            .bucket
+            .as_ref()
+            .ok_or_else(|| LumniError::NoBucketInUri(parsed_uri.to_string()))?;
+        let key = parsed_uri.path.as_deref().filter(|key| !key.is_empty());
+        let bucket_uri =
+            format!("{}://{}", parsed_uri.scheme.to_string(), bucket);
+        match ObjectStore::new(&bucket_uri, config.clone())? {
+            ObjectStore::S3Bucket(bucket) => {
+                bucket
+                    .sign_request(
+                        key,
+                        method,
+                        query_string,
+                        headers,
+                        x_amz_date,
+                    )
+                    .await
+            }
+            _ => Err(LumniError::Config(format!(
+                "Request signing is not supported for {}://",
+                parsed_uri.scheme.to_string()
+            ))),
+        }
+    }
+
+    // which of list, read, write and delete the credentials allow under
+    // the uri. with write, a probe object is written and deleted again
+    pub async fn probe_access(
+        &self,
+        parsed_uri: &ParsedUri,
+        config: &EnvironmentConfig,
+        write: bool,
+    ) -> Result<AccessReport, LumniError> {
+        let bucket = parsed_uri
+            .bucket
+            .as_ref()
+            .ok_or_else(|| LumniError::NoBucketInUri(parsed_uri.to_string()))?;
+        let bucket_uri =
+            format!("{}://{}", parsed_uri.scheme.to_string(), bucket);
+        let object_store = ObjectStore::new(&bucket_uri, config.clone())?;
+        let prefix = parsed_uri.path.as_deref().filter(|path| !path.is_empty());
+        Ok(probe_access(&object_store, prefix, write).await)
+    }
+
+    pub async fn create_bucket(
+        &self,
+        parsed_uri: &ParsedUri,
+        config: &EnvironmentConfig,
+    ) -> Result<(), LumniError> {
+        self.bucket_store(parsed_uri, config)?.create_bucket().await
+    }
+
+    // force deletes all objects in the bucket first
+    pub async fn delete_bucket(
+        &self,
+        parsed_uri: &ParsedUri,
+        config: &EnvironmentConfig,
+        force: bool,
+    ) -> Result<(), LumniError> {
+        self.bucket_store(parsed_uri, config)?
+            .delete_bucket(force)
+            .await
+    }
+
+    // response headers if the bucket exists, None if it does not
+    pub async fn head_bucket(
+        &self,
+        parsed_uri: &ParsedUri,
+        config: &EnvironmentConfig,
+    ) -> Result<Option<HashMap<String, String>>, LumniError> {
+        self.bucket_store(parsed_uri, config)?.head_bucket().await
+    }
+
+    pub async fn get_object_metadata(
+        &self,
+        parsed_uri: &ParsedUri,
+        config: &EnvironmentConfig,
+    ) -> Result<ObjectMetadata, LumniError> {
+        let (object_store, key) = self.object_store_key(parsed_uri, config)?;
+        object_store.get_object_metadata(key).await
+    }
+
+    // replaces all tags of the object
+    pub async fn set_object_tags(
+        &self,
+        parsed_uri: &ParsedUri,
+        config: &EnvironmentConfig,
+        tags: &HashMap<String, String>,
+    ) -> Result<(), LumniError> {
+        let (object_store, key) = self.object_store_key(parsed_uri, config)?;
+        object_store.set_object_tags(key, tags).await
+    }
+
+    pub async fn list_object_versions(
+        &self,
+        parsed_uri: &ParsedUri,
+        config: &EnvironmentConfig,
+        recursive: bool,
+        max_files: Option<u32>,
+        filter: &Option<FileObjectFilter>,
+        callback: Option<Arc<dyn TableCallback>>,
+    ) -> Result<Box<dyn Table>, LumniError> {
+        let bucket = parsed_uri
+            .bucket
+            .as_ref()
+            .ok_or_else(|| LumniError::NoBucketInUri(parsed_uri.to_string()))?;
+        let bucket_uri =
+            format!("{}://{}", parsed_uri.scheme.to_string(), bucket);
+        let object_store = ObjectStore::new(&bucket_uri, config.clone())?;
+        object_store
+            .list_object_versions(
+                parsed_uri.path.as_deref(),
+                recursive,
+                max_files,
+                filter,
+                callback,
+            )
+            .await
+    }
+
+    // the objects under the uri as they were at as_of (epoch seconds), e.g.
+    // to audit a bucket or plan a recovery. versioning must be enabled
+    #[allow(clippy::too_many_arguments)]
+    pub async fn list_objects_as_of(
+        &self,
+        parsed_uri: &ParsedUri,
+        config: &EnvironmentConfig,
+        selected_columns: Option<Vec<&str>>,
+        recursive: bool,
+        max_files: Option<u32>,
+        filter: &Option<FileObjectFilter>,
+        as_of: u64,
+        callback: Option<Arc<dyn TableCallback>>,
+    ) -> Result<Box<dyn Table>, LumniError> {
+        let bucket = parsed_uri
+            .bucket
+            .as_ref()
+            .ok_or_else(|| LumniError::NoBucketInUri(parsed_uri.to_string()))?;
+        let bucket_uri =
+            format!("{}://{}", parsed_uri.scheme.to_string(), bucket);
+        let object_store = ObjectStore::new(&bucket_uri, config.clone())?;
+        object_store
+            .list_objects_as_of(
+                parsed_uri.path.as_deref(),
+                &selected_columns,
+                recursive,
+                max_files,
+                filter,
+                as_of,
+                callback,
+            )
+            .await
+    }
+
+    // request a readable copy of an archived (e.g. GLACIER) object
+    pub async fn restore_object(
+        &self,
+        parsed_uri: &ParsedUri,
+        config: &EnvironmentConfig,
+        request: &RestoreRequest,
+    ) -> Result<RestoreStatus, LumniError> {
+        let (object_store, key) = self.object_store_key(parsed_uri, config)?;
+        object_store.restore_object(key, request).await
+    }
+
+    // files created, modified or deleted under the uri, as they happen.
+    // localfs, and s3 through event notifications in an SQS queue
+    pub async fn watch(
+        &self,
+        parsed_uri: &ParsedUri,
+        config: &EnvironmentConfig,
+        recursive: bool,
+        filter: &Option<FileObjectFilter>,
+        callback: Arc<dyn WatchCallback>,
+    ) -> Result<(), LumniError> {
+        // before other stores ask for credentials
+        if !matches!(parsed_uri.scheme, UriScheme::LocalFs | UriScheme::S3) {
+            return Err(LumniError::Config(format!(
+                "Watching for changes is not supported for {}://",
+                parsed_uri.scheme.to_string()
+            )));
         }
+        let bucket = parsed_uri
+            .bucket
+            .as_ref()
+            .ok_or_else(|| LumniError::NoBucketInUri(parsed_uri.to_string()))?;
+        let bucket_uri =
+            format!("{}://{}", parsed_uri.scheme.to_string(), bucket);
+        let object_store = ObjectStore::new(&bucket_uri, config.clone())?;
+        object_store
+            .watch(parsed_uri.path.as_deref(), recursive, filter, callback)
+            .await
     }
 
-    pub fn config(&self) -> &EnvironmentConfig {
-        match self {
-            ObjectStore::S3Bucket(bucket) => bucket.config(),
-            ObjectStore::LocalFsBucket(local_fs) => local_fs.config(),
-        }
+    // object store of the bucket and the key of the object in it
+    fn object_store_key<'a>(
+        &self,
+        parsed_uri: &'a ParsedUri,
+        config: &EnvironmentConfig,
+    ) -> Result<(ObjectStore, &'a str), LumniError> {
+        let bucket = parsed_uri
+            .bucket
+            .as_ref()
+            .ok_or_else(|| LumniError::NoBucketInUri(parsed_uri.to_string()))?;
+        let key = parsed_uri
+            .path
+            .as_deref()
+            .filter(|key| !key.is_empty())
+            .ok_or_else(|| {
+                LumniError::Config(format!(
+                    "No object key in {}",
+                    parsed_uri.to_string()
+                ))
+            })?;
+        let bucket_uri =
+            format!("{}://{}", parsed_uri.scheme.to_string(), bucket);
+        let object_store = ObjectStore::new(&bucket_uri, config.clone())?;
+        Ok((object_store, key))
     }
 
-    pub fn uri(&self) -> String {
-        match self {
-            ObjectStore::S3Bucket(bucket) => {
-                format!("s3://{}", bucket.name())
+    // object store for a uri that names a bucket, for localfs this is
+    // the directory the uri points to
+    fn bucket_store(
+        &self,
+        parsed_uri: &ParsedUri,
+        config: &EnvironmentConfig,
+    ) -> Result<ObjectStore, LumniError> {
+        let bucket = parsed_uri
+            .bucket
+            .as_ref()
+            .ok_or_else(|| LumniError::NoBucketInUri(parsed_uri.to_string()))?;
+        let path = parsed_uri.path.as_deref().filter(|path| !path.is_empty());
+        let name = match (&parsed_uri.scheme, path) {
+            (UriScheme::LocalFs, Some(path)) => {
+                Path::new(bucket).join(path).to_string_lossy().to_string()
             }
-            ObjectStore::LocalFsBucket(local_fs) => {
-                format!("{}", local_fs.name())
+            (_, None) => bucket.to_string(),
+            (_, Some(path)) => {
+                return Err(LumniError::Config(format!(
+                    "{}://{}/{} is not a bucket, remove the path after the \
+                     bucket name",
+                    parsed_uri.scheme.to_string(),
+                    bucket,
+                    path
+                )))
             }
-        }
+        };
+        let bucket_uri =
+            format!("{}://{}", parsed_uri.scheme.to_string(), name);
+        Ok(ObjectStore::new(&bucket_uri, config.clone())?)
     }
 
-    pub async fn list_files(
+    pub async fn get_object_stream(
         &self,
-        prefix: Option<&str>,
-        selected_columns: &Option<Vec<&str>>,
+        parsed_uri: &ParsedUri,
+        config: &EnvironmentConfig,
+        range: Option<ByteRange>,
+    ) -> Result<ObjectStream, LumniError> {
+        let bucket = parsed_uri
+            .bucket
+            .as_ref()
+            .ok_or_else(|| LumniError::NoBucketInUri(parsed_uri.to_string()))?;
+        let key = parsed_uri.path.as_deref().unwrap_or("");
+        let bucket_uri =
+            format!("{}://{}", parsed_uri.scheme.to_string(), bucket);
+        let object_store = ObjectStore::new(&bucket_uri, config.clone())?;
+        object_store.get_object_stream(key, range).await
+    }
+
+    // deletes the object, or with recursive all objects under the prefix
+    pub async fn delete_objects(
+        &self,
+        parsed_uri: &ParsedUri,
+        config: &EnvironmentConfig,
         recursive: bool,
-        max_files: Option<u32>,
-        filter: &Option<FileObjectFilter>,
-        callback: Option<Arc<dyn TableCallback>>,
-    ) -> Result<Box<dyn Table>, LakestreamError> {
-        let mut table = FileObjectTable::new(&selected_columns, callback);
+        confirm: Option<&ConfirmCallback<'_>>,
+    ) -> Result<DeleteResult, LumniError> {
+        let bucket = parsed_uri
+            .bucket
+            .as_ref()
+            .ok_or_else(|| LumniError::NoBucketInUri(parsed_uri.to_string()))?;
+        let bucket_uri =
+            format!("{}://{}", parsed_uri.scheme.to_string(), bucket);
+        let object_store = ObjectStore::new(&bucket_uri, config.clone())?;
 
-        match self {
-            ObjectStore::S3Bucket(bucket) => {
-                bucket
-                    .list_files(
-                        prefix,
-                        selected_columns,
-                        recursive,
-                        max_files,
-                        filter,
-                        &mut table,
-                    )
-                    .await
+        let path = parsed_uri.path.as_deref().filter(|path| !path.is_empty());
+        let keys = match path {
+            // the prefix is a directory, "logs" should not match "logs2/"
+            Some(prefix) if recursive => {
+                let prefix = format!("{}/", prefix.trim_end_matches('/'));
+                object_store.list_keys(Some(&prefix)).await?
             }
-            ObjectStore::LocalFsBucket(local_fs) => {
-                local_fs
-                    .list_files(
-                        prefix,
-                        selected_columns,
-                        recursive,
-                        max_files,
-                        filter,
-                        &mut table,
-                    )
-                    .await
+            None if recursive => object_store.list_keys(None).await?,
+            Some(key) if !key.ends_with('/') => vec![key.to_string()],
+            _ => {
+                return Err(LumniError::Config(format!(
+                    "{} is not an object, use recursive to delete a prefix",
+                    parsed_uri.to_string()
+                )))
             }
-        }?;
-        Ok(Box::new(table))
+        };
+
+        let mut result = DeleteResult::default();
+        let keys: Vec<String> = match confirm {
+            Some(confirm) => keys
+                .into_iter()
+                .filter(|key| {
+                    let confirmed = confirm(key);
+                    if !confirmed {
+                        result.add_skipped(key);
+                    }
+                    confirmed
+                })
+                .collect(),
+            None => keys,
+        };
+        if keys.is_empty() {
+            return Ok(result);
+        }
+
+        if !recursive {
+            // a single object is deleted directly, so errors such as
+            // NotFound are returned as is
+            object_store.delete_object(&keys[0]).await?;
+            result.add_deleted(&keys[0]);
+            return Ok(result);
+        }
+        let deleted = object_store.delete_objects(&keys).await?;
+        result.deleted.extend_from_slice(deleted.deleted());
+        result.failed.extend_from_slice(deleted.failed());
+        Ok(result)
     }
 
-    pub async fn get_object(
+    pub async fn put_object(
         &self,
-        key: &str,
-        data: &mut Vec<u8>,
-    ) -> Result<(), LakestreamError> {
-        match self {
-            ObjectStore::S3Bucket(bucket) => bucket.get_object(key, data).await,
-            ObjectStore::LocalFsBucket(local_fs) => {
-                local_fs.get_object(key, data).await
+        parsed_uri: &ParsedUri,
+        config: &EnvironmentConfig,
+        source: &mut dyn Read,
+        options: &UploadOptions,
+        callback: Option<BinaryCallbackWrapper>,
+    ) -> Result<u64, LumniError> {
+        let bucket = parsed_uri
+            .bucket
+            .as_ref()
+            .ok_or_else(|| LumniError::NoBucketInUri(parsed_uri.to_string()))?;
+        let key = match parsed_uri.path.as_deref() {
+            Some(key) if !key.is_empty() && !key.ends_with('/') => key,
+            _ => {
+                return Err(LumniError::Config(format!(
+                    "No object key in {}",
+                    parsed_uri.to_string()
+                )))
             }
+        };
+        let bucket_uri =
+            format!("{}://{}", parsed_uri.scheme.to_string(), bucket);
+        let object_store = ObjectStore::new(&bucket_uri, config.clone())?;
+        #[cfg(feature = "compression")]
+        if let Some(compression) = options.compression() {
+            let mut source = CompressReader::new(source, compression)?;
+            return object_store
+                .put_object_multipart(
+                    key,
+                    &mut source,
+                    options,
+                    callback.as_ref(),
+                )
+                .await;
         }
+        object_store
+            .put_object_multipart(key, source, options, callback.as_ref())
+            .await
     }
-}
 
-#[async_trait(?Send)]
-pub trait ObjectStoreTrait: Send {
-    fn name(&self) -> &str;
-    fn config(&self) -> &EnvironmentConfig;
-    async fn list_files(
-        &self,
-        prefix: Option<&str>,
-        selected_columns: &Option<Vec<&str>>,
-        recursive: bool,
-        max_keys: Option<u32>,
-        filter: &Option<FileObjectFilter>,
-        table: &mut FileObjectTable,
-    ) -> Result<(), LakestreamError>;
-    async fn get_object(
-        &self,
-        key: &str,
-        data: &mut Vec<u8>,
-    ) -> Result<(), LakestreamError>;
-    async fn head_object(
+    // objects added, removed or modified at b compared to a, the
+    // locations can be on different object stores
+    pub async fn diff(
         &self,
-        key: &str,
-    ) -> Result<(u16, HashMap<String, String>), LakestreamError>;
-}
-
-#[derive(Clone)]
-pub struct ObjectStoreHandler {}
+        uri_a: &ParsedUri,
+        uri_b: &ParsedUri,
+        config: &EnvironmentConfig,
+        strategy: DiffStrategy,
+        callback: Option<Arc<dyn TableCallback>>,
+    ) -> Result<Box<dyn Table>, LumniError> {
+        let inventory_a = self.list_inventory(uri_a, config, strategy).await?;
+        let inventory_b = self.list_inventory(uri_b, config, strategy).await?;
 
-impl ObjectStoreHandler {
-    pub fn new(_configs: Option<Vec<EnvironmentConfig>>) -> Self {
-        // creating with config will be used in future
-        ObjectStoreHandler {}
+        let mut table = DiffTable::new();
+        if let Some(callback) = callback {
+            table.set_callback(callback);
+        }
+        let side = |entry: Option<&InventoryEntry>| {
+            entry
+                .map(|entry| DiffSide {
+                    size: Some(entry.size),
+                    modified: entry.modified,
+                    checksum: entry.checksum.clone(),
+                })
+                .unwrap_or_default()
+        };
+        for (key, change) in
+            diff_inventories(&inventory_a, &inventory_b, strategy)
+        {
+            table.add_change(
+                &key,
+                change,
+                side(inventory_a.get(&key)),
+                side(inventory_b.get(&key)),
+            )?;
+        }
+        Ok(Box::new(table))
     }
 
-    pub async fn list_objects(
+    // what cp would write: an object is copied to the target, or into it
+    // when the target ends with a slash. a prefix is copied as every object
+    // under it, each to its relative key under the target
+    pub async fn plan_copy(
         &self,
-        parsed_uri: &ParsedUri,
+        source: &ParsedUri,
+        target: &ParsedUri,
         config: &EnvironmentConfig,
-        selected_columns: Option<Vec<&str>>,
-        recursive: bool,
-        max_files: Option<u32>,
-        filter: &Option<FileObjectFilter>,
-        callback: Option<Arc<dyn TableCallback>>,
-    ) -> Result<Box<dyn Table>, LakestreamError> {
-        if let Some(bucket) = &parsed_uri.bucket {
-            // list files in a bucket
-            debug!("Listing files in bucket {}", bucket);
-            let table = self
-                .list_files_in_bucket(
-                    parsed_uri, // FROM
-                    config.clone(),
-                    &selected_columns, // SELECT
-                    recursive,         // true in case Query is used
-                    max_files,         // LIMIT
-                    filter,            // WHERE
-                    callback,          // callback is a custom function
-                                       // applied to what gets selected (via ROW addition)
-                )
-                .await?;
-            Ok(table)
-        } else {
-            if parsed_uri.scheme == UriScheme::S3 {
-                debug!("Listing buckets on S3");
-                return self
-                    .list_buckets(
-                        &parsed_uri,
-                        config,
-                        &selected_columns,
-                        max_files,
-                        callback,
+    ) -> Result<Vec<PlannedOperation>, LumniError> {
+        let object_uri = |uri: &ParsedUri, key: &str| {
+            format!(
+                "{}://{}/{}",
+                uri.scheme.to_string(),
+                uri.bucket.as_deref().unwrap_or_default().trim_end_matches('/'),
+                key
+            )
+        };
+        let target_prefix = inventory_prefix(target);
+
+        let path = source
+            .path
+            .as_deref()
+            .filter(|path| !path.is_empty() && !path.ends_with('/'));
+        if let Some(path) = path {
+            match self.get_object_metadata(source, config).await {
+                Ok(metadata) => {
+                    let target_key = match target.path.as_deref() {
+                        Some(key) if !key.is_empty() && !key.ends_with('/') => {
+                            key.to_string()
+                        }
+                        _ => {
+                            let name = path.rsplit('/').next().unwrap_or(path);
+                            format!("{}{}", target_prefix, name)
+                        }
+                    };
+                    let operation = PlannedOperation::new(
+                        "copy",
+                        &object_uri(target, &target_key),
                     )
-                    .await;
+                    .set_source(&object_uri(source, path))
+                    .set_size(metadata.size());
+                    return Ok(vec![operation]);
+                }
+                // not an object, copied as a prefix
+                Err(LumniError::NotFound(_)) => {}
+                Err(err) => return Err(err),
             }
-            Err(LakestreamError::NoBucketInUri(parsed_uri.to_string()))
         }
+
+        let source_prefix = inventory_prefix(source);
+        let inventory = self
+            .list_inventory(source, config, DiffStrategy::Size)
+            .await?;
+        Ok(inventory
+            .into_iter()
+            .map(|(key, entry)| {
+                PlannedOperation::new(
+                    "copy",
+                    &object_uri(target, &format!("{}{}", target_prefix, key)),
+                )
+                .set_source(&object_uri(
+                    source,
+                    &format!("{}{}", source_prefix, key),
+                ))
+                .set_size(Some(entry.size))
+            })
+            .collect())
     }
 
-    pub async fn list_buckets(
+    // lines matching the pattern, a regex, in all objects under the uri,
+    // listed to the last page. objects that can not be read are listed in
+    // GrepTable::failed
+    pub async fn grep(
         &self,
         parsed_uri: &ParsedUri,
         config: &EnvironmentConfig,
-        selected_columns: &Option<Vec<&str>>,
-        max_files: Option<u32>,
+        pattern: &str,
+        options: &GrepOptions,
         callback: Option<Arc<dyn TableCallback>>,
-    ) -> Result<Box<dyn Table>, LakestreamError> {
-        if parsed_uri.bucket.is_some() {
-            // should not happen, prefer to panic in case it does
-            panic!("list_buckets called with a bucket uri");
-        }
+    ) -> Result<GrepTable, LumniError> {
+        let regex = options.regex(pattern)?;
+        let bucket = parsed_uri
+            .bucket
+            .as_ref()
+            .ok_or_else(|| LumniError::NoBucketInUri(parsed_uri.to_string()))?;
+        let bucket_uri =
+            format!("{}://{}", parsed_uri.scheme.to_string(), bucket);
+        let prefix = inventory_prefix(parsed_uri);
+        let object_keys = self
+            .list_inventory(parsed_uri, config, DiffStrategy::Mtime)
+            .await?
+            .into_keys()
+            .filter(|key| options.matches_name(key))
+            .map(|key| format!("{}{}", prefix, key))
+            .collect();
 
-        // Clone the original config and update the settings
-        let mut updated_config = config.clone();
-        updated_config.set(
-            "uri".to_string(),
-            format!("{}://", parsed_uri.scheme.to_string()),
-        );
-        table_from_list_bucket(
-            updated_config,
-            selected_columns,
-            max_files,
-            callback,
+        let mut table = GrepTable::new();
+        if let Some(callback) = callback {
+            table.set_callback(callback);
+        }
+        grep_objects(
+            &bucket_uri,
+            config,
+            object_keys,
+            &regex,
+            options,
+            &mut table,
         )
-        .await
+        .await?;
+        Ok(table)
     }
 
-    pub async fn get_object(
+    // files under the uri by their key relative to it, listed to the
+    // last page
+    pub(crate) async fn list_inventory(
         &self,
         parsed_uri: &ParsedUri,
         config: &EnvironmentConfig,
-        callback: Option<BinaryCallbackWrapper>,
-    ) -> Result<Option<Vec<u8>>, LakestreamError> {
-        if let Some(bucket) = &parsed_uri.bucket {
-            let bucket_uri =
-                format!("{}://{}", parsed_uri.scheme.to_string(), bucket);
-            let key = parsed_uri.path.as_deref().unwrap_or("");
-            let object_store = ObjectStore::new(&bucket_uri, config.clone())?;
-
-            // NOTE: initial callback implementation for get_object. In future updates the callback
-            // mechanism will be pushed to the underlying object store methods, so we can add
-            // chunking as well for increased performance and ability to handle big files that not
-            // fit in memory
-            if let Some(callback) = callback {
-                let mut data = Vec::new();
-                object_store.get_object(key, &mut data).await?;
-                callback.call(data).await?;
-                Ok(None)
-            } else {
-                let mut data = Vec::new();
-                object_store.get_object(key, &mut data).await?;
-                Ok(Some(data))
+        strategy: DiffStrategy,
+    ) -> Result<BTreeMap<String, InventoryEntry>, LumniError> {
+        let bucket = parsed_uri
+            .bucket
+            .as_ref()
+            .ok_or_else(|| LumniError::NoBucketInUri(parsed_uri.to_string()))?;
+        let bucket_uri =
+            format!("{}://{}", parsed_uri.scheme.to_string(), bucket);
+        let object_store = ObjectStore::new(&bucket_uri, config.clone())?;
+
+        let prefix = inventory_prefix(parsed_uri);
+        let mut columns = vec!["name", "size", "modified"];
+        if strategy.needs_checksum() {
+            columns.push("checksum");
+        }
+        let collector = Arc::new(InventoryCollector::default());
+        object_store
+            .list_files(
+                Some(prefix.as_str()).filter(|prefix| !prefix.is_empty()),
+                &Some(columns),
+                true,
+                Some(LIST_ALL_KEYS),
+                &None,
+                Some(collector.clone()),
+            )
+            .await?;
+
+        let mut inventory = BTreeMap::new();
+        for (name, entry) in collector.take_entries() {
+            let key = match &object_store {
+                // local files are listed with their full path, directories
+                // only by their name
+                ObjectStore::LocalFsBucket(local_fs) => {
+                    if !Path::new(&name).is_file() {
+                        continue;
+                    }
+                    match name.strip_prefix(local_fs.name()) {
+                        Some(key) => key.trim_start_matches('/').to_string(),
+                        None => continue,
+                    }
+                }
+                // common prefixes end with a slash and are not objects
+                _ if name.ends_with('/') => continue,
+                _ => name,
+            };
+            if let Some(key) = key.strip_prefix(&prefix) {
+                inventory.insert(key.to_string(), entry);
             }
-        } else {
-            Err(LakestreamError::NoBucketInUri(parsed_uri.to_string()))
         }
+        Ok(inventory)
     }
 
     async fn list_files_in_bucket(
@@ -269,16 +2004,16 @@ impl ObjectStoreHandler {
         max_files: Option<u32>,
         filter: &Option<FileObjectFilter>,
         callback: Option<Arc<dyn TableCallback>>,
-    ) -> Result<Box<dyn Table>, LakestreamError> {
+    ) -> Result<Box<dyn Table>, LumniError> {
         let bucket_uri = format!(
             "{}://{}",
             parsed_uri.scheme.to_string(),
             parsed_uri.bucket.as_ref().unwrap()
         );
 
-        let object_store = ObjectStore::new(&bucket_uri, config).unwrap();
+        let object_store = ObjectStore::new(&bucket_uri, config)?;
         object_store
-            .list_files(
+            .list_files_cached(
                 parsed_uri.path.as_deref(),
                 selected_columns,
                 recursive,
@@ -294,7 +2029,7 @@ impl ObjectStoreHandler {
         statement: &str,
         config: &EnvironmentConfig,
         callback: Option<Arc<dyn TableCallback>>,
-    ) -> Result<Box<dyn Table>, LakestreamError> {
+    ) -> Result<Box<dyn Table>, LumniError> {
         let dialect = GenericDialect {};
         let parsed = Parser::parse_sql(&dialect, statement);
 
@@ -305,12 +2040,12 @@ impl ObjectStoreHandler {
                 {
                     self.handle_select_statement(&query, config, callback).await
                 } else {
-                    Err(LakestreamError::InternalError(
+                    Err(LumniError::Internal(
                         "Unsupported query statement".to_string(),
                     ))
                 }
             }
-            Err(_e) => Err(LakestreamError::InternalError(
+            Err(_e) => Err(LumniError::Internal(
                 "Failed to parse query statement".to_string(),
             )),
         }
@@ -321,98 +2056,56 @@ impl ObjectStoreHandler {
         query: &Query,
         config: &EnvironmentConfig,
         callback: Option<Arc<dyn TableCallback>>,
-    ) -> Result<Box<dyn Table>, LakestreamError> {
-        if let SetExpr::Select(select) = &*query.body {
-            let selected_columns = if select
-                .projection
-                .iter()
-                .any(|item| matches!(item, SelectItem::Wildcard(_)))
-            {
-                // wildcard: e.g. SELECT * FROM "uri"
-                None
-            } else {
-                Some(
-                    select
-                        .projection
-                        .iter()
-                        .filter_map(|item| {
-                            match item {
-                                SelectItem::UnnamedExpr(Expr::Identifier(
-                                    ident,
-                                )) => Some(ident.value.as_str()), // Directly use the reference
-                                _ => {
-                                    log::warn!(
-                                        "Skipping non-identifier selection: \
-                                         {:?}",
-                                        item
-                                    );
-                                    None
-                                }
-                            }
-                        })
-                        .collect::<Vec<&str>>(),
-                ) // Collect as Vec<&str>
-            };
+    ) -> Result<Box<dyn Table>, LumniError> {
+        // TODO: directories vs files
+        // in this implementation, everything is treated as a directory,
+        // while we should distinguish between files and directories
+        // directories -> call list_objects()
+        // files -> treat as a database file (e.g. .sql, .parquet)
+        let select_query = Arc::new(SelectQuery::from_query(query)?);
+        select_query.validate()?;
+        let parsed_uri = ParsedUri::from_uri(select_query.uri(), true);
 
-            if let Some(table) = select.from.first() {
-                // assume the query is of the form 'SELECT * FROM "uri"'
-                // TODOs:
-                // 1. directories vs files
-                // in this first implementation, everything is treated as a directory,
-                // while we should distinguish between files and directories
-                // directories -> call list_objects()
-                // files -> treat as a database file (e.g. .sql, .parquet)
-                //
-                // 2. handle the following SQL clauses:
-                // non-wildcards in the SELECT statementA, e.g. 'SELECT name, size FROM "uri"'
-                // WHERE clauses, e.g. 'SELECT * FROM "uri" WHERE size > 100'
-                // ORDER BY, LIMIT and OFFSET clauses
-                let mut uri = table.relation.to_string();
-
-                // check for and remove leading and trailing quotes
-                if (uri.starts_with('"') && uri.ends_with('"'))
-                    || (uri.starts_with('\'') && uri.ends_with('\''))
-                {
-                    uri = uri[1..uri.len() - 1].to_string(); // Remove the first and last characters
-                }
-
-                // Extract the LIMIT value if present
-                let limit = match &query.limit {
-                    Some(sqlparser::ast::Expr::Value(
-                        sqlparser::ast::Value::Number(n, _),
-                    )) => n.parse::<u32>().ok(),
-                    _ => None,
-                };
-
-                let result = self
-                    .list_objects(
-                        &ParsedUri::from_uri(&uri, true),
-                        config,
-                        selected_columns,
-                        true,
-                        limit,
-                        &None,
-                        callback.clone(),
-                    )
-                    .await;
+        let result = if select_query.is_plain_listing() {
+            let limit = select_query
+                .limit()
+                .map(|limit| u32::try_from(limit).unwrap_or(u32::MAX));
+            self.list_objects(
+                &parsed_uri,
+                config,
+                select_query.selected_columns(),
+                true,
+                limit,
+                &None,
+                callback.clone(),
+            )
+            .await
+        } else {
+            // rows are filtered (and sorted) as they are listed, the
+            // listed table itself is not needed
+            let collector = select_query.collector(callback.clone());
+            self.list_objects(
+                &parsed_uri,
+                config,
+                Some(select_query.listing_columns()),
+                true,
+                None,
+                &select_query.pushdown_filter(),
+                Some(collector.clone()),
+            )
+            .await
+            .and_then(|_| select_query.build_table(collector, callback.clone()))
+        };
 
-                match result {
-                    Err(LakestreamError::NoBucketInUri(_)) => {
-                        // uri does not point to a bucket or (virtual) directory
-                        // assume it to be a pointer to a database file (e.g. .sql, .parquet)
-                        return self
-                            .query_object(&uri, config, query, callback)
-                            .await;
-                    }
-                    _ => return result, // TODO: query should return Table
-                }
+        match result {
+            Err(LumniError::NoBucketInUri(_)) => {
+                // uri does not point to a bucket or (virtual) directory
+                // assume it to be a pointer to a database file (e.g. .sql, .parquet)
+                self.query_object(select_query.uri(), config, query, callback)
+                    .await
             }
+            _ => result,
         }
-        //}
-
-        Err(LakestreamError::InternalError(
-            "Query does not match 'SELECT * FROM uri' pattern".to_string(),
-        ))
     }
 
     async fn query_object(
@@ -421,20 +2114,30 @@ impl ObjectStoreHandler {
         _config: &EnvironmentConfig,
         _query: &Query,
         _callback: Option<Arc<dyn TableCallback>>,
-    ) -> Result<Box<dyn Table>, LakestreamError> {
+    ) -> Result<Box<dyn Table>, LumniError> {
         // Logic to treat the URI as a database file and query it
 
         // This is a placeholder for the actual implementation.
-        Err(LakestreamError::InternalError(
+        Err(LumniError::Internal(
             "Querying object not implemented".to_string(),
         ))
     }
 }
 
+// keys of an inventory are relative to this prefix of the uri
+pub(crate) fn inventory_prefix(parsed_uri: &ParsedUri) -> String {
+    match parsed_uri.path.as_deref() {
+        Some(path) if !path.is_empty() => {
+            format!("{}/", path.trim_end_matches('/'))
+        }
+        _ => String::new(),
+    }
+}
+
 #[allow(dead_code)]
 #[async_trait(?Send)]
 pub trait ObjectStoreBackend: Send {
-    fn new(config: EnvironmentConfig) -> Result<Self, LakestreamError>
+    fn new(config: EnvironmentConfig) -> Result<Self, LumniError>
     where
         Self: Sized;
 
@@ -442,5 +2145,205 @@ pub trait ObjectStoreBackend: Send {
         config: EnvironmentConfig,
         max_files: Option<u32>,
         table: &mut ObjectStoreTable,
-    ) -> Result<(), LakestreamError>;
+    ) -> Result<(), LumniError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::registry::{
+        register_backend, ObjectStoreBackendFactory,
+    };
+    use crate::FileObject;
+
+    // as S3, keys match the prefix as a string and a listing without
+    // max_keys stops after the first page
+    const PAGE_SIZE: usize = 2;
+
+    type Objects = Arc<Mutex<BTreeMap<String, u64>>>;
+
+    struct PagedFactory {
+        objects: Objects,
+    }
+
+    struct PagedStore {
+        name: String,
+        config: EnvironmentConfig,
+        objects: Objects,
+    }
+
+    impl ObjectStoreBackendFactory for PagedFactory {
+        fn create(
+            &self,
+            name: &str,
+            config: EnvironmentConfig,
+        ) -> Result<Box<dyn ObjectStoreTrait + Sync>, LumniError> {
+            Ok(Box::new(PagedStore {
+                name: name.to_string(),
+                config,
+                objects: self.objects.clone(),
+            }))
+        }
+    }
+
+    #[async_trait(?Send)]
+    impl ObjectStoreTrait for PagedStore {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn config(&self) -> &EnvironmentConfig {
+            &self.config
+        }
+
+        async fn list_files(
+            &self,
+            prefix: Option<&str>,
+            _selected_columns: &Option<Vec<&str>>,
+            _recursive: bool,
+            max_keys: Option<u32>,
+            _filter: &Option<FileObjectFilter>,
+            table: &mut FileObjectTable,
+        ) -> Result<(), LumniError> {
+            let max_keys = max_keys.map_or(PAGE_SIZE, |max| max as usize);
+            let file_objects: Vec<FileObject> = self
+                .objects
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(key, _)| key.starts_with(prefix.unwrap_or("")))
+                .map(|(key, size)| {
+                    FileObject::new(key.clone(), *size, None, None)
+                })
+                .collect();
+            for page in file_objects.chunks(PAGE_SIZE) {
+                let remaining = max_keys.saturating_sub(table.len());
+                if remaining == 0 {
+                    break;
+                }
+                table
+                    .add_file_objects(
+                        page.iter().take(remaining).cloned().collect(),
+                    )
+                    .await
+                    .map_err(LumniError::Internal)?;
+            }
+            Ok(())
+        }
+
+        async fn get_object(
+            &self,
+            key: &str,
+            _data: &mut Vec<u8>,
+        ) -> Result<(), LumniError> {
+            Err(LumniError::NotFound(key.to_string()))
+        }
+
+        async fn head_object(
+            &self,
+            _key: &str,
+        ) -> Result<(u16, HashMap<String, String>), LumniError> {
+            Ok((404, HashMap::new()))
+        }
+
+        async fn delete_object(&self, key: &str) -> Result<(), LumniError> {
+            match self.objects.lock().unwrap().remove(key) {
+                Some(_) => Ok(()),
+                None => Err(LumniError::NotFound(key.to_string())),
+            }
+        }
+    }
+
+    // registers a store with the keys under its own scheme, tests run
+    // in parallel and share the registry
+    fn paged_store(scheme: &str, keys: &[&str]) -> Objects {
+        let objects: Objects = Arc::new(Mutex::new(
+            keys.iter().map(|key| (key.to_string(), 1)).collect(),
+        ));
+        register_backend(
+            scheme,
+            Box::new(PagedFactory {
+                objects: objects.clone(),
+            }),
+        )
+        .unwrap();
+        objects
+    }
+
+    #[tokio::test]
+    async fn test_delete_prefix() {
+        let objects = paged_store(
+            "pageddelete",
+            &["logs/a", "logs/b", "logs/c", "logs-old/a", "logs2/a", "top"],
+        );
+        let handler = ObjectStoreHandler::new(None);
+        let config = EnvironmentConfig::new(HashMap::new());
+        let result = handler
+            .delete_objects(
+                &ParsedUri::from_uri("pageddelete://bucket/logs", false),
+                &config,
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+        // more keys than fit on a page, none of the sibling prefixes
+        assert_eq!(result.deleted(), ["logs/a", "logs/b", "logs/c"]);
+        assert_eq!(
+            objects.lock().unwrap().keys().collect::<Vec<_>>(),
+            ["logs-old/a", "logs2/a", "top"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_inventory() {
+        paged_store(
+            "pageddiff",
+            &["a/1", "a/2", "a/3", "b/1", "b/2", "b/3", "b/4"],
+        );
+        let handler = ObjectStoreHandler::new(None);
+        let config = EnvironmentConfig::new(HashMap::new());
+        let uri_a = ParsedUri::from_uri("pageddiff://bucket/a", false);
+        let uri_b = ParsedUri::from_uri("pageddiff://bucket/b", false);
+        let inventory = handler
+            .list_inventory(&uri_a, &config, DiffStrategy::Mtime)
+            .await
+            .unwrap();
+        assert_eq!(inventory.keys().collect::<Vec<_>>(), ["1", "2", "3"]);
+
+        // only the object that is not in a, on any page
+        let table = handler
+            .diff(&uri_a, &uri_b, &config, DiffStrategy::Mtime, None)
+            .await
+            .unwrap();
+        assert_eq!(table.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_plan_copy() {
+        paged_store("pagedcopy", &["a/1", "a/2", "a/sub/3", "ab/1"]);
+        let handler = ObjectStoreHandler::new(None);
+        let config = EnvironmentConfig::new(HashMap::new());
+        let planned = handler
+            .plan_copy(
+                &ParsedUri::from_uri("pagedcopy://bucket/a", false),
+                &ParsedUri::from_uri("pagedcopy://other/b", false),
+                &config,
+            )
+            .await
+            .unwrap();
+        // every object under the prefix, on any page
+        let copies: Vec<_> = planned
+            .iter()
+            .map(|operation| (operation.source().unwrap(), operation.target()))
+            .collect();
+        assert_eq!(
+            copies,
+            [
+                ("pagedcopy://bucket/a/1", "pagedcopy://other/b/1"),
+                ("pagedcopy://bucket/a/2", "pagedcopy://other/b/2"),
+                ("pagedcopy://bucket/a/sub/3", "pagedcopy://other/b/sub/3"),
+            ]
+        );
+    }
 }
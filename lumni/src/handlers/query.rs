@@ -0,0 +1,872 @@
+use std::cmp::Ordering;
+use std::sync::{Arc, Mutex};
+
+use regex::Regex;
+use sqlparser::ast::{
+    BinaryOperator, Expr, OrderByExpr, Query, SelectItem, SetExpr,
+    UnaryOperator, Value,
+};
+
+use crate::table::{
+    FileObjectTable, Table, TableCallback, TableColumnValue, TableRow,
+};
+use crate::{FileObjectFilter, LumniError};
+
+// columns that WHERE and ORDER BY can refer to
+const FILE_COLUMNS: [&str; 5] =
+    ["name", "size", "modified", "checksum", "storage_class"];
+// columns listed for SELECT *
+const DEFAULT_COLUMNS: [&str; 3] = ["name", "size", "modified"];
+
+type RowData = Vec<(String, TableColumnValue)>;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Literal {
+    Number(f64),
+    String(String),
+    Null,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+impl CompareOp {
+    fn from_operator(op: &BinaryOperator) -> Option<Self> {
+        match op {
+            BinaryOperator::Eq => Some(CompareOp::Eq),
+            BinaryOperator::NotEq => Some(CompareOp::NotEq),
+            BinaryOperator::Lt => Some(CompareOp::Lt),
+            BinaryOperator::LtEq => Some(CompareOp::LtEq),
+            BinaryOperator::Gt => Some(CompareOp::Gt),
+            BinaryOperator::GtEq => Some(CompareOp::GtEq),
+            _ => None,
+        }
+    }
+
+    // for "literal op column", rewritten as "column op literal"
+    fn flip(self) -> Self {
+        match self {
+            CompareOp::Lt => CompareOp::Gt,
+            CompareOp::LtEq => CompareOp::GtEq,
+            CompareOp::Gt => CompareOp::Lt,
+            CompareOp::GtEq => CompareOp::LtEq,
+            op => op,
+        }
+    }
+
+    fn matches(self, ordering: Ordering) -> bool {
+        match self {
+            CompareOp::Eq => ordering == Ordering::Equal,
+            CompareOp::NotEq => ordering != Ordering::Equal,
+            CompareOp::Lt => ordering == Ordering::Less,
+            CompareOp::LtEq => ordering != Ordering::Greater,
+            CompareOp::Gt => ordering == Ordering::Greater,
+            CompareOp::GtEq => ordering != Ordering::Less,
+        }
+    }
+}
+
+// WHERE clause, evaluated with SQL semantics: a comparison with NULL is
+// unknown (None), and only rows that evaluate to true are selected
+#[derive(Debug, Clone)]
+enum Predicate {
+    Compare(String, CompareOp, Literal),
+    Like(String, Regex, bool), // bool: negated
+    IsNull(String, bool),      // bool: negated
+    InList(String, Vec<Literal>, bool),
+    And(Box<Predicate>, Box<Predicate>),
+    Or(Box<Predicate>, Box<Predicate>),
+    Not(Box<Predicate>),
+}
+
+impl Predicate {
+    fn from_expr(expr: &Expr) -> Result<Self, LumniError> {
+        match expr {
+            Expr::Nested(expr) => Predicate::from_expr(expr),
+            Expr::UnaryOp {
+                op: UnaryOperator::Not,
+                expr,
+            } => Ok(Predicate::Not(Box::new(Predicate::from_expr(expr)?))),
+            Expr::BinaryOp { left, op, right } => match op {
+                BinaryOperator::And => Ok(Predicate::And(
+                    Box::new(Predicate::from_expr(left)?),
+                    Box::new(Predicate::from_expr(right)?),
+                )),
+                BinaryOperator::Or => Ok(Predicate::Or(
+                    Box::new(Predicate::from_expr(left)?),
+                    Box::new(Predicate::from_expr(right)?),
+                )),
+                op => {
+                    let op = CompareOp::from_operator(op).ok_or_else(|| {
+                        invalid_query(format!("Unsupported operator: {}", op))
+                    })?;
+                    match (column_name(left), column_name(right)) {
+                        (Some(column), None) => {
+                            Ok(Predicate::Compare(column?, op, literal(right)?))
+                        }
+                        (None, Some(column)) => Ok(Predicate::Compare(
+                            column?,
+                            op.flip(),
+                            literal(left)?,
+                        )),
+                        _ => Err(invalid_query(format!(
+                            "Expected a comparison between a column and a \
+                             value: {}",
+                            expr
+                        ))),
+                    }
+                }
+            },
+            Expr::Like {
+                negated,
+                expr,
+                pattern,
+                escape_char,
+            } => {
+                let column = expect_column(expr)?;
+                let pattern = match literal(pattern)? {
+                    Literal::String(pattern) => pattern,
+                    _ => {
+                        return Err(invalid_query(
+                            "LIKE expects a string pattern".to_string(),
+                        ))
+                    }
+                };
+                Ok(Predicate::Like(
+                    column,
+                    like_to_regex(&pattern, *escape_char)?,
+                    *negated,
+                ))
+            }
+            Expr::IsNull(expr) => {
+                Ok(Predicate::IsNull(expect_column(expr)?, false))
+            }
+            Expr::IsNotNull(expr) => {
+                Ok(Predicate::IsNull(expect_column(expr)?, true))
+            }
+            Expr::InList {
+                expr,
+                list,
+                negated,
+            } => Ok(Predicate::InList(
+                expect_column(expr)?,
+                list.iter().map(literal).collect::<Result<_, _>>()?,
+                *negated,
+            )),
+            Expr::Between {
+                expr,
+                negated,
+                low,
+                high,
+            } => {
+                let column = expect_column(expr)?;
+                let between = Predicate::And(
+                    Box::new(Predicate::Compare(
+                        column.clone(),
+                        CompareOp::GtEq,
+                        literal(low)?,
+                    )),
+                    Box::new(Predicate::Compare(
+                        column,
+                        CompareOp::LtEq,
+                        literal(high)?,
+                    )),
+                );
+                if *negated {
+                    Ok(Predicate::Not(Box::new(between)))
+                } else {
+                    Ok(between)
+                }
+            }
+            _ => {
+                Err(invalid_query(format!("Unsupported expression: {}", expr)))
+            }
+        }
+    }
+
+    fn evaluate(&self, row: &[(String, TableColumnValue)]) -> Option<bool> {
+        match self {
+            Predicate::Compare(column, op, literal) => {
+                compare(row_value(row, column)?, literal)
+                    .map(|ordering| op.matches(ordering))
+            }
+            Predicate::Like(column, regex, negated) => {
+                match row_value(row, column)? {
+                    Literal::String(value) => {
+                        Some(regex.is_match(&value) != *negated)
+                    }
+                    _ => None,
+                }
+            }
+            Predicate::IsNull(column, negated) => {
+                Some(row_value(row, column).is_none() != *negated)
+            }
+            Predicate::InList(column, list, negated) => {
+                let value = row_value(row, column)?;
+                let found = list.iter().any(|literal| {
+                    compare(value.clone(), literal) == Some(Ordering::Equal)
+                });
+                Some(found != *negated)
+            }
+            Predicate::And(left, right) => {
+                match (left.evaluate(row), right.evaluate(row)) {
+                    (Some(false), _) | (_, Some(false)) => Some(false),
+                    (Some(true), Some(true)) => Some(true),
+                    _ => None,
+                }
+            }
+            Predicate::Or(left, right) => {
+                match (left.evaluate(row), right.evaluate(row)) {
+                    (Some(true), _) | (_, Some(true)) => Some(true),
+                    (Some(false), Some(false)) => Some(false),
+                    _ => None,
+                }
+            }
+            Predicate::Not(predicate) => predicate.evaluate(row).map(|b| !b),
+        }
+    }
+
+    fn columns<'a>(&'a self, columns: &mut Vec<&'a str>) {
+        match self {
+            Predicate::Compare(column, _, _)
+            | Predicate::Like(column, _, _)
+            | Predicate::IsNull(column, _)
+            | Predicate::InList(column, _, _) => columns.push(column),
+            Predicate::And(left, right) | Predicate::Or(left, right) => {
+                left.columns(columns);
+                right.columns(columns);
+            }
+            Predicate::Not(predicate) => predicate.columns(columns),
+        }
+    }
+
+    // conditions that must hold for every selected row, i.e. the terms
+    // of the top-level AND
+    fn conjuncts(&self) -> Vec<&Predicate> {
+        match self {
+            Predicate::And(left, right) => {
+                let mut conjuncts = left.conjuncts();
+                conjuncts.extend(right.conjuncts());
+                conjuncts
+            }
+            predicate => vec![predicate],
+        }
+    }
+}
+
+// a SELECT on an object listing, e.g.
+// SELECT name, size FROM 's3://bucket/prefix' WHERE size > 1000000
+// ORDER BY modified DESC LIMIT 10
+#[derive(Debug)]
+pub struct SelectQuery {
+    uri: String,
+    columns: Option<Vec<String>>, // None for SELECT *
+    predicate: Option<Predicate>,
+    order_by: Vec<(String, bool, bool)>, // column, ascending, nulls first
+    limit: Option<usize>,
+    offset: usize,
+}
+
+impl SelectQuery {
+    pub fn from_query(query: &Query) -> Result<Self, LumniError> {
+        let select = match &*query.body {
+            SetExpr::Select(select) => select,
+            _ => {
+                return Err(invalid_query(
+                    "Only SELECT statements are supported".to_string(),
+                ))
+            }
+        };
+
+        let columns = if select
+            .projection
+            .iter()
+            .any(|item| matches!(item, SelectItem::Wildcard(_)))
+        {
+            None
+        } else {
+            Some(
+                select
+                    .projection
+                    .iter()
+                    .filter_map(|item| match item {
+                        SelectItem::UnnamedExpr(Expr::Identifier(ident)) => {
+                            Some(ident.value.clone())
+                        }
+                        _ => {
+                            log::warn!(
+                                "Skipping non-identifier selection: {:?}",
+                                item
+                            );
+                            None
+                        }
+                    })
+                    .collect(),
+            )
+        };
+
+        let mut uri = select
+            .from
+            .first()
+            .map(|table| table.relation.to_string())
+            .ok_or_else(|| {
+                invalid_query("Query does not have a FROM clause".to_string())
+            })?;
+        // check for and remove leading and trailing quotes
+        if (uri.starts_with('"') && uri.ends_with('"'))
+            || (uri.starts_with('\'') && uri.ends_with('\''))
+        {
+            uri = uri[1..uri.len() - 1].to_string();
+        }
+
+        let predicate = select
+            .selection
+            .as_ref()
+            .map(Predicate::from_expr)
+            .transpose()?;
+        let order_by = query
+            .order_by
+            .iter()
+            .map(|order_by| {
+                let OrderByExpr {
+                    expr,
+                    asc,
+                    nulls_first,
+                } = order_by;
+                let ascending = asc.unwrap_or(true);
+                // NULLs are larger than any value, as in PostgreSQL
+                let nulls_first = nulls_first.unwrap_or(!ascending);
+                Ok((expect_column(expr)?, ascending, nulls_first))
+            })
+            .collect::<Result<Vec<_>, LumniError>>()?;
+        let limit = query.limit.as_ref().map(count).transpose()?;
+        let offset = query
+            .offset
+            .as_ref()
+            .map(|offset| count(&offset.value))
+            .transpose()?
+            .unwrap_or(0);
+
+        Ok(SelectQuery {
+            uri,
+            columns,
+            predicate,
+            order_by,
+            limit,
+            offset,
+        })
+    }
+
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    // without WHERE or ORDER BY the listing is the result, so it can be
+    // listed as selected, including LIMIT
+    pub fn is_plain_listing(&self) -> bool {
+        self.predicate.is_none() && self.order_by.is_empty() && self.offset == 0
+    }
+
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    pub fn selected_columns(&self) -> Option<Vec<&str>> {
+        self.columns
+            .as_ref()
+            .map(|columns| columns.iter().map(String::as_str).collect())
+    }
+
+    // the selected columns plus the ones needed to filter and sort
+    pub fn listing_columns(&self) -> Vec<&str> {
+        let mut columns = self
+            .selected_columns()
+            .unwrap_or_else(|| DEFAULT_COLUMNS.to_vec());
+        let mut referenced = Vec::new();
+        if let Some(predicate) = &self.predicate {
+            predicate.columns(&mut referenced);
+        }
+        referenced
+            .extend(self.order_by.iter().map(|(column, _, _)| column.as_str()));
+        for column in referenced {
+            if !columns.contains(&column) {
+                columns.push(column);
+            }
+        }
+        columns
+    }
+
+    // conditions on name, size and modified are passed to the backend
+    // listing, so objects that can not match are skipped early. the full
+    // predicate is still evaluated on each row that is listed
+    pub fn pushdown_filter(&self) -> Option<FileObjectFilter> {
+        let predicate = self.predicate.as_ref()?;
+        let mut size = (None, None);
+        let mut mtime = (None, None);
+        let mut name_regex = None;
+        for conjunct in predicate.conjuncts() {
+            match conjunct {
+                Predicate::Compare(column, op, Literal::Number(value))
+                    if column == "size" || column == "modified" =>
+                {
+                    let range = if column == "size" {
+                        &mut size
+                    } else {
+                        &mut mtime
+                    };
+                    narrow_range(range, *op, *value);
+                }
+                Predicate::Like(column, regex, false)
+                    if column == "name" && name_regex.is_none() =>
+                {
+                    name_regex = Some(regex.clone());
+                }
+                _ => {}
+            }
+        }
+        if size == (None, None) && mtime == (None, None) && name_regex.is_none()
+        {
+            return None;
+        }
+        let mut filter = FileObjectFilter::default()
+            .set_size_range(size.0, size.1)
+            .set_mtime_range(mtime.0, mtime.1);
+        if let Some(name_regex) = name_regex {
+            filter = filter.set_name_regex(name_regex);
+        }
+        Some(filter)
+    }
+
+    // collects the rows that match the WHERE clause while they are listed.
+    // without ORDER BY, these are also passed on to the callback directly
+    pub fn collector(
+        self: &Arc<Self>,
+        callback: Option<Arc<dyn TableCallback>>,
+    ) -> Arc<QueryCollector> {
+        let callback = if self.order_by.is_empty() {
+            callback
+        } else {
+            None
+        };
+        Arc::new(QueryCollector {
+            query: Arc::clone(self),
+            callback,
+            rows: Mutex::new(Vec::new()),
+        })
+    }
+
+    // sorts, applies OFFSET and LIMIT, and selects the columns of the
+    // collected rows
+    pub fn build_table(
+        &self,
+        collector: Arc<QueryCollector>,
+        callback: Option<Arc<dyn TableCallback>>,
+    ) -> Result<Box<dyn Table>, LumniError> {
+        let mut rows = std::mem::take(&mut *collector.rows.lock().unwrap());
+        for (column, ascending, nulls_first) in self.order_by.iter().rev() {
+            // stable sort, so earlier ORDER BY columns take precedence
+            rows.sort_by(|a, b| {
+                match (row_value(a, column), row_value(b, column)) {
+                    (None, None) => Ordering::Equal,
+                    (None, Some(_)) if *nulls_first => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (Some(_), None) if *nulls_first => Ordering::Greater,
+                    (Some(_), None) => Ordering::Less,
+                    (Some(a), Some(b)) => {
+                        let ordering =
+                            compare(a, &b).unwrap_or(Ordering::Equal);
+                        if *ascending {
+                            ordering
+                        } else {
+                            ordering.reverse()
+                        }
+                    }
+                }
+            });
+        }
+
+        let columns = self
+            .selected_columns()
+            .unwrap_or_else(|| DEFAULT_COLUMNS.to_vec());
+        // rows were already passed to the callback while listing
+        let callback = match collector.callback {
+            Some(_) => None,
+            None => callback,
+        };
+        let mut table = FileObjectTable::new(&Some(columns.clone()), callback);
+        for row in self.page(rows) {
+            table
+                .add_row(project(&row, &columns))
+                .map_err(LumniError::Internal)?;
+        }
+        Ok(Box::new(table))
+    }
+
+    fn page(&self, rows: Vec<RowData>) -> impl Iterator<Item = RowData> {
+        rows.into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+    }
+
+    fn in_page(&self, position: usize) -> bool {
+        position >= self.offset
+            && self
+                .limit
+                .is_none_or(|limit| position < self.offset + limit)
+    }
+
+    pub fn validate(&self) -> Result<(), LumniError> {
+        let mut referenced = Vec::new();
+        if let Some(predicate) = &self.predicate {
+            predicate.columns(&mut referenced);
+        }
+        referenced
+            .extend(self.order_by.iter().map(|(column, _, _)| column.as_str()));
+        match referenced
+            .into_iter()
+            .find(|column| !FILE_COLUMNS.contains(column))
+        {
+            Some(column) => Err(invalid_query(format!(
+                "Unknown column: {}, expected one of: {}",
+                column,
+                FILE_COLUMNS.join(", ")
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+pub struct QueryCollector {
+    query: Arc<SelectQuery>,
+    callback: Option<Arc<dyn TableCallback>>,
+    rows: Mutex<Vec<RowData>>,
+}
+
+impl TableCallback for QueryCollector {
+    fn on_row_add(&self, row: &mut TableRow) {
+        let selected = self.query.predicate.as_ref().is_none_or(|predicate| {
+            predicate.evaluate(row.data()) == Some(true)
+        });
+        if !selected {
+            return;
+        }
+        let mut rows = self.rows.lock().unwrap();
+        rows.push(row.data().to_vec());
+        if let Some(callback) = &self.callback {
+            if self.query.in_page(rows.len() - 1) {
+                let columns = self
+                    .query
+                    .selected_columns()
+                    .unwrap_or_else(|| DEFAULT_COLUMNS.to_vec());
+                let mut row =
+                    TableRow::new(project(row.data(), &columns), None);
+                callback.on_row_add(&mut row);
+            }
+        }
+    }
+}
+
+fn invalid_query(message: String) -> LumniError {
+    LumniError::Config(format!("Invalid query: {}", message))
+}
+
+fn column_name(expr: &Expr) -> Option<Result<String, LumniError>> {
+    match expr {
+        Expr::Identifier(ident) => Some(Ok(ident.value.clone())),
+        Expr::Nested(expr) => column_name(expr),
+        Expr::Value(_) | Expr::UnaryOp { .. } => None,
+        _ => Some(Err(invalid_query(format!(
+            "Expected a column or value: {}",
+            expr
+        )))),
+    }
+}
+
+fn expect_column(expr: &Expr) -> Result<String, LumniError> {
+    column_name(expr).unwrap_or_else(|| {
+        Err(invalid_query(format!("Expected a column: {}", expr)))
+    })
+}
+
+fn literal(expr: &Expr) -> Result<Literal, LumniError> {
+    match expr {
+        Expr::Value(Value::Number(n, _)) => n
+            .parse::<f64>()
+            .map(Literal::Number)
+            .map_err(|_| invalid_query(format!("Invalid number: {}", n))),
+        Expr::Value(Value::SingleQuotedString(s)) => {
+            Ok(Literal::String(s.clone()))
+        }
+        Expr::Value(Value::Null) => Ok(Literal::Null),
+        Expr::UnaryOp {
+            op: UnaryOperator::Minus,
+            expr,
+        } => match literal(expr)? {
+            Literal::Number(n) => Ok(Literal::Number(-n)),
+            _ => Err(invalid_query(format!("Invalid value: -{}", expr))),
+        },
+        Expr::Nested(expr) => literal(expr),
+        _ => Err(invalid_query(format!("Expected a value: {}", expr))),
+    }
+}
+
+fn count(expr: &Expr) -> Result<usize, LumniError> {
+    match expr {
+        Expr::Value(Value::Number(n, _)) => n
+            .parse::<usize>()
+            .map_err(|_| invalid_query(format!("Invalid count: {}", n))),
+        _ => Err(invalid_query(format!("Expected a number: {}", expr))),
+    }
+}
+
+// % matches any sequence, _ a single character
+fn like_to_regex(
+    pattern: &str,
+    escape_char: Option<char>,
+) -> Result<Regex, LumniError> {
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if Some(c) == escape_char => {
+                if let Some(next) = chars.next() {
+                    regex.push_str(&regex::escape(&next.to_string()));
+                }
+            }
+            '%' => regex.push_str("(?s:.*)"),
+            '_' => regex.push_str("(?s:.)"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex)
+        .map_err(|e| invalid_query(format!("Invalid LIKE pattern: {}", e)))
+}
+
+fn row_value(
+    row: &[(String, TableColumnValue)],
+    column: &str,
+) -> Option<Literal> {
+    let (_, value) = row.iter().find(|(name, _)| name == column)?;
+    match value {
+        TableColumnValue::Int32Column(val)
+        | TableColumnValue::OptionalInt32Column(Some(val)) => {
+            Some(Literal::Number(*val as f64))
+        }
+        TableColumnValue::Uint64Column(val)
+        | TableColumnValue::OptionalUint64Column(Some(val)) => {
+            Some(Literal::Number(*val as f64))
+        }
+        TableColumnValue::FloatColumn(val)
+        | TableColumnValue::OptionalFloatColumn(Some(val)) => {
+            Some(Literal::Number(*val))
+        }
+        TableColumnValue::StringColumn(val)
+        | TableColumnValue::OptionalStringColumn(Some(val)) => {
+            Some(Literal::String(val.clone()))
+        }
+        _ => None,
+    }
+}
+
+// None if either side is NULL, or the types differ
+fn compare(value: Literal, other: &Literal) -> Option<Ordering> {
+    match (value, other) {
+        (Literal::Number(a), Literal::Number(b)) => a.partial_cmp(b),
+        (Literal::String(a), Literal::String(b)) => Some(a.as_str().cmp(b)),
+        _ => None,
+    }
+}
+
+fn narrow_range(
+    range: &mut (Option<u64>, Option<u64>),
+    op: CompareOp,
+    value: f64,
+) {
+    if value < 0.0 {
+        return;
+    }
+    let (min, max) = match op {
+        CompareOp::Eq => (Some(value.ceil()), Some(value.floor())),
+        CompareOp::Gt => (Some(value.floor() + 1.0), None),
+        CompareOp::GtEq => (Some(value.ceil()), None),
+        CompareOp::Lt => (None, Some(value.ceil() - 1.0)),
+        CompareOp::LtEq => (None, Some(value.floor())),
+        CompareOp::NotEq => (None, None),
+    };
+    if let Some(min) = min {
+        let min = min as u64;
+        range.0 = Some(range.0.map_or(min, |current| current.max(min)));
+    }
+    if let Some(max) = max {
+        if max < 0.0 {
+            // nothing can match, but leave that to the predicate
+            return;
+        }
+        let max = max as u64;
+        range.1 = Some(range.1.map_or(max, |current| current.min(max)));
+    }
+}
+
+// the selected columns of a row, with NULL for the ones that were not
+// listed (e.g. modified of a directory)
+fn project(row: &[(String, TableColumnValue)], columns: &[&str]) -> RowData {
+    columns
+        .iter()
+        .filter_map(|column| {
+            let value = row
+                .iter()
+                .find(|(name, _)| name == column)
+                .map(|(_, value)| value.clone())
+                .or(match *column {
+                    "modified" => {
+                        Some(TableColumnValue::OptionalUint64Column(None))
+                    }
+                    "checksum" | "storage_class" => {
+                        Some(TableColumnValue::OptionalStringColumn(None))
+                    }
+                    _ => None,
+                })?;
+            Some((column.to_string(), value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlparser::ast::Statement;
+    use sqlparser::dialect::GenericDialect;
+    use sqlparser::parser::Parser;
+
+    use super::*;
+    use crate::FileObject;
+
+    fn parse(statement: &str) -> SelectQuery {
+        match Parser::parse_sql(&GenericDialect {}, statement)
+            .unwrap()
+            .remove(0)
+        {
+            Statement::Query(query) => SelectQuery::from_query(&query).unwrap(),
+            _ => panic!("not a query"),
+        }
+    }
+
+    fn row(name: &str, size: u64, modified: Option<u64>) -> RowData {
+        vec![
+            (
+                "name".to_string(),
+                TableColumnValue::StringColumn(name.to_string()),
+            ),
+            ("size".to_string(), TableColumnValue::Uint64Column(size)),
+            (
+                "modified".to_string(),
+                TableColumnValue::OptionalUint64Column(modified),
+            ),
+        ]
+    }
+
+    fn select(statement: &str) -> Vec<String> {
+        let query = Arc::new(parse(statement));
+        query.validate().unwrap();
+        let collector = query.collector(None);
+        for data in [
+            row("a.txt", 10, Some(300)),
+            row("b.csv", 2000, Some(100)),
+            row("c.txt", 500, None),
+            row("d.csv", 2000, Some(200)),
+        ] {
+            collector.on_row_add(&mut TableRow::new(data, None));
+        }
+        let table = query.build_table(collector, None).unwrap();
+        (0..table.len())
+            .map(|index| {
+                table.columns()[0].1.get_value(index).unwrap().to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_where_order_by_limit() {
+        let from = "FROM 'localfs:///tmp'";
+        assert_eq!(
+            select(&format!("SELECT name {} WHERE size > 100", from)),
+            ["b.csv", "c.txt", "d.csv"]
+        );
+        assert_eq!(
+            select(&format!(
+                "SELECT name {} WHERE name LIKE '%.csv' OR modified IS NULL \
+                 ORDER BY size DESC, modified",
+                from
+            )),
+            ["b.csv", "d.csv", "c.txt"]
+        );
+        // NULL is neither larger nor smaller than 150
+        assert_eq!(
+            select(&format!(
+                "SELECT name {} WHERE NOT modified < 150 ORDER BY modified \
+                 DESC LIMIT 1",
+                from
+            )),
+            ["a.txt"]
+        );
+        assert_eq!(
+            select(&format!(
+                "SELECT name {} WHERE size BETWEEN 10 AND 500 \
+                 ORDER BY name DESC LIMIT 1 OFFSET 1",
+                from
+            )),
+            ["a.txt"]
+        );
+        assert_eq!(
+            select(&format!(
+                "SELECT name {} WHERE name IN ('a.txt', 'x')",
+                from
+            )),
+            ["a.txt"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_query() {
+        assert!(parse("SELECT * FROM 'x' WHERE owner = 'me'")
+            .validate()
+            .is_err());
+        let statement = "SELECT * FROM 'x' WHERE size > modified";
+        let query = match Parser::parse_sql(&GenericDialect {}, statement)
+            .unwrap()
+            .remove(0)
+        {
+            Statement::Query(query) => query,
+            _ => panic!("not a query"),
+        };
+        assert!(SelectQuery::from_query(&query).is_err());
+    }
+
+    #[test]
+    fn test_pushdown_filter() {
+        let query = parse(
+            "SELECT * FROM 'x' WHERE size > 1000 AND size <= 5000 \
+             AND name LIKE 'logs/%' AND (modified < 10 OR size = 1)",
+        );
+        let filter = query.pushdown_filter().unwrap();
+        let file = |name: &str, size| {
+            FileObject::new(name.to_string(), size, Some(5), None)
+        };
+        assert!(filter.matches(&file("logs/a", 1001)));
+        assert!(filter.matches(&file("logs/a", 5000)));
+        assert!(!filter.matches(&file("logs/a", 1000)));
+        assert!(!filter.matches(&file("logs/a", 5001)));
+        assert!(!filter.matches(&file("data/a", 2000)));
+
+        // OR can not be pushed down
+        let query = parse("SELECT * FROM 'x' WHERE size > 10 OR size = 1");
+        assert!(query.pushdown_filter().is_none());
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use super::object_store::ObjectStoreTrait;
+use crate::{EnvironmentConfig, LumniError};
+
+// schemes of the built-in backends, these can not be registered again
+const BUILTIN_SCHEMES: [&str; 9] = [
+    "localfs", "s3", "gs", "az", "abfss", "sftp", "hdfs", "http", "https",
+];
+
+static BACKENDS: OnceLock<
+    RwLock<HashMap<String, Arc<dyn ObjectStoreBackendFactory>>>,
+> = OnceLock::new();
+
+// creates the object store of a bucket for a registered scheme, e.g. for
+// "ftp://host/path" the name is "host"
+pub trait ObjectStoreBackendFactory: Send + Sync {
+    fn create(
+        &self,
+        name: &str,
+        config: EnvironmentConfig,
+    ) -> Result<Box<dyn ObjectStoreTrait + Sync>, LumniError>;
+}
+
+// makes ObjectStoreHandler dispatch uris of the scheme to the factory,
+// a later registration of the same scheme replaces the earlier one
+pub fn register_backend(
+    scheme: &str,
+    factory: Box<dyn ObjectStoreBackendFactory>,
+) -> Result<(), LumniError> {
+    let valid = !scheme.is_empty()
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit());
+    if !valid || BUILTIN_SCHEMES.contains(&scheme) {
+        return Err(LumniError::Config(format!(
+            "Can not register a backend for scheme \"{}\"",
+            scheme
+        )));
+    }
+    backends()
+        .write()
+        .unwrap()
+        .insert(scheme.to_string(), Arc::from(factory));
+    Ok(())
+}
+
+pub(crate) fn is_registered(scheme: &str) -> bool {
+    backends().read().unwrap().contains_key(scheme)
+}
+
+pub(crate) fn backend_factory(
+    scheme: &str,
+) -> Option<Arc<dyn ObjectStoreBackendFactory>> {
+    backends().read().unwrap().get(scheme).cloned()
+}
+
+fn backends(
+) -> &'static RwLock<HashMap<String, Arc<dyn ObjectStoreBackendFactory>>> {
+    BACKENDS.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+// object store of a registered scheme
+#[derive(Clone)]
+pub struct RegisteredBucket {
+    scheme: String,
+    store: Arc<dyn ObjectStoreTrait + Sync>,
+}
+
+impl RegisteredBucket {
+    pub(crate) fn new(
+        scheme: &str,
+        name: &str,
+        config: EnvironmentConfig,
+    ) -> Result<Self, LumniError> {
+        let factory = backend_factory(scheme).ok_or_else(|| {
+            LumniError::Config(format!(
+                "No backend registered for {}://",
+                scheme
+            ))
+        })?;
+        Ok(RegisteredBucket {
+            scheme: scheme.to_string(),
+            store: Arc::from(factory.create(name, config)?),
+        })
+    }
+
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+
+    pub fn store(&self) -> &(dyn ObjectStoreTrait + Sync) {
+        self.store.as_ref()
+    }
+}
+
+impl fmt::Debug for RegisteredBucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegisteredBucket")
+            .field("scheme", &self.scheme)
+            .field("name", &self.store.name())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::table::FileObjectTable;
+    use crate::{FileObject, FileObjectFilter, ObjectStoreHandler, ParsedUri};
+
+    struct MemoryFactory;
+
+    struct MemoryStore {
+        name: String,
+        config: EnvironmentConfig,
+    }
+
+    impl ObjectStoreBackendFactory for MemoryFactory {
+        fn create(
+            &self,
+            name: &str,
+            config: EnvironmentConfig,
+        ) -> Result<Box<dyn ObjectStoreTrait + Sync>, LumniError> {
+            Ok(Box::new(MemoryStore {
+                name: name.to_string(),
+                config,
+            }))
+        }
+    }
+
+    #[async_trait(?Send)]
+    impl ObjectStoreTrait for MemoryStore {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn config(&self) -> &EnvironmentConfig {
+            &self.config
+        }
+
+        async fn list_files(
+            &self,
+            _prefix: Option<&str>,
+            _selected_columns: &Option<Vec<&str>>,
+            _recursive: bool,
+            _max_keys: Option<u32>,
+            _filter: &Option<FileObjectFilter>,
+            table: &mut FileObjectTable,
+        ) -> Result<(), LumniError> {
+            let file_object =
+                FileObject::new("a.txt".to_string(), 5, None, None);
+            table
+                .add_file_objects(vec![file_object])
+                .await
+                .map_err(LumniError::Internal)
+        }
+
+        async fn get_object(
+            &self,
+            key: &str,
+            data: &mut Vec<u8>,
+        ) -> Result<(), LumniError> {
+            data.extend_from_slice(format!("{}/{}", self.name, key).as_bytes());
+            Ok(())
+        }
+
+        async fn head_object(
+            &self,
+            _key: &str,
+        ) -> Result<(u16, HashMap<String, String>), LumniError> {
+            Ok((200, HashMap::new()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_register_backend() {
+        assert!(register_backend("s3", Box::new(MemoryFactory)).is_err());
+        assert!(register_backend("mem:", Box::new(MemoryFactory)).is_err());
+        register_backend("mem", Box::new(MemoryFactory)).unwrap();
+
+        let handler = ObjectStoreHandler::new(None);
+        let config = EnvironmentConfig::new(HashMap::new());
+        let table = handler
+            .list_objects(
+                &ParsedUri::from_uri("mem://bucket/", true),
+                &config,
+                None,
+                false,
+                None,
+                &None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(table.len(), 1);
+        let data = handler
+            .get_object(
+                &ParsedUri::from_uri("mem://bucket/a.txt", false),
+                &config,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(data.as_deref(), Some("bucket/a.txt".as_bytes()));
+    }
+}
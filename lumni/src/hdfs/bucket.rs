@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+
+use super::client::HdfsClient;
+use super::config::validate_config;
+use super::get::{get_object, get_object_stream};
+use super::list::{check_status, list_files, parse_error};
+use super::parse_http_response::parse_file_status;
+use crate::base::config::EnvironmentConfig;
+use crate::base::object_metadata::ObjectMetadata;
+use crate::handlers::object_store::{
+    ByteRange, ObjectStoreTrait, ObjectStream,
+};
+use crate::http::requests::http_get_request;
+use crate::table::FileObjectTable;
+use crate::{FileObjectFilter, LumniError};
+
+// a cluster, named by its namenode, keys are paths from the root of the
+// filesystem
+#[derive(Debug, Clone)]
+pub struct HdfsBucket {
+    name: String,
+    config: EnvironmentConfig,
+}
+
+impl HdfsBucket {
+    pub fn new(
+        name: &str,
+        mut config: EnvironmentConfig,
+    ) -> Result<HdfsBucket, LumniError> {
+        validate_config(name, &mut config)?;
+
+        Ok(HdfsBucket {
+            name: name.to_string(),
+            config,
+        })
+    }
+
+    pub fn config(&self) -> &EnvironmentConfig {
+        &self.config
+    }
+}
+
+#[async_trait(?Send)]
+impl ObjectStoreTrait for HdfsBucket {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn config(&self) -> &EnvironmentConfig {
+        &self.config
+    }
+
+    async fn list_files(
+        &self,
+        prefix: Option<&str>,
+        _selected_columns: &Option<Vec<&str>>,
+        recursive: bool,
+        max_keys: Option<u32>,
+        filter: &Option<FileObjectFilter>,
+        table: &mut FileObjectTable,
+    ) -> Result<(), LumniError> {
+        list_files(self, prefix, recursive, max_keys, filter, table).await
+    }
+
+    async fn get_object(
+        &self,
+        key: &str,
+        data: &mut Vec<u8>,
+    ) -> Result<(), LumniError> {
+        get_object(self, key, data).await
+    }
+
+    async fn get_object_stream(
+        &self,
+        key: &str,
+        range: Option<ByteRange>,
+    ) -> Result<ObjectStream, LumniError> {
+        get_object_stream(self, key, range).await
+    }
+
+    async fn head_object(
+        &self,
+        key: &str,
+    ) -> Result<(u16, HashMap<String, String>), LumniError> {
+        let client = HdfsClient::new(&self.config);
+        let (body, status) = http_get_request(
+            &client.operation_url(key, "GETFILESTATUS"),
+            &HashMap::new(),
+        )
+        .await?;
+        if !(200..300).contains(&status) {
+            return Ok((status, HashMap::new()));
+        }
+        let file_status = parse_file_status(&body).map_err(parse_error)?;
+        if !file_status.is_file() {
+            return Ok((404, HashMap::new()));
+        }
+        let mut headers = HashMap::new();
+        headers.insert(
+            "content-length".to_string(),
+            file_status.length().to_string(),
+        );
+        Ok((status, headers))
+    }
+
+    async fn get_object_metadata(
+        &self,
+        key: &str,
+    ) -> Result<ObjectMetadata, LumniError> {
+        let client = HdfsClient::new(&self.config);
+        let (body, status) = http_get_request(
+            &client.operation_url(key, "GETFILESTATUS"),
+            &HashMap::new(),
+        )
+        .await?;
+        check_status(status, key)?;
+        let file_status = parse_file_status(&body).map_err(parse_error)?;
+        if !file_status.is_file() {
+            return Err(LumniError::NotFound(key.to_string()));
+        }
+        Ok(ObjectMetadata::new(key)
+            .set_size(Some(file_status.length()))
+            .set_modified(file_status.modified()))
+    }
+}
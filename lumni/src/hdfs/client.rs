@@ -0,0 +1,48 @@
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+
+use crate::EnvironmentConfig;
+
+pub struct HdfsClient {
+    endpoint_url: String,
+    user: Option<String>,
+    delegation_token: Option<String>,
+}
+
+impl HdfsClient {
+    pub fn new(config: &EnvironmentConfig) -> HdfsClient {
+        let endpoint_url = config
+            .get("HDFS_WEBHDFS_ENDPOINT")
+            .expect("Missing endpoint in the configuration")
+            .to_string();
+        log::info!("HdfsClient created with endpoint_url: {}", endpoint_url);
+        HdfsClient {
+            endpoint_url,
+            user: config.get("HDFS_USER").cloned(),
+            delegation_token: config.get("HDFS_DELEGATION_TOKEN").cloned(),
+        }
+    }
+
+    // url of an operation on a key, keys are paths from the root of the
+    // filesystem
+    pub fn operation_url(&self, key: &str, op: &str) -> String {
+        let path = key
+            .trim_start_matches('/')
+            .split('/')
+            .map(encode)
+            .collect::<Vec<_>>()
+            .join("/");
+        let mut url =
+            format!("{}/webhdfs/v1/{}?op={}", self.endpoint_url, path, op);
+        // a delegation token replaces simple authentication
+        if let Some(token) = &self.delegation_token {
+            url.push_str(&format!("&delegation={}", encode(token)));
+        } else if let Some(user) = &self.user {
+            url.push_str(&format!("&user.name={}", encode(user)));
+        }
+        url
+    }
+}
+
+fn encode(value: &str) -> String {
+    utf8_percent_encode(value, NON_ALPHANUMERIC).to_string()
+}
@@ -0,0 +1,62 @@
+use std::env;
+
+use crate::{EnvironmentConfig, LumniError};
+
+// default HTTP port of the namenode since Hadoop 3, 50070 before
+const WEBHDFS_DEFAULT_PORT: u16 = 9870;
+
+// name is the namenode from hdfs://namenode[:port]/path, where the port is
+// that of WebHDFS, not of the RPC interface
+pub fn validate_config(
+    name: &str,
+    config: &mut EnvironmentConfig,
+) -> Result<(), LumniError> {
+    for key in ["HDFS_USER", "HDFS_DELEGATION_TOKEN"] {
+        if !config.contains_key(key) {
+            if let Ok(value) = env::var(key) {
+                config.insert(key.to_string(), value);
+            }
+        }
+    }
+    // same as the hadoop cli for simple authentication
+    if !config.contains_key("HDFS_USER") {
+        if let Ok(user) = env::var("HADOOP_USER_NAME") {
+            config.insert("HDFS_USER".to_string(), user);
+        }
+    }
+
+    // Set HDFS_WEBHDFS_ENDPOINT, can point to a gateway such as Knox,
+    // e.g. https://knox:8443/gateway/default
+    if !config.contains_key("HDFS_WEBHDFS_ENDPOINT") {
+        let endpoint_url = match env::var("HDFS_WEBHDFS_ENDPOINT") {
+            Ok(endpoint_url) => endpoint_url,
+            Err(_) => {
+                let (host, port) = match name.rsplit_once(':') {
+                    Some((host, port)) => (
+                        host,
+                        port.parse::<u16>().map_err(|_| {
+                            LumniError::Config(format!(
+                                "Invalid hdfs namenode \"{}\", expected \
+                                 host[:port]",
+                                name
+                            ))
+                        })?,
+                    ),
+                    None => (name, WEBHDFS_DEFAULT_PORT),
+                };
+                if host.is_empty() {
+                    return Err(LumniError::NoBucketInUri(format!(
+                        "hdfs://{}",
+                        name
+                    )));
+                }
+                format!("http://{}:{}", host, port)
+            }
+        };
+        config.insert(
+            "HDFS_WEBHDFS_ENDPOINT".to_string(),
+            endpoint_url.trim_end_matches('/').to_string(),
+        );
+    }
+    Ok(())
+}
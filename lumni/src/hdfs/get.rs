@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use super::bucket::HdfsBucket;
+use super::client::HdfsClient;
+use super::list::check_status;
+use crate::base::ranged_read::{ranged_stream, Chunk};
+use crate::handlers::object_store::{ByteRange, ObjectStream};
+use crate::http::requests::{http_get_request, http_request_with_headers};
+use crate::{LumniError, DEFAULT_DOWNLOAD_CHUNK_SIZE};
+
+pub async fn get_object(
+    hdfs_bucket: &HdfsBucket,
+    object_key: &str,
+    data: &mut Vec<u8>,
+) -> Result<(), LumniError> {
+    let client = HdfsClient::new(hdfs_bucket.config());
+    let headers = HashMap::new();
+
+    log::info!("Getting object: {}", object_key);
+    // the namenode redirects to a datanode that has the data
+    let (mut body_bytes, mut status, response_headers) =
+        http_request_with_headers(
+            &client.operation_url(object_key, "OPEN"),
+            &headers,
+            "GET",
+        )
+        .await?;
+    if matches!(status, 301 | 302 | 307) {
+        let location = response_headers.get("location").ok_or_else(|| {
+            LumniError::Internal(format!(
+                "Redirect without location for {}",
+                object_key
+            ))
+        })?;
+        (body_bytes, status) = http_get_request(location, &headers).await?;
+    }
+    check_status(status, object_key)?;
+    log::info!(
+        "Got object: {} of size {} bytes",
+        object_key,
+        body_bytes.len()
+    );
+    data.clear();
+    data.extend_from_slice(&body_bytes);
+    Ok(())
+}
+
+// each chunk is read with its own OPEN request, as S3 does with ranges
+pub async fn get_object_stream(
+    hdfs_bucket: &HdfsBucket,
+    object_key: &str,
+    range: Option<ByteRange>,
+) -> Result<ObjectStream, LumniError> {
+    let hdfs_bucket = hdfs_bucket.clone();
+    let object_key = object_key.to_string();
+    Ok(ranged_stream(range, DEFAULT_DOWNLOAD_CHUNK_SIZE, move |range| {
+        let hdfs_bucket = hdfs_bucket.clone();
+        let object_key = object_key.clone();
+        async move { get_chunk(&hdfs_bucket, &object_key, range).await }
+    }))
+}
+
+async fn get_chunk(
+    hdfs_bucket: &HdfsBucket,
+    object_key: &str,
+    range: ByteRange,
+) -> Result<Chunk, LumniError> {
+    let client = HdfsClient::new(hdfs_bucket.config());
+    let headers = HashMap::new();
+    // webhdfs takes the range as offset and length instead of a header
+    let offset = range.start();
+    let length = range.end().map(|end| end - offset + 1);
+    let mut url = client.operation_url(object_key, "OPEN");
+    url.push_str(&format!("&offset={}", offset));
+    if let Some(length) = length {
+        url.push_str(&format!("&length={}", length));
+    }
+    log::debug!(
+        "Getting range {} of object: {}",
+        range.to_header_value(),
+        object_key
+    );
+    let (mut body_bytes, mut status, response_headers) =
+        http_request_with_headers(&url, &headers, "GET").await?;
+    if matches!(status, 301 | 302 | 307) {
+        let location = response_headers.get("location").ok_or_else(|| {
+            LumniError::Internal(format!(
+                "Redirect without location for {}",
+                object_key
+            ))
+        })?;
+        (body_bytes, status) = http_get_request(location, &headers).await?;
+    }
+    check_status(status, object_key)?;
+    // a short read is the end of the file, an empty one ends the stream
+    let read = body_bytes.len() as u64;
+    let size = match length {
+        Some(length) if read < length => Some(offset + read),
+        Some(_) => None,
+        None => Some(offset + read),
+    };
+    Ok(Chunk::Partial(body_bytes, size))
+}
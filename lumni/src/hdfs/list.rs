@@ -0,0 +1,102 @@
+use std::collections::{HashMap, VecDeque};
+
+use super::bucket::HdfsBucket;
+use super::client::HdfsClient;
+use super::parse_http_response::{parse_file_status, parse_list_status};
+use crate::handlers::object_store::ObjectStoreTrait;
+use crate::http::requests::http_get_request;
+use crate::table::{FileObjectTable, Table};
+use crate::{FileObject, FileObjectFilter, LumniError};
+
+pub async fn list_files(
+    hdfs_bucket: &HdfsBucket,
+    prefix: Option<&str>,
+    recursive: bool,
+    max_keys: Option<u32>,
+    filter: &Option<FileObjectFilter>,
+    table: &mut FileObjectTable,
+) -> Result<(), LumniError> {
+    let client = HdfsClient::new(hdfs_bucket.config());
+    let max_files = max_keys.map_or(usize::MAX, |max| max as usize);
+    let prefix = prefix.unwrap_or("");
+
+    // a prefix without trailing slash can name a single file
+    if !prefix.is_empty() && !prefix.ends_with('/') {
+        let url = client.operation_url(prefix, "GETFILESTATUS");
+        let (body, status) = http_get_request(&url, &HashMap::new()).await?;
+        if status == 404 {
+            return Ok(());
+        }
+        check_status(status, prefix)?;
+        let file_status = parse_file_status(&body).map_err(parse_error)?;
+        if file_status.is_file() {
+            let file_object = FileObject::new(
+                prefix.to_string(),
+                file_status.length(),
+                file_status.modified(),
+                Some(file_status.tags()),
+            );
+            if filter.as_ref().is_none_or(|f| f.matches(&file_object)) {
+                table.add_file_objects(vec![file_object]).await?;
+            }
+            return Ok(());
+        }
+    }
+
+    let mut directory_stack = VecDeque::new();
+    directory_stack.push_back(match prefix {
+        "" => String::new(),
+        prefix => format!("{}/", prefix.trim_end_matches('/')),
+    });
+
+    while let Some(directory) = directory_stack.pop_front() {
+        let url = client.operation_url(&directory, "LISTSTATUS");
+        let (body, status) = http_get_request(&url, &HashMap::new()).await?;
+        check_status(status, hdfs_bucket.name())?;
+        let mut statuses = parse_list_status(&body).map_err(parse_error)?;
+        statuses.sort_by(|a, b| a.path_suffix().cmp(b.path_suffix()));
+
+        let mut temp_file_objects = Vec::new();
+        for file_status in statuses {
+            let is_dir = file_status.is_dir();
+            if !is_dir && !file_status.is_file() {
+                continue; // symlinks, as listed without resolving them
+            }
+            let file_object = file_status.into_file_object(&directory);
+            if is_dir {
+                if recursive {
+                    directory_stack.push_back(file_object.name().to_string());
+                }
+                if filter.is_none() {
+                    temp_file_objects.push(file_object);
+                }
+            } else if filter.as_ref().is_none_or(|f| f.matches(&file_object)) {
+                temp_file_objects.push(file_object);
+            }
+        }
+
+        let max_to_add = max_files.saturating_sub(table.len());
+        if !temp_file_objects.is_empty() && max_to_add > 0 {
+            let objects_to_add = temp_file_objects
+                .drain(..)
+                .take(max_to_add)
+                .collect::<Vec<_>>();
+            table.add_file_objects(objects_to_add).await?;
+        }
+        if table.len() >= max_files {
+            break;
+        }
+    }
+    Ok(())
+}
+
+pub fn check_status(status: u16, resource: &str) -> Result<(), LumniError> {
+    match status {
+        200..=299 => Ok(()),
+        _ => Err(LumniError::from_status(status, resource, "")),
+    }
+}
+
+pub fn parse_error(err: serde_json::Error) -> LumniError {
+    LumniError::Internal(format!("Failed to parse WebHDFS response: {}", err))
+}
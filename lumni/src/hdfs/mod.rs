@@ -0,0 +1,8 @@
+// expose to library via backend mod
+pub mod backend;
+mod bucket;
+mod client;
+mod config;
+mod get;
+mod list;
+mod parse_http_response;
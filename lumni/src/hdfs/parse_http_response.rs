@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::FileObject;
+
+#[derive(Debug, Deserialize)]
+struct ListStatusResponse {
+    #[serde(rename = "FileStatuses")]
+    file_statuses: FileStatuses,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileStatuses {
+    #[serde(rename = "FileStatus")]
+    file_status: Vec<FileStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GetFileStatusResponse {
+    #[serde(rename = "FileStatus")]
+    file_status: FileStatus,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileStatus {
+    // empty when the status is of the path itself
+    path_suffix: String,
+    #[serde(rename = "type")]
+    file_type: String, // FILE, DIRECTORY or SYMLINK
+    length: u64,
+    modification_time: u64, // milliseconds since epoch
+    owner: Option<String>,
+    group: Option<String>,
+    permission: Option<String>,
+    replication: Option<u16>,
+}
+
+impl FileStatus {
+    pub fn path_suffix(&self) -> &str {
+        &self.path_suffix
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.file_type == "DIRECTORY"
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.file_type == "FILE"
+    }
+
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    pub fn modified(&self) -> Option<u64> {
+        match self.modification_time {
+            0 => None,
+            time => Some(time / 1000),
+        }
+    }
+
+    pub fn tags(&self) -> HashMap<String, String> {
+        let mut tags = HashMap::new();
+        if let Some(owner) = &self.owner {
+            tags.insert("owner".to_string(), owner.clone());
+        }
+        if let Some(group) = &self.group {
+            tags.insert("group".to_string(), group.clone());
+        }
+        if let Some(permission) = &self.permission {
+            tags.insert("permission".to_string(), permission.clone());
+        }
+        if let Some(replication) = self.replication {
+            tags.insert("replication".to_string(), replication.to_string());
+        }
+        tags
+    }
+
+    // named by its key, directories end with a slash as common prefixes
+    // do on object stores
+    pub fn into_file_object(self, directory: &str) -> FileObject {
+        if self.path_suffix.is_empty() {
+            // a file lists itself
+            let name = directory.trim_end_matches('/').to_string();
+            let modified = self.modified();
+            FileObject::new(name, self.length, modified, Some(self.tags()))
+        } else if self.is_dir() {
+            let name = format!("{}{}/", directory, self.path_suffix);
+            FileObject::new(name, 0, None, None)
+        } else {
+            let name = format!("{}{}", directory, self.path_suffix);
+            let modified = self.modified();
+            FileObject::new(name, self.length, modified, Some(self.tags()))
+        }
+    }
+}
+
+pub fn parse_list_status(
+    body: &[u8],
+) -> Result<Vec<FileStatus>, serde_json::Error> {
+    let response: ListStatusResponse = serde_json::from_slice(body)?;
+    Ok(response.file_statuses.file_status)
+}
+
+pub fn parse_file_status(body: &[u8]) -> Result<FileStatus, serde_json::Error> {
+    let response: GetFileStatusResponse = serde_json::from_slice(body)?;
+    Ok(response.file_status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_list_status() {
+        let body = br#"{"FileStatuses":{"FileStatus":[
+            {"accessTime":0,"blockSize":0,"group":"supergroup","length":0,
+             "modificationTime":1320173277227,"owner":"webuser",
+             "pathSuffix":"bar","permission":"711","replication":0,
+             "type":"DIRECTORY"},
+            {"accessTime":1320171722771,"blockSize":33554432,
+             "group":"supergroup","length":24930,
+             "modificationTime":1320171722771,"owner":"webuser",
+             "pathSuffix":"a.patch","permission":"644","replication":1,
+             "type":"FILE"}
+        ]}}"#;
+        let statuses = parse_list_status(body).unwrap();
+        let objects: Vec<FileObject> = statuses
+            .into_iter()
+            .map(|status| status.into_file_object("user/"))
+            .collect();
+        assert_eq!(objects[0].name(), "user/bar/");
+        assert_eq!(objects[1].name(), "user/a.patch");
+        assert_eq!(objects[1].size(), 24930);
+        assert_eq!(objects[1].modified(), Some(1320171722));
+    }
+}
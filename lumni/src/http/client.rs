@@ -1,5 +1,4 @@
 use std::collections::HashMap;
-use std::convert::Infallible;
 use std::fmt;
 use std::str::FromStr;
 use std::string::FromUtf8Error;
@@ -11,16 +10,19 @@ use bytes::{Bytes, BytesMut};
 use futures::future::pending;
 use http_body_util::combinators::BoxBody;
 use http_body_util::{BodyExt, Empty, Full};
+use hyper::body::Incoming;
 use hyper::header::{HeaderName, HeaderValue};
-use hyper::{HeaderMap, Request, Uri};
-use hyper_tls::HttpsConnector;
-use hyper_util::client::legacy::connect::HttpConnector;
-use hyper_util::client::legacy::Client;
-use hyper_util::rt::TokioExecutor;
-use percent_encoding::{percent_encode, utf8_percent_encode, NON_ALPHANUMERIC};
+use hyper::{HeaderMap, Request, Response, Uri};
 use serde::de::DeserializeOwned;
 use tokio::sync::{mpsc, oneshot};
 
+use super::middleware::{HttpMiddleware, MiddlewareChain};
+use super::pool::{ConnectionPool, PoolStats, RequestBody};
+use super::retry::{parse_retry_after, RetryPolicy};
+use crate::base::throttle::Throttle;
+use crate::error::RETRYABLE_STATUS;
+use crate::utils::string_replace;
+
 #[derive(Debug)]
 pub struct HttpClientResponse {
     body: Option<Bytes>,
@@ -52,6 +54,20 @@ pub enum HttpClientError {
     Other(String),
 }
 
+impl HttpClientError {
+    // with the statuses HttpClient retries by default
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            HttpClientError::ConnectionError(_)
+            | HttpClientError::TimeoutError => true,
+            HttpClientError::HttpError(status_code, _) => {
+                RETRYABLE_STATUS.contains(status_code)
+            }
+            _ => false,
+        }
+    }
+}
+
 impl fmt::Display for HttpClientError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -68,6 +84,8 @@ impl fmt::Display for HttpClientError {
         }
     }
 }
+
+impl std::error::Error for HttpClientError {}
 pub trait HttpClientErrorHandler {
     fn handle_error(
         &self,
@@ -108,28 +126,23 @@ pub type HttpClientResult = Result<HttpClientResponse, HttpClientError>;
 
 #[derive(Clone)]
 pub struct HttpClient {
-    client: Client<
-        HttpsConnector<HttpConnector>,
-        BoxBody<bytes::Bytes, Infallible>,
-    >,
+    pool: ConnectionPool,
     timeout: Duration,
     error_handler: Option<Arc<dyn HttpClientErrorHandler + Send + Sync>>,
+    retry_policy: RetryPolicy,
+    middleware: MiddlewareChain,
+    throttle: Throttle,
 }
 
 impl HttpClient {
     pub fn new() -> Self {
-        let https = HttpsConnector::new();
-
-        let client: Client<
-            HttpsConnector<HttpConnector>,
-            BoxBody<Bytes, Infallible>,
-        > = Client::builder(TokioExecutor::new())
-            .build::<_, BoxBody<Bytes, Infallible>>(https);
-
         HttpClient {
-            client,
+            pool: ConnectionPool::shared(),
             timeout: Duration::from_secs(30),
             error_handler: None,
+            retry_policy: RetryPolicy::default(),
+            middleware: MiddlewareChain::default(),
+            throttle: Throttle::global(),
         }
     }
 
@@ -146,18 +159,46 @@ impl HttpClient {
         self
     }
 
-    async fn request(
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    // use a separate pool instead of the shared one, e.g. to limit the
+    // connections kept open to a single host
+    pub fn with_pool(mut self, pool: ConnectionPool) -> Self {
+        self.pool = pool;
+        self
+    }
+
+    // runs before the middleware of the pool on requests, and after it
+    // on responses
+    pub fn with_middleware(
+        mut self,
+        middleware: Arc<dyn HttpMiddleware>,
+    ) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    // replaces the global throttle, e.g. with Throttle::for_operation to
+    // limit one operation further
+    pub fn with_throttle(mut self, throttle: Throttle) -> Self {
+        self.throttle = throttle;
+        self
+    }
+
+    pub fn pool_stats(&self) -> PoolStats {
+        self.pool.stats()
+    }
+
+    async fn send_request(
         &self,
         method: &str,
-        url: &str,
+        uri: &Uri,
         headers: Option<&HashMap<String, String>>,
         body: Option<&Bytes>,
-        tx: Option<mpsc::Sender<Bytes>>,
-        mut cancel_rx: Option<oneshot::Receiver<()>>,
-    ) -> HttpClientResult {
-        let uri = Uri::from_str(url)
-            .map_err(|e| HttpClientError::Other(e.to_string()))?;
-
+    ) -> Result<Response<Incoming>, HttpClientError> {
         let mut req_builder = Request::builder().method(method).uri(uri);
 
         if let Some(headers_map) = headers {
@@ -171,15 +212,83 @@ impl HttpClient {
         }
         let request_body = create_request_body(body);
 
-        let request = req_builder
+        let mut request = req_builder
             .body(request_body)
             .expect("Failed to build the request");
+        self.middleware.on_request(&mut request);
         // Send the request and await the response, handling timeout as needed
-        let mut response = self
-            .client
+        let response = self
+            .pool
             .request(request)
             .await
             .map_err(|e| HttpClientError::ConnectionError(e.to_string()))?;
+        self.middleware.on_response(&response);
+        Ok(response)
+    }
+
+    async fn request(
+        &self,
+        method: &str,
+        url: &str,
+        headers: Option<&HashMap<String, String>>,
+        body: Option<&Bytes>,
+        tx: Option<mpsc::Sender<Bytes>>,
+        mut cancel_rx: Option<oneshot::Receiver<()>>,
+    ) -> HttpClientResult {
+        let uri = Uri::from_str(url)
+            .map_err(|e| HttpClientError::Other(e.to_string()))?;
+        // held until the body is read, retries count as the same connection
+        let _permit = self.throttle.acquire().await;
+        if let Some(body) = body {
+            self.throttle.consume(body.len()).await;
+        }
+
+        // retries happen before any of the body is read, so a streamed
+        // response is never sent twice
+        let mut attempt = 1;
+        let mut response = loop {
+            let result = self.send_request(method, &uri, headers, body).await;
+            let (retryable, retry_after) = match &result {
+                Ok(response) => (
+                    self.retry_policy
+                        .is_retryable_status(response.status().as_u16()),
+                    parse_retry_after(response.headers()),
+                ),
+                Err(e) => (self.retry_policy.is_retryable_error(e), None),
+            };
+            let delay = if retryable {
+                self.retry_policy.retry_delay(attempt, retry_after)
+            } else {
+                None
+            };
+            let Some(delay) = delay else {
+                break result?;
+            };
+            let reason = match &result {
+                Ok(response) => format!("status {}", response.status()),
+                Err(e) => e.to_string(),
+            };
+            log::warn!(
+                "{} {} attempt {} failed ({}), retrying in {:?}",
+                method,
+                url,
+                attempt,
+                reason,
+                delay
+            );
+            drop(result);
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {},
+                _ = async {
+                    if let Some(rx) = &mut cancel_rx {
+                        rx.await.ok();
+                    } else {
+                        pending::<()>().await;
+                    }
+                } => return Err(HttpClientError::RequestCancelled),
+            }
+            attempt += 1;
+        };
 
         if !response.status().is_success() {
             let canonical_reason = response
@@ -217,6 +326,7 @@ impl HttpClient {
                         match next {
                             Some(Ok(frame)) => {
                                 if let Ok(chunk) = frame.into_data() {
+                                    self.throttle.consume(chunk.len()).await;
                                     if let Err(e) = tx.send(chunk).await {
                                         return Err(HttpClientError::Other(e.to_string()));
                                     }
@@ -245,6 +355,7 @@ impl HttpClient {
                 // get headers for debugging
                 let frame = next.map_err(|e| anyhow!(e))?;
                 if let Some(chunk) = frame.data_ref() {
+                    self.throttle.consume(chunk.len()).await;
                     body_bytes.extend_from_slice(chunk);
                 }
             }
@@ -269,6 +380,23 @@ impl HttpClient {
         self.request("GET", url, headers, None, tx, cancel_rx).await
     }
 
+    pub async fn head(
+        &self,
+        url: &str,
+        headers: Option<&HashMap<String, String>>,
+    ) -> HttpClientResult {
+        self.request("HEAD", url, headers, None, None, None).await
+    }
+
+    pub async fn put(
+        &self,
+        url: &str,
+        headers: Option<&HashMap<String, String>>,
+        body: Option<&Bytes>,
+    ) -> HttpClientResult {
+        self.request("PUT", url, headers, body, None, None).await
+    }
+
     pub async fn post(
         &self,
         url: &str,
@@ -289,31 +417,11 @@ impl HttpClient {
         input: &str,
         exclude: Option<&[u8]>,
     ) -> String {
-        let mut result = String::new();
-        let set = NON_ALPHANUMERIC;
-
-        if let Some(exclusions) = exclude {
-            // percent-encode each byte while skipping excluded characters
-            for byte in input.bytes() {
-                if exclusions.contains(&byte) {
-                    result.push(byte as char);
-                } else {
-                    result.push_str(
-                        &percent_encode(&[byte][..], &set).to_string(),
-                    );
-                }
-            }
-        } else {
-            // use the standard percent encoding for the entire input
-            result.push_str(&utf8_percent_encode(input, &set).to_string());
-        }
-        result
+        string_replace::percent_encode_with_exclusion(input, exclude)
     }
 }
 
-fn create_request_body(
-    body_content: Option<&Bytes>,
-) -> BoxBody<Bytes, Infallible> {
+pub(crate) fn create_request_body(body_content: Option<&Bytes>) -> RequestBody {
     match body_content {
         Some(content) => {
             let full_body: Full<Bytes> = Full::new(content.clone());
@@ -0,0 +1,101 @@
+use std::fmt;
+use std::sync::Arc;
+
+use hyper::body::Incoming;
+use hyper::header::{HeaderName, HeaderValue};
+use hyper::{HeaderMap, Request, Response};
+
+use super::pool::RequestBody;
+
+// hooks around every request, e.g. to add headers or tracing IDs, or to
+// collect metrics. with retries, the hooks run for every attempt
+pub trait HttpMiddleware: Send + Sync {
+    fn on_request(&self, _request: &mut Request<RequestBody>) {}
+
+    fn on_response(&self, _response: &Response<Incoming>) {}
+}
+
+// middleware in the order it is added. requests pass it first to last,
+// responses last to first
+#[derive(Clone, Default)]
+pub struct MiddlewareChain {
+    middleware: Vec<Arc<dyn HttpMiddleware>>,
+}
+
+impl MiddlewareChain {
+    pub fn push(&mut self, middleware: Arc<dyn HttpMiddleware>) {
+        self.middleware.push(middleware);
+    }
+
+    pub fn on_request(&self, request: &mut Request<RequestBody>) {
+        for middleware in &self.middleware {
+            middleware.on_request(request);
+        }
+    }
+
+    pub fn on_response(&self, response: &Response<Incoming>) {
+        for middleware in self.middleware.iter().rev() {
+            middleware.on_response(response);
+        }
+    }
+}
+
+impl fmt::Debug for MiddlewareChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MiddlewareChain({})", self.middleware.len())
+    }
+}
+
+// adds headers to requests that do not have them yet, e.g. a User-Agent
+#[derive(Debug, Clone, Default)]
+pub struct DefaultHeaders {
+    headers: HeaderMap,
+}
+
+impl DefaultHeaders {
+    pub fn new() -> Self {
+        DefaultHeaders::default()
+    }
+
+    // invalid names or values are skipped
+    pub fn set_header(mut self, name: &str, value: &str) -> Self {
+        if let (Ok(name), Ok(value)) =
+            (HeaderName::try_from(name), HeaderValue::try_from(value))
+        {
+            self.headers.insert(name, value);
+        } else {
+            log::warn!("Invalid default header: {}", name);
+        }
+        self
+    }
+}
+
+impl HttpMiddleware for DefaultHeaders {
+    fn on_request(&self, request: &mut Request<RequestBody>) {
+        for (name, value) in &self.headers {
+            if !request.headers().contains_key(name) {
+                request.headers_mut().insert(name, value.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::client::create_request_body;
+
+    #[test]
+    fn test_default_headers() {
+        let defaults = DefaultHeaders::new()
+            .set_header("user-agent", "lumni")
+            .set_header("x-trace-id", "abc");
+        let mut request = Request::builder()
+            .header("x-trace-id", "def")
+            .body(create_request_body(None))
+            .unwrap();
+        defaults.on_request(&mut request);
+        assert_eq!(request.headers()["user-agent"], "lumni");
+        assert_eq!(request.headers()["x-trace-id"], "def");
+    }
+}
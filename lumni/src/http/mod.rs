@@ -1,8 +1,20 @@
 #[cfg(feature = "http_client")]
 pub mod client;
+#[cfg(feature = "http_client")]
+pub mod middleware;
+#[cfg(feature = "http_client")]
+pub mod pool;
+#[cfg(feature = "http_client")]
+pub mod proxy;
+#[cfg(feature = "http_client")]
+pub mod retry;
 //#[cfg(feature = "http_client")]
 //pub use client::{HttpClient, HttpClientError, HttpClientErrorHandler, HttpClientResponse, HttpClientResult};
 
+// requests go through the native client, or through fetch on wasm32
+#[cfg(all(not(target_arch = "wasm32"), not(feature = "http_client")))]
+compile_error!("lumni requires the http_client feature outside of wasm32");
+
 #[cfg(not(target_arch = "wasm32"))]
 pub mod requests;
 
@@ -0,0 +1,247 @@
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::combinators::BoxBody;
+use hyper::body::Incoming;
+use hyper::header::{HeaderValue, PROXY_AUTHORIZATION};
+use hyper::{Request, Response, Uri};
+use hyper_tls::HttpsConnector;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::client::legacy::{Client, Error as ClientError};
+use hyper_util::rt::{TokioExecutor, TokioTimer};
+use tower_service::Service;
+
+use super::middleware::{HttpMiddleware, MiddlewareChain};
+use super::proxy::{ProxyConfig, ProxyConnector};
+
+const DEFAULT_MAX_IDLE_PER_HOST: usize = 16;
+const DEFAULT_IDLE_TIMEOUT_SECONDS: u64 = 90;
+const DEFAULT_TCP_KEEPALIVE_SECONDS: u64 = 60;
+
+pub type RequestBody = BoxBody<Bytes, Infallible>;
+type Connector = HttpsConnector<ProxyConnector>;
+type PooledClient = Client<CountingConnector, RequestBody>;
+
+static SHARED_POOL: OnceLock<ConnectionPool> = OnceLock::new();
+
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    max_idle_per_host: usize, // 0 disables reuse of connections
+    idle_timeout: Option<Duration>, // None keeps idle connections open
+    tcp_keepalive: Option<Duration>,
+    http2: bool, // offer HTTP/2 to https servers, falls back to HTTP/1.1
+    proxy: ProxyConfig,
+    middleware: MiddlewareChain, // runs for every request of the pool
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        PoolConfig {
+            max_idle_per_host: DEFAULT_MAX_IDLE_PER_HOST,
+            idle_timeout: Some(Duration::from_secs(
+                DEFAULT_IDLE_TIMEOUT_SECONDS,
+            )),
+            tcp_keepalive: Some(Duration::from_secs(
+                DEFAULT_TCP_KEEPALIVE_SECONDS,
+            )),
+            http2: true,
+            proxy: ProxyConfig::from_env(),
+            middleware: MiddlewareChain::default(),
+        }
+    }
+}
+
+impl PoolConfig {
+    pub fn new() -> Self {
+        PoolConfig::default()
+    }
+
+    pub fn set_max_idle_per_host(mut self, max_idle_per_host: usize) -> Self {
+        self.max_idle_per_host = max_idle_per_host;
+        self
+    }
+
+    pub fn set_idle_timeout(mut self, idle_timeout: Option<Duration>) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    pub fn set_tcp_keepalive(
+        mut self,
+        tcp_keepalive: Option<Duration>,
+    ) -> Self {
+        self.tcp_keepalive = tcp_keepalive;
+        self
+    }
+
+    pub fn set_http2(mut self, http2: bool) -> Self {
+        self.http2 = http2;
+        self
+    }
+
+    // replaces the proxies from the environment
+    pub fn set_proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    // middleware of the shared pool also applies to the http::requests
+    // functions, e.g. the requests of s3
+    pub fn add_middleware(
+        mut self,
+        middleware: Arc<dyn HttpMiddleware>,
+    ) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    pub fn max_idle_per_host(&self) -> usize {
+        self.max_idle_per_host
+    }
+
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout
+    }
+}
+
+// counters of a pool since it was created, for debugging
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    pub requests: u64,
+    pub connections: u64, // new TCP (and TLS) connections
+}
+
+impl PoolStats {
+    // requests sent over a connection that was already open
+    pub fn reused(&self) -> u64 {
+        self.requests.saturating_sub(self.connections)
+    }
+}
+
+#[derive(Debug, Default)]
+struct PoolCounters {
+    requests: AtomicU64,
+    connections: AtomicU64,
+}
+
+// keeps connections open between requests, so repeated calls to the same
+// host (e.g. pages of an S3 listing, or LLM completions) skip the TCP and
+// TLS handshakes. clones share the same connections
+#[derive(Clone)]
+pub struct ConnectionPool {
+    client: PooledClient,
+    counters: Arc<PoolCounters>,
+    config: PoolConfig,
+    proxy: Arc<ProxyConfig>,
+}
+
+impl ConnectionPool {
+    pub fn new(config: PoolConfig) -> Self {
+        let mut http = HttpConnector::new();
+        http.enforce_http(false);
+        http.set_keepalive(config.tcp_keepalive);
+        let proxy = Arc::new(config.proxy.clone());
+        let mut tls = native_tls::TlsConnector::builder();
+        if config.http2 {
+            // the server picks HTTP/2 during the TLS handshake (ALPN)
+            tls.request_alpns(&["h2", "http/1.1"]);
+        }
+        let tls = tls.build().expect("Failed to create TLS connector");
+        let counters = Arc::new(PoolCounters::default());
+        let connector = CountingConnector {
+            inner: HttpsConnector::from((
+                ProxyConnector::new(http, Arc::clone(&proxy)),
+                tls.into(),
+            )),
+            counters: Arc::clone(&counters),
+        };
+        let client = Client::builder(TokioExecutor::new())
+            .pool_timer(TokioTimer::new())
+            .pool_idle_timeout(config.idle_timeout)
+            .pool_max_idle_per_host(config.max_idle_per_host)
+            .build(connector);
+        ConnectionPool {
+            client,
+            counters,
+            config,
+            proxy,
+        }
+    }
+
+    // pool used by HttpClient::new() and the http::requests functions
+    pub fn shared() -> Self {
+        SHARED_POOL
+            .get_or_init(|| ConnectionPool::new(PoolConfig::default()))
+            .clone()
+    }
+
+    // configure the shared pool, before it is first used. returns false
+    // if it already exists
+    pub fn init_shared(config: PoolConfig) -> bool {
+        SHARED_POOL.set(ConnectionPool::new(config)).is_ok()
+    }
+
+    pub fn config(&self) -> &PoolConfig {
+        &self.config
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            requests: self.counters.requests.load(Ordering::Relaxed),
+            connections: self.counters.connections.load(Ordering::Relaxed),
+        }
+    }
+
+    pub async fn request(
+        &self,
+        mut request: Request<RequestBody>,
+    ) -> Result<Response<Incoming>, ClientError> {
+        self.counters.requests.fetch_add(1, Ordering::Relaxed);
+        if request.uri().scheme_str() == Some("http") {
+            // https requests authenticate when the tunnel is opened
+            let authorization = self
+                .proxy
+                .for_uri(request.uri())
+                .and_then(|proxy| proxy.authorization())
+                .and_then(|value| HeaderValue::from_str(value).ok());
+            if let Some(authorization) = authorization {
+                request
+                    .headers_mut()
+                    .insert(PROXY_AUTHORIZATION, authorization);
+            }
+        }
+        self.config.middleware.on_request(&mut request);
+        let response = self.client.request(request).await?;
+        self.config.middleware.on_response(&response);
+        Ok(response)
+    }
+}
+
+// counts the connections the pool opens
+#[derive(Clone)]
+struct CountingConnector {
+    inner: Connector,
+    counters: Arc<PoolCounters>,
+}
+
+impl Service<Uri> for CountingConnector {
+    type Response = <Connector as Service<Uri>>::Response;
+    type Error = <Connector as Service<Uri>>::Error;
+    type Future = <Connector as Service<Uri>>::Future;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        self.counters.connections.fetch_add(1, Ordering::Relaxed);
+        self.inner.call(uri)
+    }
+}
@@ -2,16 +2,18 @@ use std::collections::HashMap;
 use std::str::FromStr;
 
 use bytes::{Bytes, BytesMut};
-use http_body_util::{BodyExt, Empty};
+use http_body_util::BodyExt;
 use hyper::body::Incoming;
 use hyper::header::{HeaderName, HeaderValue};
 use hyper::{Request, Response, Uri};
-use hyper_tls::HttpsConnector;
-use hyper_util::client::legacy::Client;
-use hyper_util::rt::TokioExecutor;
 
-type HttpResult = Result<(Bytes, u16, HashMap<String, String>), anyhow::Error>;
-type HttpResultWithoutHeaders = Result<(Bytes, u16), anyhow::Error>;
+use super::client::create_request_body;
+use super::pool::ConnectionPool;
+use crate::base::throttle::Throttle;
+use crate::LumniError;
+
+type HttpResult = Result<(Bytes, u16, HashMap<String, String>), LumniError>;
+type HttpResultWithoutHeaders = Result<(Bytes, u16), LumniError>;
 
 pub async fn http_get_request(
     url: &str,
@@ -28,15 +30,14 @@ pub async fn http_request_with_headers(
     headers: &HashMap<String, String>,
     method: &str,
 ) -> HttpResult {
-    let https = HttpsConnector::new();
-    let client: Client<_, Empty<Bytes>> =
-        Client::builder(TokioExecutor::new()).build::<_, Empty<Bytes>>(https);
-
-    let uri = url.parse::<Uri>()?;
+    let uri = url
+        .parse::<Uri>()
+        .map_err(|e| LumniError::Config(format!("{}: {}", url, e)))?;
     let mut request = Request::builder()
         .method(method)
         .uri(uri)
-        .body(Empty::<Bytes>::new())?;
+        .body(create_request_body(None))
+        .map_err(|e| LumniError::Internal(e.to_string()))?;
 
     for (key, value) in headers.iter() {
         if let (Ok(header_name), Ok(header_value)) =
@@ -46,7 +47,12 @@ pub async fn http_request_with_headers(
         }
     }
 
-    let mut response = client.request(request).await?;
+    let throttle = Throttle::global();
+    let _permit = throttle.acquire().await;
+    let mut response = ConnectionPool::shared()
+        .request(request)
+        .await
+        .map_err(network_error)?;
 
     let status = response.status().as_u16();
     let headers_map = parse_response_headers(&response);
@@ -58,8 +64,9 @@ pub async fn http_request_with_headers(
     let mut body_bytes = BytesMut::new();
 
     while let Some(next) = response.frame().await {
-        let frame = next?;
+        let frame = next.map_err(network_error)?;
         if let Some(chunk) = frame.data_ref() {
+            throttle.consume(chunk.len()).await;
             body_bytes.extend_from_slice(chunk);
         }
     }
@@ -80,6 +87,72 @@ pub async fn http_request_with_headers(
     return Ok((body_bytes.into(), status, headers_map));
 }
 
+pub async fn http_post_request(
+    url: &str,
+    headers: &HashMap<String, String>,
+    body: &[u8],
+) -> HttpResultWithoutHeaders {
+    let (body, status, _) = http_request_with_body(
+        url,
+        headers,
+        "POST",
+        Bytes::copy_from_slice(body),
+    )
+    .await?;
+    Ok((body, status))
+}
+
+pub async fn http_request_with_body(
+    url: &str,
+    headers: &HashMap<String, String>,
+    method: &str,
+    body: Bytes,
+) -> HttpResult {
+    let uri = url
+        .parse::<Uri>()
+        .map_err(|e| LumniError::Config(format!("{}: {}", url, e)))?;
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .body(create_request_body(Some(&body)))
+        .map_err(|e| LumniError::Internal(e.to_string()))?;
+
+    for (key, value) in headers.iter() {
+        if let (Ok(header_name), Ok(header_value)) =
+            (HeaderName::from_str(key), HeaderValue::from_str(value))
+        {
+            request.headers_mut().append(header_name, header_value);
+        }
+    }
+
+    let throttle = Throttle::global();
+    let _permit = throttle.acquire().await;
+    throttle.consume(body.len()).await;
+    let mut response = ConnectionPool::shared()
+        .request(request)
+        .await
+        .map_err(network_error)?;
+    let status = response.status().as_u16();
+    let headers_map = parse_response_headers(&response);
+
+    // body is returned for any status, as error details are in the body
+    let mut body_bytes = BytesMut::new();
+    while let Some(next) = response.frame().await {
+        let frame = next.map_err(network_error)?;
+        if let Some(chunk) = frame.data_ref() {
+            throttle.consume(chunk.len()).await;
+            body_bytes.extend_from_slice(chunk);
+        }
+    }
+    Ok((body_bytes.into(), status, headers_map))
+}
+
+// the request failed before a complete response was received, e.g. the
+// connection was refused or dropped
+fn network_error(error: impl std::fmt::Display) -> LumniError {
+    LumniError::Network(error.to_string())
+}
+
 fn parse_response_headers(
     response: &Response<Incoming>,
 ) -> HashMap<String, String> {
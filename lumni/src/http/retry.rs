@@ -0,0 +1,182 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use hyper::header::RETRY_AFTER;
+use hyper::HeaderMap;
+
+use super::client::HttpClientError;
+use crate::error::RETRYABLE_STATUS;
+use crate::utils::time::{rfc2822_to_epoch, system_time_in_seconds};
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+const DEFAULT_BACKOFF_BASE_MILLIS: u64 = 500;
+const DEFAULT_MAX_BACKOFF_SECONDS: u64 = 20;
+
+// when and how often HttpClient retries a failed request. the delay
+// before a retry doubles after every attempt, with full jitter so clients
+// that failed at the same time do not retry at the same time
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32, // including the first attempt, 1 disables retries
+    backoff_base: Duration, // upper bound of the first delay
+    max_backoff: Duration,
+    retry_on_status: Vec<u16>,
+    honor_retry_after: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            backoff_base: Duration::from_millis(DEFAULT_BACKOFF_BASE_MILLIS),
+            max_backoff: Duration::from_secs(DEFAULT_MAX_BACKOFF_SECONDS),
+            retry_on_status: RETRYABLE_STATUS.to_vec(),
+            honor_retry_after: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        RetryPolicy::default()
+    }
+
+    // a single attempt, for requests that are not safe to repeat
+    pub fn none() -> Self {
+        RetryPolicy::default().set_max_attempts(1)
+    }
+
+    pub fn set_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    pub fn set_backoff_base(mut self, backoff_base: Duration) -> Self {
+        self.backoff_base = backoff_base;
+        self
+    }
+
+    pub fn set_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    pub fn set_retry_on_status(mut self, retry_on_status: Vec<u16>) -> Self {
+        self.retry_on_status = retry_on_status;
+        self
+    }
+
+    pub fn set_honor_retry_after(mut self, honor_retry_after: bool) -> Self {
+        self.honor_retry_after = honor_retry_after;
+        self
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    pub fn is_retryable_status(&self, status_code: u16) -> bool {
+        self.retry_on_status.contains(&status_code)
+    }
+
+    // errors from before a response is received
+    pub fn is_retryable_error(&self, error: &HttpClientError) -> bool {
+        match error {
+            HttpClientError::ConnectionError(_)
+            | HttpClientError::TimeoutError => true,
+            HttpClientError::HttpError(status_code, _) => {
+                self.is_retryable_status(*status_code)
+            }
+            _ => false,
+        }
+    }
+
+    // delay before the next attempt, None if the attempts are used up.
+    // a Retry-After from the server is used as is, but if it asks to wait
+    // longer than max_backoff the request is not retried
+    pub fn retry_delay(
+        &self,
+        attempt: u32,
+        retry_after: Option<Duration>,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        if let Some(retry_after) =
+            retry_after.filter(|_| self.honor_retry_after)
+        {
+            return (retry_after <= self.max_backoff).then_some(retry_after);
+        }
+        let exponent = attempt.saturating_sub(1).min(31);
+        let backoff = self
+            .backoff_base
+            .saturating_mul(1 << exponent)
+            .min(self.max_backoff);
+        Some(backoff.mul_f64(jitter()))
+    }
+}
+
+// Retry-After is either a number of seconds or an HTTP date
+pub fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let retry_at = rfc2822_to_epoch(value).ok()?;
+    Some(Duration::from_secs(
+        retry_at.saturating_sub(system_time_in_seconds()),
+    ))
+}
+
+// random factor in [0, 1), seeded per call by the std hasher so no
+// random number crate is needed
+fn jitter() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::header::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn test_retry_delay() {
+        let policy = RetryPolicy::new()
+            .set_max_attempts(4)
+            .set_backoff_base(Duration::from_millis(100))
+            .set_max_backoff(Duration::from_millis(300));
+        for (attempt, max_delay) in [(1, 100), (2, 200), (3, 300)] {
+            let delay = policy.retry_delay(attempt, None).unwrap();
+            assert!(delay <= Duration::from_millis(max_delay));
+        }
+        assert_eq!(policy.retry_delay(4, None), None);
+
+        let retry_after = Some(Duration::from_millis(250));
+        assert_eq!(policy.retry_delay(1, retry_after), retry_after);
+        assert_eq!(policy.retry_delay(1, Some(Duration::from_secs(1))), None);
+
+        assert!(policy.is_retryable_status(503));
+        assert!(!policy.is_retryable_error(&HttpClientError::HttpError(
+            404,
+            "Not Found".to_string()
+        )));
+        assert_eq!(RetryPolicy::none().retry_delay(1, None), None);
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_retry_after(&headers), None);
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(120)));
+        // a date in the past means retry right away
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(parse_retry_after(&headers), Some(Duration::ZERO));
+    }
+}
@@ -1,29 +1,80 @@
 pub(crate) mod apps;
+pub(crate) mod azure;
 pub(crate) mod base;
 pub(crate) mod default;
 pub(crate) mod error;
+pub(crate) mod gcs;
 pub(crate) mod handlers;
+pub(crate) mod hdfs;
 pub(crate) mod http;
 pub(crate) mod localfs;
 pub(crate) mod s3;
+#[cfg(feature = "sftp")]
+pub(crate) mod sftp;
 pub(crate) mod table;
 pub(crate) mod utils;
 
 // note - please not rely on these components to remain exposed as part of the API
 // see external module for parts that are meant to be part of the stable API
-pub use api::error::{ApplicationError, LumniError};
+pub use api::error::ApplicationError;
 pub use base::callback_wrapper::{
     BinaryCallbackWrapper, CallbackItem, CallbackWrapper,
 };
+pub use base::checksum::{Checksum, ChecksumAlgorithm};
+#[cfg(feature = "compression")]
+pub use base::compression::{
+    AutoDecoder, CompressReader, Compression, StreamDecoder, StreamEncoder,
+    DECOMPRESS_SETTING,
+};
 pub use base::config::EnvironmentConfig;
-pub use base::file_object::FileObject;
+pub use base::downloader::{
+    DownloadResult, Downloader, DOWNLOAD_MANIFEST_NAME, DOWNLOAD_OPERATION,
+};
+#[cfg(feature = "http_client")]
+pub use base::encryption::EncryptionHandler;
+pub use base::file_object::{FileObject, ObjectVersion};
 pub use base::filters::FileObjectFilter;
-// LakestreamError should be phased out in favor of LumniError
-pub use error::LakestreamError;
-pub use handlers::ObjectStoreHandler;
+pub use base::listing_cache::{ListingCache, LISTING_CACHE_TTL_SETTING};
+pub use base::manifest::{
+    IntegrityManifest, ManifestHasher, ManifestObject, ManifestVerification,
+    SignatureStatus, MANIFEST_OPERATION, MANIFEST_SIGNING_KEY_SETTING,
+};
+pub use base::object_metadata::ObjectMetadata;
+pub use base::progress::{
+    ProgressReporter, ProgressTableCallback, ProgressTracker, ProgressUpdate,
+};
+#[cfg(feature = "http_client")]
+pub use base::telemetry::{
+    record_usage, Telemetry, DATA_DICTIONARY, TELEMETRY_ENV,
+};
+#[cfg(feature = "http_client")]
+pub use base::throttle::{
+    Throttle, ThrottleConfig, ThrottlePermit, BANDWIDTH_LIMIT_SETTING,
+    MAX_CONNECTIONS_SETTING, REQUEST_RATE_LIMIT_SETTING, THROTTLE_SETTINGS,
+};
+#[cfg(feature = "http_client")]
+pub use base::token_cache::{CachedToken, TokenCache, TOKEN_CACHE_ENV};
+pub use base::watch::{WatchCallback, WatchEvent, WatchEventKind};
+pub use error::LumniError;
+#[deprecated(note = "use LumniError")]
+pub type LakestreamError = LumniError;
+pub use handlers::{
+    AccessOperation, AccessReport, AccessStatus, ByteRange, Conditions,
+    ConfirmCallback, DeleteResult, DiffStrategy, GrepOptions,
+    ObjectStoreHandler, ObjectStream, UploadOptions, GREP_OPERATION,
+};
+pub use s3::{
+    RestoreRequest, RestoreStatus, RestoreTier, ServerSideEncryption,
+    SignedRequest,
+};
+#[cfg(feature = "parquet")]
+pub use table::ParquetWriter;
 pub use table::{
-    FileObjectTable, ObjectStoreTable, Table, TableCallback, TableColumn,
-    TableColumnValue, TableRow,
+    ColumnSchema, ColumnType, DiffChange, DiffTable, FileObjectTable,
+    GrepTable, ObjectMetadataTable, ObjectStoreTable, OperationTable,
+    PlannedOperation, RowTemplate, Table, TableCallback, TableColumn,
+    TableColumnValue, TableExportOptions, TableRow, TableSchema, TableStream,
+    DEFAULT_STREAM_BATCH_SIZE,
 };
 pub use utils::{ParsedUri, UriScheme};
 
@@ -31,14 +82,41 @@ pub use utils::{ParsedUri, UriScheme};
 pub mod external {
     pub use crate::apps::api;
     #[cfg(feature = "http_client")]
+    pub use crate::base::device_code::{
+        DeviceCodeFlow, DeviceCodePrompt, DeviceCodePromptCallback,
+    };
+    pub use crate::base::head_batch::{
+        HeadBatch, DEFAULT_HEAD_CONCURRENCY, HEAD_CONCURRENCY_SETTING,
+    };
+    pub use crate::base::memory::{
+        MemoryBudget, DEFAULT_MEMORY_BUDGET, MEMORY_BUDGET_SETTING,
+    };
+    #[cfg(feature = "http_client")]
     pub use crate::handlers::HttpHandler;
+    // backends of other crates, for uri schemes lumni does not support
+    pub use crate::handlers::{
+        register_backend, ObjectStoreBackendFactory, ObjectStoreTrait,
+    };
     #[cfg(feature = "http_client")]
     pub use crate::http::client::{
         HttpClient, HttpClientError, HttpClientErrorHandler,
         HttpClientResponse, HttpClientResult,
     };
     #[cfg(feature = "http_client")]
-    pub use crate::s3::{AWSCredentials, AWSRequestBuilder};
+    pub use crate::http::middleware::{DefaultHeaders, HttpMiddleware};
+    #[cfg(feature = "http_client")]
+    pub use crate::http::pool::{ConnectionPool, PoolConfig, PoolStats};
+    #[cfg(feature = "http_client")]
+    pub use crate::http::proxy::{Proxy, ProxyConfig};
+    #[cfg(feature = "http_client")]
+    pub use crate::http::retry::RetryPolicy;
+    #[cfg(feature = "http_client")]
+    pub use crate::s3::{AWSCredentialProvider, AWSCredentialSource};
+    pub use crate::s3::{AWSCredentials, AWSRequestBuilder, SigningAlgorithm};
+    pub use crate::table::{
+        OptionalStringColumn, StringColumn, Table, TableCallback, TableColumn,
+        TableColumnValue, TableExportOptions, Uint64Column,
+    };
 }
 pub use default::*;
 pub use external::*;
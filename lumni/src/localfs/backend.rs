@@ -2,13 +2,13 @@ use async_trait::async_trait;
 
 pub use super::bucket::LocalFsBucket;
 use crate::handlers::object_store::ObjectStoreBackend;
-use crate::{EnvironmentConfig, LakestreamError, ObjectStoreTable};
+use crate::{EnvironmentConfig, LumniError, ObjectStoreTable};
 
 pub struct LocalFsBackend;
 
 #[async_trait(?Send)]
 impl ObjectStoreBackend for LocalFsBackend {
-    fn new(_config: EnvironmentConfig) -> Result<Self, LakestreamError> {
+    fn new(_config: EnvironmentConfig) -> Result<Self, LumniError> {
         Ok(Self)
     }
 
@@ -16,7 +16,7 @@ impl ObjectStoreBackend for LocalFsBackend {
         _config: EnvironmentConfig,
         _max_files: Option<u32>,
         _table: &mut ObjectStoreTable,
-    ) -> Result<(), LakestreamError> {
+    ) -> Result<(), LumniError> {
         Ok(())
     }
 }
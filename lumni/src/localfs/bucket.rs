@@ -2,15 +2,28 @@ use std::collections::HashMap;
 use std::fs::{self, ReadDir};
 use std::io;
 use std::path::Path;
+#[cfg(feature = "watch")]
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
 
 use async_trait::async_trait;
 
+use super::delete::delete_object;
 use super::get::get_object;
+use super::get_stream::get_object_stream;
 use super::list::list_files;
+use super::put::put_object;
+#[cfg(feature = "watch")]
+use super::watch::watch_directory;
 use crate::base::config::EnvironmentConfig;
-use crate::handlers::object_store::ObjectStoreTrait;
+use crate::base::object_metadata::ObjectMetadata;
+#[cfg(feature = "watch")]
+use crate::base::watch::WatchCallback;
+use crate::handlers::object_store::{
+    ByteRange, ObjectStoreTrait, ObjectStream,
+};
 use crate::table::FileObjectTable;
-use crate::{FileObjectFilter, LakestreamError};
+use crate::{ChecksumAlgorithm, FileObjectFilter, HeadBatch, LumniError};
 
 pub struct LocalFileSystem;
 
@@ -27,7 +40,6 @@ impl FileSystem for LocalFileSystem {
 #[derive(Debug, Clone)]
 pub struct LocalFsBucket {
     name: String,
-    #[allow(dead_code)]
     config: EnvironmentConfig,
 }
 
@@ -41,6 +53,66 @@ impl LocalFsBucket {
             config,
         })
     }
+
+    // a bucket is a directory, parent directories are created as needed
+    pub fn create_bucket(&self) -> Result<(), LumniError> {
+        let path = Path::new(&self.name);
+        if path.exists() {
+            return Err(LumniError::Config(format!(
+                "{} already exists",
+                path.display()
+            )));
+        }
+        fs::create_dir_all(path).map_err(|err| {
+            LumniError::Internal(format!(
+                "Failed to create directory {}: {}",
+                path.display(),
+                err
+            ))
+        })
+    }
+
+    // the directory must be empty, unless recursive is set
+    pub fn delete_bucket(&self, recursive: bool) -> Result<(), LumniError> {
+        let path = Path::new(&self.name);
+        let result = if recursive {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_dir(path)
+        };
+        result.map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => {
+                LumniError::NotFound(path.display().to_string())
+            }
+            io::ErrorKind::DirectoryNotEmpty => {
+                LumniError::Config(format!("{} is not empty", path.display()))
+            }
+            _ => LumniError::Internal(format!(
+                "Failed to remove directory {}: {}",
+                path.display(),
+                err
+            )),
+        })
+    }
+
+    pub fn head_bucket(&self) -> Option<HashMap<String, String>> {
+        Path::new(&self.name).is_dir().then(HashMap::new)
+    }
+
+    #[cfg(feature = "watch")]
+    pub async fn watch(
+        &self,
+        prefix: Option<&str>,
+        recursive: bool,
+        filter: &Option<FileObjectFilter>,
+        callback: Arc<dyn WatchCallback>,
+    ) -> Result<(), LumniError> {
+        let path = match prefix {
+            Some(prefix) => Path::new(&self.name).join(prefix),
+            None => Path::new(&self.name).to_path_buf(),
+        };
+        watch_directory(&path, recursive, filter, callback).await
+    }
 }
 
 #[async_trait(?Send)]
@@ -61,7 +133,7 @@ impl ObjectStoreTrait for LocalFsBucket {
         max_keys: Option<u32>,
         filter: &Option<FileObjectFilter>,
         table: &mut FileObjectTable,
-    ) -> Result<(), LakestreamError> {
+    ) -> Result<(), LumniError> {
         let path = match prefix {
             Some(prefix) => Path::new(&self.name).join(prefix),
             None => Path::new(&self.name).to_path_buf(),
@@ -69,12 +141,28 @@ impl ObjectStoreTrait for LocalFsBucket {
 
         // to be considered a Bucket, path must be a directory
         if !path.is_dir() {
-            return Err(LakestreamError::NoBucketInUri(
+            return Err(LumniError::NoBucketInUri(
                 path.to_string_lossy().to_string(),
             ));
         }
-        list_files(&path, selected_columns, max_keys, recursive, filter, table)
-            .await;
+        // hashing reads every file, only done when the column is selected
+        let checksum_algorithm = match selected_columns {
+            Some(columns) if columns.contains(&"checksum") => {
+                Some(ChecksumAlgorithm::from_config(&self.config)?)
+            }
+            _ => None,
+        };
+        list_files(
+            &path,
+            selected_columns,
+            max_keys,
+            recursive,
+            filter,
+            checksum_algorithm,
+            &HeadBatch::from_config(&self.config)?,
+            table,
+        )
+        .await;
         Ok(())
     }
 
@@ -82,17 +170,62 @@ impl ObjectStoreTrait for LocalFsBucket {
         &self,
         key: &str,
         data: &mut Vec<u8>,
-    ) -> Result<(), LakestreamError> {
+    ) -> Result<(), LumniError> {
         let path = Path::new(&self.name);
         get_object(path, key, data).await
     }
 
+    async fn get_object_stream(
+        &self,
+        key: &str,
+        range: Option<ByteRange>,
+    ) -> Result<ObjectStream, LumniError> {
+        let path = Path::new(&self.name);
+        get_object_stream(path, key, range).await
+    }
+
     async fn head_object(
         &self,
         _key: &str,
-    ) -> Result<(u16, HashMap<String, String>), LakestreamError> {
-        return Err(LakestreamError::InternalError(
-            "Not implemented".to_string(),
-        ));
+    ) -> Result<(u16, HashMap<String, String>), LumniError> {
+        return Err(LumniError::Internal("Not implemented".to_string()));
+    }
+
+    async fn get_object_metadata(
+        &self,
+        key: &str,
+    ) -> Result<ObjectMetadata, LumniError> {
+        let object_path = Path::new(&self.name).join(key);
+        let metadata = match fs::metadata(&object_path) {
+            Ok(metadata) if metadata.is_file() => metadata,
+            _ => {
+                return Err(LumniError::NotFound(format!(
+                    "Object not found for key: {}",
+                    key
+                )))
+            }
+        };
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs());
+        Ok(ObjectMetadata::new(key)
+            .set_size(Some(metadata.len()))
+            .set_modified(modified))
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), LumniError> {
+        let path = Path::new(&self.name);
+        delete_object(path, key).await
+    }
+
+    async fn put_object(
+        &self,
+        key: &str,
+        data: &[u8],
+    ) -> Result<(), LumniError> {
+        let path = Path::new(&self.name);
+        put_object(path, key, data).await
     }
 }
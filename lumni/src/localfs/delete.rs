@@ -0,0 +1,22 @@
+// localfs/delete.rs
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::LumniError;
+
+pub async fn delete_object(path: &Path, key: &str) -> Result<(), LumniError> {
+    let object_path = path.join(key);
+
+    fs::remove_file(&object_path).map_err(|err| match err.kind() {
+        io::ErrorKind::NotFound => {
+            LumniError::NotFound(format!("Object not found for key: {}", key))
+        }
+        _ => LumniError::Internal(format!(
+            "Failed to delete file {}: {}",
+            object_path.display(),
+            err
+        )),
+    })
+}
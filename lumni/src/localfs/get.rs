@@ -4,18 +4,18 @@ use std::fs;
 use std::io::Read;
 use std::path::Path;
 
-use crate::LakestreamError;
+use crate::LumniError;
 
 pub async fn get_object(
     path: &Path,
     key: &str,
     data: &mut Vec<u8>,
-) -> Result<(), LakestreamError> {
+) -> Result<(), LumniError> {
     let object_path = path.join(key);
 
     if object_path.is_file() {
         let mut file = fs::File::open(&object_path).map_err(|err| {
-            LakestreamError::InternalError(format!(
+            LumniError::Internal(format!(
                 "Failed to open file {}: {}",
                 object_path.display(),
                 err
@@ -23,7 +23,7 @@ pub async fn get_object(
         })?;
 
         file.read_to_end(data).map_err(|err| {
-            LakestreamError::InternalError(format!(
+            LumniError::Internal(format!(
                 "Failed to read file {}: {}",
                 object_path.display(),
                 err
@@ -32,7 +32,7 @@ pub async fn get_object(
 
         Ok(())
     } else {
-        Err(LakestreamError::NotFound(format!(
+        Err(LumniError::NotFound(format!(
             "Object not found for key: {}",
             key
         )))
@@ -0,0 +1,97 @@
+// localfs/get_stream.rs
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use bytes::Bytes;
+use futures::stream;
+
+use crate::handlers::object_store::{ByteRange, ObjectStream};
+use crate::LumniError;
+
+const READ_BUFFER_SIZE: u64 = 64 * 1024;
+
+pub async fn get_object_stream(
+    path: &Path,
+    key: &str,
+    range: Option<ByteRange>,
+) -> Result<ObjectStream, LumniError> {
+    let object_path = path.join(key);
+    if !object_path.is_file() {
+        return Err(LumniError::NotFound(format!(
+            "Object not found for key: {}",
+            key
+        )));
+    }
+
+    let mut file = File::open(&object_path).map_err(|err| {
+        LumniError::Internal(format!(
+            "Failed to open file {}: {}",
+            object_path.display(),
+            err
+        ))
+    })?;
+    let start = range.map_or(0, |r| r.start());
+    file.seek(SeekFrom::Start(start))?;
+    // bytes left to read, or None to read until the end of the file
+    let remaining = range.and_then(|r| r.end()).map(|end| end + 1 - start);
+
+    let stream = stream::unfold(
+        (file, remaining, false),
+        |(mut file, remaining, done)| async move {
+            if done || remaining == Some(0) {
+                return None;
+            }
+            let size = remaining.map_or(READ_BUFFER_SIZE, |remaining| {
+                remaining.min(READ_BUFFER_SIZE)
+            });
+            let mut buffer = Vec::with_capacity(size as usize);
+            match (&mut file).take(size).read_to_end(&mut buffer) {
+                Ok(0) => None,
+                Ok(n) => {
+                    let remaining = remaining.map(|r| r - n as u64);
+                    Some((Ok(Bytes::from(buffer)), (file, remaining, false)))
+                }
+                Err(err) => {
+                    Some((Err(LumniError::from(err)), (file, remaining, true)))
+                }
+            }
+        },
+    );
+    Ok(Box::pin(stream))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use futures::executor::block_on;
+    use futures::StreamExt;
+
+    use super::*;
+
+    #[test]
+    fn test_get_object_stream_range() {
+        let dir = std::env::temp_dir().join("lumni_test_get_stream");
+        fs::create_dir_all(&dir).unwrap();
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        fs::write(dir.join("object"), &data).unwrap();
+
+        let range = ByteRange::new(100, Some(150_099)).unwrap();
+        let chunks: Vec<Bytes> = block_on(async {
+            get_object_stream(&dir, "object", Some(range))
+                .await
+                .unwrap()
+                .map(|chunk| chunk.unwrap())
+                .collect()
+                .await
+        });
+        let streamed: Vec<u8> = chunks.concat();
+        assert!(chunks.len() > 1);
+        assert_eq!(streamed, &data[100..150_100]);
+        assert_eq!(range.slice(&data), &data[100..150_100]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,54 +1,88 @@
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use tracing::info_span;
 
 use super::bucket::{FileSystem, LocalFileSystem};
 use crate::table::{FileObjectTable, TableColumnValue};
-use crate::{FileObject, FileObjectFilter};
+use crate::{
+    Checksum, ChecksumAlgorithm, FileObject, FileObjectFilter, HeadBatch,
+    LumniError, ObjectMetadata,
+};
 
+#[allow(clippy::too_many_arguments)]
 pub async fn list_files(
     path: &Path,
     selected_columns: &Option<Vec<&str>>,
     max_keys: Option<u32>,
     recursive: bool,
     filter: &Option<FileObjectFilter>,
+    checksum_algorithm: Option<ChecksumAlgorithm>,
+    head_batch: &HeadBatch,
     table: &mut FileObjectTable,
 ) {
-    list_files_next(path, selected_columns, max_keys, recursive, filter, table)
-        .await;
+    list_files_next(
+        path,
+        selected_columns,
+        max_keys,
+        recursive,
+        filter,
+        checksum_algorithm,
+        head_batch,
+        table,
+    )
+    .await;
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn list_files_next(
     path: &Path,
     selected_columns: &Option<Vec<&str>>,
     max_keys: Option<u32>,
     recursive: bool,
     filter: &Option<FileObjectFilter>,
+    checksum_algorithm: Option<ChecksumAlgorithm>,
+    head_batch: &HeadBatch,
     table: &mut FileObjectTable,
 ) {
     let fs = &LocalFileSystem;
     let mut directory_stack = vec![path.to_owned()];
     let mut object_count = 0usize;
+    let reached =
+        |count: usize| max_keys.is_some_and(|max| count >= max as usize);
 
-    println!("Selected columns: {:?}", selected_columns);
+    log::debug!("Selected columns: {:?}", selected_columns);
     while let Some(current_path) = directory_stack.pop() {
         let mut temp_rows = Vec::new();
+        let mut entries =
+            fs.read_dir(&current_path).ok().map(Iterator::flatten);
 
-        if let Ok(entries) = fs.read_dir(&current_path) {
-            for entry in entries.flatten() {
-                if max_keys.map_or(false, |max| object_count >= max as usize) {
-                    break; // Stop processing more entries
-                }
+        while let Some(entries) = entries.as_mut() {
+            // entry.metadata() does not follow symlinks, their targets are
+            // read in a batch once the directory is listed, or once the
+            // links could reach max_keys
+            let mut links = Vec::new();
+            let mut listed = false;
 
+            let page = info_span!("list_page").entered();
+            while !reached(object_count + links.len()) {
+                let Some(entry) = entries.next() else {
+                    listed = true;
+                    break;
+                };
                 let metadata = match entry.metadata() {
                     Ok(md) => md,
                     Err(_) => continue,
                 };
 
                 if metadata.is_file() {
-                    if let Some(row_data) =
-                        handle_file(&entry, filter, selected_columns)
-                    {
+                    if let Some(row_data) = handle_file(
+                        &entry,
+                        filter,
+                        selected_columns,
+                        checksum_algorithm,
+                    ) {
                         temp_rows.push(row_data);
                         object_count += 1;
                     }
@@ -66,15 +100,35 @@ async fn list_files_next(
                     if recursive {
                         directory_stack.push(entry.path());
                     }
+                } else if metadata.file_type().is_symlink() {
+                    links.push(entry.path());
                 }
             }
+            drop(page);
+            if !links.is_empty() {
+                let rows = handle_links(
+                    links,
+                    filter,
+                    selected_columns,
+                    checksum_algorithm,
+                    head_batch,
+                )
+                .await;
+                object_count += rows.len();
+                temp_rows.extend(rows);
+            }
+            // broken links and links to a directory are not listed, the
+            // rest of the directory makes up for them
+            if listed || reached(object_count) {
+                break;
+            }
         }
         if !temp_rows.is_empty() {
             let _ = table.add_rows(temp_rows).await;
         }
 
         // Exit the loop early if the max_keys limit has been reached
-        if max_keys.map_or(false, |max| object_count >= max as usize) {
+        if reached(object_count) {
             break;
         }
     }
@@ -118,6 +172,16 @@ fn handle_directory(
         );
     }
 
+    if selected_columns
+        .as_ref()
+        .is_some_and(|cols| cols.contains(&"checksum"))
+    {
+        dir_row_data.insert(
+            "checksum".to_string(),
+            TableColumnValue::OptionalStringColumn(None),
+        );
+    }
+
     if dir_row_data.is_empty() {
         None
     } else {
@@ -129,26 +193,91 @@ fn handle_file(
     entry: &fs::DirEntry,
     filter: &Option<FileObjectFilter>,
     selected_columns: &Option<Vec<&str>>,
+    checksum_algorithm: Option<ChecksumAlgorithm>,
 ) -> Option<HashMap<String, TableColumnValue>> {
     let metadata = entry.metadata().ok()?;
 
     let file_name = entry.path().to_string_lossy().to_string();
-    let file_size = metadata.len();
-    let modified = metadata.modified().ok().map(|mtime| {
-        mtime
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|duration| duration.as_secs())
-            .unwrap_or(0)
-    });
-
+    let file_object = FileObject::new(
+        file_name,
+        metadata.len(),
+        modified_epoch(&metadata),
+        None,
+    );
     // Check if the file_object satisfies the filter conditions
-    let file_object =
-        FileObject::new(file_name.clone(), file_size, modified, None);
     if let Some(ref filter) = filter {
         if !filter.matches(&file_object) {
             return None;
         }
     }
+    file_row(
+        &entry.path(),
+        &file_object,
+        selected_columns,
+        checksum_algorithm,
+    )
+}
+
+// symlinks to files are listed with the size and modified time of the
+// target. Links to directories are not followed, as they may loop, and
+// are skipped like broken links
+async fn handle_links(
+    links: Vec<PathBuf>,
+    filter: &Option<FileObjectFilter>,
+    selected_columns: &Option<Vec<&str>>,
+    checksum_algorithm: Option<ChecksumAlgorithm>,
+    head_batch: &HeadBatch,
+) -> Vec<HashMap<String, TableColumnValue>> {
+    let file_objects = links
+        .iter()
+        .map(|link| {
+            FileObject::new(link.to_string_lossy().to_string(), 0, None, None)
+        })
+        .collect();
+    let file_objects = head_batch
+        .complete(file_objects, |name| async move { head_link(&name) })
+        .await;
+    file_objects
+        .iter()
+        .zip(links)
+        .filter(|(file_object, _)| !HeadBatch::needs_head(file_object))
+        .filter(|(file_object, _)| {
+            filter
+                .as_ref()
+                .is_none_or(|filter| filter.matches(file_object))
+        })
+        .filter_map(|(file_object, link)| {
+            file_row(&link, file_object, selected_columns, checksum_algorithm)
+        })
+        .collect()
+}
+
+fn head_link(name: &str) -> Result<ObjectMetadata, LumniError> {
+    match fs::metadata(name) {
+        Ok(metadata) if metadata.is_file() => Ok(ObjectMetadata::new(name)
+            .set_size(Some(metadata.len()))
+            .set_modified(modified_epoch(&metadata))),
+        _ => Err(LumniError::NotFound(name.to_string())),
+    }
+}
+
+fn modified_epoch(metadata: &fs::Metadata) -> Option<u64> {
+    metadata.modified().ok().map(|mtime| {
+        mtime
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0)
+    })
+}
+
+fn file_row(
+    path: &Path,
+    file_object: &FileObject,
+    selected_columns: &Option<Vec<&str>>,
+    checksum_algorithm: Option<ChecksumAlgorithm>,
+) -> Option<HashMap<String, TableColumnValue>> {
+    let file_size = file_object.size();
+    let modified = file_object.modified();
     let mut row_data = HashMap::new();
     if selected_columns
         .as_ref()
@@ -156,7 +285,7 @@ fn handle_file(
     {
         row_data.insert(
             "name".to_string(),
-            TableColumnValue::StringColumn(file_name),
+            TableColumnValue::StringColumn(file_object.name().to_string()),
         );
     }
     if selected_columns
@@ -179,9 +308,109 @@ fn handle_file(
         );
     }
 
+    if let Some(algorithm) = checksum_algorithm {
+        // an unreadable file is listed without a checksum
+        let checksum = fs::File::open(path)
+            .and_then(|mut file| Checksum::from_reader(algorithm, &mut file))
+            .map_err(|e| {
+                log::warn!("Checksum of {} failed: {}", path.display(), e)
+            })
+            .ok();
+        row_data.insert(
+            "checksum".to_string(),
+            TableColumnValue::OptionalStringColumn(
+                checksum.map(|checksum| checksum.to_string()),
+            ),
+        );
+    }
+
     if row_data.is_empty() {
         None
     } else {
         Some(row_data)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+    use crate::Table;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_list_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("target.txt"), "0123456789").unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        symlink(dir.path().join("target.txt"), dir.path().join("link.txt"))
+            .unwrap();
+        symlink(dir.path().join("sub"), dir.path().join("link_dir")).unwrap();
+        symlink(dir.path().join("gone"), dir.path().join("broken")).unwrap();
+
+        let list = |filter: Option<FileObjectFilter>| {
+            let columns = Some(vec!["name", "size"]);
+            let mut table = FileObjectTable::new(&columns, None);
+            block_on(list_files(
+                dir.path(),
+                &columns,
+                None,
+                false,
+                &filter,
+                None,
+                &HeadBatch::new().set_concurrency(2),
+                &mut table,
+            ));
+            let mut rows: Vec<String> = table
+                .to_csv(&Default::default())
+                .unwrap()
+                .lines()
+                .skip(1)
+                .map(|line| {
+                    line.trim_start_matches(&*dir.path().to_string_lossy())
+                        .to_string()
+                })
+                .collect();
+            rows.sort();
+            rows
+        };
+        assert_eq!(list(None), vec!["/link.txt,10", "/target.txt,10", "sub,0"]);
+        let filter = FileObjectFilter::new(None, Some("+5b"), None).unwrap();
+        assert_eq!(list(Some(filter)), vec!["/link.txt,10", "/target.txt,10"]);
+    }
+
+    #[test]
+    fn test_list_max_keys_skips_broken_links() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            fs::write(dir.path().join(name), "data").unwrap();
+        }
+        for index in 0..10 {
+            symlink(
+                dir.path().join("gone"),
+                dir.path().join(format!("broken{}", index)),
+            )
+            .unwrap();
+        }
+
+        // broken links are not counted, the limit is filled with files
+        let columns = Some(vec!["name"]);
+        let mut table = FileObjectTable::new(&columns, None);
+        block_on(list_files(
+            dir.path(),
+            &columns,
+            Some(2),
+            false,
+            &None,
+            None,
+            &HeadBatch::new().set_concurrency(2),
+            &mut table,
+        ));
+        assert_eq!(table.len(), 2);
+    }
+}
@@ -1,5 +1,10 @@
 // expose to library via backend mod
 pub mod backend;
 mod bucket;
+mod delete;
 mod get;
+mod get_stream;
 mod list;
+mod put;
+#[cfg(feature = "watch")]
+mod watch;
@@ -0,0 +1,32 @@
+// localfs/put.rs
+
+use std::fs;
+use std::path::Path;
+
+use crate::LumniError;
+
+pub async fn put_object(
+    path: &Path,
+    key: &str,
+    data: &[u8],
+) -> Result<(), LumniError> {
+    let object_path = path.join(key);
+
+    if let Some(parent) = object_path.parent() {
+        fs::create_dir_all(parent).map_err(|err| {
+            LumniError::Internal(format!(
+                "Failed to create directory {}: {}",
+                parent.display(),
+                err
+            ))
+        })?;
+    }
+
+    fs::write(&object_path, data).map_err(|err| {
+        LumniError::Internal(format!(
+            "Failed to write file {}: {}",
+            object_path.display(),
+            err
+        ))
+    })
+}
@@ -0,0 +1,163 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use futures::channel::mpsc;
+use futures::StreamExt;
+use notify::event::{EventKind, ModifyKind, RenameMode};
+use notify::{Event, RecursiveMode, Watcher};
+
+use crate::base::watch::{WatchCallback, WatchEvent, WatchEventKind};
+use crate::{FileObject, FileObjectFilter, LumniError};
+
+// events of the platform watcher (inotify, FSEvents, ...) as file events,
+// until the callback returns false
+pub async fn watch_directory(
+    path: &Path,
+    recursive: bool,
+    filter: &Option<FileObjectFilter>,
+    callback: Arc<dyn WatchCallback>,
+) -> Result<(), LumniError> {
+    if !path.is_dir() {
+        return Err(LumniError::NotFound(format!(
+            "{} is not a directory",
+            path.display()
+        )));
+    }
+    let (sender, mut receiver) = mpsc::unbounded();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = sender.unbounded_send(event);
+    })
+    .map_err(watch_error)?;
+    let mode = match recursive {
+        true => RecursiveMode::Recursive,
+        false => RecursiveMode::NonRecursive,
+    };
+    watcher.watch(path, mode).map_err(watch_error)?;
+
+    while let Some(event) = receiver.next().await {
+        for (kind, file_object) in file_events(event.map_err(watch_error)?) {
+            let matches = filter.as_ref().is_none_or(|filter| match kind {
+                WatchEventKind::Deleted => {
+                    filter.matches_name(file_object.name())
+                }
+                _ => filter.matches(&file_object),
+            });
+            if matches
+                && !callback.on_event(&WatchEvent::new(kind, file_object))
+            {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+// changes to directories themselves, and to metadata only, are left out
+fn file_events(event: Event) -> Vec<(WatchEventKind, FileObject)> {
+    let kinds = match event.kind {
+        EventKind::Create(_) => vec![WatchEventKind::Created],
+        EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Any) => {
+            vec![WatchEventKind::Modified]
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+            vec![WatchEventKind::Deleted]
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+            vec![WatchEventKind::Created]
+        }
+        // paths are the old and the new name
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+            vec![WatchEventKind::Deleted, WatchEventKind::Created]
+        }
+        EventKind::Modify(ModifyKind::Name(_)) => event
+            .paths
+            .iter()
+            .map(|path| match path.exists() {
+                true => WatchEventKind::Created,
+                false => WatchEventKind::Deleted,
+            })
+            .collect(),
+        EventKind::Remove(_) => vec![WatchEventKind::Deleted],
+        _ => return Vec::new(),
+    };
+    event
+        .paths
+        .iter()
+        .enumerate()
+        .filter_map(|(index, path)| {
+            let kind = *kinds.get(index).or(kinds.last())?;
+            let name = path.to_string_lossy().to_string();
+            if kind == WatchEventKind::Deleted {
+                return Some((kind, FileObject::new(name, 0, None, None)));
+            }
+            let metadata = path.metadata().ok().filter(|m| m.is_file())?;
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|mtime| mtime.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_secs());
+            Some((kind, FileObject::new(name, metadata.len(), modified, None)))
+        })
+        .collect()
+}
+
+fn watch_error(err: notify::Error) -> LumniError {
+    LumniError::Internal(format!("Watch failed: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use std::{fs, thread};
+
+    use super::*;
+
+    struct Collector(Mutex<Vec<(WatchEventKind, String)>>);
+
+    impl WatchCallback for Collector {
+        fn on_event(&self, event: &WatchEvent) -> bool {
+            let name = event.file_object().name();
+            let mut events = self.0.lock().unwrap();
+            events.push((event.kind(), name.to_string()));
+            !(event.kind() == WatchEventKind::Deleted
+                && name.ends_with("a.txt"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watch_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        let collector = Arc::new(Collector(Mutex::new(Vec::new())));
+        let filter = FileObjectFilter::new(Some(".*\\.txt"), None, None).ok();
+
+        let writer = {
+            let file = file.clone();
+            let other = dir.path().join("b.log");
+            // after the watch has started
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(200));
+                fs::write(&other, "skipped").unwrap();
+                fs::write(&file, "aaaa").unwrap();
+                // created events of files that are gone are left out
+                thread::sleep(Duration::from_millis(200));
+                fs::remove_file(&file).unwrap();
+            })
+        };
+        watch_directory(dir.path(), false, &filter, collector.clone())
+            .await
+            .unwrap();
+        writer.join().unwrap();
+
+        let events = collector.0.lock().unwrap();
+        let name = file.to_string_lossy().to_string();
+        assert_eq!(
+            events.first(),
+            Some(&(WatchEventKind::Created, name.clone()))
+        );
+        assert_eq!(events.last(), Some(&(WatchEventKind::Deleted, name)));
+        assert!(events.iter().all(|(_, name)| name.ends_with("a.txt")));
+    }
+}
@@ -1,8 +1,13 @@
 use std::env;
 
+#[cfg(feature = "http_client")]
+use super::credential_provider::AWSCredentialProvider;
+use crate::utils::time::system_time_in_seconds;
 use crate::{ApplicationError, LumniError};
 
 pub const AWS_DEFAULT_REGION: &str = "us-east-1";
+// temporary credentials are refreshed this long before they expire
+pub(super) const EXPIRY_MARGIN_SECONDS: u64 = 300;
 
 #[derive(Clone)]
 pub struct AWSCredentials {
@@ -10,6 +15,7 @@ pub struct AWSCredentials {
     secret_key: String,
     region: String,
     session_token: Option<String>,
+    expiration: Option<u64>, // epoch seconds, for temporary credentials
 }
 
 impl AWSCredentials {
@@ -24,9 +30,47 @@ impl AWSCredentials {
             secret_key,
             region,
             session_token,
+            expiration: None,
         }
     }
 
+    pub fn set_expiration(mut self, expiration: Option<u64>) -> Self {
+        self.expiration = expiration;
+        self
+    }
+
+    // resolved through the default provider chain: environment, shared
+    // config files, web identity, ECS task role and EC2 instance metadata.
+    // temporary credentials are cached and refreshed before they expire
+    #[cfg(feature = "http_client")]
+    pub async fn from_chain() -> Result<AWSCredentials, LumniError> {
+        AWSCredentialProvider::shared().credentials().await
+    }
+
+    // the other sources of the chain are fetched over http
+    #[cfg(not(feature = "http_client"))]
+    pub async fn from_chain() -> Result<AWSCredentials, LumniError> {
+        AWSCredentials::from_env()
+    }
+
+    // new credentials of the chain if these are about to expire, with the
+    // same region to sign with. None if these can still be used
+    pub async fn refresh(&self) -> Result<Option<AWSCredentials>, LumniError> {
+        if !self.expires_within(EXPIRY_MARGIN_SECONDS) {
+            return Ok(None);
+        }
+        let credentials = AWSCredentials::from_chain().await?;
+        Ok(Some(
+            AWSCredentials::new(
+                credentials.access_key,
+                credentials.secret_key,
+                self.region.clone(),
+                credentials.session_token,
+            )
+            .set_expiration(credentials.expiration),
+        ))
+    }
+
     pub fn from_env() -> Result<AWSCredentials, LumniError> {
         let access_key = env::var("AWS_ACCESS_KEY_ID").map_err(|_| {
             LumniError::Application(
@@ -51,12 +95,12 @@ impl AWSCredentials {
         });
         let session_token = env::var("AWS_SESSION_TOKEN").ok();
 
-        Ok(AWSCredentials {
+        Ok(AWSCredentials::new(
             access_key,
             secret_key,
             region,
             session_token,
-        })
+        ))
     }
 
     pub fn access_key(&self) -> &str {
@@ -74,4 +118,15 @@ impl AWSCredentials {
     pub fn region(&self) -> &str {
         &self.region
     }
+
+    pub fn expiration(&self) -> Option<u64> {
+        self.expiration
+    }
+
+    // true if the credentials expire in less than the given seconds
+    pub fn expires_within(&self, seconds: u64) -> bool {
+        self.expiration.is_some_and(|expiration| {
+            expiration <= system_time_in_seconds() + seconds
+        })
+    }
 }
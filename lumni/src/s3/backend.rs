@@ -5,13 +5,13 @@ pub use super::bucket::S3Bucket;
 pub use super::config::validate_config;
 pub use super::list::list_buckets;
 use crate::handlers::object_store::ObjectStoreBackend;
-use crate::{EnvironmentConfig, LakestreamError, ObjectStoreTable};
+use crate::{EnvironmentConfig, LumniError, ObjectStoreTable};
 
 pub struct S3Backend;
 
 #[async_trait(?Send)]
 impl ObjectStoreBackend for S3Backend {
-    fn new(_config: EnvironmentConfig) -> Result<Self, LakestreamError> {
+    fn new(_config: EnvironmentConfig) -> Result<Self, LumniError> {
         Ok(Self)
     }
 
@@ -19,14 +19,14 @@ impl ObjectStoreBackend for S3Backend {
         config: EnvironmentConfig,
         max_files: Option<u32>,
         table: &mut ObjectStoreTable,
-    ) -> Result<(), LakestreamError> {
+    ) -> Result<(), LumniError> {
         let config_map = config.get_settings().clone();
         let mut config_instance = EnvironmentConfig::new(config_map);
 
         if let Err(e) = validate_config(&mut config_instance) {
             // Handle the error, e.g., log the error and/or return early with an appropriate error value
             error!("Error validating the config: {}", e);
-            return Err(LakestreamError::ConfigError(
+            return Err(LumniError::Config(
                 "Invalid configuration".to_string(),
             ));
         }
@@ -1,15 +1,31 @@
 use std::collections::HashMap;
+use std::io::Read;
+use std::sync::Arc;
 
 use async_trait::async_trait;
 
-use super::get::get_object;
+use super::bucket_ops::{create_bucket, delete_bucket, head_bucket};
+use super::delete::{delete_object, delete_objects};
+use super::get::{get_object, get_object_if, get_object_stream};
 use super::head::head_object;
 use super::list::list_files;
+use super::notifications::watch_notifications;
+use super::presign::presign_object;
+use super::put::{put_object, put_object_multipart};
+use super::restore::{restore_object, RestoreRequest, RestoreStatus};
+use super::sign::sign_object_request;
+use super::tagging::{get_object_tags, put_object_tags};
+use super::versions::{list_object_versions, list_objects_as_of};
+use super::SignedRequest;
 use crate::base::config::EnvironmentConfig;
-use crate::handlers::object_store::ObjectStoreTrait;
-use crate::s3::config::validate_config;
+use crate::base::object_metadata::ObjectMetadata;
+use crate::base::watch::WatchCallback;
+use crate::handlers::object_store::{
+    ByteRange, DeleteResult, ObjectStoreTrait, ObjectStream, UploadOptions,
+};
+use crate::s3::config::{path_style, validate_bucket_name, validate_config};
 use crate::table::FileObjectTable;
-use crate::{FileObjectFilter, LakestreamError};
+use crate::{BinaryCallbackWrapper, Conditions, FileObjectFilter, LumniError};
 
 #[derive(Debug, Clone)]
 pub struct S3Bucket {
@@ -21,8 +37,9 @@ impl S3Bucket {
     pub fn new(
         name: &str,
         mut config: EnvironmentConfig,
-    ) -> Result<S3Bucket, LakestreamError> {
+    ) -> Result<S3Bucket, LumniError> {
         validate_config(&mut config)?;
+        validate_bucket_name(name, &config)?;
 
         Ok(S3Bucket {
             name: name.to_string(),
@@ -40,7 +57,104 @@ impl S3Bucket {
             self.config.get("S3_ENDPOINT_URL").map(String::as_str);
         let name = Some(self.name().to_string());
 
-        configure_bucket_url(region, endpoint_url, name.as_deref())
+        configure_bucket_url(
+            region,
+            endpoint_url,
+            name.as_deref(),
+            path_style(&self.config),
+        )
+    }
+
+    // URL to GET or PUT the object without credentials, S3 only as
+    // other stores use different mechanisms (e.g. SAS tokens)
+    pub async fn presign_url(
+        &self,
+        object_key: &str,
+        method: &str,
+        expires_in: u64,
+    ) -> Result<String, LumniError> {
+        presign_object(self, object_key, method, expires_in).await
+    }
+
+    // headers of a request as it would be signed at x_amz_date (or now),
+    // with the canonical request and string to sign, for debugging
+    pub async fn sign_request(
+        &self,
+        object_key: Option<&str>,
+        method: &str,
+        query_string: Option<&str>,
+        headers: &HashMap<String, String>,
+        x_amz_date: Option<&str>,
+    ) -> Result<SignedRequest, LumniError> {
+        sign_object_request(
+            self,
+            object_key,
+            method,
+            query_string,
+            headers,
+            x_amz_date,
+        )
+        .await
+    }
+
+    pub async fn create_bucket(&self) -> Result<(), LumniError> {
+        create_bucket(self).await
+    }
+
+    pub async fn delete_bucket(&self) -> Result<(), LumniError> {
+        delete_bucket(self).await
+    }
+
+    pub async fn head_bucket(
+        &self,
+    ) -> Result<Option<HashMap<String, String>>, LumniError> {
+        head_bucket(self).await
+    }
+
+    pub async fn list_object_versions(
+        &self,
+        prefix: Option<&str>,
+        recursive: bool,
+        max_keys: Option<u32>,
+        filter: &Option<FileObjectFilter>,
+        table: &mut FileObjectTable,
+    ) -> Result<(), LumniError> {
+        list_object_versions(self, prefix, recursive, max_keys, filter, table)
+            .await
+    }
+
+    pub async fn list_objects_as_of(
+        &self,
+        prefix: Option<&str>,
+        recursive: bool,
+        max_keys: Option<u32>,
+        filter: &Option<FileObjectFilter>,
+        as_of: u64,
+        table: &mut FileObjectTable,
+    ) -> Result<(), LumniError> {
+        list_objects_as_of(
+            self, prefix, recursive, max_keys, filter, as_of, table,
+        )
+        .await
+    }
+
+    pub async fn restore_object(
+        &self,
+        key: &str,
+        request: &RestoreRequest,
+    ) -> Result<RestoreStatus, LumniError> {
+        restore_object(self, key, request).await
+    }
+
+    // events from the queue in S3_SQS_QUEUE_URL, see watch_notifications
+    pub async fn watch(
+        &self,
+        prefix: Option<&str>,
+        recursive: bool,
+        filter: &Option<FileObjectFilter>,
+        callback: Arc<dyn WatchCallback>,
+    ) -> Result<(), LumniError> {
+        watch_notifications(self, prefix, recursive, filter, callback).await
     }
 }
 
@@ -61,13 +175,13 @@ impl ObjectStoreTrait for S3Bucket {
         max_keys: Option<u32>,
         filter: &Option<FileObjectFilter>,
         table: &mut FileObjectTable,
-    ) -> Result<(), LakestreamError> {
+    ) -> Result<(), LumniError> {
         if let Some(prefix) = prefix {
             // prefix should not exist as a file object
             let (status_code, _response_headers) =
                 self.head_object(prefix.trim_end_matches("/")).await?;
             if status_code != 404 {
-                return Err(LakestreamError::NoBucketInUri(prefix.to_string()));
+                return Err(LumniError::NoBucketInUri(prefix.to_string()));
             }
         }
         list_files(
@@ -86,33 +200,120 @@ impl ObjectStoreTrait for S3Bucket {
         &self,
         key: &str,
         data: &mut Vec<u8>,
-    ) -> Result<(), LakestreamError> {
+    ) -> Result<(), LumniError> {
         get_object(self, key, data).await
     }
 
+    async fn get_object_if(
+        &self,
+        key: &str,
+        conditions: &Conditions,
+        data: &mut Vec<u8>,
+    ) -> Result<bool, LumniError> {
+        get_object_if(self, key, conditions, data).await
+    }
+
     async fn head_object(
         &self,
         key: &str,
-    ) -> Result<(u16, HashMap<String, String>), LakestreamError> {
+    ) -> Result<(u16, HashMap<String, String>), LumniError> {
         head_object(self, key).await
     }
+
+    async fn get_object_metadata(
+        &self,
+        key: &str,
+    ) -> Result<ObjectMetadata, LumniError> {
+        let (status, headers) = self.head_object(key).await?;
+        match status {
+            200..=299 => {}
+            _ => return Err(LumniError::from_status(status, key, "")),
+        }
+        // not all S3-compatible stores support tagging, or the caller
+        // may lack permission to read tags, which should not hide the rest
+        let tags = match get_object_tags(self, key).await {
+            Ok(tags) => tags,
+            Err(err) => {
+                log::warn!("Could not get tags of {}: {}", key, err);
+                HashMap::new()
+            }
+        };
+        Ok(ObjectMetadata::from_headers(key, &headers).set_tags(tags))
+    }
+
+    async fn set_object_tags(
+        &self,
+        key: &str,
+        tags: &HashMap<String, String>,
+    ) -> Result<(), LumniError> {
+        put_object_tags(self, key, tags).await
+    }
+
+    async fn get_object_stream(
+        &self,
+        key: &str,
+        range: Option<ByteRange>,
+    ) -> Result<ObjectStream, LumniError> {
+        get_object_stream(self, key, range).await
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), LumniError> {
+        delete_object(self, key).await
+    }
+
+    async fn delete_objects(
+        &self,
+        keys: &[String],
+    ) -> Result<DeleteResult, LumniError> {
+        delete_objects(self, keys).await
+    }
+
+    async fn put_object(
+        &self,
+        key: &str,
+        data: &[u8],
+    ) -> Result<(), LumniError> {
+        put_object(self, key, data, &UploadOptions::default()).await
+    }
+
+    async fn put_object_multipart(
+        &self,
+        key: &str,
+        source: &mut dyn Read,
+        options: &UploadOptions,
+        callback: Option<&BinaryCallbackWrapper>,
+    ) -> Result<u64, LumniError> {
+        put_object_multipart(self, key, source, options, callback).await
+    }
+}
+
+// multi-region access points are addressed by an alias like
+// "mfzwi23gnjvgw.mrap", requests to them are signed with SigV4A
+pub fn is_mrap_alias(name: &str) -> bool {
+    name.strip_suffix(".mrap")
+        .is_some_and(|alias| !alias.is_empty() && !alias.contains('.'))
 }
 
 pub fn configure_bucket_url(
     region: &str,
     endpoint_url: Option<&str>,
     bucket_name: Option<&str>,
+    path_style: bool,
 ) -> String {
-    match endpoint_url {
-        Some(url) => match bucket_name {
-            Some(name) => format!("{}/{}", url.trim_end_matches('/'), name),
-            None => url.to_owned(),
-        },
-        None => match bucket_name {
-            Some(name) => {
-                format!("https://{}.s3.{}.amazonaws.com", name, region)
-            }
-            None => format!("https://s3.{}.amazonaws.com", region),
+    let base_url = match endpoint_url {
+        Some(url) => url.trim_end_matches('/').to_owned(),
+        None => format!("https://s3.{}.amazonaws.com", region),
+    };
+    match bucket_name {
+        // only reachable with virtual-hosted addressing, in any region
+        Some(name) if endpoint_url.is_none() && is_mrap_alias(name) => {
+            format!("https://{}.accesspoint.s3-global.amazonaws.com", name)
+        }
+        None => base_url,
+        Some(name) if path_style => format!("{}/{}", base_url, name),
+        Some(name) => match base_url.split_once("://") {
+            Some((scheme, host)) => format!("{}://{}.{}", scheme, name, host),
+            None => format!("{}/{}", base_url, name),
         },
     }
 }
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use bytes::Bytes;
+
+use super::bucket::S3Bucket;
+use super::client_headers::Headers;
+use super::list::create_s3_client;
+use super::request_handler::http_with_redirect_handling;
+use crate::handlers::object_store::ObjectStoreTrait;
+use crate::http::requests::http_request_with_body;
+use crate::LumniError;
+
+pub async fn create_bucket(s3_bucket: &S3Bucket) -> Result<(), LumniError> {
+    let mut s3_client =
+        create_s3_client(s3_bucket.config(), Some(s3_bucket.name())).await?;
+    let headers = s3_client.generate_bucket_headers("PUT")?;
+
+    // us-east-1 is the default location and may not be given explicitly,
+    // "auto" is how R2 picks the location itself
+    let region = s3_client.region().to_string();
+    let body = match region.as_str() {
+        "us-east-1" | "auto" => Bytes::new(),
+        _ => Bytes::from(format!(
+            "<CreateBucketConfiguration \
+             xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\">\
+             <LocationConstraint>{}</LocationConstraint>\
+             </CreateBucketConfiguration>",
+            region
+        )),
+    };
+
+    log::info!("Creating bucket: {} in {}", s3_bucket.name(), region);
+    let (body, status, _) =
+        http_request_with_body(&s3_client.url(), &headers, "PUT", body).await?;
+    match status {
+        200..=299 => Ok(()),
+        403 => Err(LumniError::AccessDenied(s3_bucket.name().to_string())),
+        409 => Err(LumniError::Config(format!(
+            "Bucket {} already exists",
+            s3_bucket.name()
+        ))),
+        _ => Err(unexpected_status(status, s3_bucket.name(), &body)),
+    }
+}
+
+// the bucket must be empty
+pub async fn delete_bucket(s3_bucket: &S3Bucket) -> Result<(), LumniError> {
+    let mut s3_client =
+        create_s3_client(s3_bucket.config(), Some(s3_bucket.name())).await?;
+    let headers = s3_client.generate_bucket_headers("DELETE")?;
+
+    log::info!("Deleting bucket: {}", s3_bucket.name());
+    let (body, status, _) = http_request_with_body(
+        &s3_client.url(),
+        &headers,
+        "DELETE",
+        Bytes::new(),
+    )
+    .await?;
+    match status {
+        200..=299 => Ok(()),
+        403 => Err(LumniError::AccessDenied(s3_bucket.name().to_string())),
+        404 => Err(LumniError::NotFound(s3_bucket.name().to_string())),
+        409 => Err(LumniError::Config(format!(
+            "Bucket {} is not empty",
+            s3_bucket.name()
+        ))),
+        _ => Err(unexpected_status(status, s3_bucket.name(), &body)),
+    }
+}
+
+// response headers if the bucket exists, these include the region of
+// the bucket as x-amz-bucket-region
+pub async fn head_bucket(
+    s3_bucket: &S3Bucket,
+) -> Result<Option<HashMap<String, String>>, LumniError> {
+    let s3_client =
+        create_s3_client(s3_bucket.config(), Some(s3_bucket.name())).await?;
+
+    log::info!("Head bucket: {}", s3_bucket.name());
+    let (body, _updated_s3_client, status_code, response_headers) =
+        http_with_redirect_handling(
+            &s3_client,
+            |s3_client| s3_client.generate_bucket_headers("HEAD"),
+            "HEAD",
+        )
+        .await?;
+    match status_code {
+        200..=299 => Ok(Some(response_headers)),
+        404 => Ok(None),
+        _ => Err(unexpected_status(status_code, s3_bucket.name(), &body)),
+    }
+}
+
+fn unexpected_status(
+    status: u16,
+    bucket_name: &str,
+    body: &[u8],
+) -> LumniError {
+    LumniError::from_status(
+        status,
+        &format!("bucket {}", bucket_name),
+        &String::from_utf8_lossy(body),
+    )
+}
@@ -1,5 +1,6 @@
 use super::aws_request_builder::AWSRequestBuilder;
 use super::client_config::S3ClientConfig;
+use crate::LumniError;
 
 pub struct S3Client {
     pub resource: Option<String>,
@@ -16,7 +17,8 @@ impl S3Client {
         );
 
         // Initialize RequestBuilder
-        let request_builder = AWSRequestBuilder::new(config.bucket_url());
+        let request_builder = AWSRequestBuilder::new(config.bucket_url())
+            .set_request_payer(config.request_payer());
 
         S3Client {
             resource: None,
@@ -34,6 +36,15 @@ impl S3Client {
         self.config.region()
     }
 
+    // a client can outlive temporary credentials, e.g. during a long
+    // listing, so these are refreshed before each request
+    pub async fn refresh_credentials(&mut self) -> Result<(), LumniError> {
+        if let Some(credentials) = self.config.credentials().refresh().await? {
+            self.config.set_credentials(credentials);
+        }
+        Ok(())
+    }
+
     pub fn url(&self) -> String {
         let mut url = format!(
             "{}/{}",
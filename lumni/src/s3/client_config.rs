@@ -7,6 +7,8 @@ pub struct S3ClientConfig {
     bucket_name: Option<String>,
     endpoint_url: Option<String>,
     region: String,
+    path_style: bool,
+    request_payer: bool,
 }
 
 impl S3ClientConfig {
@@ -21,9 +23,28 @@ impl S3ClientConfig {
             bucket_name: bucket_name.map(str::to_string),
             endpoint_url: endpoint_url.map(str::to_string),
             region: region.to_string(),
+            path_style: false,
+            request_payer: false,
         }
     }
 
+    // see config::path_style()
+    pub fn set_path_style(mut self, path_style: bool) -> Self {
+        self.path_style = path_style;
+        self
+    }
+
+    // see config::request_payer()
+    pub fn set_request_payer(mut self, request_payer: bool) -> Self {
+        self.request_payer = request_payer;
+        self
+    }
+
+    // e.g. temporary credentials that replace expiring ones
+    pub fn set_credentials(&mut self, credentials: AWSCredentials) {
+        self.credentials = credentials;
+    }
+
     pub fn credentials(&self) -> &AWSCredentials {
         &self.credentials
     }
@@ -40,11 +61,20 @@ impl S3ClientConfig {
         &self.region
     }
 
+    pub fn path_style(&self) -> bool {
+        self.path_style
+    }
+
+    pub fn request_payer(&self) -> bool {
+        self.request_payer
+    }
+
     pub fn bucket_url(&self) -> String {
         configure_bucket_url(
             self.region(),
             self.endpoint_url.as_deref(),
             self.bucket_name.as_deref(),
+            self.path_style,
         )
     }
 }
@@ -3,26 +3,85 @@ use std::collections::HashMap;
 use url::form_urlencoded;
 
 use super::client::S3Client;
-use crate::{LakestreamError, AWS_MAX_LIST_OBJECTS};
+use crate::{LumniError, AWS_MAX_LIST_OBJECTS};
 
 pub trait Headers {
     fn generate_list_buckets_headers(
         &self,
-    ) -> Result<HashMap<String, String>, LakestreamError>;
+    ) -> Result<HashMap<String, String>, LumniError>;
+    fn generate_bucket_headers(
+        &mut self,
+        method: &str,
+    ) -> Result<HashMap<String, String>, LumniError>;
     fn generate_list_objects_headers(
         &mut self,
         prefix: Option<&str>,
         max_keys: Option<u32>,
         continuation_token: Option<&str>,
-    ) -> Result<HashMap<String, String>, LakestreamError>;
+    ) -> Result<HashMap<String, String>, LumniError>;
     fn generate_get_object_headers(
         &mut self,
         object_key: &str,
-    ) -> Result<HashMap<String, String>, LakestreamError>;
+    ) -> Result<HashMap<String, String>, LumniError>;
     fn generate_head_object_headers(
         &mut self,
         object_key: &str,
-    ) -> Result<HashMap<String, String>, LakestreamError>;
+        request_headers: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, LumniError>;
+    fn generate_put_object_headers(
+        &mut self,
+        object_key: &str,
+        object_headers: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, LumniError>;
+    fn generate_create_multipart_upload_headers(
+        &mut self,
+        object_key: &str,
+        object_headers: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, LumniError>;
+    fn generate_upload_part_headers(
+        &mut self,
+        object_key: &str,
+        upload_id: &str,
+        part_number: usize,
+        part_headers: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, LumniError>;
+    fn generate_complete_multipart_upload_headers(
+        &mut self,
+        object_key: &str,
+        upload_id: &str,
+    ) -> Result<HashMap<String, String>, LumniError>;
+    fn generate_abort_multipart_upload_headers(
+        &mut self,
+        object_key: &str,
+        upload_id: &str,
+    ) -> Result<HashMap<String, String>, LumniError>;
+    fn generate_delete_object_headers(
+        &mut self,
+        object_key: &str,
+    ) -> Result<HashMap<String, String>, LumniError>;
+    fn generate_delete_objects_headers(
+        &mut self,
+        request_headers: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, LumniError>;
+    fn generate_object_tagging_headers(
+        &mut self,
+        method: &str,
+        object_key: &str,
+        request_headers: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, LumniError>;
+    fn generate_list_object_versions_headers(
+        &mut self,
+        prefix: Option<&str>,
+        recursive: bool,
+        max_keys: Option<u32>,
+        markers: Option<&(String, String)>,
+    ) -> Result<HashMap<String, String>, LumniError>;
+    fn generate_restore_object_headers(
+        &mut self,
+        object_key: &str,
+        version_id: Option<&str>,
+        request_headers: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, LumniError>;
     fn create_list_objects_query_string(
         &self,
         prefix: Option<&str>,
@@ -34,7 +93,7 @@ pub trait Headers {
 impl Headers for S3Client {
     fn generate_list_buckets_headers(
         &self,
-    ) -> Result<HashMap<String, String>, LakestreamError> {
+    ) -> Result<HashMap<String, String>, LumniError> {
         let method = "GET";
         self.request_builder.generate_headers(
             method,
@@ -46,12 +105,29 @@ impl Headers for S3Client {
         )
     }
 
+    // request on the bucket itself, e.g. to create, delete or head it
+    fn generate_bucket_headers(
+        &mut self,
+        method: &str,
+    ) -> Result<HashMap<String, String>, LumniError> {
+        self.resource = None;
+        self.query_string = None;
+        self.request_builder.generate_headers(
+            method,
+            "s3",
+            self.config().credentials(),
+            None,
+            None,
+            None,
+        )
+    }
+
     fn generate_list_objects_headers(
         &mut self,
         prefix: Option<&str>,
         max_keys: Option<u32>,
         continuation_token: Option<&str>,
-    ) -> Result<HashMap<String, String>, LakestreamError> {
+    ) -> Result<HashMap<String, String>, LumniError> {
         let method = "GET";
         let query_string = Some(self.create_list_objects_query_string(
             prefix,
@@ -73,7 +149,7 @@ impl Headers for S3Client {
     fn generate_get_object_headers(
         &mut self,
         object_key: &str,
-    ) -> Result<HashMap<String, String>, LakestreamError> {
+    ) -> Result<HashMap<String, String>, LumniError> {
         self.resource = Some(object_key.to_string());
         let method = "GET";
         self.request_builder.generate_headers(
@@ -89,8 +165,10 @@ impl Headers for S3Client {
     fn generate_head_object_headers(
         &mut self,
         object_key: &str,
-    ) -> Result<HashMap<String, String>, LakestreamError> {
+        request_headers: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, LumniError> {
         self.resource = Some(object_key.to_string());
+        self.request_builder.set_headers(request_headers.clone());
         let method = "HEAD";
         self.request_builder.generate_headers(
             method,
@@ -102,6 +180,221 @@ impl Headers for S3Client {
         )
     }
 
+    fn generate_put_object_headers(
+        &mut self,
+        object_key: &str,
+        object_headers: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, LumniError> {
+        self.resource = Some(object_key.to_string());
+        self.request_builder.set_headers(object_headers.clone());
+        self.query_string = None;
+        self.request_builder.generate_headers(
+            "PUT",
+            "s3",
+            self.config().credentials(),
+            self.resource.as_deref(),
+            None,
+            None,
+        )
+    }
+
+    fn generate_create_multipart_upload_headers(
+        &mut self,
+        object_key: &str,
+        object_headers: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, LumniError> {
+        self.resource = Some(object_key.to_string());
+        self.request_builder.set_headers(object_headers.clone());
+        // empty value is required for the canonical query string
+        self.query_string = Some("uploads=".to_string());
+        self.request_builder.generate_headers(
+            "POST",
+            "s3",
+            self.config().credentials(),
+            self.resource.as_deref(),
+            self.query_string.as_deref(),
+            None,
+        )
+    }
+
+    fn generate_upload_part_headers(
+        &mut self,
+        object_key: &str,
+        upload_id: &str,
+        part_number: usize,
+        part_headers: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, LumniError> {
+        self.resource = Some(object_key.to_string());
+        self.request_builder.set_headers(part_headers.clone());
+        let mut query_parts = form_urlencoded::Serializer::new(String::new());
+        query_parts.append_pair("partNumber", &part_number.to_string());
+        query_parts.append_pair("uploadId", upload_id);
+        self.query_string = Some(query_parts.finish());
+        self.request_builder.generate_headers(
+            "PUT",
+            "s3",
+            self.config().credentials(),
+            self.resource.as_deref(),
+            self.query_string.as_deref(),
+            None,
+        )
+    }
+
+    fn generate_complete_multipart_upload_headers(
+        &mut self,
+        object_key: &str,
+        upload_id: &str,
+    ) -> Result<HashMap<String, String>, LumniError> {
+        self.resource = Some(object_key.to_string());
+        let mut query_parts = form_urlencoded::Serializer::new(String::new());
+        query_parts.append_pair("uploadId", upload_id);
+        self.query_string = Some(query_parts.finish());
+        self.request_builder.generate_headers(
+            "POST",
+            "s3",
+            self.config().credentials(),
+            self.resource.as_deref(),
+            self.query_string.as_deref(),
+            None,
+        )
+    }
+
+    fn generate_abort_multipart_upload_headers(
+        &mut self,
+        object_key: &str,
+        upload_id: &str,
+    ) -> Result<HashMap<String, String>, LumniError> {
+        self.resource = Some(object_key.to_string());
+        let mut query_parts = form_urlencoded::Serializer::new(String::new());
+        query_parts.append_pair("uploadId", upload_id);
+        self.query_string = Some(query_parts.finish());
+        self.request_builder.generate_headers(
+            "DELETE",
+            "s3",
+            self.config().credentials(),
+            self.resource.as_deref(),
+            self.query_string.as_deref(),
+            None,
+        )
+    }
+
+    fn generate_delete_object_headers(
+        &mut self,
+        object_key: &str,
+    ) -> Result<HashMap<String, String>, LumniError> {
+        self.resource = Some(object_key.to_string());
+        self.query_string = None;
+        self.request_builder.generate_headers(
+            "DELETE",
+            "s3",
+            self.config().credentials(),
+            self.resource.as_deref(),
+            None,
+            None,
+        )
+    }
+
+    fn generate_delete_objects_headers(
+        &mut self,
+        request_headers: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, LumniError> {
+        self.resource = None;
+        // empty value is required for the canonical query string
+        self.query_string = Some("delete=".to_string());
+        self.request_builder.set_headers(request_headers.clone());
+        self.request_builder.generate_headers(
+            "POST",
+            "s3",
+            self.config().credentials(),
+            None,
+            self.query_string.as_deref(),
+            None,
+        )
+    }
+
+    fn generate_object_tagging_headers(
+        &mut self,
+        method: &str,
+        object_key: &str,
+        request_headers: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, LumniError> {
+        self.resource = Some(object_key.to_string());
+        // empty value is required for the canonical query string
+        self.query_string = Some("tagging=".to_string());
+        self.request_builder.set_headers(request_headers.clone());
+        self.request_builder.generate_headers(
+            method,
+            "s3",
+            self.config().credentials(),
+            self.resource.as_deref(),
+            self.query_string.as_deref(),
+            None,
+        )
+    }
+
+    // markers are the key and version id where the previous page ended
+    fn generate_list_object_versions_headers(
+        &mut self,
+        prefix: Option<&str>,
+        recursive: bool,
+        max_keys: Option<u32>,
+        markers: Option<&(String, String)>,
+    ) -> Result<HashMap<String, String>, LumniError> {
+        let max_keys = max_keys
+            .map(|keys| std::cmp::min(keys, AWS_MAX_LIST_OBJECTS))
+            .unwrap_or(AWS_MAX_LIST_OBJECTS);
+        let mut query_parts = form_urlencoded::Serializer::new(String::new());
+        query_parts.append_pair("versions", "");
+        query_parts.append_pair("max-keys", &max_keys.to_string());
+        if !recursive {
+            query_parts.append_pair("delimiter", "/");
+        }
+        if let Some(prefix) = prefix {
+            query_parts.append_pair("prefix", prefix);
+        }
+        if let Some((key_marker, version_id_marker)) = markers {
+            query_parts.append_pair("key-marker", key_marker);
+            if !version_id_marker.is_empty() {
+                query_parts.append_pair("version-id-marker", version_id_marker);
+            }
+        }
+        self.resource = None;
+        self.query_string = Some(query_parts.finish());
+        self.request_builder.generate_headers(
+            "GET",
+            "s3",
+            self.config().credentials(),
+            None,
+            self.query_string.as_deref(),
+            None,
+        )
+    }
+
+    fn generate_restore_object_headers(
+        &mut self,
+        object_key: &str,
+        version_id: Option<&str>,
+        request_headers: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, LumniError> {
+        self.resource = Some(object_key.to_string());
+        let mut query_parts = form_urlencoded::Serializer::new(String::new());
+        // empty value is required for the canonical query string
+        query_parts.append_pair("restore", "");
+        if let Some(version_id) = version_id {
+            query_parts.append_pair("versionId", version_id);
+        }
+        self.query_string = Some(query_parts.finish());
+        self.request_builder.set_headers(request_headers.clone());
+        self.request_builder.generate_headers(
+            "POST",
+            "s3",
+            self.config().credentials(),
+            self.resource.as_deref(),
+            self.query_string.as_deref(),
+            None,
+        )
+    }
+
     fn create_list_objects_query_string(
         &self,
         prefix: Option<&str>,
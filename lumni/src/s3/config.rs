@@ -1,11 +1,27 @@
 use std::env;
 
-use super::aws_credentials::AWS_DEFAULT_REGION;
-use crate::{EnvironmentConfig, LakestreamError};
+use tracing::info_span;
+use url::Url;
+
+use super::aws_credentials::{AWSCredentials, AWS_DEFAULT_REGION};
+use super::bucket::is_mrap_alias;
+use super::sse::ServerSideEncryption;
+use crate::{EnvironmentConfig, LumniError};
 
 pub fn validate_config(
     config: &mut EnvironmentConfig,
-) -> Result<(), LakestreamError> {
+) -> Result<(), LumniError> {
+    let _span = info_span!("auth").entered();
+    // S3_REGION overrides AWS_REGION, for S3-compatible stores that sign
+    // with a fixed region, e.g. "auto" for R2 or "us-east-1" for MinIO
+    if let Some(region) = config
+        .get("S3_REGION")
+        .cloned()
+        .or_else(|| env::var("S3_REGION").ok())
+    {
+        config.insert("AWS_REGION".to_string(), region);
+    }
+
     // Set AWS_REGION
     if !config.contains_key("AWS_REGION") {
         if let Some(region) = config.get("region").cloned() {
@@ -19,27 +35,25 @@ pub fn validate_config(
         }
     }
 
-    // Set AWS_ACCESS_KEY_ID
+    // Set AWS_ACCESS_KEY_ID (optional), without keys the credentials
+    // come from the provider chain, see credentials()
     if !config.contains_key("AWS_ACCESS_KEY_ID") {
         if let Ok(aws_access_key_id) = env::var("AWS_ACCESS_KEY_ID") {
             config.insert("AWS_ACCESS_KEY_ID".to_string(), aws_access_key_id);
-        } else {
-            return Err(LakestreamError::ConfigError(
-                "AWS_ACCESS_KEY_ID not found in the config and environment"
-                    .to_string(),
-            ));
         }
     }
 
-    // Set AWS_SECRET_ACCESS_KEY
-    if !config.contains_key("AWS_SECRET_ACCESS_KEY") {
+    // Set AWS_SECRET_ACCESS_KEY, required with AWS_ACCESS_KEY_ID
+    if config.contains_key("AWS_ACCESS_KEY_ID")
+        && !config.contains_key("AWS_SECRET_ACCESS_KEY")
+    {
         if let Ok(aws_secret_access_key) = env::var("AWS_SECRET_ACCESS_KEY") {
             config.insert(
                 "AWS_SECRET_ACCESS_KEY".to_string(),
                 aws_secret_access_key,
             );
         } else {
-            return Err(LakestreamError::ConfigError(
+            return Err(LumniError::Config(
                 "AWS_SECRET_ACCESS_KEY not found in the config and environment"
                     .to_string(),
             ));
@@ -59,6 +73,259 @@ pub fn validate_config(
             config.insert("S3_ENDPOINT_URL".to_string(), s3_endpoint_url);
         }
     }
+    if let Some(endpoint_url) = config.get("S3_ENDPOINT_URL") {
+        let endpoint_url = validate_endpoint_url(endpoint_url)?;
+        config.insert("S3_ENDPOINT_URL".to_string(), endpoint_url);
+    }
+
+    // Set server-side encryption of uploads (optional)
+    for key in ["S3_SSE", "S3_SSE_KMS_KEY_ID", "S3_SSE_CUSTOMER_KEY"] {
+        if !config.contains_key(key) {
+            if let Ok(value) = env::var(key) {
+                config.insert(key.to_string(), value);
+            }
+        }
+    }
+    ServerSideEncryption::from_config(config)?;
+
+    // Set S3_FORCE_PATH_STYLE (optional), see path_style()
+    if !config.contains_key("S3_FORCE_PATH_STYLE") {
+        if let Ok(force_path_style) = env::var("S3_FORCE_PATH_STYLE") {
+            config.insert("S3_FORCE_PATH_STYLE".to_string(), force_path_style);
+        }
+    }
+    if let Some(force_path_style) = config.get("S3_FORCE_PATH_STYLE") {
+        parse_bool(force_path_style).ok_or_else(|| {
+            LumniError::Config(format!(
+                "S3_FORCE_PATH_STYLE should be true or false, got \"{}\"",
+                force_path_style
+            ))
+        })?;
+    }
+
+    // Set S3_SQS_QUEUE_URL (optional), the queue a watch polls for events
+    if !config.contains_key("S3_SQS_QUEUE_URL") {
+        if let Ok(queue_url) = env::var("S3_SQS_QUEUE_URL") {
+            config.insert("S3_SQS_QUEUE_URL".to_string(), queue_url);
+        }
+    }
+
+    // Set S3_REQUEST_PAYER (optional), see request_payer()
+    if !config.contains_key("S3_REQUEST_PAYER") {
+        if let Ok(request_payer) = env::var("S3_REQUEST_PAYER") {
+            config.insert("S3_REQUEST_PAYER".to_string(), request_payer);
+        }
+    }
+    if let Some(request_payer) = config.get("S3_REQUEST_PAYER") {
+        parse_request_payer(request_payer).ok_or_else(|| {
+            LumniError::Config(format!(
+                "S3_REQUEST_PAYER should be requester, true or false, got \
+                 \"{}\"",
+                request_payer
+            ))
+        })?;
+    }
 
     Ok(())
 }
+
+// keys of the config, or else of the default provider chain, e.g. the
+// role of an EC2 instance, ECS task or EKS pod. temporary credentials of
+// the chain are cached and refreshed before they expire, so a client is
+// created with the credentials that are valid at the time
+pub async fn credentials(
+    config: &EnvironmentConfig,
+) -> Result<AWSCredentials, LumniError> {
+    let region = config
+        .get("AWS_REGION")
+        .cloned()
+        .unwrap_or_else(|| AWS_DEFAULT_REGION.to_string());
+    if let (Some(access_key), Some(secret_key)) = (
+        config.get("AWS_ACCESS_KEY_ID"),
+        config.get("AWS_SECRET_ACCESS_KEY"),
+    ) {
+        return Ok(AWSCredentials::new(
+            access_key.to_string(),
+            secret_key.to_string(),
+            region,
+            config.get("AWS_SESSION_TOKEN").cloned(),
+        ));
+    }
+    // signed with the region of the bucket, not that of the chain
+    let credentials = AWSCredentials::from_chain().await?;
+    Ok(AWSCredentials::new(
+        credentials.access_key().to_string(),
+        credentials.secret_key().to_string(),
+        region,
+        credentials.session_token().map(String::from),
+    )
+    .set_expiration(credentials.expiration()))
+}
+
+// credentials of the config or the provider chain, as the environment
+// variables of the AWS CLI and SDKs
+pub async fn credential_variables(
+    config: &EnvironmentConfig,
+) -> Result<Vec<(String, String)>, LumniError> {
+    let mut config = config.clone();
+    validate_config(&mut config)?;
+    let credentials = credentials(&config).await?;
+    let mut variables = vec![
+        ("AWS_ACCESS_KEY_ID", credentials.access_key()),
+        ("AWS_SECRET_ACCESS_KEY", credentials.secret_key()),
+        ("AWS_REGION", credentials.region()),
+    ];
+    if let Some(token) = credentials.session_token() {
+        variables.push(("AWS_SESSION_TOKEN", token));
+    }
+    if let Some(endpoint_url) = config.get("S3_ENDPOINT_URL") {
+        variables.push(("AWS_ENDPOINT_URL_S3", endpoint_url));
+    }
+    Ok(variables
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect())
+}
+
+// requests to a requester pays bucket are refused unless the requester
+// agrees to pay, with S3_REQUEST_PAYER=requester (or true)
+pub fn request_payer(config: &EnvironmentConfig) -> bool {
+    config
+        .get("S3_REQUEST_PAYER")
+        .and_then(|value| parse_request_payer(value))
+        .unwrap_or(false)
+}
+
+fn parse_request_payer(value: &str) -> Option<bool> {
+    match value {
+        "requester" => Some(true),
+        _ => parse_bool(value),
+    }
+}
+
+// addressing of buckets: path-style ("endpoint/bucket") or virtual-hosted
+// ("bucket.endpoint"). AWS uses virtual-hosted, while custom endpoints
+// default to path-style as MinIO and Ceph RGW do not resolve bucket
+// subdomains unless configured to
+pub fn path_style(config: &EnvironmentConfig) -> bool {
+    match config.get("S3_FORCE_PATH_STYLE") {
+        Some(value) => parse_bool(value).unwrap_or(false),
+        None => config.contains_key("S3_ENDPOINT_URL"),
+    }
+}
+
+// check a bucket name can be used with the configured endpoint
+pub fn validate_bucket_name(
+    name: &str,
+    config: &EnvironmentConfig,
+) -> Result<(), LumniError> {
+    if name.is_empty() || name.contains('/') {
+        return Err(LumniError::Config(format!(
+            "Invalid bucket name \"{}\"",
+            name
+        )));
+    }
+    if is_mrap_alias(name) && config.get("S3_ENDPOINT_URL").is_none() {
+        // the global endpoint has a certificate for *.mrap subdomains
+        return Ok(());
+    }
+    if path_style(config) {
+        // any name the store accepts, e.g. Ceph RGW allows legacy names
+        return Ok(());
+    }
+    // the name becomes part of the hostname
+    let valid_dns_name = (3..=63).contains(&name.len())
+        && name.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| {
+                    c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-'
+                })
+        });
+    if !valid_dns_name {
+        return Err(LumniError::Config(format!(
+            "Bucket name \"{}\" can not be used in a hostname, set \
+             S3_FORCE_PATH_STYLE=true to use path-style addressing",
+            name
+        )));
+    }
+    // a wildcard certificate only covers a single level of subdomains
+    let https = config
+        .get("S3_ENDPOINT_URL")
+        .is_none_or(|url| url.starts_with("https://"));
+    if https && name.contains('.') {
+        return Err(LumniError::Config(format!(
+            "Bucket name \"{}\" contains dots and fails TLS verification with \
+             virtual-hosted addressing, set S3_FORCE_PATH_STYLE=true",
+            name
+        )));
+    }
+    Ok(())
+}
+
+// endpoint of an S3-compatible store, e.g. http://localhost:9000 for
+// MinIO or https://<account_id>.r2.cloudflarestorage.com for R2
+fn validate_endpoint_url(endpoint_url: &str) -> Result<String, LumniError> {
+    let invalid = |reason: &str| {
+        LumniError::Config(format!(
+            "Invalid S3_ENDPOINT_URL \"{}\": {}",
+            endpoint_url, reason
+        ))
+    };
+    let url = Url::parse(endpoint_url).map_err(|e| invalid(&e.to_string()))?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(invalid("expected an http:// or https:// URL"));
+    }
+    if url.host_str().is_none() {
+        return Err(invalid("missing host"));
+    }
+    if url.query().is_some() || url.fragment().is_some() {
+        return Err(invalid("query and fragment are not supported"));
+    }
+    Ok(endpoint_url.trim_end_matches('/').to_string())
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "1" | "yes" => Some(true),
+        "false" | "0" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_bucket_name() {
+        let mut config = EnvironmentConfig::default();
+        assert!(validate_bucket_name("my-bucket", &config).is_ok());
+        assert!(validate_bucket_name("My_Bucket", &config).is_err());
+        assert!(validate_bucket_name("my.bucket", &config).is_err());
+
+        config.insert(
+            "S3_ENDPOINT_URL".to_string(),
+            "http://localhost:9000".to_string(),
+        );
+        assert!(validate_bucket_name("My_Bucket", &config).is_ok());
+        config.insert("S3_FORCE_PATH_STYLE".to_string(), "false".to_string());
+        assert!(validate_bucket_name("my.bucket", &config).is_ok());
+        assert!(validate_bucket_name("My_Bucket", &config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_credentials() {
+        let mut config = EnvironmentConfig::default();
+        config.insert("AWS_REGION".to_string(), "eu-west-1".to_string());
+        config.insert("AWS_ACCESS_KEY_ID".to_string(), "AKID".to_string());
+        config
+            .insert("AWS_SECRET_ACCESS_KEY".to_string(), "secret".to_string());
+        let credentials = credentials(&config).await.unwrap();
+        assert_eq!(credentials.access_key(), "AKID");
+        assert_eq!(credentials.region(), "eu-west-1");
+        // keys of the config do not expire
+        assert!(credentials.refresh().await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,1018 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::future::{BoxFuture, FutureExt};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use super::aws_credentials::{
+    AWSCredentials, AWS_DEFAULT_REGION, EXPIRY_MARGIN_SECONDS,
+};
+use super::aws_request_builder::AWSRequestBuilder;
+use crate::base::token_cache::{cache_token, cached_token};
+use crate::http::client::{HttpClient, HttpClientError};
+use crate::utils::time::{rfc3339_to_epoch, system_time_in_seconds};
+use crate::{ApplicationError, LumniError};
+
+// the metadata endpoints are only reachable on AWS, give up quickly elsewhere
+const METADATA_TIMEOUT: Duration = Duration::from_secs(2);
+const IMDS_ENDPOINT: &str = "http://169.254.169.254";
+const IMDS_TOKEN_TTL_SECONDS: &str = "21600";
+const ECS_ENDPOINT: &str = "http://169.254.170.2";
+const STS_VERSION: &str = "2011-06-15";
+// roles can be chained through source_profile, this catches loops
+const MAX_PROFILE_DEPTH: usize = 8;
+
+const QUERY_VALUE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AWSCredentialSource {
+    Environment, // AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY, ...
+    Profile,     // ~/.aws/credentials and ~/.aws/config, incl. roles and SSO
+    WebIdentity, // AWS_ROLE_ARN and AWS_WEB_IDENTITY_TOKEN_FILE
+    Ecs,         // ECS task role
+    Imds,        // EC2 instance profile, via IMDSv2
+}
+
+impl AWSCredentialSource {
+    // same order as the AWS SDKs
+    pub const DEFAULT_CHAIN: [AWSCredentialSource; 5] = [
+        AWSCredentialSource::Environment,
+        AWSCredentialSource::Profile,
+        AWSCredentialSource::WebIdentity,
+        AWSCredentialSource::Ecs,
+        AWSCredentialSource::Imds,
+    ];
+}
+
+// tries each source in order, the first one that is configured provides
+// the credentials. a source that is configured but fails is an error,
+// rather than silently falling through to the next one
+#[derive(Clone)]
+pub struct AWSCredentialProvider {
+    sources: Vec<AWSCredentialSource>,
+    profile: Option<String>, // AWS_PROFILE or "default" if not set
+    region: Option<String>,  // overrides the region of the source
+    http_client: HttpClient,
+    cached: Arc<Mutex<Option<AWSCredentials>>>,
+}
+
+impl Default for AWSCredentialProvider {
+    fn default() -> Self {
+        AWSCredentialProvider::new()
+    }
+}
+
+impl AWSCredentialProvider {
+    pub fn new() -> Self {
+        AWSCredentialProvider {
+            sources: AWSCredentialSource::DEFAULT_CHAIN.to_vec(),
+            profile: None,
+            region: None,
+            http_client: HttpClient::new(),
+            cached: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    // provider with the default chain, shared so that temporary
+    // credentials are cached across requests
+    pub fn shared() -> AWSCredentialProvider {
+        static SHARED: OnceLock<AWSCredentialProvider> = OnceLock::new();
+        SHARED.get_or_init(AWSCredentialProvider::new).clone()
+    }
+
+    pub fn set_sources(mut self, sources: Vec<AWSCredentialSource>) -> Self {
+        self.sources = sources;
+        self
+    }
+
+    pub fn set_profile(mut self, profile: &str) -> Self {
+        self.profile = Some(profile.to_string());
+        self
+    }
+
+    pub fn set_region(mut self, region: &str) -> Self {
+        self.region = Some(region.to_string());
+        self
+    }
+
+    pub async fn credentials(&self) -> Result<AWSCredentials, LumniError> {
+        if let Some(credentials) = self.cached.lock().unwrap().as_ref() {
+            if !credentials.expires_within(EXPIRY_MARGIN_SECONDS) {
+                return Ok(credentials.clone());
+            }
+        }
+        let credentials = self.resolve().await?;
+        *self.cached.lock().unwrap() = Some(credentials.clone());
+        Ok(credentials)
+    }
+
+    async fn resolve(&self) -> Result<AWSCredentials, LumniError> {
+        for source in &self.sources {
+            if let Some(credentials) = self.try_source(*source).await? {
+                log::debug!("Using AWS credentials from {:?}", source);
+                return Ok(credentials);
+            }
+        }
+        Err(credentials_error(format!(
+            "No AWS credentials found, tried: {:?}",
+            self.sources
+        )))
+    }
+
+    // None if the source is not configured. temporary credentials are
+    // also cached on disk, so the next invocation can use them as well
+    async fn try_source(
+        &self,
+        source: AWSCredentialSource,
+    ) -> Result<Option<AWSCredentials>, LumniError> {
+        if source == AWSCredentialSource::Environment {
+            return self.env_credentials();
+        }
+        let profile = self
+            .profile
+            .clone()
+            .or_else(|| env::var("AWS_PROFILE").ok())
+            .unwrap_or_else(|| "default".to_string());
+        // the settings the credentials come from are hashed into the key,
+        // credentials of e.g. a previous role of the profile are not reused
+        let identity = self.source_identity(source, &profile).join("\n");
+        let hash = hex::encode(Sha256::digest(identity.as_bytes()));
+        let cache_key =
+            format!("aws:{:?}:{}:{}", source, profile, &hash[..16]);
+        if let Some(credentials) =
+            cached_token(&cache_key).and_then(|value| from_cached(&value))
+        {
+            log::debug!("Using cached AWS credentials from {:?}", source);
+            return Ok(Some(credentials));
+        }
+        let credentials = self.fetch_source(source).await?;
+        if let Some(credentials) = &credentials {
+            if let Some(expiration) = credentials.expiration() {
+                cache_token(&cache_key, to_cached(credentials), expiration);
+            }
+        }
+        Ok(credentials)
+    }
+
+    // settings and environment variables that decide which credentials
+    // the source provides
+    fn source_identity(
+        &self,
+        source: AWSCredentialSource,
+        profile: &str,
+    ) -> Vec<String> {
+        let variables = |names: &[&str]| -> Vec<String> {
+            names
+                .iter()
+                .filter_map(|name| {
+                    let value = env::var(name).ok()?;
+                    Some(format!("{}={}", name, value))
+                })
+                .collect()
+        };
+        let mut identity = match source {
+            AWSCredentialSource::Environment => Vec::new(),
+            AWSCredentialSource::Profile => {
+                profile_identity(&SharedConfig::load(), profile)
+            }
+            AWSCredentialSource::WebIdentity => variables(&[
+                "AWS_ROLE_ARN",
+                "AWS_WEB_IDENTITY_TOKEN_FILE",
+                "AWS_ROLE_SESSION_NAME",
+            ]),
+            AWSCredentialSource::Ecs => variables(&[
+                "AWS_CONTAINER_CREDENTIALS_RELATIVE_URI",
+                "AWS_CONTAINER_CREDENTIALS_FULL_URI",
+            ]),
+            AWSCredentialSource::Imds => {
+                variables(&["AWS_EC2_METADATA_SERVICE_ENDPOINT"])
+            }
+        };
+        // the region is stored with the credentials
+        identity.push(format!("region={}", self.region(None)));
+        identity
+    }
+
+    async fn fetch_source(
+        &self,
+        source: AWSCredentialSource,
+    ) -> Result<Option<AWSCredentials>, LumniError> {
+        match source {
+            AWSCredentialSource::Environment => self.env_credentials(),
+            AWSCredentialSource::Profile => self.profile_credentials().await,
+            AWSCredentialSource::WebIdentity => {
+                let (Ok(role_arn), Ok(token_file)) = (
+                    env::var("AWS_ROLE_ARN"),
+                    env::var("AWS_WEB_IDENTITY_TOKEN_FILE"),
+                ) else {
+                    return Ok(None);
+                };
+                let session_name = env::var("AWS_ROLE_SESSION_NAME").ok();
+                self.assume_role_with_web_identity(
+                    &role_arn,
+                    &token_file,
+                    session_name.as_deref(),
+                    &self.region(None),
+                )
+                .await
+                .map(Some)
+            }
+            AWSCredentialSource::Ecs => self.ecs_credentials().await,
+            AWSCredentialSource::Imds => self.imds_credentials().await,
+        }
+    }
+
+    fn region(&self, profile: Option<&HashMap<String, String>>) -> String {
+        self.region
+            .clone()
+            .or_else(|| env::var("AWS_REGION").ok())
+            .or_else(|| env::var("AWS_DEFAULT_REGION").ok())
+            .or_else(|| profile.and_then(|p| p.get("region").cloned()))
+            .unwrap_or_else(|| AWS_DEFAULT_REGION.to_string())
+    }
+
+    fn env_credentials(&self) -> Result<Option<AWSCredentials>, LumniError> {
+        let (Ok(access_key), Ok(secret_key)) = (
+            env::var("AWS_ACCESS_KEY_ID"),
+            env::var("AWS_SECRET_ACCESS_KEY"),
+        ) else {
+            return Ok(None);
+        };
+        Ok(Some(AWSCredentials::new(
+            access_key,
+            secret_key,
+            self.region(None),
+            env::var("AWS_SESSION_TOKEN").ok(),
+        )))
+    }
+
+    async fn profile_credentials(
+        &self,
+    ) -> Result<Option<AWSCredentials>, LumniError> {
+        let explicit = self
+            .profile
+            .clone()
+            .or_else(|| env::var("AWS_PROFILE").ok());
+        let name = explicit.clone().unwrap_or_else(|| "default".to_string());
+        let config = SharedConfig::load();
+        if !config.profiles.contains_key(&name) {
+            return match explicit {
+                Some(_) => Err(credentials_error(format!(
+                    "AWS profile \"{}\" not found",
+                    name
+                ))),
+                None => Ok(None),
+            };
+        }
+        self.resolve_profile(&config, &name, 0).await.map(Some)
+    }
+
+    fn resolve_profile<'a>(
+        &'a self,
+        config: &'a SharedConfig,
+        name: &'a str,
+        depth: usize,
+    ) -> BoxFuture<'a, Result<AWSCredentials, LumniError>> {
+        async move {
+            if depth > MAX_PROFILE_DEPTH {
+                return Err(credentials_error(format!(
+                    "AWS profile \"{}\" has a source_profile loop",
+                    name
+                )));
+            }
+            let profile = config.profiles.get(name).ok_or_else(|| {
+                credentials_error(format!("AWS profile \"{}\" not found", name))
+            })?;
+            let region = self.region(Some(profile));
+            let static_keys = (
+                profile.get("aws_access_key_id"),
+                profile.get("aws_secret_access_key"),
+            );
+
+            // keys of a source profile are used as is, even if it has a
+            // role_arn, like the AWS CLI does
+            if let (Some(access_key), Some(secret_key)) = static_keys {
+                if depth > 0 || !profile.contains_key("role_arn") {
+                    return Ok(AWSCredentials::new(
+                        access_key.to_string(),
+                        secret_key.to_string(),
+                        region,
+                        profile.get("aws_session_token").cloned(),
+                    ));
+                }
+            }
+
+            if let Some(role_arn) = profile.get("role_arn") {
+                let session_name =
+                    profile.get("role_session_name").map(String::as_str);
+                if let Some(token_file) = profile.get("web_identity_token_file")
+                {
+                    return self
+                        .assume_role_with_web_identity(
+                            role_arn,
+                            token_file,
+                            session_name,
+                            &region,
+                        )
+                        .await;
+                }
+                let source = if let Some(source_profile) =
+                    profile.get("source_profile")
+                {
+                    self.resolve_profile(config, source_profile, depth + 1)
+                        .await?
+                } else if let Some(credential_source) =
+                    profile.get("credential_source")
+                {
+                    self.credential_source_credentials(credential_source)
+                        .await?
+                } else {
+                    return Err(credentials_error(format!(
+                        "AWS profile \"{}\" has a role_arn, but no \
+                         source_profile or credential_source",
+                        name
+                    )));
+                };
+                let assume_role = AssumeRole {
+                    role_arn,
+                    session_name,
+                    external_id: profile.get("external_id").map(String::as_str),
+                    duration_seconds: profile
+                        .get("duration_seconds")
+                        .map(String::as_str),
+                };
+                return self.assume_role(&source, &assume_role, &region).await;
+            }
+
+            if profile.contains_key("sso_account_id") {
+                return self.sso_credentials(config, profile, &region).await;
+            }
+            Err(credentials_error(format!(
+                "AWS profile \"{}\" has no usable credentials",
+                name
+            )))
+        }
+        .boxed()
+    }
+
+    async fn credential_source_credentials(
+        &self,
+        credential_source: &str,
+    ) -> Result<AWSCredentials, LumniError> {
+        let source = match credential_source {
+            "Environment" => AWSCredentialSource::Environment,
+            "EcsContainer" => AWSCredentialSource::Ecs,
+            "Ec2InstanceMetadata" => AWSCredentialSource::Imds,
+            _ => {
+                return Err(credentials_error(format!(
+                    "Unsupported credential_source: {}",
+                    credential_source
+                )))
+            }
+        };
+        self.try_source(source).await?.ok_or_else(|| {
+            credentials_error(format!(
+                "No credentials found for credential_source {}",
+                credential_source
+            ))
+        })
+    }
+
+    async fn assume_role(
+        &self,
+        source: &AWSCredentials,
+        assume_role: &AssumeRole<'_>,
+        region: &str,
+    ) -> Result<AWSCredentials, LumniError> {
+        let session_name = assume_role
+            .session_name
+            .map(String::from)
+            .unwrap_or_else(default_session_name);
+        let mut parameters = vec![
+            ("Action", "AssumeRole"),
+            ("Version", STS_VERSION),
+            ("RoleArn", assume_role.role_arn),
+            ("RoleSessionName", &session_name),
+        ];
+        if let Some(external_id) = assume_role.external_id {
+            parameters.push(("ExternalId", external_id));
+        }
+        if let Some(duration_seconds) = assume_role.duration_seconds {
+            parameters.push(("DurationSeconds", duration_seconds));
+        }
+        let query = query_string(&parameters);
+        let endpoint = sts_endpoint(region);
+
+        // STS is signed with the region of the endpoint
+        let signing_credentials = AWSCredentials::new(
+            source.access_key().to_string(),
+            source.secret_key().to_string(),
+            region.to_string(),
+            source.session_token().map(String::from),
+        );
+        let payload_hash = format!("{:x}", Sha256::digest(b""));
+        let headers = AWSRequestBuilder::new(endpoint.clone())
+            .generate_headers(
+                "GET",
+                "sts",
+                &signing_credentials,
+                None,
+                Some(&query),
+                Some(&payload_hash),
+            )
+            .map_err(|e| credentials_error(e.to_string()))?;
+        let body = self
+            .get(&format!("{}?{}", endpoint, query), &headers)
+            .await
+            .map_err(|e| {
+                credentials_error(format!(
+                    "AssumeRole {} failed: {}",
+                    assume_role.role_arn, e
+                ))
+            })?;
+        let credentials = parse_sts_response(&body)?;
+        temporary_credentials(credentials, region)
+    }
+
+    async fn assume_role_with_web_identity(
+        &self,
+        role_arn: &str,
+        token_file: &str,
+        session_name: Option<&str>,
+        region: &str,
+    ) -> Result<AWSCredentials, LumniError> {
+        // the token is rotated by the platform, so it is read every time
+        let token = fs::read_to_string(token_file).map_err(|e| {
+            credentials_error(format!(
+                "Failed to read web identity token {}: {}",
+                token_file, e
+            ))
+        })?;
+        let session_name = session_name
+            .map(String::from)
+            .unwrap_or_else(default_session_name);
+        let query = query_string(&[
+            ("Action", "AssumeRoleWithWebIdentity"),
+            ("Version", STS_VERSION),
+            ("RoleArn", role_arn),
+            ("RoleSessionName", &session_name),
+            ("WebIdentityToken", token.trim()),
+        ]);
+        // the token is the proof of identity, this request is not signed
+        let url = format!("{}?{}", sts_endpoint(region), query);
+        let body = self.get(&url, &HashMap::new()).await.map_err(|e| {
+            credentials_error(format!(
+                "AssumeRoleWithWebIdentity {} failed: {}",
+                role_arn, e
+            ))
+        })?;
+        let credentials = parse_sts_response(&body)?;
+        temporary_credentials(credentials, region)
+    }
+
+    async fn sso_credentials(
+        &self,
+        config: &SharedConfig,
+        profile: &HashMap<String, String>,
+        region: &str,
+    ) -> Result<AWSCredentials, LumniError> {
+        // settings are either in an sso-session section, or in the profile
+        let session = match profile.get("sso_session") {
+            Some(name) => config.sso_sessions.get(name).ok_or_else(|| {
+                credentials_error(format!("sso-session \"{}\" not found", name))
+            })?,
+            None => profile,
+        };
+        let (Some(start_url), Some(sso_region), Some(account_id), Some(role)) = (
+            session.get("sso_start_url"),
+            session.get("sso_region"),
+            profile.get("sso_account_id"),
+            profile.get("sso_role_name"),
+        ) else {
+            return Err(credentials_error(
+                "Incomplete SSO configuration, expected sso_start_url, \
+                 sso_region, sso_account_id and sso_role_name"
+                    .to_string(),
+            ));
+        };
+        let token = sso_cached_token(start_url).ok_or_else(|| {
+            credentials_error(format!(
+                "No valid SSO session for {}, run `aws sso login`",
+                start_url
+            ))
+        })?;
+        let url = format!(
+            "https://portal.sso.{}.amazonaws.com/federation/credentials?{}",
+            sso_region,
+            query_string(&[("account_id", account_id), ("role_name", role)])
+        );
+        let headers =
+            HashMap::from([("x-amz-sso_bearer_token".to_string(), token)]);
+        let body = self.get(&url, &headers).await.map_err(|e| {
+            credentials_error(format!("SSO GetRoleCredentials failed: {}", e))
+        })?;
+        let response: SsoResponse = serde_json::from_slice(&body)
+            .map_err(|e| credentials_error(e.to_string()))?;
+        let role = response.role_credentials;
+        Ok(AWSCredentials::new(
+            role.access_key_id,
+            role.secret_access_key,
+            region.to_string(),
+            Some(role.session_token),
+        )
+        .set_expiration(Some(role.expiration / 1000)))
+    }
+
+    async fn ecs_credentials(
+        &self,
+    ) -> Result<Option<AWSCredentials>, LumniError> {
+        let url = if let Ok(uri) =
+            env::var("AWS_CONTAINER_CREDENTIALS_RELATIVE_URI")
+        {
+            format!("{}{}", ECS_ENDPOINT, uri)
+        } else if let Ok(url) = env::var("AWS_CONTAINER_CREDENTIALS_FULL_URI") {
+            url
+        } else {
+            return Ok(None);
+        };
+        let token = match env::var("AWS_CONTAINER_AUTHORIZATION_TOKEN_FILE") {
+            Ok(path) => Some(fs::read_to_string(&path).map_err(|e| {
+                credentials_error(format!(
+                    "Failed to read container authorization token {}: {}",
+                    path, e
+                ))
+            })?),
+            Err(_) => env::var("AWS_CONTAINER_AUTHORIZATION_TOKEN").ok(),
+        };
+        let mut headers = HashMap::new();
+        if let Some(token) = token {
+            headers
+                .insert("authorization".to_string(), token.trim().to_string());
+        }
+        let body = self.metadata_get(&url, &headers).await.map_err(|e| {
+            credentials_error(format!("ECS credentials request failed: {}", e))
+        })?;
+        let credentials: TemporaryCredentials =
+            serde_json::from_slice(&body)
+                .map_err(|e| credentials_error(e.to_string()))?;
+        temporary_credentials(credentials, &self.region(None)).map(Some)
+    }
+
+    async fn imds_credentials(
+        &self,
+    ) -> Result<Option<AWSCredentials>, LumniError> {
+        if env::var("AWS_EC2_METADATA_DISABLED")
+            .is_ok_and(|v| v.eq_ignore_ascii_case("true"))
+        {
+            return Ok(None);
+        }
+        let endpoint = env::var("AWS_EC2_METADATA_SERVICE_ENDPOINT")
+            .unwrap_or_else(|_| IMDS_ENDPOINT.to_string());
+        let endpoint = endpoint.trim_end_matches('/');
+
+        // no token means this is not an EC2 instance
+        let headers = HashMap::from([(
+            "x-aws-ec2-metadata-token-ttl-seconds".to_string(),
+            IMDS_TOKEN_TTL_SECONDS.to_string(),
+        )]);
+        let token_url = format!("{}/latest/api/token", endpoint);
+        let request = self.http_client.put(&token_url, Some(&headers), None);
+        let token = match tokio::time::timeout(METADATA_TIMEOUT, request).await
+        {
+            Ok(Ok(response)) => response_text(response.body()),
+            _ => return Ok(None),
+        };
+
+        let headers =
+            HashMap::from([("x-aws-ec2-metadata-token".to_string(), token)]);
+        let roles_url =
+            format!("{}/latest/meta-data/iam/security-credentials/", endpoint);
+        let roles = match self.metadata_get(&roles_url, &headers).await {
+            Ok(body) => response_text(Some(&body)),
+            // instance without an instance profile
+            Err(HttpClientError::HttpError(404, _)) => return Ok(None),
+            Err(e) => {
+                return Err(credentials_error(format!(
+                    "Instance metadata request failed: {}",
+                    e
+                )))
+            }
+        };
+        let Some(role) = roles.lines().next().filter(|role| !role.is_empty())
+        else {
+            return Ok(None);
+        };
+        let body = self
+            .metadata_get(&format!("{}{}", roles_url, role), &headers)
+            .await
+            .map_err(|e| {
+                credentials_error(format!(
+                    "Instance metadata request failed: {}",
+                    e
+                ))
+            })?;
+        let credentials: TemporaryCredentials =
+            serde_json::from_slice(&body)
+                .map_err(|e| credentials_error(e.to_string()))?;
+        temporary_credentials(credentials, &self.region(None)).map(Some)
+    }
+
+    async fn get(
+        &self,
+        url: &str,
+        headers: &HashMap<String, String>,
+    ) -> Result<Bytes, HttpClientError> {
+        let response = self
+            .http_client
+            .get(url, Some(headers), None, None, None)
+            .await?;
+        Ok(response.body().cloned().unwrap_or_default())
+    }
+
+    async fn metadata_get(
+        &self,
+        url: &str,
+        headers: &HashMap<String, String>,
+    ) -> Result<Bytes, HttpClientError> {
+        tokio::time::timeout(METADATA_TIMEOUT, self.get(url, headers))
+            .await
+            .map_err(|_| HttpClientError::TimeoutError)?
+    }
+}
+
+struct AssumeRole<'a> {
+    role_arn: &'a str,
+    session_name: Option<&'a str>,
+    external_id: Option<&'a str>,
+    duration_seconds: Option<&'a str>,
+}
+
+// credentials as returned by STS (as XML), ECS and IMDS (as JSON)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TemporaryCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    #[serde(alias = "SessionToken")]
+    token: Option<String>,
+    expiration: Option<String>, // RFC 3339
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct StsResponse {
+    assume_role_result: Option<StsResult>,
+    assume_role_with_web_identity_result: Option<StsResult>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct StsResult {
+    credentials: TemporaryCredentials,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SsoResponse {
+    role_credentials: SsoRoleCredentials,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SsoRoleCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: String,
+    expiration: u64, // epoch milliseconds
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SsoCachedToken {
+    start_url: Option<String>,
+    access_token: Option<String>,
+    expires_at: Option<String>,
+}
+
+// settings of the profile, of the profiles it takes credentials from
+// and of its sso-session, one "section:key=value" per setting
+fn profile_identity(config: &SharedConfig, name: &str) -> Vec<String> {
+    let settings = |kind: &str, section: &HashMap<String, String>| {
+        let mut settings: Vec<String> = section
+            .iter()
+            .map(|(key, value)| format!("{}:{}={}", kind, key, value))
+            .collect();
+        settings.sort();
+        settings
+    };
+    let mut identity = Vec::new();
+    let mut name = Some(name);
+    for _ in 0..=MAX_PROFILE_DEPTH {
+        let Some(profile) = name.and_then(|name| config.profiles.get(name))
+        else {
+            break;
+        };
+        identity.extend(settings("profile", profile));
+        if let Some(session) = profile
+            .get("sso_session")
+            .and_then(|session| config.sso_sessions.get(session))
+        {
+            identity.extend(settings("sso-session", session));
+        }
+        name = profile.get("source_profile").map(String::as_str);
+    }
+    identity
+}
+
+// profiles and sso-session sections of the shared config and credentials
+// files. in the config file profiles are named "[profile name]", except
+// for "[default]". keys in the credentials file take precedence
+#[derive(Debug, Default)]
+struct SharedConfig {
+    profiles: HashMap<String, HashMap<String, String>>,
+    sso_sessions: HashMap<String, HashMap<String, String>>,
+}
+
+impl SharedConfig {
+    fn load() -> Self {
+        let mut config = SharedConfig::default();
+        let files = [
+            (shared_file("AWS_CONFIG_FILE", "config"), true),
+            (
+                shared_file("AWS_SHARED_CREDENTIALS_FILE", "credentials"),
+                false,
+            ),
+        ];
+        for (path, is_config_file) in files {
+            if let Some(contents) =
+                path.and_then(|path| fs::read_to_string(path).ok())
+            {
+                config.parse(&contents, is_config_file);
+            }
+        }
+        config
+    }
+
+    fn parse(&mut self, contents: &str, is_config_file: bool) {
+        // (is an sso-session, name) of the current section
+        let mut section: Option<(bool, String)> = None;
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(['#', ';']) {
+                continue;
+            }
+            if let Some(header) =
+                line.strip_prefix('[').and_then(|l| l.strip_suffix(']'))
+            {
+                let header = header.trim();
+                section =
+                    if let Some(name) = header.strip_prefix("sso-session ") {
+                        Some((true, name.trim().to_string()))
+                    } else if !is_config_file || header == "default" {
+                        Some((false, header.to_string()))
+                    } else {
+                        header
+                            .strip_prefix("profile ")
+                            .map(|name| (false, name.trim().to_string()))
+                    };
+                continue;
+            }
+            let (Some((is_sso_session, name)), Some((key, value))) =
+                (&section, line.split_once('='))
+            else {
+                continue;
+            };
+            let sections = if *is_sso_session {
+                &mut self.sso_sessions
+            } else {
+                &mut self.profiles
+            };
+            sections
+                .entry(name.clone())
+                .or_default()
+                .insert(key.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+}
+
+fn to_cached(credentials: &AWSCredentials) -> String {
+    serde_json::json!({
+        "access_key": credentials.access_key(),
+        "secret_key": credentials.secret_key(),
+        "region": credentials.region(),
+        "session_token": credentials.session_token(),
+        "expiration": credentials.expiration(),
+    })
+    .to_string()
+}
+
+fn from_cached(value: &str) -> Option<AWSCredentials> {
+    let value: serde_json::Value = serde_json::from_str(value).ok()?;
+    let field = |name: &str| value[name].as_str().map(String::from);
+    Some(
+        AWSCredentials::new(
+            field("access_key")?,
+            field("secret_key")?,
+            field("region")?,
+            field("session_token"),
+        )
+        .set_expiration(value["expiration"].as_u64()),
+    )
+}
+
+fn credentials_error(message: String) -> LumniError {
+    LumniError::Application(ApplicationError::InvalidCredentials(message), None)
+}
+
+fn aws_dir() -> Option<PathBuf> {
+    env::var("HOME")
+        .or_else(|_| env::var("USERPROFILE"))
+        .ok()
+        .map(|home| PathBuf::from(home).join(".aws"))
+}
+
+fn shared_file(env_var: &str, file_name: &str) -> Option<PathBuf> {
+    match env::var(env_var) {
+        Ok(path) if !path.is_empty() => Some(PathBuf::from(path)),
+        _ => aws_dir().map(|dir| dir.join(file_name)),
+    }
+}
+
+// access token of a login with `aws sso login` that has not expired yet
+fn sso_cached_token(start_url: &str) -> Option<String> {
+    let entries = fs::read_dir(aws_dir()?.join("sso").join("cache")).ok()?;
+    let now = system_time_in_seconds();
+    entries
+        .filter_map(|entry| fs::read(entry.ok()?.path()).ok())
+        .filter_map(|contents| {
+            serde_json::from_slice::<SsoCachedToken>(&contents).ok()
+        })
+        .filter(|token| token.start_url.as_deref() == Some(start_url))
+        .filter_map(|token| {
+            let expires_at =
+                rfc3339_to_epoch(token.expires_at.as_deref()?).ok()?;
+            Some((expires_at, token.access_token?))
+        })
+        .filter(|(expires_at, _)| *expires_at > now)
+        .max_by_key(|(expires_at, _)| *expires_at)
+        .map(|(_, access_token)| access_token)
+}
+
+fn sts_endpoint(region: &str) -> String {
+    format!("https://sts.{}.amazonaws.com/", region)
+}
+
+fn default_session_name() -> String {
+    format!("lumni-{}", system_time_in_seconds())
+}
+
+// values are fully encoded, so the same string can be signed and sent
+fn query_string(parameters: &[(&str, &str)]) -> String {
+    parameters
+        .iter()
+        .map(|(key, value)| {
+            format!("{}={}", key, utf8_percent_encode(value, QUERY_VALUE))
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn response_text(body: Option<&Bytes>) -> String {
+    body.map(|body| String::from_utf8_lossy(body).trim().to_string())
+        .unwrap_or_default()
+}
+
+fn parse_sts_response(body: &[u8]) -> Result<TemporaryCredentials, LumniError> {
+    let response: StsResponse =
+        serde_xml_rs::from_reader(body).map_err(|e| {
+            credentials_error(format!("Invalid STS response: {}", e))
+        })?;
+    response
+        .assume_role_result
+        .or(response.assume_role_with_web_identity_result)
+        .map(|result| result.credentials)
+        .ok_or_else(|| {
+            credentials_error("STS response has no credentials".to_string())
+        })
+}
+
+fn temporary_credentials(
+    credentials: TemporaryCredentials,
+    region: &str,
+) -> Result<AWSCredentials, LumniError> {
+    let expiration = match &credentials.expiration {
+        Some(expiration) => {
+            Some(rfc3339_to_epoch(expiration).map_err(|e| {
+                credentials_error(format!(
+                    "Invalid expiration \"{}\": {}",
+                    expiration, e
+                ))
+            })?)
+        }
+        None => None,
+    };
+    Ok(AWSCredentials::new(
+        credentials.access_key_id,
+        credentials.secret_access_key,
+        region.to_string(),
+        credentials.token,
+    )
+    .set_expiration(expiration))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_config() {
+        let mut config = SharedConfig::default();
+        config.parse(
+            "[default]\nregion = eu-west-1\n\n\
+             [profile dev]\nrole_arn = arn:aws:iam::123:role/dev\n\
+             source_profile = default\n\n\
+             [sso-session corp]\nsso_start_url = https://corp.awsapps.com/start\n\
+             [ignored]\nregion = us-east-2\n",
+            true,
+        );
+        config.parse(
+            "[default]\naws_access_key_id = AKID\n# comment\n\
+             aws_secret_access_key = secret\n",
+            false,
+        );
+        let default = &config.profiles["default"];
+        assert_eq!(default["region"], "eu-west-1");
+        assert_eq!(default["aws_access_key_id"], "AKID");
+        assert_eq!(config.profiles["dev"]["source_profile"], "default");
+        assert_eq!(
+            config.sso_sessions["corp"]["sso_start_url"],
+            "https://corp.awsapps.com/start"
+        );
+        assert!(!config.profiles.contains_key("ignored"));
+    }
+
+    #[test]
+    fn test_profile_identity() {
+        let parse = |config_file: &str| {
+            let mut config = SharedConfig::default();
+            config.parse(config_file, true);
+            profile_identity(&config, "dev")
+        };
+        let base = "[default]\nregion = eu-west-1\n\
+                    [profile dev]\nrole_arn = arn:aws:iam::123:role/dev\n\
+                    source_profile = default\n";
+        // a role of the profile or its source profile is another identity
+        assert_ne!(
+            parse(base),
+            parse(&base.replace("role/dev", "role/admin"))
+        );
+        assert_ne!(
+            parse(base),
+            parse(&base.replace("eu-west-1", "us-east-1"))
+        );
+        // unrelated profiles are not
+        assert_eq!(
+            parse(base),
+            parse(&format!("{}[profile other]\nregion = us-east-1\n", base))
+        );
+    }
+
+    #[test]
+    fn test_parse_sts_response() {
+        let body = r#"<AssumeRoleResponse xmlns="https://sts.amazonaws.com/doc/2011-06-15/">
+  <AssumeRoleResult>
+    <AssumedRoleUser>
+      <Arn>arn:aws:sts::123:assumed-role/dev/lumni</Arn>
+      <AssumedRoleId>ARO123:lumni</AssumedRoleId>
+    </AssumedRoleUser>
+    <Credentials>
+      <AccessKeyId>ASIA123</AccessKeyId>
+      <SecretAccessKey>secret</SecretAccessKey>
+      <SessionToken>token</SessionToken>
+      <Expiration>2024-01-01T00:00:00Z</Expiration>
+    </Credentials>
+  </AssumeRoleResult>
+  <ResponseMetadata><RequestId>abc</RequestId></ResponseMetadata>
+</AssumeRoleResponse>"#;
+        let credentials = temporary_credentials(
+            parse_sts_response(body.as_bytes()).unwrap(),
+            "eu-west-1",
+        )
+        .unwrap();
+        assert_eq!(credentials.access_key(), "ASIA123");
+        assert_eq!(credentials.session_token(), Some("token"));
+        assert_eq!(credentials.expiration(), Some(1704067200));
+        assert!(credentials.expires_within(0));
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::future::Future;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use bytes::Bytes;
+
+use super::bucket::S3Bucket;
+use super::client_headers::Headers;
+use super::list::create_s3_client;
+use super::parse_http_response::parse_delete_errors;
+use crate::handlers::object_store::{DeleteResult, ObjectStoreTrait};
+use crate::http::requests::http_request_with_body;
+use crate::LumniError;
+
+// S3 limit for a single DeleteObjects request
+const AWS_MAX_DELETE_OBJECTS: usize = 1000;
+
+pub async fn delete_object(
+    s3_bucket: &S3Bucket,
+    object_key: &str,
+) -> Result<(), LumniError> {
+    let mut s3_client =
+        create_s3_client(s3_bucket.config(), Some(s3_bucket.name())).await?;
+    let headers = s3_client.generate_delete_object_headers(object_key)?;
+
+    log::info!("Deleting object: {}", object_key);
+    let (body, status, _) = http_request_with_body(
+        &s3_client.url(),
+        &headers,
+        "DELETE",
+        Bytes::new(),
+    )
+    .await?;
+    match status {
+        200..=299 => Ok(()),
+        _ => Err(LumniError::from_status(
+            status,
+            object_key,
+            &String::from_utf8_lossy(&body),
+        )),
+    }
+}
+
+pub async fn delete_objects(
+    s3_bucket: &S3Bucket,
+    object_keys: &[String],
+) -> Result<DeleteResult, LumniError> {
+    delete_in_batches(object_keys, |keys| delete_objects_batch(s3_bucket, keys))
+        .await
+}
+
+// deletes the keys with a request per AWS_MAX_DELETE_OBJECTS keys, a key
+// is deleted unless delete_batch returns it as failed
+async fn delete_in_batches<'a, F, Fut>(
+    object_keys: &'a [String],
+    delete_batch: F,
+) -> Result<DeleteResult, LumniError>
+where
+    F: Fn(&'a [String]) -> Fut,
+    Fut: Future<Output = Result<Vec<(String, String)>, LumniError>>,
+{
+    let mut result = DeleteResult::default();
+    for keys in object_keys.chunks(AWS_MAX_DELETE_OBJECTS) {
+        let failed = delete_batch(keys).await?;
+        for key in keys {
+            if !failed.iter().any(|(failed_key, _)| failed_key == key) {
+                result.add_deleted(key);
+            }
+        }
+        for (key, reason) in failed {
+            result.add_failed(&key, &reason);
+        }
+    }
+    Ok(result)
+}
+
+// returns the keys that could not be deleted
+async fn delete_objects_batch(
+    s3_bucket: &S3Bucket,
+    object_keys: &[String],
+) -> Result<Vec<(String, String)>, LumniError> {
+    let payload = delete_payload(object_keys);
+
+    // DeleteObjects requires a checksum of the request body
+    let mut request_headers = HashMap::new();
+    request_headers.insert(
+        "x-amz-checksum-crc32".to_string(),
+        STANDARD.encode(crc32fast::hash(payload.as_bytes()).to_be_bytes()),
+    );
+    request_headers.insert(
+        "x-amz-sdk-checksum-algorithm".to_string(),
+        "CRC32".to_string(),
+    );
+    request_headers
+        .insert("content-type".to_string(), "application/xml".to_string());
+
+    let mut s3_client =
+        create_s3_client(s3_bucket.config(), Some(s3_bucket.name())).await?;
+    let headers =
+        s3_client.generate_delete_objects_headers(&request_headers)?;
+
+    log::info!("Deleting {} objects", object_keys.len());
+    let (body, status, _) = http_request_with_body(
+        &s3_client.url(),
+        &headers,
+        "POST",
+        Bytes::from(payload),
+    )
+    .await?;
+    let body = String::from_utf8_lossy(&body);
+    match status {
+        200..=299 => parse_delete_errors(&body).map_err(|e| {
+            LumniError::Internal(format!(
+                "Failed to parse delete response: {}",
+                e
+            ))
+        }),
+        _ => Err(LumniError::from_status(status, s3_bucket.name(), &body)),
+    }
+}
+
+// body of a DeleteObjects request
+fn delete_payload(object_keys: &[String]) -> String {
+    // quiet mode only reports the keys that failed
+    let mut payload = String::from("<Delete><Quiet>true</Quiet>");
+    for key in object_keys {
+        payload.push_str(&format!(
+            "<Object><Key>{}</Key></Object>",
+            escape_xml(key)
+        ));
+    }
+    payload.push_str("</Delete>");
+    payload
+}
+
+pub(super) fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use futures::executor::block_on;
+
+    use super::*;
+
+    #[test]
+    fn test_delete_in_batches() {
+        let object_keys: Vec<String> =
+            (0..2500).map(|index| format!("key-{}", index)).collect();
+        let batches = RefCell::new(Vec::new());
+        let result = block_on(delete_in_batches(&object_keys, |keys| {
+            batches.borrow_mut().push(keys.len());
+            let failed = keys
+                .iter()
+                .filter(|key| key.ends_with("00"))
+                .map(|key| (key.clone(), "AccessDenied".to_string()))
+                .collect();
+            async move { Ok(failed) }
+        }))
+        .unwrap();
+        assert_eq!(*batches.borrow(), vec![1000, 1000, 500]);
+        assert_eq!(result.deleted().len(), 2476);
+        assert_eq!(result.failed().len(), 24);
+        assert_eq!(
+            result.failed()[0],
+            ("key-100".to_string(), "AccessDenied".to_string())
+        );
+        assert!(!result.deleted().contains(&"key-2400".to_string()));
+
+        // a failed request stops the deletion
+        let batches = RefCell::new(0);
+        let result = block_on(delete_in_batches(&object_keys, |_| {
+            *batches.borrow_mut() += 1;
+            async { Err(LumniError::Internal("SlowDown".to_string())) }
+        }));
+        assert!(result.is_err());
+        assert_eq!(*batches.borrow(), 1);
+    }
+
+    #[test]
+    fn test_delete_payload() {
+        let object_keys =
+            vec!["a.txt".to_string(), "dir/<b> & \"c\" 'd'".to_string()];
+        assert_eq!(
+            delete_payload(&object_keys),
+            "<Delete><Quiet>true</Quiet>\
+             <Object><Key>a.txt</Key></Object>\
+             <Object><Key>dir/&lt;b&gt; &amp; &quot;c&quot; &apos;d&apos;\
+             </Key></Object></Delete>"
+        );
+        // escaped once, & first
+        assert_eq!(escape_xml("&lt;"), "&amp;lt;");
+    }
+}
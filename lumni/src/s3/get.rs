@@ -2,25 +2,54 @@ use super::bucket::S3Bucket;
 use super::client_headers::Headers;
 use super::list::create_s3_client;
 use super::request_handler::http_with_redirect_handling;
-use crate::handlers::object_store::ObjectStoreTrait;
-use crate::LakestreamError;
+use crate::base::ranged_read::{ranged_stream, Chunk};
+use crate::handlers::object_store::{
+    ByteRange, ObjectStoreTrait, ObjectStream,
+};
+use crate::{Conditions, LumniError, DEFAULT_DOWNLOAD_CHUNK_SIZE};
 
 pub async fn get_object(
     s3_bucket: &S3Bucket,
     object_key: &str,
     data: &mut Vec<u8>,
-) -> Result<(), LakestreamError> {
+) -> Result<(), LumniError> {
+    get_object_if(s3_bucket, object_key, &Conditions::default(), data)
+        .await
+        .map(|_| ())
+}
+
+// the conditions are sent with the request, false if S3 replies the
+// object is not modified
+pub async fn get_object_if(
+    s3_bucket: &S3Bucket,
+    object_key: &str,
+    conditions: &Conditions,
+    data: &mut Vec<u8>,
+) -> Result<bool, LumniError> {
     let s3_client =
-        create_s3_client(s3_bucket.config(), Some(s3_bucket.name()));
+        create_s3_client(s3_bucket.config(), Some(s3_bucket.name())).await?;
 
     log::info!("Getting object: {}", object_key);
-    let (body_bytes, _updated_s3_client, _status_code, _response_headers) =
+    let (body_bytes, _updated_s3_client, status_code, _response_headers) =
         http_with_redirect_handling(
             &s3_client,
-            |s3_client| s3_client.generate_get_object_headers(object_key),
+            |s3_client| {
+                let mut headers =
+                    s3_client.generate_get_object_headers(object_key)?;
+                headers.extend(conditions.headers());
+                Ok(headers)
+            },
             "GET",
         )
         .await?;
+    match status_code {
+        200..=299 => {}
+        304 => {
+            log::info!("Object not modified: {}", object_key);
+            return Ok(false);
+        }
+        _ => return Err(LumniError::from_status(status_code, object_key, "")),
+    }
     log::info!(
         "Got object: {} of size {} bytes",
         object_key,
@@ -30,5 +59,48 @@ pub async fn get_object(
     data.clear();
     data.extend_from_slice(&body_bytes);
 
-    Ok(())
+    Ok(true)
+}
+
+// each chunk is fetched with its own range request, so only one chunk is
+// held in memory at a time
+pub async fn get_object_stream(
+    s3_bucket: &S3Bucket,
+    object_key: &str,
+    range: Option<ByteRange>,
+) -> Result<ObjectStream, LumniError> {
+    let s3_bucket = s3_bucket.clone();
+    let object_key = object_key.to_string();
+    Ok(ranged_stream(range, DEFAULT_DOWNLOAD_CHUNK_SIZE, move |range| {
+        let s3_bucket = s3_bucket.clone();
+        let object_key = object_key.clone();
+        async move { get_chunk(&s3_bucket, &object_key, range).await }
+    }))
+}
+
+async fn get_chunk(
+    s3_bucket: &S3Bucket,
+    object_key: &str,
+    range: ByteRange,
+) -> Result<Chunk, LumniError> {
+    let s3_client =
+        create_s3_client(s3_bucket.config(), Some(s3_bucket.name())).await?;
+    log::debug!(
+        "Getting range {} of object: {}",
+        range.to_header_value(),
+        object_key
+    );
+    let (body_bytes, _updated_s3_client, status_code, response_headers) =
+        http_with_redirect_handling(
+            &s3_client,
+            |s3_client| {
+                let mut headers =
+                    s3_client.generate_get_object_headers(object_key)?;
+                headers.insert("range".to_string(), range.to_header_value());
+                Ok(headers)
+            },
+            "GET",
+        )
+        .await?;
+    Chunk::from_http(status_code, &response_headers, body_bytes, object_key)
 }
@@ -1,20 +1,30 @@
 use std::collections::HashMap;
 
+use futures::stream::{self, StreamExt};
 use log::info;
 
 use super::bucket::S3Bucket;
 use super::client_headers::Headers;
 use super::list::create_s3_client;
+use super::parse_http_response::checksum_from_head;
 use super::request_handler::http_with_redirect_handling;
 use crate::handlers::object_store::ObjectStoreTrait;
-use crate::LakestreamError;
+use crate::{FileObject, HeadBatch, LumniError};
 
 pub async fn head_object(
     s3_bucket: &S3Bucket,
     object_key: &str,
-) -> Result<(u16, HashMap<String, String>), LakestreamError> {
+) -> Result<(u16, HashMap<String, String>), LumniError> {
+    head_object_with_headers(s3_bucket, object_key, &HashMap::new()).await
+}
+
+async fn head_object_with_headers(
+    s3_bucket: &S3Bucket,
+    object_key: &str,
+    request_headers: &HashMap<String, String>,
+) -> Result<(u16, HashMap<String, String>), LumniError> {
     let s3_client =
-        create_s3_client(s3_bucket.config(), Some(s3_bucket.name()));
+        create_s3_client(s3_bucket.config(), Some(s3_bucket.name())).await?;
 
     info!("Head object: {}", object_key);
 
@@ -23,9 +33,66 @@ pub async fn head_object(
     let (_body_bytes, _updated_s3_client, status_code, response_headers) =
         http_with_redirect_handling(
             &s3_client,
-            |s3_client| s3_client.generate_head_object_headers(object_key),
+            |s3_client| {
+                s3_client
+                    .generate_head_object_headers(object_key, request_headers)
+            },
             "HEAD",
         )
         .await?;
     Ok((status_code, response_headers))
 }
+
+// a listing only has the ETag of an object, which is not always the MD5
+// of the content. With checksum mode enabled a HEAD returns the checksum
+// an object was uploaded with, and tells whether the ETag is an MD5.
+// objects of which this is not known keep the ETag
+pub async fn resolve_checksums(
+    s3_bucket: &S3Bucket,
+    file_objects: Vec<FileObject>,
+) -> Result<Vec<FileObject>, LumniError> {
+    let concurrency = HeadBatch::from_config(s3_bucket.config())?.concurrency();
+    let request_headers = HashMap::from([(
+        "x-amz-checksum-mode".to_string(),
+        "ENABLED".to_string(),
+    )]);
+    let request_headers = &request_headers;
+    Ok(stream::iter(file_objects)
+        .map(|file_object| async move {
+            if file_object.name().ends_with('/') {
+                return file_object;
+            }
+            match head_object_with_headers(
+                s3_bucket,
+                file_object.name(),
+                request_headers,
+            )
+            .await
+            {
+                Ok((200..=299, headers)) => match checksum_from_head(&headers)
+                {
+                    Some(checksum) => file_object.set_checksum(Some(checksum)),
+                    None => file_object,
+                },
+                Ok((status, _)) => {
+                    log::debug!(
+                        "Checksum of {} not read: status {}",
+                        file_object.name(),
+                        status
+                    );
+                    file_object
+                }
+                Err(err) => {
+                    log::debug!(
+                        "Checksum of {} not read: {}",
+                        file_object.name(),
+                        err
+                    );
+                    file_object
+                }
+            }
+        })
+        .buffered(concurrency)
+        .collect()
+        .await)
+}
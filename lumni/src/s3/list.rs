@@ -1,12 +1,15 @@
 use std::collections::HashMap;
 
 use log::error;
+use tracing::{info_span, Instrument};
 
-use super::aws_credentials::AWSCredentials;
 use super::bucket::S3Bucket;
 use super::client::S3Client;
 use super::client_config::S3ClientConfig;
 use super::client_headers::Headers;
+use super::config::{credentials, path_style, request_payer};
+use super::head::resolve_checksums;
+use super::list_parallel::list_files_parallel;
 use super::parse_http_response::{
     extract_continuation_token, parse_bucket_objects, parse_file_objects,
 };
@@ -15,17 +18,17 @@ use crate::base::config::EnvironmentConfig;
 use crate::handlers::object_store::ObjectStoreTrait;
 use crate::http::requests::http_get_request;
 use crate::table::{FileObjectTable, ObjectStoreTable, Table};
-use crate::{
-    FileObject, FileObjectFilter, LakestreamError, AWS_MAX_LIST_OBJECTS,
-};
+use crate::{FileObject, FileObjectFilter, LumniError, AWS_MAX_LIST_OBJECTS};
 
 pub struct ListFilesParams<'a> {
+    s3_bucket: &'a S3Bucket,
     prefix: Option<String>,
     max_keys: Option<u32>,
     s3_client: &'a mut S3Client,
     continuation_token: Option<String>,
     recursive: bool,
     filter: &'a Option<FileObjectFilter>,
+    checksums: bool,
 }
 
 pub async fn list_files(
@@ -36,21 +39,33 @@ pub async fn list_files(
     max_keys: Option<u32>,
     filter: &Option<FileObjectFilter>,
     table: &mut FileObjectTable,
-) -> Result<(), LakestreamError> {
+) -> Result<(), LumniError> {
+    // the checksum of a listed object is its ETag, which is only known
+    // to be an MD5 after a HEAD of the object
+    let checksums = selected_columns
+        .as_ref()
+        .is_some_and(|columns| columns.contains(&"checksum"));
+    if recursive {
+        return list_files_parallel(
+            s3_bucket, prefix, max_keys, filter, checksums, table,
+        )
+        .await;
+    }
     let mut s3_client =
-        create_s3_client(s3_bucket.config(), Some(s3_bucket.name()));
+        create_s3_client(s3_bucket.config(), Some(s3_bucket.name())).await?;
 
     list_files_next(
         &mut ListFilesParams {
+            s3_bucket,
             prefix: prefix.map(|p| p.to_owned()),
             max_keys,
             s3_client: &mut s3_client,
             continuation_token: None, // start with no continuation_token
             recursive,
             filter: &(*filter).clone(),
+            checksums,
         },
         table,
-        selected_columns,
     )
     .await?;
     Ok(())
@@ -59,8 +74,7 @@ pub async fn list_files(
 async fn list_files_next(
     params: &mut ListFilesParams<'_>,
     table: &mut FileObjectTable,
-    _selected_columns: &Option<Vec<&str>>, // not yet implemented
-) -> Result<(), LakestreamError> {
+) -> Result<(), LumniError> {
     let mut directory_stack = std::collections::VecDeque::new();
     let mut temp_file_objects = Vec::new();
 
@@ -89,6 +103,7 @@ async fn list_files_next(
                 },
                 "GET",
             )
+            .instrument(info_span!("list_page"))
             .await?;
 
             if let Some(new_s3_client) = updated_s3_client {
@@ -119,10 +134,14 @@ async fn list_files_next(
             as usize
             - table.len();
         if !temp_file_objects.is_empty() && max_to_add > 0 {
-            let objects_to_add = temp_file_objects
+            let mut objects_to_add = temp_file_objects
                 .drain(..)
                 .take(max_to_add)
                 .collect::<Vec<_>>();
+            if params.checksums {
+                objects_to_add =
+                    resolve_checksums(params.s3_bucket, objects_to_add).await?;
+            }
             table.add_file_objects(objects_to_add).await?;
         }
 
@@ -166,7 +185,7 @@ fn process_file_object(
     }
 }
 
-fn process_response_body(
+pub(super) fn process_response_body(
     response_body: &str,
     recursive: bool,
     filter: &Option<FileObjectFilter>,
@@ -197,8 +216,8 @@ pub async fn list_buckets(
     config: &EnvironmentConfig,
     max_files: Option<u32>,
     table: &mut ObjectStoreTable,
-) -> Result<(), LakestreamError> {
-    let s3_client = create_s3_client(config, None);
+) -> Result<(), LumniError> {
+    let s3_client = create_s3_client(config, None).await?;
     let headers: HashMap<String, String> =
         s3_client.generate_list_buckets_headers().unwrap();
     let result = http_get_request(&s3_client.url().clone(), &headers).await;
@@ -229,38 +248,26 @@ pub async fn list_buckets(
     Ok(())
 }
 
-pub fn create_s3_client(
+pub async fn create_s3_client(
     config: &EnvironmentConfig,
     bucket_name: Option<&str>,
-) -> S3Client {
+) -> Result<S3Client, LumniError> {
     let region = config
         .get("AWS_REGION")
         .expect("Missing region in the configuration");
-    let access_key = config
-        .get("AWS_ACCESS_KEY_ID")
-        .expect("Missing access_key in the configuration");
-    let secret_key = config
-        .get("AWS_SECRET_ACCESS_KEY")
-        .expect("Missing secret_key in the configuration");
-
-    let session_token = config.get("AWS_SESSION_TOKEN").map(|s| s.to_string());
-
-    let credentials = AWSCredentials::new(
-        access_key.to_string(),
-        secret_key.to_string(),
-        region.to_string(),
-        session_token,
-    );
+    let credentials = credentials(config).await?;
     let endpoint_url = config.get("S3_ENDPOINT_URL").map(String::as_str);
 
     let s3_client_config =
-        S3ClientConfig::new(credentials, bucket_name, endpoint_url, region);
-    S3Client::new(s3_client_config)
+        S3ClientConfig::new(credentials, bucket_name, endpoint_url, region)
+            .set_path_style(path_style(config))
+            .set_request_payer(request_payer(config));
+    Ok(S3Client::new(s3_client_config))
 }
 
 // when filter is provided, the effective max_keys is AWS_MAX_LIST_OBJECTS
 // because we are not sure how many objects will be filtered out
-fn get_effective_max_keys(
+pub(super) fn get_effective_max_keys(
     filter: &Option<FileObjectFilter>,
     max_keys: Option<u32>,
 ) -> u32 {
@@ -0,0 +1,284 @@
+use std::collections::VecDeque;
+use std::future::Future;
+
+use futures::stream::{FuturesUnordered, StreamExt};
+
+use super::bucket::S3Bucket;
+use super::client::S3Client;
+use super::client_headers::Headers;
+use super::head::resolve_checksums;
+use super::list::{
+    create_s3_client, get_effective_max_keys, process_response_body,
+};
+use super::request_handler::http_with_redirect_handling;
+use crate::handlers::object_store::ObjectStoreTrait;
+use crate::table::{FileObjectTable, Table};
+use crate::{
+    FileObject, FileObjectFilter, LumniError, AWS_MAX_LIST_OBJECTS,
+    DEFAULT_LIST_CONCURRENCY,
+};
+
+// Recursive listing that shards the bucket by common prefixes ("virtual
+// directories"). Every prefix is listed by its own paginated request
+// sequence, up to `concurrency` at the same time. Prefixes found in a
+// response are queued as new shards, results are added to the table as
+// soon as a shard completes.
+pub async fn list_files_parallel(
+    s3_bucket: &S3Bucket,
+    prefix: Option<&str>,
+    max_keys: Option<u32>,
+    filter: &Option<FileObjectFilter>,
+    checksums: bool,
+    table: &mut FileObjectTable,
+) -> Result<(), LumniError> {
+    fan_out(
+        prefix.map(|p| p.to_owned()),
+        list_concurrency(s3_bucket),
+        max_keys.unwrap_or(AWS_MAX_LIST_OBJECTS) as usize,
+        |prefix, limit| {
+            list_shard(s3_bucket, prefix, filter, limit, checksums)
+        },
+        table,
+    )
+    .await
+}
+
+// runs list_shard(prefix, limit) for the prefix and every prefix found
+// below it. objects are added in the order the shards complete, within a
+// shard in the order listed
+async fn fan_out<F, Fut>(
+    prefix: Option<String>,
+    concurrency: usize,
+    max_objects: usize,
+    list_shard: F,
+    table: &mut FileObjectTable,
+) -> Result<(), LumniError>
+where
+    F: Fn(Option<String>, usize) -> Fut,
+    Fut: Future<Output = Result<(Vec<FileObject>, Vec<String>), LumniError>>,
+{
+    let mut pending = VecDeque::from([prefix]);
+    let mut in_flight = FuturesUnordered::new();
+
+    loop {
+        // no new shards once the table is full, shards in flight are
+        // drained but their results are truncated
+        while in_flight.len() < concurrency && table.len() < max_objects {
+            match pending.pop_front() {
+                Some(prefix) => {
+                    let limit = max_objects - table.len();
+                    in_flight.push(list_shard(prefix, limit))
+                }
+                None => break,
+            }
+        }
+
+        let (file_objects, virtual_directories) = match in_flight.next().await {
+            Some(result) => result?,
+            None => break,
+        };
+        let remaining = max_objects.saturating_sub(table.len());
+        if remaining > 0 && !file_objects.is_empty() {
+            table
+                .add_file_objects(
+                    file_objects.into_iter().take(remaining).collect(),
+                )
+                .await?;
+        }
+        pending.extend(virtual_directories.into_iter().map(Some));
+    }
+    log::debug!(
+        "Listed {} objects, {} prefixes not listed",
+        table.len(),
+        pending.len()
+    );
+    Ok(())
+}
+
+// lists a single prefix until its last page, or until `limit` objects
+async fn list_shard(
+    s3_bucket: &S3Bucket,
+    prefix: Option<String>,
+    filter: &Option<FileObjectFilter>,
+    limit: usize,
+    checksums: bool,
+) -> Result<(Vec<FileObject>, Vec<String>), LumniError> {
+    let mut s3_client =
+        create_s3_client(s3_bucket.config(), Some(s3_bucket.name())).await?;
+    let effective_max_keys = get_effective_max_keys(filter, Some(limit as u32));
+    let mut file_objects = Vec::new();
+    let mut virtual_directories = Vec::new();
+    let mut continuation_token: Option<String> = None;
+
+    loop {
+        let (body_bytes, updated_s3_client, _status_code, _response_headers) =
+            http_with_redirect_handling(
+                &s3_client,
+                |s3_client: &mut S3Client| {
+                    s3_client.generate_list_objects_headers(
+                        prefix.as_deref(),
+                        Some(effective_max_keys),
+                        continuation_token.as_deref(),
+                    )
+                },
+                "GET",
+            )
+            .await?;
+        if let Some(new_s3_client) = updated_s3_client {
+            s3_client = new_s3_client;
+        }
+
+        let body = String::from_utf8_lossy(&body_bytes).to_string();
+        continuation_token = process_response_body(
+            &body,
+            true,
+            filter,
+            &mut file_objects,
+            &mut virtual_directories,
+        );
+        if continuation_token.is_none() || file_objects.len() >= limit {
+            break;
+        }
+    }
+    if checksums {
+        // objects past the limit are not added, nor worth a HEAD
+        file_objects.truncate(limit);
+        file_objects = resolve_checksums(s3_bucket, file_objects).await?;
+    }
+    Ok((file_objects, virtual_directories))
+}
+
+fn list_concurrency(s3_bucket: &S3Bucket) -> usize {
+    s3_bucket
+        .config()
+        .get("S3_LIST_CONCURRENCY")
+        .and_then(|value| value.parse::<usize>().ok())
+        .filter(|concurrency| *concurrency > 0)
+        .unwrap_or(DEFAULT_LIST_CONCURRENCY)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::task::Poll;
+
+    use futures::executor::block_on;
+
+    use super::*;
+    use crate::TableColumnValue;
+
+    // objects, prefixes and number of polls before a shard completes
+    fn shard(
+        prefix: Option<&str>,
+    ) -> (Vec<&'static str>, Vec<&'static str>, usize) {
+        match prefix {
+            None => (vec!["top.txt"], vec!["a/", "b/"], 0),
+            Some("a/") => (vec!["a/1", "a/2"], vec!["a/x/"], 3),
+            Some("b/") => (vec!["b/1"], vec![], 1),
+            Some("a/x/") => (vec!["a/x/1"], vec![], 0),
+            Some(prefix) => panic!("unexpected prefix {}", prefix),
+        }
+    }
+
+    struct Listing {
+        names: Vec<String>,
+        listed: Vec<(Option<String>, usize)>, // prefix and limit
+        max_in_flight: usize,
+    }
+
+    fn list(concurrency: usize, max_objects: usize) -> Listing {
+        let in_flight = Cell::new(0usize);
+        let max_in_flight = Cell::new(0usize);
+        let listed = RefCell::new(Vec::new());
+        let list_shard = |prefix: Option<String>, limit: usize| {
+            listed.borrow_mut().push((prefix.clone(), limit));
+            let (in_flight, max_in_flight) = (&in_flight, &max_in_flight);
+            async move {
+                in_flight.set(in_flight.get() + 1);
+                max_in_flight.set(max_in_flight.get().max(in_flight.get()));
+                let (objects, prefixes, mut polls) = shard(prefix.as_deref());
+                futures::future::poll_fn(|cx| {
+                    if polls == 0 {
+                        return Poll::Ready(());
+                    }
+                    polls -= 1;
+                    cx.waker().wake_by_ref();
+                    Poll::Pending
+                })
+                .await;
+                in_flight.set(in_flight.get() - 1);
+                Ok((
+                    objects
+                        .into_iter()
+                        .map(|name| {
+                            FileObject::new(name.to_string(), 1, None, None)
+                        })
+                        .collect(),
+                    prefixes.into_iter().map(String::from).collect(),
+                ))
+            }
+        };
+        let mut table = FileObjectTable::new(&None, None);
+        block_on(fan_out(
+            None,
+            concurrency,
+            max_objects,
+            list_shard,
+            &mut table,
+        ))
+        .unwrap();
+        let (_, names) = table
+            .columns()
+            .iter()
+            .find(|(name, _)| name == "name")
+            .unwrap();
+        let names = (0..names.len())
+            .map(|index| match names.get_value(index) {
+                Some(TableColumnValue::StringColumn(name)) => name,
+                value => panic!("unexpected name {:?}", value),
+            })
+            .collect();
+        Listing {
+            names,
+            listed: listed.into_inner(),
+            max_in_flight: max_in_flight.get(),
+        }
+    }
+
+    #[test]
+    fn test_fan_out() {
+        // every prefix found is listed, at most 2 at the same time
+        let listing = list(2, 100);
+        assert_eq!(listing.max_in_flight, 2);
+        assert_eq!(
+            listing
+                .listed
+                .iter()
+                .map(|(prefix, _)| prefix.as_deref())
+                .collect::<Vec<_>>(),
+            [None, Some("a/"), Some("b/"), Some("a/x/")]
+        );
+
+        // one shard at a time, in the order the prefixes were found
+        let listing = list(1, 100);
+        assert_eq!(listing.max_in_flight, 1);
+        assert_eq!(listing.names, ["top.txt", "a/1", "a/2", "b/1", "a/x/1"]);
+    }
+
+    #[test]
+    fn test_merge_order() {
+        // b/ completes before a/, a shard is added as a whole in the
+        // order it was listed
+        let listing = list(2, 100);
+        assert_eq!(listing.names, ["top.txt", "b/1", "a/1", "a/2", "a/x/1"]);
+    }
+
+    #[test]
+    fn test_max_objects() {
+        // a shard is limited to what is left, and truncated when it lists
+        // more. no shards start once the table is full
+        let listing = list(1, 2);
+        assert_eq!(listing.names, ["top.txt", "a/1"]);
+        assert_eq!(listing.listed, [(None, 2), (Some("a/".to_string()), 1)]);
+    }
+}
@@ -2,16 +2,38 @@ mod aws_credentials;
 mod aws_request_builder;
 pub mod backend;
 mod bucket;
+mod bucket_ops;
 mod client;
 mod client_config;
 mod client_headers;
 mod config;
+// the chain fetches credentials over http, e.g. of IMDS or STS
+#[cfg(feature = "http_client")]
+mod credential_provider;
+mod delete;
 mod get;
 mod head;
 mod list;
+mod list_parallel;
+mod notifications;
 mod parse_http_response;
+mod presign;
+mod put;
 mod request_handler;
+mod restore;
+mod sigv4a;
+mod sign;
+mod sse;
+mod tagging;
+mod versions;
 
 // Re-export for external use
 pub use aws_credentials::AWSCredentials;
-pub use aws_request_builder::AWSRequestBuilder;
+pub use aws_request_builder::{AWSRequestBuilder, SignedRequest};
+#[cfg(feature = "http_client")]
+pub use credential_provider::{AWSCredentialProvider, AWSCredentialSource};
+pub use restore::{RestoreRequest, RestoreStatus, RestoreTier};
+pub use sigv4a::SigningAlgorithm;
+pub use sse::ServerSideEncryption;
+
+pub(crate) use config::credential_variables;
@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use super::aws_request_builder::AWSRequestBuilder;
+use super::bucket::S3Bucket;
+use super::list::create_s3_client;
+use crate::base::watch::{WatchCallback, WatchEvent, WatchEventKind};
+use crate::handlers::object_store::ObjectStoreTrait;
+use crate::http::requests::http_request_with_body;
+use crate::utils::time::rfc3339_to_epoch;
+use crate::{FileObject, FileObjectFilter, LumniError};
+
+// seconds a receive waits for messages, the maximum of SQS
+const WAIT_TIME_SECONDS: u32 = 20;
+const MAX_MESSAGES: u32 = 10;
+
+// polls the SQS queue in S3_SQS_QUEUE_URL for event notifications of the
+// bucket, until the callback returns false. The queue is subscribed to
+// the bucket directly or through SNS. Messages are deleted once handled,
+// so the queue should not be shared with other consumers
+pub async fn watch_notifications(
+    s3_bucket: &S3Bucket,
+    prefix: Option<&str>,
+    recursive: bool,
+    filter: &Option<FileObjectFilter>,
+    callback: Arc<dyn WatchCallback>,
+) -> Result<(), LumniError> {
+    let queue_url =
+        s3_bucket.config().get("S3_SQS_QUEUE_URL").ok_or_else(|| {
+            LumniError::Config(
+                "Watching an S3 bucket requires S3_SQS_QUEUE_URL, a queue \
+                 that receives the event notifications of the bucket"
+                    .to_string(),
+            )
+        })?;
+    let queue = SqsQueue::new(s3_bucket, queue_url)?;
+    let prefix = prefix.unwrap_or_default();
+    log::info!("Polling {} for events of {}", queue_url, s3_bucket.name());
+
+    loop {
+        for message in queue.receive_messages().await? {
+            let Some(events) = s3_events(&message.body) else {
+                // left in the queue, e.g. for another consumer
+                log::warn!("Skipped message that is not an S3 event");
+                continue;
+            };
+            let mut stop = false;
+            for (bucket, kind, file_object) in events {
+                let name = file_object.name();
+                let matches = bucket == s3_bucket.name()
+                    && name.strip_prefix(prefix).is_some_and(|rest| {
+                        recursive || !rest.trim_start_matches('/').contains('/')
+                    })
+                    && filter.as_ref().is_none_or(|filter| match kind {
+                        WatchEventKind::Deleted => filter.matches_name(name),
+                        _ => filter.matches(&file_object),
+                    });
+                if matches
+                    && !callback.on_event(&WatchEvent::new(kind, file_object))
+                {
+                    stop = true;
+                    break;
+                }
+            }
+            queue.delete_message(&message.receipt_handle).await?;
+            if stop {
+                return Ok(());
+            }
+        }
+    }
+}
+
+struct SqsMessage {
+    body: String,
+    receipt_handle: String,
+}
+
+// requests in the JSON protocol of SQS, signed as the bucket requests
+struct SqsQueue<'a> {
+    s3_bucket: &'a S3Bucket,
+    queue_url: &'a str,
+    endpoint: String,
+    path: String,
+}
+
+impl<'a> SqsQueue<'a> {
+    fn new(
+        s3_bucket: &'a S3Bucket,
+        queue_url: &'a str,
+    ) -> Result<Self, LumniError> {
+        let url = Url::parse(queue_url).map_err(|_| {
+            LumniError::Config(format!(
+                "Invalid S3_SQS_QUEUE_URL \"{}\"",
+                queue_url
+            ))
+        })?;
+        let path = url.path().trim_matches('/').to_string();
+        let mut endpoint = url;
+        endpoint.set_path("");
+        let endpoint = endpoint.as_str().trim_end_matches('/').to_string();
+        Ok(SqsQueue {
+            s3_bucket,
+            queue_url,
+            endpoint,
+            path,
+        })
+    }
+
+    async fn receive_messages(&self) -> Result<Vec<SqsMessage>, LumniError> {
+        let response = self
+            .request(
+                "ReceiveMessage",
+                json!({
+                    "QueueUrl": self.queue_url,
+                    "MaxNumberOfMessages": MAX_MESSAGES,
+                    "WaitTimeSeconds": WAIT_TIME_SECONDS,
+                }),
+            )
+            .await?;
+        let messages = response["Messages"]
+            .as_array()
+            .map(|messages| {
+                messages
+                    .iter()
+                    .filter_map(|message| {
+                        Some(SqsMessage {
+                            body: message["Body"].as_str()?.to_string(),
+                            receipt_handle: message["ReceiptHandle"]
+                                .as_str()?
+                                .to_string(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(messages)
+    }
+
+    async fn delete_message(
+        &self,
+        receipt_handle: &str,
+    ) -> Result<(), LumniError> {
+        self.request(
+            "DeleteMessage",
+            json!({
+                "QueueUrl": self.queue_url,
+                "ReceiptHandle": receipt_handle,
+            }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn request(
+        &self,
+        action: &str,
+        body: Value,
+    ) -> Result<Value, LumniError> {
+        let body = body.to_string();
+        let payload_hash = hex::encode(Sha256::digest(body.as_bytes()));
+        // the path is signed as resource, a path in the url would be
+        // signed with a trailing slash
+        let mut request_builder = AWSRequestBuilder::new(self.endpoint.clone());
+        request_builder.set_headers(HashMap::from([
+            (
+                "content-type".to_string(),
+                "application/x-amz-json-1.0".to_string(),
+            ),
+            ("x-amz-target".to_string(), format!("AmazonSQS.{}", action)),
+        ]));
+        let s3_client = create_s3_client(self.s3_bucket.config(), None).await?;
+        let headers = request_builder.generate_headers(
+            "POST",
+            "sqs",
+            s3_client.config().credentials(),
+            Some(&self.path),
+            None,
+            Some(&payload_hash),
+        )?;
+        let (response, status, _) = http_request_with_body(
+            self.queue_url,
+            &headers,
+            "POST",
+            Bytes::from(body),
+        )
+        .await?;
+        let response = String::from_utf8_lossy(&response);
+        match status {
+            200 => Ok(serde_json::from_str(&response).unwrap_or(Value::Null)),
+            _ => {
+                Err(LumniError::from_status(status, self.queue_url, &response)
+                    .context(format!("{} failed", action)))
+            }
+        }
+    }
+}
+
+// records of an S3 event notification as (bucket, kind, object), or None
+// if the message is not one. Messages sent through SNS are wrapped in a
+// notification, test events have no records
+fn s3_events(body: &str) -> Option<Vec<(String, WatchEventKind, FileObject)>> {
+    let message: Value = serde_json::from_str(body).ok()?;
+    let message = match message["Message"].as_str() {
+        Some(inner) => serde_json::from_str(inner).ok()?,
+        None => message,
+    };
+    if message["Event"] == "s3:TestEvent" {
+        return Some(Vec::new());
+    }
+    let records = message["Records"].as_array()?;
+    let events = records
+        .iter()
+        .filter_map(|record| {
+            let event_name = record["eventName"].as_str()?;
+            let kind = match event_name.split(':').next()? {
+                "ObjectCreated" => WatchEventKind::Created,
+                "ObjectRemoved" => WatchEventKind::Deleted,
+                "LifecycleExpiration" if event_name.ends_with(":Delete") => {
+                    WatchEventKind::Deleted
+                }
+                _ => return None,
+            };
+            let bucket = record["s3"]["bucket"]["name"].as_str()?.to_string();
+            let object = &record["s3"]["object"];
+            // keys are form encoded, spaces as "+"
+            let key = object["key"].as_str()?.replace('+', " ");
+            let key = percent_encoding::percent_decode_str(&key)
+                .decode_utf8_lossy()
+                .to_string();
+            let size = match kind {
+                WatchEventKind::Deleted => 0,
+                _ => object["size"].as_u64().unwrap_or(0),
+            };
+            let modified = record["eventTime"]
+                .as_str()
+                .and_then(|time| rfc3339_to_epoch(time).ok());
+            Some((bucket, kind, FileObject::new(key, size, modified, None)))
+        })
+        .collect();
+    Some(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_s3_events() {
+        let notification = json!({
+            "Records": [
+                {
+                    "eventName": "ObjectCreated:Put",
+                    "eventTime": "2024-01-02T00:00:00.000Z",
+                    "s3": {
+                        "bucket": {"name": "data"},
+                        "object": {"key": "logs/a+b%2B.txt", "size": 12}
+                    }
+                },
+                {
+                    "eventName": "ObjectRemoved:Delete",
+                    "s3": {
+                        "bucket": {"name": "data"},
+                        "object": {"key": "logs/c.txt"}
+                    }
+                },
+                {
+                    "eventName": "ObjectRestore:Completed",
+                    "s3": {
+                        "bucket": {"name": "data"},
+                        "object": {"key": "logs/d.txt", "size": 1}
+                    }
+                }
+            ]
+        });
+        // delivered through SNS
+        let body = json!({
+            "Type": "Notification",
+            "Message": notification.to_string()
+        });
+        let events = s3_events(&body.to_string()).unwrap();
+        assert_eq!(events.len(), 2);
+        let (bucket, kind, file_object) = &events[0];
+        assert_eq!(bucket, "data");
+        assert_eq!(*kind, WatchEventKind::Created);
+        assert_eq!(file_object.name(), "logs/a b+.txt");
+        assert_eq!(file_object.size(), 12);
+        assert_eq!(file_object.modified(), Some(1704153600));
+        assert_eq!(events[1].1, WatchEventKind::Deleted);
+
+        let test_event =
+            json!({"Service": "Amazon S3", "Event": "s3:TestEvent"});
+        assert!(s3_events(&test_event.to_string()).unwrap().is_empty());
+        assert!(s3_events("not json").is_none());
+    }
+}
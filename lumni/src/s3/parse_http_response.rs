@@ -1,10 +1,13 @@
 use std::collections::HashMap;
 
+use serde::de::IgnoredAny;
 use serde::Deserialize;
 
 use crate::handlers::object_store::ObjectStore;
 use crate::utils::time::rfc3339_to_epoch;
-use crate::{EnvironmentConfig, FileObject};
+use crate::{
+    Checksum, ChecksumAlgorithm, EnvironmentConfig, FileObject, ObjectVersion,
+};
 
 // allow non snake case for the XML response
 #[allow(non_snake_case)]
@@ -44,6 +47,7 @@ pub struct Content {
     LastModified: String,
     Size: u64,
     ETag: String,
+    StorageClass: Option<String>,
 }
 
 // allow non snake case for the XML response
@@ -53,6 +57,61 @@ pub struct CommonPrefix {
     Prefix: String,
 }
 
+// versions and delete markers are interleaved in the response, which
+// serde-xml-rs can only read as one sequence of elements
+#[derive(Debug, Deserialize)]
+struct ListVersionsResult {
+    #[serde(rename = "$value")]
+    elements: Vec<ListVersionsElement>,
+}
+
+#[derive(Debug, Deserialize)]
+enum ListVersionsElement {
+    Version(VersionEntry),
+    DeleteMarker(DeleteMarkerEntry),
+    CommonPrefixes(CommonPrefix),
+    IsTruncated(bool),
+    NextKeyMarker(String),
+    NextVersionIdMarker(String),
+    Name(IgnoredAny),
+    Prefix(IgnoredAny),
+    KeyMarker(IgnoredAny),
+    VersionIdMarker(IgnoredAny),
+    MaxKeys(IgnoredAny),
+    Delimiter(IgnoredAny),
+    EncodingType(IgnoredAny),
+}
+
+// allow non snake case for the XML response
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize)]
+struct VersionEntry {
+    Key: String,
+    VersionId: String,
+    IsLatest: bool,
+    LastModified: String,
+    Size: u64,
+    ETag: String,
+    StorageClass: Option<String>,
+}
+
+// allow non snake case for the XML response
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize)]
+struct DeleteMarkerEntry {
+    Key: String,
+    VersionId: String,
+    IsLatest: bool,
+    LastModified: String,
+}
+
+// allow non snake case for the XML response
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize)]
+struct InitiateMultipartUploadResult {
+    UploadId: String,
+}
+
 pub fn parse_bucket_objects(
     body: &str,
     config: Option<EnvironmentConfig>,
@@ -72,6 +131,43 @@ pub fn parse_bucket_objects(
     Ok(object_stores)
 }
 
+// a listing does not tell whether the ETag is the MD5 of the content, it
+// is not for multipart uploads ("<md5 of part md5s>-<parts>") nor for
+// objects encrypted with SSE-KMS/SSE-C, so it is reported as an ETag.
+// checksum_from_head tells what it is, from the headers of the object
+fn checksum_from_etag(etag: &str) -> Option<Checksum> {
+    let etag = etag.trim_matches('"');
+    (!etag.is_empty()).then(|| Checksum::new(ChecksumAlgorithm::Etag, etag))
+}
+
+// checksum of a HEAD response with checksum mode enabled: the additional
+// checksum the object was uploaded with, else the ETag if it is known to
+// be the MD5 of the content
+pub fn checksum_from_head(
+    headers: &HashMap<String, String>,
+) -> Option<Checksum> {
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    };
+    if let Some(sha256) = header("x-amz-checksum-sha256") {
+        return Checksum::from_base64(ChecksumAlgorithm::Sha256, sha256);
+    }
+    if let Some(crc32c) = header("x-amz-checksum-crc32c") {
+        return Checksum::from_base64(ChecksumAlgorithm::Crc32c, crc32c);
+    }
+    let etag = header("etag")?.trim_matches('"');
+    let kms = header("x-amz-server-side-encryption")
+        .is_some_and(|encryption| encryption.starts_with("aws:kms"));
+    let customer_key =
+        header("x-amz-server-side-encryption-customer-algorithm").is_some();
+    let md5 = etag.len() == 32 && etag.chars().all(|c| c.is_ascii_hexdigit());
+    (md5 && !kms && !customer_key)
+        .then(|| Checksum::new(ChecksumAlgorithm::Md5, etag))
+}
+
 pub fn parse_file_objects(
     body: &str,
 ) -> Result<Vec<FileObject>, Box<dyn std::error::Error>> {
@@ -95,6 +191,8 @@ pub fn parse_file_objects(
                     .collect::<HashMap<String, String>>(),
                 ),
             )
+            .set_checksum(checksum_from_etag(&content.ETag))
+            .set_storage_class(content.StorageClass.clone())
         })
         .collect();
     let common_prefixes: Vec<String> = list_bucket_result
@@ -119,6 +217,79 @@ pub fn parse_file_objects(
     Ok(all_file_objects)
 }
 
+// one page of a ListObjectVersions response
+pub struct VersionsPage {
+    pub file_objects: Vec<FileObject>,
+    // key and version id to continue from, if the listing is truncated
+    pub next_markers: Option<(String, String)>,
+}
+
+pub fn parse_object_versions(
+    body: &str,
+) -> Result<VersionsPage, Box<dyn std::error::Error>> {
+    let result: ListVersionsResult = serde_xml_rs::from_str(body)?;
+    let mut file_objects = Vec::new();
+    let mut truncated = false;
+    let mut next_key_marker = None;
+    let mut next_version_id_marker = None;
+    for element in result.elements {
+        match element {
+            ListVersionsElement::Version(version) => {
+                let modified = rfc3339_to_epoch(&version.LastModified)
+                    .map_err(|e| format!("{:?}", e))?;
+                file_objects.push(
+                    FileObject::new(
+                        version.Key,
+                        version.Size,
+                        Some(modified),
+                        None,
+                    )
+                    .set_checksum(checksum_from_etag(&version.ETag))
+                    .set_storage_class(version.StorageClass)
+                    .set_version(Some(ObjectVersion::new(
+                        version.VersionId,
+                        version.IsLatest,
+                        false,
+                    ))),
+                );
+            }
+            ListVersionsElement::DeleteMarker(marker) => {
+                let modified = rfc3339_to_epoch(&marker.LastModified)
+                    .map_err(|e| format!("{:?}", e))?;
+                file_objects.push(
+                    FileObject::new(marker.Key, 0, Some(modified), None)
+                        .set_version(Some(ObjectVersion::new(
+                            marker.VersionId,
+                            marker.IsLatest,
+                            true,
+                        ))),
+                );
+            }
+            ListVersionsElement::CommonPrefixes(prefix) => {
+                file_objects.push(FileObject::new(prefix.Prefix, 0, None, None))
+            }
+            ListVersionsElement::IsTruncated(value) => truncated = value,
+            ListVersionsElement::NextKeyMarker(marker) => {
+                next_key_marker = Some(marker)
+            }
+            ListVersionsElement::NextVersionIdMarker(marker) => {
+                next_version_id_marker = Some(marker)
+            }
+            _ => {}
+        }
+    }
+    let next_markers = match (truncated, next_key_marker) {
+        (true, Some(key_marker)) => {
+            Some((key_marker, next_version_id_marker.unwrap_or_default()))
+        }
+        _ => None,
+    };
+    Ok(VersionsPage {
+        file_objects,
+        next_markers,
+    })
+}
+
 pub fn extract_continuation_token(body: &str) -> Option<String> {
     let list_bucket_result: Result<ListBucketResult, _> =
         serde_xml_rs::from_str(body);
@@ -128,3 +299,213 @@ pub fn extract_continuation_token(body: &str) -> Option<String> {
         Err(_) => None,
     }
 }
+
+pub fn parse_upload_id(body: &str) -> Result<String, serde_xml_rs::Error> {
+    let result: InitiateMultipartUploadResult = serde_xml_rs::from_str(body)?;
+    Ok(result.UploadId)
+}
+
+// allow non snake case for the XML response
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize)]
+struct DeleteObjectsResult {
+    #[serde(default)]
+    Error: Vec<DeleteObjectError>,
+}
+
+// allow non snake case for the XML response
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize)]
+struct DeleteObjectError {
+    Key: String,
+    Code: String,
+    Message: Option<String>,
+}
+
+// returns the keys that failed to delete, with the reason
+pub fn parse_delete_errors(
+    body: &str,
+) -> Result<Vec<(String, String)>, serde_xml_rs::Error> {
+    let result: DeleteObjectsResult = serde_xml_rs::from_str(body)?;
+    Ok(result
+        .Error
+        .into_iter()
+        .map(|error| {
+            let reason = match error.Message {
+                Some(message) => format!("{}: {}", error.Code, message),
+                None => error.Code,
+            };
+            (error.Key, reason)
+        })
+        .collect())
+}
+
+// allow non snake case for the XML response
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize)]
+struct Tagging {
+    TagSet: TagSet,
+}
+
+// allow non snake case for the XML response
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize)]
+struct TagSet {
+    #[serde(default)]
+    Tag: Vec<Tag>,
+}
+
+// allow non snake case for the XML response
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize)]
+struct Tag {
+    Key: String,
+    Value: String,
+}
+
+pub fn parse_object_tags(
+    body: &str,
+) -> Result<HashMap<String, String>, serde_xml_rs::Error> {
+    let result: Tagging = serde_xml_rs::from_str(body)?;
+    Ok(result
+        .TagSet
+        .Tag
+        .into_iter()
+        .map(|tag| (tag.Key, tag.Value))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_delete_errors() {
+        let body = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+            <DeleteResult>\
+            <Error><Key>a.txt</Key><Code>AccessDenied</Code>\
+            <Message>Access Denied</Message></Error>\
+            <Error><Key>dir/b &amp; c.txt</Key><Code>InternalError</Code>\
+            </Error></DeleteResult>";
+        assert_eq!(
+            parse_delete_errors(body).unwrap(),
+            vec![
+                (
+                    "a.txt".to_string(),
+                    "AccessDenied: Access Denied".to_string()
+                ),
+                ("dir/b & c.txt".to_string(), "InternalError".to_string()),
+            ]
+        );
+        // quiet mode returns an empty result when all keys are deleted
+        assert!(parse_delete_errors("<DeleteResult></DeleteResult>")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_parse_object_versions() {
+        let body = "<ListVersionsResult><Name>bucket</Name><Prefix></Prefix>\
+            <KeyMarker/><VersionIdMarker/><NextKeyMarker>b.txt</NextKeyMarker>\
+            <NextVersionIdMarker>v4</NextVersionIdMarker><MaxKeys>3</MaxKeys>\
+            <IsTruncated>true</IsTruncated>\
+            <DeleteMarker><Key>a.txt</Key><VersionId>v3</VersionId>\
+            <IsLatest>true</IsLatest>\
+            <LastModified>2024-01-02T00:00:00.000Z</LastModified>\
+            </DeleteMarker>\
+            <Version><Key>a.txt</Key><VersionId>v1</VersionId>\
+            <IsLatest>false</IsLatest>\
+            <LastModified>2024-01-01T00:00:00.000Z</LastModified>\
+            <ETag>&quot;d41d8cd98f00b204e9800998ecf8427e&quot;</ETag>\
+            <Size>0</Size><StorageClass>GLACIER</StorageClass></Version>\
+            <Version><Key>b.txt</Key><VersionId>v4</VersionId>\
+            <IsLatest>true</IsLatest>\
+            <LastModified>2024-01-03T00:00:00.000Z</LastModified>\
+            <ETag>&quot;abc-2&quot;</ETag><Size>10</Size>\
+            <StorageClass>STANDARD</StorageClass></Version>\
+            </ListVersionsResult>";
+        let page = parse_object_versions(body).unwrap();
+        let versions: Vec<_> = page
+            .file_objects
+            .iter()
+            .map(|file_object| {
+                let version = file_object.version().unwrap();
+                (
+                    file_object.name(),
+                    version.id(),
+                    version.is_latest(),
+                    version.is_delete_marker(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            versions,
+            [
+                ("a.txt", "v3", true, true),
+                ("a.txt", "v1", false, false),
+                ("b.txt", "v4", true, false),
+            ]
+        );
+        assert_eq!(page.file_objects[1].storage_class(), Some("GLACIER"));
+        assert_eq!(
+            page.next_markers,
+            Some(("b.txt".to_string(), "v4".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_checksum_from_head() {
+        let checksum = |headers: &[(&str, &str)]| {
+            let headers = headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect::<HashMap<_, _>>();
+            checksum_from_head(&headers).map(|checksum| checksum.to_string())
+        };
+        let etag = ("ETag", "\"5eb63bbbe01eeed093cb22bb8f5acdc3\"");
+        assert_eq!(
+            checksum(&[etag]).as_deref(),
+            Some("md5:5eb63bbbe01eeed093cb22bb8f5acdc3")
+        );
+        assert_eq!(
+            checksum(&[etag, ("x-amz-server-side-encryption", "AES256")])
+                .as_deref(),
+            Some("md5:5eb63bbbe01eeed093cb22bb8f5acdc3")
+        );
+        // the ETag of these is not the MD5 of the content
+        assert_eq!(
+            checksum(&[etag, ("x-amz-server-side-encryption", "aws:kms")]),
+            None
+        );
+        assert_eq!(
+            checksum(&[
+                etag,
+                ("x-amz-server-side-encryption-customer-algorithm", "AES256")
+            ]),
+            None
+        );
+        assert_eq!(checksum(&[("ETag", "\"abc-2\"")]), None);
+        // an additional checksum is reported, whatever the ETag is
+        assert_eq!(
+            checksum(&[
+                ("ETag", "\"abc-2\""),
+                ("x-amz-checksum-crc32c", "yZRlqg==")
+            ])
+            .as_deref(),
+            Some("crc32c:c99465aa")
+        );
+    }
+
+    #[test]
+    fn test_listed_etag_is_not_md5() {
+        let body = "<ListBucketResult><Contents><Key>a.txt</Key>\
+            <LastModified>2024-01-01T00:00:00.000Z</LastModified>\
+            <ETag>&quot;5eb63bbbe01eeed093cb22bb8f5acdc3&quot;</ETag>\
+            <Size>11</Size></Contents></ListBucketResult>";
+        let file_objects = parse_file_objects(body).unwrap();
+        assert_eq!(
+            file_objects[0].checksum().map(|checksum| checksum.to_string()),
+            Some("etag:5eb63bbbe01eeed093cb22bb8f5acdc3".to_string())
+        );
+    }
+}
@@ -0,0 +1,27 @@
+use super::bucket::S3Bucket;
+use super::list::create_s3_client;
+use crate::handlers::object_store::ObjectStoreTrait;
+use crate::LumniError;
+
+pub async fn presign_object(
+    s3_bucket: &S3Bucket,
+    object_key: &str,
+    method: &str,
+    expires_in: u64,
+) -> Result<String, LumniError> {
+    let s3_client =
+        create_s3_client(s3_bucket.config(), Some(s3_bucket.name())).await?;
+    log::info!(
+        "Presigning {} {} for {} seconds",
+        method,
+        object_key,
+        expires_in
+    );
+    s3_client.request_builder.presign(
+        method,
+        "s3",
+        s3_client.config().credentials(),
+        Some(object_key),
+        expires_in,
+    )
+}
@@ -0,0 +1,514 @@
+use std::collections::HashMap;
+use std::io::Read;
+
+use bytes::Bytes;
+use futures::stream::{FuturesUnordered, StreamExt};
+
+use super::bucket::S3Bucket;
+use super::client_headers::Headers;
+use super::list::create_s3_client;
+use super::parse_http_response::parse_upload_id;
+use super::sse::ServerSideEncryption;
+use crate::handlers::object_store::{ObjectStoreTrait, UploadOptions};
+use crate::http::requests::http_request_with_body;
+use crate::{
+    BinaryCallbackWrapper, Conditions, LumniError, AWS_MAX_PARTS,
+    AWS_MIN_PART_SIZE,
+};
+
+// S3 limit for a single part, parts are held in memory so on 32-bit
+// targets (wasm32) the address space is the limit
+const AWS_MAX_PART_SIZE: usize = if usize::BITS < 64 {
+    usize::MAX
+} else {
+    (5 * 1024 * 1024 * 1024u64) as usize
+};
+// part size doubles after this many parts, so sources of unknown size
+// do not run into the AWS_MAX_PARTS limit
+const PARTS_PER_SIZE_STEP: usize = 1000;
+
+// encryption of the upload, if not given for the call then as configured
+fn encryption(
+    s3_bucket: &S3Bucket,
+    options: &UploadOptions,
+) -> Result<Option<ServerSideEncryption>, LumniError> {
+    match options.server_side_encryption() {
+        Some(encryption) => Ok(Some(encryption.clone())),
+        None => ServerSideEncryption::from_config(s3_bucket.config()),
+    }
+}
+
+// S3 stores these with the object, for single and multipart uploads
+fn object_headers(
+    options: &UploadOptions,
+    encryption: Option<&ServerSideEncryption>,
+) -> HashMap<String, String> {
+    let mut headers: HashMap<String, String> = options
+        .metadata()
+        .iter()
+        .map(|(key, value)| (format!("x-amz-meta-{}", key), value.clone()))
+        .collect();
+    headers.insert(
+        "content-type".to_string(),
+        options
+            .content_type()
+            .unwrap_or("application/octet-stream")
+            .to_string(),
+    );
+    if let Some(encryption) = encryption {
+        headers.extend(encryption.object_headers());
+    }
+    headers
+}
+
+pub async fn put_object(
+    s3_bucket: &S3Bucket,
+    object_key: &str,
+    data: &[u8],
+    options: &UploadOptions,
+) -> Result<(), LumniError> {
+    let encryption = encryption(s3_bucket, options)?;
+    let mut s3_client =
+        create_s3_client(s3_bucket.config(), Some(s3_bucket.name())).await?;
+    let mut headers = s3_client.generate_put_object_headers(
+        object_key,
+        &object_headers(options, encryption.as_ref()),
+    )?;
+    // not signed, as the range of a get
+    headers.extend(options.conditions().headers());
+
+    log::info!("Putting object: {} ({} bytes)", object_key, data.len());
+    let (body, status, _) = http_request_with_body(
+        &s3_client.url(),
+        &headers,
+        "PUT",
+        Bytes::copy_from_slice(data),
+    )
+    .await?;
+    check_response(status, &body, object_key)
+}
+
+pub async fn put_object_multipart(
+    s3_bucket: &S3Bucket,
+    object_key: &str,
+    source: &mut dyn Read,
+    options: &UploadOptions,
+    callback: Option<&BinaryCallbackWrapper>,
+) -> Result<u64, LumniError> {
+    let part_size = options.part_size().max(AWS_MIN_PART_SIZE);
+    let first_part = read_part(source, part_size)?;
+
+    if first_part.len() < part_size {
+        // source fits in a single part, a regular put is sufficient
+        put_object(s3_bucket, object_key, &first_part, options).await?;
+        let size = first_part.len() as u64;
+        if let Some(callback) = callback {
+            callback.call(first_part).await?;
+        }
+        return Ok(size);
+    }
+
+    let encryption = encryption(s3_bucket, options)?;
+    let upload_id = create_multipart_upload(
+        s3_bucket,
+        object_key,
+        &object_headers(options, encryption.as_ref()),
+    )
+    .await?;
+    let upload = MultipartUpload {
+        s3_bucket,
+        object_key,
+        upload_id: &upload_id,
+        part_headers: encryption
+            .map(|encryption| encryption.part_headers())
+            .unwrap_or_default(),
+        conditions: options.conditions(),
+    };
+
+    let mut parts = PartReader {
+        source,
+        object_key,
+        first_part: Some(first_part),
+        part_size,
+        part_number: 0,
+    };
+    let result = match upload.upload_parts(&mut parts, options, callback).await
+    {
+        Ok((parts, size)) => upload.complete(&parts).await.map(|_| size),
+        Err(err) => Err(err),
+    };
+    if result.is_err() {
+        // uploaded parts are billed until the upload is aborted
+        if let Err(err) = upload.abort().await {
+            log::warn!("Failed to abort upload {}: {}", upload_id, err);
+        }
+    }
+    result
+}
+
+async fn create_multipart_upload(
+    s3_bucket: &S3Bucket,
+    object_key: &str,
+    object_headers: &HashMap<String, String>,
+) -> Result<String, LumniError> {
+    let mut s3_client =
+        create_s3_client(s3_bucket.config(), Some(s3_bucket.name())).await?;
+    let headers = s3_client
+        .generate_create_multipart_upload_headers(object_key, object_headers)?;
+    let (body, status, _) = http_request_with_body(
+        &s3_client.url(),
+        &headers,
+        "POST",
+        Bytes::new(),
+    )
+    .await?;
+    check_response(status, &body, object_key)?;
+    parse_upload_id(&String::from_utf8_lossy(&body)).map_err(|e| {
+        LumniError::Internal(format!("Failed to parse upload id: {}", e))
+    })
+}
+
+struct MultipartUpload<'a> {
+    s3_bucket: &'a S3Bucket,
+    object_key: &'a str,
+    upload_id: &'a str,
+    // sent with every part, e.g. the SSE-C key
+    part_headers: HashMap<String, String>,
+    // checked by S3 when the upload completes
+    conditions: &'a Conditions,
+}
+
+impl MultipartUpload<'_> {
+    // returns the part numbers with their ETag, and the total size
+    async fn upload_parts(
+        &self,
+        parts: &mut PartReader<'_>,
+        options: &UploadOptions,
+        callback: Option<&BinaryCallbackWrapper>,
+    ) -> Result<(Vec<(usize, String)>, u64), LumniError> {
+        let mut completed_parts = Vec::new();
+        let mut in_flight = FuturesUnordered::new();
+        let mut total_size = 0u64;
+        let mut end_of_source = false;
+
+        loop {
+            // keep up to `concurrency` parts uploading, reading ahead only
+            // as far as needed to limit memory use
+            while !end_of_source && in_flight.len() < options.concurrency() {
+                match parts.next_part()? {
+                    Some((part_number, data)) => {
+                        total_size += data.len() as u64;
+                        in_flight.push(self.upload_part(part_number, data));
+                    }
+                    None => end_of_source = true,
+                }
+            }
+
+            match in_flight.next().await {
+                Some(result) => {
+                    let (part_number, etag, data) = result?;
+                    completed_parts.push((part_number, etag));
+                    if let Some(callback) = callback {
+                        callback.call(data).await?;
+                    }
+                }
+                None => break,
+            }
+        }
+        completed_parts.sort_by_key(|(part_number, _)| *part_number);
+        Ok((completed_parts, total_size))
+    }
+
+    async fn upload_part(
+        &self,
+        part_number: usize,
+        data: Vec<u8>,
+    ) -> Result<(usize, String, Vec<u8>), LumniError> {
+        let mut s3_client = create_s3_client(
+            self.s3_bucket.config(),
+            Some(self.s3_bucket.name()),
+        )
+        .await?;
+        let headers = s3_client.generate_upload_part_headers(
+            self.object_key,
+            self.upload_id,
+            part_number,
+            &self.part_headers,
+        )?;
+
+        log::debug!(
+            "Uploading part {} of {} ({} bytes)",
+            part_number,
+            self.object_key,
+            data.len()
+        );
+        let (body, status, response_headers) = http_request_with_body(
+            &s3_client.url(),
+            &headers,
+            "PUT",
+            Bytes::copy_from_slice(&data),
+        )
+        .await?;
+        check_response(status, &body, self.object_key)?;
+
+        let etag = response_headers.get("etag").cloned().ok_or_else(|| {
+            LumniError::Internal(format!(
+                "No ETag returned for part {} of {}",
+                part_number, self.object_key
+            ))
+        })?;
+        Ok((part_number, etag, data))
+    }
+
+    async fn complete(
+        &self,
+        parts: &[(usize, String)],
+    ) -> Result<(), LumniError> {
+        let mut s3_client = create_s3_client(
+            self.s3_bucket.config(),
+            Some(self.s3_bucket.name()),
+        )
+        .await?;
+        let mut headers = s3_client
+            .generate_complete_multipart_upload_headers(
+                self.object_key,
+                self.upload_id,
+            )?;
+        headers.extend(self.conditions.headers());
+
+        let (body, status, _) = http_request_with_body(
+            &s3_client.url(),
+            &headers,
+            "POST",
+            Bytes::from(complete_payload(parts)),
+        )
+        .await?;
+        check_response(status, &body, self.object_key)?;
+        // completion can fail after a 200 status, with the error in the body
+        if String::from_utf8_lossy(&body).contains("<Error>") {
+            return Err(LumniError::Internal(format!(
+                "Failed to complete upload of {}: {}",
+                self.object_key,
+                String::from_utf8_lossy(&body)
+            )));
+        }
+        Ok(())
+    }
+
+    async fn abort(&self) -> Result<(), LumniError> {
+        let mut s3_client = create_s3_client(
+            self.s3_bucket.config(),
+            Some(self.s3_bucket.name()),
+        )
+        .await?;
+        let headers = s3_client.generate_abort_multipart_upload_headers(
+            self.object_key,
+            self.upload_id,
+        )?;
+        let (body, status, _) = http_request_with_body(
+            &s3_client.url(),
+            &headers,
+            "DELETE",
+            Bytes::new(),
+        )
+        .await?;
+        check_response(status, &body, self.object_key)
+    }
+}
+
+// splits the source in numbered parts, the first part is read before to
+// decide between a single and a multipart upload
+struct PartReader<'a> {
+    source: &'a mut dyn Read,
+    object_key: &'a str,
+    first_part: Option<Vec<u8>>,
+    part_size: usize,
+    // number of parts read
+    part_number: usize,
+}
+
+impl PartReader<'_> {
+    // size of the next part, doubled every PARTS_PER_SIZE_STEP parts
+    fn next_part_size(&self) -> usize {
+        let step = self.part_number / PARTS_PER_SIZE_STEP;
+        self.part_size
+            .saturating_mul(1 << step.min(10))
+            .min(AWS_MAX_PART_SIZE)
+    }
+
+    // the part number and data of the next part, None when the source is
+    // exhausted. The last part is smaller unless the source ends on a
+    // part boundary
+    fn next_part(&mut self) -> Result<Option<(usize, Vec<u8>)>, LumniError> {
+        let data = match self.first_part.take() {
+            Some(data) => data,
+            None => {
+                let size = self.next_part_size();
+                read_part(self.source, size)?
+            }
+        };
+        if data.is_empty() {
+            return Ok(None);
+        }
+        self.part_number += 1;
+        if self.part_number > AWS_MAX_PARTS {
+            return Err(LumniError::Internal(format!(
+                "Upload of {} exceeds {} parts",
+                self.object_key, AWS_MAX_PARTS
+            )));
+        }
+        Ok(Some((self.part_number, data)))
+    }
+}
+
+// body of a CompleteMultipartUpload request, parts in ascending order
+fn complete_payload(parts: &[(usize, String)]) -> String {
+    let mut payload = String::from("<CompleteMultipartUpload>");
+    for (part_number, etag) in parts {
+        payload.push_str(&format!(
+            "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+            part_number, etag
+        ));
+    }
+    payload.push_str("</CompleteMultipartUpload>");
+    payload
+}
+
+// reads until the part is full or the source is exhausted
+fn read_part(
+    source: &mut dyn Read,
+    size: usize,
+) -> Result<Vec<u8>, LumniError> {
+    let mut data = Vec::with_capacity(size);
+    source.take(size as u64).read_to_end(&mut data)?;
+    Ok(data)
+}
+
+fn check_response(
+    status: u16,
+    body: &[u8],
+    object_key: &str,
+) -> Result<(), LumniError> {
+    match status {
+        200..=299 => Ok(()),
+        _ => Err(LumniError::from_status(
+            status,
+            object_key,
+            &String::from_utf8_lossy(body),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn part_reader<'a>(
+        source: &'a mut Cursor<Vec<u8>>,
+        part_size: usize,
+    ) -> PartReader<'a> {
+        PartReader {
+            source,
+            object_key: "key",
+            first_part: None,
+            part_size,
+            part_number: 0,
+        }
+    }
+
+    #[test]
+    fn test_last_part() {
+        let mut source = Cursor::new(vec![1u8; 25]);
+        let mut parts = part_reader(&mut source, 10);
+        parts.first_part = Some(vec![0u8; 10]);
+        let mut sizes = Vec::new();
+        while let Some((part_number, data)) = parts.next_part().unwrap() {
+            sizes.push((part_number, data.len()));
+        }
+        assert_eq!(sizes, vec![(1, 10), (2, 10), (3, 10), (4, 5)]);
+        assert!(parts.next_part().unwrap().is_none());
+
+        // no empty part when the source ends on a part boundary
+        let mut source = Cursor::new(vec![0u8; 20]);
+        let mut parts = part_reader(&mut source, 10);
+        assert_eq!(parts.next_part().unwrap().unwrap().0, 1);
+        assert_eq!(parts.next_part().unwrap().unwrap().0, 2);
+        assert!(parts.next_part().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_part_size() {
+        let mut source = Cursor::new(Vec::new());
+        let mut parts = part_reader(&mut source, AWS_MIN_PART_SIZE);
+        assert_eq!(parts.next_part_size(), AWS_MIN_PART_SIZE);
+        parts.part_number = PARTS_PER_SIZE_STEP - 1;
+        assert_eq!(parts.next_part_size(), AWS_MIN_PART_SIZE);
+        parts.part_number = PARTS_PER_SIZE_STEP;
+        assert_eq!(parts.next_part_size(), 2 * AWS_MIN_PART_SIZE);
+        parts.part_number = AWS_MAX_PARTS - 1;
+        assert_eq!(parts.next_part_size(), 512 * AWS_MIN_PART_SIZE);
+
+        // sizes stay within the S3 limit for a part
+        parts.part_size = AWS_MAX_PART_SIZE / 2;
+        assert_eq!(parts.next_part_size(), AWS_MAX_PART_SIZE);
+
+        // growing parts fit a source of over 4.8 TiB in AWS_MAX_PARTS
+        let total: u64 = (0..AWS_MAX_PARTS)
+            .map(|part_number| {
+                parts.part_size = AWS_MIN_PART_SIZE;
+                parts.part_number = part_number;
+                parts.next_part_size() as u64
+            })
+            .sum();
+        assert!(total > 4_800 * 1024 * 1024 * 1024 * 1024 / 1000);
+    }
+
+    #[test]
+    fn test_max_parts() {
+        // a part size of 1 reads this many bytes in AWS_MAX_PARTS parts
+        let steps = AWS_MAX_PARTS / PARTS_PER_SIZE_STEP;
+        let max_size = PARTS_PER_SIZE_STEP * ((1 << steps) - 1);
+
+        let mut source = Cursor::new(vec![0u8; max_size]);
+        let mut parts = part_reader(&mut source, 1);
+        let mut count = 0;
+        while parts.next_part().unwrap().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, AWS_MAX_PARTS);
+
+        let mut source = Cursor::new(vec![0u8; max_size + 1]);
+        let mut parts = part_reader(&mut source, 1);
+        let result = loop {
+            match parts.next_part() {
+                Ok(Some(_)) => continue,
+                result => break result,
+            }
+        };
+        assert!(result.is_err());
+        assert_eq!(parts.part_number, AWS_MAX_PARTS + 1);
+    }
+
+    #[test]
+    fn test_complete_payload() {
+        let parts = vec![
+            (1, "\"a54357aff0632cce46d942af68356b38\"".to_string()),
+            (2, "\"0c78aef83f66abc1fa1e8477f296d394\"".to_string()),
+        ];
+        assert_eq!(
+            complete_payload(&parts),
+            "<CompleteMultipartUpload>\
+             <Part><PartNumber>1</PartNumber>\
+             <ETag>\"a54357aff0632cce46d942af68356b38\"</ETag></Part>\
+             <Part><PartNumber>2</PartNumber>\
+             <ETag>\"0c78aef83f66abc1fa1e8477f296d394\"</ETag></Part>\
+             </CompleteMultipartUpload>"
+        );
+        assert_eq!(
+            complete_payload(&[]),
+            "<CompleteMultipartUpload></CompleteMultipartUpload>"
+        );
+    }
+}
@@ -5,7 +5,7 @@ use bytes::Bytes;
 use crate::http::requests::http_request_with_headers;
 use crate::s3::client::S3Client;
 use crate::s3::client_config::S3ClientConfig;
-use crate::LakestreamError;
+use crate::LumniError;
 
 async fn handle_redirect(s3_client: &S3Client, new_region: &str) -> S3Client {
     let config = s3_client.config();
@@ -14,7 +14,9 @@ async fn handle_redirect(s3_client: &S3Client, new_region: &str) -> S3Client {
     let endpoint_url = config.endpoint_url();
 
     let s3_client_config =
-        S3ClientConfig::new(credentials, bucket_name, endpoint_url, new_region);
+        S3ClientConfig::new(credentials, bucket_name, endpoint_url, new_region)
+            .set_path_style(config.path_style())
+            .set_request_payer(config.request_payer());
     S3Client::new(s3_client_config)
 }
 
@@ -22,15 +24,13 @@ pub async fn http_with_redirect_handling<F>(
     s3_client: &S3Client,
     generate_headers: F,
     method: &str,
-) -> Result<
-    (Bytes, Option<S3Client>, u16, HashMap<String, String>),
-    LakestreamError,
->
+) -> Result<(Bytes, Option<S3Client>, u16, HashMap<String, String>), LumniError>
 where
-    F: Fn(&mut S3Client) -> Result<HashMap<String, String>, LakestreamError>,
+    F: Fn(&mut S3Client) -> Result<HashMap<String, String>, LumniError>,
 {
     let mut current_s3_client = s3_client.clone();
     loop {
+        current_s3_client.refresh_credentials().await?;
         let headers = generate_headers(&mut current_s3_client)?;
         let result = http_request_with_headers(
             &current_s3_client.url(),
@@ -51,14 +51,12 @@ where
                     } else {
                         let error = "Error: Redirect without \
                                      x-amz-bucket-region header";
-                        return Err(LakestreamError::from(error));
+                        return Err(LumniError::from(error));
                     }
                 } else {
                     if status_code == 403 {
                         let url = current_s3_client.url();
-                        return Err(LakestreamError::AccessDenied(
-                            url.to_string(),
-                        ));
+                        return Err(LumniError::AccessDenied(url.to_string()));
                     }
 
                     // TODO: Handle non-200 status codes
@@ -75,7 +73,7 @@ where
                     ));
                 }
             }
-            Err(e) => return Err(LakestreamError::from(e)),
+            Err(e) => return Err(e),
         }
     }
 }
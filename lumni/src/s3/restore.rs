@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use bytes::Bytes;
+
+use super::bucket::S3Bucket;
+use super::client_headers::Headers;
+use super::list::create_s3_client;
+use crate::handlers::object_store::ObjectStoreTrait;
+use crate::http::requests::http_request_with_body;
+use crate::LumniError;
+
+// retrieval speed of an archived object, faster tiers cost more.
+// Expedited is not available for DEEP_ARCHIVE
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RestoreTier {
+    Expedited,
+    #[default]
+    Standard,
+    Bulk,
+}
+
+impl RestoreTier {
+    pub fn from_name(tier: &str) -> Option<Self> {
+        match tier.to_lowercase().as_str() {
+            "expedited" => Some(RestoreTier::Expedited),
+            "standard" => Some(RestoreTier::Standard),
+            "bulk" => Some(RestoreTier::Bulk),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RestoreTier::Expedited => "Expedited",
+            RestoreTier::Standard => "Standard",
+            RestoreTier::Bulk => "Bulk",
+        }
+    }
+}
+
+// makes a temporary copy of a GLACIER or DEEP_ARCHIVE object readable
+// for a number of days, the archived object itself stays where it is
+#[derive(Debug, Clone)]
+pub struct RestoreRequest {
+    days: u32,
+    tier: RestoreTier,
+    version_id: Option<String>,
+}
+
+impl RestoreRequest {
+    pub fn new(days: u32) -> Self {
+        RestoreRequest {
+            days: days.max(1),
+            tier: RestoreTier::default(),
+            version_id: None,
+        }
+    }
+
+    pub fn set_tier(mut self, tier: RestoreTier) -> Self {
+        self.tier = tier;
+        self
+    }
+
+    // restore a noncurrent version instead of the latest
+    pub fn set_version_id(mut self, version_id: Option<String>) -> Self {
+        self.version_id = version_id;
+        self
+    }
+
+    pub fn days(&self) -> u32 {
+        self.days
+    }
+
+    pub fn tier(&self) -> RestoreTier {
+        self.tier
+    }
+
+    pub fn version_id(&self) -> Option<&str> {
+        self.version_id.as_deref()
+    }
+
+    fn to_xml(&self) -> String {
+        format!(
+            "<RestoreRequest><Days>{}</Days><GlacierJobParameters><Tier>{}\
+             </Tier></GlacierJobParameters></RestoreRequest>",
+            self.days,
+            self.tier.as_str()
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreStatus {
+    // the restore takes minutes to hours, depending on the tier
+    Started,
+    // an earlier request is still running
+    InProgress,
+    // a restored copy is readable, its expiry is updated to the new days
+    AlreadyRestored,
+}
+
+pub async fn restore_object(
+    s3_bucket: &S3Bucket,
+    object_key: &str,
+    request: &RestoreRequest,
+) -> Result<RestoreStatus, LumniError> {
+    let mut request_headers = HashMap::new();
+    request_headers
+        .insert("content-type".to_string(), "application/xml".to_string());
+
+    let mut s3_client =
+        create_s3_client(s3_bucket.config(), Some(s3_bucket.name())).await?;
+    let headers = s3_client.generate_restore_object_headers(
+        object_key,
+        request.version_id(),
+        &request_headers,
+    )?;
+
+    log::info!(
+        "Restore object: {} for {} days ({})",
+        object_key,
+        request.days(),
+        request.tier().as_str()
+    );
+    let (body, status, _) = http_request_with_body(
+        &s3_client.url(),
+        &headers,
+        "POST",
+        Bytes::from(request.to_xml()),
+    )
+    .await?;
+    let body = String::from_utf8_lossy(&body);
+    match status {
+        202 => Ok(RestoreStatus::Started),
+        200 => Ok(RestoreStatus::AlreadyRestored),
+        409 => Ok(RestoreStatus::InProgress),
+        // objects in other storage classes can be read without a restore
+        403 if body.contains("InvalidObjectState") => {
+            Err(LumniError::Config(format!(
+                "{} is not archived and does not need to be restored",
+                object_key
+            )))
+        }
+        _ => Err(LumniError::from_status(status, object_key, &body)),
+    }
+}
@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+
+use super::aws_request_builder::SignedRequest;
+use super::bucket::S3Bucket;
+use super::list::create_s3_client;
+use crate::handlers::object_store::ObjectStoreTrait;
+use crate::utils::time::UtcTimeNow;
+use crate::LumniError;
+
+// signs a request without sending it, at the current time if x_amz_date
+// is None. a x-amz-content-sha256 header is
+// used as the payload hash, as it would be for a request with a body
+pub async fn sign_object_request(
+    s3_bucket: &S3Bucket,
+    object_key: Option<&str>,
+    method: &str,
+    query_string: Option<&str>,
+    headers: &HashMap<String, String>,
+    x_amz_date: Option<&str>,
+) -> Result<SignedRequest, LumniError> {
+    let mut s3_client =
+        create_s3_client(s3_bucket.config(), Some(s3_bucket.name())).await?;
+    let payload_hash = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("x-amz-content-sha256"))
+        .map(|(_, value)| value.clone());
+    s3_client.request_builder.set_headers(headers.clone());
+    s3_client.request_builder.sign_request(
+        method,
+        "s3",
+        s3_client.config().credentials(),
+        object_key,
+        query_string,
+        payload_hash.as_deref(),
+        &x_amz_date
+            .map_or_else(|| UtcTimeNow::new().x_amz_date(), str::to_string),
+    )
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use md5::{Digest, Md5};
+
+use crate::{EnvironmentConfig, LumniError};
+
+// SSE-C keys are AES-256 keys
+const CUSTOMER_KEY_LENGTH: usize = 32;
+
+// how S3 encrypts a stored object
+#[derive(Clone, PartialEq)]
+pub enum ServerSideEncryption {
+    // SSE-S3, keys managed by S3
+    S3,
+    // SSE-KMS, with the account default key if no key id is given
+    Kms { key_id: Option<String> },
+    // SSE-C, the key is sent with every request and not stored
+    Customer { key: Vec<u8> },
+}
+
+impl ServerSideEncryption {
+    // key as base64, e.g. from "openssl rand -base64 32"
+    pub fn customer(key: &str) -> Result<Self, LumniError> {
+        let key = STANDARD
+            .decode(key.trim())
+            .ok()
+            .filter(|key| key.len() == CUSTOMER_KEY_LENGTH)
+            .ok_or_else(|| {
+                LumniError::Config(format!(
+                    "SSE-C key should be {} bytes, base64 encoded",
+                    CUSTOMER_KEY_LENGTH
+                ))
+            })?;
+        Ok(ServerSideEncryption::Customer { key })
+    }
+
+    // S3_SSE is AES256, aws:kms or customer, with the key in
+    // S3_SSE_KMS_KEY_ID or S3_SSE_CUSTOMER_KEY
+    pub fn from_config(
+        config: &EnvironmentConfig,
+    ) -> Result<Option<Self>, LumniError> {
+        let Some(mode) = config.get("S3_SSE") else {
+            return Ok(None);
+        };
+        let sse = match mode.as_str() {
+            "AES256" => ServerSideEncryption::S3,
+            "aws:kms" => ServerSideEncryption::Kms {
+                key_id: config.get("S3_SSE_KMS_KEY_ID").cloned(),
+            },
+            "customer" => {
+                let key =
+                    config.get("S3_SSE_CUSTOMER_KEY").ok_or_else(|| {
+                        LumniError::Config(
+                            "S3_SSE=customer requires S3_SSE_CUSTOMER_KEY"
+                                .to_string(),
+                        )
+                    })?;
+                ServerSideEncryption::customer(key)?
+            }
+            _ => {
+                return Err(LumniError::Config(format!(
+                    "S3_SSE should be AES256, aws:kms or customer, got \"{}\"",
+                    mode
+                )))
+            }
+        };
+        Ok(Some(sse))
+    }
+
+    // headers of a PutObject or CreateMultipartUpload request
+    pub fn object_headers(&self) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        match self {
+            ServerSideEncryption::S3 => {
+                headers.insert(
+                    "x-amz-server-side-encryption".to_string(),
+                    "AES256".to_string(),
+                );
+            }
+            ServerSideEncryption::Kms { key_id } => {
+                headers.insert(
+                    "x-amz-server-side-encryption".to_string(),
+                    "aws:kms".to_string(),
+                );
+                if let Some(key_id) = key_id {
+                    headers.insert(
+                        "x-amz-server-side-encryption-aws-kms-key-id"
+                            .to_string(),
+                        key_id.clone(),
+                    );
+                }
+            }
+            ServerSideEncryption::Customer { .. } => {
+                headers.extend(self.part_headers());
+            }
+        }
+        headers
+    }
+
+    // headers of an UploadPart request, only SSE-C repeats the key there
+    pub fn part_headers(&self) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        if let ServerSideEncryption::Customer { key } = self {
+            headers.insert(
+                "x-amz-server-side-encryption-customer-algorithm".to_string(),
+                "AES256".to_string(),
+            );
+            headers.insert(
+                "x-amz-server-side-encryption-customer-key".to_string(),
+                STANDARD.encode(key),
+            );
+            headers.insert(
+                "x-amz-server-side-encryption-customer-key-md5".to_string(),
+                STANDARD.encode(Md5::digest(key)),
+            );
+        }
+        headers
+    }
+}
+
+// keeps SSE-C keys out of logs
+impl fmt::Debug for ServerSideEncryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerSideEncryption::S3 => write!(f, "S3"),
+            ServerSideEncryption::Kms { key_id } => {
+                f.debug_struct("Kms").field("key_id", key_id).finish()
+            }
+            ServerSideEncryption::Customer { .. } => write!(f, "Customer"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_from_config() {
+        let mut config = EnvironmentConfig::default();
+        assert_eq!(ServerSideEncryption::from_config(&config).unwrap(), None);
+
+        config.insert("S3_SSE".to_string(), "customer".to_string());
+        assert!(ServerSideEncryption::from_config(&config).is_err());
+        config.insert(
+            "S3_SSE_CUSTOMER_KEY".to_string(),
+            "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=".to_string(),
+        );
+        let sse = ServerSideEncryption::from_config(&config).unwrap().unwrap();
+        let headers = sse.part_headers();
+        assert_eq!(
+            headers["x-amz-server-side-encryption-customer-key-md5"],
+            "tP/LI3N87DFaSk0aoqYgzg=="
+        );
+        assert_eq!(sse.object_headers(), headers);
+        assert!(ServerSideEncryption::S3.part_headers().is_empty());
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use bytes::Bytes;
+
+use super::bucket::S3Bucket;
+use super::client_headers::Headers;
+use super::delete::escape_xml;
+use super::list::create_s3_client;
+use super::parse_http_response::parse_object_tags;
+use crate::handlers::object_store::ObjectStoreTrait;
+use crate::http::requests::http_request_with_body;
+use crate::LumniError;
+
+// S3 limit of tags on a single object
+const AWS_MAX_OBJECT_TAGS: usize = 10;
+
+pub async fn get_object_tags(
+    s3_bucket: &S3Bucket,
+    object_key: &str,
+) -> Result<HashMap<String, String>, LumniError> {
+    let mut s3_client =
+        create_s3_client(s3_bucket.config(), Some(s3_bucket.name())).await?;
+    let headers = s3_client.generate_object_tagging_headers(
+        "GET",
+        object_key,
+        &HashMap::new(),
+    )?;
+
+    log::info!("Get object tags: {}", object_key);
+    let (body, status, _) =
+        http_request_with_body(&s3_client.url(), &headers, "GET", Bytes::new())
+            .await?;
+    let body = String::from_utf8_lossy(&body);
+    match status {
+        200..=299 => parse_object_tags(&body).map_err(|e| {
+            LumniError::Internal(format!(
+                "Failed to parse tagging response: {}",
+                e
+            ))
+        }),
+        _ => Err(LumniError::from_status(status, object_key, &body)),
+    }
+}
+
+// replaces all tags of the object, an empty map removes them
+pub async fn put_object_tags(
+    s3_bucket: &S3Bucket,
+    object_key: &str,
+    tags: &HashMap<String, String>,
+) -> Result<(), LumniError> {
+    if tags.len() > AWS_MAX_OBJECT_TAGS {
+        return Err(LumniError::Config(format!(
+            "An object can have at most {} tags, got {}",
+            AWS_MAX_OBJECT_TAGS,
+            tags.len()
+        )));
+    }
+    // sorted, so the same tags always produce the same request
+    let mut tags: Vec<(&String, &String)> = tags.iter().collect();
+    tags.sort();
+    let mut payload = String::from("<Tagging><TagSet>");
+    for (key, value) in tags {
+        payload.push_str(&format!(
+            "<Tag><Key>{}</Key><Value>{}</Value></Tag>",
+            escape_xml(key),
+            escape_xml(value)
+        ));
+    }
+    payload.push_str("</TagSet></Tagging>");
+
+    // PutObjectTagging requires a checksum of the request body
+    let mut request_headers = HashMap::new();
+    request_headers.insert(
+        "x-amz-checksum-crc32".to_string(),
+        STANDARD.encode(crc32fast::hash(payload.as_bytes()).to_be_bytes()),
+    );
+    request_headers.insert(
+        "x-amz-sdk-checksum-algorithm".to_string(),
+        "CRC32".to_string(),
+    );
+    request_headers
+        .insert("content-type".to_string(), "application/xml".to_string());
+
+    let mut s3_client =
+        create_s3_client(s3_bucket.config(), Some(s3_bucket.name())).await?;
+    let headers = s3_client.generate_object_tagging_headers(
+        "PUT",
+        object_key,
+        &request_headers,
+    )?;
+
+    log::info!("Put object tags: {}", object_key);
+    let (body, status, _) = http_request_with_body(
+        &s3_client.url(),
+        &headers,
+        "PUT",
+        Bytes::from(payload),
+    )
+    .await?;
+    match status {
+        200..=299 => Ok(()),
+        _ => Err(LumniError::from_status(
+            status,
+            object_key,
+            &String::from_utf8_lossy(&body),
+        )),
+    }
+}
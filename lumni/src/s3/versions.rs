@@ -0,0 +1,296 @@
+use super::bucket::S3Bucket;
+use super::client::S3Client;
+use super::client_headers::Headers;
+use super::list::create_s3_client;
+use super::parse_http_response::{parse_object_versions, VersionsPage};
+use super::request_handler::http_with_redirect_handling;
+use crate::handlers::object_store::ObjectStoreTrait;
+use crate::table::FileObjectTable;
+use crate::{FileObject, FileObjectFilter, LumniError, AWS_MAX_LIST_OBJECTS};
+
+// all versions and delete markers of the objects under the prefix, newest
+// version of each key first. Buckets that never had versioning enabled
+// list each object once, with version id "null"
+pub async fn list_object_versions(
+    s3_bucket: &S3Bucket,
+    prefix: Option<&str>,
+    recursive: bool,
+    max_keys: Option<u32>,
+    filter: &Option<FileObjectFilter>,
+    table: &mut FileObjectTable,
+) -> Result<(), LumniError> {
+    let mut s3_client =
+        create_s3_client(s3_bucket.config(), Some(s3_bucket.name())).await?;
+    let max_keys = max_keys.unwrap_or(AWS_MAX_LIST_OBJECTS) as usize;
+    let mut markers: Option<(String, String)> = None;
+    let mut listed = 0usize;
+
+    loop {
+        let page = list_versions_page(
+            s3_bucket,
+            &mut s3_client,
+            prefix,
+            recursive,
+            markers.as_ref(),
+        )
+        .await?;
+
+        // as with list_files, directories are not subject to the filter
+        // and left out when filtering
+        let file_objects: Vec<_> = page
+            .file_objects
+            .into_iter()
+            .filter(|file_object| match (filter, file_object.version()) {
+                (None, _) => true,
+                (Some(_), None) => false,
+                (Some(filter), Some(_)) => filter.matches(file_object),
+            })
+            .take(max_keys - listed)
+            .collect();
+        listed += file_objects.len();
+        table.add_file_objects(file_objects).await?;
+
+        markers = page.next_markers;
+        if markers.is_none() || listed >= max_keys {
+            break;
+        }
+    }
+    Ok(())
+}
+
+// the objects under the prefix as they were at as_of (epoch seconds),
+// rebuilt from the versions. all versions are listed, max_keys limits
+// the objects returned
+pub async fn list_objects_as_of(
+    s3_bucket: &S3Bucket,
+    prefix: Option<&str>,
+    recursive: bool,
+    max_keys: Option<u32>,
+    filter: &Option<FileObjectFilter>,
+    as_of: u64,
+    table: &mut FileObjectTable,
+) -> Result<(), LumniError> {
+    let mut s3_client =
+        create_s3_client(s3_bucket.config(), Some(s3_bucket.name())).await?;
+    let max_keys = max_keys.unwrap_or(AWS_MAX_LIST_OBJECTS) as usize;
+    let mut markers: Option<(String, String)> = None;
+    let mut listed = 0usize;
+    let mut point_in_time = PointInTime::new(prefix, recursive, as_of);
+
+    loop {
+        // directories that existed then are not known from the common
+        // prefixes of now, so the versions below them are listed too
+        let page = list_versions_page(
+            s3_bucket,
+            &mut s3_client,
+            prefix,
+            true,
+            markers.as_ref(),
+        )
+        .await?;
+        markers = page.next_markers;
+
+        let mut file_objects: Vec<FileObject> = page
+            .file_objects
+            .into_iter()
+            .filter_map(|version| point_in_time.push(version))
+            .collect();
+        if markers.is_none() {
+            file_objects.extend(point_in_time.finish());
+        }
+        let file_objects: Vec<_> = file_objects
+            .into_iter()
+            .filter(|file_object| match (filter, file_object.version()) {
+                (None, _) => true,
+                (Some(_), None) => false,
+                (Some(filter), Some(_)) => filter.matches(file_object),
+            })
+            .take(max_keys - listed)
+            .collect();
+        listed += file_objects.len();
+        table.add_file_objects(file_objects).await?;
+
+        if markers.is_none() || listed >= max_keys {
+            break;
+        }
+    }
+    Ok(())
+}
+
+async fn list_versions_page(
+    s3_bucket: &S3Bucket,
+    s3_client: &mut S3Client,
+    prefix: Option<&str>,
+    recursive: bool,
+    markers: Option<&(String, String)>,
+) -> Result<VersionsPage, LumniError> {
+    let (body_bytes, updated_s3_client, status_code, _) =
+        http_with_redirect_handling(
+            s3_client,
+            |s3_client: &mut S3Client| {
+                s3_client.generate_list_object_versions_headers(
+                    prefix, recursive, None, markers,
+                )
+            },
+            "GET",
+        )
+        .await?;
+    if let Some(new_s3_client) = updated_s3_client {
+        *s3_client = new_s3_client;
+    }
+    let body = String::from_utf8_lossy(&body_bytes);
+    if !(200..=299).contains(&status_code) {
+        return Err(LumniError::from_status(
+            status_code,
+            s3_bucket.name(),
+            &body,
+        ));
+    }
+    parse_object_versions(&body).map_err(|e| {
+        LumniError::Internal(format!(
+            "Failed to parse versions response: {}",
+            e
+        ))
+    })
+}
+
+// picks the version of each key that was current at a point in time. the
+// versions of a key are listed together, so a key is done when the next
+// one starts
+struct PointInTime {
+    prefix: String,
+    recursive: bool,
+    as_of: u64,
+    key: Option<String>,
+    current: Option<FileObject>, // newest version of key at as_of
+    directory: Option<String>,   // last directory returned
+}
+
+impl PointInTime {
+    fn new(prefix: Option<&str>, recursive: bool, as_of: u64) -> Self {
+        PointInTime {
+            prefix: prefix.unwrap_or_default().to_string(),
+            recursive,
+            as_of,
+            key: None,
+            current: None,
+            directory: None,
+        }
+    }
+
+    // the object of the previous key, once the versions of the next key
+    // start
+    fn push(&mut self, version: FileObject) -> Option<FileObject> {
+        let done = if self.key.as_deref() != Some(version.name()) {
+            self.key = Some(version.name().to_string());
+            self.current.take().and_then(|object| self.resolve(object))
+        } else {
+            None
+        };
+        let modified = version.modified().unwrap_or(u64::MAX);
+        let newer = self
+            .current
+            .as_ref()
+            .is_none_or(|current| modified > current.modified().unwrap_or(0));
+        if modified <= self.as_of && newer {
+            self.current = Some(version);
+        }
+        done
+    }
+
+    fn finish(&mut self) -> Option<FileObject> {
+        self.key = None;
+        self.current.take().and_then(|object| self.resolve(object))
+    }
+
+    // nothing if the key was deleted then. without recursive, keys in
+    // subdirectories are returned as their directory, once
+    fn resolve(&mut self, object: FileObject) -> Option<FileObject> {
+        if object.version().is_none_or(|v| v.is_delete_marker()) {
+            return None;
+        }
+        if self.recursive {
+            return Some(object);
+        }
+        let name = object.name().strip_prefix(&self.prefix)?;
+        let Some(end) = name.find('/') else {
+            return Some(object);
+        };
+        let directory = format!("{}{}", self.prefix, &name[..=end]);
+        if self.directory.as_ref() == Some(&directory) {
+            return None;
+        }
+        self.directory = Some(directory.clone());
+        Some(FileObject::new(directory, 0, None, None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ObjectVersion;
+
+    fn version(
+        key: &str,
+        id: &str,
+        modified: u64,
+        deleted: bool,
+    ) -> FileObject {
+        FileObject::new(key.to_string(), 4, Some(modified), None).set_version(
+            Some(ObjectVersion::new(id.to_string(), false, deleted)),
+        )
+    }
+
+    fn resolve(recursive: bool, as_of: u64) -> Vec<(String, Option<String>)> {
+        let versions = vec![
+            version("data/a.csv", "a2", 300, false),
+            version("data/a.csv", "a1", 100, false),
+            version("data/b.csv", "b2", 200, true),
+            version("data/b.csv", "b1", 100, false),
+            version("data/sub/c.csv", "c1", 250, false),
+            version("data/sub/d.csv", "d1", 100, false),
+        ];
+        let mut point_in_time =
+            PointInTime::new(Some("data/"), recursive, as_of);
+        let mut objects: Vec<FileObject> = versions
+            .into_iter()
+            .filter_map(|version| point_in_time.push(version))
+            .collect();
+        objects.extend(point_in_time.finish());
+        objects
+            .iter()
+            .map(|object| {
+                (
+                    object.name().to_string(),
+                    object.version().map(|v| v.id().to_string()),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_point_in_time() {
+        let names = |objects: Vec<(String, Option<String>)>| {
+            objects
+                .into_iter()
+                .map(|(name, id)| {
+                    format!("{}@{}", name, id.unwrap_or_default())
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(resolve(true, 150)),
+            vec!["data/a.csv@a1", "data/b.csv@b1", "data/sub/d.csv@d1"]
+        );
+        // b.csv was deleted, c.csv created
+        assert_eq!(
+            names(resolve(true, 260)),
+            vec!["data/a.csv@a1", "data/sub/c.csv@c1", "data/sub/d.csv@d1"]
+        );
+        assert!(resolve(true, 50).is_empty());
+        assert_eq!(
+            names(resolve(false, 400)),
+            vec!["data/a.csv@a2", "data/sub/@"]
+        );
+    }
+}
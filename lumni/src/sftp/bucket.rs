@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use ssh2::Sftp;
+
+use super::config::{validate_config, SftpHost};
+use super::list::list_files;
+use super::session::{connect, sftp_error};
+use crate::base::config::EnvironmentConfig;
+use crate::base::object_metadata::ObjectMetadata;
+use crate::handlers::object_store::ObjectStoreTrait;
+use crate::table::FileObjectTable;
+use crate::{FileObjectFilter, LumniError};
+
+// permissions of directories created for uploads
+const SFTP_DIRECTORY_MODE: i32 = 0o755;
+
+// a remote server, keys are paths from its root or, when they start
+// with "~/", from the home directory of the user
+#[derive(Clone)]
+pub struct SftpBucket {
+    name: String,
+    config: EnvironmentConfig,
+    host: SftpHost,
+    // connected on first use, and reused for the next operations
+    sftp: Arc<Mutex<Option<Sftp>>>,
+}
+
+impl SftpBucket {
+    pub fn new(
+        name: &str,
+        mut config: EnvironmentConfig,
+    ) -> Result<SftpBucket, LumniError> {
+        validate_config(&mut config)?;
+        let host = SftpHost::parse(name, &config)?;
+        Ok(SftpBucket {
+            name: name.to_string(),
+            config,
+            host,
+            sftp: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    // relative paths are resolved by the server from the home directory
+    pub fn key_path(&self, key: &str) -> PathBuf {
+        match key.strip_prefix('~') {
+            Some(key) => PathBuf::from(".").join(key.trim_start_matches('/')),
+            None => PathBuf::from("/").join(key),
+        }
+    }
+
+    pub fn with_sftp<T>(
+        &self,
+        f: impl FnOnce(&Sftp) -> Result<T, LumniError>,
+    ) -> Result<T, LumniError> {
+        let mut sftp = self.sftp.lock().unwrap();
+        if sftp.is_none() {
+            *sftp = Some(connect(&self.host, &self.config)?);
+        }
+        f(sftp.as_ref().unwrap())
+    }
+}
+
+impl fmt::Debug for SftpBucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SftpBucket")
+            .field("name", &self.name)
+            .field("host", &self.host)
+            .finish()
+    }
+}
+
+#[async_trait(?Send)]
+impl ObjectStoreTrait for SftpBucket {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn config(&self) -> &EnvironmentConfig {
+        &self.config
+    }
+
+    async fn list_files(
+        &self,
+        prefix: Option<&str>,
+        _selected_columns: &Option<Vec<&str>>,
+        recursive: bool,
+        max_keys: Option<u32>,
+        filter: &Option<FileObjectFilter>,
+        table: &mut FileObjectTable,
+    ) -> Result<(), LumniError> {
+        list_files(self, prefix, recursive, max_keys, filter, table).await
+    }
+
+    async fn get_object(
+        &self,
+        key: &str,
+        data: &mut Vec<u8>,
+    ) -> Result<(), LumniError> {
+        let path = self.key_path(key);
+        self.with_sftp(|sftp| {
+            let mut file =
+                sftp.open(&path).map_err(|err| sftp_error(err, &path))?;
+            file.read_to_end(data)?;
+            Ok(())
+        })
+    }
+
+    async fn head_object(
+        &self,
+        key: &str,
+    ) -> Result<(u16, HashMap<String, String>), LumniError> {
+        let path = self.key_path(key);
+        let stat = match self.with_sftp(|sftp| {
+            sftp.stat(&path).map_err(|err| sftp_error(err, &path))
+        }) {
+            Ok(stat) if stat.is_file() => stat,
+            Ok(_) | Err(LumniError::NotFound(_)) => {
+                return Ok((404, HashMap::new()))
+            }
+            Err(err) => return Err(err),
+        };
+        let mut headers = HashMap::new();
+        if let Some(size) = stat.size {
+            headers.insert("content-length".to_string(), size.to_string());
+        }
+        Ok((200, headers))
+    }
+
+    async fn get_object_metadata(
+        &self,
+        key: &str,
+    ) -> Result<ObjectMetadata, LumniError> {
+        let path = self.key_path(key);
+        let stat = self.with_sftp(|sftp| {
+            sftp.stat(&path).map_err(|err| sftp_error(err, &path))
+        })?;
+        if !stat.is_file() {
+            return Err(LumniError::NotFound(key.to_string()));
+        }
+        Ok(ObjectMetadata::new(key)
+            .set_size(stat.size)
+            .set_modified(stat.mtime))
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), LumniError> {
+        let path = self.key_path(key);
+        self.with_sftp(|sftp| {
+            sftp.unlink(&path).map_err(|err| sftp_error(err, &path))
+        })
+    }
+
+    async fn put_object(
+        &self,
+        key: &str,
+        data: &[u8],
+    ) -> Result<(), LumniError> {
+        let path = self.key_path(key);
+        self.with_sftp(|sftp| {
+            if let Some(parent) = path.parent() {
+                create_dir_all(sftp, parent)?;
+            }
+            let mut file =
+                sftp.create(&path).map_err(|err| sftp_error(err, &path))?;
+            file.write_all(data)?;
+            Ok(())
+        })
+    }
+}
+
+// keys can name directories that do not exist yet, as on object stores
+fn create_dir_all(sftp: &Sftp, path: &Path) -> Result<(), LumniError> {
+    let mut missing: Vec<&Path> = path
+        .ancestors()
+        .take_while(|dir| {
+            !dir.as_os_str().is_empty()
+                && *dir != Path::new("/")
+                && *dir != Path::new(".")
+                && sftp.stat(dir).is_err()
+        })
+        .collect();
+    missing.reverse();
+    for dir in missing {
+        sftp.mkdir(dir, SFTP_DIRECTORY_MODE)
+            .map_err(|err| sftp_error(err, dir))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use std::env;
+use std::path::PathBuf;
+
+use crate::{EnvironmentConfig, LumniError};
+
+const SFTP_DEFAULT_PORT: u16 = 22;
+// tried in order when no key file is configured and ssh-agent has no
+// usable identity, as ssh does
+const DEFAULT_KEY_FILES: [&str; 3] = ["id_ed25519", "id_ecdsa", "id_rsa"];
+
+// server to connect to, from the "bucket" part of sftp://user@host:port/path
+#[derive(Debug, Clone, PartialEq)]
+pub struct SftpHost {
+    pub user: String,
+    pub host: String,
+    pub port: u16,
+}
+
+impl SftpHost {
+    pub fn parse(
+        name: &str,
+        config: &EnvironmentConfig,
+    ) -> Result<SftpHost, LumniError> {
+        let invalid = || {
+            LumniError::Config(format!(
+                "Invalid sftp host \"{}\", expected [user@]host[:port]",
+                name
+            ))
+        };
+        let (user, host_port) = match name.rsplit_once('@') {
+            Some((user, host_port)) if !user.is_empty() => {
+                (Some(user.to_string()), host_port)
+            }
+            Some(_) => return Err(invalid()),
+            None => (None, name),
+        };
+        let (host, port) = match host_port.rsplit_once(':') {
+            Some((host, port)) => {
+                (host, port.parse::<u16>().map_err(|_| invalid())?)
+            }
+            None => (host_port, SFTP_DEFAULT_PORT),
+        };
+        if host.is_empty() {
+            return Err(invalid());
+        }
+        let user = user
+            .or_else(|| config.get("SFTP_USER").cloned())
+            .or_else(|| env::var("USER").ok())
+            .ok_or_else(|| {
+                LumniError::Config(format!(
+                    "No user for {}, add it as user@{} or set SFTP_USER",
+                    host, host
+                ))
+            })?;
+        Ok(SftpHost {
+            user,
+            host: host.to_string(),
+            port,
+        })
+    }
+}
+
+pub fn validate_config(
+    config: &mut EnvironmentConfig,
+) -> Result<(), LumniError> {
+    for key in [
+        "SFTP_USER",
+        "SFTP_PRIVATE_KEY_FILE",
+        "SFTP_PRIVATE_KEY_PASSPHRASE",
+        "SFTP_KNOWN_HOSTS_FILE",
+        "SFTP_STRICT_HOST_KEY_CHECKING",
+    ] {
+        if !config.contains_key(key) {
+            if let Ok(value) = env::var(key) {
+                config.insert(key.to_string(), value);
+            }
+        }
+    }
+    if let Some(value) = config.get("SFTP_STRICT_HOST_KEY_CHECKING") {
+        if !matches!(value.as_str(), "true" | "false") {
+            return Err(LumniError::Config(format!(
+                "SFTP_STRICT_HOST_KEY_CHECKING should be true or false, got \
+                 \"{}\"",
+                value
+            )));
+        }
+    }
+    if let Some(key_file) = config.get("SFTP_PRIVATE_KEY_FILE") {
+        if !expand_home(key_file).is_file() {
+            return Err(LumniError::Config(format!(
+                "SFTP_PRIVATE_KEY_FILE {} does not exist",
+                key_file
+            )));
+        }
+    }
+    Ok(())
+}
+
+// unknown hosts are rejected unless this is set to false, a changed host
+// key is always rejected
+pub fn strict_host_key_checking(config: &EnvironmentConfig) -> bool {
+    config
+        .get("SFTP_STRICT_HOST_KEY_CHECKING")
+        .is_none_or(|value| value != "false")
+}
+
+pub fn known_hosts_file(config: &EnvironmentConfig) -> PathBuf {
+    match config.get("SFTP_KNOWN_HOSTS_FILE") {
+        Some(path) => expand_home(path),
+        None => expand_home("~/.ssh/known_hosts"),
+    }
+}
+
+// the configured key file, or the default keys in ~/.ssh that exist
+pub fn private_key_files(config: &EnvironmentConfig) -> Vec<PathBuf> {
+    match config.get("SFTP_PRIVATE_KEY_FILE") {
+        Some(path) => vec![expand_home(path)],
+        None => DEFAULT_KEY_FILES
+            .iter()
+            .map(|name| expand_home(&format!("~/.ssh/{}", name)))
+            .filter(|path| path.is_file())
+            .collect(),
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), env::var("HOME")) {
+        (Some(path), Ok(home)) => PathBuf::from(home).join(path),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_host() {
+        let config = EnvironmentConfig::default();
+        assert_eq!(
+            SftpHost::parse("alice@example.com:2222", &config).unwrap(),
+            SftpHost {
+                user: "alice".to_string(),
+                host: "example.com".to_string(),
+                port: 2222,
+            }
+        );
+        let host = SftpHost::parse("bob@10.0.0.1", &config).unwrap();
+        assert_eq!((host.user.as_str(), host.port), ("bob", 22));
+        assert!(SftpHost::parse("alice@", &config).is_err());
+        assert!(SftpHost::parse("alice@host:ssh", &config).is_err());
+    }
+}
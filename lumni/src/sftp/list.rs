@@ -0,0 +1,113 @@
+use std::collections::VecDeque;
+use std::path::Path;
+
+use ssh2::Sftp;
+
+use super::bucket::SftpBucket;
+use super::session::sftp_error;
+use crate::table::{FileObjectTable, Table};
+use crate::{FileObject, FileObjectFilter, LumniError};
+
+pub async fn list_files(
+    sftp_bucket: &SftpBucket,
+    prefix: Option<&str>,
+    recursive: bool,
+    max_keys: Option<u32>,
+    filter: &Option<FileObjectFilter>,
+    table: &mut FileObjectTable,
+) -> Result<(), LumniError> {
+    let max_files = max_keys.map_or(usize::MAX, |max| max as usize);
+    let prefix = prefix.unwrap_or("");
+
+    // a prefix without trailing slash can name a single file
+    if !prefix.is_empty() && !prefix.ends_with('/') {
+        let path = sftp_bucket.key_path(prefix);
+        let stat = match sftp_bucket.with_sftp(|sftp| {
+            sftp.stat(&path).map_err(|err| sftp_error(err, &path))
+        }) {
+            Ok(stat) => stat,
+            Err(LumniError::NotFound(_)) => return Ok(()),
+            Err(err) => return Err(err),
+        };
+        if stat.is_file() {
+            let file_object = FileObject::new(
+                prefix.to_string(),
+                stat.size.unwrap_or(0),
+                stat.mtime,
+                None,
+            );
+            if filter.as_ref().is_none_or(|f| f.matches(&file_object)) {
+                table.add_file_objects(vec![file_object]).await?;
+            }
+            return Ok(());
+        }
+    }
+
+    let mut directory_stack = VecDeque::new();
+    directory_stack.push_back(match prefix {
+        "" => String::new(),
+        prefix => format!("{}/", prefix.trim_end_matches('/')),
+    });
+
+    while let Some(directory) = directory_stack.pop_front() {
+        let path = sftp_bucket.key_path(&directory);
+        let entries = sftp_bucket
+            .with_sftp(|sftp| read_directory(sftp, &path, &directory))?;
+
+        let mut temp_file_objects = Vec::new();
+        for (file_object, is_dir) in entries {
+            if is_dir {
+                if recursive {
+                    directory_stack.push_back(file_object.name().to_string());
+                }
+                if filter.is_none() {
+                    temp_file_objects.push(file_object);
+                }
+            } else if filter.as_ref().is_none_or(|f| f.matches(&file_object)) {
+                temp_file_objects.push(file_object);
+            }
+        }
+
+        let max_to_add = max_files.saturating_sub(table.len());
+        if !temp_file_objects.is_empty() && max_to_add > 0 {
+            let objects_to_add = temp_file_objects
+                .drain(..)
+                .take(max_to_add)
+                .collect::<Vec<_>>();
+            table.add_file_objects(objects_to_add).await?;
+        }
+        if table.len() >= max_files {
+            break;
+        }
+    }
+    Ok(())
+}
+
+// entries of a directory, named by their key and sorted, directories
+// end with a slash as common prefixes do on object stores
+fn read_directory(
+    sftp: &Sftp,
+    path: &Path,
+    directory: &str,
+) -> Result<Vec<(FileObject, bool)>, LumniError> {
+    let mut entries: Vec<(FileObject, bool)> = sftp
+        .readdir(path)
+        .map_err(|err| sftp_error(err, path))?
+        .into_iter()
+        .filter_map(|(path, stat)| {
+            let name = path.file_name()?.to_string_lossy().to_string();
+            if stat.is_dir() {
+                let name = format!("{}{}/", directory, name);
+                Some((FileObject::new(name, 0, None, None), true))
+            } else if stat.is_file() {
+                let name = format!("{}{}", directory, name);
+                let size = stat.size.unwrap_or(0);
+                Some((FileObject::new(name, size, stat.mtime, None), false))
+            } else {
+                None // sockets, devices and symlinks that do not resolve
+            }
+        })
+        .collect();
+    entries.sort_by(|a, b| a.0.name().cmp(b.0.name()));
+    Ok(entries)
+}
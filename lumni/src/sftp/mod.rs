@@ -0,0 +1,6 @@
+// expose to library via backend mod
+pub mod backend;
+mod bucket;
+mod config;
+mod list;
+mod session;
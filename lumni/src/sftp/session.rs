@@ -0,0 +1,134 @@
+use std::net::TcpStream;
+use std::path::Path;
+
+use ssh2::{CheckResult, ErrorCode, KnownHostFileKind, Session, Sftp};
+
+use super::config::{
+    known_hosts_file, private_key_files, strict_host_key_checking, SftpHost,
+};
+use crate::{EnvironmentConfig, LumniError};
+
+// libssh2 blocks on each call, this bounds how long a stalled server
+// can hold up a command
+const SFTP_TIMEOUT_MS: u32 = 30_000;
+// status codes of the SFTP protocol
+const SSH_FX_NO_SUCH_FILE: i32 = 2;
+const SSH_FX_PERMISSION_DENIED: i32 = 3;
+
+pub fn connect(
+    host: &SftpHost,
+    config: &EnvironmentConfig,
+) -> Result<Sftp, LumniError> {
+    let address = format!("{}:{}", host.host, host.port);
+    log::info!("Connecting to sftp://{}@{}", host.user, address);
+    let tcp = TcpStream::connect(&address).map_err(|err| {
+        LumniError::Internal(format!(
+            "Failed to connect to {}: {}",
+            address, err
+        ))
+    })?;
+    let mut session = Session::new().map_err(|err| ssh_error(err, &address))?;
+    session.set_tcp_stream(tcp);
+    session.set_timeout(SFTP_TIMEOUT_MS);
+    session
+        .handshake()
+        .map_err(|err| ssh_error(err, &address))?;
+
+    verify_host_key(&session, host, config)?;
+    authenticate(&session, host, config)?;
+    session.sftp().map_err(|err| ssh_error(err, &address))
+}
+
+// the server must be in known_hosts, as ssh would check on first connect
+fn verify_host_key(
+    session: &Session,
+    host: &SftpHost,
+    config: &EnvironmentConfig,
+) -> Result<(), LumniError> {
+    let (key, _) = session.host_key().ok_or_else(|| {
+        LumniError::Internal(format!("No host key received from {}", host.host))
+    })?;
+    let path = known_hosts_file(config);
+    let mut known_hosts = session
+        .known_hosts()
+        .map_err(|err| ssh_error(err, &host.host))?;
+    if path.is_file() {
+        known_hosts
+            .read_file(&path, KnownHostFileKind::OpenSSH)
+            .map_err(|err| ssh_error(err, &path.display().to_string()))?;
+    }
+    match known_hosts.check_port(&host.host, host.port, key) {
+        CheckResult::Match => Ok(()),
+        CheckResult::NotFound if !strict_host_key_checking(config) => {
+            log::warn!(
+                "Host key of {} is not in {}",
+                host.host,
+                path.display()
+            );
+            Ok(())
+        }
+        CheckResult::NotFound => Err(LumniError::AccessDenied(format!(
+            "Host key of {} is not in {}, connect once with ssh to add it \
+             or set SFTP_STRICT_HOST_KEY_CHECKING=false",
+            host.host,
+            path.display()
+        ))),
+        CheckResult::Mismatch => Err(LumniError::AccessDenied(format!(
+            "Host key of {} does not match the key in {}",
+            host.host,
+            path.display()
+        ))),
+        CheckResult::Failure => Err(LumniError::Internal(format!(
+            "Failed to check the host key of {}",
+            host.host
+        ))),
+    }
+}
+
+// ssh-agent first, then the key files, the same order ssh uses
+fn authenticate(
+    session: &Session,
+    host: &SftpHost,
+    config: &EnvironmentConfig,
+) -> Result<(), LumniError> {
+    if !config.contains_key("SFTP_PRIVATE_KEY_FILE")
+        && session.userauth_agent(&host.user).is_ok()
+    {
+        return Ok(());
+    }
+    let passphrase = config.get("SFTP_PRIVATE_KEY_PASSPHRASE");
+    for key_file in private_key_files(config) {
+        match session.userauth_pubkey_file(
+            &host.user,
+            None,
+            &key_file,
+            passphrase.map(String::as_str),
+        ) {
+            Ok(()) => return Ok(()),
+            Err(err) => {
+                log::debug!("Key {} not accepted: {}", key_file.display(), err)
+            }
+        }
+    }
+    Err(LumniError::AccessDenied(format!(
+        "{}@{}, no key was accepted, add one to ssh-agent or set \
+         SFTP_PRIVATE_KEY_FILE",
+        host.user, host.host
+    )))
+}
+
+pub fn sftp_error(err: ssh2::Error, path: &Path) -> LumniError {
+    match err.code() {
+        ErrorCode::SFTP(SSH_FX_NO_SUCH_FILE) => {
+            LumniError::NotFound(path.display().to_string())
+        }
+        ErrorCode::SFTP(SSH_FX_PERMISSION_DENIED) => {
+            LumniError::AccessDenied(path.display().to_string())
+        }
+        _ => ssh_error(err, &path.display().to_string()),
+    }
+}
+
+fn ssh_error(err: ssh2::Error, context: &str) -> LumniError {
+    LumniError::Internal(format!("{}: {}", context, err))
+}
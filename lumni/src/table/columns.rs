@@ -1,7 +1,11 @@
 use std::any::Any;
 use std::fmt::Debug;
 
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+
+use super::schema::{to_f64, to_i32, to_text, to_u64, ColumnType};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TableColumnValue {
     Int32Column(i32),
     Uint64Column(u64),
@@ -15,12 +19,30 @@ pub enum TableColumnValue {
 
 pub trait TableColumn: Debug {
     fn len(&self) -> usize;
+    // drops the values, and frees the memory they took
+    fn clear(&mut self);
     fn append(&mut self, value: TableColumnValue) -> Result<(), String>;
+    fn get_value(&self, index: usize) -> Option<TableColumnValue>;
     fn as_any(&self) -> &dyn Any;
+    fn data_type(&self) -> ColumnType;
+    fn is_nullable(&self) -> bool;
+    fn clone_box(&self) -> Box<dyn TableColumn>;
+    // converts a value to the type stored in this column,
+    // e.g. a string "42" for an integer column
+    fn coerce(
+        &self,
+        value: TableColumnValue,
+    ) -> Result<TableColumnValue, String>;
 }
 
 macro_rules! create_column_types {
-    ($TypeName:ident, $OptionalTypeName:ident, $ValueType:ty) => {
+    (
+        $TypeName:ident,
+        $OptionalTypeName:ident,
+        $ValueType:ty,
+        $DataType:expr,
+        $convert:ident
+    ) => {
         #[derive(Debug, Clone)]
         pub struct $TypeName(pub Vec<$ValueType>);
 
@@ -46,6 +68,10 @@ macro_rules! create_column_types {
                 self.0.len()
             }
 
+            fn clear(&mut self) {
+                self.0 = Vec::new();
+            }
+
             fn append(
                 &mut self,
                 value: TableColumnValue,
@@ -58,9 +84,37 @@ macro_rules! create_column_types {
                 }
             }
 
+            fn get_value(&self, index: usize) -> Option<TableColumnValue> {
+                self.0
+                    .get(index)
+                    .map(|val| TableColumnValue::$TypeName(val.clone()))
+            }
+
             fn as_any(&self) -> &dyn Any {
                 self
             }
+
+            fn data_type(&self) -> ColumnType {
+                $DataType
+            }
+
+            fn is_nullable(&self) -> bool {
+                false
+            }
+
+            fn clone_box(&self) -> Box<dyn TableColumn> {
+                Box::new(self.clone())
+            }
+
+            fn coerce(
+                &self,
+                value: TableColumnValue,
+            ) -> Result<TableColumnValue, String> {
+                match $convert(&value)? {
+                    Some(val) => Ok(TableColumnValue::$TypeName(val)),
+                    None => Err("NULL in a non-nullable column".to_string()),
+                }
+            }
         }
 
         impl TableColumn for $OptionalTypeName {
@@ -68,6 +122,10 @@ macro_rules! create_column_types {
                 self.0.len()
             }
 
+            fn clear(&mut self) {
+                self.0 = Vec::new();
+            }
+
             fn append(
                 &mut self,
                 value: TableColumnValue,
@@ -80,17 +138,66 @@ macro_rules! create_column_types {
                 }
             }
 
+            fn get_value(&self, index: usize) -> Option<TableColumnValue> {
+                self.0
+                    .get(index)
+                    .map(|val| TableColumnValue::$OptionalTypeName(val.clone()))
+            }
+
             fn as_any(&self) -> &dyn Any {
                 self
             }
+
+            fn data_type(&self) -> ColumnType {
+                $DataType
+            }
+
+            fn is_nullable(&self) -> bool {
+                true
+            }
+
+            fn clone_box(&self) -> Box<dyn TableColumn> {
+                Box::new(self.clone())
+            }
+
+            fn coerce(
+                &self,
+                value: TableColumnValue,
+            ) -> Result<TableColumnValue, String> {
+                Ok(TableColumnValue::$OptionalTypeName($convert(&value)?))
+            }
         }
     };
 }
 
-create_column_types!(Int32Column, OptionalInt32Column, i32);
-create_column_types!(Uint64Column, OptionalUint64Column, u64);
-create_column_types!(FloatColumn, OptionalFloatColumn, f64);
-create_column_types!(StringColumn, OptionalStringColumn, String);
+create_column_types!(
+    Int32Column,
+    OptionalInt32Column,
+    i32,
+    ColumnType::Int,
+    to_i32
+);
+create_column_types!(
+    Uint64Column,
+    OptionalUint64Column,
+    u64,
+    ColumnType::Int,
+    to_u64
+);
+create_column_types!(
+    FloatColumn,
+    OptionalFloatColumn,
+    f64,
+    ColumnType::Float,
+    to_f64
+);
+create_column_types!(
+    StringColumn,
+    OptionalStringColumn,
+    String,
+    ColumnType::String,
+    to_text
+);
 
 impl TableColumnValue {
     pub fn to_string(&self) -> String {
@@ -0,0 +1,207 @@
+use core::fmt;
+use std::sync::Arc;
+
+use crate::table::schema::coerce_row;
+use crate::table::{OptionalStringColumn, OptionalUint64Column, StringColumn};
+use crate::{Table, TableCallback, TableColumn, TableColumnValue, TableRow};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffChange {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl DiffChange {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiffChange::Added => "added",
+            DiffChange::Removed => "removed",
+            DiffChange::Modified => "modified",
+        }
+    }
+}
+
+// properties of an object on one side of a diff
+#[derive(Debug, Clone, Default)]
+pub struct DiffSide {
+    pub size: Option<u64>,
+    pub modified: Option<u64>,
+    pub checksum: Option<String>,
+}
+
+// differences between two locations, one row per key that was added,
+// removed or modified, as returned by "lumni diff"
+pub struct DiffTable {
+    columns: Vec<(String, Box<dyn TableColumn>)>, // Store columns in order
+    changes: Vec<(String, DiffChange)>,
+    callback: Option<Arc<dyn TableCallback>>,
+}
+
+impl DiffTable {
+    pub fn new() -> Self {
+        let mut table = Self {
+            columns: Vec::new(),
+            changes: Vec::new(),
+            callback: None,
+        };
+        table.add_column("key", Box::new(StringColumn(Vec::new())));
+        table.add_column("change", Box::new(StringColumn(Vec::new())));
+        for name in ["size_a", "size_b", "modified_a", "modified_b"] {
+            table.add_column(name, Box::new(OptionalUint64Column(Vec::new())));
+        }
+        for name in ["checksum_a", "checksum_b"] {
+            table.add_column(name, Box::new(OptionalStringColumn(Vec::new())));
+        }
+        table
+    }
+
+    pub fn add_change(
+        &mut self,
+        key: &str,
+        change: DiffChange,
+        a: DiffSide,
+        b: DiffSide,
+    ) -> Result<(), String> {
+        let row_data = vec![
+            (
+                "key".to_string(),
+                TableColumnValue::StringColumn(key.to_string()),
+            ),
+            (
+                "change".to_string(),
+                TableColumnValue::StringColumn(change.as_str().to_string()),
+            ),
+            (
+                "size_a".to_string(),
+                TableColumnValue::OptionalUint64Column(a.size),
+            ),
+            (
+                "size_b".to_string(),
+                TableColumnValue::OptionalUint64Column(b.size),
+            ),
+            (
+                "modified_a".to_string(),
+                TableColumnValue::OptionalUint64Column(a.modified),
+            ),
+            (
+                "modified_b".to_string(),
+                TableColumnValue::OptionalUint64Column(b.modified),
+            ),
+            (
+                "checksum_a".to_string(),
+                TableColumnValue::OptionalStringColumn(a.checksum),
+            ),
+            (
+                "checksum_b".to_string(),
+                TableColumnValue::OptionalStringColumn(b.checksum),
+            ),
+        ];
+        self.add_row(row_data)?;
+        self.changes.push((key.to_string(), change));
+        Ok(())
+    }
+
+    pub fn changes(&self) -> &[(String, DiffChange)] {
+        &self.changes
+    }
+}
+
+impl Default for DiffTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Table for DiffTable {
+    fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    fn add_column(&mut self, name: &str, column_type: Box<dyn TableColumn>) {
+        self.columns.push((name.to_string(), column_type));
+    }
+
+    fn columns(&self) -> &[(String, Box<dyn TableColumn>)] {
+        &self.columns
+    }
+
+    fn set_callback(&mut self, callback: Arc<dyn TableCallback>) {
+        self.callback = Some(callback);
+    }
+
+    fn add_row(
+        &mut self,
+        row_data: Vec<(String, TableColumnValue)>,
+    ) -> Result<(), String> {
+        let row_data = coerce_row(&self.columns, row_data)?;
+        if let Some(callback) = &self.callback {
+            let mut row = TableRow::new(row_data.clone(), Some(&print_row));
+            callback.on_row_add(&mut row);
+            if !callback.retain_rows() {
+                return Ok(());
+            }
+        }
+        for (column_name, value) in row_data {
+            if let Some((_, column)) = self
+                .columns
+                .iter_mut()
+                .find(|(name, _)| name == &column_name)
+            {
+                column.append(value)?;
+            } else {
+                return Err(format!("Column '{}' not found", column_name));
+            }
+        }
+        Ok(())
+    }
+
+    fn fmt_debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Table")
+            .field("callback", &"Callback Omitted")
+            .finish()?;
+
+        f.write_str("columns: {\n")?;
+        for (name, column) in &self.columns {
+            write!(f, "    {}: ", name)?;
+            write!(f, "{:?}", column)?;
+            f.write_str(",\n")?;
+        }
+        f.write_str("}\n")
+    }
+}
+
+// prints "+", "-" or "~" and the key, with the sizes when they differ
+fn print_row(row: &TableRow) {
+    let value = |name: &str| {
+        row.data()
+            .iter()
+            .find(|(column, _)| column == name)
+            .map(|(_, value)| value)
+    };
+    let key = match value("key") {
+        Some(TableColumnValue::StringColumn(key)) => key.as_str(),
+        _ => "",
+    };
+    let size = |name: &str| match value(name) {
+        Some(TableColumnValue::OptionalUint64Column(Some(size))) => {
+            size.to_string()
+        }
+        _ => "-".to_string(),
+    };
+    match value("change") {
+        Some(TableColumnValue::StringColumn(change)) if change == "added" => {
+            println!("+ {} ({})", key, size("size_b"))
+        }
+        Some(TableColumnValue::StringColumn(change)) if change == "removed" => {
+            println!("- {} ({})", key, size("size_a"))
+        }
+        _ => println!("~ {} ({} -> {})", key, size("size_a"), size("size_b")),
+    }
+}
+
+impl fmt::Debug for DiffTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_debug(f)
+    }
+}
@@ -0,0 +1,406 @@
+use serde_json::{Number, Value};
+
+use super::schema::Columns;
+use super::{
+    ColumnType, RowTemplate, Table, TableColumn, TableColumnValue, TableRow,
+    TableSchema,
+};
+use crate::utils::time::epoch_to_rfc3339_utc;
+
+// options for Table::to_csv, Table::to_jsonl and Table::to_markdown
+#[derive(Debug, Clone)]
+pub struct TableExportOptions {
+    columns: Option<Vec<String>>, // None exports all columns, in order
+    delimiter: char,              // csv only
+    header: bool,                 // csv only
+    schema: Option<TableSchema>,  // casts columns before export
+    max_rows: Option<usize>,      // None exports all rows
+}
+
+impl Default for TableExportOptions {
+    fn default() -> Self {
+        TableExportOptions {
+            columns: None,
+            delimiter: ',',
+            header: true,
+            schema: None,
+            max_rows: None,
+        }
+    }
+}
+
+impl TableExportOptions {
+    pub fn new() -> Self {
+        TableExportOptions::default()
+    }
+
+    pub fn set_columns(mut self, columns: Vec<String>) -> Self {
+        self.columns = Some(columns);
+        self
+    }
+
+    pub fn set_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn set_header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    // columns are cast to the schema types, timestamps are written
+    // as RFC 3339
+    pub fn set_schema(mut self, schema: TableSchema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    // the first rows only, e.g. to keep a preview short
+    pub fn set_max_rows(mut self, max_rows: Option<usize>) -> Self {
+        self.max_rows = max_rows;
+        self
+    }
+
+    pub fn delimiter(&self) -> char {
+        self.delimiter
+    }
+
+    fn row_count<T: Table + ?Sized>(&self, table: &T) -> usize {
+        self.max_rows
+            .map_or(table.len(), |max| max.min(table.len()))
+    }
+}
+
+impl TableRow<'_> {
+    // a single csv line, without line ending
+    pub fn to_csv(&self, delimiter: char) -> String {
+        csv_line(self.data().iter().map(|(_, value)| value), delimiter)
+    }
+
+    // a single JSON object, without line ending
+    pub fn to_json(&self) -> String {
+        json_object(
+            self.data()
+                .iter()
+                .map(|(name, value)| (name.as_str(), value)),
+        )
+    }
+}
+
+pub(super) fn table_to_csv<T: Table + ?Sized>(
+    table: &T,
+    options: &TableExportOptions,
+) -> Result<String, String> {
+    let cast = cast_columns(table, options)?;
+    let columns = selected_columns(cast.as_deref(), table, options)?;
+    let mut output = String::new();
+    if options.header {
+        let names: Vec<String> = columns
+            .iter()
+            .map(|(name, _, _)| csv_escape(name, options.delimiter))
+            .collect();
+        output.push_str(&names.join(&options.delimiter.to_string()));
+        output.push('\n');
+    }
+    for row in rows(&columns, options.row_count(table)) {
+        let values = row.iter().map(|(_, value)| value);
+        output.push_str(&csv_line(values, options.delimiter));
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+pub(super) fn table_to_jsonl<T: Table + ?Sized>(
+    table: &T,
+    options: &TableExportOptions,
+) -> Result<String, String> {
+    let cast = cast_columns(table, options)?;
+    let columns = selected_columns(cast.as_deref(), table, options)?;
+    let mut output = String::new();
+    for row in rows(&columns, options.row_count(table)) {
+        output.push_str(&json_object(
+            row.iter().map(|(name, value)| (*name, value)),
+        ));
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+// a line per row, formatted by the template
+pub(super) fn table_to_template<T: Table + ?Sized>(
+    table: &T,
+    template: &RowTemplate,
+    options: &TableExportOptions,
+) -> Result<String, String> {
+    let cast = cast_columns(table, options)?;
+    let columns = selected_columns(cast.as_deref(), table, options)?;
+    let mut output = String::new();
+    for row in rows(&columns, options.row_count(table)) {
+        let data = row
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect();
+        output.push_str(&template.render(&TableRow::new(data, None))?);
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+// a GitHub flavored markdown table, NULL is an empty cell
+pub(super) fn table_to_markdown<T: Table + ?Sized>(
+    table: &T,
+    options: &TableExportOptions,
+) -> Result<String, String> {
+    let cast = cast_columns(table, options)?;
+    let columns = selected_columns(cast.as_deref(), table, options)?;
+    let names: Vec<String> = columns
+        .iter()
+        .map(|(name, _, _)| markdown_escape(name))
+        .collect();
+    let mut output = format!("| {} |\n", names.join(" | "));
+    output.push_str(&format!("|{}\n", " --- |".repeat(columns.len())));
+    for row in rows(&columns, options.row_count(table)) {
+        let values: Vec<String> = row
+            .iter()
+            .map(|(_, value)| match value {
+                TableColumnValue::OptionalInt32Column(None)
+                | TableColumnValue::OptionalUint64Column(None)
+                | TableColumnValue::OptionalFloatColumn(None)
+                | TableColumnValue::OptionalStringColumn(None) => String::new(),
+                value => markdown_escape(&value.to_string()),
+            })
+            .collect();
+        output.push_str(&format!("| {} |\n", values.join(" | ")));
+    }
+    Ok(output)
+}
+
+type ColumnRef<'a> = (&'a str, &'a dyn TableColumn, bool); // bool: timestamp
+
+fn cast_columns<T: Table + ?Sized>(
+    table: &T,
+    options: &TableExportOptions,
+) -> Result<Option<Columns>, String> {
+    options
+        .schema
+        .as_ref()
+        .map(|schema| schema.cast_columns(table.columns()))
+        .transpose()
+}
+
+fn selected_columns<'a, T: Table + ?Sized>(
+    cast: Option<&'a [(String, Box<dyn TableColumn>)]>,
+    table: &'a T,
+    options: &'a TableExportOptions,
+) -> Result<Vec<ColumnRef<'a>>, String> {
+    let columns = cast.unwrap_or(table.columns());
+    let is_timestamp = |name: &str| {
+        options.schema.as_ref().is_some_and(|schema| {
+            schema.column(name).is_some_and(|column| {
+                column.column_type() == ColumnType::Timestamp
+            })
+        })
+    };
+    let column_ref = |(name, column): &'a (String, Box<dyn TableColumn>)| {
+        (name.as_str(), column.as_ref(), is_timestamp(name))
+    };
+    match &options.columns {
+        None => Ok(columns.iter().map(column_ref).collect()),
+        Some(selected) => selected
+            .iter()
+            .map(|selected| {
+                columns
+                    .iter()
+                    .find(|(name, _)| name == selected)
+                    .map(column_ref)
+                    .ok_or_else(|| format!("Column '{}' not found", selected))
+            })
+            .collect(),
+    }
+}
+
+fn rows<'a>(
+    columns: &'a [ColumnRef<'a>],
+    len: usize,
+) -> impl Iterator<Item = Vec<(&'a str, TableColumnValue)>> + 'a {
+    (0..len).map(move |index| {
+        columns
+            .iter()
+            .filter_map(|(name, column, timestamp)| {
+                let value = column.get_value(index)?;
+                if *timestamp {
+                    Some((*name, timestamp_value(value)))
+                } else {
+                    Some((*name, value))
+                }
+            })
+            .collect()
+    })
+}
+
+fn timestamp_value(value: TableColumnValue) -> TableColumnValue {
+    match value {
+        TableColumnValue::Uint64Column(val)
+        | TableColumnValue::OptionalUint64Column(Some(val)) => {
+            TableColumnValue::StringColumn(
+                epoch_to_rfc3339_utc(val).unwrap_or_else(|_| val.to_string()),
+            )
+        }
+        _ => TableColumnValue::OptionalStringColumn(None),
+    }
+}
+
+fn csv_line<'a>(
+    values: impl Iterator<Item = &'a TableColumnValue>,
+    delimiter: char,
+) -> String {
+    values
+        .map(|value| match value {
+            // NULL is written as an empty field
+            TableColumnValue::OptionalInt32Column(None)
+            | TableColumnValue::OptionalUint64Column(None)
+            | TableColumnValue::OptionalFloatColumn(None)
+            | TableColumnValue::OptionalStringColumn(None) => String::new(),
+            value => csv_escape(&value.to_string(), delimiter),
+        })
+        .collect::<Vec<_>>()
+        .join(&delimiter.to_string())
+}
+
+// quotes fields that contain the delimiter, quotes or line breaks (RFC 4180)
+fn csv_escape(field: &str, delimiter: char) -> String {
+    if field.contains(delimiter)
+        || field.contains('"')
+        || field.contains('\n')
+        || field.contains('\r')
+    {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// pipes would end the cell, line breaks the row
+fn markdown_escape(field: &str) -> String {
+    field.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+// written by hand to keep the column order, serde_json::Map sorts keys
+fn json_object<'a>(
+    values: impl Iterator<Item = (&'a str, &'a TableColumnValue)>,
+) -> String {
+    let fields: Vec<String> = values
+        .map(|(name, value)| {
+            format!("{}:{}", Value::from(name), json_value(value))
+        })
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+pub(super) fn json_value(value: &TableColumnValue) -> Value {
+    match value {
+        TableColumnValue::Int32Column(val)
+        | TableColumnValue::OptionalInt32Column(Some(val)) => Value::from(*val),
+        TableColumnValue::Uint64Column(val)
+        | TableColumnValue::OptionalUint64Column(Some(val)) => {
+            Value::from(*val)
+        }
+        TableColumnValue::FloatColumn(val)
+        | TableColumnValue::OptionalFloatColumn(Some(val)) => {
+            // NaN and infinity have no JSON representation
+            Number::from_f64(*val).map_or(Value::Null, Value::Number)
+        }
+        TableColumnValue::StringColumn(val)
+        | TableColumnValue::OptionalStringColumn(Some(val)) => {
+            Value::from(val.as_str())
+        }
+        _ => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileObjectTable;
+
+    fn table() -> FileObjectTable {
+        let mut table = FileObjectTable::new(&None, None);
+        for (name, size, modified) in
+            [("a \"b\".txt", 1, Some(1700000000)), ("c;d", 2, None)]
+        {
+            table
+                .add_row(vec![
+                    (
+                        "name".to_string(),
+                        TableColumnValue::StringColumn(name.to_string()),
+                    ),
+                    ("size".to_string(), TableColumnValue::Uint64Column(size)),
+                    (
+                        "modified".to_string(),
+                        TableColumnValue::OptionalUint64Column(modified),
+                    ),
+                ])
+                .unwrap();
+        }
+        table
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let table = table();
+        assert_eq!(
+            table.to_csv(&TableExportOptions::new()).unwrap(),
+            "name,size,modified\n\"a \"\"b\"\".txt\",1,1700000000\nc;d,2,\n"
+        );
+        let options = TableExportOptions::new()
+            .set_columns(vec!["size".to_string(), "name".to_string()])
+            .set_delimiter(';')
+            .set_header(false);
+        assert_eq!(
+            table.to_csv(&options).unwrap(),
+            "1;\"a \"\"b\"\".txt\"\n2;\"c;d\"\n"
+        );
+        let options =
+            TableExportOptions::new().set_columns(vec!["owner".to_string()]);
+        assert!(table.to_csv(&options).is_err());
+
+        let schema = TableSchema::from_columns(table.columns())
+            .cast("modified", ColumnType::Timestamp)
+            .unwrap();
+        let options = TableExportOptions::new()
+            .set_columns(vec!["modified".to_string()])
+            .set_schema(schema);
+        assert_eq!(
+            table.to_csv(&options).unwrap(),
+            "modified\n2023-11-14T22:13:20Z\n\n"
+        );
+    }
+
+    #[test]
+    fn test_to_jsonl() {
+        assert_eq!(
+            table().to_jsonl(&TableExportOptions::new()).unwrap(),
+            "{\"name\":\"a \\\"b\\\".txt\",\"size\":1,\"modified\":1700000000}\n\
+             {\"name\":\"c;d\",\"size\":2,\"modified\":null}\n"
+        );
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let table = table();
+        assert_eq!(
+            table.to_markdown(&TableExportOptions::new()).unwrap(),
+            "| name | size | modified |\n| --- | --- | --- |\n\
+             | a \"b\".txt | 1 | 1700000000 |\n| c;d | 2 |  |\n"
+        );
+        let options = TableExportOptions::new()
+            .set_columns(vec!["size".to_string()])
+            .set_max_rows(Some(1));
+        assert_eq!(
+            table.to_markdown(&options).unwrap(),
+            "| size |\n| --- |\n| 1 |\n"
+        );
+        assert_eq!(markdown_escape("a|b\nc"), "a\\|b c");
+    }
+}
@@ -1,17 +1,30 @@
 use core::{fmt, panic};
 use std::collections::HashMap;
+use std::future::Future;
 use std::sync::Arc;
 
+use log::warn;
+use tracing::info_span;
+
+use crate::base::memory::{row_size, MemoryBudget};
+use crate::table::schema::coerce_row;
 use crate::table::{
-    OptionalUint64Column, StringColumn, TableRow, Uint64Column,
+    OptionalInt32Column, OptionalStringColumn, OptionalUint64Column,
+    StringColumn, TableRow, Uint64Column,
 };
 use crate::utils::formatters::{bytes_human_readable, time_human_readable};
-use crate::{FileObject, Table, TableCallback, TableColumn, TableColumnValue};
+use crate::{
+    FileObject, FileObjectFilter, HeadBatch, LumniError, ObjectMetadata, Table,
+    TableCallback, TableColumn, TableColumnValue,
+};
 
 pub struct FileObjectTable {
     columns: Vec<(String, Box<dyn TableColumn>)>, // Store columns in order
     column_index: HashMap<String, usize>,         // store order of columns
     callback: Option<Arc<dyn TableCallback>>,
+    memory_budget: Option<MemoryBudget>,
+    retained_bytes: u64,
+    streaming: bool, // rows only go to the callback, see add_row
 }
 
 impl FileObjectTable {
@@ -23,9 +36,13 @@ impl FileObjectTable {
             columns: Vec::new(),
             column_index: HashMap::new(),
             callback,
+            memory_budget: None,
+            retained_bytes: 0,
+            streaming: false,
         };
 
-        // Define a list of valid column names
+        // Define a list of valid column names, checksum is not a default
+        // column as it may require reading every file
         let valid_columns = vec!["name", "size", "modified"];
 
         if let Some(columns) = selected_columns {
@@ -39,6 +56,22 @@ impl FileObjectTable {
                         "modified",
                         Box::new(OptionalUint64Column(Vec::new())),
                     ),
+                    "checksum" => table.add_column(
+                        "checksum",
+                        Box::new(OptionalStringColumn(Vec::new())),
+                    ),
+                    "storage_class" => table.add_column(
+                        "storage_class",
+                        Box::new(OptionalStringColumn(Vec::new())),
+                    ),
+                    "version_id" => table.add_column(
+                        "version_id",
+                        Box::new(OptionalStringColumn(Vec::new())),
+                    ),
+                    "is_latest" | "delete_marker" => table.add_column(
+                        column,
+                        Box::new(OptionalInt32Column(Vec::new())),
+                    ),
                     _ => panic!("Invalid column name: {}", column),
                 }
             }
@@ -61,6 +94,52 @@ impl FileObjectTable {
         }
         table
     }
+
+    // when the rows outgrow the budget, a table with a callback no longer
+    // keeps them, as the callback already received them (e.g. to print
+    // them). without a callback add_row fails instead, so a listing can be
+    // narrowed down before it takes all memory
+    pub fn set_memory_budget(mut self, budget: Option<MemoryBudget>) -> Self {
+        self.memory_budget = budget;
+        self
+    }
+
+    // true if the rows outgrew the memory budget and were dropped
+    pub fn is_streaming(&self) -> bool {
+        self.streaming
+    }
+
+    fn check_memory_budget(
+        &mut self,
+        row_data: &[(String, TableColumnValue)],
+    ) -> Result<(), String> {
+        let Some(budget) = self.memory_budget else {
+            return Ok(());
+        };
+        self.retained_bytes += row_size(row_data);
+        if !budget.exceeded_by(self.retained_bytes) {
+            return Ok(());
+        }
+        if self.callback.is_none() {
+            return Err(budget.exceeded_message(
+                &format!("Listing of more than {} rows", self.len()),
+                "narrow it down with a prefix, a filter or a lower maximum \
+                 of files",
+            ));
+        }
+        warn!(
+            "Listing exceeds the memory budget of {} bytes after {} rows, \
+             continuing without keeping the rows",
+            budget.limit(),
+            self.len()
+        );
+        for (_, column) in self.columns.iter_mut() {
+            column.clear();
+        }
+        self.retained_bytes = 0;
+        self.streaming = true;
+        Ok(())
+    }
 }
 
 impl Table for FileObjectTable {
@@ -80,6 +159,10 @@ impl Table for FileObjectTable {
         self.column_index.insert(name.to_string(), index);
     }
 
+    fn columns(&self) -> &[(String, Box<dyn TableColumn>)] {
+        &self.columns
+    }
+
     fn set_callback(&mut self, callback: Arc<dyn TableCallback>) {
         self.callback = Some(callback);
     }
@@ -88,9 +171,17 @@ impl Table for FileObjectTable {
         &mut self,
         row_data: Vec<(String, TableColumnValue)>,
     ) -> Result<(), String> {
+        let row_data = coerce_row(&self.columns, row_data)?;
         if let Some(callback) = &self.callback {
             let mut row = TableRow::new(row_data.clone(), Some(&print_row));
             callback.on_row_add(&mut row);
+            if !callback.retain_rows() || self.streaming {
+                return Ok(());
+            }
+        }
+        self.check_memory_budget(&row_data)?;
+        if self.streaming {
+            return Ok(());
         }
 
         for (column_name, value) in row_data {
@@ -148,6 +239,42 @@ fn print_row(row: &TableRow) {
         .map(|(_, value)| extract_u64_value(value))
         .flatten();
 
+    let checksum = row_data.iter().find_map(|(key, value)| match value {
+        TableColumnValue::OptionalStringColumn(Some(val))
+            if key == "checksum" =>
+        {
+            Some(val.as_str())
+        }
+        _ => None,
+    });
+
+    let storage_class = row_data.iter().find_map(|(key, value)| match value {
+        TableColumnValue::OptionalStringColumn(Some(val))
+            if key == "storage_class" =>
+        {
+            Some(val.as_str())
+        }
+        _ => None,
+    });
+
+    let version_id = row_data.iter().find_map(|(key, value)| match value {
+        TableColumnValue::OptionalStringColumn(Some(val))
+            if key == "version_id" =>
+        {
+            Some(val.as_str())
+        }
+        _ => None,
+    });
+    let is_flag_set = |column: &str| {
+        row_data.iter().any(|(key, value)| {
+            key == column
+                && matches!(
+                    value,
+                    TableColumnValue::OptionalInt32Column(Some(1))
+                )
+        })
+    };
+
     let name_without_trailing_slash = name.trim_end_matches('/');
     let mut name_to_print = if full_path {
         name_without_trailing_slash.to_string()
@@ -163,6 +290,23 @@ fn print_row(row: &TableRow) {
         name_to_print.push('/');
     }
 
+    if let Some(checksum) = checksum {
+        name_to_print = format!("{} {}", checksum, name_to_print);
+    }
+    if let Some(storage_class) = storage_class {
+        name_to_print = format!("{:19} {}", storage_class, name_to_print);
+    }
+    if let Some(version_id) = version_id {
+        name_to_print.push_str(&format!(" ({}", version_id));
+        if is_flag_set("is_latest") {
+            name_to_print.push_str(", latest");
+        }
+        if is_flag_set("delete_marker") {
+            name_to_print.push_str(", delete marker");
+        }
+        name_to_print.push(')');
+    }
+
     println!(
         "{}",
         format!(
@@ -183,6 +327,7 @@ impl FileObjectTable {
         &mut self,
         file_objects: Vec<FileObject>,
     ) -> Result<(), String> {
+        let _span = info_span!("table_build").entered();
         for file_object in file_objects {
             let mut row_data: Vec<(String, TableColumnValue)> = Vec::new();
 
@@ -202,10 +347,41 @@ impl FileObjectTable {
         Ok(())
     }
 
+    // as add_file_objects, for a listing that may not return size or
+    // modified time. When the filter needs them, objects without are
+    // completed by a batch of head requests first, e.g. of
+    // ObjectStoreTrait::get_object_metadata. The filter is applied after
+    pub async fn add_file_objects_with_head<F, Fut>(
+        &mut self,
+        file_objects: Vec<FileObject>,
+        filter: &Option<FileObjectFilter>,
+        batch: &HeadBatch,
+        head: F,
+    ) -> Result<(), String>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<ObjectMetadata, LumniError>>,
+    {
+        let Some(filter) = filter else {
+            return self.add_file_objects(file_objects).await;
+        };
+        let file_objects = if filter.needs_metadata() {
+            batch.complete(file_objects, head).await
+        } else {
+            file_objects
+        };
+        let matching = file_objects
+            .into_iter()
+            .filter(|file_object| filter.matches(file_object))
+            .collect();
+        self.add_file_objects(matching).await
+    }
+
     pub async fn add_rows(
         &mut self,
         rows: Vec<HashMap<String, TableColumnValue>>,
     ) -> Result<(), String> {
+        let _span = info_span!("table_build").entered();
         for row_data in rows {
             let mut row_vec: Vec<(String, TableColumnValue)> = Vec::new();
 
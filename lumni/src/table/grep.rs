@@ -0,0 +1,147 @@
+use core::fmt;
+use std::sync::Arc;
+
+use crate::table::schema::coerce_row;
+use crate::table::{StringColumn, Uint64Column};
+use crate::{Table, TableCallback, TableColumn, TableColumnValue, TableRow};
+
+// lines that match a pattern, one row per line, as returned by
+// "lumni grep". objects that could not be searched are kept aside
+pub struct GrepTable {
+    columns: Vec<(String, Box<dyn TableColumn>)>, // Store columns in order
+    matches: usize,
+    failed: Vec<(String, String)>, // uri and reason
+    callback: Option<Arc<dyn TableCallback>>,
+}
+
+impl GrepTable {
+    pub fn new() -> Self {
+        let mut table = Self {
+            columns: Vec::new(),
+            matches: 0,
+            failed: Vec::new(),
+            callback: None,
+        };
+        table.add_column("uri", Box::new(StringColumn(Vec::new())));
+        table.add_column("line", Box::new(Uint64Column(Vec::new())));
+        table.add_column("match", Box::new(StringColumn(Vec::new())));
+        table
+    }
+
+    // line numbers start at 1
+    pub fn add_match(
+        &mut self,
+        uri: &str,
+        line: u64,
+        text: &str,
+    ) -> Result<(), String> {
+        let row_data = vec![
+            (
+                "uri".to_string(),
+                TableColumnValue::StringColumn(uri.to_string()),
+            ),
+            ("line".to_string(), TableColumnValue::Uint64Column(line)),
+            (
+                "match".to_string(),
+                TableColumnValue::StringColumn(text.to_string()),
+            ),
+        ];
+        self.add_row(row_data)?;
+        self.matches += 1;
+        Ok(())
+    }
+
+    pub fn add_failed(&mut self, uri: &str, reason: &str) {
+        self.failed.push((uri.to_string(), reason.to_string()));
+    }
+
+    pub fn failed(&self) -> &[(String, String)] {
+        &self.failed
+    }
+}
+
+impl Default for GrepTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Table for GrepTable {
+    fn len(&self) -> usize {
+        self.matches
+    }
+
+    fn add_column(&mut self, name: &str, column_type: Box<dyn TableColumn>) {
+        self.columns.push((name.to_string(), column_type));
+    }
+
+    fn columns(&self) -> &[(String, Box<dyn TableColumn>)] {
+        &self.columns
+    }
+
+    fn set_callback(&mut self, callback: Arc<dyn TableCallback>) {
+        self.callback = Some(callback);
+    }
+
+    fn add_row(
+        &mut self,
+        row_data: Vec<(String, TableColumnValue)>,
+    ) -> Result<(), String> {
+        let row_data = coerce_row(&self.columns, row_data)?;
+        if let Some(callback) = &self.callback {
+            let mut row = TableRow::new(row_data.clone(), Some(&print_row));
+            callback.on_row_add(&mut row);
+            if !callback.retain_rows() {
+                return Ok(());
+            }
+        }
+        for (column_name, value) in row_data {
+            if let Some((_, column)) = self
+                .columns
+                .iter_mut()
+                .find(|(name, _)| name == &column_name)
+            {
+                column.append(value)?;
+            } else {
+                return Err(format!("Column '{}' not found", column_name));
+            }
+        }
+        Ok(())
+    }
+
+    fn fmt_debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Table")
+            .field("callback", &"Callback Omitted")
+            .finish()?;
+
+        f.write_str("columns: {\n")?;
+        for (name, column) in &self.columns {
+            write!(f, "    {}: ", name)?;
+            write!(f, "{:?}", column)?;
+            f.write_str(",\n")?;
+        }
+        f.write_str("}\n")
+    }
+}
+
+// prints uri:line:match, as grep does
+fn print_row(row: &TableRow) {
+    let mut uri = "";
+    let mut line = 0;
+    let mut text = "";
+    for (name, value) in row.data() {
+        match (name.as_str(), value) {
+            ("uri", TableColumnValue::StringColumn(value)) => uri = value,
+            ("line", TableColumnValue::Uint64Column(value)) => line = *value,
+            ("match", TableColumnValue::StringColumn(value)) => text = value,
+            _ => {}
+        }
+    }
+    println!("{}:{}:{}", uri, line, text);
+}
+
+impl fmt::Debug for GrepTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_debug(f)
+    }
+}
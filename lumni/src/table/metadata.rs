@@ -0,0 +1,184 @@
+use core::fmt;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use crate::base::object_metadata::ObjectMetadata;
+use crate::table::schema::coerce_row;
+use crate::table::{OptionalStringColumn, OptionalUint64Column, StringColumn};
+use crate::utils::time::epoch_to_rfc3339_utc;
+use crate::{Table, TableCallback, TableColumn, TableColumnValue, TableRow};
+
+// properties of objects, one row per object as returned by "lumni stat"
+pub struct ObjectMetadataTable {
+    columns: Vec<(String, Box<dyn TableColumn>)>, // Store columns in order
+    objects: Vec<ObjectMetadata>,
+    callback: Option<Arc<dyn TableCallback>>,
+}
+
+impl ObjectMetadataTable {
+    pub fn new() -> Self {
+        let mut table = Self {
+            columns: Vec::new(),
+            objects: Vec::new(),
+            callback: None,
+        };
+        table.add_column("key", Box::new(StringColumn(Vec::new())));
+        table.add_column("size", Box::new(OptionalUint64Column(Vec::new())));
+        table
+            .add_column("modified", Box::new(OptionalUint64Column(Vec::new())));
+        for name in
+            ["content_type", "storage_class", "etag", "metadata", "tags"]
+        {
+            table.add_column(name, Box::new(OptionalStringColumn(Vec::new())));
+        }
+        table
+    }
+
+    pub fn add_metadata(
+        &mut self,
+        metadata: ObjectMetadata,
+    ) -> Result<(), String> {
+        let optional = |value: Option<&str>| {
+            TableColumnValue::OptionalStringColumn(value.map(String::from))
+        };
+        let row_data = vec![
+            (
+                "key".to_string(),
+                TableColumnValue::StringColumn(metadata.key().to_string()),
+            ),
+            (
+                "size".to_string(),
+                TableColumnValue::OptionalUint64Column(metadata.size()),
+            ),
+            (
+                "modified".to_string(),
+                TableColumnValue::OptionalUint64Column(metadata.modified()),
+            ),
+            (
+                "content_type".to_string(),
+                optional(metadata.content_type()),
+            ),
+            (
+                "storage_class".to_string(),
+                optional(metadata.storage_class()),
+            ),
+            ("etag".to_string(), optional(metadata.etag())),
+            (
+                "metadata".to_string(),
+                TableColumnValue::OptionalStringColumn(map_to_json(
+                    metadata.metadata(),
+                )),
+            ),
+            (
+                "tags".to_string(),
+                TableColumnValue::OptionalStringColumn(map_to_json(
+                    metadata.tags(),
+                )),
+            ),
+        ];
+        self.add_row(row_data)?;
+        self.objects.push(metadata);
+        Ok(())
+    }
+
+    pub fn objects(&self) -> &[ObjectMetadata] {
+        &self.objects
+    }
+}
+
+// sorted, so the output is stable between runs
+fn map_to_json(map: &HashMap<String, String>) -> Option<String> {
+    if map.is_empty() {
+        return None;
+    }
+    let sorted: BTreeMap<&String, &String> = map.iter().collect();
+    serde_json::to_string(&sorted).ok()
+}
+
+impl Default for ObjectMetadataTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Table for ObjectMetadataTable {
+    fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    fn add_column(&mut self, name: &str, column_type: Box<dyn TableColumn>) {
+        self.columns.push((name.to_string(), column_type));
+    }
+
+    fn columns(&self) -> &[(String, Box<dyn TableColumn>)] {
+        &self.columns
+    }
+
+    fn set_callback(&mut self, callback: Arc<dyn TableCallback>) {
+        self.callback = Some(callback);
+    }
+
+    fn add_row(
+        &mut self,
+        row_data: Vec<(String, TableColumnValue)>,
+    ) -> Result<(), String> {
+        let row_data = coerce_row(&self.columns, row_data)?;
+        if let Some(callback) = &self.callback {
+            let mut row = TableRow::new(row_data.clone(), Some(&print_row));
+            callback.on_row_add(&mut row);
+            if !callback.retain_rows() {
+                return Ok(());
+            }
+        }
+        for (column_name, value) in row_data {
+            if let Some((_, column)) = self
+                .columns
+                .iter_mut()
+                .find(|(name, _)| name == &column_name)
+            {
+                column.append(value)?;
+            } else {
+                return Err(format!("Column '{}' not found", column_name));
+            }
+        }
+        Ok(())
+    }
+
+    fn fmt_debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Table")
+            .field("callback", &"Callback Omitted")
+            .finish()?;
+
+        f.write_str("columns: {\n")?;
+        for (name, column) in &self.columns {
+            write!(f, "    {}: ", name)?;
+            write!(f, "{:?}", column)?;
+            f.write_str(",\n")?;
+        }
+        f.write_str("}\n")
+    }
+}
+
+// prints one "name: value" line per column that has a value
+fn print_row(row: &TableRow) {
+    for (name, value) in row.data() {
+        let value = match value {
+            TableColumnValue::OptionalStringColumn(None)
+            | TableColumnValue::OptionalUint64Column(None) => continue,
+            TableColumnValue::OptionalUint64Column(Some(epoch))
+                if name == "modified" =>
+            {
+                epoch_to_rfc3339_utc(*epoch)
+                    .unwrap_or_else(|_| epoch.to_string())
+            }
+            value => value.to_string(),
+        };
+        println!("{:14} {}", format!("{}:", name), value);
+    }
+}
+
+impl fmt::Debug for ObjectMetadataTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_debug(f)
+    }
+}
@@ -1,14 +1,34 @@
 pub mod columns;
+pub mod diff;
+pub mod export;
 pub mod file_object;
+pub mod grep;
+pub mod metadata;
 pub mod object_store;
+pub mod operation;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod schema;
+pub mod stream;
+pub mod template;
 
 use core::fmt;
 use std::fmt::Debug;
 use std::sync::Arc;
 
 pub use columns::*;
+pub use diff::{DiffChange, DiffSide, DiffTable};
+pub use export::TableExportOptions;
 pub use file_object::FileObjectTable;
+pub use grep::GrepTable;
+pub use metadata::ObjectMetadataTable;
 pub use object_store::ObjectStoreTable;
+pub use operation::{OperationTable, PlannedOperation};
+#[cfg(feature = "parquet")]
+pub use parquet::ParquetWriter;
+pub use schema::{ColumnSchema, ColumnType, TableSchema};
+pub use stream::{TableStream, DEFAULT_STREAM_BATCH_SIZE};
+pub use template::RowTemplate;
 
 pub struct TableRow<'a> {
     data: Vec<(String, TableColumnValue)>,
@@ -77,15 +97,51 @@ impl<'a> Clone for TableRow<'a> {
 }
 pub trait TableCallback: Send + Sync {
     fn on_row_add(&self, row: &mut TableRow);
+
+    // false if the callback consumes the rows, so the table does not
+    // need to keep them (e.g. TableStream)
+    fn retain_rows(&self) -> bool {
+        true
+    }
 }
 
 pub trait Table: Debug {
     fn len(&self) -> usize;
     fn add_column(&mut self, name: &str, column_type: Box<dyn TableColumn>);
+    fn columns(&self) -> &[(String, Box<dyn TableColumn>)];
     fn set_callback(&mut self, callback: Arc<dyn TableCallback>);
     fn add_row(
         &mut self,
         row_data: Vec<(String, TableColumnValue)>,
     ) -> Result<(), String>;
     fn fmt_debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+
+    fn to_csv(&self, options: &TableExportOptions) -> Result<String, String> {
+        export::table_to_csv(self, options)
+    }
+
+    fn to_jsonl(&self, options: &TableExportOptions) -> Result<String, String> {
+        export::table_to_jsonl(self, options)
+    }
+
+    fn to_markdown(
+        &self,
+        options: &TableExportOptions,
+    ) -> Result<String, String> {
+        export::table_to_markdown(self, options)
+    }
+
+    fn to_template(
+        &self,
+        template: &RowTemplate,
+        options: &TableExportOptions,
+    ) -> Result<String, String> {
+        export::table_to_template(self, template, options)
+    }
+
+    // column types, with string columns narrowed to the type their
+    // values have (see TableSchema::infer)
+    fn schema(&self) -> TableSchema {
+        TableSchema::infer(self)
+    }
 }
@@ -3,12 +3,15 @@ use std::sync::Arc;
 
 use log::error;
 
+use crate::azure::backend::AzureBackend;
+use crate::gcs::backend::GCSBackend;
 use crate::handlers::object_store::{ObjectStore, ObjectStoreBackend};
 use crate::localfs::backend::LocalFsBackend;
 use crate::s3::backend::S3Backend;
+use crate::table::schema::coerce_row;
 use crate::table::{StringColumn, TableRow};
 use crate::{
-    EnvironmentConfig, LakestreamError, Table, TableCallback, TableColumn,
+    EnvironmentConfig, LumniError, Table, TableCallback, TableColumn,
     TableColumnValue,
 };
 
@@ -63,6 +66,10 @@ impl Table for ObjectStoreTable {
         self.columns.push((name.to_string(), column_type));
     }
 
+    fn columns(&self) -> &[(String, Box<dyn TableColumn>)] {
+        &self.columns
+    }
+
     fn set_callback(&mut self, callback: Arc<dyn TableCallback>) {
         self.callback = Some(callback);
     }
@@ -71,9 +78,13 @@ impl Table for ObjectStoreTable {
         &mut self,
         row_data: Vec<(String, TableColumnValue)>,
     ) -> Result<(), String> {
+        let row_data = coerce_row(&self.columns, row_data)?;
         if let Some(callback) = &self.callback {
             let mut row = TableRow::new(row_data.clone(), Some(&print_row));
             callback.on_row_add(&mut row);
+            if !callback.retain_rows() {
+                return Ok(());
+            }
         }
         for (column_name, value) in row_data {
             if let Some((_, column)) = self
@@ -142,7 +153,7 @@ pub async fn table_from_list_bucket(
     selected_columns: &Option<Vec<&str>>,
     max_files: Option<u32>,
     callback: Option<Arc<dyn TableCallback>>,
-) -> Result<Box<dyn Table>, LakestreamError> {
+) -> Result<Box<dyn Table>, LumniError> {
     let uri = config.get("uri").unwrap_or(&"".to_string()).clone();
 
     let mut table = ObjectStoreTable::new(selected_columns);
@@ -155,6 +166,13 @@ pub async fn table_from_list_bucket(
     if uri.starts_with("s3://") {
         // Delegate the logic to the S3 backend
         S3Backend::list_buckets(config.clone(), max_files, &mut table).await?;
+    } else if uri.starts_with("gs://") {
+        // Delegate the logic to the GCS backend
+        GCSBackend::list_buckets(config.clone(), max_files, &mut table).await?;
+    } else if uri.starts_with("az://") {
+        // Delegate the logic to the Azure backend
+        AzureBackend::list_buckets(config.clone(), max_files, &mut table)
+            .await?;
     } else if uri.starts_with("localfs://") {
         // Delegate the logic to the LocalFs backend
         LocalFsBackend::list_buckets(config.clone(), max_files, &mut table)
@@ -0,0 +1,207 @@
+use core::fmt;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::table::schema::coerce_row;
+use crate::table::{OptionalStringColumn, OptionalUint64Column, StringColumn};
+use crate::{Table, TableCallback, TableColumn, TableColumnValue, TableRow};
+
+// an operation a mutating command would perform, collected into an
+// OperationTable so the plan can be reviewed before it is executed
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedOperation {
+    action: String,
+    source: Option<String>,
+    target: String,
+    size: Option<u64>,
+}
+
+impl PlannedOperation {
+    pub fn new(action: &str, target: &str) -> Self {
+        PlannedOperation {
+            action: action.to_string(),
+            source: None,
+            target: target.to_string(),
+            size: None,
+        }
+    }
+
+    pub fn set_source(mut self, source: &str) -> Self {
+        self.source = Some(source.to_string());
+        self
+    }
+
+    pub fn set_size(mut self, size: Option<u64>) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+}
+
+pub struct OperationTable {
+    columns: Vec<(String, Box<dyn TableColumn>)>, // Store columns in order
+    operations: Vec<PlannedOperation>,
+    callback: Option<Arc<dyn TableCallback>>,
+}
+
+impl OperationTable {
+    pub fn new() -> Self {
+        let mut table = Self {
+            columns: Vec::new(),
+            operations: Vec::new(),
+            callback: None,
+        };
+        table.add_column("action", Box::new(StringColumn(Vec::new())));
+        table.add_column("source", Box::new(OptionalStringColumn(Vec::new())));
+        table.add_column("target", Box::new(StringColumn(Vec::new())));
+        table.add_column("size", Box::new(OptionalUint64Column(Vec::new())));
+        table
+    }
+
+    pub fn add_operation(
+        &mut self,
+        operation: PlannedOperation,
+    ) -> Result<(), String> {
+        let row_data = vec![
+            (
+                "action".to_string(),
+                TableColumnValue::StringColumn(operation.action.clone()),
+            ),
+            (
+                "source".to_string(),
+                TableColumnValue::OptionalStringColumn(
+                    operation.source.clone(),
+                ),
+            ),
+            (
+                "target".to_string(),
+                TableColumnValue::StringColumn(operation.target.clone()),
+            ),
+            (
+                "size".to_string(),
+                TableColumnValue::OptionalUint64Column(operation.size),
+            ),
+        ];
+        self.add_row(row_data)?;
+        self.operations.push(operation);
+        Ok(())
+    }
+
+    pub fn operations(&self) -> &[PlannedOperation] {
+        &self.operations
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&self.operations)
+    }
+}
+
+impl Default for OperationTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Table for OperationTable {
+    fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    fn add_column(&mut self, name: &str, column_type: Box<dyn TableColumn>) {
+        self.columns.push((name.to_string(), column_type));
+    }
+
+    fn columns(&self) -> &[(String, Box<dyn TableColumn>)] {
+        &self.columns
+    }
+
+    fn set_callback(&mut self, callback: Arc<dyn TableCallback>) {
+        self.callback = Some(callback);
+    }
+
+    fn add_row(
+        &mut self,
+        row_data: Vec<(String, TableColumnValue)>,
+    ) -> Result<(), String> {
+        let row_data = coerce_row(&self.columns, row_data)?;
+        if let Some(callback) = &self.callback {
+            let mut row = TableRow::new(row_data.clone(), Some(&print_row));
+            callback.on_row_add(&mut row);
+            if !callback.retain_rows() {
+                return Ok(());
+            }
+        }
+        for (column_name, value) in row_data {
+            if let Some((_, column)) = self
+                .columns
+                .iter_mut()
+                .find(|(name, _)| name == &column_name)
+            {
+                column.append(value)?;
+            } else {
+                return Err(format!("Column '{}' not found", column_name));
+            }
+        }
+        Ok(())
+    }
+
+    fn fmt_debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Table")
+            .field("callback", &"Callback Omitted")
+            .finish()?;
+
+        f.write_str("columns: {\n")?;
+        for (name, column) in &self.columns {
+            write!(f, "    {}: ", name)?;
+            write!(f, "{:?}", column)?;
+            f.write_str(",\n")?;
+        }
+        f.write_str("}\n")
+    }
+}
+
+// prints e.g. "upload: ./backup.sql -> s3://bucket/backup.sql (1024 bytes)"
+fn print_row(row: &TableRow) {
+    let value = |key: &str| {
+        row.data()
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value)
+    };
+    let action = value("action").map(|v| v.to_string()).unwrap_or_default();
+    let target = value("target").map(|v| v.to_string()).unwrap_or_default();
+    let mut line = match value("source") {
+        Some(TableColumnValue::OptionalStringColumn(Some(source))) => {
+            format!("{}: {} -> {}", action, source, target)
+        }
+        _ => format!("{}: {}", action, target),
+    };
+    if let Some(TableColumnValue::OptionalUint64Column(Some(size))) =
+        value("size")
+    {
+        line.push_str(&format!(" ({} bytes)", size));
+    }
+    println!("{}", line);
+}
+
+impl fmt::Debug for OperationTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_debug(f)
+    }
+}
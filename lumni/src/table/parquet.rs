@@ -0,0 +1,370 @@
+use std::io::Cursor;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use parquet::basic::{
+    Compression, LogicalType, Repetition, TimeUnit, Type as PhysicalType,
+};
+use parquet::data_type::{
+    ByteArray, ByteArrayType, DataType, DoubleType, Int32Type, Int64Type,
+};
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::{SerializedColumnWriter, SerializedFileWriter};
+use parquet::format::MilliSeconds;
+use parquet::schema::types::Type;
+
+use super::columns::*;
+use super::{ColumnType, Table, TableColumn, TableSchema};
+use crate::{
+    EnvironmentConfig, LumniError, ObjectStoreHandler, ParsedUri, UploadOptions,
+};
+
+const PARQUET_CONTENT_TYPE: &str = "application/vnd.apache.parquet";
+
+// writes a Table as a Parquet file, to any URI supported by
+// ObjectStoreHandler (e.g. "localfs://out.parquet", "s3://bucket/key")
+#[async_trait(?Send)]
+pub trait ParquetWriter {
+    fn to_parquet(&self) -> Result<Vec<u8>, LumniError>;
+
+    // columns are cast to the schema types first, timestamp columns are
+    // written as Parquet timestamps
+    fn to_parquet_with_schema(
+        &self,
+        schema: &TableSchema,
+    ) -> Result<Vec<u8>, LumniError>;
+
+    async fn write_parquet(
+        &self,
+        uri: &str,
+        config: &EnvironmentConfig,
+    ) -> Result<u64, LumniError> {
+        let data = self.to_parquet()?;
+        let options =
+            UploadOptions::new().set_content_type(PARQUET_CONTENT_TYPE);
+        ObjectStoreHandler::new(None)
+            .put_object(
+                &ParsedUri::from_uri(uri, false),
+                config,
+                &mut Cursor::new(data),
+                &options,
+                None,
+            )
+            .await
+    }
+}
+
+impl<T: Table + ?Sized> ParquetWriter for T {
+    fn to_parquet(&self) -> Result<Vec<u8>, LumniError> {
+        encode_table(self.columns(), None).map_err(parquet_error)
+    }
+
+    fn to_parquet_with_schema(
+        &self,
+        schema: &TableSchema,
+    ) -> Result<Vec<u8>, LumniError> {
+        let columns = schema
+            .cast_columns(self.columns())
+            .map_err(LumniError::Internal)?;
+        encode_table(&columns, Some(schema)).map_err(parquet_error)
+    }
+}
+
+fn parquet_error(e: ParquetError) -> LumniError {
+    LumniError::Internal(format!("Failed to write Parquet: {}", e))
+}
+
+// a column converted to Parquet values, optional columns get definition
+// levels to mark which rows are NULL
+enum ParquetColumn {
+    Int32(Vec<i32>, Option<Vec<i16>>),
+    Int64(Vec<i64>, Option<Vec<i16>>, bool), // bool: unsigned
+    Double(Vec<f64>, Option<Vec<i16>>),
+    String(Vec<ByteArray>, Option<Vec<i16>>),
+    Timestamp(Vec<i64>, Option<Vec<i16>>), // milliseconds
+}
+
+impl ParquetColumn {
+    fn from_table_column(
+        name: &str,
+        column: &dyn TableColumn,
+    ) -> Result<Self, ParquetError> {
+        let any = column.as_any();
+        // u64 is stored as INT64 annotated as unsigned, which readers
+        // convert back
+        let unsigned = |val: &u64| *val as i64;
+        let string = |val: &String| ByteArray::from(val.as_str());
+
+        if let Some(col) = any.downcast_ref::<Int32Column>() {
+            Ok(ParquetColumn::Int32(col.values().to_vec(), None))
+        } else if let Some(col) = any.downcast_ref::<OptionalInt32Column>() {
+            let (values, levels) = optional(col.values(), |val| *val);
+            Ok(ParquetColumn::Int32(values, Some(levels)))
+        } else if let Some(col) = any.downcast_ref::<Uint64Column>() {
+            let values = col.values().iter().map(unsigned).collect();
+            Ok(ParquetColumn::Int64(values, None, true))
+        } else if let Some(col) = any.downcast_ref::<OptionalUint64Column>() {
+            let (values, levels) = optional(col.values(), unsigned);
+            Ok(ParquetColumn::Int64(values, Some(levels), true))
+        } else if let Some(col) = any.downcast_ref::<FloatColumn>() {
+            Ok(ParquetColumn::Double(col.values().to_vec(), None))
+        } else if let Some(col) = any.downcast_ref::<OptionalFloatColumn>() {
+            let (values, levels) = optional(col.values(), |val| *val);
+            Ok(ParquetColumn::Double(values, Some(levels)))
+        } else if let Some(col) = any.downcast_ref::<StringColumn>() {
+            let values = col.values().iter().map(string).collect();
+            Ok(ParquetColumn::String(values, None))
+        } else if let Some(col) = any.downcast_ref::<OptionalStringColumn>() {
+            let (values, levels) = optional(col.values(), string);
+            Ok(ParquetColumn::String(values, Some(levels)))
+        } else {
+            Err(ParquetError::General(format!(
+                "Unsupported column type for {}",
+                name
+            )))
+        }
+    }
+
+    // timestamps are stored in epoch seconds, Parquet has no unit for
+    // seconds so they are written as milliseconds
+    fn into_timestamp(self, name: &str) -> Result<Self, ParquetError> {
+        let millis = |values: Vec<i64>| -> Vec<i64> {
+            values.iter().map(|val| val.saturating_mul(1000)).collect()
+        };
+        match self {
+            ParquetColumn::Int64(values, levels, _) => {
+                Ok(ParquetColumn::Timestamp(millis(values), levels))
+            }
+            ParquetColumn::Int32(values, levels) => {
+                Ok(ParquetColumn::Timestamp(
+                    millis(values.into_iter().map(i64::from).collect()),
+                    levels,
+                ))
+            }
+            _ => Err(ParquetError::General(format!(
+                "Column {} is not stored as a timestamp",
+                name
+            ))),
+        }
+    }
+
+    fn schema_type(&self, name: &str) -> Result<Type, ParquetError> {
+        let (physical_type, logical_type, levels) = match self {
+            ParquetColumn::Int32(_, levels) => {
+                (PhysicalType::INT32, None, levels)
+            }
+            ParquetColumn::Int64(_, levels, unsigned) => (
+                PhysicalType::INT64,
+                Some(LogicalType::Integer {
+                    bit_width: 64,
+                    is_signed: !unsigned,
+                }),
+                levels,
+            ),
+            ParquetColumn::Double(_, levels) => {
+                (PhysicalType::DOUBLE, None, levels)
+            }
+            ParquetColumn::String(_, levels) => {
+                (PhysicalType::BYTE_ARRAY, Some(LogicalType::String), levels)
+            }
+            ParquetColumn::Timestamp(_, levels) => (
+                PhysicalType::INT64,
+                Some(LogicalType::Timestamp {
+                    is_adjusted_to_u_t_c: true,
+                    unit: TimeUnit::MILLIS(MilliSeconds {}),
+                }),
+                levels,
+            ),
+        };
+        let repetition = if levels.is_some() {
+            Repetition::OPTIONAL
+        } else {
+            Repetition::REQUIRED
+        };
+        Type::primitive_type_builder(name, physical_type)
+            .with_repetition(repetition)
+            .with_logical_type(logical_type)
+            .build()
+    }
+
+    fn write(
+        &self,
+        writer: &mut SerializedColumnWriter<'_>,
+    ) -> Result<(), ParquetError> {
+        match self {
+            ParquetColumn::Int32(values, levels) => {
+                write_batch::<Int32Type>(writer, values, levels)
+            }
+            ParquetColumn::Int64(values, levels, _)
+            | ParquetColumn::Timestamp(values, levels) => {
+                write_batch::<Int64Type>(writer, values, levels)
+            }
+            ParquetColumn::Double(values, levels) => {
+                write_batch::<DoubleType>(writer, values, levels)
+            }
+            ParquetColumn::String(values, levels) => {
+                write_batch::<ByteArrayType>(writer, values, levels)
+            }
+        }
+    }
+}
+
+fn optional<V, P>(
+    values: &[Option<V>],
+    convert: impl Fn(&V) -> P,
+) -> (Vec<P>, Vec<i16>) {
+    let levels = values.iter().map(|val| val.is_some() as i16).collect();
+    let values = values.iter().flatten().map(convert).collect();
+    (values, levels)
+}
+
+fn write_batch<D: DataType>(
+    writer: &mut SerializedColumnWriter<'_>,
+    values: &[D::T],
+    levels: &Option<Vec<i16>>,
+) -> Result<(), ParquetError> {
+    writer
+        .typed::<D>()
+        .write_batch(values, levels.as_deref(), None)?;
+    Ok(())
+}
+
+// encodes the columns in a single row group, tables are held in memory
+// so they are small enough for that
+fn encode_table(
+    columns: &[(String, Box<dyn TableColumn>)],
+    schema: Option<&TableSchema>,
+) -> Result<Vec<u8>, ParquetError> {
+    let is_timestamp = |name: &str| {
+        schema
+            .and_then(|schema| schema.column(name))
+            .is_some_and(|column| column.column_type() == ColumnType::Timestamp)
+    };
+    let parquet_columns = columns
+        .iter()
+        .map(|(name, column)| {
+            let column =
+                ParquetColumn::from_table_column(name, column.as_ref())?;
+            if is_timestamp(name) {
+                column.into_timestamp(name)
+            } else {
+                Ok(column)
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let fields = columns
+        .iter()
+        .zip(&parquet_columns)
+        .map(|((name, _), column)| column.schema_type(name).map(Arc::new))
+        .collect::<Result<Vec<_>, _>>()?;
+    let schema = Arc::new(
+        Type::group_type_builder("schema")
+            .with_fields(fields)
+            .build()?,
+    );
+    let properties = Arc::new(
+        WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build(),
+    );
+
+    let mut buffer = Vec::new();
+    let mut writer =
+        SerializedFileWriter::new(&mut buffer, schema, properties)?;
+    let mut row_group = writer.next_row_group()?;
+    for column in &parquet_columns {
+        let mut column_writer = row_group.next_column()?.ok_or_else(|| {
+            ParquetError::General("Schema has fewer columns".to_string())
+        })?;
+        column.write(&mut column_writer)?;
+        column_writer.close()?;
+    }
+    row_group.close()?;
+    writer.close()?;
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::Field;
+
+    use super::*;
+    use crate::FileObjectTable;
+
+    #[test]
+    fn test_to_parquet_roundtrip() {
+        let mut table = FileObjectTable::new(&None, None);
+        for (name, size, modified) in
+            [("a.txt", 1, Some(1700000000000)), ("b.txt", u64::MAX, None)]
+        {
+            table
+                .add_row(vec![
+                    (
+                        "name".to_string(),
+                        TableColumnValue::StringColumn(name.to_string()),
+                    ),
+                    ("size".to_string(), TableColumnValue::Uint64Column(size)),
+                    (
+                        "modified".to_string(),
+                        TableColumnValue::OptionalUint64Column(modified),
+                    ),
+                ])
+                .unwrap();
+        }
+
+        let data = table.to_parquet().unwrap();
+        let reader = SerializedFileReader::new(Bytes::from(data)).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 2);
+
+        let rows: Vec<Vec<Field>> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| {
+                row.unwrap()
+                    .get_column_iter()
+                    .map(|(_, field)| field.clone())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            rows[0],
+            vec![
+                Field::Str("a.txt".to_string()),
+                Field::ULong(1),
+                Field::ULong(1700000000000)
+            ]
+        );
+        assert_eq!(
+            rows[1],
+            vec![
+                Field::Str("b.txt".to_string()),
+                Field::ULong(u64::MAX),
+                Field::Null
+            ]
+        );
+
+        let schema = TableSchema::from_columns(table.columns())
+            .cast("modified", ColumnType::Timestamp)
+            .unwrap()
+            .cast("size", ColumnType::String)
+            .unwrap();
+        let data = table.to_parquet_with_schema(&schema).unwrap();
+        let reader = SerializedFileReader::new(Bytes::from(data)).unwrap();
+        let row = reader.get_row_iter(None).unwrap().next().unwrap().unwrap();
+        let fields: Vec<Field> = row
+            .get_column_iter()
+            .map(|(_, field)| field.clone())
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                Field::Str("a.txt".to_string()),
+                Field::Str("1".to_string()),
+                Field::TimestampMillis(1700000000000000)
+            ]
+        );
+    }
+}
@@ -0,0 +1,425 @@
+use super::columns::*;
+use super::{Table, TableColumn, TableColumnValue};
+use crate::utils::time::rfc3339_to_epoch;
+
+pub(crate) type Columns = Vec<(String, Box<dyn TableColumn>)>;
+
+// logical type of a column, independent of how values are stored:
+// ints are stored as i32 or u64, timestamps as epoch seconds in u64
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Int,
+    Float,
+    Timestamp,
+    String,
+}
+
+impl ColumnType {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "int" | "integer" => Some(ColumnType::Int),
+            "float" | "double" => Some(ColumnType::Float),
+            "timestamp" => Some(ColumnType::Timestamp),
+            "string" | "text" => Some(ColumnType::String),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ColumnType::Int => "int",
+            ColumnType::Float => "float",
+            ColumnType::Timestamp => "timestamp",
+            ColumnType::String => "string",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnSchema {
+    name: String,
+    column_type: ColumnType,
+    nullable: bool,
+}
+
+impl ColumnSchema {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn column_type(&self) -> ColumnType {
+        self.column_type
+    }
+
+    pub fn is_nullable(&self) -> bool {
+        self.nullable
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableSchema {
+    columns: Vec<ColumnSchema>,
+}
+
+impl TableSchema {
+    // types as the columns are stored
+    pub fn from_columns(columns: &[(String, Box<dyn TableColumn>)]) -> Self {
+        TableSchema {
+            columns: columns
+                .iter()
+                .map(|(name, column)| ColumnSchema {
+                    name: name.clone(),
+                    column_type: column.data_type(),
+                    nullable: column.is_nullable(),
+                })
+                .collect(),
+        }
+    }
+
+    // as from_columns, but string columns get the narrowest type that
+    // all their values can be cast to (e.g. "42" is an int)
+    pub fn infer<T: Table + ?Sized>(table: &T) -> Self {
+        let mut schema = TableSchema::from_columns(table.columns());
+        for ((_, column), column_schema) in
+            table.columns().iter().zip(schema.columns.iter_mut())
+        {
+            column_schema.column_type = infer_type(column.as_ref());
+        }
+        schema
+    }
+
+    // explicit cast, applied when the columns are cast
+    pub fn cast(
+        mut self,
+        name: &str,
+        column_type: ColumnType,
+    ) -> Result<Self, String> {
+        let column = self
+            .columns
+            .iter_mut()
+            .find(|column| column.name == name)
+            .ok_or_else(|| format!("Column '{}' not found", name))?;
+        column.column_type = column_type;
+        Ok(self)
+    }
+
+    pub fn columns(&self) -> &[ColumnSchema] {
+        &self.columns
+    }
+
+    pub fn column(&self, name: &str) -> Option<&ColumnSchema> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+
+    // copies of the columns, cast to the types in this schema. columns
+    // not in the schema are copied as is
+    pub fn cast_columns(
+        &self,
+        columns: &[(String, Box<dyn TableColumn>)],
+    ) -> Result<Columns, String> {
+        columns
+            .iter()
+            .map(|(name, column)| {
+                let cast = match self.column(name) {
+                    Some(schema) => cast_column(column.as_ref(), schema)?,
+                    None => column.clone_box(),
+                };
+                Ok((name.clone(), cast))
+            })
+            .collect()
+    }
+}
+
+fn infer_type(column: &dyn TableColumn) -> ColumnType {
+    if column.data_type() != ColumnType::String {
+        return column.data_type();
+    }
+    let values: Vec<TableColumnValue> = (0..column.len())
+        .filter_map(|index| column.get_value(index))
+        .filter(|value| !is_null(value))
+        .collect();
+    if values.is_empty() {
+        return ColumnType::String;
+    }
+    [ColumnType::Int, ColumnType::Float, ColumnType::Timestamp]
+        .into_iter()
+        .find(|column_type| {
+            values
+                .iter()
+                .all(|value| cast_value(value, *column_type).is_ok())
+        })
+        .unwrap_or(ColumnType::String)
+}
+
+fn cast_column(
+    column: &dyn TableColumn,
+    schema: &ColumnSchema,
+) -> Result<Box<dyn TableColumn>, String> {
+    if column.data_type() == schema.column_type {
+        return Ok(column.clone_box());
+    }
+    let mut cast: Box<dyn TableColumn> =
+        match (schema.column_type, schema.nullable) {
+            (ColumnType::Int | ColumnType::Timestamp, false) => {
+                Box::new(Uint64Column(Vec::new()))
+            }
+            (ColumnType::Int | ColumnType::Timestamp, true) => {
+                Box::new(OptionalUint64Column(Vec::new()))
+            }
+            (ColumnType::Float, false) => Box::new(FloatColumn(Vec::new())),
+            (ColumnType::Float, true) => {
+                Box::new(OptionalFloatColumn(Vec::new()))
+            }
+            (ColumnType::String, false) => Box::new(StringColumn(Vec::new())),
+            (ColumnType::String, true) => {
+                Box::new(OptionalStringColumn(Vec::new()))
+            }
+        };
+    for value in (0..column.len()).filter_map(|index| column.get_value(index)) {
+        let value = cast_value(&value, schema.column_type)
+            .and_then(|value| cast.coerce(value))
+            .map_err(|e| format!("Column '{}': {}", schema.name, e))?;
+        cast.append(value)?;
+    }
+    Ok(cast)
+}
+
+// a value as the type it is stored as for the given column type,
+// NULL stays NULL
+pub fn cast_value(
+    value: &TableColumnValue,
+    column_type: ColumnType,
+) -> Result<TableColumnValue, String> {
+    match column_type {
+        ColumnType::Int => {
+            Ok(TableColumnValue::OptionalUint64Column(to_u64(value)?))
+        }
+        ColumnType::Float => {
+            Ok(TableColumnValue::OptionalFloatColumn(to_f64(value)?))
+        }
+        ColumnType::Timestamp => {
+            Ok(TableColumnValue::OptionalUint64Column(to_timestamp(value)?))
+        }
+        ColumnType::String => {
+            Ok(TableColumnValue::OptionalStringColumn(to_text(value)?))
+        }
+    }
+}
+
+// validates a row before it is inserted, values are coerced to the type
+// of the column they are inserted in
+pub(crate) fn coerce_row(
+    columns: &[(String, Box<dyn TableColumn>)],
+    row_data: Vec<(String, TableColumnValue)>,
+) -> Result<Vec<(String, TableColumnValue)>, String> {
+    row_data
+        .into_iter()
+        .map(|(name, value)| {
+            let (_, column) = columns
+                .iter()
+                .find(|(column_name, _)| *column_name == name)
+                .ok_or_else(|| format!("Column '{}' not found", name))?;
+            let value = column
+                .coerce(value)
+                .map_err(|e| format!("Column '{}': {}", name, e))?;
+            Ok((name, value))
+        })
+        .collect()
+}
+
+fn is_null(value: &TableColumnValue) -> bool {
+    matches!(
+        value,
+        TableColumnValue::OptionalInt32Column(None)
+            | TableColumnValue::OptionalUint64Column(None)
+            | TableColumnValue::OptionalFloatColumn(None)
+            | TableColumnValue::OptionalStringColumn(None)
+    )
+}
+
+fn cast_error(value: &TableColumnValue, column_type: ColumnType) -> String {
+    format!(
+        "Cannot convert '{}' to {}",
+        value.to_string(),
+        column_type.as_str()
+    )
+}
+
+// the conversions below return Ok(None) for NULL
+
+fn to_integer(value: &TableColumnValue) -> Result<Option<i128>, String> {
+    match value {
+        TableColumnValue::Int32Column(val)
+        | TableColumnValue::OptionalInt32Column(Some(val)) => {
+            Ok(Some(*val as i128))
+        }
+        TableColumnValue::Uint64Column(val)
+        | TableColumnValue::OptionalUint64Column(Some(val)) => {
+            Ok(Some(*val as i128))
+        }
+        TableColumnValue::FloatColumn(val)
+        | TableColumnValue::OptionalFloatColumn(Some(val))
+            if val.is_finite() && val.fract() == 0.0 =>
+        {
+            Ok(Some(*val as i128))
+        }
+        TableColumnValue::StringColumn(val)
+        | TableColumnValue::OptionalStringColumn(Some(val)) => val
+            .trim()
+            .parse::<i128>()
+            .map(Some)
+            .map_err(|_| cast_error(value, ColumnType::Int)),
+        value if is_null(value) => Ok(None),
+        _ => Err(cast_error(value, ColumnType::Int)),
+    }
+}
+
+pub(crate) fn to_i32(value: &TableColumnValue) -> Result<Option<i32>, String> {
+    to_integer(value)?
+        .map(|val| {
+            i32::try_from(val).map_err(|_| cast_error(value, ColumnType::Int))
+        })
+        .transpose()
+}
+
+pub(crate) fn to_u64(value: &TableColumnValue) -> Result<Option<u64>, String> {
+    to_integer(value)?
+        .map(|val| {
+            u64::try_from(val).map_err(|_| cast_error(value, ColumnType::Int))
+        })
+        .transpose()
+}
+
+pub(crate) fn to_f64(value: &TableColumnValue) -> Result<Option<f64>, String> {
+    match value {
+        TableColumnValue::FloatColumn(val)
+        | TableColumnValue::OptionalFloatColumn(Some(val)) => Ok(Some(*val)),
+        TableColumnValue::StringColumn(val)
+        | TableColumnValue::OptionalStringColumn(Some(val)) => val
+            .trim()
+            .parse::<f64>()
+            .map(Some)
+            .map_err(|_| cast_error(value, ColumnType::Float)),
+        value => Ok(to_integer(value)?.map(|val| val as f64)),
+    }
+}
+
+pub(crate) fn to_text(
+    value: &TableColumnValue,
+) -> Result<Option<String>, String> {
+    if is_null(value) {
+        Ok(None)
+    } else {
+        Ok(Some(value.to_string()))
+    }
+}
+
+// epoch seconds, strings can also be RFC 3339
+fn to_timestamp(value: &TableColumnValue) -> Result<Option<u64>, String> {
+    match value {
+        TableColumnValue::StringColumn(val)
+        | TableColumnValue::OptionalStringColumn(Some(val)) => {
+            match val.trim().parse::<u64>() {
+                Ok(seconds) => Ok(Some(seconds)),
+                Err(_) => rfc3339_to_epoch(val.trim())
+                    .map(Some)
+                    .map_err(|_| cast_error(value, ColumnType::Timestamp)),
+            }
+        }
+        value => {
+            to_u64(value).map_err(|_| cast_error(value, ColumnType::Timestamp))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileObjectTable;
+
+    #[test]
+    fn test_infer_and_cast() {
+        let mut table = FileObjectTable::new(&Some(vec!["name"]), None);
+        let mut checksums = FileObjectTable::new(&Some(vec!["checksum"]), None);
+        for (name, checksum) in
+            [("1", Some("2024-01-01T00:00:00Z")), ("2", None)]
+        {
+            table
+                .add_row(vec![(
+                    "name".to_string(),
+                    TableColumnValue::StringColumn(name.to_string()),
+                )])
+                .unwrap();
+            checksums
+                .add_row(vec![(
+                    "checksum".to_string(),
+                    TableColumnValue::OptionalStringColumn(
+                        checksum.map(String::from),
+                    ),
+                )])
+                .unwrap();
+        }
+
+        let schema = TableSchema::infer(&table);
+        assert_eq!(schema.columns()[0].column_type(), ColumnType::Int);
+        let columns = schema.cast_columns(table.columns()).unwrap();
+        assert!(matches!(
+            columns[0].1.get_value(1),
+            Some(TableColumnValue::Uint64Column(2))
+        ));
+
+        let schema = TableSchema::infer(&checksums);
+        assert_eq!(schema.columns()[0].column_type(), ColumnType::Timestamp);
+        assert!(schema.columns()[0].is_nullable());
+        let columns = schema.cast_columns(checksums.columns()).unwrap();
+        assert!(matches!(
+            columns[0].1.get_value(0),
+            Some(TableColumnValue::OptionalUint64Column(Some(1704067200)))
+        ));
+
+        let schema = TableSchema::from_columns(checksums.columns())
+            .cast("checksum", ColumnType::Float)
+            .unwrap();
+        assert!(schema.cast_columns(checksums.columns()).is_err());
+        assert!(schema.cast("size", ColumnType::Int).is_err());
+    }
+
+    #[test]
+    fn test_coerce_row() {
+        let mut table = FileObjectTable::new(&None, None);
+        table
+            .add_row(vec![
+                (
+                    "name".to_string(),
+                    TableColumnValue::StringColumn("a.txt".to_string()),
+                ),
+                (
+                    "size".to_string(),
+                    TableColumnValue::StringColumn("42".to_string()),
+                ),
+                (
+                    "modified".to_string(),
+                    TableColumnValue::Uint64Column(1700000000),
+                ),
+            ])
+            .unwrap();
+        assert!(matches!(
+            table.columns()[1].1.get_value(0),
+            Some(TableColumnValue::Uint64Column(42))
+        ));
+        let row = |size: TableColumnValue| vec![("size".to_string(), size)];
+        assert!(table
+            .add_row(row(TableColumnValue::StringColumn("big".to_string())))
+            .is_err());
+        assert!(table
+            .add_row(row(TableColumnValue::OptionalUint64Column(None)))
+            .is_err());
+        assert!(table
+            .add_row(vec![(
+                "owner".to_string(),
+                TableColumnValue::StringColumn("root".to_string())
+            )])
+            .is_err());
+        assert_eq!(table.len(), 1);
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use futures::stream::Stream;
+
+use super::{Table, TableCallback, TableColumnValue, TableRow};
+use crate::LumniError;
+
+type RowData = Vec<(String, TableColumnValue)>;
+type Listing<'a> =
+    Pin<Box<dyn Future<Output = Result<Box<dyn Table>, LumniError>> + 'a>>;
+
+pub const DEFAULT_STREAM_BATCH_SIZE: usize = 1000;
+
+// rows of a listing as batches, while it is listed. the listing only
+// progresses when the next batch is requested, and the rows are not kept
+// in a Table, so memory use is bounded by what a backend returns per
+// request (e.g. one page of an S3 listing)
+pub struct TableStream<'a> {
+    listing: Option<Listing<'a>>,
+    rows: Arc<Mutex<VecDeque<RowData>>>,
+    error: Option<LumniError>,
+    batch_size: usize,
+}
+
+impl<'a> TableStream<'a> {
+    // listing is called with the callback that collects the rows
+    pub fn new<F>(listing: F, batch_size: usize) -> Self
+    where
+        F: FnOnce(Arc<dyn TableCallback>) -> Listing<'a>,
+    {
+        let rows = Arc::new(Mutex::new(VecDeque::new()));
+        let collector = Arc::new(StreamCollector {
+            rows: Arc::clone(&rows),
+        });
+        TableStream {
+            listing: Some(listing(collector)),
+            rows,
+            error: None,
+            batch_size: batch_size.max(1),
+        }
+    }
+
+    fn next_batch(&self, partial: bool) -> Option<Vec<TableRow<'static>>> {
+        let mut rows = self.rows.lock().unwrap();
+        if rows.is_empty() || (!partial && rows.len() < self.batch_size) {
+            return None;
+        }
+        let count = rows.len().min(self.batch_size);
+        Some(
+            rows.drain(..count)
+                .map(|data| TableRow::new(data, None))
+                .collect(),
+        )
+    }
+}
+
+impl Stream for TableStream<'_> {
+    type Item = Result<Vec<TableRow<'static>>, LumniError>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let listing_done = this.listing.is_none();
+            if let Some(batch) = this.next_batch(listing_done) {
+                return Poll::Ready(Some(Ok(batch)));
+            }
+            let listing = match this.listing.as_mut() {
+                Some(listing) => listing,
+                // rows before the error, as they were listed before it
+                None => return Poll::Ready(this.error.take().map(Err)),
+            };
+            match listing.as_mut().poll(cx) {
+                Poll::Ready(result) => {
+                    this.listing = None;
+                    this.error = result.err();
+                }
+                Poll::Pending => {
+                    // pass on what is listed so far, rather than wait
+                    // for a full batch
+                    return match this.next_batch(true) {
+                        Some(batch) => Poll::Ready(Some(Ok(batch))),
+                        None => Poll::Pending,
+                    };
+                }
+            }
+        }
+    }
+}
+
+struct StreamCollector {
+    rows: Arc<Mutex<VecDeque<RowData>>>,
+}
+
+impl TableCallback for StreamCollector {
+    fn on_row_add(&self, row: &mut TableRow) {
+        self.rows.lock().unwrap().push_back(row.data().to_vec());
+    }
+
+    fn retain_rows(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::FileObjectTable;
+
+    #[tokio::test]
+    async fn test_table_stream() {
+        let stream = TableStream::new(
+            |callback| {
+                Box::pin(async move {
+                    let mut table = FileObjectTable::new(&None, Some(callback));
+                    for size in 0..5 {
+                        table.add_row(vec![(
+                            "size".to_string(),
+                            TableColumnValue::Uint64Column(size),
+                        )])?;
+                    }
+                    Ok(Box::new(table) as Box<dyn Table>)
+                })
+            },
+            2,
+        );
+        let batches: Vec<usize> =
+            stream.map(|batch| batch.unwrap().len()).collect().await;
+        assert_eq!(batches, vec![2, 2, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_table_stream_error() {
+        let mut stream = TableStream::new(
+            |callback| {
+                Box::pin(async move {
+                    let mut table = FileObjectTable::new(&None, Some(callback));
+                    table.add_row(vec![(
+                        "size".to_string(),
+                        TableColumnValue::Uint64Column(1),
+                    )])?;
+                    Err(LumniError::NotFound("bucket".to_string()))
+                })
+            },
+            10,
+        );
+        assert_eq!(stream.next().await.unwrap().unwrap().len(), 1);
+        assert!(stream.next().await.unwrap().is_err());
+        assert!(stream.next().await.is_none());
+    }
+}
@@ -0,0 +1,268 @@
+use super::export::json_value;
+use super::{TableColumnValue, TableRow};
+use crate::utils::formatters::bytes_human_readable;
+use crate::utils::time::UtcTimeNow;
+
+// a line per row from a user-defined format, e.g.
+// "{name}\t{size_human}\t{modified:%Y-%m-%d}"
+//
+// {column} is the value of the column, NULL is empty. The spec after a
+// colon formats the value: "human" for a humanized size, "json" for a
+// JSON value (strings quoted and escaped), any other spec is a date
+// format of epoch seconds (see UtcTimeNow::format). {column_human} is
+// short for {column:human}. Braces are written as {{ and }}, \t and \n
+// as a tab and a newline
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowTemplate {
+    parts: Vec<TemplatePart>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TemplatePart {
+    Literal(String),
+    Field { column: String, spec: FieldSpec },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FieldSpec {
+    Plain,
+    Human,
+    Json,
+    Date(String),
+}
+
+impl RowTemplate {
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '}' => {
+                    return Err(
+                        "Unmatched '}' in format, write '}}' for a brace"
+                            .to_string(),
+                    )
+                }
+                '\\' => match chars.peek() {
+                    Some('t') => {
+                        chars.next();
+                        literal.push('\t');
+                    }
+                    Some('n') => {
+                        chars.next();
+                        literal.push('\n');
+                    }
+                    Some('\\') => {
+                        chars.next();
+                        literal.push('\\');
+                    }
+                    _ => literal.push('\\'),
+                },
+                '{' => {
+                    let mut field = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        field.push(c);
+                    }
+                    if !closed {
+                        return Err(format!(
+                            "Unclosed '{{{}' in format",
+                            field
+                        ));
+                    }
+                    if !literal.is_empty() {
+                        parts.push(TemplatePart::Literal(std::mem::take(
+                            &mut literal,
+                        )));
+                    }
+                    parts.push(parse_field(&field)?);
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(TemplatePart::Literal(literal));
+        }
+        Ok(RowTemplate { parts })
+    }
+
+    // names of the columns used, in order of appearance
+    pub fn columns(&self) -> Vec<&str> {
+        self.parts
+            .iter()
+            .filter_map(|part| match part {
+                TemplatePart::Field { column, .. } => Some(column.as_str()),
+                TemplatePart::Literal(_) => None,
+            })
+            .collect()
+    }
+
+    // fails if the row does not have a column of the template
+    pub fn render(&self, row: &TableRow) -> Result<String, String> {
+        let mut output = String::new();
+        for part in &self.parts {
+            match part {
+                TemplatePart::Literal(literal) => output.push_str(literal),
+                TemplatePart::Field { column, spec } => {
+                    let (value, spec) = lookup(row, column, spec)?;
+                    output.push_str(&format_value(value, spec));
+                }
+            }
+        }
+        Ok(output)
+    }
+}
+
+fn parse_field(field: &str) -> Result<TemplatePart, String> {
+    let (column, spec) = match field.split_once(':') {
+        None => (field, FieldSpec::Plain),
+        Some((column, "human")) => (column, FieldSpec::Human),
+        Some((column, "json")) => (column, FieldSpec::Json),
+        Some((column, format)) => {
+            // checked here rather than on every row
+            UtcTimeNow::from_epoch(0).format(format)?;
+            (column, FieldSpec::Date(format.to_string()))
+        }
+    };
+    let column = column.trim();
+    if column.is_empty() {
+        return Err(format!("Missing column name in '{{{}}}'", field));
+    }
+    Ok(TemplatePart::Field {
+        column: column.to_string(),
+        spec,
+    })
+}
+
+fn lookup<'a>(
+    row: &'a TableRow,
+    column: &str,
+    spec: &'a FieldSpec,
+) -> Result<(&'a TableColumnValue, &'a FieldSpec), String> {
+    let find = |name: &str| {
+        row.data()
+            .iter()
+            .find(|(column, _)| column == name)
+            .map(|(_, value)| value)
+    };
+    if let Some(value) = find(column) {
+        return Ok((value, spec));
+    }
+    match column.strip_suffix("_human").and_then(find) {
+        Some(value) if *spec == FieldSpec::Plain => {
+            Ok((value, &FieldSpec::Human))
+        }
+        _ => Err(format!("Column '{}' not found", column)),
+    }
+}
+
+fn format_value(value: &TableColumnValue, spec: &FieldSpec) -> String {
+    let number = match value {
+        TableColumnValue::Int32Column(val)
+        | TableColumnValue::OptionalInt32Column(Some(val)) => {
+            u64::try_from(*val).ok()
+        }
+        TableColumnValue::Uint64Column(val)
+        | TableColumnValue::OptionalUint64Column(Some(val)) => Some(*val),
+        _ => None,
+    };
+    match (spec, number) {
+        (FieldSpec::Json, _) => json_value(value).to_string(),
+        (FieldSpec::Human, Some(number)) => bytes_human_readable(number),
+        // validated when parsed
+        (FieldSpec::Date(format), Some(epoch)) => UtcTimeNow::from_epoch(epoch)
+            .format(format)
+            .unwrap_or_default(),
+        _ => match value {
+            TableColumnValue::OptionalInt32Column(None)
+            | TableColumnValue::OptionalUint64Column(None)
+            | TableColumnValue::OptionalFloatColumn(None)
+            | TableColumnValue::OptionalStringColumn(None) => String::new(),
+            value => value.to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FileObjectTable, Table, TableExportOptions};
+
+    fn row() -> TableRow<'static> {
+        TableRow::new(
+            vec![
+                (
+                    "name".to_string(),
+                    TableColumnValue::StringColumn("a \"b\".txt".to_string()),
+                ),
+                ("size".to_string(), TableColumnValue::Uint64Column(1536)),
+                (
+                    "modified".to_string(),
+                    TableColumnValue::OptionalUint64Column(Some(1700000000)),
+                ),
+                (
+                    "checksum".to_string(),
+                    TableColumnValue::OptionalStringColumn(None),
+                ),
+            ],
+            None,
+        )
+    }
+
+    #[test]
+    fn test_render() {
+        let render = |template: &str| {
+            RowTemplate::parse(template)
+                .unwrap()
+                .render(&row())
+                .unwrap()
+        };
+        assert_eq!(
+            render("{name}\\t{size_human}\\t{modified:%Y-%m-%d}"),
+            "a \"b\".txt\t1.5k\t2023-11-14"
+        );
+        assert_eq!(
+            render("{size} {size:human} {modified:%H:%M}"),
+            "1536 1.5k 22:13"
+        );
+        assert_eq!(
+            render("{{\"name\": {name:json}, \"sum\": {checksum:json}}}"),
+            "{\"name\": \"a \\\"b\\\".txt\", \"sum\": null}"
+        );
+        assert_eq!(render("[{checksum}] {name:%Y}"), "[] a \"b\".txt");
+
+        let template = RowTemplate::parse("{owner}").unwrap();
+        assert_eq!(template.columns(), vec!["owner"]);
+        assert!(template.render(&row()).is_err());
+        assert!(RowTemplate::parse("{name").is_err());
+        assert!(RowTemplate::parse("name}").is_err());
+        assert!(RowTemplate::parse("{:human}").is_err());
+        assert!(RowTemplate::parse("{modified:%Q}").is_err());
+    }
+
+    #[test]
+    fn test_to_template() {
+        let mut table = FileObjectTable::new(&None, None);
+        table.add_row(row().data()[..3].to_vec()).unwrap();
+        let template = RowTemplate::parse("{name}:{size_human}").unwrap();
+        assert_eq!(
+            table
+                .to_template(&template, &TableExportOptions::new())
+                .unwrap(),
+            "a \"b\".txt:1.5k\n"
+        );
+    }
+}
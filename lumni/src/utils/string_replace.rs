@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use percent_encoding::{percent_encode, utf8_percent_encode, NON_ALPHANUMERIC};
 use regex::Regex;
 
 pub fn replace_variables_in_string_with_map(
@@ -16,3 +17,27 @@ pub fn replace_variables_in_string_with_map(
     })
     .to_string()
 }
+
+// percent-encode all but the alphanumeric and the excluded characters
+pub fn percent_encode_with_exclusion(
+    input: &str,
+    exclude: Option<&[u8]>,
+) -> String {
+    let mut result = String::new();
+    let set = NON_ALPHANUMERIC;
+
+    if let Some(exclusions) = exclude {
+        // percent-encode each byte while skipping excluded characters
+        for byte in input.bytes() {
+            if exclusions.contains(&byte) {
+                result.push(byte as char);
+            } else {
+                result.push_str(&percent_encode(&[byte][..], set).to_string());
+            }
+        }
+    } else {
+        // use the standard percent encoding for the entire input
+        result.push_str(&utf8_percent_encode(input, set).to_string());
+    }
+    result
+}
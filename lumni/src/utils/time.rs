@@ -1,6 +1,8 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
-pub use super::time_parse_ext::{datetime_utc, rfc3339_to_epoch};
+pub use super::time_parse_ext::{
+    datetime_utc, epoch_to_rfc3339_utc, rfc2822_to_epoch, rfc3339_to_epoch,
+};
 
 impl UtcTimeNow {
     pub fn new() -> UtcTimeNow {
@@ -16,6 +18,12 @@ impl UtcTimeNow {
     }
 }
 
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct",
+    "Nov", "Dec",
+];
+
 pub struct UtcTimeNow {
     year: u32,
     month: u8,
@@ -38,6 +46,105 @@ impl UtcTimeNow {
             self.second
         )
     }
+
+    // HTTP date format (RFC 1123), e.g. "Mon, 27 Jul 2009 12:28:53 GMT"
+    pub fn http_date(&self) -> String {
+        format!(
+            "{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT",
+            WEEKDAYS[self.weekday()],
+            self.day,
+            MONTHS[self.month as usize - 1],
+            self.year,
+            self.hour,
+            self.minute,
+            self.second
+        )
+    }
+
+    // day of the week with 0 = Sunday (Sakamoto's method)
+    fn weekday(&self) -> usize {
+        const OFFSETS: [u32; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+        let month = self.month as usize;
+        let year = if month < 3 { self.year - 1 } else { self.year };
+        let day = year + year / 4 - year / 100
+            + year / 400
+            + OFFSETS[month - 1]
+            + self.day as u32;
+        (day % 7) as usize
+    }
+}
+
+impl UtcTimeNow {
+    // civil date of epoch seconds (Hinnant's algorithm)
+    pub fn from_epoch(epoch: u64) -> UtcTimeNow {
+        let days = (epoch / 86400) as i64 + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days.rem_euclid(146097);
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524
+            - day_of_era / 146096)
+            / 365;
+        let day_of_year = day_of_era
+            - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u8;
+        let month = if month_index < 10 {
+            month_index + 3
+        } else {
+            month_index - 9
+        } as u8;
+        let year = (year_of_era + era * 400 + i64::from(month <= 2)) as u32;
+        let seconds = epoch % 86400;
+        UtcTimeNow {
+            year,
+            month,
+            day,
+            hour: (seconds / 3600) as u8,
+            minute: (seconds / 60 % 60) as u8,
+            second: (seconds % 60) as u8,
+        }
+    }
+
+    // strftime-like, e.g. "%Y-%m-%d %H:%M". Supports %Y %y %m %d %H %M
+    // %S, %F (%Y-%m-%d), %T (%H:%M:%S), %b (month name), %a (weekday)
+    // and %%
+    pub fn format(&self, pattern: &str) -> Result<String, String> {
+        let mut output = String::new();
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                output.push(c);
+                continue;
+            }
+            let formatted = match chars.next() {
+                Some('Y') => format!("{:04}", self.year),
+                Some('y') => format!("{:02}", self.year % 100),
+                Some('m') => format!("{:02}", self.month),
+                Some('d') => format!("{:02}", self.day),
+                Some('H') => format!("{:02}", self.hour),
+                Some('M') => format!("{:02}", self.minute),
+                Some('S') => format!("{:02}", self.second),
+                Some('F') => self.format("%Y-%m-%d")?,
+                Some('T') => self.format("%H:%M:%S")?,
+                Some('b') => MONTHS[self.month as usize - 1].to_string(),
+                Some('a') => WEEKDAYS[self.weekday()].to_string(),
+                Some('%') => "%".to_string(),
+                Some(other) => {
+                    return Err(format!(
+                        "Unknown date format specifier '%{}'",
+                        other
+                    ))
+                }
+                None => return Err("Date format ends with '%'".to_string()),
+            };
+            output.push_str(&formatted);
+        }
+        Ok(output)
+    }
+}
+
+// HTTP date of epoch seconds, e.g. for If-Modified-Since
+pub fn epoch_to_http_date(epoch: u64) -> String {
+    UtcTimeNow::from_epoch(epoch).http_date()
 }
 
 pub fn system_time_in_seconds() -> u64 {
@@ -46,3 +153,34 @@ pub fn system_time_in_seconds() -> u64 {
         .expect("Time went backwards")
         .as_secs()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_epoch_to_http_date() {
+        assert_eq!(epoch_to_http_date(0), "Thu, 01 Jan 1970 00:00:00 GMT");
+        assert_eq!(
+            epoch_to_http_date(1248697733),
+            "Mon, 27 Jul 2009 12:28:53 GMT"
+        );
+        assert_eq!(
+            epoch_to_http_date(1709208000),
+            "Thu, 29 Feb 2024 12:00:00 GMT"
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        let time = UtcTimeNow::from_epoch(1700000000);
+        assert_eq!(time.format("%Y-%m-%d").unwrap(), "2023-11-14");
+        assert_eq!(
+            time.format("%a %d %b %y, %T").unwrap(),
+            "Tue 14 Nov 23, 22:13:20"
+        );
+        assert_eq!(time.format("100%% %F").unwrap(), "100% 2023-11-14");
+        assert!(time.format("%Q").is_err());
+        assert!(time.format("%").is_err());
+    }
+}
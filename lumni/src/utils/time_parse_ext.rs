@@ -1,4 +1,4 @@
-use time::format_description::well_known::Rfc3339;
+use time::format_description::well_known::{Rfc2822, Rfc3339};
 use time::OffsetDateTime;
 
 pub fn rfc3339_to_epoch(timestamp: &str) -> Result<u64, time::Error> {
@@ -6,11 +6,23 @@ pub fn rfc3339_to_epoch(timestamp: &str) -> Result<u64, time::Error> {
     Ok(datetime.unix_timestamp() as u64)
 }
 
+// HTTP dates, e.g. "Mon, 27 Jul 2009 12:28:53 GMT"
+pub fn rfc2822_to_epoch(timestamp: &str) -> Result<u64, time::Error> {
+    let datetime = OffsetDateTime::parse(timestamp, &Rfc2822)?;
+    Ok(datetime.unix_timestamp() as u64)
+}
+
 pub fn epoch_to_rfc3339(timestamp: u64) -> Result<String, time::Error> {
     let datetime = OffsetDateTime::from_unix_timestamp(timestamp as i64)?;
     Ok(datetime.to_string())
 }
 
+// e.g. "2023-11-14T22:13:20Z"
+pub fn epoch_to_rfc3339_utc(timestamp: u64) -> Result<String, time::Error> {
+    let datetime = OffsetDateTime::from_unix_timestamp(timestamp as i64)?;
+    Ok(datetime.format(&Rfc3339)?)
+}
+
 pub fn datetime_utc() -> (u32, u8, u8, u8, u8, u8) {
     let time = OffsetDateTime::now_utc();
     (
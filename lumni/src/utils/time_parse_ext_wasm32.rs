@@ -6,12 +6,23 @@ pub fn rfc3339_to_epoch(timestamp: &str) -> Result<u64, JsValue> {
     Ok((date.get_time() / 1000.0) as u64)
 }
 
+pub fn rfc2822_to_epoch(timestamp: &str) -> Result<u64, JsValue> {
+    // Date parses HTTP dates as well
+    rfc3339_to_epoch(timestamp)
+}
+
 pub fn epoch_to_rfc3339(timestamp: u64) -> Result<String, JsValue> {
     let date = Date::new(&JsValue::from_f64(timestamp as f64 * 1000.0));
     let date_string = date.to_iso_string().as_string().unwrap();
     Ok(date_string[0..19].replace("T", " ") + "Z")
 }
 
+pub fn epoch_to_rfc3339_utc(timestamp: u64) -> Result<String, JsValue> {
+    let date = Date::new(&JsValue::from_f64(timestamp as f64 * 1000.0));
+    let date_string = date.to_iso_string().as_string().unwrap();
+    Ok(date_string[0..19].to_string() + "Z")
+}
+
 pub fn datetime_utc() -> (u32, u8, u8, u8, u8, u8) {
     let timestamp = Date::now();
     let date = Date::new(&JsValue::from_f64(timestamp));
@@ -1,11 +1,21 @@
 use regex::Regex;
+use tracing::info_span;
+
+use crate::handlers::is_registered;
 
 #[derive(Debug, PartialEq)]
 pub enum UriScheme {
     LocalFs,
     S3,
+    GCS,
+    Azure,
+    Abfss,
+    Sftp,
+    Hdfs,
     Http,
     Https,
+    // a scheme of a backend registered with register_backend
+    Registered(String),
     None,
     Unsupported(String),
 }
@@ -15,9 +25,17 @@ impl UriScheme {
         match scheme {
             "localfs" => UriScheme::LocalFs,
             "s3" => UriScheme::S3,
+            "gs" => UriScheme::GCS,
+            "az" => UriScheme::Azure,
+            "abfss" => UriScheme::Abfss,
+            "sftp" => UriScheme::Sftp,
+            "hdfs" => UriScheme::Hdfs,
             "http" => UriScheme::Http,
             "https" => UriScheme::Https,
             "" => UriScheme::None,
+            _ if is_registered(scheme) => {
+                UriScheme::Registered(scheme.to_string())
+            }
             _ => UriScheme::Unsupported(scheme.to_string()),
         }
     }
@@ -26,9 +44,15 @@ impl UriScheme {
         match self {
             UriScheme::LocalFs => "localfs".to_string(),
             UriScheme::S3 => "s3".to_string(),
+            UriScheme::GCS => "gs".to_string(),
+            UriScheme::Azure => "az".to_string(),
+            UriScheme::Abfss => "abfss".to_string(),
+            UriScheme::Sftp => "sftp".to_string(),
+            UriScheme::Hdfs => "hdfs".to_string(),
             UriScheme::Http => "http".to_string(),
             UriScheme::Https => "https".to_string(),
             UriScheme::None => "".to_string(),
+            UriScheme::Registered(scheme) => scheme.to_string(),
             UriScheme::Unsupported(scheme) => scheme.to_string(),
         }
     }
@@ -59,6 +83,7 @@ impl ParsedUri {
     }
 
     pub fn from_uri(uri: &str, append_slash: bool) -> ParsedUri {
+        let _span = info_span!("uri_parse").entered();
         if uri.is_empty() {
             return ParsedUri {
                 scheme: UriScheme::None,
@@ -141,7 +166,17 @@ fn parse_uri_path(
 
     // If there is no path, treat the input as a path instead of a bucket
     // bucket is currenth path on LocalFs
-    if scheme != &UriScheme::S3 && path.is_none() && bucket.is_some() {
+    let is_bucket_scheme = matches!(
+        scheme,
+        UriScheme::S3
+            | UriScheme::GCS
+            | UriScheme::Azure
+            | UriScheme::Abfss
+            | UriScheme::Sftp
+            | UriScheme::Hdfs
+            | UriScheme::Registered(_)
+    );
+    if !is_bucket_scheme && path.is_none() && bucket.is_some() {
         if append_slash {
             return (
                 Some(".".to_string()),